@@ -1,184 +1,85 @@
-use canbus_rmq_realtime::config::rabbitmq::QUEUE_NAME;
 /// Complete driving scenario that uses the actual structs from the features folder
 /// This example demonstrates the complete flow with all 6 scenario steps:
 /// 1. Create DrivingStep scenarios (Vehicle Start, First Gear, Acceleration, Highway Cruise, Emergency Braking, Vehicle Stop)
-/// 2. Convert each to CAN messages
-/// 3. Store in SQLx database
-/// 4. Send to RabbitMQ
-/// 5. Simulate WebSocket/Stream retrieving and reconstructing
-use lapin::options::BasicPublishOptions;
-use lapin::{BasicProperties, Channel, Connection, ConnectionProperties};
-use serde_json;
-use sqlx::SqlitePool;
-use tokio;
+/// 2. POST each step to the running server, which validates, encodes and stores its CAN frames
+/// 3. The server sends the step notice through RabbitMQ
+/// 4. Receive the reconstructed steps back from the /stream-lab endpoint
 use tokio_stream::StreamExt;
 
 // Import the actual structs from the main crate library
+use canbus_rmq_realtime::client::sse_client;
+use canbus_rmq_realtime::core::bus::BusMessage;
+use canbus_rmq_realtime::core::format::{CanFrames, StepRenderer};
+use canbus_rmq_realtime::features::driving_step::model::StoredStep;
 use canbus_rmq_realtime::features::driving_step::model::{
-    ClimateData, EngineData, VehicleSpeedData,
+    AdasData, ClimateData, EngineData, FuelData, VehicleSpeedData,
 };
-use canbus_rmq_realtime::{CanMessage, DrivingStep};
+use canbus_rmq_realtime::DrivingStep;
 
-/// Store CAN messages in SQLite database
-async fn store_can_messages(
-    pool: &SqlitePool,
-    can_messages: &[CanMessage],
-) -> Result<(), Box<dyn std::error::Error>> {
-    for can_msg in can_messages {
-        sqlx::query(
-            "INSERT INTO can_messages (id, dlc, data, timestamp, endian) VALUES (?, ?, ?, ?, ?)",
-        )
-        .bind(can_msg.id as i64)
-        .bind(can_msg.dlc as i64)
-        .bind(serde_json::to_string(&can_msg.data)?)
-        .bind(&can_msg.timestamp)
-        .bind(std::env::var("ENDIAN").unwrap_or_else(|_| "little".to_string()))
-        .execute(pool)
-        .await?;
-    }
-    Ok(())
-}
-
-/// Send step_name and endianness to RabbitMQ
-async fn send_step_data_to_rabbitmq(
-    channel: &Channel,
-    step_name: &str,
-    endian: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let step_data = serde_json::json!({
-        "step_name": step_name,
-        "endian": endian
-    });
-    let payload = serde_json::to_vec(&step_data)?;
-
-    channel
-        .basic_publish(
-            "",         // Use default exchange for direct queue publishing
-            QUEUE_NAME, // Direct to queue name
-            BasicPublishOptions::default(),
-            &payload,
-            BasicProperties::default(),
-        )
-        .await?;
-    Ok(())
-}
-
-/// Connect to the server's /stream-lab endpoint to receive DrivingStep broadcasts
-async fn connect_to_stream_endpoint() -> Result<(), Box<dyn std::error::Error>> {
+/// Follow the server's /stream-lab endpoint to receive DrivingStep and event broadcasts
+async fn connect_to_stream_endpoint() {
     println!("\n🌐 Connecting to server /stream-lab endpoint...");
 
-    // Try to connect to the SSE stream
-    let client = reqwest::Client::new();
-    let response = client
-        .get("http://127.0.0.1:8080/stream-lab")
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        println!("   ✅ Connected to /stream-lab endpoint");
-
-        let mut stream = response.bytes_stream();
-        let mut buffer = String::new();
-
-        // Process the SSE stream with buffering for larger structs
-        while let Some(chunk) = stream.next().await {
-            match chunk {
-                Ok(bytes) => {
-                    // Convert bytes to string and add to buffer
-                    if let Ok(chunk_str) = std::str::from_utf8(&bytes) {
-                        buffer.push_str(chunk_str);
-
-                        // Process complete SSE events (ending with \n\n)
-                        while let Some(event_end) = buffer.find("\n\n") {
-                            let event_data = buffer[..event_end].to_string();
-                            buffer.drain(..event_end + 2); // Remove processed event including \n\n
-
-                            // Process each line in the event
-                            for line in event_data.lines() {
-                                if line.starts_with("data: ") {
-                                    let json_data = &line[6..]; // Remove "data: " prefix
-                                    match serde_json::from_str::<DrivingStep>(json_data) {
-                                        Ok(driving_step) => {
-                                            println!("\n📻 RECEIVED DRIVINGSTEP FROM STREAM:");
-                                            driving_step.print_status();
-                                            driving_step.show_can_messages();
-                                        }
-                                        Err(e) => {
-                                            println!("❌ Failed to parse DrivingStep: {}", e);
-                                            println!("   Raw JSON: {}", json_data);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-
-                        // Prevent buffer from growing too large (protect against memory issues)
-                        if buffer.len() > 10_000 {
-                            println!(
-                                "⚠️ Buffer size exceeded 10KB, clearing to prevent memory issues"
-                            );
-                            buffer.clear();
-                        }
-                    }
-                }
-                Err(e) => {
-                    println!("   ❌ Stream error: {}", e);
-                    break;
-                }
+    // Buffering, `data:` parsing and reconnection are handled by the client
+    let mut stream = Box::pin(sse_client("http://127.0.0.1:8080/stream-lab"));
+    while let Some(message) = stream.next().await {
+        match message {
+            BusMessage::DrivingStep(driving_step) => {
+                println!("\n📻 RECEIVED DRIVINGSTEP FROM STREAM:");
+                println!("{:#}", driving_step);
+                println!("{}", CanFrames.render(&driving_step));
+            }
+            BusMessage::Event(event) => {
+                println!(
+                    "\n🔔 RECEIVED EVENT FROM STREAM: [{}] {}",
+                    event.name, event.message
+                );
+            }
+            BusMessage::Geofence(event) => {
+                println!(
+                    "\n📍 RECEIVED GEOFENCE EVENT FROM STREAM: {} {}",
+                    event.transition.as_str(),
+                    event.geofence
+                );
+            }
+            BusMessage::Anomaly(anomaly) => {
+                println!(
+                    "\n📈 RECEIVED ANOMALY FROM STREAM: {} = {}",
+                    anomaly.signal, anomaly.value
+                );
+            }
+            BusMessage::System(event) => {
+                println!(
+                    "\n🛠️ RECEIVED SYSTEM EVENT FROM STREAM: [{}] {}",
+                    event.kind.as_str(),
+                    event.message
+                );
             }
         }
-    } else {
-        println!(
-            "   ❌ Failed to connect to /stream-lab: {}",
-            response.status()
-        );
     }
-
-    Ok(())
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🚗🇺🇸 COMPLETE REALISTIC DRIVING SIMULATION 🇺🇸🚗");
     println!("═══════════════════════════════════════════════════════════");
-    println!("🎯 DEMONSTRATION: DrivingStep → CAN Messages → SQLx → RabbitMQ → Reconstruction");
+    println!(
+        "🎯 DEMONSTRATION: DrivingStep → POST → CAN Messages → SQLx → RabbitMQ → Reconstruction"
+    );
 
-    // Initialize database schema first
-    canbus_rmq_realtime::config::sqlite::init().await?;
-    println!("✅ Connected to SQLite database");
-    let pool = canbus_rmq_realtime::config::sqlite::get_pool().await?;
-
-    // Setup RabbitMQ connection (optional)
-    let rabbitmq_url =
-        std::env::var("RABBITMQ_URL").unwrap_or_else(|_| "amqp://127.0.0.1:5672".to_string());
-    let (_connection, channel) =
-        match Connection::connect(&rabbitmq_url, ConnectionProperties::default()).await {
-            Ok(conn) => {
-                let ch = conn.create_channel().await?;
-                println!("✅ Connected to RabbitMQ");
-                (Some(conn), Some(ch))
-            }
-            Err(e) => {
-                println!(
-                    "⚠️ Could not connect to RabbitMQ ({}), continuing without it",
-                    e
-                );
-                (None, None)
-            }
-        };
+    let server_url =
+        std::env::var("SERVER_URL").unwrap_or_else(|_| "http://127.0.0.1:8080".to_string());
+    let client = reqwest::Client::new();
 
     // Start stream endpoint connection in background
-    let _stream_handle = tokio::spawn(async {
-        if let Err(e) = connect_to_stream_endpoint().await {
-            println!("   ⚠️ Could not connect to stream endpoint: {}", e);
-        }
-    });
+    let _stream_handle = tokio::spawn(connect_to_stream_endpoint());
 
     // Create realistic driving scenario with all 6 steps
-    let scenario = vec![
+    let scenario = [
         // 1. Vehicle Start
         DrivingStep {
             step_name: "Vehicle Start".to_string(),
+            step_id: None,
             engine: EngineData {
                 rpm: 800,
                 coolant_temp: 20,
@@ -207,11 +108,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 auto_mode: true,
                 air_recirculation: false,
             },
+            adas: None,
+            fuel: Some(FuelData {
+                tank_level: 72,
+                consumption: 0.0,
+                range_remaining: 540,
+            }),
+            gps: None,
             duration_ms: 2000,
+            pipeline: None,
+            frames: Vec::new(),
+            tenant: None,
+            vehicle_id: None,
         },
         // 2. First Gear Engagement
         DrivingStep {
             step_name: "First Gear Engagement".to_string(),
+            step_id: None,
             engine: EngineData {
                 rpm: 1200,
                 coolant_temp: 25,
@@ -240,11 +153,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 auto_mode: true,
                 air_recirculation: false,
             },
+            adas: None,
+            fuel: Some(FuelData {
+                tank_level: 72,
+                consumption: 9.5,
+                range_remaining: 540,
+            }),
+            gps: None,
             duration_ms: 1500,
+            pipeline: None,
+            frames: Vec::new(),
+            tenant: None,
+            vehicle_id: None,
         },
         // 3. Acceleration
         DrivingStep {
             step_name: "Acceleration".to_string(),
+            step_id: None,
             engine: EngineData {
                 rpm: 2500,
                 coolant_temp: 45,
@@ -273,11 +198,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 auto_mode: true,
                 air_recirculation: false,
             },
+            adas: None,
+            fuel: Some(FuelData {
+                tank_level: 71,
+                consumption: 14.2,
+                range_remaining: 535,
+            }),
+            gps: None,
             duration_ms: 3000,
+            pipeline: None,
+            frames: Vec::new(),
+            tenant: None,
+            vehicle_id: None,
         },
         // 4. Highway Cruise
         DrivingStep {
             step_name: "Highway Cruise".to_string(),
+            step_id: None,
             engine: EngineData {
                 rpm: 2000,
                 coolant_temp: 75,
@@ -306,11 +243,30 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 auto_mode: true,
                 air_recirculation: true,
             },
+            adas: Some(AdasData {
+                lead_distance: 62.5,
+                relative_speed: -3.2,
+                acc_set_speed: 90,
+                acc_active: true,
+                lane_keep_active: true,
+                lane_departure_warning: false,
+            }),
+            fuel: Some(FuelData {
+                tank_level: 70,
+                consumption: 6.1,
+                range_remaining: 528,
+            }),
+            gps: None,
             duration_ms: 5000,
+            pipeline: None,
+            frames: Vec::new(),
+            tenant: None,
+            vehicle_id: None,
         },
         // 5. Emergency Braking
         DrivingStep {
             step_name: "Emergency Braking".to_string(),
+            step_id: None,
             engine: EngineData {
                 rpm: 1500,
                 coolant_temp: 78,
@@ -339,11 +295,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 auto_mode: true,
                 air_recirculation: true,
             },
+            adas: None,
+            fuel: Some(FuelData {
+                tank_level: 70,
+                consumption: 8.0,
+                range_remaining: 527,
+            }),
+            gps: None,
             duration_ms: 2000,
+            pipeline: None,
+            frames: Vec::new(),
+            tenant: None,
+            vehicle_id: None,
         },
         // 6. Vehicle Stop
         DrivingStep {
             step_name: "Vehicle Stop".to_string(),
+            step_id: None,
             engine: EngineData {
                 rpm: 800,
                 coolant_temp: 80,
@@ -372,14 +340,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 auto_mode: true,
                 air_recirculation: true,
             },
+            adas: None,
+            fuel: Some(FuelData {
+                tank_level: 70,
+                consumption: 0.0,
+                range_remaining: 527,
+            }),
+            gps: None,
             duration_ms: 1000,
+            pipeline: None,
+            frames: Vec::new(),
+            tenant: None,
+            vehicle_id: None,
         },
     ];
 
     for endian in ["little", "big"] {
-        std::env::set_var("ENDIAN", endian);
-        let is_big_endian = endian == "big";
-
         println!(
             "\n🎬 RUNNING COMPLETE DRIVING SCENARIO ({} steps) - {} ENDIAN",
             scenario.len(),
@@ -396,41 +372,29 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 endian
             );
 
-            // Convert to CAN messages with explicit endianness
-            let can_messages = step.to_can_messages_with_endian(is_big_endian);
-            println!(
-                "\n📡 Converting to {} CAN messages ({} endian)...",
-                can_messages.len(),
-                endian
-            );
-
-            // Store CAN messages in database
+            // The server converts the step to CAN messages, stores them as one step and
+            // sends the step notice (step_id, step_name, endianness) to RabbitMQ
+            println!("\n📡 Posting step to the server ({} endian)...", endian);
+            let response = client
+                .post(format!("{}/driving-steps", server_url))
+                .query(&[("endianness", endian)])
+                .json(step)
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                println!(
+                    "   └─ ❌ Server rejected the step ({}): {}",
+                    response.status(),
+                    response.text().await?
+                );
+                continue;
+            }
+            let stored: StoredStep = response.json().await?;
             println!(
-                "\n💾 Storing {} CAN messages to SQLite database...",
-                can_messages.len()
+                "\n💾 Server stored {} CAN messages as step {} and notified RabbitMQ ✅",
+                stored.can_messages.len(),
+                stored.step_id
             );
-            store_can_messages(&pool, &can_messages).await?;
-
-            // Wait a moment to ensure database write is committed
-            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-
-            // Send step_name and endianness to RabbitMQ (if available)
-            println!("\n📨 Sending step_data to RabbitMQ...");
-            if let Some(ch) = &channel {
-                match send_step_data_to_rabbitmq(ch, &step.step_name, endian).await {
-                    Ok(_) => {
-                        println!(
-                            "   └─ Step '{}' + endian '{}' → RabbitMQ ✅",
-                            step.step_name, endian
-                        );
-                    }
-                    Err(e) => {
-                        println!("   └─ ⚠️ RabbitMQ error ({}), continuing without it", e);
-                    }
-                }
-            } else {
-                println!("   └─ ⚠️ Skipping RabbitMQ (not connected)");
-            }
             tokio::time::sleep(std::time::Duration::from_secs(2)).await;
         }
     }
@@ -0,0 +1,74 @@
+//! Rust clients of a running server, enabled with the `client` feature
+//!
+//! `EventBusClient` follows `/ws` and `sse_client` follows `/stream` or `/stream-lab`; both
+//! reconnect when the connection drops and hand out the messages of the server as crate types.
+
+mod sse;
+mod ws;
+
+use derive_more::Display;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::core::bus::BusMessage;
+use crate::core::playback::PlaybackStatus;
+use crate::core::signals::DecodedFrame;
+
+pub use sse::{sse_client, sse_events};
+pub use ws::{ClientBuilder, EventBusClient};
+
+/// Errors raised by the clients
+#[derive(Debug, Display)]
+pub enum ClientError {
+    #[display("Invalid server URL '{}': {}", _0, _1)]
+    InvalidUrl(String, String),
+    #[display("Client is closed")]
+    Closed,
+    #[display("Failed to serialize message: {}", _0)]
+    Serialize(serde_json::Error),
+}
+
+impl std::error::Error for ClientError {}
+
+/// Error reply of the server to a message it rejected
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ServerError {
+    pub error: String,
+    /// HTTP equivalent of the rejection, when the server gives one
+    pub code: Option<u16>,
+}
+
+/// Everything a client hands out, in the order it happened
+#[derive(Debug, Clone)]
+pub enum ClientEvent {
+    /// The connection was (re)opened
+    Connected,
+    /// A bus message, steps of a playback included
+    Message(Box<BusMessage>),
+    /// One frame of a step, for clients following `?mode=decoded`
+    Frame(Box<DecodedFrame>),
+    /// Reply to a playback command
+    Playback(PlaybackStatus),
+    Error(ServerError),
+    /// The connection dropped; the client reconnects after its delay
+    Disconnected(String),
+    /// The server ended the stream for good (its subscription was deleted); no reconnection
+    Ended(String),
+}
+
+impl ClientEvent {
+    /// Parse one text message of the server, `None` for messages of unknown shape
+    pub fn parse(text: &str) -> Option<ClientEvent> {
+        let value: Value = serde_json::from_str(text).ok()?;
+        let event = match value.get("type").and_then(Value::as_str) {
+            Some("frame") => ClientEvent::Frame(Box::new(serde_json::from_value(value).ok()?)),
+            Some("playback") => ClientEvent::Playback(serde_json::from_value(value).ok()?),
+            Some(_) => ClientEvent::Message(Box::new(serde_json::from_value(value).ok()?)),
+            None if value.get("error").is_some() => {
+                ClientEvent::Error(serde_json::from_value(value).ok()?)
+            }
+            None => return None,
+        };
+        Some(event)
+    }
+}
@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use futures_util::{Stream, StreamExt};
+
+use crate::client::ClientEvent;
+use crate::core::bus::BusMessage;
+
+/// Delay before reconnecting to an SSE endpoint
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+/// Bus messages of the `/stream` or `/stream-lab` endpoint at `url`, across reconnections
+///
+/// Filters go in the URL (`?min_severity=warning`, `?subscription=<id>`). The stream ends
+/// when the server rejects the URL or ends a stored subscription.
+pub fn sse_client(url: impl Into<String>) -> impl Stream<Item = BusMessage> + Send {
+    sse_events(url, RECONNECT_DELAY).filter_map(|event| async move {
+        match event {
+            ClientEvent::Message(message) => Some(*message),
+            _ => None,
+        }
+    })
+}
+
+/// Every event of an SSE endpoint, connection changes included, reconnecting after `delay`
+pub fn sse_events(
+    url: impl Into<String>,
+    reconnect_delay: Duration,
+) -> impl Stream<Item = ClientEvent> + Send {
+    let url = url.into();
+    let client = reqwest::Client::new();
+
+    async_stream::stream! {
+        loop {
+            let reason = match client.get(&url).send().await {
+                // Unknown subscriptions and bad filters will not get better by retrying
+                Ok(response) if response.status().is_client_error() => {
+                    yield ClientEvent::Ended(format!("{} returned {}", url, response.status()));
+                    return;
+                }
+                Ok(response) if !response.status().is_success() => {
+                    format!("{} returned {}", url, response.status())
+                }
+                Ok(response) => {
+                    yield ClientEvent::Connected;
+                    let mut body = response.bytes_stream();
+                    let mut parser = SseParser::default();
+                    loop {
+                        match body.next().await {
+                            Some(Ok(bytes)) => {
+                                for data in parser.push(&bytes) {
+                                    if let Some(event) = ClientEvent::parse(&data) {
+                                        yield event;
+                                    }
+                                }
+                            }
+                            Some(Err(e)) => break e.to_string(),
+                            None => break "stream closed".to_string(),
+                        }
+                    }
+                }
+                Err(e) => e.to_string(),
+            };
+            yield ClientEvent::Disconnected(reason);
+            tokio::time::sleep(reconnect_delay).await;
+        }
+    }
+}
+
+/// Incremental parser of `text/event-stream` bodies, yielding the data of complete events
+#[derive(Debug, Default)]
+struct SseParser {
+    /// Bytes after the last complete line, which may end inside a UTF-8 sequence
+    pending: Vec<u8>,
+    /// `data:` lines of the event being read
+    data: Vec<String>,
+}
+
+impl SseParser {
+    fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(bytes);
+        let mut complete = Vec::new();
+
+        while let Some(end) = self.pending.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                // A blank line dispatches the event; comments and keep-alives carry no data
+                if !self.data.is_empty() {
+                    complete.push(self.data.join("\n"));
+                    self.data.clear();
+                }
+            } else if let Some(value) = line.strip_prefix("data:") {
+                self.data
+                    .push(value.strip_prefix(' ').unwrap_or(value).to_string());
+            }
+        }
+        complete
+    }
+}
@@ -0,0 +1,228 @@
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use reqwest::Url;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::client::{ClientError, ClientEvent};
+use crate::core::bus::{BusMessage, StreamMode};
+use crate::core::playback::PlaybackCommand;
+use crate::features::driving_step::DrivingStep;
+use crate::features::event::model::Severity;
+
+/// Delay before reconnecting, unless the builder sets another
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+/// Builder of an `EventBusClient`, carrying the query parameters of `/ws`
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    url: String,
+    subscription: Option<String>,
+    min_severity: Option<Severity>,
+    mode: StreamMode,
+    latency: bool,
+    reconnect_delay: Duration,
+}
+
+impl ClientBuilder {
+    /// Stored subscription replacing the other filter parameters
+    pub fn subscription(mut self, id: impl Into<String>) -> Self {
+        self.subscription = Some(id.into());
+        self
+    }
+
+    pub fn min_severity(mut self, severity: Severity) -> Self {
+        self.min_severity = Some(severity);
+        self
+    }
+
+    pub fn mode(mut self, mode: StreamMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Ask for the `X-Pipeline-Latency` field on driving steps
+    pub fn latency(mut self, latency: bool) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    pub fn reconnect_delay(mut self, delay: Duration) -> Self {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    /// URL the client connects to, with the filter in its query string
+    pub fn endpoint(&self) -> Result<Url, ClientError> {
+        let mut url = Url::parse(&self.url)
+            .map_err(|e| ClientError::InvalidUrl(self.url.clone(), e.to_string()))?;
+        {
+            let mut query = url.query_pairs_mut();
+            if let Some(subscription) = &self.subscription {
+                query.append_pair("subscription", subscription);
+            }
+            if let Some(severity) = self.min_severity {
+                query.append_pair("min_severity", severity.as_str());
+            }
+            if self.mode != StreamMode::Steps {
+                query.append_pair("mode", self.mode.as_str());
+            }
+            if self.latency {
+                query.append_pair("latency", "true");
+            }
+        }
+        // An empty query would leave a trailing `?`
+        if url.query() == Some("") {
+            url.set_query(None);
+        }
+        Ok(url)
+    }
+
+    /// Start following the server in a background task
+    ///
+    /// The first connection is attempted by the task, so an unreachable server shows up as
+    /// `ClientEvent::Disconnected` rather than an error here.
+    pub fn connect(self) -> Result<EventBusClient, ClientError> {
+        let endpoint = self.endpoint()?;
+        let (events_tx, events) = mpsc::unbounded_channel();
+        let (outbound, outbound_rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(follow(
+            endpoint.to_string(),
+            self.reconnect_delay,
+            events_tx,
+            outbound_rx,
+        ));
+
+        Ok(EventBusClient {
+            events,
+            outbound,
+            task,
+        })
+    }
+}
+
+/// WebSocket client of `/ws`, reconnecting until it is dropped
+///
+/// ```no_run
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// use canbus_rmq_realtime::client::EventBusClient;
+/// use canbus_rmq_realtime::core::bus::BusMessage;
+///
+/// let mut client = EventBusClient::connect("ws://127.0.0.1:8080/ws")?;
+/// while let Some(message) = client.next_message().await {
+///     if let BusMessage::DrivingStep(step) = message {
+///         println!("{}", step);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct EventBusClient {
+    events: mpsc::UnboundedReceiver<ClientEvent>,
+    outbound: mpsc::UnboundedSender<String>,
+    task: JoinHandle<()>,
+}
+
+impl EventBusClient {
+    /// Builder for a client of the `/ws` endpoint at `url` (e.g. `ws://127.0.0.1:8080/ws`)
+    pub fn builder(url: impl Into<String>) -> ClientBuilder {
+        ClientBuilder {
+            url: url.into(),
+            subscription: None,
+            min_severity: None,
+            mode: StreamMode::Steps,
+            latency: false,
+            reconnect_delay: RECONNECT_DELAY,
+        }
+    }
+
+    /// Follow every message of the server at `url`
+    pub fn connect(url: impl Into<String>) -> Result<EventBusClient, ClientError> {
+        Self::builder(url).connect()
+    }
+
+    /// Next event, `None` once the server ended the stream
+    pub async fn next(&mut self) -> Option<ClientEvent> {
+        self.events.recv().await
+    }
+
+    /// Next bus message, skipping connection changes, frames and replies
+    pub async fn next_message(&mut self) -> Option<BusMessage> {
+        loop {
+            if let ClientEvent::Message(message) = self.next().await? {
+                return Some(*message);
+            }
+        }
+    }
+
+    /// Publish a step through the server, as `POST /driving-steps` does
+    ///
+    /// Messages sent while disconnected are delivered once the connection is back.
+    pub fn send_step(&self, step: &DrivingStep) -> Result<(), ClientError> {
+        self.send(step)
+    }
+
+    pub fn send_playback(&self, command: &PlaybackCommand) -> Result<(), ClientError> {
+        self.send(command)
+    }
+
+    fn send(&self, message: &impl serde::Serialize) -> Result<(), ClientError> {
+        let text = serde_json::to_string(message).map_err(ClientError::Serialize)?;
+        self.outbound.send(text).map_err(|_| ClientError::Closed)
+    }
+}
+
+impl Drop for EventBusClient {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Forward the server's messages and send queued ones, reconnecting when the socket drops
+async fn follow(
+    url: String,
+    reconnect_delay: Duration,
+    events: mpsc::UnboundedSender<ClientEvent>,
+    mut outbound: mpsc::UnboundedReceiver<String>,
+) {
+    loop {
+        let reason = match tokio_tungstenite::connect_async(url.as_str()).await {
+            Ok((socket, _)) => {
+                let _ = events.send(ClientEvent::Connected);
+                let (mut writer, mut reader) = socket.split();
+                loop {
+                    tokio::select! {
+                        received = reader.next() => match received {
+                            Some(Ok(Message::Text(text))) => {
+                                if let Some(event) = ClientEvent::parse(&text) {
+                                    let _ = events.send(event);
+                                }
+                            }
+                            // Deleted subscriptions are closed with a policy frame
+                            Some(Ok(Message::Close(Some(frame)))) if frame.code == CloseCode::Policy => {
+                                let _ = events.send(ClientEvent::Ended(frame.reason.to_string()));
+                                return;
+                            }
+                            Some(Ok(_)) => {}
+                            Some(Err(e)) => break e.to_string(),
+                            None => break "connection closed".to_string(),
+                        },
+                        Some(text) = outbound.recv() => {
+                            if let Err(e) = writer.send(Message::Text(text)).await {
+                                break e.to_string();
+                            }
+                        }
+                    }
+                }
+            }
+            Err(e) => e.to_string(),
+        };
+        if events.send(ClientEvent::Disconnected(reason)).is_err() {
+            return;
+        }
+        tokio::time::sleep(reconnect_delay).await;
+    }
+}
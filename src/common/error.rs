@@ -15,8 +15,18 @@ pub enum AppError {
     InternalServerError { message: String },
     #[display("Invalid request parameters: {}", message)]
     BadRequest { message: String },
+    #[display("Unsupported media type: {}", message)]
+    UnsupportedMediaType { message: String },
+    #[display("Too many requests: {}", message)]
+    TooManyRequests { message: String },
+    #[display("Conflict: {}", message)]
+    Conflict { message: String },
+    #[display("Service unavailable: {}", message)]
+    ServiceUnavailable { message: String, retry_after: u64 },
 }
 
+impl std::error::Error for AppError {}
+
 #[allow(dead_code)]
 pub type AppResult<T> = std::result::Result<T, AppError>;
 
@@ -34,9 +44,18 @@ macro_rules! internal_error {
 }
 
 internal_error!(
-    AppError: std::io::Error, sqlx::Error, actix_web::error::Error, serde_json::Error
+    AppError: std::io::Error, sqlx::Error, actix_web::error::Error, serde_json::Error,
+    crate::config::object_store::ObjectStoreError
 );
 
+impl From<crate::core::can::CanError> for AppError {
+    fn from(error: crate::core::can::CanError) -> Self {
+        Self::BadRequest {
+            message: error.to_string(),
+        }
+    }
+}
+
 #[derive(Serialize)]
 struct ErrorResponse {
     code: u16,
@@ -54,6 +73,12 @@ impl ResponseError for AppError {
                 actix_web::http::StatusCode::INTERNAL_SERVER_ERROR
             }
             AppError::BadRequest { .. } => actix_web::http::StatusCode::BAD_REQUEST,
+            AppError::UnsupportedMediaType { .. } => {
+                actix_web::http::StatusCode::UNSUPPORTED_MEDIA_TYPE
+            }
+            AppError::TooManyRequests { .. } => actix_web::http::StatusCode::TOO_MANY_REQUESTS,
+            AppError::Conflict { .. } => actix_web::http::StatusCode::CONFLICT,
+            AppError::ServiceUnavailable { .. } => actix_web::http::StatusCode::SERVICE_UNAVAILABLE,
         }
     }
 
@@ -65,7 +90,11 @@ impl ResponseError for AppError {
             error_type: format!("{:?}", self),
         };
 
-        HttpResponse::build(status_code).json(error_response)
+        let mut response = HttpResponse::build(status_code);
+        if let AppError::ServiceUnavailable { retry_after, .. } = self {
+            response.insert_header((actix_web::http::header::RETRY_AFTER, *retry_after));
+        }
+        response.json(error_response)
     }
 }
 
@@ -100,4 +129,30 @@ impl AppError {
             message: message.into(),
         }
     }
+
+    pub fn unsupported_media_type(message: impl Into<String>) -> Self {
+        AppError::UnsupportedMediaType {
+            message: message.into(),
+        }
+    }
+
+    pub fn too_many_requests(message: impl Into<String>) -> Self {
+        AppError::TooManyRequests {
+            message: message.into(),
+        }
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        AppError::Conflict {
+            message: message.into(),
+        }
+    }
+
+    /// `503` telling the client to try again in `retry_after` seconds
+    pub fn service_unavailable(message: impl Into<String>, retry_after: u64) -> Self {
+        AppError::ServiceUnavailable {
+            message: message.into(),
+            retry_after,
+        }
+    }
 }
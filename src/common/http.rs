@@ -0,0 +1,21 @@
+use actix_web::http::header::{ETag, EntityTag, IfNoneMatch};
+use actix_web::{HttpMessage, HttpRequest, HttpResponse};
+
+/// Weak validator of a representation that changes whenever `version` does
+pub fn weak_etag(version: impl std::fmt::Display) -> EntityTag {
+    EntityTag::new_weak(version.to_string())
+}
+
+/// `304 Not Modified` carrying `etag` when the `If-None-Match` header of the request
+/// matches it, weakly as RFC 9110 asks for this header
+pub fn not_modified(req: &HttpRequest, etag: &EntityTag) -> Option<HttpResponse> {
+    let matches = match req.get_header::<IfNoneMatch>()? {
+        IfNoneMatch::Any => true,
+        IfNoneMatch::Items(tags) => tags.iter().any(|tag| tag.weak_eq(etag)),
+    };
+    matches.then(|| {
+        HttpResponse::NotModified()
+            .insert_header(ETag(etag.clone()))
+            .finish()
+    })
+}
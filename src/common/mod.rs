@@ -1 +1,4 @@
-pub mod error;
\ No newline at end of file
+pub mod error;
+pub mod http;
+pub mod pagination;
+pub mod projection;
@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::common::error::AppError;
+
+/// Items per page when `limit` is omitted
+pub const DEFAULT_LIMIT: u32 = 100;
+/// Largest `limit` accepted
+pub const MAX_LIMIT: u32 = 1000;
+
+/// Validated `?limit=&offset=` of a list query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Paging {
+    pub limit: u32,
+    pub offset: u32,
+}
+
+impl Paging {
+    pub fn new(limit: Option<u32>, offset: Option<u32>) -> Result<Self, AppError> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT);
+        if !(1..=MAX_LIMIT).contains(&limit) {
+            return Err(AppError::bad_request(format!(
+                "limit must be between 1 and {}, got {}",
+                MAX_LIMIT, limit
+            )));
+        }
+        Ok(Paging {
+            limit,
+            offset: offset.unwrap_or(0),
+        })
+    }
+}
+
+/// Refuse a `?from=&to=` range that ends before it starts; either bound may be open
+pub fn validate_range(
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> Result<(), AppError> {
+    match (from, to) {
+        (Some(from), Some(to)) if from >= to => {
+            Err(AppError::bad_request("'from' must be before 'to'"))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// One page of a list endpoint: the items, how many match in total and where the next
+/// page starts, `None` on the last one
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: u64,
+    pub limit: u32,
+    pub offset: u32,
+    pub next_offset: Option<u32>,
+}
+
+impl<T> Page<T> {
+    pub fn new(items: Vec<T>, total: u64, paging: Paging) -> Self {
+        let end = paging.offset as u64 + items.len() as u64;
+        Page {
+            next_offset: (end < total).then_some(end as u32),
+            total,
+            limit: paging.limit,
+            offset: paging.offset,
+            items,
+        }
+    }
+
+    /// Same page holding `items`, the converted items of this one
+    pub fn with_items<U>(self, items: Vec<U>) -> Page<U> {
+        Page {
+            items,
+            total: self.total,
+            limit: self.limit,
+            offset: self.offset,
+            next_offset: self.next_offset,
+        }
+    }
+}
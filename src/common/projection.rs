@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+
+use crate::common::error::AppError;
+
+/// Fields kept of a serialized response, from `?fields=engine.rpm,speed.vehicle_speed`
+///
+/// Each field is a dotted path into the JSON object; naming an object keeps it whole.
+/// Paths crossing an array apply to each of its items, and paths missing from a value
+/// (an absent optional group) are left out of it rather than set to `null`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Projection {
+    paths: Vec<Vec<String>>,
+}
+
+impl Projection {
+    pub fn parse(fields: &str) -> Result<Self, AppError> {
+        let paths: Vec<Vec<String>> = fields
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .map(|field| {
+                let path: Vec<String> = field.split('.').map(str::to_string).collect();
+                if path.iter().any(String::is_empty) {
+                    return Err(AppError::bad_request(format!(
+                        "Invalid field '{}', expected a dotted path like engine.rpm",
+                        field
+                    )));
+                }
+                Ok(path)
+            })
+            .collect::<Result<_, _>>()?;
+        if paths.is_empty() {
+            return Err(AppError::bad_request(
+                "fields must name at least one field, e.g. fields=engine.rpm",
+            ));
+        }
+        Ok(Projection { paths })
+    }
+
+    /// The fields as given, dotted
+    pub fn fields(&self) -> impl Iterator<Item = String> + '_ {
+        self.paths.iter().map(|path| path.join("."))
+    }
+
+    /// Copy of `value` holding only the projected fields
+    pub fn apply(&self, value: &Value) -> Value {
+        let paths: Vec<&[String]> = self.paths.iter().map(Vec::as_slice).collect();
+        project(value, &paths).unwrap_or_else(|| Value::Object(Map::new()))
+    }
+}
+
+fn project(value: &Value, paths: &[&[String]]) -> Option<Value> {
+    if paths.iter().any(|path| path.is_empty()) {
+        return Some(value.clone());
+    }
+    match value {
+        Value::Object(fields) => {
+            let mut nested: BTreeMap<&str, Vec<&[String]>> = BTreeMap::new();
+            for path in paths {
+                nested.entry(&path[0]).or_default().push(&path[1..]);
+            }
+            let projected = fields
+                .iter()
+                .filter_map(|(key, field)| {
+                    let rest = nested.get(key.as_str())?;
+                    Some((key.clone(), project(field, rest)?))
+                })
+                .collect();
+            Some(Value::Object(projected))
+        }
+        Value::Array(items) => Some(Value::Array(
+            items
+                .iter()
+                .filter_map(|item| project(item, paths))
+                .collect(),
+        )),
+        _ => None,
+    }
+}
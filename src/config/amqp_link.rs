@@ -0,0 +1,199 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use lapin::{Channel, Connection};
+use tokio::sync::{mpsc, watch};
+
+use crate::config::rabbitmq;
+use crate::config::resilience::RetryPolicy;
+use crate::config::transport::ConsumerControl;
+use crate::core::bus::Bus;
+use crate::features::system::{self, SystemEventKind};
+
+/// Wait before reconnecting, doubled after every failed attempt and jittered like publish
+/// retries so instances losing the same broker do not reconnect together
+const RECONNECT_BACKOFF: RetryPolicy = RetryPolicy {
+    attempts: u32::MAX,
+    base_delay: Duration::from_millis(500),
+    max_delay: Duration::from_secs(30),
+};
+
+/// Time between two checks of the connection status, in case the broker goes away
+/// without lapin reporting an error
+const STATUS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Supervised RabbitMQ connection carrying the step-name queue
+///
+/// A background task watches the connection and its channel. When either fails, it
+/// reconnects with exponential backoff, declares the queue again and hands the new
+/// channel to publishers and to the consumer, which subscribes again on its own.
+/// Until then `channel` returns `None` and publishes fail, which the `Resilient`
+/// transport turns into outbox entries.
+#[derive(Clone)]
+pub struct AmqpLink {
+    url: Arc<str>,
+    queue: Arc<str>,
+    single_active_consumer: bool,
+    channel: watch::Sender<Option<Channel>>,
+    reconnects: Arc<AtomicU64>,
+}
+
+impl AmqpLink {
+    /// Connect to `url`, carrying the step notices on `queue`, and keep the connection up;
+    /// the first connection must succeed, so a misconfigured broker still fails startup
+    pub async fn connect(
+        url: &str,
+        queue: &str,
+        single_active_consumer: bool,
+        bus: &Bus,
+    ) -> lapin::Result<Self> {
+        let (connection, channel) = open(url, queue, single_active_consumer).await?;
+        let link = AmqpLink {
+            url: url.into(),
+            queue: queue.into(),
+            single_active_consumer,
+            channel: watch::Sender::new(Some(channel)),
+            reconnects: Arc::default(),
+        };
+        tokio::spawn(link.clone().supervise(connection, bus.clone()));
+        Ok(link)
+    }
+
+    /// Channel of the current connection, `None` while reconnecting
+    pub fn channel(&self) -> Option<Channel> {
+        self.channel
+            .borrow()
+            .clone()
+            .filter(|channel| channel.status().connected())
+    }
+
+    /// Queue the step notices travel through
+    pub fn queue(&self) -> &str {
+        &self.queue
+    }
+
+    /// Times the connection was restored since startup
+    pub fn reconnects(&self) -> u64 {
+        self.reconnects.load(Ordering::Relaxed)
+    }
+
+    /// Consume step notices on every channel the link opens, resubscribing after each
+    /// reconnection
+    pub fn consume(&self, tx: &Bus, control: &ConsumerControl) {
+        let mut channels = self.channel.subscribe();
+        let queue = self.queue.clone();
+        let tx = tx.clone();
+        let control = control.clone();
+        tokio::spawn(async move {
+            loop {
+                let channel = match channels
+                    .wait_for(|channel| channel.as_ref().is_some_and(|c| c.status().connected()))
+                    .await
+                {
+                    Ok(channel) => channel.clone().expect("checked by wait_for"),
+                    Err(_) => break,
+                };
+                channels.mark_unchanged();
+                if let Err(e) =
+                    rabbitmq::consume_until_closed(&channel, &queue, &tx, &control).await
+                {
+                    println!("⚠️ RabbitMQ Stream: Subscribing failed: {}", e);
+                }
+                // Wait for the supervisor to replace the channel, or retry on the same one
+                let _ = tokio::time::timeout(STATUS_INTERVAL, channels.changed()).await;
+            }
+        });
+    }
+
+    /// Watch the connection, reconnecting whenever it or its channel fails
+    async fn supervise(self, mut connection: Connection, bus: Bus) {
+        loop {
+            let channel = self.channel.borrow().clone();
+            let reason = failure(&connection, channel.as_ref()).await;
+            let lost_at = Instant::now();
+            self.channel.send_replace(None);
+            println!("❌ RabbitMQ: Connection lost ({}), reconnecting", reason);
+            // A failed channel can leave the connection open
+            if connection.status().connected() {
+                let _ = connection.close(200, "Reconnecting").await;
+            }
+
+            let mut attempts = 0;
+            let (reopened, channel) = loop {
+                attempts += 1;
+                tokio::time::sleep(RECONNECT_BACKOFF.delay(attempts)).await;
+                match open(&self.url, &self.queue, self.single_active_consumer).await {
+                    Ok(opened) => break opened,
+                    Err(e) => println!(
+                        "⚠️ RabbitMQ: Reconnecting failed (attempt {}): {}",
+                        attempts, e
+                    ),
+                }
+            };
+            connection = reopened;
+            self.channel.send_replace(Some(channel));
+            self.reconnects.fetch_add(1, Ordering::Relaxed);
+
+            let downtime_ms = lost_at.elapsed().as_millis() as u64;
+            println!(
+                "🔌 RabbitMQ: Reconnected after {} attempt(s), {} ms without a broker",
+                attempts, downtime_ms
+            );
+            system::controller::publish(
+                &bus,
+                SystemEventKind::BrokerReconnected,
+                format!("Reconnected to RabbitMQ after {} ms", downtime_ms),
+                serde_json::json!({
+                    "queue": &*self.queue,
+                    "attempts": attempts,
+                    "downtime_ms": downtime_ms,
+                }),
+            )
+            .await;
+        }
+    }
+}
+
+/// Connection with the step-name queue declared on a channel of its own, and the probe of
+/// its backlog started
+async fn open(
+    url: &str,
+    queue: &str,
+    single_active_consumer: bool,
+) -> lapin::Result<(Connection, Channel)> {
+    let connection = rabbitmq::connect_to(url).await?;
+    let channel =
+        rabbitmq::create_step_name_channel(&connection, queue, single_active_consumer).await?;
+    rabbitmq::spawn_backlog_probe(&connection, queue).await?;
+    Ok((connection, channel))
+}
+
+/// Wait until `connection` or `channel` fails, returning why
+async fn failure(connection: &Connection, channel: Option<&Channel>) -> String {
+    let (errors, mut failed) = mpsc::unbounded_channel();
+    let sender = errors.clone();
+    connection.on_error(move |e| {
+        let _ = sender.send(e.to_string());
+    });
+    if let Some(channel) = channel {
+        channel.on_error(move |e| {
+            let _ = errors.send(e.to_string());
+        });
+    }
+
+    let mut interval = tokio::time::interval(STATUS_INTERVAL);
+    loop {
+        tokio::select! {
+            Some(reason) = failed.recv() => return reason,
+            _ = interval.tick() => {
+                if !connection.status().connected() {
+                    return "connection closed".to_string();
+                }
+                if channel.is_some_and(|channel| !channel.status().connected()) {
+                    return "channel closed".to_string();
+                }
+            }
+        }
+    }
+}
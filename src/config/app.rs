@@ -0,0 +1,243 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::db::SqlLog;
+use crate::config::encryption::EncryptionKey;
+use crate::config::resilience::RetryPolicy;
+use crate::config::settings::{AppSettings, ArchiveStore, ConsumerMode, SqlLogLevel};
+use crate::config::transport::TransportKind;
+use crate::core::can_map::CanIdMap;
+use crate::core::compression::CompressionConfig;
+use crate::core::connections::ConnectionLimits;
+use crate::core::dbc::Dbc;
+use crate::core::scrub::ScrubRules;
+use crate::features::ingest::SigningPolicy;
+use crate::features::integrity::Repair;
+use crate::features::validation::ValidationMode;
+
+/// Runtime settings needed to start the event-bus stack
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    /// Server address, broker, queue, database and bus capacity, as read by
+    /// `AppSettings::load`
+    pub settings: AppSettings,
+    /// Queue used for step notices
+    pub transport: TransportKind,
+    /// Declare the step-name queue with RabbitMQ's single-active-consumer flag, so only
+    /// one of several instances sharing the queue reconstructs steps
+    pub single_active_consumer: bool,
+    /// Attempts and backoff of each step-notice publish
+    pub publish_retry: RetryPolicy,
+    /// Failed publishes in a row after which step notices go straight to the outbox
+    pub breaker_threshold: u32,
+    /// Time the outbox takes every step notice before the broker is tried again
+    pub breaker_cooldown: Duration,
+    /// Logging of SQL statements and slow queries, applied to the pool the server opens
+    pub sql_log: SqlLog,
+    /// AES-GCM key encrypting frame data and event messages before they are stored,
+    /// process-wide once a server is built; `None` stores them in clear
+    pub encryption_key: Option<EncryptionKey>,
+    /// CAN ID of each DrivingStep frame, process-wide once a server is built
+    pub can_ids: CanIdMap,
+    /// Messages of a user DBC decoded next to the DrivingStep frames, process-wide once a
+    /// server is built; none may reuse the CAN ID of a DrivingStep frame
+    pub dbc: Dbc,
+    /// SocketCAN interface whose frames are ingested as they arrive, e.g. `can0`; needs a
+    /// Linux build with the `socketcan` feature
+    pub can_interface: Option<String>,
+    /// gzip and Brotli levels of the HTTP responses
+    pub compression: CompressionConfig,
+    /// Most WebSocket and SSE clients connected at once, overall and per address
+    pub connection_limits: ConnectionLimits,
+    /// Silence after which a trip whose engine never reported off is closed
+    pub trip_idle_timeout: Duration,
+    /// Silence after which a device sending heartbeats is reported offline
+    pub device_timeout: Duration,
+    /// Time a device has to acknowledge a command queued without its own `ttl_seconds`
+    pub command_ttl: Duration,
+    /// Latest bus messages kept for websocket clients resuming a session
+    pub journal_capacity: usize,
+    /// Time a disconnected websocket client has to resume its session
+    pub ws_resume_window: Duration,
+    /// Window rolled up into each summary of `GET /stream/summary`
+    pub summary_interval: Duration,
+    /// Standard deviations from its moving mean at which a signal value is anomalous
+    pub anomaly_sigma: f64,
+    /// Whether ingested frames breaking a validation rule fail the request or are quarantined
+    pub validation_mode: ValidationMode,
+    /// Most frames per second accepted for one CAN ID of one vehicle, unlimited when `None`
+    pub frame_rate_limit: Option<u32>,
+    /// Wait for the missing frames of a step sent to `POST /frames` before giving up on it
+    pub step_assembly_timeout: Duration,
+    /// Directory holding the chunks of `/ingest/uploads` sessions, emptied on startup
+    pub upload_dir: PathBuf,
+    /// Time an upload session is kept without a chunk, a commit or a progress update
+    pub upload_idle_timeout: Duration,
+    /// Age after which the raw frames of a step are replaced by per-minute signal
+    /// aggregates, kept forever when `None`
+    pub raw_frame_retention: Option<Duration>,
+    /// Time between two compaction runs, and between two archive runs
+    pub compaction_interval: Duration,
+    /// Age after which the frames of a step are moved to compressed segment files of
+    /// `archive_dir`, kept in the database when `None`
+    pub archive_after: Option<Duration>,
+    /// Directory holding the archive segments and their `index.json`
+    pub archive_dir: PathBuf,
+    /// Keep the archive segments in the `s3` bucket of the settings, below `archive/`,
+    /// instead of `archive_dir`
+    pub archive_to_s3: bool,
+    /// Time soft-deleted steps, events and scenarios stay restorable before being purged
+    pub trash_retention: Duration,
+    /// Background workers running imports, replays, recodes and compactions side by side
+    pub job_workers: usize,
+    /// Check stored frame groups on startup, repairing broken ones as said, skipped when `None`
+    pub startup_verify: Option<Repair>,
+    /// Key of the administrator; setting one requires an API key on every request and
+    /// scopes the data of each tenant to its own key
+    pub admin_api_key: Option<String>,
+    /// Key following the streams of every tenant, scrubbed; needs `admin_api_key`
+    pub viewer_api_key: Option<String>,
+    /// GPS precision and VIN masking of the messages streamed to viewer keys
+    pub scrub: ScrubRules,
+    /// Whether `POST /ingest` refuses batches not signed with the API key they are sent with
+    pub ingest_signing: SigningPolicy,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            settings: AppSettings::default(),
+            transport: TransportKind::Amqp,
+            single_active_consumer: false,
+            publish_retry: RetryPolicy::default(),
+            breaker_threshold: 5,
+            breaker_cooldown: Duration::from_secs(10),
+            sql_log: SqlLog::default(),
+            encryption_key: None,
+            can_ids: CanIdMap::default(),
+            dbc: Dbc::default(),
+            can_interface: None,
+            compression: CompressionConfig::default(),
+            connection_limits: ConnectionLimits::default(),
+            trip_idle_timeout: Duration::from_secs(300),
+            device_timeout: Duration::from_secs(60),
+            command_ttl: Duration::from_secs(300),
+            journal_capacity: 10_000,
+            ws_resume_window: Duration::from_secs(300),
+            summary_interval: Duration::from_secs(1),
+            anomaly_sigma: 4.0,
+            validation_mode: ValidationMode::Reject,
+            frame_rate_limit: None,
+            step_assembly_timeout: Duration::from_secs(5),
+            upload_dir: std::env::temp_dir().join("eventbus-uploads"),
+            upload_idle_timeout: Duration::from_secs(3600),
+            raw_frame_retention: None,
+            compaction_interval: Duration::from_secs(600),
+            archive_after: None,
+            archive_dir: PathBuf::from("archive"),
+            archive_to_s3: false,
+            trash_retention: Duration::from_secs(30 * 24 * 3600),
+            job_workers: 2,
+            startup_verify: None,
+            admin_api_key: None,
+            viewer_api_key: None,
+            scrub: ScrubRules::default(),
+            ingest_signing: SigningPolicy::default(),
+        }
+    }
+}
+
+impl AppConfig {
+    /// Runtime settings of `settings`, reading the CAN ID map, DBC and encryption key
+    /// files they name
+    pub fn from_settings(settings: AppSettings) -> Result<AppConfig, String> {
+        settings.validate()?;
+        let read = |path: &std::path::Path| {
+            std::fs::read_to_string(path)
+                .map_err(|e| format!("Could not read {}: {}", path.display(), e))
+        };
+        let can_ids = match &settings.can.id_map {
+            Some(path) => serde_json::from_str(&read(path)?)
+                .map_err(|e| format!("Invalid CAN ID map {}: {}", path.display(), e))?,
+            None => CanIdMap::default(),
+        };
+        let dbc = match &settings.can.dbc_file {
+            Some(path) => read(path)?
+                .parse()
+                .map_err(|e| format!("Invalid DBC file {}: {}", path.display(), e))?,
+            None => Dbc::default(),
+        };
+        let key = match (&settings.encryption.key_file, &settings.encryption.key) {
+            (Some(path), _) => Some((read(path)?, path.display().to_string())),
+            (None, Some(key)) => Some((key.clone(), "encryption.key".to_string())),
+            (None, None) => None,
+        };
+        let encryption_key = key
+            .map(|(key, source)| {
+                key.parse()
+                    .map_err(|e| format!("Invalid encryption key {}: {}", source, e))
+            })
+            .transpose()?;
+
+        let defaults = AppConfig::default();
+        Ok(AppConfig {
+            transport: settings.transport.kind,
+            single_active_consumer: settings.transport.consumer == ConsumerMode::SingleActive,
+            publish_retry: RetryPolicy {
+                attempts: settings.transport.publish_retries,
+                ..defaults.publish_retry
+            },
+            breaker_cooldown: Duration::from_secs_f64(settings.transport.breaker_cooldown_secs),
+            sql_log: SqlLog {
+                statements: settings.database.log == SqlLogLevel::All,
+                slow_threshold: Duration::from_millis(settings.database.slow_query_ms),
+            },
+            encryption_key,
+            can_ids,
+            dbc,
+            can_interface: settings.can.interface.clone(),
+            compression: CompressionConfig {
+                gzip_level: settings.compression.gzip_level,
+                brotli_quality: settings.compression.brotli_quality,
+            },
+            connection_limits: ConnectionLimits {
+                max_total: settings.streams.max_connections,
+                max_per_ip: settings.streams.max_connections_per_ip,
+                retry_after: Duration::from_secs(settings.streams.retry_after_secs),
+            },
+            device_timeout: Duration::from_secs_f64(settings.devices.timeout_secs),
+            command_ttl: Duration::from_secs_f64(settings.devices.command_ttl_secs),
+            ws_resume_window: Duration::from_secs_f64(settings.streams.resume_window_secs),
+            validation_mode: settings.ingest.validation,
+            frame_rate_limit: settings.ingest.frame_rate_limit,
+            step_assembly_timeout: Duration::from_secs_f64(settings.ingest.assembly_timeout_secs),
+            upload_dir: settings.ingest.upload_dir.clone(),
+            upload_idle_timeout: Duration::from_secs_f64(settings.ingest.upload_idle_timeout_secs),
+            raw_frame_retention: settings
+                .retention
+                .raw_frames_secs
+                .map(Duration::from_secs_f64),
+            archive_after: settings
+                .archive
+                .after_days
+                .map(|days| Duration::from_secs_f64(days * 24.0 * 3600.0)),
+            archive_dir: settings.archive.dir.clone(),
+            archive_to_s3: settings.archive.store == ArchiveStore::S3,
+            trash_retention: Duration::from_secs_f64(settings.retention.trash_secs),
+            job_workers: settings.jobs.workers,
+            startup_verify: settings.jobs.verify_on_startup,
+            admin_api_key: settings.admin_api_key().map(str::to_string),
+            viewer_api_key: settings.viewer_api_key().map(str::to_string),
+            scrub: ScrubRules {
+                gps_decimals: settings.scrub.gps_decimals,
+                mask_vin: settings.scrub.mask_vin,
+            },
+            ingest_signing: SigningPolicy {
+                required: settings.ingest.require_signed,
+                ..defaults.ingest_signing
+            },
+            settings,
+            ..defaults
+        })
+    }
+}
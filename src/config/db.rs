@@ -0,0 +1,179 @@
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use log::LevelFilter;
+use sqlx::any::{AnyConnectOptions, AnyPoolOptions, AnyRow};
+use sqlx::{AnyPool, ConnectOptions, Result, Row};
+
+use crate::config::settings::DatabaseSettings;
+use crate::config::{postgres, sqlite};
+use crate::core::metrics;
+
+pub(crate) static SQLX_POOL: tokio::sync::OnceCell<AnyPool> = tokio::sync::OnceCell::const_new();
+
+static SQL_LOG: OnceLock<SqlLog> = OnceLock::new();
+
+/// Database engine a `DATABASE_URL` points to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DbBackend {
+    /// `sqlite:<file>?mode=rwc`, or `sqlite::memory:`
+    Sqlite,
+    /// `postgres://` or `postgresql://`
+    Postgres,
+}
+
+impl DbBackend {
+    /// Backend `pool` was opened on
+    pub fn of(pool: &AnyPool) -> Result<Self> {
+        pool.connect_options()
+            .database_url
+            .as_str()
+            .parse()
+            .map_err(|e: String| sqlx::Error::Configuration(e.into()))
+    }
+}
+
+impl FromStr for DbBackend {
+    type Err = String;
+
+    /// Backend of a database URL, told by its scheme
+    fn from_str(url: &str) -> std::result::Result<Self, Self::Err> {
+        let scheme = url.split(':').next().unwrap_or_default();
+        match scheme.to_ascii_lowercase().as_str() {
+            "sqlite" => Ok(DbBackend::Sqlite),
+            "postgres" | "postgresql" => Ok(DbBackend::Postgres),
+            _ => Err(format!(
+                "Unknown database URL '{}', expected sqlite: or postgres://",
+                url
+            )),
+        }
+    }
+}
+
+/// How SQL statements are logged, through `tracing` (forwarded to `log`)
+///
+/// sqlx logs each statement under the `sqlx::query` target; `labelled` queries are also
+/// logged under the `sql` target with their label and recorded in `/metrics`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SqlLog {
+    /// Log every statement at `info` rather than `debug`
+    pub statements: bool,
+    /// Statements and labelled queries running at least this long are logged at `warn`
+    pub slow_threshold: Duration,
+}
+
+impl Default for SqlLog {
+    fn default() -> Self {
+        SqlLog {
+            statements: false,
+            slow_threshold: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Log SQL as `settings` say, to be called before the pool is opened
+///
+/// Returns the settings back if logging was already configured.
+pub fn set_sql_log(settings: SqlLog) -> std::result::Result<(), SqlLog> {
+    SQL_LOG.set(settings)
+}
+
+fn sql_log() -> SqlLog {
+    *SQL_LOG.get_or_init(SqlLog::default)
+}
+
+/// Get the pool instance, opening the default SQLite database if no pool exists yet
+pub async fn get_pool() -> Result<&'static AnyPool> {
+    connect(&DatabaseSettings::default()).await
+}
+
+/// Get the pool instance, opening the database of `settings` if no pool exists yet
+pub async fn connect(settings: &DatabaseSettings) -> Result<&'static AnyPool> {
+    SQLX_POOL
+        .get_or_try_init(|| async {
+            let log = sql_log();
+            let statements = if log.statements {
+                LevelFilter::Info
+            } else {
+                LevelFilter::Debug
+            };
+            let options = AnyConnectOptions::from_str(&settings.url)?
+                .log_statements(statements)
+                .log_slow_statements(LevelFilter::Warn, log.slow_threshold);
+            open(options).await
+        })
+        .await
+}
+
+/// Open a pool on `options`, installing the SQLite and PostgreSQL drivers first
+///
+/// Every connection to an in-memory SQLite URL is its own database, so those pools keep a
+/// single connection open for good.
+pub async fn open(options: AnyConnectOptions) -> Result<AnyPool> {
+    sqlx::any::install_default_drivers();
+    let url = options.database_url.as_str();
+    let pool = if url.starts_with("sqlite") && url.contains(":memory:") {
+        AnyPoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+    } else {
+        AnyPoolOptions::new()
+    };
+    pool.connect_with(options).await
+}
+
+/// Run the queries of `query` under `label`, timing them as one
+///
+/// The time is recorded in the `sql_query_duration_seconds` histogram of `/metrics` and
+/// logged under the `sql` target, at `warn` past the slow-query threshold.
+pub async fn labelled<T>(label: &'static str, query: impl Future<Output = T>) -> T {
+    let started = Instant::now();
+    let output = query.await;
+    let elapsed = started.elapsed();
+
+    metrics::queries().observe(label, elapsed.as_secs_f64());
+    let log = sql_log();
+    let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+    if elapsed >= log.slow_threshold {
+        tracing::warn!(target: "sql", query = label, elapsed_ms, "slow query '{}'", label);
+    } else if log.statements {
+        tracing::info!(target: "sql", query = label, elapsed_ms, "query '{}'", label);
+    } else {
+        tracing::debug!(target: "sql", query = label, elapsed_ms, "query '{}'", label);
+    }
+    output
+}
+
+/// Use an externally created pool instead of opening the default database
+///
+/// Returns the pool back if one was already initialized.
+pub fn set_pool(pool: AnyPool) -> std::result::Result<(), AnyPool> {
+    SQLX_POOL.set(pool).map_err(|error| match error {
+        tokio::sync::SetError::AlreadyInitializedError(pool)
+        | tokio::sync::SetError::InitializingError(pool) => pool,
+    })
+}
+
+/// Create or upgrade the tables of the crate-wide pool
+pub async fn init() -> Result<()> {
+    apply_schema(get_pool().await?).await
+}
+
+/// Create or upgrade every table of the crate in `pool`, in the dialect of its backend
+pub async fn apply_schema(pool: &AnyPool) -> Result<()> {
+    match DbBackend::of(pool)? {
+        DbBackend::Sqlite => sqlite::apply_schema(pool).await,
+        DbBackend::Postgres => postgres::apply_schema(pool).await,
+    }
+}
+
+/// Read the flag `column` of `row`
+///
+/// PostgreSQL flags are `BOOLEAN`s, SQLite stores them as the integers 0 and 1.
+pub fn flag(row: &AnyRow, column: &str) -> Result<bool> {
+    row.try_get::<bool, _>(column)
+        .or_else(|_| Ok(row.try_get::<i64, _>(column)? != 0))
+}
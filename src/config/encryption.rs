@@ -0,0 +1,177 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+/// Prefix of a text column stored encrypted, followed by the base64 of nonce and ciphertext
+const TEXT_PREFIX: &str = "enc1:";
+
+const NONCE_LEN: usize = 12;
+
+/// 256-bit AES-GCM key of the columns encrypted at rest
+///
+/// Parses from 32 bytes in base64 or in hex, the form KMS and secret managers hand out.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; 32]);
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+impl FromStr for EncryptionKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let bytes = if s.len() == 64 && s.bytes().all(|b| b.is_ascii_hexdigit()) {
+            (0..64)
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&s[i..i + 2], 16))
+                .collect::<Result<Vec<u8>, _>>()
+                .map_err(|e| e.to_string())?
+        } else {
+            BASE64
+                .decode(s)
+                .map_err(|e| format!("not base64 or hex: {}", e))?
+        };
+        let key: [u8; 32] = bytes
+            .try_into()
+            .map_err(|bytes: Vec<u8>| format!("{} bytes, expected 32", bytes.len()))?;
+        Ok(EncryptionKey(key))
+    }
+}
+
+/// Column whose values are encrypted, bound to the ciphertext so a value copied into
+/// another column does not decrypt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    /// `data` of `can_messages` and `rejected_frames`
+    FrameData,
+    EventMessage,
+    EventPayload,
+    /// `secret` of `exports`, the secret access key of S3 destinations
+    ExportSecret,
+}
+
+impl Column {
+    fn label(self) -> &'static [u8] {
+        match self {
+            Column::FrameData => b"frame_data",
+            Column::EventMessage => b"events.message",
+            Column::EventPayload => b"events.payload",
+            Column::ExportSecret => b"exports.secret",
+        }
+    }
+}
+
+struct Sealer {
+    key: EncryptionKey,
+    cipher: Aes256Gcm,
+}
+
+static SEALER: OnceLock<Option<Sealer>> = OnceLock::new();
+
+/// Install the encryption key of the process, `None` storing in clear, once and before the
+/// first value is stored; returns the key back when another setting is already in force
+pub fn set_key(key: Option<EncryptionKey>) -> Result<(), Option<EncryptionKey>> {
+    let sealer = key.clone().map(|key| Sealer {
+        cipher: Aes256Gcm::new(&key.0.into()),
+        key,
+    });
+    SEALER.set(sealer).map_err(|_| key)
+}
+
+/// Encryption key in force, `None` unless `set_key` installed one
+pub fn key() -> Option<&'static EncryptionKey> {
+    sealer().map(|sealer| &sealer.key)
+}
+
+fn sealer() -> Option<&'static Sealer> {
+    SEALER.get_or_init(|| None).as_ref()
+}
+
+fn seal(sealer: &Sealer, column: Column, plain: &[u8]) -> Vec<u8> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = sealer
+        .cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plain,
+                aad: column.label(),
+            },
+        )
+        .expect("AES-GCM encrypts any value stored");
+    [nonce.as_slice(), &ciphertext].concat()
+}
+
+fn open(column: Column, sealed: &[u8]) -> Result<Vec<u8>, String> {
+    let sealer = sealer().ok_or_else(|| {
+        "Stored value is encrypted but no encryption key is configured".to_string()
+    })?;
+    if sealed.len() < NONCE_LEN {
+        return Err("Stored value is too short to be encrypted".to_string());
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    sealer
+        .cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: column.label(),
+            },
+        )
+        .map_err(|_| "Stored value does not decrypt with the configured key".to_string())
+}
+
+/// Frame data as stored: encrypted under the key in force, as is without one
+pub fn seal_frame(data: &[u8]) -> Vec<u8> {
+    match sealer() {
+        Some(sealer) => seal(sealer, Column::FrameData, data),
+        None => data.to_vec(),
+    }
+}
+
+/// Frame data of `dlc` bytes back from storage; data stored in clear, as long as the DLC
+/// (a sealed payload carries a nonce and a tag more), passes through
+pub fn open_frame(stored: Vec<u8>, dlc: usize) -> Result<Vec<u8>, String> {
+    if stored.len() == dlc {
+        return Ok(stored);
+    }
+    open_sealed_frame(&stored)
+}
+
+/// Data sealed by `seal_frame` under the key in force, such as an archive segment
+pub fn open_sealed_frame(sealed: &[u8]) -> Result<Vec<u8>, String> {
+    open(Column::FrameData, sealed)
+}
+
+/// Text as stored in `column`: encrypted under the key in force, as is without one
+pub fn seal_text(column: Column, text: &str) -> String {
+    match sealer() {
+        Some(sealer) => format!(
+            "{}{}",
+            TEXT_PREFIX,
+            BASE64.encode(seal(sealer, column, text.as_bytes()))
+        ),
+        None => text.to_string(),
+    }
+}
+
+/// Text back from storage in `column`; text stored in clear passes through
+pub fn open_text(column: Column, stored: String) -> Result<String, String> {
+    let Some(encoded) = stored.strip_prefix(TEXT_PREFIX) else {
+        return Ok(stored);
+    };
+    let sealed = BASE64
+        .decode(encoded)
+        .map_err(|e| format!("Stored value is not valid ciphertext: {}", e))?;
+    String::from_utf8(open(column, &sealed)?).map_err(|e| e.to_string())
+}
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use futures_util::future::BoxFuture;
+use sqlx::any::AnyRow;
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::config::db::{self, DbBackend};
+use crate::config::encryption;
+use crate::core::can::{CanMessage, Endianness};
+use crate::core::clock;
+use crate::features::driving_step::model::StoredStep;
+use crate::features::driving_step::service::{
+    can_message_from_row, endianness_from_row, group_endianness,
+};
+use crate::features::tenant::service as tenant_service;
+use crate::features::tenant::Usage;
+
+static FRAME_STORE: OnceLock<Box<dyn FrameStore>> = OnceLock::new();
+
+/// Frames of one step to store together
+#[derive(Debug, Clone)]
+pub struct FrameBatch<'a> {
+    pub step_id: String,
+    pub frames: Vec<CanMessage>,
+    pub endian: Endianness,
+    pub vehicle_id: Option<&'a str>,
+    pub tenant: Option<&'a str>,
+    pub step_name: &'a str,
+}
+
+/// Stored steps a `FrameStore::query_range` reads, every live step when left empty
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameRange<'a> {
+    /// Only the steps of this tenant
+    pub tenant: Option<&'a str>,
+    /// Only this step
+    pub step_id: Option<&'a str>,
+    /// Only the steps whose first frame is numbered after this one
+    pub after_seq: Option<u64>,
+}
+
+/// Where the CAN frames of driving steps are kept
+///
+/// Services store and read frames through `frame_store()`, the shared database unless an
+/// embedding application set another backend with `set_frame_store` before serving. Frames
+/// are kept by step, numbered in storage order; trashed steps are not read. Searches joining
+/// other tables, such as the pages of `GET /driving-steps`, still read the database.
+pub trait FrameStore: Send + Sync {
+    /// Store the frames of one step at once, numbering them after every frame stored so far
+    ///
+    /// Fails with `403 Forbidden` or `429 Too Many Requests` when the frames would exceed a
+    /// quota of the tenant, see `tenant::service::check_quota`.
+    fn insert_batch<'a>(
+        &'a self,
+        batch: FrameBatch<'a>,
+    ) -> BoxFuture<'a, Result<StoredStep, AppError>>;
+
+    /// Steps of `range` with their frames, in storage order
+    ///
+    /// Steps whose frames mix byte orders cannot be decoded and are skipped with a warning.
+    fn query_range<'a>(
+        &'a self,
+        range: FrameRange<'a>,
+    ) -> BoxFuture<'a, Result<Vec<StoredStep>, AppError>>;
+
+    /// The most recently stored step of `tenant`, or of any tenant
+    fn latest_group<'a>(
+        &'a self,
+        tenant: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Option<StoredStep>, AppError>>;
+
+    /// Delete the frames numbered `seqs` for good, returning how many were deleted
+    fn prune<'a>(&'a self, seqs: &'a [u64]) -> BoxFuture<'a, Result<u64, AppError>>;
+}
+
+/// Store frames in `store` instead of the database, to be called before serving
+///
+/// Returns the store back if frames already went to another one.
+pub fn set_frame_store(store: Box<dyn FrameStore>) -> Result<(), Box<dyn FrameStore>> {
+    FRAME_STORE.set(store)
+}
+
+/// Backend keeping the frames, `SqlFrameStore` unless one was set
+pub fn frame_store() -> &'static dyn FrameStore {
+    FRAME_STORE.get_or_init(|| Box::new(SqlFrameStore)).as_ref()
+}
+
+/// Frames in the `can_messages` table of the shared SQLite or PostgreSQL pool, sealed when
+/// encryption at rest is on
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SqlFrameStore;
+
+impl FrameStore for SqlFrameStore {
+    /// Each frame gets the next sequence number of the table; the number is read and written
+    /// by the same statement, under SQLite's write lock or a lock of the table taken first on
+    /// PostgreSQL, so concurrent writers never share one and numbers follow commit order.
+    /// The quota check, the frames and the usage of the tenant share a transaction.
+    fn insert_batch<'a>(
+        &'a self,
+        batch: FrameBatch<'a>,
+    ) -> BoxFuture<'a, Result<StoredStep, AppError>> {
+        Box::pin(async move {
+            let pool = db::get_pool().await?;
+            let FrameBatch {
+                step_id,
+                mut frames,
+                endian,
+                vehicle_id,
+                tenant,
+                step_name,
+            } = batch;
+
+            let usage = Usage::of(&frames);
+            let mut transaction = pool.begin().await?;
+            if DbBackend::of(pool)? == DbBackend::Postgres {
+                sqlx::query("LOCK TABLE can_messages IN EXCLUSIVE MODE")
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+            if let Some(tenant) = tenant {
+                tenant_service::check_quota(&mut transaction, tenant, 1, usage).await?;
+            }
+            for frame in &mut frames {
+                let seq: i64 = sqlx::query_scalar(
+                    "INSERT INTO can_messages
+                     (id, dlc, data, fd, brs, timestamp, timestamp_ms, endian, step_id, step_name, vehicle_id, tenant, seq)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, (SELECT COALESCE(MAX(seq), 0) + 1 FROM can_messages))
+                     RETURNING seq",
+                )
+                .bind(frame.id as i64)
+                .bind(frame.dlc as i64)
+                .bind(encryption::seal_frame(frame.data()))
+                .bind(frame.fd)
+                .bind(frame.brs)
+                .bind(&frame.timestamp)
+                .bind(clock::epoch_ms(&frame.timestamp))
+                .bind(endian.as_str())
+                .bind(&step_id)
+                .bind(step_name)
+                .bind(vehicle_id)
+                .bind(tenant)
+                .fetch_one(&mut *transaction)
+                .await?;
+                frame.seq = Some(seq as u64);
+            }
+            if let Some(tenant) = tenant {
+                tenant_service::record_usage(&mut transaction, tenant, usage).await?;
+            }
+            transaction.commit().await?;
+
+            Ok(StoredStep {
+                step_id,
+                endian,
+                vehicle_id: vehicle_id.map(str::to_string),
+                tenant: tenant.map(str::to_string),
+                step_name: Some(step_name.to_string()),
+                can_messages: frames,
+            })
+        })
+    }
+
+    fn query_range<'a>(
+        &'a self,
+        range: FrameRange<'a>,
+    ) -> BoxFuture<'a, Result<Vec<StoredStep>, AppError>> {
+        Box::pin(async move {
+            let pool = db::get_pool().await?;
+
+            let query = sqlx::query(
+                "SELECT id, dlc, data, fd, brs, timestamp, endian, seq, step_id, step_name, vehicle_id, tenant
+                 FROM can_messages
+                 WHERE deleted_at IS NULL AND ($1 IS NULL OR tenant = $1)
+                   AND ($2 IS NULL OR step_id = $2)
+                   AND ($3 IS NULL OR step_id IN (
+                       SELECT step_id FROM can_messages GROUP BY step_id HAVING MIN(seq) > $3
+                   ))
+                 ORDER BY seq ASC",
+            )
+            .bind(range.tenant)
+            .bind(range.step_id)
+            .bind(range.after_seq.map(|seq| seq as i64))
+            .fetch_all(pool);
+            let rows = db::labelled("frame_range", query).await?;
+
+            stored_steps(&rows)
+        })
+    }
+
+    fn latest_group<'a>(
+        &'a self,
+        tenant: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Option<StoredStep>, AppError>> {
+        Box::pin(async move {
+            let pool = db::get_pool().await?;
+
+            // Find the most recently stored step, then load only its own frames
+            let query = sqlx::query_scalar(
+                "SELECT step_id FROM can_messages
+                 WHERE step_id IS NOT NULL AND deleted_at IS NULL AND ($1 IS NULL OR tenant = $1)
+                 ORDER BY seq DESC LIMIT 1",
+            )
+            .bind(tenant)
+            .fetch_optional(pool);
+            let last_step_id: Option<String> = db::labelled("last_step", query).await?;
+
+            let Some(step_id) = last_step_id else {
+                return Ok(None);
+            };
+            let range = FrameRange {
+                tenant,
+                step_id: Some(&step_id),
+                after_seq: None,
+            };
+            Ok(self.query_range(range).await?.pop())
+        })
+    }
+
+    fn prune<'a>(&'a self, seqs: &'a [u64]) -> BoxFuture<'a, Result<u64, AppError>> {
+        Box::pin(async move {
+            let pool = db::get_pool().await?;
+
+            let mut transaction = pool.begin().await?;
+            let mut deleted = 0;
+            for seq in seqs {
+                deleted += sqlx::query("DELETE FROM can_messages WHERE seq = $1")
+                    .bind(*seq as i64)
+                    .execute(&mut *transaction)
+                    .await?
+                    .rows_affected();
+            }
+            transaction.commit().await?;
+            Ok(deleted)
+        })
+    }
+}
+
+/// Steps of `can_messages` rows read in storage order, in the order they were first stored
+///
+/// Rows must carry every column of the table but `deleted_at`.
+pub fn stored_steps(rows: &[AnyRow]) -> Result<Vec<StoredStep>, AppError> {
+    let mut steps: Vec<(StoredStep, Vec<Endianness>)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for row in rows {
+        let step_id: String = row.try_get("step_id")?;
+        let frame = can_message_from_row(row)?;
+        let endian = endianness_from_row(row)?;
+
+        let position = match index.get(&step_id) {
+            Some(position) => *position,
+            None => {
+                index.insert(step_id.clone(), steps.len());
+                let step = StoredStep {
+                    step_id,
+                    endian,
+                    vehicle_id: row.try_get("vehicle_id")?,
+                    tenant: row.try_get("tenant")?,
+                    step_name: row.try_get("step_name")?,
+                    can_messages: Vec::new(),
+                };
+                steps.push((step, Vec::new()));
+                steps.len() - 1
+            }
+        };
+        let (step, endians) = &mut steps[position];
+        step.can_messages.push(frame);
+        endians.push(endian);
+    }
+
+    Ok(steps
+        .into_iter()
+        .filter_map(|(mut step, endians)| match group_endianness(&endians) {
+            Ok(endian) => {
+                step.endian = endian;
+                Some(step)
+            }
+            Err(e) => {
+                println!("⚠️ Could not read driving step {}: {}", step.step_id, e);
+                None
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,92 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+
+/// In-process stand-in for the RabbitMQ step-name queue
+///
+/// Deliveries must be acknowledged like AMQP deliveries: a delivery that is dropped
+/// without `ack` (or `nack` with `requeue = true`) is put back on the queue.
+#[derive(Clone)]
+pub struct MemoryQueue {
+    sender: mpsc::UnboundedSender<Vec<u8>>,
+    receiver: Arc<Mutex<mpsc::UnboundedReceiver<Vec<u8>>>>,
+    /// Messages waiting in the queue, requeued ones included
+    ready: Arc<AtomicUsize>,
+}
+
+impl Default for MemoryQueue {
+    fn default() -> Self {
+        MemoryQueue::new()
+    }
+}
+
+impl MemoryQueue {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        MemoryQueue {
+            sender,
+            receiver: Arc::new(Mutex::new(receiver)),
+            ready: Arc::default(),
+        }
+    }
+
+    /// Enqueue a message payload
+    pub fn publish(&self, payload: Vec<u8>) -> Result<(), String> {
+        // Counted before sending, so a consumer never sees the message uncounted
+        self.ready.fetch_add(1, Ordering::Relaxed);
+        self.sender.send(payload).map_err(|_| {
+            self.ready.fetch_sub(1, Ordering::Relaxed);
+            "In-memory queue is closed".to_string()
+        })
+    }
+
+    /// Number of messages waiting for a consumer
+    pub fn len(&self) -> usize {
+        self.ready.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Wait for the next delivery; competing consumers each receive different messages
+    pub async fn next_delivery(&self) -> Option<MemoryDelivery> {
+        let data = self.receiver.lock().await.recv().await?;
+        self.ready.fetch_sub(1, Ordering::Relaxed);
+        Some(MemoryDelivery {
+            data,
+            requeue: Some((self.sender.clone(), self.ready.clone())),
+        })
+    }
+}
+
+/// A message taken from a MemoryQueue, pending acknowledgement
+pub struct MemoryDelivery {
+    pub data: Vec<u8>,
+    requeue: Option<(mpsc::UnboundedSender<Vec<u8>>, Arc<AtomicUsize>)>,
+}
+
+impl MemoryDelivery {
+    /// Acknowledge the delivery, removing it from the queue for good
+    pub fn ack(mut self) {
+        self.requeue = None;
+    }
+
+    /// Reject the delivery, optionally putting it back on the queue
+    pub fn nack(mut self, requeue: bool) {
+        if !requeue {
+            self.requeue = None;
+        }
+    }
+}
+
+impl Drop for MemoryDelivery {
+    fn drop(&mut self) {
+        if let Some((sender, ready)) = self.requeue.take() {
+            ready.fetch_add(1, Ordering::Relaxed);
+            if sender.send(std::mem::take(&mut self.data)).is_err() {
+                ready.fetch_sub(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
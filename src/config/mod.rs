@@ -1,2 +1,18 @@
+pub mod amqp_link;
+pub mod app;
+pub mod db;
+pub mod encryption;
+pub mod frame_store;
+pub mod memory_queue;
+pub mod object_store;
+pub mod outbox;
+pub mod postgres;
 pub mod rabbitmq;
+pub mod resilience;
+pub mod s3;
+pub mod settings;
 pub mod sqlite;
+pub mod transport;
+
+pub use app::AppConfig;
+pub use settings::AppSettings;
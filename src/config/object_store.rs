@@ -0,0 +1,96 @@
+//! Where archive segments and export files are kept: a directory of the server or a bucket
+//! of an S3-compatible object store
+
+use std::io;
+use std::path::PathBuf;
+
+use derive_more::Display;
+
+use crate::config::s3::{S3Bucket, S3Error};
+
+#[derive(Debug, Display)]
+pub enum ObjectStoreError {
+    #[display("{}", _0)]
+    Io(io::Error),
+    #[display("{}", _0)]
+    S3(S3Error),
+}
+
+impl std::error::Error for ObjectStoreError {}
+
+impl From<io::Error> for ObjectStoreError {
+    fn from(error: io::Error) -> Self {
+        ObjectStoreError::Io(error)
+    }
+}
+
+impl From<S3Error> for ObjectStoreError {
+    fn from(error: S3Error) -> Self {
+        ObjectStoreError::S3(error)
+    }
+}
+
+/// Objects addressed by `/`-separated keys
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ObjectStore {
+    /// Files below a directory, written through a `.partial` sibling renamed once complete
+    Local(PathBuf),
+    /// Objects below the prefix of a bucket
+    S3(S3Bucket),
+}
+
+impl ObjectStore {
+    /// The same store with every key below `prefix`, e.g. `archive/`
+    pub fn scoped(&self, prefix: &str) -> Self {
+        match self {
+            ObjectStore::Local(dir) => ObjectStore::Local(dir.join(prefix)),
+            ObjectStore::S3(bucket) => ObjectStore::S3(S3Bucket {
+                prefix: format!("{}{}", bucket.prefix, prefix),
+                ..bucket.clone()
+            }),
+        }
+    }
+
+    /// Path or URL of `key`, the store itself for an empty key
+    pub fn location(&self, key: &str) -> String {
+        match self {
+            ObjectStore::Local(dir) => dir.join(key).display().to_string(),
+            ObjectStore::S3(bucket) => bucket.url(key),
+        }
+    }
+
+    /// Write `body` as `key`, replacing any previous object, and return its location
+    pub async fn put(
+        &self,
+        key: &str,
+        body: Vec<u8>,
+        content_type: &str,
+    ) -> Result<String, ObjectStoreError> {
+        match self {
+            ObjectStore::Local(dir) => {
+                let path = dir.join(key);
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                let mut partial = path.clone().into_os_string();
+                partial.push(".partial");
+                tokio::fs::write(&partial, body).await?;
+                tokio::fs::rename(&partial, &path).await?;
+                Ok(path.display().to_string())
+            }
+            ObjectStore::S3(bucket) => Ok(bucket.put_object(key, body, content_type).await?),
+        }
+    }
+
+    /// Content of `key`, `None` when there is no such object
+    pub async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, ObjectStoreError> {
+        match self {
+            ObjectStore::Local(dir) => match tokio::fs::read(dir.join(key)).await {
+                Ok(body) => Ok(Some(body)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            },
+            ObjectStore::S3(bucket) => Ok(bucket.get_object(key).await?),
+        }
+    }
+}
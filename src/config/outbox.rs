@@ -0,0 +1,61 @@
+use sqlx::{Result, Row};
+
+use crate::config::db;
+use crate::config::rabbitmq::StepNotice;
+
+/// Step notices relayed per outbox pass
+pub const RELAY_BATCH: i64 = 100;
+
+/// Keep a step notice the broker could not take, for the relay to publish later
+pub async fn store(notice: &StepNotice) -> Result<()> {
+    let pool = db::get_pool().await?;
+    let payload = serde_json::to_string(notice).unwrap_or_default();
+    sqlx::query("INSERT INTO step_outbox (step_id, notice, created_at) VALUES ($1, $2, $3)")
+        .bind(&notice.step_id)
+        .bind(payload)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Oldest waiting notices with their outbox id, at most `limit`
+pub async fn pending(limit: i64) -> Result<Vec<(i64, StepNotice)>> {
+    let pool = db::get_pool().await?;
+    let rows = sqlx::query("SELECT id, notice FROM step_outbox ORDER BY id ASC LIMIT $1")
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+    let mut notices = Vec::with_capacity(rows.len());
+    for row in rows {
+        let id: i64 = row.try_get("id")?;
+        let notice: String = row.try_get("notice")?;
+        match serde_json::from_str(&notice) {
+            Ok(notice) => notices.push((id, notice)),
+            Err(e) => {
+                println!("❌ Outbox: Dropping malformed step notice {}: {}", id, e);
+                remove(id).await?;
+            }
+        }
+    }
+    Ok(notices)
+}
+
+/// Forget a notice once it reached the broker
+pub async fn remove(id: i64) -> Result<()> {
+    let pool = db::get_pool().await?;
+    sqlx::query("DELETE FROM step_outbox WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Number of notices waiting for the broker
+pub async fn count() -> Result<i64> {
+    let pool = db::get_pool().await?;
+    sqlx::query_scalar("SELECT COUNT(*) FROM step_outbox")
+        .fetch_one(pool)
+        .await
+}
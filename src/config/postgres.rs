@@ -0,0 +1,412 @@
+//! PostgreSQL schema, the tables of `config::sqlite` in PostgreSQL types
+//!
+//! Integers are `BIGINT`, floats `DOUBLE PRECISION`, payloads `BYTEA` and the `fd`, `brs`
+//! and `online` flags `BOOLEAN`. Tables whose rows are listed in insertion order get a
+//! `rowid` identity column standing in for SQLite's implicit one, left out of snapshots.
+//! PostgreSQL databases start at the current schema, so none of the SQLite migrations of
+//! older databases apply.
+
+use sqlx::{AnyPool, Result};
+
+const SCHEMA: &[&str] = &[
+    r#"
+    CREATE TABLE IF NOT EXISTS can_messages (
+        id BIGINT NOT NULL,
+        dlc BIGINT NOT NULL,
+        data BYTEA NOT NULL,
+        fd BOOLEAN NOT NULL DEFAULT FALSE,
+        brs BOOLEAN NOT NULL DEFAULT FALSE,
+        timestamp TEXT NOT NULL,
+        timestamp_ms BIGINT,
+        endian TEXT NOT NULL,
+        step_id TEXT NOT NULL,
+        step_name TEXT,
+        vehicle_id TEXT,
+        seq BIGINT,
+        tenant TEXT,
+        deleted_at TEXT,
+        rowid BIGINT GENERATED BY DEFAULT AS IDENTITY,
+        PRIMARY KEY (step_id, id)
+    )
+    "#,
+    "CREATE INDEX IF NOT EXISTS idx_can_messages_tenant ON can_messages (tenant)",
+    "CREATE INDEX IF NOT EXISTS idx_can_messages_step_id ON can_messages (step_id)",
+    "CREATE UNIQUE INDEX IF NOT EXISTS idx_can_messages_seq ON can_messages (seq)",
+    "CREATE INDEX IF NOT EXISTS idx_can_messages_timestamp_ms ON can_messages (timestamp_ms)",
+    r#"
+    CREATE TABLE IF NOT EXISTS events (
+        id TEXT PRIMARY KEY,
+        kind TEXT NOT NULL DEFAULT 'rule',
+        name TEXT NOT NULL,
+        severity TEXT NOT NULL DEFAULT 'info',
+        message TEXT NOT NULL,
+        payload TEXT NOT NULL DEFAULT 'null',
+        step_name TEXT,
+        source_ref TEXT,
+        timestamp TEXT NOT NULL,
+        timestamp_ms BIGINT,
+        tenant TEXT,
+        deleted_at TEXT,
+        rowid BIGINT GENERATED BY DEFAULT AS IDENTITY
+    )
+    "#,
+    "CREATE INDEX IF NOT EXISTS idx_events_timestamp_ms ON events (timestamp_ms)",
+    r#"
+    CREATE TABLE IF NOT EXISTS rules (
+        name TEXT PRIMARY KEY,
+        expression TEXT NOT NULL,
+        message TEXT,
+        severity TEXT NOT NULL DEFAULT 'warning',
+        created_at TEXT NOT NULL
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS geofences (
+        name TEXT PRIMARY KEY,
+        zone TEXT NOT NULL,
+        created_at TEXT NOT NULL
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS geofence_events (
+        id TEXT PRIMARY KEY,
+        geofence TEXT NOT NULL,
+        transition TEXT NOT NULL,
+        step_name TEXT NOT NULL,
+        latitude DOUBLE PRECISION NOT NULL,
+        longitude DOUBLE PRECISION NOT NULL,
+        timestamp TEXT NOT NULL
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS trips (
+        id TEXT PRIMARY KEY,
+        started_at TEXT NOT NULL,
+        ended_at TEXT,
+        duration_ms BIGINT NOT NULL,
+        distance_km DOUBLE PRECISION NOT NULL,
+        max_speed DOUBLE PRECISION NOT NULL,
+        step_count BIGINT NOT NULL,
+        alerts_count BIGINT NOT NULL,
+        harsh_accelerations BIGINT NOT NULL DEFAULT 0,
+        harsh_brakings BIGINT NOT NULL DEFAULT 0,
+        harsh_cornerings BIGINT NOT NULL DEFAULT 0,
+        start_tank_level BIGINT,
+        end_tank_level BIGINT,
+        range_remaining BIGINT,
+        fuel_distance_km DOUBLE PRECISION NOT NULL,
+        fuel_used_l DOUBLE PRECISION NOT NULL
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS anomalies (
+        id TEXT PRIMARY KEY,
+        signal TEXT NOT NULL,
+        value DOUBLE PRECISION NOT NULL,
+        mean DOUBLE PRECISION NOT NULL,
+        stddev DOUBLE PRECISION NOT NULL,
+        sigma DOUBLE PRECISION NOT NULL,
+        step_name TEXT NOT NULL,
+        timestamp TEXT NOT NULL
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS annotations (
+        id TEXT PRIMARY KEY,
+        step_id TEXT NOT NULL,
+        can_id BIGINT,
+        text TEXT NOT NULL,
+        tags TEXT NOT NULL,
+        author TEXT NOT NULL,
+        tenant TEXT,
+        created_at TEXT NOT NULL,
+        rowid BIGINT GENERATED BY DEFAULT AS IDENTITY
+    )
+    "#,
+    "CREATE INDEX IF NOT EXISTS idx_annotations_step_id ON annotations (step_id)",
+    r#"
+    CREATE TABLE IF NOT EXISTS tags (
+        name TEXT PRIMARY KEY,
+        created_at TEXT NOT NULL
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS step_tags (
+        tag TEXT NOT NULL,
+        step_id TEXT NOT NULL,
+        PRIMARY KEY (tag, step_id)
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS trip_tags (
+        tag TEXT NOT NULL,
+        trip_id TEXT NOT NULL,
+        PRIMARY KEY (tag, trip_id)
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS scenario_tags (
+        tag TEXT NOT NULL,
+        scenario TEXT NOT NULL,
+        PRIMARY KEY (tag, scenario)
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS system_events (
+        id TEXT PRIMARY KEY,
+        kind TEXT NOT NULL,
+        message TEXT NOT NULL,
+        details TEXT NOT NULL,
+        timestamp TEXT NOT NULL
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS step_outbox (
+        id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+        step_id TEXT NOT NULL,
+        notice TEXT NOT NULL,
+        created_at TEXT NOT NULL
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS schedules (
+        name TEXT PRIMARY KEY,
+        scenario TEXT NOT NULL,
+        cron TEXT NOT NULL,
+        created_at TEXT NOT NULL
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS scenarios (
+        name TEXT PRIMARY KEY,
+        description TEXT,
+        steps TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL,
+        deleted_at TEXT
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS scenario_runs (
+        id TEXT PRIMARY KEY,
+        scenario TEXT NOT NULL,
+        schedule TEXT,
+        started_at TEXT NOT NULL,
+        finished_at TEXT,
+        status TEXT NOT NULL,
+        steps_published BIGINT NOT NULL,
+        error TEXT,
+        faults TEXT NOT NULL DEFAULT '[]',
+        seed BIGINT,
+        epoch TEXT
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS simulated_steps (
+        step_id TEXT PRIMARY KEY,
+        run_id TEXT NOT NULL
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS webhooks (
+        name TEXT PRIMARY KEY,
+        url TEXT NOT NULL,
+        secret TEXT NOT NULL,
+        topics TEXT NOT NULL,
+        min_severity TEXT,
+        max_attempts BIGINT NOT NULL,
+        created_at TEXT NOT NULL
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS webhook_deliveries (
+        id TEXT PRIMARY KEY,
+        webhook TEXT NOT NULL,
+        topic TEXT NOT NULL,
+        payload TEXT NOT NULL,
+        status TEXT NOT NULL,
+        attempts BIGINT NOT NULL,
+        response_status BIGINT,
+        error TEXT,
+        created_at TEXT NOT NULL,
+        finished_at TEXT
+    )
+    "#,
+    "CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook ON webhook_deliveries (webhook)",
+    r#"
+    CREATE TABLE IF NOT EXISTS subscriptions (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        topics TEXT NOT NULL,
+        min_severity TEXT,
+        latency BIGINT NOT NULL,
+        mode TEXT NOT NULL DEFAULT 'steps',
+        transport TEXT,
+        max_rate DOUBLE PRECISION,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS vehicles (
+        vin TEXT PRIMARY KEY,
+        make TEXT NOT NULL,
+        model TEXT NOT NULL,
+        year BIGINT,
+        can_profile TEXT NOT NULL,
+        dbc TEXT,
+        endian TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS devices (
+        id TEXT PRIMARY KEY,
+        kind TEXT,
+        firmware TEXT,
+        version TEXT,
+        tenant TEXT,
+        online BOOLEAN NOT NULL,
+        registered_at TEXT NOT NULL,
+        last_seen_at TEXT NOT NULL,
+        heartbeats BIGINT NOT NULL
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS exports (
+        name TEXT PRIMARY KEY,
+        format TEXT NOT NULL,
+        destination TEXT NOT NULL,
+        secret TEXT,
+        cron TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        exported_until TEXT
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS export_runs (
+        id TEXT PRIMARY KEY,
+        export TEXT NOT NULL,
+        "from" TEXT NOT NULL,
+        "to" TEXT NOT NULL,
+        status TEXT NOT NULL,
+        rows BIGINT NOT NULL,
+        bytes BIGINT NOT NULL,
+        location TEXT,
+        error TEXT,
+        started_at TEXT NOT NULL,
+        finished_at TEXT NOT NULL
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS device_commands (
+        id TEXT PRIMARY KEY,
+        device_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        params TEXT NOT NULL,
+        status TEXT NOT NULL,
+        delivered_via TEXT,
+        result TEXT,
+        error TEXT,
+        created_at TEXT NOT NULL,
+        delivered_at TEXT,
+        acked_at TEXT,
+        expires_at TEXT NOT NULL
+    )
+    "#,
+    "CREATE INDEX IF NOT EXISTS idx_device_commands_device ON device_commands (device_id, status)",
+    r#"
+    CREATE TABLE IF NOT EXISTS signal_values (
+        signal TEXT NOT NULL,
+        value DOUBLE PRECISION NOT NULL,
+        step_id TEXT,
+        timestamp_ms BIGINT NOT NULL
+    )
+    "#,
+    "CREATE INDEX IF NOT EXISTS idx_signal_values_signal ON signal_values (signal, timestamp_ms)",
+    r#"
+    CREATE TABLE IF NOT EXISTS signal_aggregates (
+        signal TEXT NOT NULL,
+        minute_ms BIGINT NOT NULL,
+        count BIGINT NOT NULL,
+        min DOUBLE PRECISION NOT NULL,
+        max DOUBLE PRECISION NOT NULL,
+        sum DOUBLE PRECISION NOT NULL,
+        PRIMARY KEY (signal, minute_ms)
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS rejected_frames (
+        id TEXT PRIMARY KEY,
+        batch_id TEXT NOT NULL,
+        can_id BIGINT NOT NULL,
+        dlc BIGINT NOT NULL,
+        data BYTEA NOT NULL,
+        fd BOOLEAN NOT NULL DEFAULT FALSE,
+        brs BOOLEAN NOT NULL DEFAULT FALSE,
+        timestamp TEXT NOT NULL,
+        endian TEXT NOT NULL DEFAULT 'little',
+        vehicle_id TEXT,
+        step_name TEXT NOT NULL DEFAULT 'Ingested',
+        rule TEXT NOT NULL,
+        reason TEXT NOT NULL,
+        rejected_at TEXT NOT NULL,
+        rowid BIGINT GENERATED BY DEFAULT AS IDENTITY
+    )
+    "#,
+    "CREATE INDEX IF NOT EXISTS idx_rejected_frames_batch_id ON rejected_frames (batch_id)",
+    r#"
+    CREATE TABLE IF NOT EXISTS tenants (
+        name TEXT PRIMARY KEY,
+        key_hash TEXT NOT NULL UNIQUE,
+        max_steps BIGINT,
+        max_messages_per_minute BIGINT,
+        max_bytes BIGINT,
+        created_at TEXT NOT NULL,
+        viewer_key_hash TEXT
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS step_signatures (
+        step_id TEXT PRIMARY KEY,
+        signer TEXT NOT NULL,
+        signature TEXT NOT NULL,
+        signed_at TEXT NOT NULL,
+        verified_at TEXT NOT NULL,
+        tenant TEXT
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS tenant_usage (
+        tenant TEXT PRIMARY KEY,
+        messages BIGINT NOT NULL DEFAULT 0,
+        bytes BIGINT NOT NULL DEFAULT 0,
+        window_start_ms BIGINT NOT NULL,
+        window_messages BIGINT NOT NULL DEFAULT 0
+    )
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS jobs (
+        id TEXT PRIMARY KEY,
+        kind TEXT NOT NULL,
+        status TEXT NOT NULL,
+        tenant TEXT,
+        progress TEXT,
+        result TEXT,
+        error TEXT,
+        created_at TEXT NOT NULL,
+        started_at TEXT,
+        finished_at TEXT
+    )
+    "#,
+    "CREATE INDEX IF NOT EXISTS idx_jobs_created_at ON jobs (created_at)",
+];
+
+/// Create every table of the crate in the PostgreSQL database of `pool`
+pub async fn apply_schema(pool: &AnyPool) -> Result<()> {
+    for statement in SCHEMA {
+        sqlx::query(statement).execute(pool).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+use serde::Serialize;
+
+/// Attempts made to publish one step notice before giving up on the broker
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Publish attempts per notice, the first included
+    pub attempts: u32,
+    /// Delay before the second attempt, doubled for every further attempt
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            attempts: 3,
+            base_delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before attempt `attempt + 1`, jittered between half and all of the backoff so
+    /// writers failing together do not retry together
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        backoff.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    /// Notices are published to the broker
+    #[default]
+    Closed,
+    /// The broker kept failing; notices go to the outbox until the cooldown is over
+    Open,
+    /// The cooldown is over and one notice is trying the broker again
+    HalfOpen,
+}
+
+/// State of the circuit breaker with what it did since the server started
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct BreakerStatus {
+    pub state: BreakerState,
+    /// Publishes that failed in a row, retries included as one
+    pub consecutive_failures: u32,
+    /// Time left before the broker is tried again while open, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_in_ms: Option<u64>,
+    /// Times the breaker opened
+    pub opened: u64,
+    /// Notices written to the outbox instead of the broker
+    pub outboxed: u64,
+    /// Outbox notices published once the broker came back
+    pub relayed: u64,
+}
+
+#[derive(Default)]
+struct BreakerInner {
+    status: BreakerStatus,
+    /// When the breaker opened, or when the probe started while half-open
+    since: Option<Instant>,
+}
+
+/// Circuit breaker in front of the step-notice broker
+///
+/// Opens after `threshold` failed publishes in a row. While open, notices skip the broker
+/// and are written to the outbox; after `cooldown` one publish probes the broker, closing
+/// the breaker when it succeeds and opening it again when it fails. A probe with no outcome
+/// after another `cooldown`, e.g. dropped with the request that made it, is given up and
+/// the next publish probes instead.
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    inner: Arc<Mutex<BreakerInner>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        CircuitBreaker {
+            threshold: threshold.max(1),
+            cooldown,
+            inner: Arc::default(),
+        }
+    }
+
+    /// Whether the next publish may try the broker; every `true` should be followed by
+    /// `record_success` or `record_failure`
+    pub fn allow(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.status.state == BreakerState::Closed {
+            return true;
+        }
+        // Open during the cooldown, or a probe still in flight
+        if inner
+            .since
+            .is_some_and(|since| since.elapsed() < self.cooldown)
+        {
+            return false;
+        }
+        inner.status.state = BreakerState::HalfOpen;
+        inner.since = Some(Instant::now());
+        true
+    }
+
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.status.state != BreakerState::Closed {
+            println!("🔌 Step-notice broker reachable again, circuit closed");
+        }
+        inner.status.state = BreakerState::Closed;
+        inner.status.consecutive_failures = 0;
+        inner.since = None;
+    }
+
+    pub fn record_failure(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.status.consecutive_failures += 1;
+        let reopen = inner.status.state == BreakerState::HalfOpen;
+        if reopen || inner.status.consecutive_failures >= self.threshold {
+            if inner.status.state != BreakerState::Open {
+                inner.status.opened += 1;
+                println!(
+                    "🔌 Step-notice broker failing ({} in a row), circuit open for {:?}",
+                    inner.status.consecutive_failures, self.cooldown
+                );
+            }
+            inner.status.state = BreakerState::Open;
+            inner.since = Some(Instant::now());
+        }
+    }
+
+    pub(crate) fn record_outboxed(&self) {
+        self.inner.lock().unwrap().status.outboxed += 1;
+    }
+
+    pub(crate) fn record_relayed(&self) {
+        self.inner.lock().unwrap().status.relayed += 1;
+    }
+
+    pub fn status(&self) -> BreakerStatus {
+        let inner = self.inner.lock().unwrap();
+        let retry_in_ms = match (inner.status.state, inner.since) {
+            (BreakerState::Open, Some(since)) => {
+                Some(self.cooldown.saturating_sub(since.elapsed()).as_millis() as u64)
+            }
+            _ => None,
+        };
+        BreakerStatus {
+            retry_in_ms,
+            ..inner.status
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Uploads to and downloads from S3-compatible object stores (AWS S3, MinIO, Ceph, ...),
+//! through the `object_store` S3 client
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use derive_more::Display;
+use object_store::aws::{AmazonS3, AmazonS3Builder};
+use object_store::path::Path;
+use object_store::{
+    Attribute, Attributes, BackoffConfig, ClientOptions, ObjectStore, PutOptions, RetryConfig,
+};
+use serde::{Deserialize, Serialize};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// Attempts after a failed request, within `REQUEST_TIMEOUT` of the first one
+const MAX_RETRIES: usize = 3;
+
+fn default_region() -> String {
+    "us-east-1".to_string()
+}
+
+/// Bucket of an S3-compatible endpoint, addressed path-style (`<endpoint>/<bucket>/<key>`)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct S3Bucket {
+    /// Base URL of the service, e.g. `https://s3.eu-west-3.amazonaws.com` or
+    /// `http://127.0.0.1:9000`
+    pub endpoint: String,
+    pub bucket: String,
+    #[serde(default = "default_region")]
+    pub region: String,
+    /// Prepended to every key, e.g. `exports/`
+    #[serde(default)]
+    pub prefix: String,
+    pub access_key_id: String,
+    /// Never serialized back, so it is not leaked by the responses showing the bucket
+    #[serde(default, skip_serializing)]
+    pub secret_access_key: String,
+}
+
+#[derive(Debug, Display)]
+pub enum S3Error {
+    #[display("Invalid S3 endpoint '{}'", _0)]
+    InvalidEndpoint(String),
+    #[display("S3 request failed: {}", _0)]
+    Request(object_store::Error),
+}
+
+impl std::error::Error for S3Error {}
+
+impl S3Bucket {
+    pub fn validate(&self) -> Result<(), String> {
+        let url = reqwest::Url::parse(&self.endpoint)
+            .map_err(|e| format!("Invalid S3 endpoint '{}': {}", self.endpoint, e))?;
+        if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+            return Err(format!(
+                "Invalid S3 endpoint '{}', expected an http or https URL",
+                self.endpoint
+            ));
+        }
+        for (field, value) in [
+            ("bucket", &self.bucket),
+            ("region", &self.region),
+            ("access_key_id", &self.access_key_id),
+            ("secret_access_key", &self.secret_access_key),
+        ] {
+            if value.trim().is_empty() {
+                return Err(format!("S3 {} must not be empty", field));
+            }
+        }
+        Ok(())
+    }
+
+    /// URL of `key` below the prefix of the bucket
+    pub fn url(&self, key: &str) -> String {
+        format!(
+            "{}/{}/{}",
+            self.endpoint.trim_end_matches('/'),
+            self.bucket,
+            uri_encode(&format!("{}{}", self.prefix, key))
+        )
+    }
+
+    /// Upload `body` as `key` below the prefix of the bucket, returning its URL
+    pub async fn put_object(
+        &self,
+        key: &str,
+        body: Vec<u8>,
+        content_type: &str,
+    ) -> Result<String, S3Error> {
+        let options = PutOptions {
+            attributes: Attributes::from_iter([(Attribute::ContentType, content_type.to_string())]),
+            ..Default::default()
+        };
+        self.client()?
+            .put_opts(&self.path(key), body.into(), options)
+            .await
+            .map_err(S3Error::Request)?;
+        Ok(self.url(key))
+    }
+
+    /// Content of `key` below the prefix of the bucket, `None` when there is no such object
+    pub async fn get_object(&self, key: &str) -> Result<Option<Vec<u8>>, S3Error> {
+        let object = match self.client()?.get(&self.path(key)).await {
+            Ok(object) => object,
+            Err(object_store::Error::NotFound { .. }) => return Ok(None),
+            Err(e) => return Err(S3Error::Request(e)),
+        };
+        let body = object.bytes().await.map_err(S3Error::Request)?;
+        Ok(Some(body.to_vec()))
+    }
+
+    /// Object path of `key` below the prefix of the bucket
+    fn path(&self, key: &str) -> Path {
+        Path::from(format!("{}{}", self.prefix, key))
+    }
+
+    /// Client of the endpoint, bucket and credentials, built on first use and shared by every
+    /// copy of the bucket and by buckets differing only by their prefix
+    fn client(&self) -> Result<Arc<AmazonS3>, S3Error> {
+        static CLIENTS: OnceLock<Mutex<HashMap<S3Bucket, Arc<AmazonS3>>>> = OnceLock::new();
+        let id = S3Bucket {
+            prefix: String::new(),
+            ..self.clone()
+        };
+        let mut clients = CLIENTS.get_or_init(Mutex::default).lock().unwrap();
+        if let Some(client) = clients.get(&id) {
+            return Ok(client.clone());
+        }
+
+        let client = AmazonS3Builder::new()
+            .with_endpoint(self.endpoint.trim_end_matches('/'))
+            .with_bucket_name(&self.bucket)
+            .with_region(&self.region)
+            .with_access_key_id(&self.access_key_id)
+            .with_secret_access_key(&self.secret_access_key)
+            .with_client_options(
+                ClientOptions::new()
+                    .with_allow_http(true)
+                    .with_timeout(REQUEST_TIMEOUT),
+            )
+            .with_retry(RetryConfig {
+                backoff: BackoffConfig::default(),
+                max_retries: MAX_RETRIES,
+                retry_timeout: REQUEST_TIMEOUT,
+            })
+            .build()
+            .map_err(|_| S3Error::InvalidEndpoint(self.endpoint.clone()))?;
+        let client = Arc::new(client);
+        clients.insert(id, client.clone());
+        Ok(client)
+    }
+}
+
+/// Percent-encode a key for its URL, keeping unreserved characters and `/`
+fn uri_encode(key: &str) -> String {
+    key.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -1,37 +1,788 @@
-use sqlx::Result;
-use sqlx::SqlitePool;
+//! SQLite schema, and the migrations of databases created by older versions
 
-pub(crate) static SQLX_POOL: tokio::sync::OnceCell<sqlx::SqlitePool> =
-    tokio::sync::OnceCell::const_new();
+use sqlx::AnyConnection;
+use sqlx::{AnyPool, Result, Row};
 
-/// Get the SQLite pool instance
-pub async fn get_pool() -> Result<&'static SqlitePool> {
-    SQLX_POOL
-        .get_or_try_init(|| async {
-            let sqlite_pool = SqlitePool::connect("sqlite:eventbus.db?mode=rwc").await?;
-
-            Ok(sqlite_pool)
-        })
-        .await
-}
-
-pub async fn init() -> Result<()> {
-    let pool = get_pool().await?;
+pub const DEFAULT_DATABASE_URL: &str = "sqlite:eventbus.db?mode=rwc";
 
+/// Create or upgrade every table of the crate in the SQLite database of `pool`
+pub async fn apply_schema(pool: &AnyPool) -> Result<()> {
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS can_messages (
             id INTEGER NOT NULL,
             dlc INTEGER NOT NULL,
-            data TEXT NOT NULL,
+            data BLOB NOT NULL,
+            fd INTEGER NOT NULL DEFAULT 0,
+            brs INTEGER NOT NULL DEFAULT 0,
             timestamp TEXT NOT NULL,
             endian TEXT NOT NULL,
-            PRIMARY KEY (id, timestamp)
+            step_id TEXT NOT NULL,
+            step_name TEXT,
+            vehicle_id TEXT,
+            seq INTEGER,
+            tenant TEXT,
+            PRIMARY KEY (step_id, id)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Databases created before frames were grouped by step get ids for their legacy rows below
+    ensure_column(pool, "can_messages", "step_id", "TEXT").await?;
+    // Frames ingested before the vehicle registry belong to no vehicle
+    ensure_column(pool, "can_messages", "vehicle_id", "TEXT").await?;
+    // Frames stored before API keys belong to no tenant, only the administrator sees them
+    ensure_column(pool, "can_messages", "tenant", "TEXT").await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_can_messages_tenant ON can_messages (tenant)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_can_messages_step_id ON can_messages (step_id)")
+        .execute(pool)
+        .await?;
+
+    // Frames stored before sequence numbers existed are numbered in insertion order
+    if ensure_column(pool, "can_messages", "seq", "INTEGER").await? {
+        sqlx::query("UPDATE can_messages SET seq = rowid")
+            .execute(pool)
+            .await?;
+    }
+    sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_can_messages_seq ON can_messages (seq)")
+        .execute(pool)
+        .await?;
+
+    // Frames stored before steps had ids are cut into steps, see `migrate_step_ids`
+    migrate_step_ids(&mut *pool.acquire().await?).await?;
+    // Steps stored before their names were kept are named after their id when read
+    ensure_column(pool, "can_messages", "step_name", "TEXT").await?;
+
+    // Steps, events and scenarios stored before the trash existed are live
+    ensure_column(pool, "can_messages", "deleted_at", "TEXT").await?;
+    // Frames stored before CAN-FD support are classic frames
+    ensure_column(pool, "can_messages", "fd", "INTEGER NOT NULL DEFAULT 0").await?;
+    ensure_column(pool, "can_messages", "brs", "INTEGER NOT NULL DEFAULT 0").await?;
+    // Range searches on capture time compare integers, see `ensure_timestamp_ms`
+    ensure_timestamp_ms(pool, "can_messages").await?;
+
+    // Frames stored before payloads were binary hold a JSON array padded to 8 bytes
+    migrate_frame_data(&mut *pool.acquire().await?).await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS events (
+            id TEXT PRIMARY KEY,
+            kind TEXT NOT NULL,
+            name TEXT NOT NULL,
+            severity TEXT NOT NULL DEFAULT 'info',
+            message TEXT NOT NULL,
+            payload TEXT NOT NULL DEFAULT 'null',
+            step_name TEXT,
+            source_ref TEXT,
+            timestamp TEXT NOT NULL,
+            tenant TEXT
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Events stored before they carried a kind were rule or harsh-driving events
+    if ensure_column(pool, "events", "kind", "TEXT NOT NULL DEFAULT 'rule'").await? {
+        sqlx::query(
+            "UPDATE events SET kind = 'harsh_manoeuvre'
+             WHERE name IN ('harsh_acceleration', 'harsh_braking', 'harsh_cornering')",
+        )
+        .execute(pool)
+        .await?;
+    }
+    ensure_column(pool, "events", "severity", "TEXT NOT NULL DEFAULT 'info'").await?;
+    ensure_column(pool, "events", "payload", "TEXT NOT NULL DEFAULT 'null'").await?;
+    ensure_column(pool, "events", "source_ref", "TEXT").await?;
+    ensure_column(pool, "events", "tenant", "TEXT").await?;
+    ensure_column(pool, "events", "deleted_at", "TEXT").await?;
+    ensure_timestamp_ms(pool, "events").await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS rules (
+            name TEXT PRIMARY KEY,
+            expression TEXT NOT NULL,
+            message TEXT,
+            severity TEXT NOT NULL DEFAULT 'warning',
+            created_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Rules created before events had severities raised alerts
+    ensure_column(pool, "rules", "severity", "TEXT NOT NULL DEFAULT 'warning'").await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS geofences (
+            name TEXT PRIMARY KEY,
+            zone TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS geofence_events (
+            id TEXT PRIMARY KEY,
+            geofence TEXT NOT NULL,
+            transition TEXT NOT NULL,
+            step_name TEXT NOT NULL,
+            latitude REAL NOT NULL,
+            longitude REAL NOT NULL,
+            timestamp TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS trips (
+            id TEXT PRIMARY KEY,
+            started_at TEXT NOT NULL,
+            ended_at TEXT,
+            duration_ms INTEGER NOT NULL,
+            distance_km REAL NOT NULL,
+            max_speed REAL NOT NULL,
+            step_count INTEGER NOT NULL,
+            alerts_count INTEGER NOT NULL,
+            harsh_accelerations INTEGER NOT NULL DEFAULT 0,
+            harsh_brakings INTEGER NOT NULL DEFAULT 0,
+            harsh_cornerings INTEGER NOT NULL DEFAULT 0,
+            start_tank_level INTEGER,
+            end_tank_level INTEGER,
+            range_remaining INTEGER,
+            fuel_distance_km REAL NOT NULL,
+            fuel_used_l REAL NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Trip tables created before driving scores existed
+    for column in ["harsh_accelerations", "harsh_brakings", "harsh_cornerings"] {
+        ensure_column(pool, "trips", column, "INTEGER NOT NULL DEFAULT 0").await?;
+    }
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS anomalies (
+            id TEXT PRIMARY KEY,
+            signal TEXT NOT NULL,
+            value REAL NOT NULL,
+            mean REAL NOT NULL,
+            stddev REAL NOT NULL,
+            sigma REAL NOT NULL,
+            step_name TEXT NOT NULL,
+            timestamp TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS annotations (
+            id TEXT PRIMARY KEY,
+            step_id TEXT NOT NULL,
+            can_id INTEGER,
+            text TEXT NOT NULL,
+            tags TEXT NOT NULL,
+            author TEXT NOT NULL,
+            tenant TEXT,
+            created_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_annotations_step_id ON annotations (step_id)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS tags (
+            name TEXT PRIMARY KEY,
+            created_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // One join table per kind of tagged record, keyed by the record's id or name
+    for (table, column) in [
+        ("step_tags", "step_id"),
+        ("trip_tags", "trip_id"),
+        ("scenario_tags", "scenario"),
+    ] {
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {table} (
+                tag TEXT NOT NULL,
+                {column} TEXT NOT NULL,
+                PRIMARY KEY (tag, {column})
+            )"
+        ))
+        .execute(pool)
+        .await?;
+    }
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS system_events (
+            id TEXT PRIMARY KEY,
+            kind TEXT NOT NULL,
+            message TEXT NOT NULL,
+            details TEXT NOT NULL,
+            timestamp TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS step_outbox (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            step_id TEXT NOT NULL,
+            notice TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schedules (
+            name TEXT PRIMARY KEY,
+            scenario TEXT NOT NULL,
+            cron TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS scenarios (
+            name TEXT PRIMARY KEY,
+            description TEXT,
+            steps TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    ensure_column(pool, "scenarios", "deleted_at", "TEXT").await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS scenario_runs (
+            id TEXT PRIMARY KEY,
+            scenario TEXT NOT NULL,
+            schedule TEXT,
+            started_at TEXT NOT NULL,
+            finished_at TEXT,
+            status TEXT NOT NULL,
+            steps_published INTEGER NOT NULL,
+            error TEXT
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    // Runs recorded before faults could be injected
+    ensure_column(
+        pool,
+        "scenario_runs",
+        "faults",
+        "TEXT NOT NULL DEFAULT '[]'",
+    )
+    .await?;
+    ensure_column(pool, "scenario_runs", "seed", "INTEGER").await?;
+    // Runs recorded before the simulated clock ran on the wall clock
+    ensure_column(pool, "scenario_runs", "epoch", "TEXT").await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS simulated_steps (
+            step_id TEXT PRIMARY KEY,
+            run_id TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS webhooks (
+            name TEXT PRIMARY KEY,
+            url TEXT NOT NULL,
+            secret TEXT NOT NULL,
+            topics TEXT NOT NULL,
+            min_severity TEXT,
+            max_attempts INTEGER NOT NULL,
+            created_at TEXT NOT NULL
         )
         "#,
     )
     .execute(pool)
     .await?;
 
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            id TEXT PRIMARY KEY,
+            webhook TEXT NOT NULL,
+            topic TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            status TEXT NOT NULL,
+            attempts INTEGER NOT NULL,
+            response_status INTEGER,
+            error TEXT,
+            created_at TEXT NOT NULL,
+            finished_at TEXT
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook ON webhook_deliveries (webhook)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS subscriptions (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            topics TEXT NOT NULL,
+            min_severity TEXT,
+            latency INTEGER NOT NULL,
+            mode TEXT NOT NULL DEFAULT 'steps',
+            transport TEXT,
+            max_rate REAL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Subscriptions created before decoded streams received steps
+    ensure_column(
+        pool,
+        "subscriptions",
+        "mode",
+        "TEXT NOT NULL DEFAULT 'steps'",
+    )
+    .await?;
+    // Subscriptions created before stream decimation
+    ensure_column(pool, "subscriptions", "max_rate", "REAL").await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS vehicles (
+            vin TEXT PRIMARY KEY,
+            make TEXT NOT NULL,
+            model TEXT NOT NULL,
+            year INTEGER,
+            can_profile TEXT NOT NULL,
+            dbc TEXT,
+            endian TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS devices (
+            id TEXT PRIMARY KEY,
+            kind TEXT,
+            firmware TEXT,
+            version TEXT,
+            tenant TEXT,
+            online INTEGER NOT NULL,
+            registered_at TEXT NOT NULL,
+            last_seen_at TEXT NOT NULL,
+            heartbeats INTEGER NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS exports (
+            name TEXT PRIMARY KEY,
+            format TEXT NOT NULL,
+            destination TEXT NOT NULL,
+            secret TEXT,
+            cron TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            exported_until TEXT
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS export_runs (
+            id TEXT PRIMARY KEY,
+            export TEXT NOT NULL,
+            "from" TEXT NOT NULL,
+            "to" TEXT NOT NULL,
+            status TEXT NOT NULL,
+            rows INTEGER NOT NULL,
+            bytes INTEGER NOT NULL,
+            location TEXT,
+            error TEXT,
+            started_at TEXT NOT NULL,
+            finished_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS device_commands (
+            id TEXT PRIMARY KEY,
+            device_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            params TEXT NOT NULL,
+            status TEXT NOT NULL,
+            delivered_via TEXT,
+            result TEXT,
+            error TEXT,
+            created_at TEXT NOT NULL,
+            delivered_at TEXT,
+            acked_at TEXT,
+            expires_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_device_commands_device ON device_commands (device_id, status)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS signal_values (
+            signal TEXT NOT NULL,
+            value REAL NOT NULL,
+            step_id TEXT,
+            timestamp_ms INTEGER NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_signal_values_signal ON signal_values (signal, timestamp_ms)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS signal_aggregates (
+            signal TEXT NOT NULL,
+            minute_ms INTEGER NOT NULL,
+            count INTEGER NOT NULL,
+            min REAL NOT NULL,
+            max REAL NOT NULL,
+            sum REAL NOT NULL,
+            PRIMARY KEY (signal, minute_ms)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS rejected_frames (
+            id TEXT PRIMARY KEY,
+            batch_id TEXT NOT NULL,
+            can_id INTEGER NOT NULL,
+            dlc INTEGER NOT NULL,
+            data BLOB NOT NULL,
+            fd INTEGER NOT NULL DEFAULT 0,
+            brs INTEGER NOT NULL DEFAULT 0,
+            timestamp TEXT NOT NULL,
+            endian TEXT NOT NULL,
+            vehicle_id TEXT,
+            step_name TEXT NOT NULL,
+            rule TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            rejected_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Frames quarantined before the review workflow are reviewed one by one
+    if ensure_column(pool, "rejected_frames", "batch_id", "TEXT").await? {
+        sqlx::query("UPDATE rejected_frames SET batch_id = id")
+            .execute(pool)
+            .await?;
+    }
+    ensure_column(
+        pool,
+        "rejected_frames",
+        "endian",
+        "TEXT NOT NULL DEFAULT 'little'",
+    )
+    .await?;
+    ensure_column(
+        pool,
+        "rejected_frames",
+        "step_name",
+        "TEXT NOT NULL DEFAULT 'Ingested'",
+    )
+    .await?;
+    ensure_column(pool, "rejected_frames", "fd", "INTEGER NOT NULL DEFAULT 0").await?;
+    ensure_column(pool, "rejected_frames", "brs", "INTEGER NOT NULL DEFAULT 0").await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_rejected_frames_batch_id ON rejected_frames (batch_id)",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS tenants (
+            name TEXT PRIMARY KEY,
+            key_hash TEXT NOT NULL UNIQUE,
+            max_steps INTEGER,
+            max_messages_per_minute INTEGER,
+            max_bytes INTEGER,
+            created_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    // Tenants created before usage quotas only limit their steps
+    ensure_column(pool, "tenants", "max_messages_per_minute", "INTEGER").await?;
+    ensure_column(pool, "tenants", "max_bytes", "INTEGER").await?;
+    ensure_column(pool, "tenants", "viewer_key_hash", "TEXT").await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS step_signatures (
+            step_id TEXT PRIMARY KEY,
+            signer TEXT NOT NULL,
+            signature TEXT NOT NULL,
+            signed_at TEXT NOT NULL,
+            verified_at TEXT NOT NULL,
+            tenant TEXT
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS tenant_usage (
+            tenant TEXT PRIMARY KEY,
+            messages INTEGER NOT NULL DEFAULT 0,
+            bytes INTEGER NOT NULL DEFAULT 0,
+            window_start_ms INTEGER NOT NULL,
+            window_messages INTEGER NOT NULL DEFAULT 0
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS jobs (
+            id TEXT PRIMARY KEY,
+            kind TEXT NOT NULL,
+            status TEXT NOT NULL,
+            tenant TEXT,
+            progress TEXT,
+            result TEXT,
+            error TEXT,
+            created_at TEXT NOT NULL,
+            started_at TEXT,
+            finished_at TEXT
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_jobs_created_at ON jobs (created_at)")
+        .execute(pool)
+        .await?;
+
     Ok(())
 }
+
+/// Rewrite frames stored as a JSON array padded to 8 bytes into a blob of their `dlc` bytes
+///
+/// Returns the number of rows converted. Takes a connection so snapshot restores can run it
+/// inside their transaction.
+pub async fn migrate_frame_data(conn: &mut AnyConnection) -> Result<u64> {
+    let rows = sqlx::query("SELECT rowid, dlc, data FROM can_messages WHERE typeof(data) = 'text'")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    for row in &rows {
+        let rowid: i64 = row.try_get("rowid")?;
+        let dlc: i64 = row.try_get("dlc")?;
+        let data: String = row.try_get("data")?;
+        let padded: Vec<u8> =
+            serde_json::from_str(&data).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        let len = (dlc.max(0) as usize).min(padded.len());
+
+        sqlx::query("UPDATE can_messages SET data = $1 WHERE rowid = $2")
+            .bind(&padded[..len])
+            .bind(rowid)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(rows.len() as u64)
+}
+
+/// Give a fresh step id to frames stored before steps had one
+///
+/// Frames are taken in sequence order and a step ends where the timestamp changes or a CAN
+/// ID repeats, so two steps stored within the same timestamp are told apart. Returns the
+/// number of steps created. Takes a connection so snapshot restores can run it inside their
+/// transaction.
+pub async fn migrate_step_ids(conn: &mut AnyConnection) -> Result<u64> {
+    let rows = sqlx::query(
+        "SELECT rowid, id, timestamp FROM can_messages WHERE step_id IS NULL ORDER BY seq, rowid",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut steps = 0;
+    let mut timestamp = String::new();
+    let mut ids: Vec<i64> = Vec::new();
+    let mut step_id = String::new();
+    for row in &rows {
+        let rowid: i64 = row.try_get("rowid")?;
+        let id: i64 = row.try_get("id")?;
+        let frame_timestamp: String = row.try_get("timestamp")?;
+        if steps == 0 || frame_timestamp != timestamp || ids.contains(&id) {
+            step_id = uuid::Uuid::new_v4().to_string();
+            timestamp = frame_timestamp;
+            ids.clear();
+            steps += 1;
+        }
+        ids.push(id);
+
+        sqlx::query("UPDATE can_messages SET step_id = $1 WHERE rowid = $2")
+            .bind(&step_id)
+            .bind(rowid)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(steps)
+}
+
+/// Add the indexed `timestamp_ms` column to `table`, milliseconds since the Unix epoch of
+/// its RFC3339 `timestamp`
+///
+/// The strings only sort correctly within one offset and precision, so range searches and
+/// ordering use this column. Rows stored before it existed are filled in from `timestamp`;
+/// those SQLite cannot read stay `NULL` and match no range.
+async fn ensure_timestamp_ms(pool: &AnyPool, table: &str) -> Result<()> {
+    if ensure_column(pool, table, "timestamp_ms", "INTEGER").await? {
+        sqlx::query(&format!(
+            "UPDATE {} SET timestamp_ms =
+             CAST(ROUND((julianday(timestamp) - 2440587.5) * 86400000.0) AS INTEGER)",
+            table
+        ))
+        .execute(pool)
+        .await?;
+    }
+    sqlx::query(&format!(
+        "CREATE INDEX IF NOT EXISTS idx_{0}_timestamp_ms ON {0} (timestamp_ms)",
+        table
+    ))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Add `column` to `table` when an existing database predates it, returning whether it was added
+async fn ensure_column(
+    pool: &AnyPool,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<bool> {
+    let columns = sqlx::query(&format!("PRAGMA table_info({})", table))
+        .fetch_all(pool)
+        .await?;
+    let exists = columns.iter().any(|row| {
+        row.try_get::<String, _>("name")
+            .is_ok_and(|name| name == column)
+    });
+
+    if !exists {
+        sqlx::query(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(!exists)
+}
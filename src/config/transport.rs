@@ -0,0 +1,457 @@
+use crate::config::amqp_link::AmqpLink;
+use crate::config::memory_queue::MemoryQueue;
+use crate::config::outbox;
+use crate::config::rabbitmq::{self, StepNotice};
+use crate::config::resilience::{CircuitBreaker, RetryPolicy};
+use crate::core::bus::Bus;
+use crate::core::metrics;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use derive_more::Display;
+use lapin::Channel;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+/// Time between two attempts to relay the outbox to the broker
+const RELAY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Which queue carries step notices between writers and the reconstruction consumer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportKind {
+    /// RabbitMQ at `RabbitMqSettings::url`
+    #[default]
+    Amqp,
+    /// In-process queue, for tests and offline demos without a broker
+    Memory,
+}
+
+impl TransportKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TransportKind::Amqp => "amqp",
+            TransportKind::Memory => "memory",
+        }
+    }
+}
+
+#[derive(Debug, Display)]
+pub enum TransportError {
+    #[display("AMQP error: {}", _0)]
+    Amqp(lapin::Error),
+    #[display("AMQP connection lost, reconnecting")]
+    Reconnecting,
+    #[display("In-memory queue error: {}", _0)]
+    Memory(String),
+    #[display("Outbox error: {}", _0)]
+    Outbox(sqlx::Error),
+}
+
+impl std::error::Error for TransportError {}
+
+impl From<lapin::Error> for TransportError {
+    fn from(error: lapin::Error) -> Self {
+        TransportError::Amqp(error)
+    }
+}
+
+/// Pause switch shared by the step-notice consumer and the admin endpoints
+///
+/// While paused the consumer holds the delivery it received and stops taking new ones;
+/// notices stay queued (RabbitMQ stops pushing once the channel prefetch is exhausted)
+/// and are processed in order after resuming. The broker connection stays open.
+#[derive(Clone)]
+pub struct ConsumerControl {
+    paused: Arc<watch::Sender<bool>>,
+}
+
+impl Default for ConsumerControl {
+    fn default() -> Self {
+        ConsumerControl {
+            paused: Arc::new(watch::channel(false).0),
+        }
+    }
+}
+
+impl ConsumerControl {
+    pub fn pause(&self) {
+        self.paused.send_replace(true);
+    }
+
+    pub fn resume(&self) {
+        self.paused.send_replace(false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Wait until the consumer is allowed to process deliveries
+    pub async fn wait_until_running(&self) {
+        let mut paused = self.paused.subscribe();
+        // The sender lives in `self`, so the channel cannot close while waiting
+        let _ = paused.wait_for(|paused| !*paused).await;
+    }
+}
+
+/// Transport faults applied to published step notices, for chaos testing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChaosSettings {
+    /// Share of notices silently lost, in percent
+    #[serde(default)]
+    pub drop_percent: f64,
+    /// Share of notices published twice, in percent
+    #[serde(default)]
+    pub duplicate_percent: f64,
+    /// Delay before each notice reaches the queue, in milliseconds
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+impl ChaosSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, percent) in [
+            ("drop_percent", self.drop_percent),
+            ("duplicate_percent", self.duplicate_percent),
+        ] {
+            if !(0.0..=100.0).contains(&percent) {
+                return Err(format!(
+                    "{} must be between 0 and 100, got {}",
+                    name, percent
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Chaos settings with the number of notices they affected since they were set
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct ChaosStatus {
+    #[serde(flatten)]
+    pub settings: ChaosSettings,
+    pub published: u64,
+    pub dropped: u64,
+    pub duplicated: u64,
+    pub delayed: u64,
+}
+
+/// Fault switch shared by the step-notice publisher and the admin endpoints
+///
+/// Everything is off by default; notices then go straight to the queue.
+#[derive(Clone, Default)]
+pub struct ChaosControl {
+    status: Arc<Mutex<ChaosStatus>>,
+}
+
+impl ChaosControl {
+    /// Apply new settings and reset the counters
+    pub fn set(&self, settings: ChaosSettings) -> Result<ChaosStatus, String> {
+        settings.validate()?;
+        let status = ChaosStatus {
+            settings,
+            ..ChaosStatus::default()
+        };
+        *self.status.lock().unwrap() = status;
+        Ok(status)
+    }
+
+    /// Turn every fault off, returning the settings and counters they had
+    pub fn clear(&self) -> ChaosStatus {
+        std::mem::take(&mut *self.status.lock().unwrap())
+    }
+
+    pub fn status(&self) -> ChaosStatus {
+        *self.status.lock().unwrap()
+    }
+
+    /// Number of copies of the next notice to publish (0 when dropped) and their delay
+    fn plan(&self) -> (u32, Duration) {
+        let mut status = self.status.lock().unwrap();
+        let settings = status.settings;
+        let mut rng = rand::thread_rng();
+        status.published += 1;
+
+        if rng.gen_bool(settings.drop_percent / 100.0) {
+            status.dropped += 1;
+            return (0, Duration::ZERO);
+        }
+        let copies = if rng.gen_bool(settings.duplicate_percent / 100.0) {
+            status.duplicated += 1;
+            2
+        } else {
+            1
+        };
+        if settings.delay_ms > 0 {
+            status.delayed += 1;
+        }
+        (copies, Duration::from_millis(settings.delay_ms))
+    }
+}
+
+/// Publish/consume surface for step notices, backed by RabbitMQ or an in-memory queue
+#[derive(Clone)]
+pub enum StepTransport {
+    /// RabbitMQ channel and the queue the notices travel through
+    Amqp(Channel, Arc<str>),
+    /// RabbitMQ through a connection that is opened again whenever it fails
+    Supervised(AmqpLink),
+    Memory(MemoryQueue),
+    /// Another transport whose published notices go through a `ChaosControl`
+    Chaos(Box<StepTransport>, ChaosControl),
+    /// Another transport whose publishes are retried, falling back to the `step_outbox`
+    /// table while the broker is unavailable
+    Resilient(Box<StepTransport>, RetryPolicy, CircuitBreaker),
+}
+
+impl StepTransport {
+    /// Route the notices published through this transport past `chaos`
+    pub fn with_chaos(self, chaos: ChaosControl) -> Self {
+        StepTransport::Chaos(Box::new(self), chaos)
+    }
+
+    /// Retry the notices published through this transport as `policy` says, keeping them in
+    /// the outbox when they still fail or while `breaker` is open
+    pub fn with_resilience(self, policy: RetryPolicy, breaker: CircuitBreaker) -> Self {
+        StepTransport::Resilient(Box::new(self), policy, breaker)
+    }
+
+    /// Queue the notices travel through, behind any `ChaosControl`
+    pub fn kind(&self) -> TransportKind {
+        match self {
+            StepTransport::Amqp(..) | StepTransport::Supervised(_) => TransportKind::Amqp,
+            StepTransport::Memory(_) => TransportKind::Memory,
+            StepTransport::Chaos(inner, _) | StepTransport::Resilient(inner, ..) => inner.kind(),
+        }
+    }
+
+    /// Whether notices can currently be published and consumed
+    pub fn is_connected(&self) -> bool {
+        match self {
+            StepTransport::Amqp(channel, _) => channel.status().connected(),
+            StepTransport::Supervised(link) => link.channel().is_some(),
+            StepTransport::Memory(_) => true,
+            StepTransport::Chaos(inner, _) | StepTransport::Resilient(inner, ..) => {
+                inner.is_connected()
+            }
+        }
+    }
+
+    /// Channel of the broker behind this transport, `None` for the in-memory queue and
+    /// while reconnecting
+    pub fn amqp_channel(&self) -> Option<Channel> {
+        match self {
+            StepTransport::Amqp(channel, _) => {
+                Some(channel.clone()).filter(|channel| channel.status().connected())
+            }
+            StepTransport::Supervised(link) => link.channel(),
+            StepTransport::Memory(_) => None,
+            StepTransport::Chaos(inner, _) | StepTransport::Resilient(inner, ..) => {
+                inner.amqp_channel()
+            }
+        }
+    }
+
+    /// Times the broker connection was opened again after failing
+    pub fn reconnects(&self) -> u64 {
+        match self {
+            StepTransport::Supervised(link) => link.reconnects(),
+            StepTransport::Amqp(..) | StepTransport::Memory(_) => 0,
+            StepTransport::Chaos(inner, _) | StepTransport::Resilient(inner, ..) => {
+                inner.reconnects()
+            }
+        }
+    }
+
+    /// Publish a step notice for the reconstruction consumer
+    ///
+    /// Behind a `ChaosControl`, the notice may be dropped, published twice, or published
+    /// in the background after a delay; errors of delayed notices are only logged. Behind a
+    /// `CircuitBreaker`, a notice the broker does not take is kept in the outbox and the
+    /// publish succeeds.
+    pub async fn publish(&self, notice: &StepNotice) -> Result<(), TransportError> {
+        let (inner, chaos) = match self {
+            StepTransport::Chaos(inner, chaos) => (inner, chaos),
+            StepTransport::Resilient(inner, policy, breaker) => {
+                return Box::pin(inner.publish_resilient(policy, breaker, notice)).await;
+            }
+            _ => return self.deliver(notice).await,
+        };
+
+        let (copies, delay) = chaos.plan();
+        if delay.is_zero() {
+            for _ in 0..copies {
+                inner.deliver(notice).await?;
+            }
+            return Ok(());
+        }
+
+        let inner = inner.clone();
+        let notice = notice.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            for _ in 0..copies {
+                if let Err(e) = inner.deliver(&notice).await {
+                    println!(
+                        "❌ Failed to publish delayed step notice {}: {}",
+                        notice.step_id, e
+                    );
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Publish through `breaker`, falling back to the outbox
+    async fn publish_resilient(
+        &self,
+        policy: &RetryPolicy,
+        breaker: &CircuitBreaker,
+        notice: &StepNotice,
+    ) -> Result<(), TransportError> {
+        if breaker.allow() {
+            match self.publish_with_retries(policy, notice).await {
+                Ok(()) => {
+                    breaker.record_success();
+                    return Ok(());
+                }
+                Err(e) => {
+                    breaker.record_failure();
+                    println!(
+                        "⚠️ Step notice {} not published after {} attempts, kept in the outbox: {}",
+                        notice.step_id, policy.attempts, e
+                    );
+                }
+            }
+        }
+        outbox::store(notice)
+            .await
+            .map_err(TransportError::Outbox)?;
+        breaker.record_outboxed();
+        Ok(())
+    }
+
+    async fn publish_with_retries(
+        &self,
+        policy: &RetryPolicy,
+        notice: &StepNotice,
+    ) -> Result<(), TransportError> {
+        let mut attempt = 1;
+        loop {
+            match self.publish(notice).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt >= policy.attempts => return Err(e),
+                Err(_) => {
+                    tokio::time::sleep(policy.delay(attempt)).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// Publish the notices waiting in the outbox, oldest first, whenever the circuit
+    /// breaker lets them through; does nothing for transports without one
+    pub fn relay_outbox(&self) {
+        let StepTransport::Resilient(inner, _, breaker) = self else {
+            return;
+        };
+        let inner = inner.clone();
+        let breaker = breaker.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(RELAY_INTERVAL).await;
+                if let Err(e) = relay(&inner, &breaker).await {
+                    println!("❌ Outbox: Relaying step notices failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Publish a step notice as is
+    async fn deliver(&self, notice: &StepNotice) -> Result<(), TransportError> {
+        match self {
+            StepTransport::Amqp(channel, queue) => {
+                Ok(rabbitmq::publish_step_notice(channel, queue, notice).await?)
+            }
+            StepTransport::Supervised(link) => {
+                let channel = link.channel().ok_or(TransportError::Reconnecting)?;
+                Ok(rabbitmq::publish_step_notice(&channel, link.queue(), notice).await?)
+            }
+            StepTransport::Memory(queue) => {
+                let payload = serde_json::to_vec(notice).unwrap_or_default();
+                queue.publish(payload).map_err(TransportError::Memory)
+            }
+            StepTransport::Chaos(inner, _) | StepTransport::Resilient(inner, ..) => {
+                Box::pin(inner.deliver(notice)).await
+            }
+        }
+    }
+
+    /// Start the consumer reconstructing notified steps and broadcasting them on `tx`
+    pub async fn consume(&self, tx: &Bus, control: &ConsumerControl) -> Result<(), TransportError> {
+        match self {
+            StepTransport::Amqp(channel, queue) => {
+                Ok(rabbitmq::consume_step_names(channel, queue, tx, control).await?)
+            }
+            StepTransport::Supervised(link) => {
+                link.consume(tx, control);
+                Ok(())
+            }
+            StepTransport::Memory(queue) => {
+                let sampled = queue.clone();
+                tokio::spawn(async move {
+                    loop {
+                        metrics::consumer().set_backlog(sampled.len() as u32, 1);
+                        tokio::time::sleep(rabbitmq::BACKLOG_INTERVAL).await;
+                    }
+                });
+
+                let queue = queue.clone();
+                let tx_clone = tx.clone();
+                let control = control.clone();
+                tokio::spawn(async move {
+                    while let Some(delivery) = queue.next_delivery().await {
+                        metrics::consumer().received();
+                        // Hold the delivery unprocessed while paused, keeping queue order
+                        control.wait_until_running().await;
+                        let started_us = metrics::now_us();
+                        match serde_json::from_slice::<StepNotice>(&delivery.data) {
+                            Ok(notice) => rabbitmq::handle_step_notice(notice, &tx_clone).await,
+                            Err(e) => {
+                                println!("❌ Memory Stream: Skipping malformed step notice: {}", e)
+                            }
+                        }
+                        delivery.ack();
+                        metrics::consumer().acked(started_us);
+                    }
+                });
+                Ok(())
+            }
+            StepTransport::Chaos(inner, _) | StepTransport::Resilient(inner, ..) => {
+                Box::pin(inner.consume(tx, control)).await
+            }
+        }
+    }
+}
+
+/// One pass of the outbox relay, stopping at the first notice the broker refuses
+async fn relay(inner: &StepTransport, breaker: &CircuitBreaker) -> Result<(), TransportError> {
+    let notices = outbox::pending(outbox::RELAY_BATCH)
+        .await
+        .map_err(TransportError::Outbox)?;
+    for (id, notice) in notices {
+        if !breaker.allow() {
+            break;
+        }
+        if let Err(e) = inner.publish(&notice).await {
+            breaker.record_failure();
+            return Err(e);
+        }
+        breaker.record_success();
+        breaker.record_relayed();
+        outbox::remove(id).await.map_err(TransportError::Outbox)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::common::error::AppError;
+use crate::core::can::{CanId, CanIdRange};
+use crate::core::scrub::ScrubRules;
+use crate::core::timestamps::TimestampFormat;
+use crate::core::{metrics, signals};
+use crate::features::anomaly::AnomalyDetected;
+use crate::features::driving_step::DrivingStep;
+use crate::features::event::model::Severity;
+use crate::features::event::Event;
+use crate::features::geofence::GeofenceEvent;
+use crate::features::system::SystemEvent;
+
+/// Everything carried by the in-process broadcast bus
+///
+/// Messages are tagged with their topic in a `type` field; the remaining fields are those
+/// of the wrapped value, so a `driving_step` message still parses as a plain DrivingStep.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BusMessage {
+    /// A step reconstructed from its stored CAN frames
+    DrivingStep(DrivingStep),
+    /// An event derived from the telemetry, e.g. by a rule
+    Event(Event),
+    /// A vehicle entering or leaving a geofence
+    Geofence(GeofenceEvent),
+    /// A signal value deviating from its recent statistics
+    Anomaly(AnomalyDetected),
+    /// A lifecycle change of the server, e.g. its start or a retention run
+    System(SystemEvent),
+}
+
+/// Sending half of the bus, shared as `Data<Bus>` with the HTTP handlers
+pub type Bus = broadcast::Sender<BusMessage>;
+
+impl BusMessage {
+    /// Every topic name, in declaration order
+    pub const TOPICS: [&'static str; 5] =
+        ["driving_step", "event", "geofence", "anomaly", "system"];
+
+    /// Topic name of the message, as written in its `type` field
+    pub fn topic(&self) -> &'static str {
+        match self {
+            BusMessage::DrivingStep(_) => "driving_step",
+            BusMessage::Event(_) => "event",
+            BusMessage::Geofence(_) => "geofence",
+            BusMessage::Anomaly(_) => "anomaly",
+            BusMessage::System(_) => "system",
+        }
+    }
+}
+
+/// Shape of the driving steps sent to a stream or websocket subscriber
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamMode {
+    /// One `driving_step` message per step
+    #[default]
+    Steps,
+    /// One `frame` message per stored CAN frame, with its decoded signals
+    Decoded,
+}
+
+impl StreamMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StreamMode::Steps => "steps",
+            StreamMode::Decoded => "decoded",
+        }
+    }
+}
+
+impl std::str::FromStr for StreamMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "steps" => Ok(StreamMode::Steps),
+            "decoded" => Ok(StreamMode::Decoded),
+            other => Err(format!("Unknown stream mode '{}'", other)),
+        }
+    }
+}
+
+/// Query parameters narrowing what a stream or websocket subscriber receives
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SubscriptionFilter {
+    /// Drop events below this severity; other topics are not affected
+    pub min_severity: Option<Severity>,
+    /// Attach the `X-Pipeline-Latency` debug field to driving steps
+    #[serde(default)]
+    pub latency: bool,
+    /// `?mode=decoded` replaces driving steps with their frames and decoded signals
+    #[serde(default)]
+    pub mode: StreamMode,
+    /// Only deliver these topics, every topic when empty (set by stored subscriptions and
+    /// websocket `subscribe` messages)
+    #[serde(skip)]
+    pub topics: Vec<String>,
+    /// Only deliver the steps carrying a frame in these ranges, and in decoded mode only
+    /// those frames; every CAN ID when empty (set by websocket `subscribe` messages)
+    #[serde(skip)]
+    pub can_ids: Vec<CanIdRange>,
+    /// Id of a stored subscription replacing the other parameters
+    pub subscription: Option<String>,
+    /// Only deliver the data of this tenant (set from the API key of the connection)
+    #[serde(skip)]
+    pub tenant: Option<String>,
+    /// Scrub the messages before they are sent (set for the viewer key of the connection)
+    #[serde(skip)]
+    pub scrub: Option<ScrubRules>,
+    /// `?max_rate=5` delivers at most 5 messages per second of each topic, dropping
+    /// intermediate ones but always delivering the latest
+    pub max_rate: Option<f64>,
+    /// Format of the timestamps in the messages (set from `?timestamps=` or the Accept
+    /// header of the connection)
+    #[serde(skip)]
+    pub timestamps: TimestampFormat,
+}
+
+/// Lowest `max_rate` accepted, one message every 1000 seconds
+pub const MIN_MAX_RATE: f64 = 0.001;
+
+/// Refuse a `max_rate` that is not a number of messages per second of at least
+/// `MIN_MAX_RATE`
+pub fn validate_max_rate(max_rate: Option<f64>) -> Result<(), AppError> {
+    match max_rate {
+        Some(rate) if !(rate.is_finite() && rate >= MIN_MAX_RATE) => {
+            Err(AppError::bad_request(format!(
+                "max_rate must be at least {} messages per second, got {}",
+                MIN_MAX_RATE, rate
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+impl SubscriptionFilter {
+    pub fn accepts(&self, message: &BusMessage) -> bool {
+        if !self.topics.is_empty() && !self.topics.iter().any(|topic| topic == message.topic()) {
+            return false;
+        }
+        if let BusMessage::DrivingStep(step) = message {
+            if !self.can_ids.is_empty() && !step.frames.iter().any(|frame| self.carries(frame.id)) {
+                return false;
+            }
+        }
+        // Geofence and anomaly messages come from the telemetry of every tenant, system
+        // messages from the server shared by all of them
+        if let Some(tenant) = &self.tenant {
+            let owner = match message {
+                BusMessage::DrivingStep(step) => step.tenant.as_ref(),
+                BusMessage::Event(event) => event.tenant.as_ref(),
+                BusMessage::Geofence(_) | BusMessage::Anomaly(_) | BusMessage::System(_) => None,
+            };
+            if owner != Some(tenant) {
+                return false;
+            }
+        }
+        match (message, self.min_severity) {
+            (BusMessage::Event(event), Some(min_severity)) => event.severity >= min_severity,
+            _ => true,
+        }
+    }
+
+    /// Whether frames of `id` pass the `can_ids` ranges
+    fn carries(&self, id: impl Into<CanId>) -> bool {
+        let id = id.into();
+        self.can_ids.iter().any(|range| range.contains(id))
+    }
+
+    /// JSON texts sent to the subscriber for `message`, several for a step in decoded mode,
+    /// with timestamps in the subscriber's format
+    pub fn payloads(&self, message: &BusMessage) -> serde_json::Result<Vec<String>> {
+        let scrubbed;
+        let message = match &self.scrub {
+            Some(rules) => {
+                scrubbed = rules.apply(message);
+                &scrubbed
+            }
+            None => message,
+        };
+        let payloads = match (message, self.mode) {
+            (BusMessage::DrivingStep(step), StreamMode::Decoded) => signals::decode_frames(step)?
+                .iter()
+                .filter(|frame| {
+                    frame
+                        .id
+                        .parse::<CanId>()
+                        .is_ok_and(|id| self.can_ids.is_empty() || self.carries(id))
+                })
+                .map(serde_json::to_string)
+                .collect::<serde_json::Result<Vec<_>>>()?,
+            _ => vec![metrics::client_json(message, self.latency)?],
+        };
+        Ok(payloads
+            .into_iter()
+            .map(|payload| self.timestamps.rewrite(payload))
+            .collect())
+    }
+}
+
+impl From<DrivingStep> for BusMessage {
+    fn from(step: DrivingStep) -> Self {
+        BusMessage::DrivingStep(step)
+    }
+}
+
+impl From<Event> for BusMessage {
+    fn from(event: Event) -> Self {
+        BusMessage::Event(event)
+    }
+}
+
+impl From<GeofenceEvent> for BusMessage {
+    fn from(event: GeofenceEvent) -> Self {
+        BusMessage::Geofence(event)
+    }
+}
+
+impl From<AnomalyDetected> for BusMessage {
+    fn from(anomaly: AnomalyDetected) -> Self {
+        BusMessage::Anomaly(anomaly)
+    }
+}
+
+impl From<SystemEvent> for BusMessage {
+    fn from(event: SystemEvent) -> Self {
+        BusMessage::System(event)
+    }
+}
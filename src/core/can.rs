@@ -1,15 +1,494 @@
+use derive_more::Display;
 use serde::{Deserialize, Serialize};
 
+/// Highest identifier allowed for a standard (11-bit) CAN frame
+pub const MAX_STANDARD_ID: u16 = 0x7FF;
+/// Highest identifier allowed for an extended (29-bit) CAN frame
+pub const MAX_EXTENDED_ID: u32 = 0x1FFF_FFFF;
+/// Maximum payload length of a classic CAN frame
+pub const MAX_DLC: u8 = 8;
+/// Maximum payload length of a CAN-FD frame
+pub const MAX_FD_DLC: u8 = 64;
+/// Payload lengths a CAN-FD frame can carry, the 16 values of its 4-bit DLC field
+pub const FD_LENGTHS: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 12, 16, 20, 24, 32, 48, 64];
+
+/// Validation errors raised when building CAN data from untrusted input
+#[derive(Debug, Display, Clone, PartialEq)]
+pub enum CanError {
+    #[display("CAN ID 0x{:X} is outside the 11-bit range (max 0x7FF)", _0)]
+    InvalidId(u32),
+    #[display("CAN ID {} is outside the 29-bit range (max 0x1FFFFFFF)", _0)]
+    InvalidExtendedId(i64),
+    #[display("Malformed CAN ID '{}' (expected 416 or 0x1A0)", _0)]
+    MalformedId(String),
+    #[display("DLC {} exceeds the maximum of 8 bytes", _0)]
+    InvalidDlc(u8),
+    #[display(
+        "DLC {} is not a CAN-FD payload length (0 to 8, 12, 16, 20, 24, 32, 48 or 64 bytes)",
+        _0
+    )]
+    InvalidFdDlc(u8),
+    #[display("Invalid frame data: {}", _0)]
+    InvalidData(String),
+    #[display("{} = {} is out of range ({})", field, value, expected)]
+    OutOfRange {
+        field: &'static str,
+        value: String,
+        expected: &'static str,
+    },
+}
+
+impl std::error::Error for CanError {}
+
+/// Byte order used for multi-byte signals inside a frame payload
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Endianness {
+    #[display("little")]
+    Little,
+    #[display("big")]
+    Big,
+}
+
+impl Endianness {
+    pub fn from_is_big_endian(is_big_endian: bool) -> Self {
+        if is_big_endian {
+            Endianness::Big
+        } else {
+            Endianness::Little
+        }
+    }
+
+    pub fn is_big_endian(self) -> bool {
+        self == Endianness::Big
+    }
+
+    /// Value stored in the `endian` column
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Endianness::Little => "little",
+            Endianness::Big => "big",
+        }
+    }
+}
+
+impl std::str::FromStr for Endianness {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "little" => Ok(Endianness::Little),
+            "big" | "network" => Ok(Endianness::Big),
+            other => Err(format!("Unknown endianness '{}'", other)),
+        }
+    }
+}
+
+/// CAN identifier validated against the 29-bit extended range
+///
+/// Parses from a number (`416`) or a hex string (`"0x1A0"`), in JSON bodies, query strings
+/// and path segments alike. Frames only carry standard identifiers, see `CanId::standard`.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[display("0x{:03X}", _0)]
+#[serde(into = "u32")]
+pub struct CanId(u32);
+
+impl CanId {
+    pub fn new(raw: u32) -> Result<Self, CanError> {
+        if raw > MAX_EXTENDED_ID {
+            return Err(CanError::InvalidExtendedId(raw as i64));
+        }
+        Ok(CanId(raw))
+    }
+
+    pub fn raw(self) -> u32 {
+        self.0
+    }
+
+    /// Whether the identifier needs the 29-bit extended format
+    pub fn is_extended(self) -> bool {
+        self.0 > MAX_STANDARD_ID as u32
+    }
+
+    /// The identifier as carried by a standard (11-bit) frame
+    pub fn standard(self) -> Result<u16, CanError> {
+        if self.is_extended() {
+            return Err(CanError::InvalidId(self.0));
+        }
+        Ok(self.0 as u16)
+    }
+}
+
+impl From<u16> for CanId {
+    fn from(id: u16) -> Self {
+        CanId(id as u32)
+    }
+}
+
+impl From<CanId> for u32 {
+    fn from(id: CanId) -> Self {
+        id.0
+    }
+}
+
+impl TryFrom<i64> for CanId {
+    type Error = CanError;
+
+    fn try_from(raw: i64) -> Result<Self, Self::Error> {
+        u32::try_from(raw)
+            .map_err(|_| CanError::InvalidExtendedId(raw))
+            .and_then(CanId::new)
+    }
+}
+
+impl std::str::FromStr for CanId {
+    type Err = CanError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let parsed = match trimmed
+            .strip_prefix("0x")
+            .or_else(|| trimmed.strip_prefix("0X"))
+        {
+            // `from_str_radix` takes a leading sign, which no identifier is written with
+            Some(hex) if !hex.is_empty() && hex.bytes().all(|b| b.is_ascii_hexdigit()) => {
+                i64::from_str_radix(hex, 16).ok()
+            }
+            Some(_) => None,
+            None => trimmed.parse::<i64>().ok(),
+        };
+        parsed
+            .ok_or_else(|| CanError::MalformedId(s.to_string()))
+            .and_then(CanId::try_from)
+    }
+}
+
+/// Extracts the `{can_id}` segment of the matched route
+///
+/// Actix path deserialization cannot tell a number from a string, so path parameters go
+/// through this extractor instead of `web::Path`.
+impl actix_web::FromRequest for CanId {
+    type Error = crate::common::error::AppError;
+    type Future = std::future::Ready<Result<Self, Self::Error>>;
+
+    fn from_request(
+        req: &actix_web::HttpRequest,
+        _payload: &mut actix_web::dev::Payload,
+    ) -> Self::Future {
+        std::future::ready(match req.match_info().get("can_id") {
+            Some(segment) => segment.parse().map_err(Into::into),
+            None => Err(crate::common::error::AppError::internal_server_error(
+                "Route has no {can_id} segment",
+            )),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for CanId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct CanIdVisitor;
+
+        impl serde::de::Visitor<'_> for CanIdVisitor {
+            type Value = CanId;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a CAN ID as a number or a hex string such as \"0x1A0\"")
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<CanId, E> {
+                CanId::try_from(value).map_err(E::custom)
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<CanId, E> {
+                CanId::try_from(i64::try_from(value).unwrap_or(i64::MAX)).map_err(E::custom)
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<CanId, E> {
+                value.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(CanIdVisitor)
+    }
+}
+
+/// Inclusive range of CAN IDs, written `0x100-0x1FF`, or a single ID (`0x600` or `1536`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CanIdRange {
+    pub start: CanId,
+    pub end: CanId,
+}
+
+impl CanIdRange {
+    pub fn contains(&self, id: impl Into<CanId>) -> bool {
+        (self.start..=self.end).contains(&id.into())
+    }
+}
+
+impl std::fmt::Display for CanIdRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+impl std::str::FromStr for CanIdRange {
+    type Err = CanError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = match s.split_once('-') {
+            Some((start, end)) => (start.parse::<CanId>()?, end.parse::<CanId>()?),
+            None => {
+                let id = s.parse::<CanId>()?;
+                (id, id)
+            }
+        };
+        if start > end {
+            return Err(CanError::MalformedId(s.to_string()));
+        }
+        Ok(CanIdRange { start, end })
+    }
+}
+
+impl Serialize for CanIdRange {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Accepts the same forms as `CanId`, and strings holding a range
+impl<'de> Deserialize<'de> for CanIdRange {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Id(u64),
+            Text(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Id(raw) => {
+                let id = CanId::try_from(i64::try_from(raw).unwrap_or(i64::MAX))
+                    .map_err(serde::de::Error::custom)?;
+                Ok(CanIdRange { start: id, end: id })
+            }
+            Raw::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
 /// Unified CAN message structure for all uses
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// The payload holds exactly `dlc` bytes, up to 8 for a classic frame and up to 64 for a
+/// CAN-FD frame, whose lengths past 8 are limited to `FD_LENGTHS`. In JSON the payload is
+/// a hex string of exactly `dlc` bytes (`"data": "b0041e0001"`), and CAN-FD frames carry
+/// `"fd": true` and, when their data phase is sent at the faster rate, `"brs": true`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "RawCanMessage", try_from = "RawCanMessage")]
 pub struct CanMessage {
-    pub id: u16,           // CAN ID on 11 bits (0..=0x7FF)
-    pub dlc: u8,           // Data Length Code - number of used bytes (0..=8)
-    pub data: [u8; 8],     // CAN data payload (max 8 bytes)
+    pub id: u16,   // CAN ID on 11 bits (0..=0x7FF)
+    pub dlc: u8,   // Data Length Code - number of used bytes (0..=8, 0..=64 with FD)
+    data: Vec<u8>, // CAN data payload, `dlc` bytes
+    /// CAN-FD frame, whose payload may exceed 8 bytes
+    pub fd: bool,
+    /// Bit rate switch of a CAN-FD frame
+    pub brs: bool,
     pub timestamp: String, // ISO timestamp for tracking
+    /// Storage order, assigned by SQLite when the frame is stored and strictly increasing
+    pub seq: Option<u64>,
+}
+
+/// Frame payload on the wire: a hex string, or the legacy array padded to 8 bytes
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawData {
+    Hex(String),
+    Bytes(Vec<u8>),
+}
+
+/// Unchecked wire representation, validated through `CanMessage::try_new`
+#[derive(Serialize, Deserialize)]
+struct RawCanMessage {
+    id: CanId,
+    dlc: u8,
+    data: RawData,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    fd: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    brs: bool,
+    timestamp: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seq: Option<u64>,
+}
+
+impl TryFrom<RawCanMessage> for CanMessage {
+    type Error = CanError;
+
+    fn try_from(raw: RawCanMessage) -> Result<Self, Self::Error> {
+        if raw.dlc > MAX_FD_DLC || (!raw.fd && raw.dlc > MAX_DLC) {
+            return Err(if raw.fd {
+                CanError::InvalidFdDlc(raw.dlc)
+            } else {
+                CanError::InvalidDlc(raw.dlc)
+            });
+        }
+        let dlc = raw.dlc as usize;
+        let payload = match raw.data {
+            RawData::Hex(hex) => {
+                let bytes = from_hex(&hex).map_err(CanError::InvalidData)?;
+                if bytes.len() != dlc {
+                    return Err(CanError::InvalidData(format!(
+                        "DLC {} but {} data bytes",
+                        dlc,
+                        bytes.len()
+                    )));
+                }
+                bytes
+            }
+            // Arrays come from clients predating hex payloads, padded to 8 bytes
+            RawData::Bytes(bytes) if !raw.fd && bytes.len() >= dlc && bytes.len() <= 8 => {
+                bytes[..dlc].to_vec()
+            }
+            RawData::Bytes(bytes) => {
+                return Err(CanError::InvalidData(format!(
+                    "DLC {} but {} data bytes",
+                    dlc,
+                    bytes.len()
+                )))
+            }
+        };
+
+        let mut message = if raw.fd {
+            CanMessage::try_new_fd(raw.id, &payload, raw.brs, raw.timestamp)?
+        } else if raw.brs {
+            return Err(CanError::InvalidData(
+                "BRS is only defined for CAN-FD frames".to_string(),
+            ));
+        } else {
+            CanMessage::try_new(raw.id, &payload, raw.timestamp)?
+        };
+        message.seq = raw.seq;
+        Ok(message)
+    }
+}
+
+impl From<CanMessage> for RawCanMessage {
+    fn from(message: CanMessage) -> Self {
+        RawCanMessage {
+            id: CanId::from(message.id),
+            dlc: message.dlc,
+            data: RawData::Hex(to_hex(message.data())),
+            fd: message.fd,
+            brs: message.brs,
+            timestamp: message.timestamp,
+            seq: message.seq,
+        }
+    }
+}
+
+/// Lowercase hex form of `bytes`
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Parse a hex string (either case, no separators) into bytes
+pub fn from_hex(hex: &str) -> Result<Vec<u8>, String> {
+    if !hex.len().is_multiple_of(2) {
+        return Err(format!("Hex payload '{}' has an odd length", hex));
+    }
+    // `from_str_radix` would take a sign in place of the first digit of a pair
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("Invalid hex payload '{}'", hex));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| {
+            hex.get(index..index + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| format!("Invalid hex payload '{}'", hex))
+        })
+        .collect()
 }
 
 impl CanMessage {
+    /// Build a classic CAN message carrying `payload`, its length being the DLC
+    ///
+    /// Rejects identifiers and payload lengths a real bus could not carry.
+    pub fn try_new(
+        id: impl Into<CanId>,
+        payload: &[u8],
+        timestamp: impl Into<String>,
+    ) -> Result<Self, CanError> {
+        let id = id.into().standard()?;
+        if payload.len() > MAX_DLC as usize {
+            return Err(CanError::InvalidDlc(
+                payload.len().min(u8::MAX as usize) as u8
+            ));
+        }
+
+        Ok(CanMessage {
+            id,
+            dlc: payload.len() as u8,
+            data: payload.to_vec(),
+            fd: false,
+            brs: false,
+            timestamp: timestamp.into(),
+            seq: None,
+        })
+    }
+
+    /// Build a CAN-FD message carrying `payload`, sent with a bit rate switch when `brs`
+    ///
+    /// Rejects identifiers a real bus could not carry and payload lengths outside
+    /// `FD_LENGTHS`.
+    pub fn try_new_fd(
+        id: impl Into<CanId>,
+        payload: &[u8],
+        brs: bool,
+        timestamp: impl Into<String>,
+    ) -> Result<Self, CanError> {
+        let id = id.into().standard()?;
+        let dlc = payload.len().min(u8::MAX as usize) as u8;
+        if !FD_LENGTHS.contains(&dlc) {
+            return Err(CanError::InvalidFdDlc(dlc));
+        }
+
+        Ok(CanMessage {
+            id,
+            dlc,
+            data: payload.to_vec(),
+            fd: true,
+            brs,
+            timestamp: timestamp.into(),
+            seq: None,
+        })
+    }
+
+    /// Build a CAN message without validating the identifier or DLC
+    ///
+    /// For the encoder, whose identifiers and DLCs are constants, and for tests producing
+    /// frames a real bus could not carry. Bytes past the DLC are dropped, and a DLC past
+    /// 8 is clamped to the 8 bytes given so that `dlc` always matches `data()`.
+    pub fn new_unchecked(id: u16, dlc: u8, data: [u8; 8], timestamp: impl Into<String>) -> Self {
+        let len = (dlc as usize).min(data.len());
+        CanMessage {
+            id,
+            dlc: len as u8,
+            data: data[..len].to_vec(),
+            fd: false,
+            brs: false,
+            timestamp: timestamp.into(),
+            seq: None,
+        }
+    }
+
+    /// Bytes carried by the frame, `dlc` of them
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
     /// Extract bits from a byte array starting at a specific bit position
     ///
     /// # Arguments
@@ -18,7 +497,8 @@ impl CanMessage {
     /// * `num_bits` - The number of bits to extract (max 64)
     ///
     /// # Returns
-    /// The extracted bits as a u64 value
+    /// The extracted bits as a u64 value. Never panics: bits past the end of `data`
+    /// read as zero and `num_bits` outside 1..=64 yields 0.
     pub fn extract_bits_from_bytes(data: &[u8], start_bit: usize, num_bits: usize) -> u64 {
         if num_bits == 0 || num_bits > 64 {
             return 0;
@@ -29,12 +509,11 @@ impl CanMessage {
         let mut result = 0u64;
         let mut bits_read = 0;
 
-        for byte_idx in start_byte..data.len() {
+        for (byte_idx, &current_byte) in data.iter().enumerate().skip(start_byte) {
             if bits_read >= num_bits {
                 break;
             }
 
-            let current_byte = data[byte_idx];
             let bits_to_read_from_byte = if byte_idx == start_byte {
                 (8 - start_bit_in_byte).min(num_bits - bits_read)
             } else {
@@ -47,7 +526,8 @@ impl CanMessage {
                 0
             };
 
-            let mask = (1u8 << bits_to_read_from_byte) - 1;
+            // `1u8 << 8` would overflow, so build the mask from the top instead
+            let mask = u8::MAX >> (8 - bits_to_read_from_byte);
             let extracted_bits = (current_byte >> shift_in_byte) & mask;
 
             result |= (extracted_bits as u64) << bits_read;
@@ -64,6 +544,9 @@ impl CanMessage {
     /// * `start_bit` - The starting bit position (0-based)
     /// * `num_bits` - The number of bits to set (max 64)
     /// * `value` - The value to set in the specified bits
+    ///
+    /// Never panics: bits past the end of `data` are dropped and `num_bits` outside
+    /// 1..=64 leaves `data` untouched.
     pub fn set_bits_in_bytes(data: &mut [u8], start_bit: usize, num_bits: usize, value: u64) {
         if num_bits == 0 || num_bits > 64 {
             return;
@@ -73,7 +556,7 @@ impl CanMessage {
         let start_bit_in_byte = start_bit % 8;
         let mut bits_written = 0;
 
-        for byte_idx in start_byte..data.len() {
+        for (byte_idx, byte) in data.iter_mut().enumerate().skip(start_byte) {
             if bits_written >= num_bits {
                 break;
             }
@@ -90,10 +573,10 @@ impl CanMessage {
                 0
             };
 
-            let mask = ((1u8 << bits_to_write_to_byte) - 1) << shift_in_byte;
+            let mask = (u8::MAX >> (8 - bits_to_write_to_byte)) << shift_in_byte;
             let value_bits = ((value >> bits_written) as u8) << shift_in_byte;
 
-            data[byte_idx] = (data[byte_idx] & !mask) | (value_bits & mask);
+            *byte = (*byte & !mask) | (value_bits & mask);
             bits_written += bits_to_write_to_byte;
         }
     }
@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::core::can::CanId;
+
+/// One CAN frame of the DrivingStep layout, independent of the ID it is sent with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameGroup {
+    EngineRpm,
+    EngineTemp,
+    Fuel,
+    SpeedData,
+    SpeedFlags,
+    ClimateTemp,
+    ClimateFan,
+    StepInfo,
+    Adas,
+    Gps,
+}
+
+impl FrameGroup {
+    pub const ALL: [FrameGroup; 10] = [
+        FrameGroup::EngineRpm,
+        FrameGroup::EngineTemp,
+        FrameGroup::Fuel,
+        FrameGroup::SpeedData,
+        FrameGroup::SpeedFlags,
+        FrameGroup::ClimateTemp,
+        FrameGroup::ClimateFan,
+        FrameGroup::StepInfo,
+        FrameGroup::Adas,
+        FrameGroup::Gps,
+    ];
+
+    /// Frames that make up one complete DrivingStep, each expected exactly once
+    pub const REQUIRED: [FrameGroup; 7] = [
+        FrameGroup::EngineRpm,
+        FrameGroup::EngineTemp,
+        FrameGroup::SpeedData,
+        FrameGroup::SpeedFlags,
+        FrameGroup::ClimateTemp,
+        FrameGroup::ClimateFan,
+        FrameGroup::StepInfo,
+    ];
+
+    /// Frames of optional groups, present at most once when the step carries them
+    pub const OPTIONAL: [FrameGroup; 3] = [FrameGroup::Adas, FrameGroup::Fuel, FrameGroup::Gps];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FrameGroup::EngineRpm => "engine_rpm",
+            FrameGroup::EngineTemp => "engine_temp",
+            FrameGroup::Fuel => "fuel",
+            FrameGroup::SpeedData => "speed_data",
+            FrameGroup::SpeedFlags => "speed_flags",
+            FrameGroup::ClimateTemp => "climate_temp",
+            FrameGroup::ClimateFan => "climate_fan",
+            FrameGroup::StepInfo => "step_info",
+            FrameGroup::Adas => "adas",
+            FrameGroup::Gps => "gps",
+        }
+    }
+
+    /// CAN ID of the frame when no map overrides it
+    pub fn default_id(self) -> u16 {
+        match self {
+            FrameGroup::EngineRpm => 0x100,
+            FrameGroup::EngineTemp => 0x101,
+            FrameGroup::Fuel => 0x102,
+            FrameGroup::SpeedData => 0x200,
+            FrameGroup::SpeedFlags => 0x201,
+            FrameGroup::ClimateTemp => 0x300,
+            FrameGroup::ClimateFan => 0x301,
+            FrameGroup::StepInfo => 0x400,
+            FrameGroup::Adas => 0x500,
+            FrameGroup::Gps => 0x600,
+        }
+    }
+
+    /// DLC written for the frame, as declared by the DrivingStep layout
+    pub fn dlc(self) -> u8 {
+        crate::core::dbc::driving_step_message(self, false).dlc
+    }
+
+    pub fn purpose(self) -> &'static str {
+        match self {
+            FrameGroup::EngineRpm => "Engine RPM + Fuel Pressure + Running status",
+            FrameGroup::EngineTemp => "Engine temperatures + Throttle + Load",
+            FrameGroup::Fuel => "Fuel level + Consumption + Range",
+            FrameGroup::SpeedData => "Vehicle speed + Gear + Wheel speeds",
+            FrameGroup::SpeedFlags => "Speed flags (ABS, Traction, Cruise)",
+            FrameGroup::ClimateTemp => "Climate temperatures",
+            FrameGroup::ClimateFan => "Climate fan + flags",
+            FrameGroup::StepInfo => "Step info (duration + name hash)",
+            FrameGroup::Adas => "ADAS (lead distance, ACC, lane keeping)",
+            FrameGroup::Gps => "GPS position (latitude + longitude)",
+        }
+    }
+
+    /// CAN ID of the frame in the map in force
+    pub fn id(self) -> u16 {
+        can_ids().id(self)
+    }
+}
+
+/// CAN ID of every DrivingStep frame, for installations whose own traffic uses the defaults
+///
+/// Deserializes from an object of frame group names to CAN IDs (`"0x180"` or `384`);
+/// omitted groups keep their default ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanIdMap {
+    ids: [u16; FrameGroup::ALL.len()],
+}
+
+impl Default for CanIdMap {
+    fn default() -> Self {
+        CanIdMap {
+            ids: FrameGroup::ALL.map(FrameGroup::default_id),
+        }
+    }
+}
+
+impl CanIdMap {
+    pub fn id(&self, group: FrameGroup) -> u16 {
+        self.ids[group as usize]
+    }
+
+    /// Frame group sent with `id`, `None` for IDs outside the DrivingStep layout
+    pub fn group(&self, id: u16) -> Option<FrameGroup> {
+        FrameGroup::ALL
+            .into_iter()
+            .find(|group| self.id(*group) == id)
+    }
+
+    /// Build a map from overrides, refusing extended IDs and IDs shared by two groups
+    pub fn new(overrides: &BTreeMap<FrameGroup, CanId>) -> Result<Self, String> {
+        let mut map = CanIdMap::default();
+        for (group, id) in overrides {
+            map.ids[*group as usize] = id
+                .standard()
+                .map_err(|e| format!("{}: {}", group.as_str(), e))?;
+        }
+        for (index, group) in FrameGroup::ALL.iter().enumerate() {
+            if let Some(other) = FrameGroup::ALL[index + 1..]
+                .iter()
+                .find(|other| map.id(**other) == map.id(*group))
+            {
+                return Err(format!(
+                    "{} and {} share CAN ID {}",
+                    group.as_str(),
+                    other.as_str(),
+                    CanId::from(map.id(*group))
+                ));
+            }
+        }
+        Ok(map)
+    }
+}
+
+impl<'de> Deserialize<'de> for CanIdMap {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let overrides = BTreeMap::<FrameGroup, CanId>::deserialize(deserializer)?;
+        CanIdMap::new(&overrides).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for CanIdMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            FrameGroup::ALL
+                .iter()
+                .map(|group| (group.as_str(), CanId::from(self.id(*group)).to_string())),
+        )
+    }
+}
+
+static CAN_IDS: OnceLock<CanIdMap> = OnceLock::new();
+
+/// Install the CAN ID map of the process, once and before the first frame is encoded;
+/// returns the map back when one is already in force
+pub fn set_can_ids(map: CanIdMap) -> Result<(), CanIdMap> {
+    CAN_IDS.set(map)
+}
+
+/// CAN ID map in force, the default layout unless `set_can_ids` installed another
+pub fn can_ids() -> &'static CanIdMap {
+    CAN_IDS.get_or_init(CanIdMap::default)
+}
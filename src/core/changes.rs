@@ -0,0 +1,104 @@
+use actix_web::web::Data;
+use actix_web::{get, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::common::error::AppError;
+use crate::core::bus::{StreamMode, SubscriptionFilter};
+use crate::core::journal::Journal;
+use crate::core::scrub::Scrub;
+use crate::features::tenant::TenantScope;
+
+/// Changes returned by one poll when `limit` is not given
+const DEFAULT_LIMIT: usize = 1000;
+/// Largest `limit` accepted, the default journal capacity
+const MAX_LIMIT: usize = 10_000;
+
+#[derive(Debug, Deserialize)]
+pub struct ChangesQuery {
+    /// Journal number of the latest change already processed, 0 to read from the start
+    #[serde(default)]
+    pub cursor: u64,
+    /// Most journal messages read by this poll
+    pub limit: Option<usize>,
+    /// `?mode=decoded` returns the frames of each step with their decoded signals
+    #[serde(default)]
+    pub mode: StreamMode,
+}
+
+/// One batch of the change feed
+#[derive(Debug, Serialize)]
+pub struct ChangeBatch {
+    /// Bus messages after the cursor in journal order, each with its `seq`
+    pub changes: Vec<Value>,
+    /// Cursor of the next poll
+    pub cursor: u64,
+    /// Whether more changes wait after this batch
+    pub more: bool,
+    /// Messages after the cursor already evicted from the journal, lost to the caller
+    pub missed: u64,
+}
+
+/// Bus messages journaled after `cursor`, the poll-based counterpart of websocket resumption
+///
+/// The next cursor is the number of the last message read, even when the filter of the
+/// caller dropped it, so a poll never reads the same message twice.
+#[get("/changes")]
+async fn list_changes(
+    journal: Data<Journal>,
+    query: web::Query<ChangesQuery>,
+    scope: TenantScope,
+    scrub: Scrub,
+) -> Result<HttpResponse, AppError> {
+    let query = query.into_inner();
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(AppError::bad_request(format!(
+            "limit must be between 1 and {}, got {}",
+            MAX_LIMIT, limit
+        )));
+    }
+    let latest = journal.latest();
+    if query.cursor > latest {
+        // The journal lives in memory and starts over with the server
+        return Err(AppError::conflict(format!(
+            "Cursor {} is ahead of the journal (latest {}), the server restarted; start over from cursor 0",
+            query.cursor, latest
+        )));
+    }
+
+    let filter = SubscriptionFilter {
+        mode: query.mode,
+        tenant: scope.name().map(str::to_string),
+        scrub: scrub.0,
+        ..SubscriptionFilter::default()
+    };
+    let (entries, missed) = journal.since(query.cursor);
+    let more = entries.len() > limit;
+    let mut cursor = query.cursor;
+    let mut changes = Vec::new();
+    for entry in entries.into_iter().take(limit) {
+        cursor = entry.seq;
+        if !filter.accepts(&entry.message) {
+            continue;
+        }
+        for payload in filter.payloads(&entry.message)? {
+            let mut value: Value = serde_json::from_str(&payload)?;
+            if let Some(fields) = value.as_object_mut() {
+                fields.insert("seq".to_string(), entry.seq.into());
+            }
+            changes.push(value);
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(ChangeBatch {
+        changes,
+        cursor,
+        more,
+        missed,
+    }))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_changes);
+}
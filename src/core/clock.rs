@@ -0,0 +1,90 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Source of the timestamps written on CAN frames
+///
+/// Timestamps are informational: frames are ordered by the sequence number SQLite assigns
+/// when they are stored, since RFC3339 strings collide within a millisecond and do not
+/// sort correctly across time zones.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    /// RFC3339 form stored in the `timestamp` column
+    fn timestamp(&self) -> String {
+        self.now().to_rfc3339()
+    }
+}
+
+/// Milliseconds since the Unix epoch of an RFC3339 timestamp, stored beside it in the
+/// `timestamp_ms` columns; `None` for other strings
+pub fn epoch_ms(timestamp: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|timestamp| timestamp.timestamp_millis())
+}
+
+/// Wall clock used outside of tests
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Start of the simulated time when a run names none, far from any wall-clock data
+pub const SIMULATED_EPOCH: &str = "2000-01-01T00:00:00Z";
+
+/// Virtual clock of a replay, reading an epoch plus the simulated time elapsed so far
+///
+/// The clock only moves when `advance` is called, so data it stamps is relative to the
+/// start of the replay rather than to when the replay happened to run.
+#[derive(Debug)]
+pub struct SimulatedClock {
+    epoch: DateTime<Utc>,
+    elapsed_ms: AtomicI64,
+}
+
+impl SimulatedClock {
+    pub fn new(epoch: DateTime<Utc>) -> Self {
+        SimulatedClock {
+            epoch,
+            elapsed_ms: AtomicI64::new(0),
+        }
+    }
+
+    pub fn epoch(&self) -> DateTime<Utc> {
+        self.epoch
+    }
+
+    /// Move the clock forward by `by`, such as the duration of a replayed step
+    pub fn advance(&self, by: Duration) {
+        self.elapsed_ms
+            .fetch_add(by.as_millis() as i64, Ordering::Relaxed);
+    }
+}
+
+impl Default for SimulatedClock {
+    fn default() -> Self {
+        SimulatedClock::new(SIMULATED_EPOCH.parse().expect("valid simulated epoch"))
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.epoch + chrono::Duration::milliseconds(self.elapsed_ms.load(Ordering::Relaxed))
+    }
+}
+
+/// Time base of stored data: the wall clock, or the simulated clock of a replay
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeSource {
+    #[default]
+    Wall,
+    Simulated,
+}
@@ -0,0 +1,244 @@
+use std::io::{self, Write};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use actix_web::body::{BodySize, BoxBody, MessageBody};
+use actix_web::dev::{ResponseHead, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{
+    HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH, VARY,
+};
+use actix_web::http::StatusCode;
+use actix_web::middleware::Next;
+use actix_web::web::{Bytes, Data};
+use brotli::CompressorWriter;
+use flate2::write::GzEncoder;
+
+/// Bodies of a known size below this are sent as is, compressing them gains nothing
+pub const MIN_SIZE: u64 = 1024;
+
+/// Brotli window, as log2 of its size in bytes
+const BROTLI_WINDOW: u32 = 22;
+
+/// Compression levels of the responses, an encoding being off at level 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionConfig {
+    /// gzip level, from 1 (fastest) to 9 (smallest)
+    pub gzip_level: u32,
+    /// Brotli quality, from 1 (fastest) to 11 (smallest)
+    pub brotli_quality: u32,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        CompressionConfig {
+            gzip_level: 6,
+            brotli_quality: 4,
+        }
+    }
+}
+
+impl CompressionConfig {
+    /// Same levels, clamped to what each encoding supports
+    pub fn clamped(self) -> Self {
+        CompressionConfig {
+            gzip_level: self.gzip_level.min(9),
+            brotli_quality: self.brotli_quality.min(11),
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        self.gzip_level > 0 || self.brotli_quality > 0
+    }
+
+    /// Encoding preferred by an `Accept-Encoding` header among those enabled, Brotli
+    /// winning ties
+    fn negotiate(&self, accept: &str) -> Option<Encoding> {
+        let mut best: Option<(Encoding, f32)> = None;
+        for item in accept.split(',') {
+            let mut parts = item.split(';');
+            let name = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            let encoding = match name.as_str() {
+                "br" if self.brotli_quality > 0 => Encoding::Brotli,
+                "gzip" if self.gzip_level > 0 => Encoding::Gzip,
+                _ => continue,
+            };
+            let better = match best {
+                None => true,
+                Some((current, q)) => quality > q || (quality == q && encoding < current),
+            };
+            if quality > 0.0 && better {
+                best = Some((encoding, quality));
+            }
+        }
+        best.map(|(encoding, _)| encoding)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    fn header(self) -> HeaderValue {
+        HeaderValue::from_static(match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        })
+    }
+
+    fn encoder(self, config: &CompressionConfig) -> Encoder {
+        match self {
+            Encoding::Brotli => Encoder::Brotli(Box::new(CompressorWriter::new(
+                Vec::new(),
+                4096,
+                config.brotli_quality,
+                BROTLI_WINDOW,
+            ))),
+            Encoding::Gzip => Encoder::Gzip(GzEncoder::new(
+                Vec::new(),
+                flate2::Compression::new(config.gzip_level),
+            )),
+        }
+    }
+}
+
+enum Encoder {
+    Brotli(Box<CompressorWriter<Vec<u8>>>),
+    Gzip(GzEncoder<Vec<u8>>),
+}
+
+impl Encoder {
+    /// Compressed bytes of `chunk`, flushed so streamed lines reach the client right away
+    fn write(&mut self, chunk: &[u8]) -> io::Result<Bytes> {
+        let output = match self {
+            Encoder::Brotli(encoder) => {
+                encoder.write_all(chunk)?;
+                encoder.flush()?;
+                encoder.get_mut()
+            }
+            Encoder::Gzip(encoder) => {
+                encoder.write_all(chunk)?;
+                encoder.flush()?;
+                encoder.get_mut()
+            }
+        };
+        Ok(Bytes::from(std::mem::take(output)))
+    }
+
+    /// Bytes ending the compressed stream
+    fn finish(self) -> io::Result<Bytes> {
+        let output = match self {
+            Encoder::Brotli(encoder) => encoder.into_inner(),
+            Encoder::Gzip(encoder) => encoder.finish()?,
+        };
+        Ok(Bytes::from(output))
+    }
+}
+
+/// Response body compressed chunk by chunk, or passed through when `encoder` is `None`
+pub struct Compressed {
+    body: BoxBody,
+    encoder: Option<Encoder>,
+    done: bool,
+}
+
+impl MessageBody for Compressed {
+    type Error = Box<dyn std::error::Error>;
+
+    fn size(&self) -> BodySize {
+        match self.encoder {
+            Some(_) => BodySize::Stream,
+            None => self.body.size(),
+        }
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+        let Some(encoder) = this.encoder.as_mut() else {
+            return Pin::new(&mut this.body).poll_next(cx);
+        };
+        loop {
+            match ready!(Pin::new(&mut this.body).poll_next(cx)) {
+                Some(Ok(chunk)) => {
+                    let compressed = encoder.write(&chunk)?;
+                    if !compressed.is_empty() {
+                        return Poll::Ready(Some(Ok(compressed)));
+                    }
+                }
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => {
+                    this.done = true;
+                    let tail = this.encoder.take().map(Encoder::finish).transpose()?;
+                    return Poll::Ready(tail.filter(|tail| !tail.is_empty()).map(Ok));
+                }
+            }
+        }
+    }
+}
+
+/// Whether a response is worth compressing, whatever the client accepts
+fn compressible(head: &ResponseHead, size: BodySize) -> bool {
+    let status = head.status;
+    let small = match size {
+        BodySize::None => true,
+        BodySize::Sized(len) => len < MIN_SIZE,
+        BodySize::Stream => false,
+    };
+    !(small
+        || status.is_informational()
+        || status == StatusCode::NO_CONTENT
+        || status == StatusCode::NOT_MODIFIED
+        || head.headers.contains_key(CONTENT_ENCODING))
+}
+
+/// Middleware compressing responses with gzip or Brotli, as the client's `Accept-Encoding`
+/// prefers, at the levels of the app's `Data<CompressionConfig>` (defaults without one)
+///
+/// Streamed bodies (SSE, NDJSON progress) are compressed and flushed chunk by chunk, so
+/// every line still reaches the client as soon as it is written.
+pub async fn compress(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<Compressed>, actix_web::Error> {
+    let config = req
+        .app_data::<Data<CompressionConfig>>()
+        .map(|config| *config.get_ref())
+        .unwrap_or_default();
+    let encoding = req
+        .headers()
+        .get(ACCEPT_ENCODING)
+        .and_then(|accept| accept.to_str().ok())
+        .and_then(|accept| config.negotiate(accept));
+
+    let res = next.call(req).await?;
+    Ok(res.map_body(|head, body| {
+        let body = body.boxed();
+        let mut encoder = None;
+        if config.enabled() && compressible(head, body.size()) {
+            head.headers
+                .append(VARY, HeaderValue::from_static("accept-encoding"));
+            if let Some(encoding) = encoding {
+                head.headers.insert(CONTENT_ENCODING, encoding.header());
+                head.headers.remove(CONTENT_LENGTH);
+                encoder = Some(encoding.encoder(&config));
+            }
+        }
+        Compressed {
+            body,
+            encoder,
+            done: false,
+        }
+    }))
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::future::{ready, Ready};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use actix_web::dev::Payload;
+use actix_web::web::{Bytes, Data};
+use actix_web::{Error, FromRequest, HttpRequest};
+use futures_util::{Stream, StreamExt};
+use serde::Serialize;
+
+use crate::common::error::AppError;
+
+/// Silence after which SSE responses send a comment line, so the connection of a client
+/// that went away fails and gives its slot back even while the bus is idle
+pub const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// Caps on the WebSocket and SSE clients connected at once, unlimited when `None`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionLimits {
+    /// Stream clients across every address
+    pub max_total: Option<usize>,
+    /// Stream clients of one peer address
+    pub max_per_ip: Option<usize>,
+    /// Wait suggested to refused clients by `Retry-After`
+    pub retry_after: Duration,
+}
+
+impl Default for ConnectionLimits {
+    fn default() -> Self {
+        ConnectionLimits {
+            max_total: None,
+            max_per_ip: None,
+            retry_after: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Stream clients connected right now
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConnectionCount {
+    pub total: usize,
+    pub per_ip: HashMap<IpAddr, usize>,
+}
+
+/// Counts the WebSocket and SSE clients, refusing new ones past the limits
+///
+/// Every stream handler takes a `StreamSlot`, which holds its place until the response
+/// stream or the WebSocket actor is dropped, so the broadcast fan-out cannot grow
+/// without bound. Clients are told apart by the peer address of their TCP connection;
+/// behind a reverse proxy they all share the proxy's.
+#[derive(Clone)]
+pub struct ConnectionLimiter {
+    limits: ConnectionLimits,
+    connected: Arc<Mutex<ConnectionCount>>,
+}
+
+impl ConnectionLimiter {
+    pub fn new(limits: ConnectionLimits) -> Self {
+        ConnectionLimiter {
+            limits,
+            connected: Arc::default(),
+        }
+    }
+
+    pub fn limits(&self) -> ConnectionLimits {
+        self.limits
+    }
+
+    pub fn connected(&self) -> ConnectionCount {
+        self.connected.lock().unwrap().clone()
+    }
+
+    /// Count a client from `ip`, or `503` with a `Retry-After` when a limit is reached
+    pub fn acquire(&self, ip: Option<IpAddr>) -> Result<StreamSlot, AppError> {
+        let mut connected = self.connected.lock().unwrap();
+        let retry_after = self.limits.retry_after.as_secs().max(1);
+        if self
+            .limits
+            .max_total
+            .is_some_and(|max| connected.total >= max)
+        {
+            return Err(AppError::service_unavailable(
+                format!("{} stream clients already connected", connected.total),
+                retry_after,
+            ));
+        }
+        if let (Some(ip), Some(max)) = (ip, self.limits.max_per_ip) {
+            let from_ip = connected.per_ip.get(&ip).copied().unwrap_or_default();
+            if from_ip >= max {
+                return Err(AppError::service_unavailable(
+                    format!("{} stream clients already connected from {}", from_ip, ip),
+                    retry_after,
+                ));
+            }
+        }
+
+        connected.total += 1;
+        if let Some(ip) = ip {
+            *connected.per_ip.entry(ip).or_default() += 1;
+        }
+        Ok(StreamSlot {
+            held: Some((self.clone(), ip)),
+        })
+    }
+
+    fn release(&self, ip: Option<IpAddr>) {
+        let mut connected = self.connected.lock().unwrap();
+        connected.total = connected.total.saturating_sub(1);
+        if let Some(ip) = ip {
+            if let Some(count) = connected.per_ip.get_mut(&ip) {
+                *count -= 1;
+                if *count == 0 {
+                    connected.per_ip.remove(&ip);
+                }
+            }
+        }
+    }
+}
+
+/// Place of one stream client among the connected ones, given back when dropped
+pub struct StreamSlot {
+    held: Option<(ConnectionLimiter, Option<IpAddr>)>,
+}
+
+impl Drop for StreamSlot {
+    fn drop(&mut self) {
+        if let Some((limiter, ip)) = self.held.take() {
+            limiter.release(ip);
+        }
+    }
+}
+
+/// Slot taken from the app's `Data<ConnectionLimiter>`, unlimited for apps without one
+impl FromRequest for StreamSlot {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(match req.app_data::<Data<ConnectionLimiter>>() {
+            Some(limiter) => limiter.acquire(req.peer_addr().map(|addr| addr.ip())),
+            None => Ok(StreamSlot { held: None }),
+        })
+    }
+}
+
+/// SSE body `stream` with a `: keep-alive` comment after every `KEEP_ALIVE` without data
+pub fn keep_alive<S>(stream: S) -> impl Stream<Item = Result<Bytes, Error>>
+where
+    S: Stream<Item = Result<Bytes, Error>>,
+{
+    async_stream::stream! {
+        let mut stream = std::pin::pin!(stream);
+        loop {
+            match tokio::time::timeout(KEEP_ALIVE, stream.next()).await {
+                Ok(Some(chunk)) => yield chunk,
+                Ok(None) => break,
+                Err(_) => yield Ok(Bytes::from_static(b": keep-alive\n\n")),
+            }
+        }
+    }
+}
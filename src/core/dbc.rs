@@ -0,0 +1,496 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use serde::Serialize;
+
+use crate::core::can::{CanId, CanMessage, FD_LENGTHS};
+use crate::core::can_map::{can_ids, CanIdMap, FrameGroup};
+
+/// DrivingStep layout with its multi-byte signals in little-endian (Intel) order
+const DRIVING_STEP_DBC: &str = include_str!("driving_step.dbc");
+
+/// Pseudo-message DBC editors keep signals that belong to no frame in
+const INDEPENDENT_SIGNALS: &str = "VECTOR__INDEPENDENT_SIG_MSG";
+
+/// Order the bits of a signal are laid out in, `@1` (Intel) or `@0` (Motorola) in a DBC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ByteOrder {
+    /// Start bit is the least significant bit, the signal grows towards higher bits
+    LittleEndian,
+    /// Start bit is the most significant bit, the signal continues into the next byte
+    BigEndian,
+}
+
+/// One signal of a DBC message, as declared by its `SG_` line
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DbcSignal {
+    pub name: String,
+    pub start_bit: u16,
+    pub length: u16,
+    pub byte_order: ByteOrder,
+    pub signed: bool,
+    pub factor: f64,
+    pub offset: f64,
+    pub min: f64,
+    pub max: f64,
+    pub unit: String,
+}
+
+impl DbcSignal {
+    /// Positions of the bits of the signal in the payload, least significant first
+    fn bits(&self) -> Vec<u16> {
+        match self.byte_order {
+            ByteOrder::LittleEndian => (self.start_bit..self.start_bit + self.length).collect(),
+            ByteOrder::BigEndian => {
+                // Walk from the most significant bit down each byte, then on to the next byte
+                let mut bits = Vec::with_capacity(self.length as usize);
+                let mut bit = self.start_bit;
+                for _ in 0..self.length {
+                    bits.push(bit);
+                    bit = if bit.is_multiple_of(8) {
+                        bit + 15
+                    } else {
+                        bit - 1
+                    };
+                }
+                bits.reverse();
+                bits
+            }
+        }
+    }
+
+    /// Smallest and largest raw value the signal can carry
+    pub fn raw_range(&self) -> (i64, i64) {
+        match (self.signed, self.length) {
+            (_, 64) if self.signed => (i64::MIN, i64::MAX),
+            (_, 64) => (0, i64::MAX),
+            (true, length) => (-(1 << (length - 1)), (1 << (length - 1)) - 1),
+            (false, length) => (0, (1 << length) - 1),
+        }
+    }
+
+    /// Raw value of the signal in `data`, sign-extended for signed signals
+    pub fn extract(&self, data: &[u8]) -> i64 {
+        let raw = match self.byte_order {
+            ByteOrder::LittleEndian => CanMessage::extract_bits_from_bytes(
+                data,
+                self.start_bit as usize,
+                self.length as usize,
+            ),
+            ByteOrder::BigEndian => self.bits().iter().enumerate().fold(0, |raw, (i, bit)| {
+                raw | CanMessage::extract_bits_from_bytes(data, *bit as usize, 1) << i
+            }),
+        };
+        if self.signed && self.length < 64 && raw >> (self.length - 1) & 1 == 1 {
+            (raw | u64::MAX << self.length) as i64
+        } else {
+            raw as i64
+        }
+    }
+
+    /// Write the raw value `raw` into `data`, clamped to what the signal can carry
+    pub fn insert(&self, data: &mut [u8], raw: i64) {
+        let (min, max) = self.raw_range();
+        let raw = raw.clamp(min, max) as u64;
+        match self.byte_order {
+            ByteOrder::LittleEndian => CanMessage::set_bits_in_bytes(
+                data,
+                self.start_bit as usize,
+                self.length as usize,
+                raw,
+            ),
+            ByteOrder::BigEndian => {
+                for (i, bit) in self.bits().into_iter().enumerate() {
+                    CanMessage::set_bits_in_bytes(data, bit as usize, 1, raw >> i);
+                }
+            }
+        }
+    }
+
+    /// Physical value of the signal in `data`, `raw * factor + offset`
+    pub fn decode(&self, data: &[u8]) -> f64 {
+        self.extract(data) as f64 * self.factor + self.offset
+    }
+
+    /// Write the physical value `value` into `data`, rounded to the resolution of the signal
+    pub fn encode(&self, data: &mut [u8], value: f64) {
+        let raw = ((value - self.offset) / self.factor).round();
+        // NaN turns into 0 and out of range values saturate before `insert` clamps them
+        self.insert(data, raw as i64);
+    }
+
+    /// Same signal with its bytes in Motorola order, for byte-aligned signals
+    fn to_big_endian(&self) -> DbcSignal {
+        if self.length <= 8 || !self.start_bit.is_multiple_of(8) || !self.length.is_multiple_of(8) {
+            return self.clone();
+        }
+        DbcSignal {
+            start_bit: self.start_bit + 7,
+            byte_order: ByteOrder::BigEndian,
+            ..self.clone()
+        }
+    }
+}
+
+/// One frame of a DBC, as declared by its `BO_` line
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DbcMessage {
+    pub id: u16,
+    pub name: String,
+    pub dlc: u8,
+    pub signals: Vec<DbcSignal>,
+}
+
+impl DbcMessage {
+    pub fn signal(&self, name: &str) -> Option<&DbcSignal> {
+        self.signals.iter().find(|signal| signal.name == name)
+    }
+
+    /// Payload carrying the raw value of each named signal, the others left at zero
+    pub fn pack(&self, raw: &[(&str, i64)]) -> [u8; 8] {
+        let mut data = [0u8; 8];
+        for (name, value) in raw {
+            debug_assert!(
+                self.signal(name).is_some(),
+                "no signal {} in {}",
+                name,
+                self.name
+            );
+            if let Some(signal) = self.signal(name) {
+                signal.insert(&mut data, *value);
+            }
+        }
+        data
+    }
+
+    /// Raw value of the signal `name` in `data`, 0 for signals the message lacks
+    pub fn unpack(&self, data: &[u8], name: &str) -> i64 {
+        debug_assert!(
+            self.signal(name).is_some(),
+            "no signal {} in {}",
+            name,
+            self.name
+        );
+        self.signal(name).map_or(0, |signal| signal.extract(data))
+    }
+
+    /// Physical value of every signal the frame carries in full, in declaration order
+    pub fn decode(&self, data: &[u8]) -> Vec<(&str, f64)> {
+        self.signals
+            .iter()
+            .filter(|signal| {
+                signal
+                    .bits()
+                    .iter()
+                    .all(|bit| (*bit as usize) < data.len() * 8)
+            })
+            .map(|signal| (signal.name.as_str(), signal.decode(data)))
+            .collect()
+    }
+}
+
+/// Messages of a Vector DBC file
+///
+/// Parses the `BO_` and `SG_` lines and skips every other section (nodes, comments,
+/// value tables, attributes). Extended IDs, CAN FD payloads and multiplexed signals
+/// are refused rather than decoded wrongly.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Dbc {
+    pub messages: Vec<DbcMessage>,
+}
+
+impl Dbc {
+    pub fn message(&self, id: u16) -> Option<&DbcMessage> {
+        self.messages.iter().find(|message| message.id == id)
+    }
+
+    pub fn message_named(&self, name: &str) -> Option<&DbcMessage> {
+        self.messages.iter().find(|message| message.name == name)
+    }
+
+    /// Refuse messages sent with the CAN ID of a DrivingStep frame group in `ids`
+    pub fn check_ids(&self, ids: &CanIdMap) -> Result<(), String> {
+        match self
+            .messages
+            .iter()
+            .find_map(|message| Some((message, ids.group(message.id)?)))
+        {
+            Some((message, group)) => Err(format!(
+                "DBC message {} uses CAN ID {} of the {} frame",
+                message.name,
+                CanId::from(message.id),
+                group.as_str()
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+fn parse_message(line: &str) -> Result<DbcMessage, String> {
+    // BO_ <id> <name>: <dlc> <transmitter>
+    let (head, tail) = line["BO_".len()..]
+        .split_once(':')
+        .ok_or("expected BO_ <id> <name>: <dlc>")?;
+    let mut head = head.split_whitespace();
+    let (Some(id), Some(name), None) = (head.next(), head.next(), head.next()) else {
+        return Err("expected BO_ <id> <name>: <dlc>".to_string());
+    };
+    let id: u32 = id.parse().map_err(|_| format!("invalid CAN ID '{}'", id))?;
+    // Bit 31 flags the extended IDs of a DBC
+    if id & 0x8000_0000 != 0 {
+        return Err(format!(
+            "{}: extended CAN ID {} is not supported",
+            name,
+            CanId::new(id & !0x8000_0000).map_or(id.to_string(), |id| id.to_string())
+        ));
+    }
+    let id = CanId::new(id)
+        .and_then(CanId::standard)
+        .map_err(|e| format!("{}: {}", name, e))?;
+    let dlc = tail
+        .split_whitespace()
+        .next()
+        .and_then(|dlc| dlc.parse::<u8>().ok())
+        .ok_or_else(|| format!("{}: expected a DLC after ':'", name))?;
+    // Messages longer than 8 bytes are CAN-FD frames, whose lengths are fixed steps
+    if !FD_LENGTHS.contains(&dlc) {
+        return Err(format!(
+            "{}: DLC {} is not a CAN or CAN-FD payload length",
+            name, dlc
+        ));
+    }
+
+    Ok(DbcMessage {
+        id,
+        name: name.to_string(),
+        dlc,
+        signals: Vec::new(),
+    })
+}
+
+fn parse_signal(line: &str, dlc: u8) -> Result<DbcSignal, String> {
+    // SG_ <name> : <start>|<length>@<order><sign> (<factor>,<offset>) [<min>|<max>] "<unit>" ...
+    let (name, layout) = line["SG_".len()..]
+        .split_once(':')
+        .ok_or("expected SG_ <name> : <layout>")?;
+    let name = match name.split_whitespace().collect::<Vec<_>>()[..] {
+        [name] => name,
+        [name, _] => return Err(format!("{}: multiplexed signals are not supported", name)),
+        _ => return Err("expected SG_ <name> : <layout>".to_string()),
+    };
+    let invalid = |what: &str| format!("{}: invalid {}", name, what);
+
+    let layout = layout.trim_start();
+    let (bits, rest) = layout
+        .split_once(char::is_whitespace)
+        .unwrap_or((layout, ""));
+    let (position, format) = bits.split_once('@').ok_or_else(|| invalid("bit layout"))?;
+    let (start_bit, length) = position
+        .split_once('|')
+        .ok_or_else(|| invalid("bit layout"))?;
+    let start_bit: u16 = start_bit.parse().map_err(|_| invalid("start bit"))?;
+    let length: u16 = length.parse().map_err(|_| invalid("length"))?;
+    let byte_order = match format.get(..1) {
+        Some("1") => ByteOrder::LittleEndian,
+        Some("0") => ByteOrder::BigEndian,
+        _ => return Err(invalid("byte order")),
+    };
+    let signed = match format.get(1..) {
+        Some("+") => false,
+        Some("-") => true,
+        _ => return Err(invalid("sign")),
+    };
+
+    let between = |open: char, close: char| {
+        let start = rest.find(open)? + 1;
+        let end = start + rest[start..].find(close)?;
+        Some(&rest[start..end])
+    };
+    let pair = |text: Option<&str>, separator: char, what: &str| -> Result<(f64, f64), String> {
+        let (first, second) = text
+            .and_then(|text| text.split_once(separator))
+            .ok_or_else(|| invalid(what))?;
+        Ok((
+            first.trim().parse().map_err(|_| invalid(what))?,
+            second.trim().parse().map_err(|_| invalid(what))?,
+        ))
+    };
+    let (factor, offset) = pair(between('(', ')'), ',', "factor and offset")?;
+    let (min, max) = pair(between('[', ']'), '|', "range")?;
+    if factor == 0.0 {
+        return Err(invalid("factor, it must not be 0"));
+    }
+
+    let signal = DbcSignal {
+        name: name.to_string(),
+        start_bit,
+        length,
+        byte_order,
+        signed,
+        factor,
+        offset,
+        min,
+        max,
+        unit: between('"', '"').unwrap_or_default().to_string(),
+    };
+    if !(1..=64).contains(&length) {
+        return Err(format!("{}: length must be 1 to 64 bits", name));
+    }
+    let payload_bits = dlc as u16 * 8;
+    let fits = start_bit < payload_bits && signal.bits().iter().all(|bit| *bit < payload_bits);
+    if !fits {
+        return Err(format!(
+            "{}: bits {}|{} do not fit in {} bytes",
+            name, start_bit, length, dlc
+        ));
+    }
+
+    Ok(signal)
+}
+
+impl FromStr for Dbc {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut dbc = Dbc::default();
+        // Signals of the pseudo-message of independent signals are skipped with it
+        let mut skipping = false;
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let at_line = |e: String| format!("line {}: {}", index + 1, e);
+            match line.split_whitespace().next() {
+                Some("BO_") => {
+                    if line.contains(INDEPENDENT_SIGNALS) {
+                        skipping = true;
+                        continue;
+                    }
+                    skipping = false;
+                    let message = parse_message(line).map_err(at_line)?;
+                    if let Some(other) = dbc.message(message.id) {
+                        return Err(at_line(format!(
+                            "{} and {} share CAN ID {}",
+                            other.name,
+                            message.name,
+                            CanId::from(message.id)
+                        )));
+                    }
+                    dbc.messages.push(message);
+                }
+                Some("SG_") if skipping => {}
+                Some("SG_") => {
+                    let message = dbc
+                        .messages
+                        .last_mut()
+                        .ok_or_else(|| at_line("SG_ before any BO_".to_string()))?;
+                    let signal = parse_signal(line, message.dlc).map_err(at_line)?;
+                    message.signals.push(signal);
+                }
+                _ => {}
+            }
+        }
+        Ok(dbc)
+    }
+}
+
+impl fmt::Display for Dbc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "VERSION \"\"\n\nNS_ :\n\nBS_:\n\nBU_:")?;
+        for message in &self.messages {
+            writeln!(
+                f,
+                "\nBO_ {} {}: {} Vector__XXX",
+                message.id, message.name, message.dlc
+            )?;
+            for signal in &message.signals {
+                writeln!(
+                    f,
+                    " SG_ {} : {}|{}@{}{} ({},{}) [{}|{}] \"{}\" Vector__XXX",
+                    signal.name,
+                    signal.start_bit,
+                    signal.length,
+                    match signal.byte_order {
+                        ByteOrder::LittleEndian => '1',
+                        ByteOrder::BigEndian => '0',
+                    },
+                    if signal.signed { '-' } else { '+' },
+                    signal.factor,
+                    signal.offset,
+                    signal.min,
+                    signal.max,
+                    signal.unit
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// DrivingStep layout in the given byte order, with the default CAN IDs
+///
+/// Messages are named after `FrameGroup::as_str`. Big-endian steps carry their multi-byte
+/// signals in Motorola order, single bytes and flags are laid out the same way in both.
+pub fn driving_step_layout(is_big_endian: bool) -> &'static Dbc {
+    static LITTLE: OnceLock<Dbc> = OnceLock::new();
+    static BIG: OnceLock<Dbc> = OnceLock::new();
+
+    let little = LITTLE.get_or_init(|| {
+        DRIVING_STEP_DBC
+            .parse()
+            .expect("the DrivingStep layout is a valid DBC")
+    });
+    if !is_big_endian {
+        return little;
+    }
+    BIG.get_or_init(|| Dbc {
+        messages: little
+            .messages
+            .iter()
+            .map(|message| DbcMessage {
+                signals: message
+                    .signals
+                    .iter()
+                    .map(DbcSignal::to_big_endian)
+                    .collect(),
+                ..message.clone()
+            })
+            .collect(),
+    })
+}
+
+/// DrivingStep message of `group` in the given byte order
+pub fn driving_step_message(group: FrameGroup, is_big_endian: bool) -> &'static DbcMessage {
+    driving_step_layout(is_big_endian)
+        .message_named(group.as_str())
+        .expect("the DrivingStep layout has a message for every frame group")
+}
+
+/// DrivingStep layout with the CAN IDs in force, followed by the messages of the user DBC
+pub fn in_force(is_big_endian: bool) -> Dbc {
+    let ids = can_ids();
+    let mut layout = driving_step_layout(is_big_endian).clone();
+    for message in &mut layout.messages {
+        if let Some(group) = FrameGroup::ALL
+            .into_iter()
+            .find(|group| group.as_str() == message.name)
+        {
+            message.id = ids.id(group);
+        }
+    }
+    layout.messages.extend(dbc().messages.iter().cloned());
+    layout
+}
+
+static DBC: OnceLock<Dbc> = OnceLock::new();
+
+/// Install the user DBC of the process, once and before the first frame is decoded;
+/// returns it back when one is already in force
+pub fn set_dbc(dbc: Dbc) -> Result<(), Dbc> {
+    DBC.set(dbc)
+}
+
+/// User DBC in force, empty unless `set_dbc` installed one
+pub fn dbc() -> &'static Dbc {
+    DBC.get_or_init(Dbc::default)
+}
@@ -0,0 +1,159 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+use tokio::time::Instant;
+
+use crate::core::bus::{BusMessage, SubscriptionFilter, MIN_MAX_RATE};
+use crate::core::topics::{FeedRegistration, TopicRegistry};
+use crate::features::subscription::LiveFilter;
+
+/// Why a client feed stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedEnd {
+    /// The stored subscription of the client was deleted or no longer allows its transport
+    SubscriptionEnded,
+    /// The bus was dropped
+    Closed,
+}
+
+/// Delivery state of one topic of a rate-limited client
+#[derive(Debug)]
+struct TopicSlot<M> {
+    last_sent: Option<Instant>,
+    /// Latest message received since the last delivery, sent once the topic's slot opens
+    held: Option<M>,
+}
+
+impl<M> Default for TopicSlot<M> {
+    fn default() -> Self {
+        TopicSlot {
+            last_sent: None,
+            held: None,
+        }
+    }
+}
+
+/// Bus messages one stream client receives, through its filter and `max_rate`
+///
+/// A topic delivers at most `max_rate` messages per second: messages arriving faster replace
+/// each other, and the latest is delivered when the topic's next slot opens. Messages are
+/// bus messages, or bus messages numbered by the journal.
+pub struct ClientFeed<M = BusMessage> {
+    rx: broadcast::Receiver<M>,
+    filter: LiveFilter,
+    topics: HashMap<&'static str, TopicSlot<M>>,
+    registration: FeedRegistration,
+}
+
+impl<M: Clone + Borrow<BusMessage>> ClientFeed<M> {
+    /// Feed counted among the stream clients of `registry` while it lives
+    pub fn new(rx: broadcast::Receiver<M>, filter: LiveFilter, registry: &TopicRegistry) -> Self {
+        ClientFeed {
+            rx,
+            registration: registry.register(filter.clone()),
+            filter,
+            topics: HashMap::new(),
+        }
+    }
+
+    /// Next message to send, with the filter it is to be rendered with
+    pub async fn next(&mut self) -> Result<(SubscriptionFilter, M), FeedEnd> {
+        loop {
+            let gap = self.gap();
+            let received = match self.next_due(gap) {
+                Some(due) => tokio::select! {
+                    received = self.rx.recv() => Some(received),
+                    _ = tokio::time::sleep_until(due) => None,
+                },
+                None => Some(self.rx.recv().await),
+            };
+
+            let filter = self.filter.current().ok_or(FeedEnd::SubscriptionEnded)?;
+            let gap = gap_of(&filter);
+            let now = Instant::now();
+            let message = match received {
+                Some(Ok(message)) if filter.accepts(message.borrow()) => {
+                    self.offer(message, gap, now)
+                }
+                Some(Ok(_)) => None,
+                Some(Err(broadcast::error::RecvError::Lagged(missed))) => {
+                    self.registration.lagged(missed);
+                    None
+                }
+                Some(Err(broadcast::error::RecvError::Closed)) => return Err(FeedEnd::Closed),
+                // A held message accepted by a filter that changed since is dropped
+                None => self.take_due(gap, now).filter(|message| {
+                    let accepted = filter.accepts(message.borrow());
+                    if !accepted {
+                        self.registration.dropped(message.borrow().topic());
+                    }
+                    accepted
+                }),
+            };
+            if let Some(message) = message {
+                return Ok((filter, message));
+            }
+        }
+    }
+
+    /// Minimum time between two messages of a topic under the current filter
+    fn gap(&self) -> Duration {
+        self.filter
+            .current()
+            .map_or(Duration::ZERO, |filter| gap_of(&filter))
+    }
+
+    /// `message` when its topic may deliver at `now`, otherwise hold it as the topic's latest
+    fn offer(&mut self, message: M, gap: Duration, now: Instant) -> Option<M> {
+        let topic = message.borrow().topic();
+        let slot = self.topics.entry(topic).or_default();
+        match slot.last_sent {
+            Some(last_sent) if now < last_sent + gap => {
+                // The message held until now is replaced without being delivered
+                if slot.held.replace(message).is_some() {
+                    self.registration.dropped(topic);
+                }
+                None
+            }
+            _ => {
+                slot.last_sent = Some(now);
+                slot.held = None;
+                Some(message)
+            }
+        }
+    }
+
+    /// Earliest time a held message may be delivered
+    fn next_due(&self, gap: Duration) -> Option<Instant> {
+        self.topics
+            .values()
+            .filter(|slot| slot.held.is_some())
+            .filter_map(|slot| slot.last_sent.map(|last_sent| last_sent + gap))
+            .min()
+    }
+
+    /// The held message whose slot opened first, if it is open at `now`
+    fn take_due(&mut self, gap: Duration, now: Instant) -> Option<M> {
+        let slot = self
+            .topics
+            .values_mut()
+            .filter(|slot| slot.held.is_some())
+            .filter(|slot| {
+                slot.last_sent
+                    .is_none_or(|last_sent| last_sent + gap <= now)
+            })
+            .min_by_key(|slot| slot.last_sent)?;
+        slot.last_sent = Some(now);
+        slot.held.take()
+    }
+}
+
+/// Subscriptions stored before `MIN_MAX_RATE` was enforced may carry a lower rate, whose
+/// gap would overflow a `Duration`
+fn gap_of(filter: &SubscriptionFilter) -> Duration {
+    filter.max_rate.map_or(Duration::ZERO, |rate| {
+        Duration::try_from_secs_f64(1.0 / rate.max(MIN_MAX_RATE)).unwrap_or(Duration::ZERO)
+    })
+}
@@ -0,0 +1,325 @@
+//! Text renderings of DrivingSteps shared by the CLI tools, logs and the monitor
+//!
+//! Each renderer writes into any `fmt::Write`; `render` collects the output in a String.
+//! `DrivingStep` implements `Display` with the compact line (`{}`) and the pretty
+//! console block (`{:#}`).
+
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use crate::core::signals::SIGNALS;
+use crate::features::driving_step::DrivingStep;
+
+/// A way of turning a DrivingStep into text
+pub trait StepRenderer {
+    fn write(&self, step: &DrivingStep, out: &mut dyn Write) -> fmt::Result;
+
+    fn render(&self, step: &DrivingStep) -> String {
+        let mut out = String::new();
+        let _ = self.write(step, &mut out);
+        out
+    }
+}
+
+/// Multi-line console block with emoji section headers
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pretty;
+
+/// Single line with the main signals, suited to logs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Compact;
+
+/// The DrivingStep as JSON, the same shape the API returns
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json;
+
+/// Markdown table with one row per registered signal present in the step
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Markdown;
+
+/// The CAN frames the step is encoded into, with the purpose of each ID
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CanFrames;
+
+/// Renderer selectable by name, e.g. from a command-line flag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    #[default]
+    Pretty,
+    Compact,
+    Json,
+    Markdown,
+}
+
+impl Format {
+    pub fn renderer(self) -> &'static dyn StepRenderer {
+        match self {
+            Format::Pretty => &Pretty,
+            Format::Compact => &Compact,
+            Format::Json => &Json,
+            Format::Markdown => &Markdown,
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "pretty" => Ok(Format::Pretty),
+            "compact" => Ok(Format::Compact),
+            "json" => Ok(Format::Json),
+            "markdown" => Ok(Format::Markdown),
+            _ => Err(format!(
+                "unknown format '{}', expected pretty, compact, json or markdown",
+                name
+            )),
+        }
+    }
+}
+
+/// Gear position as shown to a driver
+pub fn gear_label(gear_position: u8) -> String {
+    match gear_position {
+        0 => "P".to_string(),
+        15 => "R".to_string(),
+        gear @ 1..=6 => gear.to_string(),
+        _ => "?".to_string(),
+    }
+}
+
+fn switch(on: bool, on_label: &'static str, off_label: &'static str) -> &'static str {
+    if on {
+        on_label
+    } else {
+        off_label
+    }
+}
+
+impl StepRenderer for Pretty {
+    fn write(&self, step: &DrivingStep, out: &mut dyn Write) -> fmt::Result {
+        writeln!(out, "\n🚗 {} 🚗", step.step_name)?;
+        writeln!(out, "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━")?;
+
+        let engine = &step.engine;
+        writeln!(out, "🔧 ENGINE:")?;
+        writeln!(out, "   • RPM: {} rpm", engine.rpm)?;
+        writeln!(out, "   • Temperature: {}°C", engine.coolant_temp)?;
+        writeln!(out, "   • Throttle: {}%", engine.throttle_pos)?;
+        writeln!(out, "   • Load: {}%", engine.engine_load)?;
+        writeln!(out, "   • Intake Temp: {}°C", engine.intake_temp)?;
+        writeln!(out, "   • Fuel Pressure: {} kPa", engine.fuel_pressure)?;
+        writeln!(
+            out,
+            "   • Running: {}",
+            switch(engine.engine_running, "✅ YES", "❌ NO")
+        )?;
+
+        let speed = &step.speed;
+        writeln!(out, "\n🏃 SPEED & TRANSMISSION:")?;
+        writeln!(out, "   • Speed: {:.1} km/h", speed.vehicle_speed)?;
+        writeln!(
+            out,
+            "   • Gear: {}",
+            match speed.gear_position {
+                0 => "P (Park)".to_string(),
+                1..=6 => format!("{}st/nd/rd/th", speed.gear_position),
+                15 => "R (Reverse)".to_string(),
+                _ => "Unknown".to_string(),
+            }
+        )?;
+        writeln!(
+            out,
+            "   • Wheel speeds: FL={:.1}, FR={:.1}, RL={:.1}, RR={:.1} km/h",
+            speed.wheel_speeds[0],
+            speed.wheel_speeds[1],
+            speed.wheel_speeds[2],
+            speed.wheel_speeds[3]
+        )?;
+        writeln!(
+            out,
+            "   • ABS: {}",
+            switch(speed.abs_active, "🔴 ACTIVE", "⚪ INACTIVE")
+        )?;
+        writeln!(
+            out,
+            "   • Traction Control: {}",
+            switch(speed.traction_control, "🔴 ON", "⚪ OFF")
+        )?;
+        writeln!(
+            out,
+            "   • Cruise Control: {}",
+            switch(speed.cruise_control, "🔴 ON", "⚪ OFF")
+        )?;
+
+        let climate = &step.climate;
+        writeln!(out, "\n🌡️ CLIMATE CONTROL:")?;
+        writeln!(out, "   • Cabin: {}°C", climate.cabin_temp)?;
+        writeln!(out, "   • Target: {}°C", climate.target_temp)?;
+        writeln!(out, "   • Outside: {}°C", climate.outside_temp)?;
+        writeln!(out, "   • Fan Speed: {}/255", climate.fan_speed)?;
+        writeln!(
+            out,
+            "   • AC: {}",
+            switch(climate.ac_compressor, "❄️ ON", "⚪ OFF")
+        )?;
+        writeln!(
+            out,
+            "   • Heater: {}",
+            switch(climate.heater, "🔥 ON", "⚪ OFF")
+        )?;
+        writeln!(
+            out,
+            "   • Defrost: {}",
+            switch(climate.defrost, "💨 ON", "⚪ OFF")
+        )?;
+        writeln!(
+            out,
+            "   • Auto Mode: {}",
+            switch(climate.auto_mode, "🤖 ON", "👤 MANUAL")
+        )?;
+
+        if let Some(adas) = &step.adas {
+            writeln!(out, "\n🛰️ DRIVER ASSISTANCE:")?;
+            writeln!(out, "   • Lead Distance: {:.1} m", adas.lead_distance)?;
+            writeln!(out, "   • Relative Speed: {:+.1} km/h", adas.relative_speed)?;
+            writeln!(
+                out,
+                "   • ACC: {} (set {} km/h)",
+                switch(adas.acc_active, "🔴 ON", "⚪ OFF"),
+                adas.acc_set_speed
+            )?;
+            writeln!(
+                out,
+                "   • Lane Keeping: {}",
+                switch(adas.lane_keep_active, "🔴 ON", "⚪ OFF")
+            )?;
+            writeln!(
+                out,
+                "   • Lane Departure Warning: {}",
+                switch(adas.lane_departure_warning, "⚠️ ACTIVE", "⚪ INACTIVE")
+            )?;
+        }
+
+        if let Some(fuel) = &step.fuel {
+            writeln!(out, "\n⛽ FUEL:")?;
+            writeln!(out, "   • Tank Level: {}%", fuel.tank_level)?;
+            writeln!(out, "   • Consumption: {:.1} L/100km", fuel.consumption)?;
+            writeln!(out, "   • Range: {} km", fuel.range_remaining)?;
+        }
+
+        if let Some(gps) = &step.gps {
+            writeln!(out, "\n📍 GPS:")?;
+            writeln!(
+                out,
+                "   • Position: {:.6}, {:.6}",
+                gps.latitude, gps.longitude
+            )?;
+        }
+
+        write!(out, "\n⏱️ Duration: {}ms", step.duration_ms)
+    }
+}
+
+impl StepRenderer for Compact {
+    fn write(&self, step: &DrivingStep, out: &mut dyn Write) -> fmt::Result {
+        write!(
+            out,
+            "{}: {:.1} km/h, gear {}, {} rpm, throttle {}%, coolant {}°C, cabin {}°C",
+            step.step_name,
+            step.speed.vehicle_speed,
+            gear_label(step.speed.gear_position),
+            step.engine.rpm,
+            step.engine.throttle_pos,
+            step.engine.coolant_temp,
+            step.climate.cabin_temp
+        )?;
+        if !step.engine.engine_running {
+            write!(out, ", engine off")?;
+        }
+        if step.speed.abs_active {
+            write!(out, ", ABS")?;
+        }
+        if let Some(adas) = &step.adas {
+            write!(out, ", lead {:.1} m", adas.lead_distance)?;
+        }
+        if let Some(fuel) = &step.fuel {
+            write!(out, ", fuel {}%", fuel.tank_level)?;
+        }
+        if let Some(gps) = &step.gps {
+            write!(out, ", at {:.5},{:.5}", gps.latitude, gps.longitude)?;
+        }
+        write!(out, " ({} ms)", step.duration_ms)
+    }
+}
+
+impl StepRenderer for Json {
+    fn write(&self, step: &DrivingStep, out: &mut dyn Write) -> fmt::Result {
+        let json = serde_json::to_string(step).map_err(|_| fmt::Error)?;
+        out.write_str(&json)
+    }
+}
+
+impl StepRenderer for Markdown {
+    fn write(&self, step: &DrivingStep, out: &mut dyn Write) -> fmt::Result {
+        let value = serde_json::to_value(step).map_err(|_| fmt::Error)?;
+
+        writeln!(out, "### {}", step.step_name)?;
+        writeln!(out)?;
+        writeln!(out, "| Signal | Value | Unit |")?;
+        write!(out, "| --- | ---: | --- |")?;
+        for signal in SIGNALS {
+            // Signals of absent optional groups are left out
+            let Some(field) = value.pointer(&signal.pointer()) else {
+                continue;
+            };
+            let text = match field {
+                serde_json::Value::Array(items) => items
+                    .iter()
+                    .map(|item| item.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                other => other.to_string(),
+            };
+            write!(
+                out,
+                "\n| {} | {} | {} |",
+                signal.name,
+                text,
+                signal.unit.symbol()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl StepRenderer for CanFrames {
+    fn write(&self, step: &DrivingStep, out: &mut dyn Write) -> fmt::Result {
+        let can_messages = step.to_can_messages();
+
+        writeln!(out, "\n📡 CAN MESSAGES ({} total):", can_messages.len())?;
+        writeln!(out, "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━")?;
+        for (i, msg) in can_messages.iter().enumerate() {
+            writeln!(out, "🔌 CAN Message {}:", i + 1)?;
+            writeln!(out, "   • ID: 0x{:03X}", msg.id)?;
+            writeln!(out, "   • DLC: {}", msg.dlc)?;
+            writeln!(out, "   • Data: {:02X?}", msg.data())?;
+            writeln!(out, "   • Purpose: {}", DrivingStep::can_id_purpose(msg.id))?;
+            if i < can_messages.len() - 1 {
+                writeln!(out, "   ├─────────────────────────────────────────")?;
+            }
+        }
+        write!(out, "   └─────────────────────────────────────────")
+    }
+}
+
+impl fmt::Display for DrivingStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            Pretty.write(self, f)
+        } else {
+            Compact.write(self, f)
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use std::borrow::Borrow;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::broadcast;
+
+use crate::core::bus::{Bus, BusMessage};
+
+/// Numbers of evicted driving steps remembered, so SSE clients further behind than the
+/// journal get their steps back from SQLite
+const MAX_EVICTED_STEPS: usize = 100_000;
+
+/// A bus message numbered in the order the journal received it
+#[derive(Debug, Clone)]
+pub struct Sequenced {
+    pub seq: u64,
+    pub message: BusMessage,
+}
+
+impl Borrow<BusMessage> for Sequenced {
+    fn borrow(&self) -> &BusMessage {
+        &self.message
+    }
+}
+
+#[derive(Default)]
+struct JournalState {
+    /// Number of the latest recorded message, 0 before the first
+    latest: u64,
+    /// Number of the latest recorded message of each topic
+    latest_by_topic: HashMap<&'static str, u64>,
+    entries: VecDeque<Sequenced>,
+    /// Numbers of the driving steps evicted from `entries`, oldest first
+    evicted_steps: VecDeque<u64>,
+}
+
+/// The latest bus messages, numbered so websocket clients can resume where they left off
+///
+/// Numbered messages are re-broadcast to the clients following the journal, so the
+/// sequence numbers they acknowledge match the recorded ones.
+#[derive(Clone)]
+pub struct Journal {
+    capacity: usize,
+    state: Arc<Mutex<JournalState>>,
+    tx: broadcast::Sender<Sequenced>,
+}
+
+impl Journal {
+    /// Keep the last `capacity` messages, buffering `channel_capacity` for slow followers
+    pub fn new(capacity: usize, channel_capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(channel_capacity);
+        Journal {
+            capacity,
+            state: Arc::new(Mutex::new(JournalState::default())),
+            tx,
+        }
+    }
+
+    /// Numbered messages recorded from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<Sequenced> {
+        self.tx.subscribe()
+    }
+
+    /// Number of the latest recorded message, 0 before the first
+    pub fn latest(&self) -> u64 {
+        self.state.lock().unwrap().latest
+    }
+
+    /// Number of the latest recorded message of each topic recorded so far
+    pub fn latest_by_topic(&self) -> HashMap<&'static str, u64> {
+        self.state.lock().unwrap().latest_by_topic.clone()
+    }
+
+    /// Recorded messages numbered after `seq`, with the count of those already evicted
+    pub fn since(&self, seq: u64) -> (Vec<Sequenced>, u64) {
+        let state = self.state.lock().unwrap();
+        let entries: Vec<Sequenced> = state
+            .entries
+            .iter()
+            .filter(|entry| entry.seq > seq)
+            .cloned()
+            .collect();
+        let first = entries.first().map_or(state.latest + 1, |entry| entry.seq);
+        (entries, first.saturating_sub(seq + 1))
+    }
+
+    /// Numbers of the driving steps recorded after `seq` and already evicted, oldest first
+    pub fn evicted_steps_since(&self, seq: u64) -> Vec<u64> {
+        let state = self.state.lock().unwrap();
+        state
+            .evicted_steps
+            .iter()
+            .copied()
+            .filter(|evicted| *evicted > seq)
+            .collect()
+    }
+
+    fn record(&self, message: BusMessage) {
+        let entry = {
+            let mut state = self.state.lock().unwrap();
+            state.latest += 1;
+            let seq = state.latest;
+            state.latest_by_topic.insert(message.topic(), seq);
+            let entry = Sequenced {
+                seq: state.latest,
+                message,
+            };
+            state.entries.push_back(entry.clone());
+            while state.entries.len() > self.capacity {
+                let Some(evicted) = state.entries.pop_front() else {
+                    break;
+                };
+                if let BusMessage::DrivingStep(_) = evicted.message {
+                    state.evicted_steps.push_back(evicted.seq);
+                    if state.evicted_steps.len() > MAX_EVICTED_STEPS {
+                        state.evicted_steps.pop_front();
+                    }
+                }
+            }
+            entry
+        };
+        // No follower connected is not an error
+        let _ = self.tx.send(entry);
+    }
+
+    /// Record every bus message
+    pub fn spawn(&self, bus: &Bus) {
+        let journal = self.clone();
+        let mut rx = bus.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(message) => journal.record(message),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(_) => break,
+                }
+            }
+        });
+    }
+}
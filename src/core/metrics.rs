@@ -0,0 +1,418 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+
+use actix_web::web::Data;
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+
+use crate::config::settings::AppSettings;
+use crate::core::bus::BusMessage;
+
+/// Upper bounds (seconds) of the latency histogram buckets
+const BUCKETS: [f64; 13] = [
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+/// Name of the debug field stream clients opt into with `?latency=true`
+pub const LATENCY_FIELD: &str = "X-Pipeline-Latency";
+
+/// Current time in microseconds since the Unix epoch, the unit of every pipeline timestamp
+pub fn now_us() -> i64 {
+    chrono::Utc::now().timestamp_micros()
+}
+
+/// Timestamps a step collects on its way from a writer to the broadcast bus
+///
+/// `ingested_at_us` and `stored_at_us` travel in the step notice; they are missing for
+/// notices published by writers that predate them.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PipelineTiming {
+    pub ingested_at_us: Option<i64>,
+    pub stored_at_us: Option<i64>,
+    /// The consumer took the notice off the queue
+    pub received_at_us: i64,
+    /// The reconstructed step was sent on the bus
+    pub broadcast_at_us: i64,
+}
+
+/// Per-stage latencies of one step, as attached to the `X-Pipeline-Latency` field
+#[derive(Debug, Clone, Serialize)]
+struct LatencyReport {
+    ingest_to_store_ms: Option<f64>,
+    store_to_amqp_ms: Option<f64>,
+    amqp_to_broadcast_ms: f64,
+    broadcast_to_client_ms: f64,
+    /// From ingestion (or queue receipt when unknown) to delivery
+    total_ms: f64,
+    delivered_at_us: i64,
+}
+
+fn ms(from_us: i64, to_us: i64) -> f64 {
+    (to_us - from_us).max(0) as f64 / 1000.0
+}
+
+/// Pipeline stages measured between ingestion and delivery to a stream client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Encoding and the SQLite transaction
+    IngestToStore,
+    /// Publishing the notice until the consumer receives it
+    StoreToAmqp,
+    /// Reconstruction from the stored frames until the bus send
+    AmqpToBroadcast,
+    /// Bus send until a WebSocket or SSE client is written to
+    BroadcastToClient,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [
+        Stage::IngestToStore,
+        Stage::StoreToAmqp,
+        Stage::AmqpToBroadcast,
+        Stage::BroadcastToClient,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Stage::IngestToStore => "ingest_to_store",
+            Stage::StoreToAmqp => "store_to_amqp",
+            Stage::AmqpToBroadcast => "amqp_to_broadcast",
+            Stage::BroadcastToClient => "broadcast_to_client",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    buckets: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+/// Steps measured by one pipeline stage, with their mean latency
+#[derive(Debug, Clone, Serialize)]
+pub struct StageStats {
+    pub stage: &'static str,
+    pub count: u64,
+    /// `None` until the stage measured a step
+    pub mean_ms: Option<f64>,
+}
+
+/// Latency histograms of every pipeline stage, shared by the consumer and stream handlers
+#[derive(Debug, Default)]
+pub struct PipelineMetrics {
+    stages: Mutex<[Histogram; Stage::ALL.len()]>,
+}
+
+impl PipelineMetrics {
+    pub fn observe(&self, stage: Stage, from_us: i64, to_us: i64) {
+        let seconds = (to_us - from_us).max(0) as f64 / 1_000_000.0;
+        self.stages.lock().unwrap()[stage as usize].observe(seconds);
+    }
+
+    /// Record the stages a step went through up to its broadcast
+    pub fn observe_broadcast(&self, timing: &PipelineTiming) {
+        if let (Some(ingested), Some(stored)) = (timing.ingested_at_us, timing.stored_at_us) {
+            self.observe(Stage::IngestToStore, ingested, stored);
+        }
+        if let Some(stored) = timing.stored_at_us {
+            self.observe(Stage::StoreToAmqp, stored, timing.received_at_us);
+        }
+        self.observe(
+            Stage::AmqpToBroadcast,
+            timing.received_at_us,
+            timing.broadcast_at_us,
+        );
+    }
+
+    /// Count and mean latency of every stage, in pipeline order
+    pub fn stats(&self) -> Vec<StageStats> {
+        let stages = self.stages.lock().unwrap();
+        Stage::ALL
+            .iter()
+            .zip(stages.iter())
+            .map(|(stage, histogram)| StageStats {
+                stage: stage.as_str(),
+                count: histogram.count,
+                mean_ms: (histogram.count > 0)
+                    .then(|| histogram.sum / histogram.count as f64 * 1000.0),
+            })
+            .collect()
+    }
+
+    /// Prometheus text exposition of the histograms
+    pub fn render(&self) -> String {
+        let stages = self.stages.lock().unwrap().clone();
+        let mut out = String::new();
+        let name = "pipeline_stage_latency_seconds";
+
+        let _ = writeln!(out, "# HELP {} Latency of each step pipeline stage", name);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        for (stage, histogram) in Stage::ALL.iter().zip(stages.iter()) {
+            render_histogram(&mut out, name, ("stage", stage.as_str()), histogram);
+        }
+        out
+    }
+}
+
+/// Durations of the labelled SQL queries, see `config::db::labelled`
+#[derive(Debug, Default)]
+pub struct QueryMetrics {
+    queries: Mutex<BTreeMap<&'static str, Histogram>>,
+}
+
+impl QueryMetrics {
+    pub fn observe(&self, label: &'static str, seconds: f64) {
+        self.queries
+            .lock()
+            .unwrap()
+            .entry(label)
+            .or_default()
+            .observe(seconds);
+    }
+
+    /// Prometheus text exposition of the histograms, empty before the first query
+    pub fn render(&self) -> String {
+        let queries = self.queries.lock().unwrap().clone();
+        let mut out = String::new();
+        if queries.is_empty() {
+            return out;
+        }
+        let name = "sql_query_duration_seconds";
+
+        let _ = writeln!(out, "# HELP {} Duration of each labelled SQL query", name);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        for (label, histogram) in &queries {
+            render_histogram(&mut out, name, ("query", label), histogram);
+        }
+        out
+    }
+}
+
+/// Depth of the step-notice queue, as last sampled
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct QueueBacklog {
+    /// Notices ready in the queue, not yet delivered to a consumer
+    pub messages: u32,
+    pub consumers: u32,
+    pub sampled_at_us: i64,
+}
+
+/// Step-notice consumer counters, as served by `/admin/rabbitmq`
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsumerStats {
+    pub acked: u64,
+    /// Deliveries taken off the queue and not acknowledged yet, held ones included
+    pub unacked: u64,
+    /// `None` until a delivery was processed
+    pub mean_processing_ms: Option<f64>,
+    /// `None` until the queue was sampled
+    pub backlog: Option<QueueBacklog>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ConsumerState {
+    processing: Histogram,
+    acked: u64,
+    unacked: u64,
+    backlog: Option<QueueBacklog>,
+}
+
+/// Deliveries of the step-notice consumer and the depth of its queue
+#[derive(Debug, Default)]
+pub struct ConsumerMetrics {
+    state: Mutex<ConsumerState>,
+}
+
+impl ConsumerMetrics {
+    /// The consumer took a delivery off the queue
+    pub fn received(&self) {
+        self.state.lock().unwrap().unacked += 1;
+    }
+
+    /// The consumer acknowledged a delivery it started processing at `started_us`
+    pub fn acked(&self, started_us: i64) {
+        let seconds = (now_us() - started_us).max(0) as f64 / 1_000_000.0;
+        let mut state = self.state.lock().unwrap();
+        state.unacked = state.unacked.saturating_sub(1);
+        state.acked += 1;
+        state.processing.observe(seconds);
+    }
+
+    pub fn set_backlog(&self, messages: u32, consumers: u32) {
+        self.state.lock().unwrap().backlog = Some(QueueBacklog {
+            messages,
+            consumers,
+            sampled_at_us: now_us(),
+        });
+    }
+
+    pub fn stats(&self) -> ConsumerStats {
+        let state = self.state.lock().unwrap();
+        let processing = &state.processing;
+        ConsumerStats {
+            acked: state.acked,
+            unacked: state.unacked,
+            mean_processing_ms: (processing.count > 0)
+                .then(|| processing.sum / processing.count as f64 * 1000.0),
+            backlog: state.backlog,
+        }
+    }
+
+    /// Prometheus text exposition of the processing histogram and the gauges of `queue`
+    pub fn render(&self, queue: &str) -> String {
+        let state = self.state.lock().unwrap().clone();
+        let mut out = String::new();
+        let name = "step_consumer_processing_seconds";
+
+        let _ = writeln!(
+            out,
+            "# HELP {} Time from processing a step notice to its ack",
+            name
+        );
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        render_histogram(&mut out, name, ("queue", queue), &state.processing);
+
+        let mut gauges = vec![(
+            "step_consumer_unacked_deliveries",
+            "Step notices taken off the queue and not acknowledged yet",
+            state.unacked,
+        )];
+        if let Some(backlog) = state.backlog {
+            gauges.push((
+                "step_queue_backlog_messages",
+                "Step notices waiting in the queue, as last sampled",
+                backlog.messages as u64,
+            ));
+            gauges.push((
+                "step_queue_consumers",
+                "Consumers of the step-notice queue, as last sampled",
+                backlog.consumers as u64,
+            ));
+        }
+        for (name, help, value) in gauges {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} gauge", name);
+            let _ = writeln!(out, "{}{{queue=\"{}\"}} {}", name, queue, value);
+        }
+        out
+    }
+}
+
+/// Buckets, sum and count of one histogram, labelled `label`
+fn render_histogram(out: &mut String, name: &str, label: (&str, &str), histogram: &Histogram) {
+    let (key, value) = label;
+    for (bound, count) in BUCKETS.iter().zip(histogram.buckets) {
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{}=\"{}\",le=\"{}\"}} {}",
+            name, key, value, bound, count
+        );
+    }
+    let _ = writeln!(
+        out,
+        "{}_bucket{{{}=\"{}\",le=\"+Inf\"}} {}",
+        name, key, value, histogram.count
+    );
+    let _ = writeln!(
+        out,
+        "{}_sum{{{}=\"{}\"}} {}",
+        name, key, value, histogram.sum
+    );
+    let _ = writeln!(
+        out,
+        "{}_count{{{}=\"{}\"}} {}",
+        name, key, value, histogram.count
+    );
+}
+
+/// Process-wide pipeline metrics
+pub fn pipeline() -> &'static PipelineMetrics {
+    static METRICS: OnceLock<PipelineMetrics> = OnceLock::new();
+    METRICS.get_or_init(PipelineMetrics::default)
+}
+
+/// Process-wide step-notice consumer metrics
+pub fn consumer() -> &'static ConsumerMetrics {
+    static METRICS: OnceLock<ConsumerMetrics> = OnceLock::new();
+    METRICS.get_or_init(ConsumerMetrics::default)
+}
+
+/// Process-wide SQL query metrics
+pub fn queries() -> &'static QueryMetrics {
+    static METRICS: OnceLock<QueryMetrics> = OnceLock::new();
+    METRICS.get_or_init(QueryMetrics::default)
+}
+
+/// Serialize a bus message for a stream client, recording its broadcast → client latency
+///
+/// With `attach_latency`, driving steps carry the per-stage breakdown in `X-Pipeline-Latency`.
+pub fn client_json(message: &BusMessage, attach_latency: bool) -> serde_json::Result<String> {
+    let timing = match message {
+        BusMessage::DrivingStep(step) => step.pipeline,
+        _ => None,
+    };
+    let Some(timing) = timing else {
+        return serde_json::to_string(message);
+    };
+
+    let delivered_at_us = now_us();
+    pipeline().observe(
+        Stage::BroadcastToClient,
+        timing.broadcast_at_us,
+        delivered_at_us,
+    );
+    if !attach_latency {
+        return serde_json::to_string(message);
+    }
+
+    let report = LatencyReport {
+        ingest_to_store_ms: timing
+            .ingested_at_us
+            .zip(timing.stored_at_us)
+            .map(|(ingested, stored)| ms(ingested, stored)),
+        store_to_amqp_ms: timing
+            .stored_at_us
+            .map(|stored| ms(stored, timing.received_at_us)),
+        amqp_to_broadcast_ms: ms(timing.received_at_us, timing.broadcast_at_us),
+        broadcast_to_client_ms: ms(timing.broadcast_at_us, delivered_at_us),
+        total_ms: ms(
+            timing.ingested_at_us.unwrap_or(timing.received_at_us),
+            delivered_at_us,
+        ),
+        delivered_at_us,
+    };
+    let mut value = serde_json::to_value(message)?;
+    if let Some(fields) = value.as_object_mut() {
+        fields.insert(LATENCY_FIELD.to_string(), serde_json::to_value(report)?);
+    }
+    serde_json::to_string(&value)
+}
+
+#[get("/metrics")]
+async fn metrics(settings: Data<AppSettings>) -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(
+            pipeline().render()
+                + &consumer().render(&settings.rabbitmq.queue)
+                + &queries().render(),
+        )
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(metrics);
+}
@@ -1,3 +1,24 @@
+pub mod bus;
 pub mod can;
+pub mod can_map;
+pub mod changes;
+pub mod clock;
+pub mod compression;
+pub mod connections;
+pub mod dbc;
+pub mod feed;
+pub mod format;
+pub mod journal;
+pub mod metrics;
+pub mod playback;
+pub mod scrub;
+pub mod selection;
+pub mod session;
+pub mod signals;
+#[cfg(all(feature = "socketcan", target_os = "linux"))]
+pub mod socketcan;
 pub mod stream;
+pub mod timestamps;
+pub mod topics;
+pub mod units;
 pub mod websocket;
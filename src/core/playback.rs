@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use crate::features::driving_step::DrivingStep;
+
+/// Fastest playback rate accepted by `play`
+pub const MAX_SPEED: f64 = 100.0;
+/// Longest wait between two played steps at speed 1, so gaps between sessions do not stall
+pub const MAX_GAP: Duration = Duration::from_secs(10);
+
+/// Playback command sent as a WebSocket text message, tagged by its `action` field
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PlaybackCommand {
+    /// Start playing stored steps from `from` (RFC3339), or resume when omitted
+    Play {
+        from: Option<String>,
+        /// Stop after the last step stored before this instant
+        to: Option<String>,
+        speed: Option<f64>,
+    },
+    Pause,
+    /// Move to the first step stored at or after `to`, keeping the play/pause state
+    Seek {
+        to: String,
+    },
+    /// Drop the playback, live messages keep flowing
+    Stop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaybackState {
+    Playing,
+    Paused,
+    Finished,
+    Stopped,
+}
+
+/// Progress report sent to the client after every command and at the end of the playback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaybackStatus {
+    /// Always `playback`, next to the `type` of the other stream messages
+    #[serde(rename = "type", skip_deserializing, default = "PlaybackStatus::kind")]
+    pub kind: &'static str,
+    pub state: PlaybackState,
+    /// Timestamp of the next step to play
+    pub position: Option<String>,
+    /// Index of the next step to play among `total`
+    pub index: usize,
+    pub total: usize,
+    pub speed: f64,
+}
+
+impl PlaybackStatus {
+    fn kind() -> &'static str {
+        "playback"
+    }
+}
+
+/// Parse a command bound, rejecting anything but RFC3339
+pub fn parse_instant(value: &str) -> Result<DateTime<FixedOffset>, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map_err(|e| format!("Invalid timestamp '{}' (expected RFC3339): {}", value, e))
+}
+
+pub fn validate_speed(speed: f64) -> Result<f64, String> {
+    if speed > 0.0 && speed <= MAX_SPEED {
+        Ok(speed)
+    } else {
+        Err(format!(
+            "Playback speed must be above 0 and at most {}",
+            MAX_SPEED
+        ))
+    }
+}
+
+/// Instant a step was stored at, read from the timestamp of its first frame
+fn step_instant(step: &DrivingStep) -> Option<DateTime<FixedOffset>> {
+    step.frames
+        .first()
+        .and_then(|frame| DateTime::parse_from_rfc3339(&frame.timestamp).ok())
+}
+
+/// Stored steps of one client's playback, with the position of the next step to play
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    steps: Vec<(Option<DateTime<FixedOffset>>, DrivingStep)>,
+    position: usize,
+    pub speed: f64,
+}
+
+impl Timeline {
+    /// Steps in storage order, cut at `to` when given
+    pub fn new(steps: Vec<DrivingStep>, to: Option<DateTime<FixedOffset>>, speed: f64) -> Self {
+        let steps = steps
+            .into_iter()
+            .map(|step| (step_instant(&step), step))
+            .filter(|(instant, _)| match (instant, to) {
+                (Some(instant), Some(to)) => *instant < to,
+                _ => true,
+            })
+            .collect();
+        Timeline {
+            steps,
+            position: 0,
+            speed,
+        }
+    }
+
+    /// Move to the first step stored at or after `instant`
+    pub fn seek(&mut self, instant: DateTime<FixedOffset>) {
+        self.position = self
+            .steps
+            .iter()
+            .position(|(stored, _)| stored.is_some_and(|stored| stored >= instant))
+            .unwrap_or(self.steps.len());
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.steps.len()
+    }
+
+    /// Take the next step and the wait before the one after it
+    pub fn advance(&mut self) -> Option<(DrivingStep, Duration)> {
+        let (instant, step) = self.steps.get(self.position)?.clone();
+        self.position += 1;
+
+        let gap = match (instant, self.steps.get(self.position)) {
+            (Some(instant), Some((Some(next), _))) => {
+                (*next - instant).to_std().unwrap_or_default()
+            }
+            _ => Duration::from_millis(step.duration_ms),
+        };
+        Some((step, gap.min(MAX_GAP).div_f64(self.speed)))
+    }
+
+    pub fn status(&self, state: PlaybackState) -> PlaybackStatus {
+        PlaybackStatus {
+            kind: "playback",
+            state,
+            position: self
+                .steps
+                .get(self.position)
+                .and_then(|(instant, _)| instant.map(|instant| instant.to_rfc3339())),
+            index: self.position,
+            total: self.steps.len(),
+            speed: self.speed,
+        }
+    }
+}
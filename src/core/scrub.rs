@@ -0,0 +1,185 @@
+use std::future::{ready, Ready};
+
+use actix_web::dev::Payload;
+use actix_web::web::Data;
+use actix_web::{FromRequest, HttpMessage, HttpRequest};
+use serde_json::Value;
+
+use crate::common::error::AppError;
+use crate::core::bus::BusMessage;
+use crate::core::can::CanMessage;
+use crate::core::can_map::FrameGroup;
+use crate::features::driving_step::DrivingStep;
+use crate::features::tenant::{Role, TenantScope};
+use crate::features::vehicle::VehicleState;
+
+/// Characters of a VIN left visible at its end by `mask_vin`
+const VIN_VISIBLE: usize = 4;
+
+/// Keys of the event payloads holding a coordinate, as the last segment of a dotted path
+const COORDINATE_KEYS: [&str; 5] = ["latitude", "longitude", "lat", "lon", "lng"];
+
+/// Keys of the event payloads holding a VIN
+const VIN_KEYS: [&str; 2] = ["vehicle_id", "vin"];
+
+/// What the stream clients of viewer keys see of the bus messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScrubRules {
+    /// Decimal places kept of GPS coordinates (2 is about a kilometre), all when `None`
+    pub gps_decimals: Option<u32>,
+    /// Replace all but the last characters of VINs with `*`
+    pub mask_vin: bool,
+}
+
+impl Default for ScrubRules {
+    fn default() -> Self {
+        ScrubRules {
+            gps_decimals: Some(2),
+            mask_vin: true,
+        }
+    }
+}
+
+impl ScrubRules {
+    /// Copy of `message` with its coordinates coarsened and its VINs masked
+    pub fn apply(&self, message: &BusMessage) -> BusMessage {
+        let mut message = message.clone();
+        match &mut message {
+            BusMessage::DrivingStep(step) => self.scrub_step(step),
+            BusMessage::Event(event) => self.scrub_json(None, &mut event.payload),
+            BusMessage::Geofence(event) => {
+                event.latitude = self.coordinate(event.latitude);
+                event.longitude = self.coordinate(event.longitude);
+            }
+            BusMessage::Anomaly(anomaly) => {
+                anomaly.value = self.signal(&anomaly.signal, anomaly.value);
+                anomaly.mean = self.signal(&anomaly.signal, anomaly.mean);
+            }
+            BusMessage::System(_) => {}
+        }
+        message
+    }
+
+    /// Coarsen the position of a step, in its fields and in its GPS frame
+    pub fn scrub_step(&self, step: &mut DrivingStep) {
+        let Some(gps) = step.gps.as_mut() else { return };
+        gps.latitude = self.coordinate(gps.latitude);
+        gps.longitude = self.coordinate(gps.longitude);
+
+        // The frame is re-encoded in the byte order it was stored with
+        let gps_id = FrameGroup::Gps.id();
+        let Some(index) = step.frames.iter().position(|frame| frame.id == gps_id) else {
+            return;
+        };
+        let is_big_endian = DrivingStep::detect_endianness(&step.frames)
+            .map_or_else(DrivingStep::get_endianness_from_env, |endian| {
+                endian.is_big_endian()
+            });
+        let stored = &step.frames[index];
+        let scrubbed = step
+            .to_can_messages_with_endian(is_big_endian)
+            .into_iter()
+            .find(|frame| frame.id == gps_id)
+            .and_then(|frame| {
+                CanMessage::try_new(gps_id, frame.data(), stored.timestamp.clone()).ok()
+            });
+        match scrubbed {
+            Some(mut frame) => {
+                frame.seq = stored.seq;
+                step.frames[index] = frame;
+            }
+            None => {
+                step.frames.remove(index);
+            }
+        }
+    }
+
+    /// Coarsen the position of a vehicle state and mask its VIN
+    pub fn scrub_state(&self, state: &mut VehicleState) {
+        state.vehicle_id = self.vin(&state.vehicle_id);
+        for (name, signal) in state.signals.iter_mut() {
+            signal.value = self.signal(name, signal.value);
+        }
+    }
+
+    /// Value of the signal `name`, coarsened for the GPS signals
+    pub fn signal(&self, name: &str, value: f64) -> f64 {
+        if COORDINATE_KEYS.contains(&name) {
+            self.coordinate(value)
+        } else {
+            value
+        }
+    }
+
+    fn coordinate(&self, value: f64) -> f64 {
+        match self.gps_decimals {
+            Some(decimals) => {
+                let factor = 10f64.powi(decimals.min(7) as i32);
+                (value * factor).round() / factor
+            }
+            None => value,
+        }
+    }
+
+    fn vin(&self, vin: &str) -> String {
+        if !self.mask_vin {
+            return vin.to_string();
+        }
+        let hidden = vin.chars().count().saturating_sub(VIN_VISIBLE);
+        vin.chars()
+            .enumerate()
+            .map(|(index, c)| if index < hidden { '*' } else { c })
+            .collect()
+    }
+
+    /// Scrub the values of an event payload by the key they are found under
+    fn scrub_json(&self, key: Option<&str>, value: &mut Value) {
+        let field = key.map(|key| key.rsplit('.').next().unwrap_or(key));
+        match value {
+            Value::Object(fields) => {
+                for (key, value) in fields.iter_mut() {
+                    self.scrub_json(Some(key), value);
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.scrub_json(key, item);
+                }
+            }
+            Value::Number(number) if field.is_some_and(|f| COORDINATE_KEYS.contains(&f)) => {
+                if let Some(coarse) = number
+                    .as_f64()
+                    .and_then(|n| serde_json::Number::from_f64(self.coordinate(n)))
+                {
+                    *number = coarse;
+                }
+            }
+            Value::String(text) if field.is_some_and(|f| VIN_KEYS.contains(&f)) => {
+                *text = self.vin(text);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Scrub rules of a request: those of the app's `Data<ScrubRules>` (defaults without one)
+/// for viewer keys, `None` for the others
+pub struct Scrub(pub Option<ScrubRules>);
+
+impl FromRequest for Scrub {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let viewer = req
+            .extensions()
+            .get::<TenantScope>()
+            .is_some_and(|scope| scope.role() == Role::Viewer);
+        let rules = viewer.then(|| {
+            req.app_data::<Data<ScrubRules>>()
+                .map(|rules| *rules.get_ref())
+                .unwrap_or_default()
+        });
+        ready(Ok(Scrub(rules)))
+    }
+}
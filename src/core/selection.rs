@@ -0,0 +1,120 @@
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::bus::{BusMessage, SubscriptionFilter};
+use crate::core::can::CanIdRange;
+
+/// Topics and CAN IDs a websocket client asked for, sent as
+/// `{"subscribe": ["driving_step", "event"], "can_ids": ["0x100-0x1FF"]}` or
+/// `{"unsubscribe": ["event"]}`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SelectionRequest {
+    /// Topics to receive from now on, replacing the current ones
+    pub subscribe: Option<Vec<String>>,
+    /// Topics to stop receiving
+    #[serde(default)]
+    pub unsubscribe: Vec<String>,
+    /// CAN ID ranges replacing the current ones, every CAN ID when empty
+    pub can_ids: Option<Vec<CanIdRange>>,
+}
+
+/// Answer to a `subscribe` or `unsubscribe` message, with what the client now receives
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectionChanged {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub topics: Vec<String>,
+    pub can_ids: Vec<CanIdRange>,
+}
+
+#[derive(Debug, Default)]
+struct Chosen {
+    /// `None` until the client picks topics, leaving those of its filter
+    topics: Option<Vec<String>>,
+    can_ids: Vec<CanIdRange>,
+}
+
+/// Topics and CAN IDs chosen by one websocket client, applied over its filter
+///
+/// Chosen topics replace those of the filter, query parameters and stored subscription
+/// alike; the other filter settings still apply. Clones share the choice, so the feed of
+/// the connection sees changes made by its actor.
+#[derive(Debug, Clone, Default)]
+pub struct Selection(Arc<RwLock<Chosen>>);
+
+impl Selection {
+    /// Selection starting from the topics and CAN IDs a resumed session had chosen
+    pub fn from_filter(filter: &SubscriptionFilter) -> Self {
+        Selection(Arc::new(RwLock::new(Chosen {
+            topics: (!filter.topics.is_empty()).then(|| filter.topics.clone()),
+            can_ids: filter.can_ids.clone(),
+        })))
+    }
+
+    /// `filter` narrowed to the chosen topics and CAN IDs
+    pub fn narrow(&self, mut filter: SubscriptionFilter) -> SubscriptionFilter {
+        let chosen = self.0.read().unwrap();
+        if let Some(topics) = &chosen.topics {
+            filter.topics = topics.clone();
+        }
+        if !chosen.can_ids.is_empty() {
+            filter.can_ids = chosen.can_ids.clone();
+        }
+        filter
+    }
+
+    /// Apply a request on top of `filter`, the filter the client had before choosing
+    ///
+    /// Unknown topics, and requests that would leave no topic at all, are refused and
+    /// change nothing.
+    pub fn apply(
+        &self,
+        request: SelectionRequest,
+        filter: &SubscriptionFilter,
+    ) -> Result<SelectionChanged, String> {
+        let unknown: Vec<&str> = request
+            .subscribe
+            .iter()
+            .flatten()
+            .chain(&request.unsubscribe)
+            .map(String::as_str)
+            .filter(|topic| !BusMessage::TOPICS.contains(topic))
+            .collect();
+        if !unknown.is_empty() {
+            return Err(format!(
+                "Unknown topic(s) {}, expected {}",
+                unknown.join(", "),
+                BusMessage::TOPICS.join(", ")
+            ));
+        }
+
+        let mut chosen = self.0.write().unwrap();
+        let mut topics = match (request.subscribe, &chosen.topics) {
+            (Some(topics), _) => topics,
+            (None, Some(topics)) => topics.clone(),
+            (None, None) if !filter.topics.is_empty() => filter.topics.clone(),
+            (None, None) => BusMessage::TOPICS.iter().map(|t| t.to_string()).collect(),
+        };
+        topics.retain(|topic| !request.unsubscribe.contains(topic));
+        // In the order of `BusMessage::TOPICS`, without repeats
+        let topics: Vec<String> = BusMessage::TOPICS
+            .iter()
+            .filter(|topic| topics.iter().any(|chosen| chosen == *topic))
+            .map(|topic| topic.to_string())
+            .collect();
+        if topics.is_empty() {
+            return Err("At least one topic must stay subscribed".to_string());
+        }
+
+        chosen.topics = Some(topics.clone());
+        if let Some(can_ids) = request.can_ids {
+            chosen.can_ids = can_ids;
+        }
+        Ok(SelectionChanged {
+            kind: "subscribed",
+            topics,
+            can_ids: chosen.can_ids.clone(),
+        })
+    }
+}
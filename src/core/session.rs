@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::error::AppError;
+use crate::core::bus::SubscriptionFilter;
+use crate::core::can::CanIdRange;
+
+/// Query parameters of `/ws` reconnecting to a session
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ResumeQuery {
+    /// Resumption token sent in the `session` message of an earlier connection
+    pub resume: Option<String>,
+}
+
+/// Acknowledgement of the messages received up to `seq`, sent as `{"action":"ack","seq":42}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Ack {
+    pub seq: u64,
+}
+
+/// First message of every websocket connection
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionOpened {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    /// Token to pass as `?resume=` when reconnecting
+    pub token: String,
+    /// Whether the connection took over an earlier session
+    pub resumed: bool,
+    /// Latest message acknowledged by the client; later journal messages are replayed
+    pub acked: u64,
+    /// Messages after `acked` already evicted from the journal, lost to the client
+    pub missed: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Session {
+    /// Query parameters the session was opened with, tenant included
+    filter: SubscriptionFilter,
+    acked: u64,
+    /// Connection currently holding the session
+    connection: u64,
+    /// Set when that connection went away
+    disconnected_at: Option<Instant>,
+}
+
+/// Websocket sessions, kept for `window` after their client disconnects
+#[derive(Clone)]
+pub struct SessionStore {
+    window: Duration,
+    sessions: Arc<Mutex<HashMap<String, Session>>>,
+    connections: Arc<Mutex<u64>>,
+}
+
+impl SessionStore {
+    pub fn new(window: Duration) -> Self {
+        SessionStore {
+            window,
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            connections: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    /// Query parameters and acknowledged position of the session of `token`, for a client
+    /// connecting with the API key of `tenant`
+    pub fn get(
+        &self,
+        token: &str,
+        tenant: Option<&str>,
+    ) -> Result<(SubscriptionFilter, u64), AppError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        self.purge(&mut sessions);
+        sessions
+            .get(token)
+            // Tokens of other tenants are reported as unknown
+            .filter(|session| session.filter.tenant.as_deref() == tenant)
+            .map(|session| (session.filter.clone(), session.acked))
+            .ok_or_else(|| AppError::not_found(format!("Session '{}'", token)))
+    }
+
+    /// Attach a new connection to the session of `token`, or to a new session when `None`,
+    /// returning the token and the connection number
+    pub fn open(
+        &self,
+        token: Option<String>,
+        filter: SubscriptionFilter,
+        acked: u64,
+    ) -> (String, u64) {
+        let connection = {
+            let mut connections = self.connections.lock().unwrap();
+            *connections += 1;
+            *connections
+        };
+        let token = token.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let mut sessions = self.sessions.lock().unwrap();
+        self.purge(&mut sessions);
+        sessions.insert(
+            token.clone(),
+            Session {
+                filter,
+                acked,
+                connection,
+                disconnected_at: None,
+            },
+        );
+        (token, connection)
+    }
+
+    /// Record that the client of `token` received every message up to `seq`
+    pub fn ack(&self, token: &str, seq: u64) {
+        if let Some(session) = self.sessions.lock().unwrap().get_mut(token) {
+            session.acked = session.acked.max(seq);
+        }
+    }
+
+    /// Keep the topics and CAN IDs the client of `token` chose for when it resumes
+    pub fn select(&self, token: &str, topics: Vec<String>, can_ids: Vec<CanIdRange>) {
+        if let Some(session) = self.sessions.lock().unwrap().get_mut(token) {
+            session.filter.topics = topics;
+            session.filter.can_ids = can_ids;
+        }
+    }
+
+    /// Start the resumption window of `token`, unless another connection took it over
+    pub fn disconnect(&self, token: &str, connection: u64) {
+        if let Some(session) = self.sessions.lock().unwrap().get_mut(token) {
+            if session.connection == connection {
+                session.disconnected_at = Some(Instant::now());
+            }
+        }
+    }
+
+    fn purge(&self, sessions: &mut HashMap<String, Session>) {
+        sessions.retain(|_, session| {
+            session
+                .disconnected_at
+                .is_none_or(|disconnected_at| disconnected_at.elapsed() < self.window)
+        });
+    }
+}
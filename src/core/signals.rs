@@ -0,0 +1,440 @@
+use actix_web::http::header;
+use actix_web::{get, web, HttpResponse};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use crate::common::error::AppError;
+use crate::core::can::{to_hex, CanId, CanMessage, Endianness};
+use crate::core::can_map::{can_ids, FrameGroup};
+use crate::core::dbc;
+use crate::core::units::{Unit, UnitSystem};
+use crate::features::driving_step::model::StepQuery;
+use crate::features::driving_step::{service as step_service, DrivingStep};
+use crate::features::tenant::TenantScope;
+
+/// Description of one signal carried inside the DrivingStep CAN frames
+#[derive(Debug, Clone, Serialize)]
+pub struct SignalDef {
+    /// Unique signal name
+    pub name: &'static str,
+    /// Dotted path of the field in the serialized DrivingStep
+    pub path: &'static str,
+    /// CAN frame carrying the signal, serialized as the CAN ID it is mapped to, like frame
+    /// ids of streams (`"0x100"`)
+    #[serde(rename = "can_id", serialize_with = "serialize_can_id")]
+    pub frame: FrameGroup,
+    /// DrivingStep group the signal belongs to
+    pub group: &'static str,
+    /// Unit of the decoded value
+    pub unit: Unit,
+    /// Lowest representable decoded value
+    pub min: f64,
+    /// Highest representable decoded value
+    pub max: f64,
+    /// Decoded value of one raw bit step
+    pub scale: f64,
+}
+
+fn serialize_can_id<S: Serializer>(frame: &FrameGroup, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&CanId::from(frame.id()))
+}
+
+const fn signal(
+    name: &'static str,
+    path: &'static str,
+    frame: FrameGroup,
+    group: &'static str,
+    unit: Unit,
+    (min, max, scale): (f64, f64, f64),
+) -> SignalDef {
+    SignalDef {
+        name,
+        path,
+        frame,
+        group,
+        unit,
+        min,
+        max,
+        scale,
+    }
+}
+
+const FLAG: (f64, f64, f64) = (0.0, 1.0, 1.0);
+const TEMPERATURE: (f64, f64, f64) = (-40.0, 215.0, 1.0);
+const PERCENT: (f64, f64, f64) = (0.0, 100.0, 1.0);
+
+/// Every signal encoded by `DrivingStep::to_can_messages` (optional groups included)
+#[rustfmt::skip]
+pub static SIGNALS: &[SignalDef] = &[
+    signal("rpm", "engine.rpm", FrameGroup::EngineRpm, "engine", Unit::Rpm, (0.0, 65535.0, 1.0)),
+    signal("fuel_pressure", "engine.fuel_pressure", FrameGroup::EngineRpm, "engine", Unit::KiloPascal, (0.0, 65530.0, 10.0)),
+    signal("engine_running", "engine.engine_running", FrameGroup::EngineRpm, "engine", Unit::Boolean, FLAG),
+    signal("tank_level", "fuel.tank_level", FrameGroup::Fuel, "fuel", Unit::Percent, PERCENT),
+    signal("fuel_consumption", "fuel.consumption", FrameGroup::Fuel, "fuel", Unit::LitersPer100Km, (0.0, 6553.5, 0.1)),
+    signal("range_remaining", "fuel.range_remaining", FrameGroup::Fuel, "fuel", Unit::Kilometers, (0.0, 65535.0, 1.0)),
+    signal("coolant_temp", "engine.coolant_temp", FrameGroup::EngineTemp, "engine", Unit::Celsius, TEMPERATURE),
+    signal("intake_temp", "engine.intake_temp", FrameGroup::EngineTemp, "engine", Unit::Celsius, TEMPERATURE),
+    signal("throttle_pos", "engine.throttle_pos", FrameGroup::EngineTemp, "engine", Unit::Percent, PERCENT),
+    signal("engine_load", "engine.engine_load", FrameGroup::EngineTemp, "engine", Unit::Percent, PERCENT),
+    signal("vehicle_speed", "speed.vehicle_speed", FrameGroup::SpeedData, "speed", Unit::KilometersPerHour, (0.0, 6553.5, 0.1)),
+    signal("gear_position", "speed.gear_position", FrameGroup::SpeedData, "speed", Unit::Raw, (0.0, 15.0, 1.0)),
+    signal("wheel_speeds", "speed.wheel_speeds", FrameGroup::SpeedData, "speed", Unit::KilometersPerHour, (0.0, 255.0, 1.0)),
+    signal("abs_active", "speed.abs_active", FrameGroup::SpeedFlags, "speed", Unit::Boolean, FLAG),
+    signal("traction_control", "speed.traction_control", FrameGroup::SpeedFlags, "speed", Unit::Boolean, FLAG),
+    signal("cruise_control", "speed.cruise_control", FrameGroup::SpeedFlags, "speed", Unit::Boolean, FLAG),
+    signal("cabin_temp", "climate.cabin_temp", FrameGroup::ClimateTemp, "climate", Unit::Celsius, TEMPERATURE),
+    signal("target_temp", "climate.target_temp", FrameGroup::ClimateTemp, "climate", Unit::Celsius, TEMPERATURE),
+    signal("outside_temp", "climate.outside_temp", FrameGroup::ClimateTemp, "climate", Unit::Celsius, TEMPERATURE),
+    signal("fan_speed", "climate.fan_speed", FrameGroup::ClimateFan, "climate", Unit::Raw, (0.0, 255.0, 1.0)),
+    signal("ac_compressor", "climate.ac_compressor", FrameGroup::ClimateFan, "climate", Unit::Boolean, FLAG),
+    signal("heater", "climate.heater", FrameGroup::ClimateFan, "climate", Unit::Boolean, FLAG),
+    signal("defrost", "climate.defrost", FrameGroup::ClimateFan, "climate", Unit::Boolean, FLAG),
+    signal("auto_mode", "climate.auto_mode", FrameGroup::ClimateFan, "climate", Unit::Boolean, FLAG),
+    signal("air_recirculation", "climate.air_recirculation", FrameGroup::ClimateFan, "climate", Unit::Boolean, FLAG),
+    signal("lead_distance", "adas.lead_distance", FrameGroup::Adas, "adas", Unit::Meters, (0.0, 6553.5, 0.1)),
+    signal("relative_speed", "adas.relative_speed", FrameGroup::Adas, "adas", Unit::KilometersPerHour, (-3276.8, 3276.7, 0.1)),
+    signal("acc_set_speed", "adas.acc_set_speed", FrameGroup::Adas, "adas", Unit::KilometersPerHour, (0.0, 255.0, 1.0)),
+    signal("acc_active", "adas.acc_active", FrameGroup::Adas, "adas", Unit::Boolean, FLAG),
+    signal("lane_keep_active", "adas.lane_keep_active", FrameGroup::Adas, "adas", Unit::Boolean, FLAG),
+    signal("lane_departure_warning", "adas.lane_departure_warning", FrameGroup::Adas, "adas", Unit::Boolean, FLAG),
+    signal("latitude", "gps.latitude", FrameGroup::Gps, "gps", Unit::Degrees, (-90.0, 90.0, 1e-7)),
+    signal("longitude", "gps.longitude", FrameGroup::Gps, "gps", Unit::Degrees, (-180.0, 180.0, 1e-7)),
+    signal("duration_ms", "duration_ms", FrameGroup::StepInfo, "step", Unit::Milliseconds, (0.0, 4294967295.0, 1.0)),
+];
+
+/// Signal map frames are decoded with, selected by the CAN profile of a vehicle
+#[derive(Debug)]
+pub struct SignalProfile {
+    /// Value of `can_profile` in the vehicle registry
+    pub name: &'static str,
+    pub signals: &'static [SignalDef],
+    /// Every frame the profile defines
+    pub frames: &'static [FrameGroup],
+    /// Frames every step carries; the others of `frames` are optional
+    pub required: &'static [FrameGroup],
+    /// Unpack one step worth of frames with the given byte order
+    pub decode: fn(&[CanMessage], String, bool) -> Result<DrivingStep, String>,
+}
+
+impl SignalProfile {
+    /// Required CAN IDs without a frame in `frames`, in profile order
+    pub fn missing(&self, frames: &[CanMessage]) -> Vec<u16> {
+        self.required
+            .iter()
+            .map(|group| group.id())
+            .filter(|id| !frames.iter().any(|frame| frame.id == *id))
+            .collect()
+    }
+
+    /// DLC the profile, or else the user DBC, defines for frames of CAN ID `id`, `None` for
+    /// IDs neither defines
+    pub fn dlc(&self, id: u16) -> Option<u8> {
+        match can_ids().group(id) {
+            Some(group) => self.frames.contains(&group).then(|| group.dlc()),
+            None => dbc::dbc().message(id).map(|message| message.dlc),
+        }
+    }
+
+    /// Unpack one step worth of frames, failing when a required frame is missing
+    pub fn decode_step(
+        &self,
+        frames: &[CanMessage],
+        step_name: String,
+        is_big_endian: bool,
+    ) -> Result<DrivingStep, String> {
+        if let Some(missing) = self.missing(frames).first() {
+            return Err(format!(
+                "Missing CAN frame {} required by profile '{}'",
+                CanId::from(*missing),
+                self.name
+            ));
+        }
+        (self.decode)(frames, step_name, is_big_endian)
+    }
+}
+
+/// Profile of frames stored without a vehicle, the layout of `DrivingStep::to_can_messages`
+pub const DEFAULT_PROFILE: &str = "driving_step_v1";
+
+/// Frames of vehicles sending the fuel, ADAS and GPS groups with every step
+const EQUIPPED_FRAMES: [FrameGroup; 10] = [
+    FrameGroup::EngineRpm,
+    FrameGroup::EngineTemp,
+    FrameGroup::Fuel,
+    FrameGroup::SpeedData,
+    FrameGroup::SpeedFlags,
+    FrameGroup::ClimateTemp,
+    FrameGroup::ClimateFan,
+    FrameGroup::StepInfo,
+    FrameGroup::Adas,
+    FrameGroup::Gps,
+];
+
+/// Every CAN profile a vehicle can reference
+pub static PROFILES: &[SignalProfile] = &[
+    SignalProfile {
+        name: DEFAULT_PROFILE,
+        signals: SIGNALS,
+        frames: &FrameGroup::ALL,
+        required: &FrameGroup::REQUIRED,
+        decode: DrivingStep::from_can_messages_with_endian,
+    },
+    SignalProfile {
+        name: "driving_step_v1_equipped",
+        signals: SIGNALS,
+        frames: &FrameGroup::ALL,
+        required: &EQUIPPED_FRAMES,
+        decode: DrivingStep::from_can_messages_with_endian,
+    },
+];
+
+/// Look up a CAN profile by name
+pub fn profile(name: &str) -> Option<&'static SignalProfile> {
+    PROFILES.iter().find(|profile| profile.name == name)
+}
+
+/// Look up a signal by name
+pub fn find(name: &str) -> Option<&'static SignalDef> {
+    SIGNALS.iter().find(|signal| signal.name == name)
+}
+
+impl SignalDef {
+    /// CAN ID of the frame carrying the signal, in the map in force
+    pub fn can_id(&self) -> u16 {
+        self.frame.id()
+    }
+
+    /// JSON pointer of the signal inside a serialized DrivingStep
+    pub fn pointer(&self) -> String {
+        format!("/{}", self.path.replace('.', "/"))
+    }
+}
+
+/// Convert one JSON number (or array of numbers) between units, rounded to 0.1
+fn convert_value(value: &mut Value, from: Unit, to: Unit) {
+    match value {
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| convert_value(item, from, to)),
+        Value::Number(number) => {
+            if let Some(converted) = number.as_f64().and_then(|raw| from.convert(raw, to)) {
+                *value = serde_json::json!((converted * 10.0).round() / 10.0);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Rewrite a serialized DrivingStep so every registered signal uses `system` units
+pub fn apply_units(step: &mut Value, system: UnitSystem) {
+    for signal in SIGNALS {
+        let target = signal.unit.in_system(system);
+        if target == signal.unit {
+            continue;
+        }
+        if let Some(value) = step.pointer_mut(&signal.pointer()) {
+            convert_value(value, signal.unit, target);
+        }
+    }
+}
+
+/// Serialize DrivingSteps in the requested unit system
+pub fn steps_in_units<'a>(
+    steps: impl IntoIterator<Item = &'a DrivingStep>,
+    system: UnitSystem,
+) -> Result<Vec<Value>, serde_json::Error> {
+    steps
+        .into_iter()
+        .map(|step| {
+            let mut value = serde_json::to_value(step)?;
+            apply_units(&mut value, system);
+            Ok(value)
+        })
+        .collect()
+}
+
+/// One stored frame with the values of the signals it carries, sent to `decoded` streams
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecodedFrame {
+    /// Always `frame`, next to the `type` of the other stream messages
+    #[serde(rename = "type", skip_deserializing, default = "DecodedFrame::kind")]
+    pub kind: &'static str,
+    /// Hex CAN ID, e.g. `0x100`
+    pub id: String,
+    pub dlc: u8,
+    /// Hex payload of `dlc` bytes
+    pub data: String,
+    /// CAN-FD frame, and whether its data phase used the faster bit rate
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub fd: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub brs: bool,
+    pub timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step_id: Option<String>,
+    /// Registry signals of the frame, or the signals the user DBC declares for it, by name,
+    /// in registry or DBC units
+    pub signals: Map<String, Value>,
+}
+
+impl DecodedFrame {
+    fn kind() -> &'static str {
+        "frame"
+    }
+}
+
+/// Split a step into its frames, each with the signals the registry assigns to it
+///
+/// Values come from the step the consumer decoded from these very frames, so clients get
+/// the bit unpacking of the server. Steps published without frames are encoded with the
+/// server `ENDIAN`.
+pub fn decode_frames(step: &DrivingStep) -> serde_json::Result<Vec<DecodedFrame>> {
+    let frames = if step.frames.is_empty() {
+        step.to_can_messages()
+    } else {
+        step.frames.clone()
+    };
+    let value = serde_json::to_value(step)?;
+
+    Ok(frames
+        .into_iter()
+        .map(|frame| decode_frame(frame, &value, step.step_id.clone()))
+        .collect())
+}
+
+fn decode_frame(frame: CanMessage, step: &Value, step_id: Option<String>) -> DecodedFrame {
+    let signals = match dbc::dbc().message(frame.id) {
+        // Frames of the user DBC are unpacked here, the step knows nothing of them
+        Some(message) => message
+            .decode(frame.data())
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), Value::from(value)))
+            .collect(),
+        None => SIGNALS
+            .iter()
+            .filter(|signal| signal.can_id() == frame.id)
+            .filter_map(|signal| {
+                step.pointer(&signal.pointer())
+                    .map(|value| (signal.name.to_string(), value.clone()))
+            })
+            .collect(),
+    };
+
+    DecodedFrame {
+        kind: "frame",
+        id: CanId::from(frame.id).to_string(),
+        dlc: frame.dlc,
+        data: to_hex(frame.data()),
+        fd: frame.fd,
+        brs: frame.brs,
+        timestamp: frame.timestamp,
+        seq: frame.seq,
+        step_id,
+        signals,
+    }
+}
+
+/// Entry of the `GET /signals` data dictionary
+#[derive(Debug, Clone, Serialize)]
+struct CatalogEntry {
+    #[serde(flatten)]
+    signal: &'static SignalDef,
+    /// Display symbol of `unit`, empty for flags and raw values
+    symbol: &'static str,
+}
+
+/// Current value of one signal, as returned by `GET /signals/{name}/latest`
+#[derive(Debug, Clone, Serialize)]
+struct LatestValue {
+    name: &'static str,
+    value: Value,
+    unit: Unit,
+    symbol: &'static str,
+    step_id: Option<String>,
+    /// Timestamp of the frame the value was decoded from
+    timestamp: Option<String>,
+}
+
+/// Every signal of the registry, in frame order
+#[get("/signals")]
+async fn catalog() -> HttpResponse {
+    let entries: Vec<CatalogEntry> = SIGNALS
+        .iter()
+        .map(|signal| CatalogEntry {
+            signal,
+            symbol: signal.unit.symbol(),
+        })
+        .collect();
+    HttpResponse::Ok().json(entries)
+}
+
+/// Value of a signal in the last stored step carrying its frame
+#[get("/signals/{name}/latest")]
+async fn latest(
+    name: web::Path<String>,
+    query: web::Query<StepQuery>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    let signal = find(&name).ok_or_else(|| AppError::not_found(format!("Signal '{}'", name)))?;
+    let step = step_service::get_last_step_with_frame(signal.can_id(), scope.name())
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("No stored value of signal '{}'", name)))?;
+
+    let mut converted = steps_in_units([&step], query.units)?.remove(0);
+    let value = converted
+        .pointer_mut(&signal.pointer())
+        .map(Value::take)
+        .ok_or_else(|| AppError::not_found(format!("No stored value of signal '{}'", name)))?;
+    let unit = signal.unit.in_system(query.units);
+
+    Ok(HttpResponse::Ok().json(LatestValue {
+        name: signal.name,
+        value,
+        unit,
+        symbol: unit.symbol(),
+        timestamp: step
+            .frames
+            .iter()
+            .find(|frame| frame.id == signal.can_id())
+            .map(|frame| frame.timestamp.clone()),
+        step_id: step.step_id,
+    }))
+}
+
+/// CAN ID of every DrivingStep frame group, as configured with `CAN_ID_MAP`
+#[get("/signals/can-ids")]
+async fn can_id_map() -> HttpResponse {
+    HttpResponse::Ok().json(can_ids())
+}
+
+#[derive(Debug, Deserialize)]
+struct DbcQuery {
+    /// Byte order of the multi-byte DrivingStep signals, `ENDIAN` when omitted
+    endianness: Option<Endianness>,
+}
+
+/// DrivingStep layout with the CAN IDs in force and the messages of `DBC_FILE`, as a DBC
+/// file for CAN tools
+#[get("/signals/dbc")]
+async fn dbc_file(query: web::Query<DbcQuery>) -> HttpResponse {
+    let is_big_endian = query.endianness.map_or_else(
+        DrivingStep::get_endianness_from_env,
+        Endianness::is_big_endian,
+    );
+    HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            "attachment; filename=\"driving_step.dbc\"",
+        ))
+        .body(dbc::in_force(is_big_endian).to_string())
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(catalog)
+        .service(can_id_map)
+        .service(dbc_file)
+        .service(latest);
+}
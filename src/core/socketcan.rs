@@ -0,0 +1,140 @@
+use std::io;
+use std::mem;
+use std::time::Duration;
+
+use socketcan::tokio::CanFdSocket;
+use socketcan::{CanAnyFrame, EmbeddedFrame, Id};
+
+use crate::common::error::AppError;
+use crate::config::transport::StepTransport;
+use crate::core::can::{CanMessage, Endianness};
+use crate::core::clock::{Clock, SystemClock};
+use crate::features::driving_step::DrivingStep;
+use crate::features::ingest::controller;
+use crate::features::validation::{self, FrameValidator, ValidationContext};
+
+/// Quiet time after which the frames read so far are stored as a step
+const IDLE_FLUSH: Duration = Duration::from_millis(500);
+
+/// Data frame of a standard ID as a `CanMessage` stamped by `clock`, CAN-FD frames keeping
+/// their payload length and bit rate switch
+///
+/// Error frames, remote frames and extended IDs have no place in a DrivingStep and
+/// return `None`.
+pub fn to_message(frame: &CanAnyFrame, clock: &dyn Clock) -> Option<CanMessage> {
+    match frame {
+        CanAnyFrame::Normal(frame) => {
+            let Id::Standard(id) = frame.id() else {
+                return None;
+            };
+            CanMessage::try_new(id.as_raw(), frame.data(), clock.timestamp()).ok()
+        }
+        CanAnyFrame::Fd(frame) => {
+            let Id::Standard(id) = frame.id() else {
+                return None;
+            };
+            CanMessage::try_new_fd(id.as_raw(), frame.data(), frame.is_brs(), clock.timestamp())
+                .ok()
+        }
+        CanAnyFrame::Remote(_) | CanAnyFrame::Error(_) => None,
+    }
+}
+
+/// Stores the frames of a SocketCAN interface as they come, like `POST /ingest` bodies
+///
+/// A step ends at the first repeated CAN ID or after `IDLE_FLUSH` without frames. Steps
+/// go through validation and are published under the name of the interface, without a
+/// vehicle, their byte order detected or `ENDIAN`.
+pub struct SocketCanReader {
+    interface: String,
+    socket: CanFdSocket,
+}
+
+impl SocketCanReader {
+    /// Open the interface now in CAN-FD mode, so a missing or down interface fails startup;
+    /// classic frames are read from it as well
+    pub fn open(interface: &str) -> io::Result<Self> {
+        let socket = CanFdSocket::open(interface)
+            .map_err(|e| io::Error::new(e.kind(), format!("CAN interface {}: {}", interface, e)))?;
+        Ok(SocketCanReader {
+            interface: interface.to_string(),
+            socket,
+        })
+    }
+
+    pub fn spawn(self, validator: FrameValidator, transport: StepTransport) {
+        println!("🔌 Reading CAN frames from {}", self.interface);
+
+        tokio::spawn(async move {
+            let mut pending: Vec<CanMessage> = Vec::new();
+            loop {
+                let frame = match tokio::time::timeout(IDLE_FLUSH, self.socket.read_frame()).await {
+                    Ok(Ok(frame)) => frame,
+                    Ok(Err(e)) => {
+                        println!("❌ Failed to read from {}: {}", self.interface, e);
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                        continue;
+                    }
+                    Err(_) if pending.is_empty() => continue,
+                    Err(_) => {
+                        self.store(mem::take(&mut pending), &validator, &transport)
+                            .await;
+                        continue;
+                    }
+                };
+                let Some(message) = to_message(&frame, &SystemClock) else {
+                    continue;
+                };
+                if pending.iter().any(|stored| stored.id == message.id) {
+                    self.store(mem::take(&mut pending), &validator, &transport)
+                        .await;
+                }
+                pending.push(message);
+            }
+        });
+    }
+
+    /// Validate, decode and publish one step, logging the steps that cannot be stored
+    async fn store(
+        &self,
+        frames: Vec<CanMessage>,
+        validator: &FrameValidator,
+        transport: &StepTransport,
+    ) {
+        match self.publish(frames, validator, transport).await {
+            Ok(Ok(())) => {}
+            Ok(Err(reason)) => println!("⚠️ Step read from {} dropped: {}", self.interface, reason),
+            Err(e) => println!(
+                "❌ Failed to store a step read from {}: {}",
+                self.interface, e
+            ),
+        }
+    }
+
+    async fn publish(
+        &self,
+        frames: Vec<CanMessage>,
+        validator: &FrameValidator,
+        transport: &StepTransport,
+    ) -> Result<Result<(), String>, AppError> {
+        let endianness = DrivingStep::detect_endianness(&frames).unwrap_or_else(|| {
+            Endianness::from_is_big_endian(DrivingStep::get_endianness_from_env())
+        });
+        let profile = controller::profile(None)?;
+        let context = ValidationContext {
+            profile,
+            endianness,
+            vehicle_id: None,
+            step_name: &self.interface,
+        };
+        let steps = match validation::controller::screen(validator, vec![frames], &context).await? {
+            Ok((steps, _)) => steps,
+            Err(failure) => return Ok(Err(failure.error)),
+        };
+        if let Err(failure) = controller::verify(&steps, endianness, profile) {
+            return Ok(Err(failure.error));
+        }
+        controller::publish(steps, endianness, None, None, &self.interface, transport).await?;
+        Ok(Ok(()))
+    }
+}
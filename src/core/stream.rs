@@ -1,56 +1,236 @@
 use actix_web::web::Data;
-use actix_web::{get, web, Error, HttpResponse, Responder};
+use actix_web::{get, web, Error, HttpRequest, HttpResponse, Responder};
 use actix_web_lab::sse;
-use tokio::sync::broadcast;
+use futures_util::{Stream, StreamExt};
 
-use crate::features::driving_step::DrivingStep;
+use crate::common::error::AppError;
+use crate::core::bus::{BusMessage, SubscriptionFilter};
+use crate::core::connections::{self, StreamSlot};
+use crate::core::feed::ClientFeed;
+use crate::core::journal::{Journal, Sequenced};
+use crate::core::scrub::{Scrub, ScrubRules};
+use crate::core::timestamps::TimestampFormat;
+use crate::core::topics::TopicRegistry;
+use crate::features::driving_step::service as step_service;
+use crate::features::subscription::{StreamTransport, SubscriptionRegistry};
+use crate::features::tenant::TenantScope;
+use crate::features::vehicle::VehicleStates;
 
-/* ---------- SSE with actix-web-lab (GET /stream-lab) ---------- */
-#[get("/stream-lab")]
-async fn stream_lab_events(tx: Data<broadcast::Sender<DrivingStep>>) -> impl Responder {
-    let mut rx = tx.subscribe();
-
-    let stream = async_stream::stream! {
-        loop {
-            match rx.recv().await {
-                Ok(driving_step) => {
-                    // Send the DrivingStep struct directly as JSON
-                    let data = serde_json::to_string(&driving_step).unwrap_or_else(|_| "{}".to_string());
-                    yield Ok::<_, Error>(sse::Event::Data(sse::Data::new(data)));
-                }
-                Err(broadcast::error::RecvError::Lagged(_)) => continue,
-                Err(_) => break,
+/// JSON array of the vehicle states visible to a stream client, sent as its first event
+fn snapshot(states: &VehicleStates, tenant: Option<&str>, scrub: Option<&ScrubRules>) -> String {
+    let mut states = states.all(tenant);
+    if let Some(rules) = scrub {
+        states.iter_mut().for_each(|state| rules.scrub_state(state));
+    }
+    serde_json::to_string(&states).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// Journal number of the last event a reconnecting client received, from `Last-Event-ID`
+fn last_event_id(req: &HttpRequest) -> Result<Option<u64>, AppError> {
+    let Some(header) = req.headers().get("Last-Event-ID") else {
+        return Ok(None);
+    };
+    header
+        .to_str()
+        .ok()
+        .and_then(|id| id.trim().parse().ok())
+        .map(Some)
+        .ok_or_else(|| {
+            AppError::bad_request("Last-Event-ID must be the id of an event sent by this server")
+        })
+}
+
+/// Messages a client reconnecting after event `last_id` missed, oldest first, none for a
+/// first connection
+///
+/// Messages still in the journal are replayed from it. Driving steps already evicted are
+/// reconstructed from SQLite and keep their journal numbers; other evicted messages are
+/// lost. An id ahead of the journal, from before a server restart, replays nothing.
+async fn replay(journal: &Journal, last_id: Option<u64>) -> Vec<Sequenced> {
+    let Some(last_id) = last_id.filter(|id| *id <= journal.latest()) else {
+        return Vec::new();
+    };
+    let evicted = journal.evicted_steps_since(last_id);
+    let (entries, _) = journal.since(last_id);
+    if evicted.is_empty() {
+        return entries;
+    }
+
+    // The evicted steps are the latest ones stored before the first journaled step
+    let first_step = entries.iter().find_map(|entry| match &entry.message {
+        BusMessage::DrivingStep(step) => step.step_id.clone(),
+        _ => None,
+    });
+    let stored =
+        match step_service::get_steps_stored_before(first_step.as_deref(), evicted.len()).await {
+            Ok(stored) => stored,
+            Err(e) => {
+                println!("⚠️ Could not replay evicted steps from SQLite: {}", e);
+                Vec::new()
             }
-        }
+        };
+    // Steps deleted since are missing from `stored`, so number them from the latest
+    let mut replayed: Vec<Sequenced> = evicted
+        .iter()
+        .rev()
+        .zip(stored.into_iter().rev())
+        .map(|(seq, step)| Sequenced {
+            seq: *seq,
+            message: BusMessage::DrivingStep(step),
+        })
+        .collect();
+    replayed.reverse();
+    replayed.extend(entries);
+    replayed
+}
+
+/// What a stream client receives, before it is framed for its endpoint
+enum StreamEvent {
+    /// JSON array of the vehicle states visible to the client
+    Snapshot(String),
+    /// A bus message as JSON, with its journal number for `Last-Event-ID`
+    Message(u64, String),
+}
+
+/// Payloads of `message` under `filter`, none when it does not serialize
+fn payloads(filter: &SubscriptionFilter, seq: u64, message: &BusMessage) -> Vec<String> {
+    filter.payloads(message).unwrap_or_else(|e| {
+        println!(
+            "⚠️ Could not serialize message {} for a stream client: {}",
+            seq, e
+        );
+        Vec::new()
+    })
+}
+
+/// Events of a `/stream` or `/stream-lab` client: the current state of the vehicles first,
+/// then the messages missed since its `Last-Event-ID`, then the live ones until its stored
+/// subscription is deleted
+#[allow(clippy::too_many_arguments)] // one argument per extractor of the handlers
+fn client_events(
+    req: &HttpRequest,
+    journal: &Journal,
+    topics: &TopicRegistry,
+    subscriptions: &SubscriptionRegistry,
+    states: &VehicleStates,
+    filter: SubscriptionFilter,
+    scope: &TenantScope,
+    scrub: Scrub,
+    slot: StreamSlot,
+) -> Result<impl Stream<Item = StreamEvent>, AppError> {
+    let filter = SubscriptionFilter {
+        tenant: scope.name().map(str::to_string),
+        scrub: scrub.0,
+        timestamps: TimestampFormat::requested(req.query_string(), req.headers())?,
+        ..filter
     };
+    let last_id = last_event_id(req)?;
+    let snapshot = filter
+        .timestamps
+        .rewrite(snapshot(states, scope.name(), scrub.0.as_ref()));
+    let filter = subscriptions.resolve(filter, StreamTransport::Sse)?;
+    // Follow the journal before reading it, so no message falls between the two
+    let mut feed = ClientFeed::new(journal.subscribe(), filter.clone(), topics);
+    let journal = journal.clone();
+
+    Ok(async_stream::stream! {
+        // The client counts against the connection limits until the stream is dropped
+        let _slot = slot;
+        yield StreamEvent::Snapshot(snapshot);
+        let mut replayed = 0;
+        for entry in replay(&journal, last_id).await {
+            replayed = entry.seq;
+            let Some(filter) = filter.current().filter(|f| f.accepts(&entry.message)) else {
+                continue;
+            };
+            for payload in payloads(&filter, entry.seq, &entry.message) {
+                yield StreamEvent::Message(entry.seq, payload);
+            }
+        }
+        while let Ok((filter, entry)) = feed.next().await {
+            if entry.seq <= replayed {
+                continue;
+            }
+            for payload in payloads(&filter, entry.seq, &entry.message) {
+                yield StreamEvent::Message(entry.seq, payload);
+            }
+        }
+    })
+}
+
+/* ---------- SSE with actix-web-lab (GET /stream-lab) ---------- */
+#[get("/stream-lab")]
+#[allow(clippy::too_many_arguments)] // one extractor per shared dependency
+async fn stream_lab_events(
+    req: HttpRequest,
+    journal: Data<Journal>,
+    topics: Data<TopicRegistry>,
+    subscriptions: Data<SubscriptionRegistry>,
+    states: Data<VehicleStates>,
+    filter: web::Query<SubscriptionFilter>,
+    scope: TenantScope,
+    scrub: Scrub,
+    slot: StreamSlot,
+) -> Result<impl Responder, AppError> {
+    let events = client_events(
+        &req,
+        &journal,
+        &topics,
+        &subscriptions,
+        &states,
+        filter.into_inner(),
+        &scope,
+        scrub,
+        slot,
+    )?;
+    let stream = events.map(|event| {
+        Ok::<_, Error>(sse::Event::Data(match event {
+            StreamEvent::Snapshot(snapshot) => sse::Data::new(snapshot).event("snapshot"),
+            StreamEvent::Message(seq, data) => sse::Data::new(data).id(seq.to_string()),
+        }))
+    });
 
-    sse::Sse::from_stream(stream)
+    Ok(sse::Sse::from_stream(stream).with_keep_alive(connections::KEEP_ALIVE))
 }
 
 /* ---------- SSE (GET /stream) ---------- */
 #[get("/stream")]
-async fn stream_events(tx: Data<broadcast::Sender<DrivingStep>>) -> impl Responder {
-    let mut rx = tx.subscribe();
-
-    let stream = async_stream::stream! {
-        loop {
-            match rx.recv().await {
-                Ok(driving_step) => {
-                    // Send the DrivingStep struct directly as JSON
-                    let line = format!("data: {}\n\n", serde_json::to_string(&driving_step).unwrap());
-                    yield Ok::<_, Error>(actix_web::web::Bytes::from(line));
-                }
-                Err(broadcast::error::RecvError::Lagged(_)) => continue,
-                Err(_) => break,
-            }
-        }
-    };
+#[allow(clippy::too_many_arguments)] // one extractor per shared dependency
+async fn stream_events(
+    req: HttpRequest,
+    journal: Data<Journal>,
+    topics: Data<TopicRegistry>,
+    subscriptions: Data<SubscriptionRegistry>,
+    states: Data<VehicleStates>,
+    filter: web::Query<SubscriptionFilter>,
+    scope: TenantScope,
+    scrub: Scrub,
+    slot: StreamSlot,
+) -> Result<HttpResponse, AppError> {
+    let events = client_events(
+        &req,
+        &journal,
+        &topics,
+        &subscriptions,
+        &states,
+        filter.into_inner(),
+        &scope,
+        scrub,
+        slot,
+    )?;
+    let stream = events.map(|event| {
+        let line = match event {
+            StreamEvent::Snapshot(snapshot) => format!("event: snapshot\ndata: {}\n\n", snapshot),
+            StreamEvent::Message(seq, data) => format!("id: {}\ndata: {}\n\n", seq, data),
+        };
+        Ok::<_, Error>(web::Bytes::from(line))
+    });
 
-    HttpResponse::Ok()
+    Ok(HttpResponse::Ok()
         .insert_header(("Content-Type", "text/event-stream"))
         .insert_header(("Cache-Control", "no-cache"))
         .insert_header(("X-Accel-Buffering", "no"))
-        .streaming(stream)
+        .streaming(connections::keep_alive(stream)))
 }
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
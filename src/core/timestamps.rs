@@ -0,0 +1,168 @@
+use std::str::FromStr;
+
+use actix_web::body::{self, BodySize, BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderMap, HeaderValue, ACCEPT, CONTENT_LENGTH, CONTENT_TYPE, VARY};
+use actix_web::middleware::Next;
+use actix_web::web;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::common::error::AppError;
+use crate::core::clock;
+
+/// How points in time are written in JSON responses and stream messages
+///
+/// The server stores and sends RFC3339 strings; clients asking for `epoch_ms` get the
+/// same fields as integer milliseconds since the Unix epoch, which sort and compare as
+/// numbers whatever the offset and precision of the string.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampFormat {
+    #[default]
+    Rfc3339,
+    EpochMs,
+}
+
+impl TimestampFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TimestampFormat::Rfc3339 => "rfc3339",
+            TimestampFormat::EpochMs => "epoch_ms",
+        }
+    }
+
+    /// Format a request asks for: its `?timestamps=` parameter, else the `timestamps`
+    /// parameter of a media range of its Accept header
+    /// (`Accept: application/json; timestamps=epoch_ms`), else RFC3339
+    pub fn requested(query: &str, headers: &HeaderMap) -> Result<Self, AppError> {
+        let query = web::Query::<TimestampsQuery>::from_query(query)
+            .map(web::Query::into_inner)
+            .unwrap_or_default();
+        let accepted = headers
+            .get_all(ACCEPT)
+            .filter_map(|accept| accept.to_str().ok())
+            .flat_map(|accept| accept.split([',', ';']))
+            .filter_map(|parameter| parameter.trim().split_once('='))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("timestamps"))
+            .map(|(_, value)| value.trim().trim_matches('"').to_string());
+        match query.timestamps.or(accepted) {
+            Some(format) => format.parse().map_err(AppError::bad_request),
+            None => Ok(TimestampFormat::Rfc3339),
+        }
+    }
+
+    /// Rewrite the timestamps of `value` in this format, in place
+    ///
+    /// Fields named `timestamp`, `from`, `to`, `cutoff` or `epoch`, or ending in `_at`,
+    /// `_until` or `_timestamp`, are rewritten at any depth when they hold an RFC3339
+    /// string; every other field is left as is.
+    pub fn apply(self, value: &mut Value) {
+        if self == TimestampFormat::Rfc3339 {
+            return;
+        }
+        match value {
+            Value::Object(fields) => {
+                for (key, field) in fields.iter_mut() {
+                    let epoch_ms = field
+                        .as_str()
+                        .filter(|_| names_time(key))
+                        .and_then(clock::epoch_ms);
+                    match epoch_ms {
+                        Some(epoch_ms) => *field = epoch_ms.into(),
+                        None => self.apply(field),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.apply(item)),
+            _ => {}
+        }
+    }
+
+    /// `json` with its timestamps in this format, unchanged when it does not parse
+    pub fn rewrite(self, json: String) -> String {
+        if self == TimestampFormat::Rfc3339 {
+            return json;
+        }
+        match serde_json::from_str::<Value>(&json) {
+            Ok(mut value) => {
+                self.apply(&mut value);
+                value.to_string()
+            }
+            Err(_) => json,
+        }
+    }
+}
+
+impl FromStr for TimestampFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "rfc3339" => Ok(TimestampFormat::Rfc3339),
+            "epoch_ms" => Ok(TimestampFormat::EpochMs),
+            other => Err(format!(
+                "Unknown timestamp format '{}', expected rfc3339 or epoch_ms",
+                other
+            )),
+        }
+    }
+}
+
+/// `?timestamps=` of any request
+#[derive(Debug, Default, Deserialize)]
+struct TimestampsQuery {
+    timestamps: Option<String>,
+}
+
+/// Whether a field of this name holds a point in time
+fn names_time(key: &str) -> bool {
+    matches!(key, "timestamp" | "from" | "to" | "cutoff" | "epoch")
+        || key.ends_with("_at")
+        || key.ends_with("_until")
+        || key.ends_with("_timestamp")
+}
+
+/// Middleware writing the timestamps of JSON responses in the format the request asked
+/// for, see `TimestampFormat::requested`; an unknown format is refused with `400`
+///
+/// Streamed bodies are left alone: stream and websocket clients get their format applied
+/// to each message through their `SubscriptionFilter`.
+pub async fn rewrite(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let format = TimestampFormat::requested(req.query_string(), req.headers())?;
+
+    let mut res = next.call(req).await?;
+    let json = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("application/json"));
+    if !json {
+        return Ok(res.map_into_boxed_body());
+    }
+    res.headers_mut()
+        .append(VARY, HeaderValue::from_static("accept"));
+    if format == TimestampFormat::Rfc3339
+        || matches!(res.response().body().size(), BodySize::Stream)
+    {
+        return Ok(res.map_into_boxed_body());
+    }
+
+    let (req, res) = res.into_parts();
+    let (mut res, body) = res.into_parts();
+    let bytes = body::to_bytes(body)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(e.into()))?;
+    let bytes = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(mut value) => {
+            format.apply(&mut value);
+            serde_json::to_vec(&value)?.into()
+        }
+        Err(_) => bytes,
+    };
+    res.headers_mut().remove(CONTENT_LENGTH);
+    Ok(ServiceResponse::new(req, res.set_body(BoxBody::new(bytes))))
+}
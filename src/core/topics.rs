@@ -0,0 +1,220 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_web::web::Data;
+use actix_web::{get, web, HttpResponse};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::common::error::AppError;
+use crate::core::bus::{Bus, BusMessage};
+use crate::core::journal::Journal;
+use crate::features::subscription::LiveFilter;
+
+/// Window the publish rate of a topic is averaged over
+pub const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+struct TopicState {
+    published: u64,
+    last_published_at: Option<String>,
+    /// Messages published per second within the rate window, oldest second first
+    recent: VecDeque<(Instant, u64)>,
+    dropped: u64,
+}
+
+impl TopicState {
+    fn prune(&mut self, now: Instant) {
+        while self
+            .recent
+            .front()
+            .is_some_and(|(second, _)| now.duration_since(*second) > RATE_WINDOW)
+        {
+            self.recent.pop_front();
+        }
+    }
+
+    fn rate(&self) -> f64 {
+        let published: u64 = self.recent.iter().map(|(_, count)| count).sum();
+        published as f64 / RATE_WINDOW.as_secs_f64()
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    topics: HashMap<&'static str, TopicState>,
+    /// Filters of the stream clients connected, by feed number
+    feeds: HashMap<u64, LiveFilter>,
+    next_feed: u64,
+    /// Messages stream clients missed because they fell behind the bus, whatever the topic
+    lagged: u64,
+}
+
+/// Statistics of one bus topic, as listed by `GET /bus/topics`
+#[derive(Debug, Clone, Serialize)]
+pub struct TopicStats {
+    pub topic: &'static str,
+    /// Stream clients (`/ws`, `/stream`, `/stream-lab`) whose filter lets the topic through
+    pub subscribers: usize,
+    /// Messages published since the server started
+    pub published: u64,
+    /// Messages per second over the last minute
+    pub rate: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_published_at: Option<String>,
+    /// Messages of the topic stream clients never received, replaced under their `max_rate`
+    pub dropped: u64,
+    /// Journal number of the latest message of the topic, `None` when none is journaled
+    pub journal_seq: Option<u64>,
+}
+
+/// Response of `GET /bus/topics`
+#[derive(Debug, Clone, Serialize)]
+pub struct BusStats {
+    /// Receivers of the bus, stream clients and internal consumers (rules, webhooks...)
+    pub receivers: usize,
+    pub stream_clients: usize,
+    /// Messages stream clients missed by falling behind the bus, any topic
+    pub lagged: u64,
+    /// Number of the latest journaled message
+    pub journal_latest: u64,
+    pub topics: Vec<TopicStats>,
+}
+
+/// Traffic of every bus topic and the stream clients following it
+///
+/// Counts what is published on the bus once `spawn` runs; stream clients report the
+/// messages they drop through the `FeedRegistration` their feed holds.
+#[derive(Clone, Default)]
+pub struct TopicRegistry {
+    state: Arc<Mutex<Registry>>,
+}
+
+impl TopicRegistry {
+    pub fn new() -> Self {
+        TopicRegistry::default()
+    }
+
+    /// Count the stream client following `filter` until the registration is dropped
+    pub fn register(&self, filter: LiveFilter) -> FeedRegistration {
+        let mut state = self.state.lock().unwrap();
+        state.next_feed += 1;
+        let id = state.next_feed;
+        state.feeds.insert(id, filter);
+        FeedRegistration {
+            registry: self.clone(),
+            id,
+        }
+    }
+
+    fn published(&self, topic: &'static str) {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        let topic = state.topics.entry(topic).or_default();
+        topic.published += 1;
+        topic.last_published_at = Some(chrono::Utc::now().to_rfc3339());
+        match topic.recent.back_mut() {
+            Some((second, count)) if now.duration_since(*second) < Duration::from_secs(1) => {
+                *count += 1
+            }
+            _ => topic.recent.push_back((now, 1)),
+        }
+        topic.prune(now);
+    }
+
+    /// Statistics of every topic, in `BusMessage::TOPICS` order
+    pub fn stats(&self, bus: &Bus, journal: &Journal) -> BusStats {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        let filters: Vec<_> = state
+            .feeds
+            .values()
+            .filter_map(LiveFilter::current)
+            .collect();
+        let journal_seqs = journal.latest_by_topic();
+
+        let topics = BusMessage::TOPICS
+            .iter()
+            .map(|topic| {
+                let stats = state.topics.entry(topic).or_default();
+                stats.prune(now);
+                TopicStats {
+                    topic,
+                    subscribers: filters
+                        .iter()
+                        .filter(|filter| {
+                            filter.topics.is_empty() || filter.topics.iter().any(|t| t == topic)
+                        })
+                        .count(),
+                    published: stats.published,
+                    rate: stats.rate(),
+                    last_published_at: stats.last_published_at.clone(),
+                    dropped: stats.dropped,
+                    journal_seq: journal_seqs.get(topic).copied(),
+                }
+            })
+            .collect();
+        BusStats {
+            receivers: bus.receiver_count(),
+            stream_clients: state.feeds.len(),
+            lagged: state.lagged,
+            journal_latest: journal.latest(),
+            topics,
+        }
+    }
+
+    /// Count every message published on `bus`
+    pub fn spawn(&self, bus: &Bus) {
+        let registry = self.clone();
+        let mut rx = bus.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(message) => registry.published(message.topic()),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(_) => break,
+                }
+            }
+        });
+    }
+}
+
+/// One stream client counted by the `TopicRegistry`, forgotten when dropped
+pub struct FeedRegistration {
+    registry: TopicRegistry,
+    id: u64,
+}
+
+impl FeedRegistration {
+    /// A message of `topic` the client will never receive
+    pub fn dropped(&self, topic: &'static str) {
+        let mut state = self.registry.state.lock().unwrap();
+        state.topics.entry(topic).or_default().dropped += 1;
+    }
+
+    /// Messages the client missed by falling behind the bus
+    pub fn lagged(&self, missed: u64) {
+        self.registry.state.lock().unwrap().lagged += missed;
+    }
+}
+
+impl Drop for FeedRegistration {
+    fn drop(&mut self) {
+        self.registry.state.lock().unwrap().feeds.remove(&self.id);
+    }
+}
+
+/// Subscribers, publish rate, drops and journal position of every bus topic
+#[get("/bus/topics")]
+async fn list_topics(
+    registry: Data<TopicRegistry>,
+    bus: Data<Bus>,
+    journal: Data<Journal>,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(registry.stats(&bus, &journal)))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_topics);
+}
@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+
+/// Physical unit of a signal value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Unit {
+    Rpm,
+    Percent,
+    Celsius,
+    Fahrenheit,
+    KiloPascal,
+    Psi,
+    KilometersPerHour,
+    MilesPerHour,
+    Meters,
+    Feet,
+    Kilometers,
+    Miles,
+    LitersPer100Km,
+    MilesPerGallon,
+    Milliseconds,
+    Degrees,
+    Boolean,
+    /// Unitless enumerations and raw counts (gear position, fan level)
+    Raw,
+}
+
+impl Unit {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Unit::Rpm => "rpm",
+            Unit::Percent => "%",
+            Unit::Celsius => "°C",
+            Unit::Fahrenheit => "°F",
+            Unit::KiloPascal => "kPa",
+            Unit::Psi => "psi",
+            Unit::KilometersPerHour => "km/h",
+            Unit::MilesPerHour => "mph",
+            Unit::Meters => "m",
+            Unit::Feet => "ft",
+            Unit::Kilometers => "km",
+            Unit::Miles => "mi",
+            Unit::LitersPer100Km => "L/100km",
+            Unit::MilesPerGallon => "mpg",
+            Unit::Milliseconds => "ms",
+            Unit::Degrees => "°",
+            Unit::Boolean => "",
+            Unit::Raw => "",
+        }
+    }
+
+    /// Unit this signal is presented in for `system`
+    pub fn in_system(self, system: UnitSystem) -> Unit {
+        match (self, system) {
+            (Unit::Celsius, UnitSystem::Imperial) => Unit::Fahrenheit,
+            (Unit::KiloPascal, UnitSystem::Imperial) => Unit::Psi,
+            (Unit::KilometersPerHour, UnitSystem::Imperial) => Unit::MilesPerHour,
+            (Unit::Meters, UnitSystem::Imperial) => Unit::Feet,
+            (Unit::Kilometers, UnitSystem::Imperial) => Unit::Miles,
+            (Unit::LitersPer100Km, UnitSystem::Imperial) => Unit::MilesPerGallon,
+            (Unit::Fahrenheit, UnitSystem::Metric) => Unit::Celsius,
+            (Unit::Psi, UnitSystem::Metric) => Unit::KiloPascal,
+            (Unit::MilesPerHour, UnitSystem::Metric) => Unit::KilometersPerHour,
+            (Unit::Feet, UnitSystem::Metric) => Unit::Meters,
+            (Unit::Miles, UnitSystem::Metric) => Unit::Kilometers,
+            (Unit::MilesPerGallon, UnitSystem::Metric) => Unit::LitersPer100Km,
+            (unit, _) => unit,
+        }
+    }
+
+    /// Convert `value` from this unit to `target`, or `None` if the units are unrelated
+    pub fn convert(self, value: f64, target: Unit) -> Option<f64> {
+        match (self, target) {
+            (from, to) if from == to => Some(value),
+            (Unit::KilometersPerHour, Unit::MilesPerHour) => Some(kmh_to_mph(value)),
+            (Unit::MilesPerHour, Unit::KilometersPerHour) => Some(mph_to_kmh(value)),
+            (Unit::Celsius, Unit::Fahrenheit) => Some(celsius_to_fahrenheit(value)),
+            (Unit::Fahrenheit, Unit::Celsius) => Some(fahrenheit_to_celsius(value)),
+            (Unit::KiloPascal, Unit::Psi) => Some(kpa_to_psi(value)),
+            (Unit::Psi, Unit::KiloPascal) => Some(psi_to_kpa(value)),
+            (Unit::Meters, Unit::Feet) => Some(meters_to_feet(value)),
+            (Unit::Feet, Unit::Meters) => Some(feet_to_meters(value)),
+            (Unit::Kilometers, Unit::Miles) => Some(kmh_to_mph(value)),
+            (Unit::Miles, Unit::Kilometers) => Some(mph_to_kmh(value)),
+            (Unit::LitersPer100Km, Unit::MilesPerGallon) => Some(l100km_to_mpg(value)),
+            (Unit::MilesPerGallon, Unit::LitersPer100Km) => Some(mpg_to_l100km(value)),
+            _ => None,
+        }
+    }
+}
+
+/// Unit system requested by API clients (`?units=imperial`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitSystem {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+const KM_PER_MILE: f64 = 1.609_344;
+const KPA_PER_PSI: f64 = 6.894_757;
+const METERS_PER_FOOT: f64 = 0.3048;
+/// L/100km × mpg (US gallons) is constant: consumption and economy are reciprocal
+const L100KM_MPG_PRODUCT: f64 = 235.214_583;
+
+pub fn kmh_to_mph(kmh: f64) -> f64 {
+    kmh / KM_PER_MILE
+}
+
+pub fn mph_to_kmh(mph: f64) -> f64 {
+    mph * KM_PER_MILE
+}
+
+pub fn celsius_to_fahrenheit(celsius: f64) -> f64 {
+    celsius * 9.0 / 5.0 + 32.0
+}
+
+pub fn fahrenheit_to_celsius(fahrenheit: f64) -> f64 {
+    (fahrenheit - 32.0) * 5.0 / 9.0
+}
+
+pub fn kpa_to_psi(kpa: f64) -> f64 {
+    kpa / KPA_PER_PSI
+}
+
+pub fn psi_to_kpa(psi: f64) -> f64 {
+    psi * KPA_PER_PSI
+}
+
+pub fn meters_to_feet(meters: f64) -> f64 {
+    meters / METERS_PER_FOOT
+}
+
+pub fn feet_to_meters(feet: f64) -> f64 {
+    feet * METERS_PER_FOOT
+}
+
+/// Convert fuel consumption to US miles per gallon (0 L/100km maps to 0 mpg)
+pub fn l100km_to_mpg(liters_per_100km: f64) -> f64 {
+    if liters_per_100km == 0.0 {
+        0.0
+    } else {
+        L100KM_MPG_PRODUCT / liters_per_100km
+    }
+}
+
+/// Convert US miles per gallon to fuel consumption (0 mpg maps to 0 L/100km)
+pub fn mpg_to_l100km(mpg: f64) -> f64 {
+    if mpg == 0.0 {
+        0.0
+    } else {
+        L100KM_MPG_PRODUCT / mpg
+    }
+}
@@ -1,45 +1,348 @@
-use actix::AsyncContext;
-use actix::{Actor, StreamHandler};
+use std::time::Duration;
+
+use actix::{Actor, ActorFutureExt, SpawnHandle, StreamHandler};
+use actix::{ActorContext, AsyncContext};
 use actix_web::web::Data;
-use actix_web::{get, web, HttpRequest, HttpResponse};
+use actix_web::{get, web, HttpRequest, HttpResponse, ResponseError};
 use actix_web_actors::ws;
-use lapin::Channel;
-
-use sqlx::SqlitePool;
-use tokio::sync::broadcast;
 
 use crate::common::error::AppError;
-use crate::features::driving_step::DrivingStep;
+use crate::config::transport::StepTransport;
+use crate::core::bus::{BusMessage, SubscriptionFilter};
+use crate::core::connections::StreamSlot;
+use crate::core::feed::{ClientFeed, FeedEnd};
+use crate::core::journal::{Journal, Sequenced};
+use crate::core::playback::{self, PlaybackCommand, PlaybackState, Timeline};
+use crate::core::scrub::Scrub;
+use crate::core::selection::{Selection, SelectionRequest};
+use crate::core::session::{Ack, ResumeQuery, SessionOpened, SessionStore};
+use crate::core::timestamps::TimestampFormat;
+use crate::core::topics::TopicRegistry;
+use crate::features::command::model::{CommandAck, DeviceQuery};
+use crate::features::command::{
+    controller as command_controller, CommandDispatcher, CommandMessage,
+};
+use crate::features::driving_step::{service, DrivingStep};
+use crate::features::ingest::model::StepFrame;
+use crate::features::ingest::{controller as ingest_controller, StepAssembler};
+use crate::features::subscription::{LiveFilter, StreamTransport, SubscriptionRegistry};
+use crate::features::tenant::{Role, TenantScope};
+use crate::features::validation::FrameValidator;
 
 #[derive(actix::Message)]
 #[rtype(result = "()")]
 struct BroadcastMessage(String);
 
+/// Close the connection, sent when its subscription goes away
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+struct SubscriptionEnded;
+
 struct WsConn {
-    rx: broadcast::Receiver<DrivingStep>,
-    pool: SqlitePool,
-    channel: Channel,
+    journal: Journal,
+    sessions: SessionStore,
+    topics: TopicRegistry,
+    /// Resumption token of the connection's session
+    token: String,
+    /// Number of this connection, telling it from later ones resuming the same session
+    connection: u64,
+    /// Whether the session existed before this connection
+    resumed: bool,
+    /// Latest journal message the client acknowledged when connecting
+    acked: u64,
+    transport: StepTransport,
+    assembler: StepAssembler,
+    validator: FrameValidator,
+    /// Filter of the connection before the client chose topics and CAN IDs
+    base_filter: LiveFilter,
+    /// Topics and CAN IDs chosen with `subscribe` and `unsubscribe` messages
+    selection: Selection,
+    /// `base_filter` narrowed by `selection`, applied to every message sent
+    filter: LiveFilter,
+    /// Tenant of the API key the connection was opened with
+    tenant: Option<String>,
+    /// Whether the connection was opened with a viewer key, which cannot send steps
+    read_only: bool,
+    /// Stored steps replayed to this client only
+    playback: Option<Timeline>,
+    /// Pending send of the next played step, set while playing
+    playback_timer: Option<SpawnHandle>,
+    /// Place of the connection among the stream clients, given back when the actor stops
+    _slot: StreamSlot,
+    commands: CommandDispatcher,
+    /// Device receiving its commands on this connection, with the number `connect` gave it
+    device: Option<(String, u64)>,
+}
+
+impl WsConn {
+    fn send_error(ctx: &mut ws::WebsocketContext<Self>, error: impl ToString, code: u16) {
+        let response = serde_json::json!({ "error": error.to_string(), "code": code });
+        ctx.text(response.to_string());
+    }
+
+    fn send_status(&self, ctx: &mut ws::WebsocketContext<Self>, state: PlaybackState) {
+        if let Some(timeline) = &self.playback {
+            if let Ok(status) = serde_json::to_string(&timeline.status(state)) {
+                ctx.text(status);
+            }
+        }
+    }
+
+    fn state(&self) -> PlaybackState {
+        if self.playback_timer.is_some() {
+            PlaybackState::Playing
+        } else {
+            PlaybackState::Paused
+        }
+    }
+
+    fn cancel_timer(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
+        if let Some(timer) = self.playback_timer.take() {
+            ctx.cancel_future(timer);
+        }
+    }
+
+    fn schedule(&mut self, delay: Duration, ctx: &mut ws::WebsocketContext<Self>) {
+        self.cancel_timer(ctx);
+        self.playback_timer = Some(ctx.run_later(delay, |act, ctx| act.play_next(ctx)));
+    }
+
+    /// Send the next stored step through the connection filter, marked with `"playback": true`
+    fn play_next(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
+        self.playback_timer = None;
+        let Some((step, wait)) = self.playback.as_mut().and_then(Timeline::advance) else {
+            self.send_status(ctx, PlaybackState::Finished);
+            return;
+        };
+
+        let message = BusMessage::DrivingStep(step);
+        if let Some(filter) = self
+            .filter
+            .current()
+            .filter(|filter| filter.accepts(&message))
+        {
+            for payload in filter.payloads(&message).unwrap_or_default() {
+                let Ok(mut value) = serde_json::from_str::<serde_json::Value>(&payload) else {
+                    continue;
+                };
+                if let Some(fields) = value.as_object_mut() {
+                    fields.insert("playback".to_string(), serde_json::Value::Bool(true));
+                }
+                ctx.text(value.to_string());
+            }
+        }
+        self.schedule(wait, ctx);
+    }
+
+    fn handle_playback(&mut self, command: PlaybackCommand, ctx: &mut ws::WebsocketContext<Self>) {
+        match command {
+            PlaybackCommand::Play { from, to, speed } => {
+                let speed = speed
+                    .or(self.playback.as_ref().map(|timeline| timeline.speed))
+                    .unwrap_or(1.0);
+                let bounds = playback::validate_speed(speed).and_then(|_| {
+                    let from = from.as_deref().map(playback::parse_instant).transpose()?;
+                    let to = to.as_deref().map(playback::parse_instant).transpose()?;
+                    Ok((from, to))
+                });
+                let (from, to) = match bounds {
+                    Ok(bounds) => bounds,
+                    Err(error) => return Self::send_error(ctx, error, 400),
+                };
+
+                // Resume the current playback unless new bounds were given
+                if let (Some(timeline), None, None) = (self.playback.as_mut(), from, to) {
+                    timeline.speed = speed;
+                    self.schedule(Duration::ZERO, ctx);
+                    return self.send_status(ctx, PlaybackState::Playing);
+                }
+
+                self.cancel_timer(ctx);
+                let tenant = self.tenant.clone();
+                let load = actix::fut::wrap_future(async move {
+                    service::get_all_steps(tenant.as_deref()).await
+                });
+                ctx.spawn(load.map(move |result, act: &mut WsConn, ctx| match result {
+                    Ok(steps) => {
+                        let mut timeline = Timeline::new(steps, to, speed);
+                        if let Some(from) = from {
+                            timeline.seek(from);
+                        }
+                        act.playback = Some(timeline);
+                        act.schedule(Duration::ZERO, ctx);
+                        act.send_status(ctx, PlaybackState::Playing);
+                    }
+                    Err(error) => Self::send_error(ctx, error, 500),
+                }));
+            }
+            PlaybackCommand::Pause if self.playback.is_some() => {
+                self.cancel_timer(ctx);
+                self.send_status(ctx, PlaybackState::Paused);
+            }
+            PlaybackCommand::Seek { to } if self.playback.is_some() => {
+                let instant = match playback::parse_instant(&to) {
+                    Ok(instant) => instant,
+                    Err(error) => return Self::send_error(ctx, error, 400),
+                };
+                if let Some(timeline) = self.playback.as_mut() {
+                    timeline.seek(instant);
+                }
+                if self.playback_timer.is_some() {
+                    self.schedule(Duration::ZERO, ctx);
+                }
+                self.send_status(ctx, self.state());
+            }
+            PlaybackCommand::Stop if self.playback.is_some() => {
+                self.cancel_timer(ctx);
+                self.send_status(ctx, PlaybackState::Stopped);
+                self.playback = None;
+            }
+            PlaybackCommand::Pause | PlaybackCommand::Seek { .. } | PlaybackCommand::Stop => {
+                Self::send_error(
+                    ctx,
+                    "No playback in progress, send a play command first",
+                    400,
+                )
+            }
+        }
+    }
+
+    /// Change the topics and CAN IDs the client receives, confirming what it now receives
+    fn handle_selection(
+        &mut self,
+        request: SelectionRequest,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
+        let Some(filter) = self.base_filter.current() else {
+            return;
+        };
+        match self.selection.apply(request, &filter) {
+            Ok(changed) => {
+                self.sessions
+                    .select(&self.token, changed.topics.clone(), changed.can_ids.clone());
+                if let Ok(changed) = serde_json::to_string(&changed) {
+                    ctx.text(changed);
+                }
+            }
+            Err(error) => Self::send_error(ctx, error, 400),
+        }
+    }
+
+    /// Record the outcome the device of this connection reported for one of its commands
+    fn handle_command_ack(&mut self, ack: CommandAck, ctx: &mut ws::WebsocketContext<Self>) {
+        let Some((device_id, _)) = self.device.clone() else {
+            return Self::send_error(
+                ctx,
+                "Open the connection with ?device_id= to acknowledge commands",
+                400,
+            );
+        };
+        let acknowledge = actix::fut::wrap_future(async move {
+            command_controller::acknowledge(&device_id, &ack).await
+        });
+        ctx.spawn(acknowledge.map(|result, _act: &mut WsConn, ctx| {
+            if let Err(error) = result {
+                let code = error.status_code().as_u16();
+                Self::send_error(ctx, error, code);
+            }
+        }));
+    }
+
+    /// Hand a frame tagged with its step id to the assembler, reporting refusals to the client
+    fn handle_frame(&mut self, frame: StepFrame, ctx: &mut ws::WebsocketContext<Self>) {
+        let assembler = self.assembler.clone();
+        let validator = self.validator.clone();
+        let transport = self.transport.clone();
+        let tenant = self.tenant.clone();
+        let assemble = actix::fut::wrap_future(async move {
+            ingest_controller::assemble(
+                &assembler,
+                &validator,
+                vec![frame],
+                tenant.as_deref(),
+                &transport,
+            )
+            .await
+        });
+        ctx.spawn(assemble.map(|result, _act: &mut WsConn, ctx| match result {
+            Ok(report) => {
+                for error in report.errors {
+                    Self::send_error(ctx, error, 422);
+                }
+            }
+            Err(error) => Self::send_error(ctx, error, 400),
+        }));
+    }
 }
 
 impl Actor for WsConn {
     type Context = ws::WebsocketContext<Self>;
     fn started(&mut self, ctx: &mut Self::Context) {
-        let mut rx = self.rx.resubscribe();
+        // Commands queued for the device go out on this connection from now on
+        if let Some((device_id, connection)) = self.device.as_mut() {
+            *connection = self.commands.connect(device_id, ctx.address().recipient());
+        }
+        // Follow the journal before reading it, so no message falls between the two
+        let mut feed = ClientFeed::new(self.journal.subscribe(), self.filter.clone(), &self.topics);
+        let (replay, missed) = self.journal.since(self.acked);
+        let opened = SessionOpened {
+            kind: "session",
+            token: self.token.clone(),
+            resumed: self.resumed,
+            acked: self.acked,
+            missed,
+        };
+        if let Ok(opened) = serde_json::to_string(&opened) {
+            ctx.text(opened);
+        }
+        let filter = self.filter.clone();
         let addr = ctx.address();
 
         tokio::spawn(async move {
-            while let Ok(driving_step) = rx.recv().await {
-                // Handle DrivingStep messages for display
-                println!("\n🚗 DRIVING STEP RECEIVED VIA WEBSOCKET:");
-                driving_step.print_status();
-                driving_step.show_can_messages();
-
-                if let Ok(txt) = serde_json::to_string(&driving_step) {
-                    addr.do_send(BroadcastMessage(txt));
+            // Messages recorded since the client's last acknowledgement, then the live ones
+            let mut replayed = 0;
+            for entry in replay {
+                replayed = entry.seq;
+                if let Some(filter) = filter.current().filter(|f| f.accepts(&entry.message)) {
+                    send_numbered(&addr, &filter, &entry);
+                }
+            }
+            // Operators follow the live steps with `canbus_rmq_realtime monitor`
+            loop {
+                match feed.next().await {
+                    Ok((filter, entry)) if entry.seq > replayed => {
+                        send_numbered(&addr, &filter, &entry)
+                    }
+                    Ok(_) => continue,
+                    Err(FeedEnd::SubscriptionEnded) => {
+                        addr.do_send(SubscriptionEnded);
+                        break;
+                    }
+                    Err(FeedEnd::Closed) => break,
                 }
             }
         });
     }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        self.sessions.disconnect(&self.token, self.connection);
+        if let Some((device_id, connection)) = &self.device {
+            self.commands.disconnect(device_id, *connection);
+        }
+    }
+}
+
+/// Send the payloads of a journal message, each carrying the `seq` the client acknowledges
+fn send_numbered(addr: &actix::Addr<WsConn>, filter: &SubscriptionFilter, entry: &Sequenced) {
+    for payload in filter.payloads(&entry.message).unwrap_or_default() {
+        let Ok(mut value) = serde_json::from_str::<serde_json::Value>(&payload) else {
+            continue;
+        };
+        if let Some(fields) = value.as_object_mut() {
+            fields.insert("seq".to_string(), entry.seq.into());
+        }
+        addr.do_send(BroadcastMessage(value.to_string()));
+    }
 }
 
 impl actix::Handler<BroadcastMessage> for WsConn {
@@ -50,58 +353,104 @@ impl actix::Handler<BroadcastMessage> for WsConn {
     }
 }
 
+impl actix::Handler<CommandMessage> for WsConn {
+    type Result = ();
+
+    fn handle(&mut self, msg: CommandMessage, ctx: &mut Self::Context) {
+        ctx.text(msg.0);
+    }
+}
+
+impl actix::Handler<SubscriptionEnded> for WsConn {
+    type Result = ();
+
+    fn handle(&mut self, _msg: SubscriptionEnded, ctx: &mut Self::Context) {
+        ctx.close(Some(ws::CloseReason {
+            code: ws::CloseCode::Policy,
+            description: Some("Subscription deleted".to_string()),
+        }));
+        ctx.stop();
+    }
+}
+
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsConn {
     fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
         if let Ok(ws::Message::Text(text)) = msg {
             println!("🔍 Received message: {}", &text);
+            if let Ok(value) = serde_json::from_str::<serde_json::Value>(&text) {
+                // Acknowledged messages are not replayed when the session resumes
+                if value.get("action").and_then(serde_json::Value::as_str) == Some("ack") {
+                    match serde_json::from_value::<Ack>(value) {
+                        Ok(ack) => self.sessions.ack(&self.token, ack.seq),
+                        Err(error) => Self::send_error(ctx, error, 400),
+                    }
+                    return;
+                }
+                // Devices report the outcome of the commands sent to them
+                if value.get("action").and_then(serde_json::Value::as_str) == Some("command_ack") {
+                    match serde_json::from_value::<CommandAck>(value) {
+                        Ok(ack) => self.handle_command_ack(ack, ctx),
+                        Err(error) => Self::send_error(ctx, error, 400),
+                    }
+                    return;
+                }
+                // `subscribe` and `unsubscribe` messages narrow what the connection receives
+                if value.get("subscribe").is_some() || value.get("unsubscribe").is_some() {
+                    match serde_json::from_value::<SelectionRequest>(value) {
+                        Ok(request) => self.handle_selection(request, ctx),
+                        Err(error) => Self::send_error(ctx, error, 400),
+                    }
+                    return;
+                }
+                // Messages with an `action` field drive the historical playback
+                if value.get("action").is_some() {
+                    match serde_json::from_value::<PlaybackCommand>(value) {
+                        Ok(command) => self.handle_playback(command, ctx),
+                        Err(error) => Self::send_error(ctx, error, 400),
+                    }
+                    return;
+                }
+                // Single CAN frames tagged with a `step_id` are assembled into steps
+                if value.get("step_id").is_some() && value.get("data").is_some() {
+                    if self.read_only {
+                        return Self::send_error(ctx, "Viewer keys cannot send frames", 403);
+                    }
+                    match serde_json::from_value::<StepFrame>(value) {
+                        Ok(frame) => self.handle_frame(frame, ctx),
+                        Err(error) => Self::send_error(ctx, error, 400),
+                    }
+                    return;
+                }
+            }
             // Try parsing as DrivingStep
-            if let Ok(driving_step) = serde_json::from_str::<DrivingStep>(&text) {
-                let pool = self.pool.clone();
-                let channel = self.channel.clone();
+            if let Ok(mut driving_step) = serde_json::from_str::<DrivingStep>(&text) {
+                if self.read_only {
+                    return Self::send_error(ctx, "Viewer keys cannot send steps", 403);
+                }
+                // Reject values the CAN encoding cannot represent (HTTP equivalent: 400)
+                if let Err(error) = driving_step.validate() {
+                    return Self::send_error(ctx, error, 400);
+                }
+
+                let transport = self.transport.clone();
                 let step_name = driving_step.step_name.clone();
+                driving_step.tenant = self.tenant.clone();
 
                 tokio::spawn(async move {
-                    // Convert to CAN messages and store
-                    let can_messages = driving_step.to_can_messages();
-
-                    // Store each CAN message in database
-                    for can_msg in &can_messages {
-                        match sqlx::query(
-                            "INSERT INTO can_messages (id, dlc, data, timestamp) 
-                             VALUES (?, ?, ?, ?)",
-                        )
-                        .bind(can_msg.id as i64)
-                        .bind(can_msg.dlc as i64)
-                        .bind(serde_json::to_string(&can_msg.data).unwrap_or_default())
-                        .bind(&can_msg.timestamp)
-                        .execute(&pool)
-                        .await
-                        {
-                            Ok(_) => println!("✅ Stored CAN message ID: 0x{:03X}", can_msg.id),
-                            Err(e) => println!(
-                                "❌ Failed to store CAN message ID: 0x{:03X}, Error: {}",
-                                can_msg.id, e
-                            ),
+                    // Convert to CAN messages, store them as one step and send the step notice
+                    let stored = match service::publish_step(&driving_step, &transport).await {
+                        Ok(stored) => stored,
+                        Err(e) => {
+                            println!("❌ Failed to process DrivingStep '{}': {}", step_name, e);
+                            return;
                         }
-                    }
-
-                    // Send step_name to RabbitMQ
-                    if let Ok(payload) = serde_json::to_vec(&step_name) {
-                        let _ = channel
-                            .basic_publish(
-                                "",                                  // Use default exchange for direct queue publishing
-                                crate::config::rabbitmq::QUEUE_NAME, // Direct to queue name
-                                lapin::options::BasicPublishOptions::default(),
-                                &payload,
-                                lapin::BasicProperties::default(),
-                            )
-                            .await;
-                    }
+                    };
 
                     println!(
-                        "📡 Processed DrivingStep '{}' via WebSocket: {} CAN messages stored, step_name sent to RabbitMQ",
+                        "📡 Processed DrivingStep '{}' via WebSocket: {} CAN messages stored as step {}, notice sent to RabbitMQ",
                         step_name,
-                        can_messages.len()
+                        stored.can_messages.len(),
+                        stored.step_id
                     );
                 });
             } else {
@@ -112,18 +461,70 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsConn {
 }
 
 #[get("/ws")]
+#[allow(clippy::too_many_arguments)] // one extractor per shared dependency
 async fn ws_handler(
     req: HttpRequest,
     stream: web::Payload,
-    channel: Data<Channel>,
-    tx: Data<broadcast::Sender<DrivingStep>>,
+    transport: Data<StepTransport>,
+    assembler: Data<StepAssembler>,
+    validator: Data<FrameValidator>,
+    journal: Data<Journal>,
+    sessions: Data<SessionStore>,
+    topics: Data<TopicRegistry>,
+    subscriptions: Data<SubscriptionRegistry>,
+    filter: web::Query<SubscriptionFilter>,
+    resume: web::Query<ResumeQuery>,
+    device: web::Query<DeviceQuery>,
+    commands: Data<CommandDispatcher>,
+    scope: TenantScope,
+    scrub: Scrub,
+    slot: StreamSlot,
 ) -> Result<HttpResponse, AppError> {
-    let rx = tx.subscribe();
-    let pool = crate::config::sqlite::get_pool().await?;
+    let tenant = scope.name().map(str::to_string);
+    let device = command_controller::ws_device(&device, &scope).await?;
+    // A resumed session keeps the filter it was opened with, other parameters are ignored
+    let resume = resume.into_inner().resume;
+    let (mut query, acked) = match &resume {
+        Some(token) => sessions.get(token, tenant.as_deref())?,
+        None => {
+            let query = SubscriptionFilter {
+                tenant: tenant.clone(),
+                ..filter.into_inner()
+            };
+            (query, journal.latest())
+        }
+    };
+    // Scrubbing follows the key of the connection, not that of the session, and so does
+    // the timestamp format
+    query.scrub = scrub.0;
+    query.timestamps = TimestampFormat::requested(req.query_string(), req.headers())?;
+    let base_filter = subscriptions.resolve(query.clone(), StreamTransport::Ws)?;
+    // A resumed session keeps the topics and CAN IDs its client chose
+    let selection = Selection::from_filter(&query);
+    let filter = LiveFilter::Selected(Box::new(base_filter.clone()), selection.clone());
+    let resumed = resume.is_some();
+    let (token, connection) = sessions.open(resume, query, acked);
     let actor = WsConn {
-        rx,
-        pool: pool.to_owned(),
-        channel: channel.get_ref().clone(),
+        journal: journal.get_ref().clone(),
+        sessions: sessions.get_ref().clone(),
+        topics: topics.get_ref().clone(),
+        token,
+        connection,
+        resumed,
+        acked,
+        transport: transport.get_ref().clone(),
+        assembler: assembler.get_ref().clone(),
+        validator: validator.get_ref().clone(),
+        base_filter,
+        selection,
+        filter,
+        tenant,
+        read_only: scope.role() == Role::Viewer,
+        playback: None,
+        playback_timer: None,
+        _slot: slot,
+        commands: commands.get_ref().clone(),
+        device: device.map(|device_id| (device_id, 0)),
     };
     ws::start(actor, &req, stream).map_err(AppError::from)
 }
@@ -0,0 +1,122 @@
+use actix_web::web::Data;
+use actix_web::{delete, get, post, put, web, HttpResponse, Result};
+use serde_json::json;
+
+use crate::common::error::AppError;
+use crate::config::outbox;
+use crate::config::rabbitmq;
+use crate::config::resilience::CircuitBreaker;
+use crate::config::settings::AppSettings;
+use crate::config::transport::{ChaosControl, ChaosSettings, ConsumerControl, StepTransport};
+use crate::core::connections::ConnectionLimiter;
+use crate::core::metrics;
+
+fn consumer_status(control: &ConsumerControl) -> HttpResponse {
+    HttpResponse::Ok().json(json!({ "paused": control.is_paused() }))
+}
+
+#[get("/admin/consumer")]
+pub async fn consumer(control: Data<ConsumerControl>) -> Result<HttpResponse, AppError> {
+    Ok(consumer_status(&control))
+}
+
+/// Stop processing step notices; they stay queued until `/admin/consumer/resume`
+#[post("/admin/consumer/pause")]
+pub async fn pause_consumer(control: Data<ConsumerControl>) -> Result<HttpResponse, AppError> {
+    control.pause();
+    println!("⏸️ Step-notice consumer paused");
+    Ok(consumer_status(&control))
+}
+
+#[post("/admin/consumer/resume")]
+pub async fn resume_consumer(control: Data<ConsumerControl>) -> Result<HttpResponse, AppError> {
+    control.resume();
+    println!("▶️ Step-notice consumer resumed");
+    Ok(consumer_status(&control))
+}
+
+/// Consumer side of the step-notice queue: deliveries in flight, processing time and backlog
+#[get("/admin/rabbitmq")]
+pub async fn rabbitmq_status(
+    transport: Data<StepTransport>,
+    control: Data<ConsumerControl>,
+    settings: Data<AppSettings>,
+) -> Result<HttpResponse, AppError> {
+    let mut status = serde_json::to_value(metrics::consumer().stats())?;
+    status["transport"] = json!(transport.kind().as_str());
+    status["connected"] = json!(transport.is_connected());
+    status["reconnects"] = json!(transport.reconnects());
+    status["paused"] = json!(control.is_paused());
+    status["queue"] = json!(settings.rabbitmq.queue);
+    status["prefetch"] = json!(rabbitmq::PREFETCH_COUNT);
+    Ok(HttpResponse::Ok().json(status))
+}
+
+/// Transport faults currently applied to step notices, with what they affected so far
+#[get("/admin/chaos")]
+pub async fn chaos_status(chaos: Data<ChaosControl>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(chaos.status()))
+}
+
+/// Drop, duplicate or delay published step notices; omitted settings are turned off
+#[put("/admin/chaos")]
+pub async fn set_chaos(
+    chaos: Data<ChaosControl>,
+    settings: web::Json<ChaosSettings>,
+) -> Result<HttpResponse, AppError> {
+    let status = chaos
+        .set(settings.into_inner())
+        .map_err(AppError::bad_request)?;
+    println!(
+        "🐒 Transport chaos: drop {}%, duplicate {}%, delay {} ms",
+        status.settings.drop_percent, status.settings.duplicate_percent, status.settings.delay_ms
+    );
+    Ok(HttpResponse::Ok().json(status))
+}
+
+/// Turn the transport faults off, answering with the final counters
+#[delete("/admin/chaos")]
+pub async fn clear_chaos(chaos: Data<ChaosControl>) -> Result<HttpResponse, AppError> {
+    let status = chaos.clear();
+    println!("🐒 Transport chaos off");
+    Ok(HttpResponse::Ok().json(status))
+}
+
+/// State of the step-notice circuit breaker, with the notices waiting in the outbox
+#[get("/admin/breaker")]
+pub async fn breaker_status(breaker: Data<CircuitBreaker>) -> Result<HttpResponse, AppError> {
+    let pending = outbox::count().await?;
+    let mut status = serde_json::to_value(breaker.status())?;
+    status["pending"] = json!(pending);
+    Ok(HttpResponse::Ok().json(status))
+}
+
+/// WebSocket and SSE clients connected, per peer address, with the limits in force
+#[get("/admin/connections")]
+pub async fn connections(limiter: Data<ConnectionLimiter>) -> Result<HttpResponse, AppError> {
+    let limits = limiter.limits();
+    let mut status = serde_json::to_value(limiter.connected())?;
+    status["max_total"] = json!(limits.max_total);
+    status["max_per_ip"] = json!(limits.max_per_ip);
+    status["retry_after_secs"] = json!(limits.retry_after.as_secs());
+    Ok(HttpResponse::Ok().json(status))
+}
+
+/// Server address, broker, queue, database and bus capacity in force, without passwords
+#[get("/admin/settings")]
+pub async fn current_settings(settings: Data<AppSettings>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(settings.redacted()))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(consumer)
+        .service(pause_consumer)
+        .service(resume_consumer)
+        .service(rabbitmq_status)
+        .service(chaos_status)
+        .service(set_chaos)
+        .service(clear_chaos)
+        .service(breaker_status)
+        .service(connections)
+        .service(current_settings);
+}
@@ -0,0 +1,85 @@
+use crate::common::error::AppError;
+use crate::core::can::CanId;
+use crate::features::annotation::model::{Annotation, AnnotationQuery, AnnotationRequest};
+use crate::features::annotation::service;
+use crate::features::driving_step::service as step_service;
+use crate::features::tenant::TenantScope;
+
+fn validate(request: &AnnotationRequest) -> Result<(), AppError> {
+    if request.text.trim().is_empty() {
+        return Err(AppError::bad_request("Annotation text must not be empty"));
+    }
+    if request.author.trim().is_empty() {
+        return Err(AppError::bad_request("Annotation author must not be empty"));
+    }
+    if request.tags.iter().any(|tag| tag.trim().is_empty()) {
+        return Err(AppError::bad_request("Annotation tags must not be empty"));
+    }
+    Ok(())
+}
+
+/// Annotate a step the scope can read, or one of its frames
+pub async fn create(
+    step_id: &str,
+    request: AnnotationRequest,
+    scope: &TenantScope,
+) -> Result<Annotation, AppError> {
+    validate(&request)?;
+    let stored = step_service::get_step_frames(step_id, scope.name())
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Driving step '{}'", step_id)))?;
+    if let Some(can_id) = request.can_id {
+        if !stored
+            .can_messages
+            .iter()
+            .any(|frame| CanId::from(frame.id) == can_id)
+        {
+            return Err(AppError::bad_request(format!(
+                "Driving step '{}' has no frame {}",
+                step_id, can_id
+            )));
+        }
+    }
+
+    let annotation = Annotation {
+        id: uuid::Uuid::new_v4().to_string(),
+        step_id: step_id.to_string(),
+        can_id: request.can_id,
+        text: request.text.trim().to_string(),
+        tags: request
+            .tags
+            .iter()
+            .map(|tag| tag.trim().to_string())
+            .collect(),
+        author: request.author.trim().to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    service::store_annotation(&annotation, scope.name()).await?;
+
+    Ok(annotation)
+}
+
+pub async fn list(
+    step_id: &str,
+    query: &AnnotationQuery,
+    scope: &TenantScope,
+) -> Result<Vec<Annotation>, AppError> {
+    let mut annotations = service::get_annotations(step_id, scope.name()).await?;
+    annotations.retain(|annotation| {
+        query
+            .tag
+            .as_ref()
+            .is_none_or(|tag| annotation.tags.contains(tag))
+            && query
+                .can_id
+                .is_none_or(|can_id| annotation.can_id == Some(can_id))
+    });
+    Ok(annotations)
+}
+
+pub async fn delete(step_id: &str, id: &str, scope: &TenantScope) -> Result<(), AppError> {
+    if !service::delete_annotation(step_id, id, scope.name()).await? {
+        return Err(AppError::not_found(format!("Annotation '{}'", id)));
+    }
+    Ok(())
+}
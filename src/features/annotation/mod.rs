@@ -0,0 +1,44 @@
+pub mod controller;
+pub mod model;
+pub mod service;
+
+use actix_web::{delete, get, post, web, HttpResponse, Result};
+
+use crate::common::error::AppError;
+use crate::features::tenant::TenantScope;
+
+pub use model::Annotation;
+use model::{AnnotationQuery, AnnotationRequest};
+
+/// Document a stored step, or one of its frames, found worth a note during review
+#[post("/driving-steps/{step_id}/annotations")]
+pub async fn create(
+    path: web::Path<String>,
+    request: web::Json<AnnotationRequest>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    let annotation = controller::create(&path, request.into_inner(), &scope).await?;
+    Ok(HttpResponse::Created().json(annotation))
+}
+
+#[get("/driving-steps/{step_id}/annotations")]
+pub async fn list(
+    path: web::Path<String>,
+    query: web::Query<AnnotationQuery>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::list(&path, &query, &scope).await?))
+}
+
+#[delete("/driving-steps/{step_id}/annotations/{id}")]
+pub async fn remove(
+    path: web::Path<(String, String)>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    controller::delete(&path.0, &path.1, &scope).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(create).service(list).service(remove);
+}
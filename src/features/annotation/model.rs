@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::can::CanId;
+
+/// Note left on a stored step during review, stored in the `annotations` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub id: String,
+    pub step_id: String,
+    /// Frame of the step the note is about, the whole step when absent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub can_id: Option<CanId>,
+    pub text: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub author: String,
+    pub created_at: String,
+}
+
+/// Body accepted by `POST /driving-steps/{step_id}/annotations`
+#[derive(Debug, Deserialize)]
+pub struct AnnotationRequest {
+    pub can_id: Option<CanId>,
+    pub text: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub author: String,
+}
+
+/// Query parameters accepted by `GET /driving-steps/{step_id}/annotations`
+#[derive(Debug, Deserialize)]
+pub struct AnnotationQuery {
+    /// Only return annotations carrying this tag
+    pub tag: Option<String>,
+    /// Only return annotations about this frame
+    pub can_id: Option<CanId>,
+}
@@ -0,0 +1,90 @@
+use sqlx::any::AnyRow;
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::core::can::CanId;
+use crate::features::annotation::model::Annotation;
+
+fn annotation_from_row(row: &AnyRow) -> Result<Annotation, AppError> {
+    let can_id: Option<i64> = row.try_get("can_id")?;
+    let tags: String = row.try_get("tags")?;
+
+    Ok(Annotation {
+        id: row.try_get("id")?,
+        step_id: row.try_get("step_id")?,
+        can_id: can_id
+            .map(|id| CanId::new(id as u32))
+            .transpose()
+            .map_err(|e| AppError::internal_server_error(e.to_string()))?,
+        text: row.try_get("text")?,
+        tags: serde_json::from_str(&tags)?,
+        author: row.try_get("author")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+pub async fn store_annotation(
+    annotation: &Annotation,
+    tenant: Option<&str>,
+) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(
+        "INSERT INTO annotations (id, step_id, can_id, text, tags, author, tenant, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+    )
+    .bind(&annotation.id)
+    .bind(&annotation.step_id)
+    .bind(annotation.can_id.map(|id| u32::from(id) as i64))
+    .bind(&annotation.text)
+    .bind(serde_json::to_string(&annotation.tags)?)
+    .bind(&annotation.author)
+    .bind(tenant)
+    .bind(&annotation.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Annotations of a step, oldest first
+///
+/// With a `tenant`, annotations written by others are not returned; `None` reads every one.
+pub async fn get_annotations(
+    step_id: &str,
+    tenant: Option<&str>,
+) -> Result<Vec<Annotation>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(
+        "SELECT id, step_id, can_id, text, tags, author, created_at FROM annotations
+         WHERE step_id = $1 AND ($2 IS NULL OR tenant = $2)
+         ORDER BY created_at ASC, rowid ASC",
+    )
+    .bind(step_id)
+    .bind(tenant)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(annotation_from_row).collect()
+}
+
+/// Delete one annotation of a step, returning whether it existed
+pub async fn delete_annotation(
+    step_id: &str,
+    id: &str,
+    tenant: Option<&str>,
+) -> Result<bool, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let result = sqlx::query(
+        "DELETE FROM annotations WHERE id = $1 AND step_id = $2 AND ($3 IS NULL OR tenant = $3)",
+    )
+    .bind(id)
+    .bind(step_id)
+    .bind(tenant)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,7 @@
+use crate::common::error::AppError;
+use crate::features::anomaly::model::{AnomalyDetected, AnomalyQuery};
+use crate::features::anomaly::service;
+
+pub async fn list(query: &AnomalyQuery) -> Result<Vec<AnomalyDetected>, AppError> {
+    service::get_anomalies(query.signal.as_deref(), query.limit).await
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::broadcast;
+
+use crate::core::bus::{Bus, BusMessage};
+use crate::core::signals::SIGNALS;
+use crate::core::units::Unit;
+use crate::features::anomaly::model::AnomalyDetected;
+use crate::features::anomaly::service;
+use crate::features::driving_step::DrivingStep;
+
+/// Weight of the newest value in the moving statistics
+const ALPHA: f64 = 0.1;
+/// Values seen before a signal's statistics are trusted
+const WARMUP_SAMPLES: u32 = 10;
+
+/// Exponentially weighted mean and variance of one signal
+#[derive(Debug, Clone, Default)]
+struct SignalStats {
+    mean: f64,
+    variance: f64,
+    samples: u32,
+}
+
+impl SignalStats {
+    fn update(&mut self, value: f64) {
+        if self.samples == 0 {
+            self.mean = value;
+        } else {
+            let delta = value - self.mean;
+            self.mean += ALPHA * delta;
+            self.variance = (1.0 - ALPHA) * (self.variance + ALPHA * delta * delta);
+        }
+        self.samples = self.samples.saturating_add(1);
+    }
+}
+
+/// Flags signal values deviating from their EWMA by more than `sigma` standard deviations
+#[derive(Clone)]
+pub struct AnomalyDetector {
+    stats: Arc<Mutex<HashMap<&'static str, SignalStats>>>,
+    sigma: f64,
+}
+
+impl AnomalyDetector {
+    pub fn new(sigma: f64) -> Self {
+        AnomalyDetector {
+            stats: Arc::new(Mutex::new(HashMap::new())),
+            sigma,
+        }
+    }
+
+    /// Check every numeric signal of `step` against its history, then fold it in
+    pub fn observe(&self, step: &DrivingStep) -> Vec<AnomalyDetected> {
+        let Ok(value) = serde_json::to_value(step) else {
+            return Vec::new();
+        };
+
+        let mut stats = self.stats.lock().unwrap();
+        let mut anomalies = Vec::new();
+        for signal in SIGNALS.iter().filter(|signal| signal.unit != Unit::Boolean) {
+            let Some(current) = value.pointer(&signal.pointer()).and_then(|v| v.as_f64()) else {
+                continue;
+            };
+
+            let entry = stats.entry(signal.name).or_default();
+            // Deviations below one raw step are quantization noise, not anomalies
+            let stddev = entry.variance.sqrt().max(signal.scale);
+            let deviation = (current - entry.mean).abs() / stddev;
+            if entry.samples >= WARMUP_SAMPLES && deviation > self.sigma {
+                anomalies.push(AnomalyDetected {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    signal: signal.name.to_string(),
+                    value: current,
+                    mean: entry.mean,
+                    stddev,
+                    sigma: deviation,
+                    step_name: step.step_name.clone(),
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                });
+            }
+            entry.update(current);
+        }
+        anomalies
+    }
+
+    /// Watch every DrivingStep on the bus, storing and publishing the anomalies found
+    pub fn spawn(&self, bus: &Bus) {
+        let detector = self.clone();
+        let bus = bus.clone();
+        let mut rx = bus.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                let step = match rx.recv().await {
+                    Ok(BusMessage::DrivingStep(step)) => step,
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(_) => break,
+                };
+
+                for anomaly in detector.observe(&step) {
+                    println!(
+                        "📈 Anomaly on '{}': {} ({:.1}σ from mean {:.1})",
+                        anomaly.signal, anomaly.value, anomaly.sigma, anomaly.mean
+                    );
+                    if let Err(e) = service::store_anomaly(&anomaly).await {
+                        println!("❌ Failed to store anomaly: {}", e);
+                    }
+                    let _ = bus.send(BusMessage::Anomaly(anomaly));
+                }
+            }
+        });
+    }
+}
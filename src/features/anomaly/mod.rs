@@ -0,0 +1,21 @@
+pub mod controller;
+pub mod detector;
+pub mod model;
+pub mod service;
+
+use actix_web::{get, web, HttpResponse, Result};
+
+use crate::common::error::AppError;
+
+pub use detector::AnomalyDetector;
+pub use model::AnomalyDetected;
+use model::AnomalyQuery;
+
+#[get("/anomalies")]
+pub async fn list(query: web::Query<AnomalyQuery>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::list(&query).await?))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list);
+}
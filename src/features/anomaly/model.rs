@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+/// A signal value far outside its recent behaviour
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnomalyDetected {
+    pub id: String,
+    /// Signal name from the registry in `core::signals`
+    pub signal: String,
+    pub value: f64,
+    /// Exponentially weighted mean before this value
+    pub mean: f64,
+    /// Exponentially weighted standard deviation before this value
+    pub stddev: f64,
+    /// Distance from the mean in standard deviations
+    pub sigma: f64,
+    pub step_name: String,
+    pub timestamp: String,
+}
+
+fn default_limit() -> u32 {
+    100
+}
+
+/// Query parameters accepted by `GET /anomalies`
+#[derive(Debug, Deserialize)]
+pub struct AnomalyQuery {
+    /// Only return anomalies of this signal
+    pub signal: Option<String>,
+    /// Maximum number of anomalies, newest first
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+}
@@ -0,0 +1,59 @@
+use sqlx::any::AnyRow;
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::features::anomaly::model::AnomalyDetected;
+
+fn anomaly_from_row(row: &AnyRow) -> Result<AnomalyDetected, AppError> {
+    Ok(AnomalyDetected {
+        id: row.try_get("id")?,
+        signal: row.try_get("signal")?,
+        value: row.try_get("value")?,
+        mean: row.try_get("mean")?,
+        stddev: row.try_get("stddev")?,
+        sigma: row.try_get("sigma")?,
+        step_name: row.try_get("step_name")?,
+        timestamp: row.try_get("timestamp")?,
+    })
+}
+
+pub async fn store_anomaly(anomaly: &AnomalyDetected) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(
+        "INSERT INTO anomalies (id, signal, value, mean, stddev, sigma, step_name, timestamp)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+    )
+    .bind(&anomaly.id)
+    .bind(&anomaly.signal)
+    .bind(anomaly.value)
+    .bind(anomaly.mean)
+    .bind(anomaly.stddev)
+    .bind(anomaly.sigma)
+    .bind(&anomaly.step_name)
+    .bind(&anomaly.timestamp)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Most recent anomalies first, optionally restricted to one signal
+pub async fn get_anomalies(
+    signal: Option<&str>,
+    limit: u32,
+) -> Result<Vec<AnomalyDetected>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(
+        "SELECT id, signal, value, mean, stddev, sigma, step_name, timestamp FROM anomalies
+         WHERE $1 IS NULL OR signal = $1
+         ORDER BY timestamp DESC LIMIT $2",
+    )
+    .bind(signal)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(anomaly_from_row).collect()
+}
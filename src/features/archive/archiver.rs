@@ -0,0 +1,217 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::common::error::AppError;
+use crate::config::frame_store::{frame_store, FrameRange};
+use crate::config::object_store::ObjectStore;
+use crate::core::bus::Bus;
+use crate::core::clock::TimeSource;
+use crate::features::archive::model::{ArchiveReport, Segment};
+use crate::features::archive::service;
+use crate::features::driving_step::model::{StepFilter, StoredStep};
+use crate::features::history::service as history_service;
+use crate::features::system::{self, SystemEventKind};
+use crate::features::tag::model::TagTarget;
+use crate::features::tag::service as tag_service;
+
+/// Most steps archived by one run, the rest waiting for the next one
+const BATCH_STEPS: usize = 500;
+
+/// Moves the frames of steps older than `after` out of the database into zstd-compressed
+/// NDJSON segment files, listed in the `index.json` of the archive directory or bucket
+///
+/// Each run writes one segment. Tags, annotations and decoded signal values of archived
+/// steps stay in the database; `GET /driving-steps` reads the segments back for queries
+/// with a time range.
+#[derive(Clone)]
+pub struct Archive {
+    /// Directory or bucket holding the segments and their index
+    store: ObjectStore,
+    /// Age after which frames are archived, archiving being off when `None`
+    after: Option<Duration>,
+    /// Bus receiving a `frames_archived` system event for every run archiving steps
+    bus: Bus,
+    last_report: Arc<Mutex<Option<ArchiveReport>>>,
+    /// Held by a run while it writes the index, so timer and manual runs never interleave
+    running: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl Archive {
+    pub fn new(store: ObjectStore, after: Option<Duration>, bus: Bus) -> Self {
+        Archive {
+            store,
+            after,
+            bus,
+            last_report: Arc::default(),
+            running: Arc::default(),
+        }
+    }
+
+    /// Path or URL of the archive
+    pub fn location(&self) -> String {
+        self.store.location("")
+    }
+
+    pub fn after(&self) -> Option<Duration> {
+        self.after
+    }
+
+    /// Report of the latest run, `None` before the first one
+    pub fn last_report(&self) -> Option<ArchiveReport> {
+        self.last_report.lock().unwrap().clone()
+    }
+
+    /// Segments written so far, oldest first
+    pub async fn segments(&self) -> Result<Vec<Segment>, AppError> {
+        service::read_index(&self.store).await
+    }
+
+    /// Archive up to `BATCH_STEPS` steps stored before the archive window
+    ///
+    /// The segment and the index are written before the frames are deleted, so a run
+    /// interrupted in between archives the same steps again under the same segment name.
+    pub async fn run(&self) -> Result<ArchiveReport, AppError> {
+        let after = self.after.ok_or_else(|| {
+            AppError::bad_request("Archiving is disabled, set ARCHIVE_AFTER_DAYS")
+        })?;
+        let _running = self.running.lock().await;
+        let started_at = chrono::Utc::now();
+        let cutoff = started_at
+            - chrono::Duration::from_std(after)
+                .map_err(|e| AppError::internal_server_error(e.to_string()))?;
+
+        let (step_ids, remaining) = history_service::get_steps_before(cutoff, BATCH_STEPS).await?;
+        let mut report = ArchiveReport {
+            started_at: started_at.to_rfc3339(),
+            cutoff: cutoff.to_rfc3339(),
+            remaining,
+            ..Default::default()
+        };
+        let mut steps = Vec::new();
+        for step_id in &step_ids {
+            let range = FrameRange {
+                step_id: Some(step_id),
+                ..FrameRange::default()
+            };
+            steps.extend(frame_store().query_range(range).await?);
+        }
+        if steps.is_empty() {
+            *self.last_report.lock().unwrap() = Some(report.clone());
+            return Ok(report);
+        }
+
+        let segment = service::write_segment(&self.store, &steps).await?;
+        let mut segments = service::read_index(&self.store).await?;
+        segments.retain(|written| written.file != segment.file);
+        segments.push(segment.clone());
+        service::write_index(&self.store, &segments).await?;
+
+        let seqs: Vec<u64> = steps
+            .iter()
+            .flat_map(|step| &step.can_messages)
+            .filter_map(|frame| frame.seq)
+            .collect();
+        report.frames = frame_store().prune(&seqs).await?;
+        report.steps = steps.len() as u64;
+        report.segment = Some(segment.file);
+
+        *self.last_report.lock().unwrap() = Some(report.clone());
+        system::controller::publish(
+            &self.bus,
+            SystemEventKind::FramesArchived,
+            format!(
+                "Archived {} step(s) stored before {}",
+                report.steps, report.cutoff
+            ),
+            serde_json::to_value(&report)?,
+        )
+        .await;
+        Ok(report)
+    }
+
+    /// Archived steps of `tenant`, or of every tenant, matching `filter`, oldest first
+    ///
+    /// Only segments overlapping the time range of the filter are read. Archived steps are
+    /// all stamped on the wall clock.
+    pub async fn matching(
+        &self,
+        tenant: Option<&str>,
+        filter: &StepFilter,
+    ) -> Result<Vec<StoredStep>, AppError> {
+        if filter.clock == Some(TimeSource::Simulated) {
+            return Ok(Vec::new());
+        }
+        let tagged = match &filter.tag {
+            Some(tag) => Some(tag_service::tagged(TagTarget::Step, tag).await?),
+            None => None,
+        };
+
+        let mut seen = HashSet::new();
+        let mut steps = Vec::new();
+        for segment in service::read_index(&self.store).await? {
+            let (Some(first), Some(last)) = (
+                service::captured_at(&segment.from),
+                service::captured_at(&segment.to),
+            ) else {
+                continue;
+            };
+            let overlaps = filter.from.is_none_or(|from| last >= from)
+                && filter.to.is_none_or(|to| first < to);
+            if !overlaps {
+                continue;
+            }
+            for step in service::read_segment(&self.store, &segment).await? {
+                let started_at = step
+                    .can_messages
+                    .iter()
+                    .filter_map(|frame| service::captured_at(&frame.timestamp))
+                    .min();
+                let in_range = started_at.is_some_and(|started_at| {
+                    filter.from.is_none_or(|from| started_at >= from)
+                        && filter.to.is_none_or(|to| started_at < to)
+                });
+                let matches = in_range
+                    && tenant.is_none_or(|tenant| step.tenant.as_deref() == Some(tenant))
+                    && filter.can_id.is_none_or(|can_id| {
+                        step.can_messages
+                            .iter()
+                            .any(|frame| frame.id as u32 == u32::from(can_id))
+                    })
+                    && tagged
+                        .as_ref()
+                        .is_none_or(|tagged| tagged.contains(&step.step_id));
+                if matches && seen.insert(step.step_id.clone()) {
+                    steps.push(step);
+                }
+            }
+        }
+        Ok(steps)
+    }
+
+    /// Run every `interval`, until the process exits; does nothing while archiving is off
+    pub fn spawn(&self, interval: Duration) {
+        if self.after.is_none() {
+            return;
+        }
+        let archive = self.clone();
+        let mut tick = tokio::time::interval(interval);
+        tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        tokio::spawn(async move {
+            loop {
+                tick.tick().await;
+                match archive.run().await {
+                    Ok(report) if report.steps > 0 => println!(
+                        "📦 Archived {} step(s), {} frame(s) to {}",
+                        report.steps,
+                        report.frames,
+                        report.segment.as_deref().unwrap_or_default()
+                    ),
+                    Ok(_) => {}
+                    Err(e) => println!("❌ Archiving failed: {}", e),
+                }
+            }
+        });
+    }
+}
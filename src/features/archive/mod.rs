@@ -0,0 +1,32 @@
+pub mod archiver;
+pub mod model;
+pub mod service;
+
+use actix_web::web::Data;
+use actix_web::{get, post, web, HttpResponse, Result};
+use serde_json::json;
+
+use crate::common::error::AppError;
+
+pub use archiver::Archive;
+
+/// Archive window, the segments written so far and the report of the latest run
+#[get("/admin/archive")]
+pub async fn segments(archive: Data<Archive>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(json!({
+        "location": archive.location(),
+        "after_s": archive.after().map(|after| after.as_secs_f64()),
+        "segments": archive.segments().await?,
+        "last_report": archive.last_report(),
+    })))
+}
+
+/// Archive the steps older than the archive window now, without waiting for the timer
+#[post("/admin/archive")]
+pub async fn archive_now(archive: Data<Archive>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(archive.run().await?))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(segments).service(archive_now);
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+/// One segment file of the archive, as listed in its `index.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Segment {
+    /// File name in the archive directory, `segment-<first_seq>-<last_seq>.ndjson.zst`
+    pub file: String,
+    /// RFC3339 capture time of the oldest frame of the segment
+    pub from: String,
+    /// RFC3339 capture time of the newest frame of the segment
+    pub to: String,
+    /// Sequence numbers of the first and last frames archived in the segment
+    pub first_seq: u64,
+    pub last_seq: u64,
+    pub steps: u64,
+    pub frames: u64,
+    /// Size of the file
+    pub bytes: u64,
+    /// Whether the compressed file is encrypted under the key of the server
+    pub sealed: bool,
+    /// RFC3339 time the segment was written
+    pub created_at: String,
+}
+
+/// Outcome of one archive run, returned by `POST /admin/archive`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ArchiveReport {
+    /// RFC3339 time the run started
+    pub started_at: String,
+    /// Steps whose frames all date from before this RFC3339 time were archived
+    pub cutoff: String,
+    /// Segment written by the run, `None` when no step was old enough
+    pub segment: Option<String>,
+    /// Steps moved to the segment
+    pub steps: u64,
+    /// Frames moved to the segment and deleted from the database
+    pub frames: u64,
+    /// Whether older steps are left for the next run
+    pub remaining: bool,
+}
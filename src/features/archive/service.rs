@@ -0,0 +1,104 @@
+use chrono::{DateTime, FixedOffset};
+
+use crate::common::error::AppError;
+use crate::config::encryption;
+use crate::config::object_store::ObjectStore;
+use crate::features::archive::model::Segment;
+use crate::features::driving_step::model::StoredStep;
+
+/// Index of the segments, in the order they were written
+const INDEX_FILE: &str = "index.json";
+/// zstd level of the segments, the library's default trade-off
+const ZSTD_LEVEL: i32 = 3;
+
+/// Capture time of a frame, `None` for timestamps that are not RFC3339
+pub fn captured_at(timestamp: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(timestamp).ok()
+}
+
+/// Segments listed in the index of `store`, none before the first run
+pub async fn read_index(store: &ObjectStore) -> Result<Vec<Segment>, AppError> {
+    match store.get(INDEX_FILE).await? {
+        Some(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Replace the index of `store`, which readers never see half written
+pub async fn write_index(store: &ObjectStore, segments: &[Segment]) -> Result<(), AppError> {
+    let index = serde_json::to_vec_pretty(segments)?;
+    store.put(INDEX_FILE, index, "application/json").await?;
+    Ok(())
+}
+
+/// Write `steps` to a new segment of `store`, one JSON step with its frames per line
+///
+/// The file is compressed with zstd, then encrypted when a key is in force like the frames
+/// of the database.
+pub async fn write_segment(store: &ObjectStore, steps: &[StoredStep]) -> Result<Segment, AppError> {
+    let frames = steps.iter().flat_map(|step| &step.can_messages);
+    let seqs: Vec<u64> = frames.clone().filter_map(|frame| frame.seq).collect();
+    let times: Vec<DateTime<FixedOffset>> = frames
+        .clone()
+        .filter_map(|frame| captured_at(&frame.timestamp))
+        .collect();
+    let (Some(first_seq), Some(last_seq)) = (seqs.iter().min(), seqs.iter().max()) else {
+        return Err(AppError::internal_server_error(
+            "No numbered frame to archive",
+        ));
+    };
+    let (Some(from), Some(to)) = (times.iter().min(), times.iter().max()) else {
+        return Err(AppError::internal_server_error(
+            "No frame with an RFC3339 timestamp to archive",
+        ));
+    };
+
+    let mut ndjson = Vec::new();
+    for step in steps {
+        serde_json::to_writer(&mut ndjson, step)?;
+        ndjson.push(b'\n');
+    }
+    let compressed = zstd::encode_all(ndjson.as_slice(), ZSTD_LEVEL)?;
+    let sealed = encryption::key().is_some();
+    let contents = encryption::seal_frame(&compressed);
+
+    let file = format!("segment-{:012}-{:012}.ndjson.zst", first_seq, last_seq);
+    let bytes = contents.len() as u64;
+    store.put(&file, contents, "application/zstd").await?;
+    Ok(Segment {
+        file,
+        from: from.to_rfc3339(),
+        to: to.to_rfc3339(),
+        first_seq: *first_seq,
+        last_seq: *last_seq,
+        steps: steps.len() as u64,
+        frames: frames.count() as u64,
+        bytes,
+        sealed,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// Steps of a segment of `store`, in the order they were archived
+pub async fn read_segment(
+    store: &ObjectStore,
+    segment: &Segment,
+) -> Result<Vec<StoredStep>, AppError> {
+    let contents = store.get(&segment.file).await?.ok_or_else(|| {
+        AppError::internal_server_error(format!(
+            "Archive segment {} is missing",
+            store.location(&segment.file)
+        ))
+    })?;
+    let compressed = if segment.sealed {
+        encryption::open_sealed_frame(&contents).map_err(AppError::internal_server_error)?
+    } else {
+        contents
+    };
+    let ndjson = zstd::decode_all(compressed.as_slice())?;
+    ndjson
+        .split(|byte| *byte == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_slice(line).map_err(AppError::from))
+        .collect()
+}
@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use crate::common::error::AppError;
+use crate::features::command::dispatcher::CommandDispatcher;
+use crate::features::command::model::{
+    AckRequest, Command, CommandAck, CommandQuery, CommandRequest, CommandStatus, DeviceQuery,
+    MAX_NAME_LEN,
+};
+use crate::features::command::service;
+use crate::features::device::service as device_service;
+use crate::features::tenant::{Role, TenantScope};
+
+/// Fail unless the device is registered in the scope of the request
+async fn check_device(id: &str, scope: &TenantScope) -> Result<(), AppError> {
+    match device_service::get_device(id, scope.name()).await? {
+        Some(_) => Ok(()),
+        None => Err(AppError::not_found(format!("Device '{}'", id))),
+    }
+}
+
+fn command_not_found(device_id: &str, id: &str) -> AppError {
+    AppError::not_found(format!("Command '{}' of device '{}'", id, device_id))
+}
+
+/// Queue a command for a device and send it right away when the device can receive it
+pub async fn send(
+    device_id: &str,
+    request: CommandRequest,
+    scope: &TenantScope,
+    dispatcher: &CommandDispatcher,
+) -> Result<Command, AppError> {
+    let name = request.name.trim();
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(AppError::bad_request(format!(
+            "Command name must be 1 to {} characters",
+            MAX_NAME_LEN
+        )));
+    }
+    let ttl = match request.ttl_seconds {
+        Some(seconds) if !seconds.is_finite() || seconds <= 0.0 => {
+            return Err(AppError::bad_request(format!(
+                "ttl_seconds must be positive, got {}",
+                seconds
+            )));
+        }
+        Some(seconds) => Duration::try_from_secs_f64(seconds)
+            .map_err(|_| AppError::bad_request("ttl_seconds is too large"))?,
+        None => dispatcher.ttl(),
+    };
+    check_device(device_id, scope).await?;
+
+    let now = chrono::Utc::now();
+    let expires_at = chrono::Duration::from_std(ttl)
+        .ok()
+        .and_then(|ttl| now.checked_add_signed(ttl))
+        .ok_or_else(|| AppError::bad_request("ttl_seconds is too large"))?;
+    let command = Command {
+        id: uuid::Uuid::new_v4().to_string(),
+        device_id: device_id.to_string(),
+        name: name.to_string(),
+        params: request.params,
+        status: CommandStatus::Pending,
+        delivered_via: None,
+        result: None,
+        error: None,
+        created_at: now.to_rfc3339(),
+        delivered_at: None,
+        acked_at: None,
+        expires_at: expires_at.to_rfc3339(),
+    };
+    service::store_command(&command).await?;
+    dispatcher.dispatch(command).await
+}
+
+pub async fn list(
+    device_id: &str,
+    query: &CommandQuery,
+    scope: &TenantScope,
+) -> Result<Vec<Command>, AppError> {
+    check_device(device_id, scope).await?;
+    service::get_commands(device_id, query.status, query.limit).await
+}
+
+pub async fn get(device_id: &str, id: &str, scope: &TenantScope) -> Result<Command, AppError> {
+    check_device(device_id, scope).await?;
+    service::get_command(device_id, id)
+        .await?
+        .ok_or_else(|| command_not_found(device_id, id))
+}
+
+/// Record the outcome a device reported for one of its commands
+///
+/// Fails with 409 once the command was acknowledged, failed or expired.
+pub async fn acknowledge(device_id: &str, ack: &CommandAck) -> Result<Command, AppError> {
+    let acked_at = chrono::Utc::now().to_rfc3339();
+    if let Some(command) =
+        service::acknowledge(device_id, &ack.command_id, &ack.ack, &acked_at).await?
+    {
+        return Ok(command);
+    }
+    match service::get_command(device_id, &ack.command_id).await? {
+        Some(command) => Err(AppError::conflict(format!(
+            "Command '{}' is already {}",
+            command.id,
+            command.status.as_str()
+        ))),
+        None => Err(command_not_found(device_id, &ack.command_id)),
+    }
+}
+
+/// `acknowledge` for devices reporting over HTTP
+pub async fn ack(
+    device_id: &str,
+    id: &str,
+    ack: AckRequest,
+    scope: &TenantScope,
+) -> Result<Command, AppError> {
+    check_device(device_id, scope).await?;
+    let ack = CommandAck {
+        command_id: id.to_string(),
+        ack,
+    };
+    acknowledge(device_id, &ack).await
+}
+
+/// Withdraw a command the device has not received yet
+pub async fn cancel(device_id: &str, id: &str, scope: &TenantScope) -> Result<(), AppError> {
+    check_device(device_id, scope).await?;
+    if service::cancel(device_id, id).await? {
+        return Ok(());
+    }
+    match service::get_command(device_id, id).await? {
+        Some(command) => Err(AppError::conflict(format!(
+            "Command '{}' is already {}, only pending commands can be cancelled",
+            command.id,
+            command.status.as_str()
+        ))),
+        None => Err(command_not_found(device_id, id)),
+    }
+}
+
+/// Device whose commands a `/ws` connection receives, checked against the scope of its key
+pub async fn ws_device(
+    query: &DeviceQuery,
+    scope: &TenantScope,
+) -> Result<Option<String>, AppError> {
+    let Some(device_id) = &query.device_id else {
+        return Ok(None);
+    };
+    if scope.role() == Role::Viewer {
+        return Err(AppError::forbidden(
+            "Viewer keys cannot receive device commands",
+        ));
+    }
+    check_device(device_id, scope).await?;
+    Ok(Some(device_id.clone()))
+}
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use actix::Recipient;
+use futures_util::StreamExt;
+use lapin::options::BasicAckOptions;
+
+use crate::common::error::AppError;
+use crate::config::rabbitmq;
+use crate::config::transport::StepTransport;
+use crate::features::command::controller;
+use crate::features::command::model::{Command, CommandEnvelope, CommandStatus, DeviceAck};
+use crate::features::command::service;
+
+/// Time between two sweeps expiring overdue commands and retrying pending ones
+const SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+/// Wait before consuming the acknowledgment queue again, or while there is no broker
+const ACK_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Command serialized for the websocket of its device
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+pub struct CommandMessage(pub String);
+
+/// Websocket connection a device receives its commands on
+struct Socket {
+    connection: u64,
+    recipient: Recipient<CommandMessage>,
+}
+
+/// Delivery of queued commands to their devices
+///
+/// A command goes out over the websocket the device opened with `/ws?device_id=`, or else on
+/// its `device_commands.<id>` queue when the transport has a broker. Without either it stays
+/// pending until the device connects. Devices report the outcome with a `command_ack`
+/// websocket message, on the `device_command_acks` queue or over HTTP; commands left
+/// unacknowledged past their `expires_at` expire.
+#[derive(Clone)]
+pub struct CommandDispatcher {
+    sockets: Arc<Mutex<HashMap<String, Socket>>>,
+    connections: Arc<AtomicU64>,
+    transport: StepTransport,
+    ttl: Duration,
+}
+
+impl CommandDispatcher {
+    pub fn new(transport: StepTransport, ttl: Duration) -> Self {
+        CommandDispatcher {
+            sockets: Arc::default(),
+            connections: Arc::default(),
+            transport,
+            ttl,
+        }
+    }
+
+    /// Time a device has to acknowledge commands queued without a `ttl_seconds`
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Make `recipient` the command channel of `device_id`, replacing an earlier websocket,
+    /// and send it the pending commands; returns the number to pass to `disconnect`
+    pub fn connect(&self, device_id: &str, recipient: Recipient<CommandMessage>) -> u64 {
+        let connection = self.connections.fetch_add(1, Ordering::Relaxed);
+        self.sockets.lock().unwrap().insert(
+            device_id.to_string(),
+            Socket {
+                connection,
+                recipient,
+            },
+        );
+
+        let dispatcher = self.clone();
+        let device_id = device_id.to_string();
+        tokio::spawn(async move {
+            if let Err(e) = dispatcher.flush(&device_id).await {
+                println!(
+                    "❌ Failed to send the pending commands of device '{}': {}",
+                    device_id, e
+                );
+            }
+        });
+        connection
+    }
+
+    /// Forget the websocket of `device_id`, unless a later connection replaced it
+    pub fn disconnect(&self, device_id: &str, connection: u64) {
+        let mut sockets = self.sockets.lock().unwrap();
+        if sockets
+            .get(device_id)
+            .is_some_and(|socket| socket.connection == connection)
+        {
+            sockets.remove(device_id);
+        }
+    }
+
+    /// Whether `device_id` has a websocket open for its commands
+    pub fn is_connected(&self, device_id: &str) -> bool {
+        self.sockets
+            .lock()
+            .unwrap()
+            .get(device_id)
+            .is_some_and(|socket| socket.recipient.connected())
+    }
+
+    /// Send a pending command to its device, recording the channel it went out on; the
+    /// command stays pending when the device has neither a websocket nor a broker queue
+    ///
+    /// The command is claimed as delivered before it is sent, so the sweep and a new
+    /// websocket never send it twice.
+    pub async fn dispatch(&self, command: Command) -> Result<Command, AppError> {
+        let channel = self.transport.amqp_channel();
+        let via = if self.is_connected(&command.device_id) {
+            "ws"
+        } else if channel.is_some() {
+            "amqp"
+        } else {
+            return Ok(command);
+        };
+        let delivered_at = chrono::Utc::now().to_rfc3339();
+        let Some(claimed) = service::mark_delivered(&command.id, via, &delivered_at).await? else {
+            // Already sent, acknowledged or expired meanwhile
+            return Ok(service::get_command(&command.device_id, &command.id)
+                .await?
+                .unwrap_or(command));
+        };
+
+        let payload = serde_json::to_string(&CommandEnvelope::from(&claimed))?;
+        if let (Some(channel), "amqp") = (channel, via) {
+            let ttl_ms = chrono::DateTime::parse_from_rfc3339(&claimed.expires_at)
+                .map(|expires_at| (expires_at.timestamp_millis() - now_ms()).max(1) as u64)
+                .unwrap_or(self.ttl.as_millis() as u64);
+            let published = rabbitmq::publish_device_command(
+                &channel,
+                &claimed.device_id,
+                payload.as_bytes(),
+                ttl_ms,
+            )
+            .await;
+            if let Err(e) = published {
+                println!(
+                    "⚠️ Command {} kept pending, publishing it failed: {}",
+                    claimed.id, e
+                );
+                return Ok(service::release(&claimed.id).await?.unwrap_or(claimed));
+            }
+        } else if let Some(socket) = self.sockets.lock().unwrap().get(&claimed.device_id) {
+            socket.recipient.do_send(CommandMessage(payload));
+        }
+        Ok(claimed)
+    }
+
+    /// Send the pending commands of a device, oldest first
+    pub async fn flush(&self, device_id: &str) -> Result<(), AppError> {
+        for command in service::get_pending(device_id).await? {
+            self.dispatch(command).await?;
+        }
+        Ok(())
+    }
+
+    /// Expire overdue commands, retry pending ones once a broker is reachable, and record
+    /// the acknowledgments devices publish on the `device_command_acks` queue
+    pub fn spawn(&self) {
+        let dispatcher = self.clone();
+        let mut sweep = tokio::time::interval(SWEEP_INTERVAL);
+        sweep.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        tokio::spawn(async move {
+            loop {
+                sweep.tick().await;
+                if let Err(e) = dispatcher.sweep().await {
+                    println!("❌ Commands: Sweeping open commands failed: {}", e);
+                }
+            }
+        });
+
+        let transport = self.transport.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Some(channel) = transport.amqp_channel() {
+                    match rabbitmq::subscribe_command_acks(&channel).await {
+                        Ok(mut consumer) => {
+                            while let Some(Ok(delivery)) = consumer.next().await {
+                                handle_ack(&delivery.data).await;
+                                let _ = delivery.ack(BasicAckOptions::default()).await;
+                            }
+                        }
+                        Err(e) => println!(
+                            "⚠️ Commands: Consuming '{}' failed: {}",
+                            rabbitmq::COMMAND_ACK_QUEUE,
+                            e
+                        ),
+                    }
+                }
+                tokio::time::sleep(ACK_RETRY_INTERVAL).await;
+            }
+        });
+    }
+
+    async fn sweep(&self) -> Result<(), AppError> {
+        let now = now_ms();
+        let has_broker = self.transport.amqp_channel().is_some();
+        for command in service::get_open().await? {
+            let overdue = chrono::DateTime::parse_from_rfc3339(&command.expires_at)
+                .is_ok_and(|expires_at| expires_at.timestamp_millis() <= now);
+            if overdue {
+                if let Some(expired) = service::expire(&command.id).await? {
+                    println!(
+                        "⌛ Command '{}' ({}) of device '{}' expired unacknowledged",
+                        expired.name, expired.id, expired.device_id
+                    );
+                }
+            } else if command.status == CommandStatus::Pending && has_broker {
+                self.dispatch(command).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Record an acknowledgment consumed from the broker, logging the malformed or late ones
+async fn handle_ack(data: &[u8]) {
+    let ack = match serde_json::from_slice::<DeviceAck>(data) {
+        Ok(ack) => ack,
+        Err(e) => {
+            println!("❌ Commands: Skipping malformed acknowledgment: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = controller::acknowledge(&ack.device_id, &ack.ack).await {
+        println!(
+            "⚠️ Commands: Acknowledgment of command {} refused: {}",
+            ack.ack.command_id, e
+        );
+    }
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
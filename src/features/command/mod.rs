@@ -0,0 +1,76 @@
+pub mod controller;
+pub mod dispatcher;
+pub mod model;
+pub mod service;
+
+use actix_web::web::Data;
+use actix_web::{delete, get, post, web, HttpResponse, Result};
+
+use crate::common::error::AppError;
+use crate::features::tenant::TenantScope;
+
+pub use dispatcher::{CommandDispatcher, CommandMessage};
+pub use model::Command;
+use model::{AckRequest, CommandQuery, CommandRequest};
+
+/// Commands of a device, newest first
+#[get("/devices/{id}/commands")]
+pub async fn list(
+    id: web::Path<String>,
+    query: web::Query<CommandQuery>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::list(&id, &query, &scope).await?))
+}
+
+/// Queue a command, sent at once over the device's websocket or command queue
+#[post("/devices/{id}/commands")]
+pub async fn send(
+    id: web::Path<String>,
+    request: web::Json<CommandRequest>,
+    scope: TenantScope,
+    dispatcher: Data<CommandDispatcher>,
+) -> Result<HttpResponse, AppError> {
+    let command = controller::send(&id, request.into_inner(), &scope, &dispatcher).await?;
+    Ok(HttpResponse::Created().json(command))
+}
+
+#[get("/devices/{id}/commands/{command_id}")]
+pub async fn get(
+    path: web::Path<(String, String)>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    let (id, command_id) = path.into_inner();
+    Ok(HttpResponse::Ok().json(controller::get(&id, &command_id, &scope).await?))
+}
+
+/// Outcome of a command, for devices reporting over HTTP
+#[post("/devices/{id}/commands/{command_id}/ack")]
+pub async fn ack(
+    path: web::Path<(String, String)>,
+    request: web::Json<AckRequest>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    let (id, command_id) = path.into_inner();
+    let command = controller::ack(&id, &command_id, request.into_inner(), &scope).await?;
+    Ok(HttpResponse::Ok().json(command))
+}
+
+/// Withdraw a pending command
+#[delete("/devices/{id}/commands/{command_id}")]
+pub async fn cancel(
+    path: web::Path<(String, String)>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    let (id, command_id) = path.into_inner();
+    controller::cancel(&id, &command_id, &scope).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list)
+        .service(send)
+        .service(get)
+        .service(ack)
+        .service(cancel);
+}
@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const MAX_NAME_LEN: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandStatus {
+    /// Queued until the device connects its websocket or a command queue is reachable
+    Pending,
+    /// Sent to the device, waiting for its acknowledgment
+    Delivered,
+    /// The device carried the command out
+    Acknowledged,
+    /// The device refused the command or failed to carry it out
+    Failed,
+    /// Not acknowledged before `expires_at`
+    Expired,
+}
+
+impl CommandStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CommandStatus::Pending => "pending",
+            CommandStatus::Delivered => "delivered",
+            CommandStatus::Acknowledged => "acknowledged",
+            CommandStatus::Failed => "failed",
+            CommandStatus::Expired => "expired",
+        }
+    }
+
+    /// Whether the device may still acknowledge the command
+    pub fn is_open(self) -> bool {
+        matches!(self, CommandStatus::Pending | CommandStatus::Delivered)
+    }
+}
+
+impl std::str::FromStr for CommandStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "pending" => Ok(CommandStatus::Pending),
+            "delivered" => Ok(CommandStatus::Delivered),
+            "acknowledged" => Ok(CommandStatus::Acknowledged),
+            "failed" => Ok(CommandStatus::Failed),
+            "expired" => Ok(CommandStatus::Expired),
+            other => Err(format!("Unknown command status '{}'", other)),
+        }
+    }
+}
+
+/// Instruction queued by the server for one device, with the outcome of its delivery
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Command {
+    pub id: String,
+    pub device_id: String,
+    /// What the device should do, e.g. `set_sample_rate` or `start_scenario`
+    pub name: String,
+    /// Arguments of the command, passed to the device as is
+    pub params: Value,
+    pub status: CommandStatus,
+    /// Channel the command went out on, `ws` or `amqp`
+    pub delivered_via: Option<String>,
+    /// What the device reported when acknowledging
+    pub result: Option<Value>,
+    /// Why the device refused the command or failed to carry it out
+    pub error: Option<String>,
+    pub created_at: String,
+    pub delivered_at: Option<String>,
+    pub acked_at: Option<String>,
+    /// RFC3339 time after which an unacknowledged command expires
+    pub expires_at: String,
+}
+
+/// Body of `POST /devices/{id}/commands`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommandRequest {
+    pub name: String,
+    #[serde(default)]
+    pub params: Value,
+    /// Time the device has to acknowledge, in seconds; defaults to `COMMAND_TTL`
+    pub ttl_seconds: Option<f64>,
+}
+
+/// Message carrying a command to its device, over the websocket or the command queue
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandEnvelope<'a> {
+    pub kind: &'static str,
+    pub id: &'a str,
+    pub name: &'a str,
+    pub params: &'a Value,
+    pub expires_at: &'a str,
+}
+
+impl<'a> From<&'a Command> for CommandEnvelope<'a> {
+    fn from(command: &'a Command) -> Self {
+        CommandEnvelope {
+            kind: "command",
+            id: &command.id,
+            name: &command.name,
+            params: &command.params,
+            expires_at: &command.expires_at,
+        }
+    }
+}
+
+fn default_ok() -> bool {
+    true
+}
+
+/// Outcome of a command reported by its device, the body of
+/// `POST /devices/{id}/commands/{command_id}/ack`
+#[derive(Debug, Clone, Deserialize)]
+pub struct AckRequest {
+    /// Whether the device carried the command out
+    #[serde(default = "default_ok")]
+    pub ok: bool,
+    pub result: Option<Value>,
+    pub error: Option<String>,
+}
+
+/// Acknowledgment sent as a `{"action": "command_ack"}` websocket message
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommandAck {
+    pub command_id: String,
+    #[serde(flatten)]
+    pub ack: AckRequest,
+}
+
+/// Acknowledgment published on the `device_command_acks` queue, naming its device
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceAck {
+    pub device_id: String,
+    #[serde(flatten)]
+    pub ack: CommandAck,
+}
+
+fn default_limit() -> u32 {
+    100
+}
+
+/// Query parameters accepted by `GET /devices/{id}/commands`
+#[derive(Debug, Deserialize)]
+pub struct CommandQuery {
+    pub status: Option<CommandStatus>,
+    /// Maximum number of commands, newest first
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+}
+
+/// Query parameter of `/ws` opening the command channel of a device
+#[derive(Debug, Default, Deserialize)]
+pub struct DeviceQuery {
+    pub device_id: Option<String>,
+}
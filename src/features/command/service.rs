@@ -0,0 +1,228 @@
+use sqlx::any::AnyRow;
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::features::command::model::{AckRequest, Command, CommandStatus};
+
+const COLUMNS: &str = "id, device_id, name, params, status, delivered_via, result, error, \
+                       created_at, delivered_at, acked_at, expires_at";
+
+fn command_from_row(row: &AnyRow) -> Result<Command, AppError> {
+    let params: String = row.try_get("params")?;
+    let status: String = row.try_get("status")?;
+    let result: Option<String> = row.try_get("result")?;
+
+    Ok(Command {
+        id: row.try_get("id")?,
+        device_id: row.try_get("device_id")?,
+        name: row.try_get("name")?,
+        params: serde_json::from_str(&params)?,
+        status: status.parse().map_err(AppError::internal_server_error)?,
+        delivered_via: row.try_get("delivered_via")?,
+        result: result
+            .map(|result| serde_json::from_str(&result))
+            .transpose()?,
+        error: row.try_get("error")?,
+        created_at: row.try_get("created_at")?,
+        delivered_at: row.try_get("delivered_at")?,
+        acked_at: row.try_get("acked_at")?,
+        expires_at: row.try_get("expires_at")?,
+    })
+}
+
+pub async fn store_command(command: &Command) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(&format!(
+        "INSERT INTO device_commands ({COLUMNS}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)"
+    ))
+    .bind(&command.id)
+    .bind(&command.device_id)
+    .bind(&command.name)
+    .bind(serde_json::to_string(&command.params)?)
+    .bind(command.status.as_str())
+    .bind(&command.delivered_via)
+    .bind(command.result.as_ref().map(|r| r.to_string()))
+    .bind(&command.error)
+    .bind(&command.created_at)
+    .bind(&command.delivered_at)
+    .bind(&command.acked_at)
+    .bind(&command.expires_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Commands of a device, newest first
+pub async fn get_commands(
+    device_id: &str,
+    status: Option<CommandStatus>,
+    limit: u32,
+) -> Result<Vec<Command>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(&format!(
+        "SELECT {COLUMNS} FROM device_commands
+         WHERE device_id = $1 AND ($2 IS NULL OR status = $2)
+         ORDER BY created_at DESC LIMIT $3"
+    ))
+    .bind(device_id)
+    .bind(status.map(CommandStatus::as_str))
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(command_from_row).collect()
+}
+
+pub async fn get_command(device_id: &str, id: &str) -> Result<Option<Command>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let row = sqlx::query(&format!(
+        "SELECT {COLUMNS} FROM device_commands WHERE id = $1 AND device_id = $2"
+    ))
+    .bind(id)
+    .bind(device_id)
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(command_from_row).transpose()
+}
+
+/// Commands of a device still waiting for a channel, oldest first
+pub async fn get_pending(device_id: &str) -> Result<Vec<Command>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(&format!(
+        "SELECT {COLUMNS} FROM device_commands
+         WHERE device_id = $1 AND status = 'pending' ORDER BY created_at ASC"
+    ))
+    .bind(device_id)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(command_from_row).collect()
+}
+
+/// Commands of every device that may still be acknowledged
+pub async fn get_open() -> Result<Vec<Command>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(&format!(
+        "SELECT {COLUMNS} FROM device_commands WHERE status IN ('pending', 'delivered')"
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(command_from_row).collect()
+}
+
+/// Record that a pending command went out on `via`, returning it when it was pending
+pub async fn mark_delivered(
+    id: &str,
+    via: &str,
+    delivered_at: &str,
+) -> Result<Option<Command>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let row = sqlx::query(&format!(
+        "UPDATE device_commands SET status = 'delivered', delivered_via = $1, delivered_at = $2
+         WHERE id = $3 AND status = 'pending' RETURNING {COLUMNS}"
+    ))
+    .bind(via)
+    .bind(delivered_at)
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(command_from_row).transpose()
+}
+
+/// Put back a command whose delivery failed, returning it when it was still delivered
+pub async fn release(id: &str) -> Result<Option<Command>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let row = sqlx::query(&format!(
+        "UPDATE device_commands SET status = 'pending', delivered_via = NULL, delivered_at = NULL
+         WHERE id = $1 AND status = 'delivered' RETURNING {COLUMNS}"
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(command_from_row).transpose()
+}
+
+/// Record the outcome reported by the device, returning the command when it was open
+pub async fn acknowledge(
+    device_id: &str,
+    id: &str,
+    ack: &AckRequest,
+    acked_at: &str,
+) -> Result<Option<Command>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+    let status = if ack.ok {
+        CommandStatus::Acknowledged
+    } else {
+        CommandStatus::Failed
+    };
+
+    let row = sqlx::query(&format!(
+        "UPDATE device_commands SET status = $1, result = $2, error = $3, acked_at = $4
+         WHERE id = $5 AND device_id = $6 AND status IN ('pending', 'delivered')
+         RETURNING {COLUMNS}"
+    ))
+    .bind(status.as_str())
+    .bind(ack.result.as_ref().map(|r| r.to_string()))
+    .bind(&ack.error)
+    .bind(acked_at)
+    .bind(id)
+    .bind(device_id)
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(command_from_row).transpose()
+}
+
+/// Mark an open command expired, returning it when it was still open
+pub async fn expire(id: &str) -> Result<Option<Command>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let row = sqlx::query(&format!(
+        "UPDATE device_commands SET status = 'expired'
+         WHERE id = $1 AND status IN ('pending', 'delivered') RETURNING {COLUMNS}"
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(command_from_row).transpose()
+}
+
+/// Withdraw a command not yet delivered, returning whether it was pending
+pub async fn cancel(device_id: &str, id: &str) -> Result<bool, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let result = sqlx::query(
+        "DELETE FROM device_commands WHERE id = $1 AND device_id = $2 AND status = 'pending'",
+    )
+    .bind(id)
+    .bind(device_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Forget the commands of a deleted device
+pub async fn delete_device_commands(device_id: &str) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query("DELETE FROM device_commands WHERE device_id = $1")
+        .bind(device_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use crate::common::error::AppError;
+use crate::config::transport::{ConsumerControl, StepTransport};
+use crate::core::bus::Bus;
+use crate::core::units::UnitSystem;
+use crate::core::{metrics, signals};
+use crate::features::dashboard::model::{BusStats, ConsumerHealth, DashboardSnapshot};
+use crate::features::device::service as device_service;
+use crate::features::driving_step::service as step_service;
+use crate::features::rule::RuleEngine;
+use crate::features::trip::{service as trip_service, TripSummary, TripTracker};
+
+pub async fn snapshot(
+    bus: &Bus,
+    rules: &RuleEngine,
+    trips: &TripTracker,
+    transport: &StepTransport,
+    consumer: &ConsumerControl,
+    units: UnitSystem,
+) -> Result<DashboardSnapshot, AppError> {
+    let latest_step = match step_service::get_last_step(None).await? {
+        Some(step) => signals::steps_in_units([&step], units)?.pop(),
+        None => None,
+    };
+    // The tracker holds the trip in progress; stored trips cover a restarted server
+    let trip = match trips.current() {
+        Some(trip) => Some(trip),
+        None => trip_service::get_last_trip().await?,
+    };
+
+    Ok(DashboardSnapshot {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        latest_step,
+        active_alerts: rules.active(),
+        trip: trip.map(TripSummary::from),
+        bus: BusStats {
+            subscribers: bus.receiver_count(),
+            queued: bus.len(),
+            stages: metrics::pipeline().stats(),
+        },
+        consumer: ConsumerHealth {
+            transport: transport.kind().as_str(),
+            connected: transport.is_connected(),
+            paused: consumer.is_paused(),
+        },
+        devices: device_service::get_devices(None).await?,
+    })
+}
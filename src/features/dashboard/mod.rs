@@ -0,0 +1,34 @@
+pub mod controller;
+pub mod model;
+
+use actix_web::web::Data;
+use actix_web::{get, web, HttpResponse, Result};
+
+use crate::common::error::AppError;
+use crate::config::transport::{ConsumerControl, StepTransport};
+use crate::core::bus::Bus;
+use crate::features::driving_step::model::StepQuery;
+use crate::features::rule::RuleEngine;
+use crate::features::trip::TripTracker;
+
+pub use model::DashboardSnapshot;
+
+/// Latest step, active alerts, trip, bus statistics, consumer health and device presence
+/// in one document
+#[get("/dashboard/snapshot")]
+pub async fn snapshot(
+    query: web::Query<StepQuery>,
+    bus: Data<Bus>,
+    rules: Data<RuleEngine>,
+    trips: Data<TripTracker>,
+    transport: Data<StepTransport>,
+    consumer: Data<ConsumerControl>,
+) -> Result<HttpResponse, AppError> {
+    let snapshot =
+        controller::snapshot(&bus, &rules, &trips, &transport, &consumer, query.units).await?;
+    Ok(HttpResponse::Ok().json(snapshot))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(snapshot);
+}
@@ -0,0 +1,42 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::core::metrics::StageStats;
+use crate::features::device::Device;
+use crate::features::rule::model::Rule;
+use crate::features::trip::TripSummary;
+
+/// Activity of the broadcast bus and latency of the step pipeline
+#[derive(Debug, Clone, Serialize)]
+pub struct BusStats {
+    /// Stream clients and background tasks receiving bus messages
+    pub subscribers: usize,
+    /// Messages not yet received by the slowest subscriber
+    pub queued: usize,
+    pub stages: Vec<StageStats>,
+}
+
+/// State of the step-notice consumer
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsumerHealth {
+    /// `amqp` or `memory`
+    pub transport: &'static str,
+    pub connected: bool,
+    pub paused: bool,
+}
+
+/// Response of `GET /dashboard/snapshot`, everything a dashboard shows on load
+#[derive(Debug, Clone, Serialize)]
+pub struct DashboardSnapshot {
+    pub generated_at: String,
+    /// Most recently stored step, in the requested unit system
+    pub latest_step: Option<Value>,
+    /// Rules matching the latest step
+    pub active_alerts: Vec<Rule>,
+    /// Trip in progress, or the last one
+    pub trip: Option<TripSummary>,
+    pub bus: BusStats,
+    pub consumer: ConsumerHealth,
+    /// Registered ingesting devices with their presence, by id
+    pub devices: Vec<Device>,
+}
@@ -0,0 +1,57 @@
+use crate::common::error::AppError;
+use crate::features::command::service as command_service;
+use crate::features::device::model::{Device, HeartbeatRequest};
+use crate::features::device::presence::DevicePresence;
+use crate::features::device::service;
+use crate::features::tenant::TenantScope;
+
+/// Device ids are 1 to 64 letters, digits, `.`, `_` or `-`
+fn validate_id(id: &str) -> Result<(), AppError> {
+    let valid = (1..=64).contains(&id.len())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if !valid {
+        return Err(AppError::bad_request(format!(
+            "Invalid device id '{}', expected 1 to 64 letters, digits, '.', '_' or '-'",
+            id
+        )));
+    }
+    Ok(())
+}
+
+/// Record a heartbeat, registering the device on its first one
+pub async fn heartbeat(
+    id: &str,
+    request: &HeartbeatRequest,
+    scope: &TenantScope,
+    presence: &DevicePresence,
+) -> Result<Device, AppError> {
+    validate_id(id)?;
+    let seen_at = chrono::Utc::now().to_rfc3339();
+    let device = service::record_heartbeat(id, request, scope.name(), &seen_at)
+        .await?
+        .ok_or_else(|| {
+            AppError::conflict(format!("Device '{}' is registered by another tenant", id))
+        })?;
+    presence.beat(id);
+    Ok(device)
+}
+
+pub async fn get(id: &str, scope: &TenantScope) -> Result<Device, AppError> {
+    service::get_device(id, scope.name())
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Device '{}'", id)))
+}
+
+pub async fn delete(
+    id: &str,
+    scope: &TenantScope,
+    presence: &DevicePresence,
+) -> Result<(), AppError> {
+    if !service::delete_device(id, scope.name()).await? {
+        return Err(AppError::not_found(format!("Device '{}'", id)));
+    }
+    presence.forget(id);
+    command_service::delete_device_commands(id).await
+}
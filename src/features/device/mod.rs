@@ -0,0 +1,53 @@
+pub mod controller;
+pub mod model;
+pub mod presence;
+pub mod service;
+
+use actix_web::web::Data;
+use actix_web::{delete, get, post, web, HttpResponse, Result};
+
+use crate::common::error::AppError;
+use crate::features::tenant::TenantScope;
+
+pub use model::Device;
+use model::HeartbeatRequest;
+pub use presence::DevicePresence;
+
+/// Registered devices of the scope with their presence, by id
+#[get("/devices")]
+pub async fn list(scope: TenantScope) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(service::get_devices(scope.name()).await?))
+}
+
+#[get("/devices/{id}")]
+pub async fn get(id: web::Path<String>, scope: TenantScope) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::get(&id, &scope).await?))
+}
+
+/// Heartbeat of an ingesting device, registering it on the first one
+#[post("/devices/{id}/heartbeat")]
+pub async fn heartbeat(
+    id: web::Path<String>,
+    request: web::Json<HeartbeatRequest>,
+    scope: TenantScope,
+    presence: Data<DevicePresence>,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::heartbeat(&id, &request, &scope, &presence).await?))
+}
+
+#[delete("/devices/{id}")]
+pub async fn remove(
+    id: web::Path<String>,
+    scope: TenantScope,
+    presence: Data<DevicePresence>,
+) -> Result<HttpResponse, AppError> {
+    controller::delete(&id, &scope, &presence).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list)
+        .service(get)
+        .service(heartbeat)
+        .service(remove);
+}
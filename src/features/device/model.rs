@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+/// Device ingesting telemetry (a simulator, a gateway, ...) known from its heartbeats
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Device {
+    pub id: String,
+    /// What the device is, e.g. `simulator` or `gateway`
+    pub kind: Option<String>,
+    pub firmware: Option<String>,
+    /// Version of the software sending the telemetry
+    pub version: Option<String>,
+    /// Tenant whose API key registered the device
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    /// Whether a heartbeat arrived within the device timeout
+    pub online: bool,
+    /// RFC3339 time of the first heartbeat
+    pub registered_at: String,
+    /// RFC3339 time of the latest heartbeat
+    pub last_seen_at: String,
+    pub heartbeats: u64,
+}
+
+/// Body of `POST /devices/{id}/heartbeat`, fields left out keeping their previous value
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct HeartbeatRequest {
+    pub kind: Option<String>,
+    pub firmware: Option<String>,
+    pub version: Option<String>,
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::common::error::AppError;
+use crate::core::bus::{Bus, BusMessage};
+use crate::features::device::model::Device;
+use crate::features::device::service;
+use crate::features::event::model::{EventKind, Severity};
+use crate::features::event::{service as event_service, Event};
+
+/// Diagnostic `device_offline` event of a device silent for longer than `timeout`
+pub fn offline_event(device: &Device, timeout: Duration) -> Event {
+    Event::new(
+        EventKind::DeviceOffline,
+        "device_presence",
+        format!(
+            "Device '{}' sent no heartbeat for {:.0} s, last seen at {}",
+            device.id,
+            timeout.as_secs_f64(),
+            device.last_seen_at
+        ),
+        None,
+    )
+    .with_severity(Severity::Warning)
+    .with_tenant(device.tenant.clone())
+    .with_payload(serde_json::json!({
+        "device_id": device.id,
+        "kind": device.kind,
+        "firmware": device.firmware,
+        "version": device.version,
+        "last_seen_at": device.last_seen_at,
+        "timeout_ms": timeout.as_millis() as u64,
+    }))
+}
+
+/// Time each online device has left to send its next heartbeat
+///
+/// A device going `timeout` without a heartbeat is marked offline in the `devices` table
+/// and reported with a `device_offline` event; its next heartbeat brings it back online.
+#[derive(Clone)]
+pub struct DevicePresence {
+    last_heartbeats: Arc<Mutex<HashMap<String, Instant>>>,
+    timeout: Duration,
+}
+
+impl DevicePresence {
+    /// Track the devices stored online, as if they had just sent a heartbeat, so devices
+    /// that went silent while the server was down are reported after `timeout`
+    pub async fn load(timeout: Duration) -> Result<Self, AppError> {
+        let presence = DevicePresence {
+            last_heartbeats: Arc::default(),
+            timeout,
+        };
+        for device in service::get_devices(None).await? {
+            if device.online {
+                presence.beat(&device.id);
+            }
+        }
+        Ok(presence)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Note a heartbeat of `id`
+    pub fn beat(&self, id: &str) {
+        self.last_heartbeats
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), Instant::now());
+    }
+
+    /// Stop tracking a deleted device
+    pub fn forget(&self, id: &str) {
+        self.last_heartbeats.lock().unwrap().remove(id);
+    }
+
+    /// Ids of the devices silent for longer than the timeout, no longer tracked
+    pub fn expire(&self) -> Vec<String> {
+        let mut last_heartbeats = self.last_heartbeats.lock().unwrap();
+        let expired: Vec<String> = last_heartbeats
+            .iter()
+            .filter(|(_, last)| last.elapsed() > self.timeout)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &expired {
+            last_heartbeats.remove(id);
+        }
+        expired
+    }
+
+    /// Mark silent devices offline, storing and publishing a `device_offline` event for each
+    pub fn spawn(&self, bus: &Bus) {
+        let presence = self.clone();
+        let bus = bus.clone();
+        let mut check = tokio::time::interval((self.timeout / 4).max(Duration::from_millis(100)));
+        check.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        tokio::spawn(async move {
+            loop {
+                check.tick().await;
+                for id in presence.expire() {
+                    let device = match service::set_offline(&id).await {
+                        Ok(Some(device)) => device,
+                        Ok(None) => continue,
+                        Err(e) => {
+                            println!("❌ Failed to mark device '{}' offline: {}", id, e);
+                            continue;
+                        }
+                    };
+                    let event = offline_event(&device, presence.timeout);
+                    println!("⚠️ {}", event.message);
+                    if let Err(e) = event_service::store_event(&event).await {
+                        println!("❌ Failed to store event '{}': {}", event.name, e);
+                    }
+                    let _ = bus.send(BusMessage::Event(event));
+                }
+            }
+        });
+    }
+}
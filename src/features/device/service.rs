@@ -0,0 +1,115 @@
+use sqlx::any::AnyRow;
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::config::db;
+use crate::features::device::model::{Device, HeartbeatRequest};
+
+const COLUMNS: &str =
+    "id, kind, firmware, version, tenant, online, registered_at, last_seen_at, heartbeats";
+
+fn device_from_row(row: &AnyRow) -> Result<Device, AppError> {
+    let heartbeats: i64 = row.try_get("heartbeats")?;
+
+    Ok(Device {
+        id: row.try_get("id")?,
+        kind: row.try_get("kind")?,
+        firmware: row.try_get("firmware")?,
+        version: row.try_get("version")?,
+        tenant: row.try_get("tenant")?,
+        online: db::flag(row, "online")?,
+        registered_at: row.try_get("registered_at")?,
+        last_seen_at: row.try_get("last_seen_at")?,
+        heartbeats: heartbeats as u64,
+    })
+}
+
+/// Record a heartbeat of `id`, registering the device under `tenant` on its first one
+///
+/// Returns `None` when the device is registered under another tenant.
+pub async fn record_heartbeat(
+    id: &str,
+    request: &HeartbeatRequest,
+    tenant: Option<&str>,
+    seen_at: &str,
+) -> Result<Option<Device>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let row = sqlx::query(&format!(
+        "INSERT INTO devices ({COLUMNS}) VALUES ($1, $2, $3, $4, $5, TRUE, $6, $6, 1)
+         ON CONFLICT (id) DO UPDATE SET
+             kind = COALESCE(excluded.kind, devices.kind),
+             firmware = COALESCE(excluded.firmware, devices.firmware),
+             version = COALESCE(excluded.version, devices.version),
+             online = TRUE,
+             last_seen_at = excluded.last_seen_at,
+             heartbeats = devices.heartbeats + 1
+         WHERE devices.tenant IS NOT DISTINCT FROM excluded.tenant
+         RETURNING {COLUMNS}"
+    ))
+    .bind(id)
+    .bind(&request.kind)
+    .bind(&request.firmware)
+    .bind(&request.version)
+    .bind(tenant)
+    .bind(seen_at)
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(device_from_row).transpose()
+}
+
+/// Devices of `tenant`, or of every tenant, by id
+pub async fn get_devices(tenant: Option<&str>) -> Result<Vec<Device>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(&format!(
+        "SELECT {COLUMNS} FROM devices WHERE $1 IS NULL OR tenant = $1 ORDER BY id ASC"
+    ))
+    .bind(tenant)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(device_from_row).collect()
+}
+
+pub async fn get_device(id: &str, tenant: Option<&str>) -> Result<Option<Device>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let row = sqlx::query(&format!(
+        "SELECT {COLUMNS} FROM devices WHERE id = $1 AND ($2 IS NULL OR tenant = $2)"
+    ))
+    .bind(id)
+    .bind(tenant)
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(device_from_row).transpose()
+}
+
+/// Mark a device offline, returning it when it was online
+pub async fn set_offline(id: &str) -> Result<Option<Device>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let row = sqlx::query(&format!(
+        "UPDATE devices SET online = FALSE WHERE id = $1 AND online RETURNING {COLUMNS}"
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(device_from_row).transpose()
+}
+
+/// Forget a device, returning whether it existed
+pub async fn delete_device(id: &str, tenant: Option<&str>) -> Result<bool, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let result = sqlx::query("DELETE FROM devices WHERE id = $1 AND ($2 IS NULL OR tenant = $2)")
+        .bind(id)
+        .bind(tenant)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
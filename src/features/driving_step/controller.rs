@@ -1,11 +1,313 @@
+use actix_web::http::header::EntityTag;
+
 use crate::common::error::AppError;
-use crate::features::driving_step::model::DrivingStep;
+use crate::common::http;
+use crate::common::pagination::{self, Page, Paging};
+use crate::common::projection::Projection;
+use crate::config::transport::StepTransport;
+use crate::core::can::{CanId, CanMessage, Endianness};
+use crate::core::signals;
+use crate::features::archive::Archive;
+use crate::features::driving_step::model::{
+    DecodeFailure, DrivingStep, EncodeQuery, EncodedStep, FrameQuery, IngestQuery, RecodeFailure,
+    RecodeReport, RecodeRequest, ReconstructRequest, StepFilter, StepListQuery, StoredStep,
+};
 use crate::features::driving_step::service;
+use crate::features::job::{Job, JobKind, JobProgress, JobQueue};
+use crate::features::tag::controller as tag_controller;
+use crate::features::tenant::TenantScope;
+use crate::features::vehicle::controller as vehicle_controller;
+
+/// Fields of a serialized step that are not signals of `core::signals::SIGNALS`
+const STEP_FIELDS: [&str; 3] = ["step_name", "step_id", "annotations"];
+
+/// Projection of `?fields=`, refusing the fields a serialized step never holds
+///
+/// A field is a signal path (`engine.rpm`), a group of signals (`engine`), or one of
+/// `STEP_FIELDS`.
+pub fn projection(fields: Option<&str>) -> Result<Option<Projection>, AppError> {
+    let Some(fields) = fields else {
+        return Ok(None);
+    };
+    let projection = Projection::parse(fields)?;
+    for field in projection.fields() {
+        let group = format!("{}.", field);
+        let known = STEP_FIELDS.contains(&field.as_str())
+            || field.starts_with("annotations.")
+            || signals::SIGNALS
+                .iter()
+                .any(|signal| signal.path == field || signal.path.starts_with(&group));
+        if !known {
+            return Err(AppError::bad_request(format!(
+                "Unknown field '{}', expected a signal path like engine.rpm, a group like engine, or one of {}",
+                field,
+                STEP_FIELDS.join(", ")
+            )));
+        }
+    }
+    Ok(Some(projection))
+}
+
+/// One page of the stored steps visible to `scope` matching the query, in storage order
+///
+/// With a time range, steps moved to the archive are read back from its segments and
+/// listed before the steps of the database, which are all stored after them.
+pub async fn list(
+    query: &StepListQuery,
+    scope: &TenantScope,
+    archive: &Archive,
+) -> Result<Page<DrivingStep>, AppError> {
+    let paging = Paging::new(query.limit, query.offset)?;
+    pagination::validate_range(query.from, query.to)?;
+    let filter = StepFilter {
+        tag: query
+            .tag
+            .as_deref()
+            .map(tag_controller::normalize)
+            .transpose()?,
+        clock: query.clock,
+        can_id: query.can_id,
+        from: query.from,
+        to: query.to,
+    };
+    if filter.from.is_none() && filter.to.is_none() {
+        let (steps, total) = service::get_step_page(scope.name(), &filter, paging).await?;
+        return Ok(Page::new(steps, total, paging));
+    }
+
+    let archived = archive.matching(scope.name(), &filter).await?;
+    let archived_total = archived.len() as u64;
+    let mut steps = service::steps_from_stored(
+        archived
+            .into_iter()
+            .skip(paging.offset as usize)
+            .take(paging.limit as usize)
+            .collect(),
+    );
+    // The rest of the page, and the number of live steps, come from the database
+    let live_paging = Paging {
+        limit: paging.limit - steps.len() as u32,
+        offset: (paging.offset as u64).saturating_sub(archived_total) as u32,
+    };
+    let (live, live_total) = service::get_step_page(scope.name(), &filter, live_paging).await?;
+    steps.extend(live);
+    Ok(Page::new(steps, archived_total + live_total, paging))
+}
+
+/// Validator of `GET /driving-steps/last`, from the sequence number of the latest frame
+pub async fn last_etag(scope: &TenantScope) -> Result<Option<EntityTag>, AppError> {
+    Ok(service::latest_seq(scope.name())
+        .await?
+        .map(|seq| http::weak_etag(format!("step-{}", seq))))
+}
+
+pub async fn get_last(scope: &TenantScope) -> Result<Option<DrivingStep>, AppError> {
+    service::get_last_step(scope.name()).await
+}
+
+/// Validate, store and publish a step; the consumer reconstructs and broadcasts it
+pub async fn ingest(
+    step: &DrivingStep,
+    query: &IngestQuery,
+    scope: &TenantScope,
+    transport: &StepTransport,
+) -> Result<StoredStep, AppError> {
+    // Encoding clamps out-of-range values, so they are rejected before anything is stored
+    step.validate()?;
+
+    let vehicle = vehicle_controller::for_ingestion(query.vehicle_id.as_deref()).await?;
+    let is_big_endian = match (query.endianness, &vehicle) {
+        (Some(endianness), _) => endianness.is_big_endian(),
+        (None, Some(vehicle)) => vehicle.endianness.is_big_endian(),
+        (None, None) => DrivingStep::get_endianness_from_env(),
+    };
+    // Profiles may require optional groups, without which the step could not be reconstructed
+    if let Some(profile) = vehicle
+        .as_ref()
+        .and_then(|vehicle| signals::profile(&vehicle.can_profile))
+    {
+        let missing = profile.missing(&step.to_can_messages_with_endian(is_big_endian));
+        if !missing.is_empty() {
+            let missing: Vec<String> = missing
+                .into_iter()
+                .map(|id| CanId::from(id).to_string())
+                .collect();
+            return Err(AppError::bad_request(format!(
+                "CAN profile '{}' requires frames {} the step does not carry",
+                profile.name,
+                missing.join(", ")
+            )));
+        }
+    }
+    service::publish_vehicle_step(
+        step,
+        is_big_endian,
+        query.vehicle_id.as_deref(),
+        scope.name(),
+        transport,
+    )
+    .await
+}
+
+/// Stored steps have no name of their own, so the step id stands in for it
+pub async fn get(step_id: &str, scope: &TenantScope) -> Result<DrivingStep, AppError> {
+    service::reconstruct_step(step_id, step_id.to_string(), scope.name())
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Driving step '{}'", step_id)))
+}
 
-pub async fn list() -> Result<Vec<DrivingStep>, AppError> {
-    service::get_all_steps().await
+pub async fn frames(
+    step_id: &str,
+    query: &FrameQuery,
+    scope: &TenantScope,
+) -> Result<StoredStep, AppError> {
+    let mut stored = service::get_step_frames(step_id, scope.name())
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Driving step '{}'", step_id)))?;
+    if let Some(can_id) = query.can_id {
+        stored
+            .can_messages
+            .retain(|frame| CanId::from(frame.id) == can_id);
+    }
+    Ok(stored)
 }
 
-pub async fn get_last() -> Result<Option<DrivingStep>, AppError> {
-    service::get_last_step().await
+/// The frame with `can_id` among the frames stored for a step
+pub async fn frame(
+    step_id: &str,
+    can_id: CanId,
+    scope: &TenantScope,
+) -> Result<CanMessage, AppError> {
+    let query = FrameQuery {
+        can_id: Some(can_id),
+    };
+    frames(step_id, &query, scope)
+        .await?
+        .can_messages
+        .pop()
+        .ok_or_else(|| AppError::not_found(format!("CAN frame {} of step '{}'", can_id, step_id)))
+}
+
+/// Frames the consumer would receive for `step`, without storing or publishing them
+pub fn encode(step: &DrivingStep, query: &EncodeQuery) -> Result<EncodedStep, AppError> {
+    let version = wire_format_version(query.version)?;
+    if query.crc {
+        return Err(AppError::bad_request(format!(
+            "Wire format version {} frames carry no CRC or rolling counter",
+            version
+        )));
+    }
+    // Encoding clamps out-of-range values, which would document frames the step cannot produce
+    step.validate()?;
+
+    let endian = query
+        .endianness
+        .unwrap_or_else(|| Endianness::from_is_big_endian(DrivingStep::get_endianness_from_env()));
+    Ok(EncodedStep {
+        version,
+        endian,
+        can_messages: step.to_can_messages_with_endian(endian.is_big_endian()),
+    })
+}
+
+/// Requested wire format version, the current one when omitted
+fn wire_format_version(version: Option<u32>) -> Result<u32, AppError> {
+    let version = version.unwrap_or(DrivingStep::WIRE_FORMAT_VERSION);
+    if version != DrivingStep::WIRE_FORMAT_VERSION {
+        return Err(AppError::bad_request(format!(
+            "Unsupported wire format version {} (expected {})",
+            version,
+            DrivingStep::WIRE_FORMAT_VERSION
+        )));
+    }
+    Ok(version)
+}
+
+/// Wire format version of a recode, checked before its job is queued
+fn check_recode(request: &RecodeRequest) -> Result<u32, AppError> {
+    let version = wire_format_version(request.version)?;
+    if request.from == Some(request.to) {
+        return Err(AppError::bad_request(format!(
+            "Frames are already stored in {} byte order",
+            request.to
+        )));
+    }
+    Ok(version)
+}
+
+/// Queue a recode job, see `GET /jobs/{id}` for its progress and report
+pub async fn submit_recode(jobs: &JobQueue, request: RecodeRequest) -> Result<Job, AppError> {
+    check_recode(&request)?;
+    jobs.submit(JobKind::Recode, None, move |progress| async move {
+        Ok(serde_json::to_value(recode(&request, &progress).await?)?)
+    })
+    .await
+}
+
+/// Rewrite stored steps in another byte order, step by step
+///
+/// A step that does not round-trip is left as stored and listed in the report's failures.
+pub async fn recode(
+    request: &RecodeRequest,
+    progress: &JobProgress,
+) -> Result<RecodeReport, AppError> {
+    let version = check_recode(request)?;
+
+    let step_ids =
+        service::get_steps_to_recode(request.to, request.from, request.vehicle_id.as_deref())
+            .await?;
+    let total = step_ids.len();
+    let mut report = RecodeReport {
+        to: request.to,
+        version,
+        dry_run: request.dry_run,
+        steps: 0,
+        frames: 0,
+        failures: Vec::new(),
+    };
+    for step_id in step_ids {
+        match service::recode_step(&step_id, request.to, request.dry_run).await {
+            Ok(frames) => {
+                report.steps += 1;
+                report.frames += frames;
+            }
+            Err(e) => report.failures.push(RecodeFailure {
+                step_id,
+                error: e.to_string(),
+            }),
+        }
+        progress
+            .report(serde_json::json!({
+                "steps_done": report.steps as usize + report.failures.len(),
+                "steps_total": total,
+            }))
+            .await;
+    }
+    Ok(report)
+}
+
+/// Decode client-supplied frames with the same decoder as the consumer, without storing them
+pub fn reconstruct(request: ReconstructRequest) -> Result<DrivingStep, DecodeFailure> {
+    let endianness = request
+        .endianness
+        .or_else(|| DrivingStep::detect_endianness(&request.frames))
+        .unwrap_or_else(|| Endianness::from_is_big_endian(DrivingStep::get_endianness_from_env()));
+    let step_name = request
+        .step_name
+        .unwrap_or_else(|| "Reconstructed".to_string());
+
+    DrivingStep::from_can_messages_with_endian(
+        &request.frames,
+        step_name,
+        endianness.is_big_endian(),
+    )
+    .map_err(|error| DecodeFailure {
+        error,
+        endianness,
+        can_ids: request
+            .frames
+            .iter()
+            .map(|frame| CanId::from(frame.id).to_string())
+            .collect(),
+    })
 }
@@ -2,24 +2,86 @@ pub mod controller;
 pub mod model;
 pub mod service;
 
-use actix_web::{get, web, HttpResponse, Result};
+use actix_web::http::header::ETag;
+use actix_web::web::Data;
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Result};
 use serde_json;
 
 use crate::common::error::AppError;
+use crate::common::http;
+use crate::config::transport::StepTransport;
+use crate::core::can::CanId;
+use crate::core::signals;
+use crate::features::annotation;
+use crate::features::archive::Archive;
+use crate::features::job::JobQueue;
+use crate::features::tenant::TenantScope;
+use crate::features::trash::{self, DeleteQuery, TrashKind};
 
 pub use model::DrivingStep;
+use model::{
+    EncodeQuery, FrameQuery, IngestQuery, RecodeRequest, ReconstructRequest, StepListQuery,
+    StepQuery,
+};
 
 #[get("/driving-steps")]
-pub async fn list() -> Result<HttpResponse, AppError> {
-    let steps = controller::list().await?;
-    Ok(HttpResponse::Ok().json(steps))
+pub async fn list(
+    query: web::Query<StepListQuery>,
+    scope: TenantScope,
+    archive: Data<Archive>,
+) -> Result<HttpResponse, AppError> {
+    let projection = controller::projection(query.fields.as_deref())?;
+    let page = controller::list(&query, &scope, &archive).await?;
+    let mut converted = signals::steps_in_units(&page.items, query.units)?;
+    if let Some(projection) = projection {
+        converted = converted
+            .iter()
+            .map(|step| projection.apply(step))
+            .collect();
+    }
+    Ok(HttpResponse::Ok().json(page.with_items(converted)))
+}
+
+/// Single ingestion point: the step is reconstructed and broadcast once its notice is consumed
+#[post("/driving-steps")]
+pub async fn create(
+    step: web::Json<DrivingStep>,
+    query: web::Query<IngestQuery>,
+    scope: TenantScope,
+    transport: Data<StepTransport>,
+) -> Result<HttpResponse, AppError> {
+    let stored = controller::ingest(&step, &query, &scope, &transport).await?;
+    Ok(HttpResponse::Accepted().json(stored))
 }
 
+/// Answers `304` to an `If-None-Match` naming the ETag of the latest step, for pollers
 #[get("/driving-steps/last")]
-pub async fn get_last() -> Result<HttpResponse, AppError> {
-    let step = controller::get_last().await?;
+pub async fn get_last(
+    req: HttpRequest,
+    query: web::Query<StepQuery>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    let projection = controller::projection(query.fields.as_deref())?;
+    let etag = controller::last_etag(&scope).await?;
+    if let Some(response) = etag
+        .as_ref()
+        .and_then(|etag| http::not_modified(&req, etag))
+    {
+        return Ok(response);
+    }
+    let step = controller::get_last(&scope).await?;
     match step {
-        Some(step) => Ok(HttpResponse::Ok().json(step)),
+        Some(step) => {
+            let mut converted = signals::steps_in_units([&step], query.units)?.remove(0);
+            if let Some(projection) = projection {
+                converted = projection.apply(&converted);
+            }
+            let mut response = HttpResponse::Ok();
+            if let Some(etag) = etag {
+                response.insert_header(ETag(etag));
+            }
+            Ok(response.json(converted))
+        }
         None => {
             Ok(HttpResponse::NotFound()
                 .json(serde_json::json!({"error": "No driving steps found"})))
@@ -27,6 +89,105 @@ pub async fn get_last() -> Result<HttpResponse, AppError> {
     }
 }
 
+/// Step an event's `source_ref` points to, with the annotations left on it
+#[get("/driving-steps/{step_id}")]
+pub async fn get(
+    path: web::Path<String>,
+    query: web::Query<StepQuery>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    let projection = controller::projection(query.fields.as_deref())?;
+    let step = controller::get(&path, &scope).await?;
+    let annotations = annotation::service::get_annotations(&path, scope.name()).await?;
+    let mut converted = signals::steps_in_units([&step], query.units)?.remove(0);
+    converted["annotations"] = serde_json::to_value(annotations)?;
+    if let Some(projection) = projection {
+        converted = projection.apply(&converted);
+    }
+    Ok(HttpResponse::Ok().json(converted))
+}
+
+/// Move a step to the trash, or delete it for good with `?permanent=true`
+#[delete("/driving-steps/{step_id}")]
+pub async fn remove(
+    path: web::Path<String>,
+    query: web::Query<DeleteQuery>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    trash::controller::discard(TrashKind::Step, &path, &query, scope.name()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Raw CAN frames stored for a step, as written by the producer
+#[get("/driving-steps/{step_id}/frames")]
+pub async fn frames(
+    path: web::Path<String>,
+    query: web::Query<FrameQuery>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::frames(&path, &query, &scope).await?))
+}
+
+/// One raw frame of a step, addressed by CAN ID (`/frames/0x100` or `/frames/256`)
+#[get("/driving-steps/{step_id}/frames/{can_id}")]
+pub async fn frame(
+    path: web::Path<(String, String)>,
+    can_id: CanId,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::frame(&path.0, can_id, &scope).await?))
+}
+
+/// Frames the server would generate for a step, leaving the database untouched
+#[post("/driving-steps/encode")]
+pub async fn encode(
+    step: web::Json<DrivingStep>,
+    query: web::Query<EncodeQuery>,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::encode(&step, &query)?))
+}
+
+/// Decode frames produced by an external encoder, leaving the database untouched
+#[post("/driving-steps/reconstruct")]
+pub async fn reconstruct(
+    request: web::Json<ReconstructRequest>,
+    query: web::Query<StepQuery>,
+) -> Result<HttpResponse, AppError> {
+    let projection = controller::projection(query.fields.as_deref())?;
+    match controller::reconstruct(request.into_inner()) {
+        Ok(step) => {
+            let mut converted = signals::steps_in_units([&step], query.units)?.remove(0);
+            if let Some(projection) = projection {
+                converted = projection.apply(&converted);
+            }
+            Ok(HttpResponse::Ok().json(converted))
+        }
+        Err(failure) => Ok(HttpResponse::UnprocessableEntity().json(failure)),
+    }
+}
+
+/// Rewrite stored frames in another byte order, keeping them decodable after the format moves
+///
+/// Runs as a job: answers `202` with the job, whose result is the recode report.
+#[post("/admin/recode")]
+pub async fn recode(
+    jobs: Data<JobQueue>,
+    request: web::Json<RecodeRequest>,
+) -> Result<HttpResponse, AppError> {
+    let job = controller::submit_recode(&jobs, request.into_inner()).await?;
+    Ok(HttpResponse::Accepted().json(job))
+}
+
 pub fn configure(cfg: &mut web::ServiceConfig) {
-    cfg.service(list).service(get_last);
+    // `/driving-steps/last` before the `{step_id}` routes that would otherwise match it
+    cfg.service(list)
+        .service(create)
+        .service(get_last)
+        .service(get)
+        .service(remove)
+        .service(frames)
+        .service(frame)
+        .service(encode)
+        .service(reconstruct)
+        .service(recode);
 }
@@ -1,9 +1,14 @@
 use serde::{Deserialize, Serialize};
 
-use crate::core::can::CanMessage;
+use crate::core::can::{CanError, CanId, CanMessage, Endianness};
+use crate::core::can_map::{can_ids, FrameGroup};
+use crate::core::clock::{Clock, SystemClock, TimeSource};
+use crate::core::dbc;
+use crate::core::metrics::PipelineTiming;
+use crate::core::units::UnitSystem;
 
 /// Realistic engine data
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EngineData {
     pub rpm: u16,             // Engine RPM
     pub coolant_temp: i16,    // Coolant temperature in °C (-40 to +215)
@@ -15,7 +20,7 @@ pub struct EngineData {
 }
 
 /// Vehicle speed and transmission data
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VehicleSpeedData {
     pub vehicle_speed: f32,     // Speed in km/h
     pub gear_position: u8,      // Current gear (0=Park, 1-6=gears, 15=Reverse)
@@ -26,7 +31,7 @@ pub struct VehicleSpeedData {
 }
 
 /// Climate control data
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ClimateData {
     pub cabin_temp: i16,         // Cabin temperature in °C (-40 to +85)
     pub target_temp: i16,        // Target temperature in °C
@@ -39,73 +44,374 @@ pub struct ClimateData {
     pub air_recirculation: bool, // Air recirculation mode
 }
 
-/// Complete driving step with all vehicle data
+/// Driver-assistance data (radar, lane keeping, adaptive cruise control)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdasData {
+    pub lead_distance: f32,  // Distance to the lead vehicle in m (0 = no target)
+    pub relative_speed: f32, // Lead vehicle speed relative to ours in km/h
+    pub acc_set_speed: u8,   // Adaptive cruise control set speed in km/h
+    pub acc_active: bool,    // Adaptive cruise control engaged
+    pub lane_keep_active: bool, // Lane keeping assist engaged
+    pub lane_departure_warning: bool, // Lane departure warning raised
+}
+
+/// Fuel system data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FuelData {
+    pub tank_level: u8,       // Fuel tank level (0-100%)
+    pub consumption: f32,     // Instantaneous consumption in L/100km
+    pub range_remaining: u16, // Estimated range on the remaining fuel in km
+}
+
+/// GPS position fix
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GpsData {
+    pub latitude: f64,  // Latitude in degrees (-90 to +90, 1e-7° resolution)
+    pub longitude: f64, // Longitude in degrees (-180 to +180, 1e-7° resolution)
+}
+
+/// Query parameters accepted by the driving-step endpoints
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StepQuery {
+    /// Unit system of the returned signal values (`?units=imperial`)
+    #[serde(default)]
+    pub units: UnitSystem,
+    /// Only return these comma-separated fields (`?fields=engine.rpm,speed.vehicle_speed`)
+    pub fields: Option<String>,
+}
+
+/// Query parameters accepted by `GET /driving-steps`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StepListQuery {
+    #[serde(default)]
+    pub units: UnitSystem,
+    pub fields: Option<String>,
+    /// Only return steps carrying this tag (`?tag=regression`)
+    pub tag: Option<String>,
+    /// Only return steps stamped on the wall clock, or by simulated scenario runs
+    pub clock: Option<TimeSource>,
+    /// Only return steps carrying a frame with this CAN ID (`?can_id=0x100` or `?can_id=256`)
+    pub can_id: Option<CanId>,
+    /// Only return steps whose first frame was captured at or after this RFC3339 time
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    /// Only return steps whose first frame was captured before this RFC3339 time
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+    /// Steps per page, in storage order, `DEFAULT_LIMIT` when omitted
+    pub limit: Option<u32>,
+    /// Steps of the previous pages to skip
+    pub offset: Option<u32>,
+}
+
+/// Conditions a listed step must meet, from a `StepListQuery`
+#[derive(Debug, Clone, Default)]
+pub struct StepFilter {
+    /// Normalized tag name
+    pub tag: Option<String>,
+    pub clock: Option<TimeSource>,
+    pub can_id: Option<CanId>,
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Frames written to storage for one DrivingStep, grouped under a shared step id
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredStep {
+    pub step_id: String,
+    pub endian: Endianness,
+    /// VIN of the registered vehicle the frames were ingested for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vehicle_id: Option<String>,
+    /// Tenant whose API key stored the frames
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    /// Name the step was stored with, none for steps stored before names were kept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_name: Option<String>,
+    pub can_messages: Vec<CanMessage>,
+}
+
+/// Query parameters accepted by `GET /driving-steps/{step_id}/frames`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FrameQuery {
+    /// Only return frames with this CAN ID (`?can_id=0x100` or `?can_id=256`)
+    pub can_id: Option<CanId>,
+}
+
+/// Query parameters accepted by `POST /driving-steps`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IngestQuery {
+    /// Byte order of the stored frames, the vehicle's or `ENDIAN` of the server when omitted
+    pub endianness: Option<Endianness>,
+    /// VIN of a registered vehicle, see `GET /vehicles`
+    pub vehicle_id: Option<String>,
+}
+
+/// Body of `POST /driving-steps/reconstruct`
+///
+/// The aliases accept the output of `GET /driving-steps/{step_id}/frames` as is.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReconstructRequest {
+    #[serde(alias = "can_messages")]
+    pub frames: Vec<CanMessage>,
+    /// Byte order of the multi-byte signals, detected from the frames when omitted and
+    /// `ENDIAN` of the server when it cannot be told
+    #[serde(default, alias = "endian")]
+    pub endianness: Option<Endianness>,
+    /// Name given to the decoded step
+    #[serde(default)]
+    pub step_name: Option<String>,
+}
+
+/// Query parameters accepted by `POST /driving-steps/encode`
+#[derive(Debug, Clone, Deserialize)]
+pub struct EncodeQuery {
+    /// `ENDIAN` of the server when omitted
+    pub endianness: Option<Endianness>,
+    /// Wire format version, only `DrivingStep::WIRE_FORMAT_VERSION` is produced
+    pub version: Option<u32>,
+    /// CRC and rolling counter bytes, rejected as long as no wire format version carries them
+    #[serde(default)]
+    pub crc: bool,
+}
+
+/// Frames the server would generate for a step, in the shape `reconstruct` accepts
+#[derive(Debug, Clone, Serialize)]
+pub struct EncodedStep {
+    pub version: u32,
+    pub endian: Endianness,
+    pub can_messages: Vec<CanMessage>,
+}
+
+/// Body of `POST /admin/recode`
+#[derive(Debug, Clone, Deserialize)]
+pub struct RecodeRequest {
+    /// Byte order the frames are rewritten in
+    pub to: Endianness,
+    /// Only rewrite steps stored in this byte order, every step not yet in `to` when omitted
+    pub from: Option<Endianness>,
+    /// Wire format version of the rewritten frames, only `DrivingStep::WIRE_FORMAT_VERSION`
+    pub version: Option<u32>,
+    /// Only rewrite the steps ingested for this VIN
+    pub vehicle_id: Option<String>,
+    /// Report what would be rewritten, leaving the database untouched
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Outcome of `POST /admin/recode`
+#[derive(Debug, Clone, Serialize)]
+pub struct RecodeReport {
+    pub to: Endianness,
+    pub version: u32,
+    pub dry_run: bool,
+    /// Steps rewritten, or that would be on a dry run
+    pub steps: u64,
+    /// Frames rewritten, or that would be on a dry run
+    pub frames: u64,
+    /// Steps left in their byte order
+    pub failures: Vec<RecodeFailure>,
+}
+
+/// Step `POST /admin/recode` could not rewrite
+#[derive(Debug, Clone, Serialize)]
+pub struct RecodeFailure {
+    pub step_id: String,
+    pub error: String,
+}
+
+/// Why a frame group could not be decoded, returned with `422 Unprocessable Entity`
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodeFailure {
+    pub error: String,
+    pub endianness: Endianness,
+    /// CAN IDs of the submitted frames, in request order
+    pub can_ids: Vec<String>,
+}
+
+/// Complete driving step with all vehicle data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DrivingStep {
     pub step_name: String,
+    /// Id of the stored frames the step was reconstructed from; not carried on the CAN bus
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_id: Option<String>,
     pub engine: EngineData,
     pub speed: VehicleSpeedData,
     pub climate: ClimateData,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adas: Option<AdasData>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fuel: Option<FuelData>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gps: Option<GpsData>,
     pub duration_ms: u64,
+    /// Timestamps collected by the consumer, reported to stream clients on request
+    #[serde(skip)]
+    pub pipeline: Option<PipelineTiming>,
+    /// Stored frames the consumer decoded the step from, streamed in `decoded` mode
+    #[serde(skip)]
+    pub frames: Vec<CanMessage>,
+    /// Tenant owning the stored frames, only streamed to its own API key
+    #[serde(skip)]
+    pub tenant: Option<String>,
+    /// VIN of the registered vehicle the frames were ingested for
+    #[serde(skip)]
+    pub vehicle_id: Option<String>,
 }
 
 impl DrivingStep {
-    // CAN ID assignments for different parts of DrivingStep
-    const ENGINE_RPM_CAN_ID: u16 = 0x100;
-    const ENGINE_TEMP_CAN_ID: u16 = 0x101;
-
-    const SPEED_DATA_CAN_ID: u16 = 0x200;
-    const SPEED_FLAGS_CAN_ID: u16 = 0x201;
-    const CLIMATE_TEMP_CAN_ID: u16 = 0x300;
-    const CLIMATE_FAN_CAN_ID: u16 = 0x301;
-    const STEP_INFO_CAN_ID: u16 = 0x400;
+    /// Layout of the frames produced by `to_can_messages_with_endian`
+    ///
+    /// Version 1 frames carry no CRC or rolling counter: integrity is left to the CAN
+    /// controller and duplicate detection to `verify_frame_group`. The CAN ID of each frame
+    /// is looked up in the `CanIdMap` in force.
+    pub const WIRE_FORMAT_VERSION: u32 = 1;
+
+    /// Bit of the speed flags frame set by encoders that record their byte order
+    const BYTE_ORDER_MARKED: u8 = 0b0100_0000;
+    /// Bit of the speed flags frame set for big-endian steps, next to `BYTE_ORDER_MARKED`
+    const BYTE_ORDER_BIG: u8 = 0b1000_0000;
 
     /// Get endianness from environment variable
     pub fn get_endianness_from_env() -> bool {
-        match std::env::var("ENDIAN")
-            .unwrap_or_else(|_| "little".to_string())
-            .to_lowercase()
-            .as_str()
-        {
-            "big" | "network" => true,
-            _ => false,
-        }
+        matches!(
+            std::env::var("ENDIAN")
+                .unwrap_or_else(|_| "little".to_string())
+                .to_lowercase()
+                .as_str(),
+            "big" | "network"
+        )
     }
 
-    /// Helper function to encode u16 value with specified endianness
-    fn encode_u16_with_endian(value: u16, is_big_endian: bool) -> [u8; 2] {
-        if is_big_endian {
-            value.to_be_bytes()
-        } else {
-            value.to_le_bytes()
+    /// Check that every field fits the range its CAN encoding can represent
+    ///
+    /// Encoding silently clamps out-of-range values, so untrusted input (WebSocket/HTTP
+    /// bodies) must go through this before being converted to frames.
+    pub fn validate(&self) -> Result<(), CanError> {
+        fn out_of_range(
+            field: &'static str,
+            value: impl ToString,
+            expected: &'static str,
+        ) -> CanError {
+            CanError::OutOfRange {
+                field,
+                value: value.to_string(),
+                expected,
+            }
         }
-    }
 
-    /// Helper function to encode u32 value with specified endianness
-    fn encode_u32_with_endian(value: u32, is_big_endian: bool) -> [u8; 4] {
-        if is_big_endian {
-            value.to_be_bytes()
-        } else {
-            value.to_le_bytes()
+        let temperatures = [
+            ("engine.coolant_temp", self.engine.coolant_temp),
+            ("engine.intake_temp", self.engine.intake_temp),
+            ("climate.cabin_temp", self.climate.cabin_temp),
+            ("climate.target_temp", self.climate.target_temp),
+            ("climate.outside_temp", self.climate.outside_temp),
+        ];
+        for (field, value) in temperatures {
+            if !(-40..=215).contains(&value) {
+                return Err(out_of_range(field, value, "-40..=215 °C"));
+            }
         }
-    }
 
-    /// Helper function to decode u16 value with specified endianness
-    fn decode_u16_with_endian(bytes: [u8; 2], is_big_endian: bool) -> u16 {
-        if is_big_endian {
-            u16::from_be_bytes(bytes)
-        } else {
-            u16::from_le_bytes(bytes)
+        if self.engine.throttle_pos > 100 {
+            return Err(out_of_range(
+                "engine.throttle_pos",
+                self.engine.throttle_pos,
+                "0..=100 %",
+            ));
+        }
+        if self.engine.engine_load > 100 {
+            return Err(out_of_range(
+                "engine.engine_load",
+                self.engine.engine_load,
+                "0..=100 %",
+            ));
         }
-    }
 
-    /// Helper function to decode u32 value with specified endianness
-    fn decode_u32_with_endian(bytes: [u8; 4], is_big_endian: bool) -> u32 {
-        if is_big_endian {
-            u32::from_be_bytes(bytes)
-        } else {
-            u32::from_le_bytes(bytes)
+        let speed = self.speed.vehicle_speed;
+        if !speed.is_finite() || !(0.0..=6553.5).contains(&speed) {
+            return Err(out_of_range(
+                "speed.vehicle_speed",
+                speed,
+                "0.0..=6553.5 km/h",
+            ));
+        }
+        for wheel_speed in self.speed.wheel_speeds {
+            if !wheel_speed.is_finite() || !(0.0..=255.0).contains(&wheel_speed) {
+                return Err(out_of_range(
+                    "speed.wheel_speeds",
+                    wheel_speed,
+                    "0.0..=255.0 km/h",
+                ));
+            }
         }
+        if !matches!(self.speed.gear_position, 0..=6 | 15) {
+            return Err(out_of_range(
+                "speed.gear_position",
+                self.speed.gear_position,
+                "0 (Park), 1-6 or 15 (Reverse)",
+            ));
+        }
+
+        if let Some(adas) = &self.adas {
+            let distance = adas.lead_distance;
+            if !distance.is_finite() || !(0.0..=6553.5).contains(&distance) {
+                return Err(out_of_range(
+                    "adas.lead_distance",
+                    distance,
+                    "0.0..=6553.5 m",
+                ));
+            }
+            let relative_speed = adas.relative_speed;
+            if !relative_speed.is_finite() || !(-3276.8..=3276.7).contains(&relative_speed) {
+                return Err(out_of_range(
+                    "adas.relative_speed",
+                    relative_speed,
+                    "-3276.8..=3276.7 km/h",
+                ));
+            }
+        }
+
+        if let Some(fuel) = &self.fuel {
+            if fuel.tank_level > 100 {
+                return Err(out_of_range(
+                    "fuel.tank_level",
+                    fuel.tank_level,
+                    "0..=100 %",
+                ));
+            }
+            let consumption = fuel.consumption;
+            if !consumption.is_finite() || !(0.0..=6553.5).contains(&consumption) {
+                return Err(out_of_range(
+                    "fuel.consumption",
+                    consumption,
+                    "0.0..=6553.5 L/100km",
+                ));
+            }
+        }
+
+        if let Some(gps) = &self.gps {
+            if !gps.latitude.is_finite() || !(-90.0..=90.0).contains(&gps.latitude) {
+                return Err(out_of_range("gps.latitude", gps.latitude, "-90.0..=90.0 °"));
+            }
+            if !gps.longitude.is_finite() || !(-180.0..=180.0).contains(&gps.longitude) {
+                return Err(out_of_range(
+                    "gps.longitude",
+                    gps.longitude,
+                    "-180.0..=180.0 °",
+                ));
+            }
+        }
+
+        if self.duration_ms > u32::MAX as u64 {
+            return Err(out_of_range(
+                "duration_ms",
+                self.duration_ms,
+                "0..=4294967295 ms",
+            ));
+        }
+
+        Ok(())
     }
 
     /// Convert DrivingStep to multiple CAN messages with specified endianness
@@ -115,158 +421,270 @@ impl DrivingStep {
 
     /// Convert DrivingStep to multiple CAN messages with explicit endianness
     pub fn to_can_messages_with_endian(&self, is_big_endian: bool) -> Vec<CanMessage> {
+        self.to_can_messages_with_clock(is_big_endian, &SystemClock)
+    }
+
+    /// Convert DrivingStep to CAN messages stamped with the time read from `clock`
+    pub fn to_can_messages_with_clock(
+        &self,
+        is_big_endian: bool,
+        clock: &dyn Clock,
+    ) -> Vec<CanMessage> {
         let mut messages = Vec::new();
-        let timestamp = chrono::Utc::now().to_rfc3339();
-
-        // Engine RPM and related data
-        let mut engine_rpm_data = [0u8; 8];
-
-        // RPM (16 bits) at bytes 0-1 with endianness
-        let rpm_bytes = Self::encode_u16_with_endian(self.engine.rpm, is_big_endian);
-        engine_rpm_data[0..2].copy_from_slice(&rpm_bytes);
-
-        // Fuel pressure (16 bits, scaled by 10) at bytes 2-3 with endianness
-        let fuel_scaled = self.engine.fuel_pressure / 10;
-        let fuel_bytes = Self::encode_u16_with_endian(fuel_scaled, is_big_endian);
-        engine_rpm_data[2..4].copy_from_slice(&fuel_bytes);
-
-        // Engine running flag at byte 4
-        engine_rpm_data[4] = if self.engine.engine_running { 1 } else { 0 };
-
-        messages.push(CanMessage {
-            id: Self::ENGINE_RPM_CAN_ID,
-            dlc: 5,
-            data: engine_rpm_data,
-            timestamp: timestamp.clone(),
-        });
-
-        // Engine temperature data
-        let mut engine_temp_data = [0u8; 8];
-        engine_temp_data[0] = ((self.engine.coolant_temp + 40).max(0).min(255i16)) as u8;
-        engine_temp_data[1] = ((self.engine.intake_temp + 40).max(0).min(255i16)) as u8;
-        engine_temp_data[2] = self.engine.throttle_pos;
-        engine_temp_data[3] = self.engine.engine_load;
-
-        messages.push(CanMessage {
-            id: Self::ENGINE_TEMP_CAN_ID,
-            dlc: 4,
-            data: engine_temp_data,
-            timestamp: timestamp.clone(),
-        });
-
-        // Vehicle speed and gear data
-        let mut speed_data = [0u8; 8];
-
-        // Vehicle speed (16 bits, scaled by 10) at bytes 0-1 with endianness
-        let speed_encoded = (self.speed.vehicle_speed * 10.0).min(6553.5) as u16;
-        let speed_bytes = Self::encode_u16_with_endian(speed_encoded, is_big_endian);
-        speed_data[0..2].copy_from_slice(&speed_bytes);
-
-        // Gear position at byte 2
-        speed_data[2] = self.speed.gear_position;
-
-        // Wheel speeds (simplified, 1 byte each)
-        for (i, &wheel_speed) in self.speed.wheel_speeds.iter().enumerate().take(4) {
-            speed_data[3 + i] = wheel_speed.min(255.0) as u8;
-        }
+        let timestamp = clock.timestamp();
+        let ids = can_ids();
+
+        // Each frame packs raw values through the DrivingStep layout in the requested order
+        let mut push = |group: FrameGroup, raw: &[(&str, i64)]| {
+            let message = dbc::driving_step_message(group, is_big_endian);
+            messages.push(CanMessage::new_unchecked(
+                ids.id(group),
+                message.dlc,
+                message.pack(raw),
+                timestamp.clone(),
+            ));
+        };
+        let flag = |set: bool| set as i64;
+
+        // Fuel pressure is carried in steps of 10 kPa, truncated
+        push(
+            FrameGroup::EngineRpm,
+            &[
+                ("rpm", self.engine.rpm as i64),
+                ("fuel_pressure", (self.engine.fuel_pressure / 10) as i64),
+                ("engine_running", flag(self.engine.engine_running)),
+            ],
+        );
 
-        messages.push(CanMessage {
-            id: Self::SPEED_DATA_CAN_ID,
-            dlc: 7,
-            data: speed_data,
-            timestamp: timestamp.clone(),
-        });
-
-        // Speed flags (ABS, traction control, etc.)
-        let mut speed_flags_data = [0u8; 8];
-        let mut flags = 0u8;
-        if self.speed.abs_active {
-            flags |= 0b0000_0001; // Bit 0: ABS active
-        }
-        if self.speed.traction_control {
-            flags |= 0b0000_0010; // Bit 1: Traction control active
-        }
-        if self.speed.cruise_control {
-            flags |= 0b0000_0100; // Bit 2: Cruise control active
-        }
-        speed_flags_data[0] = flags;
-
-        messages.push(CanMessage {
-            id: Self::SPEED_FLAGS_CAN_ID,
-            dlc: 1,
-            data: speed_flags_data,
-            timestamp: timestamp.clone(),
-        });
-
-        // Climate temperature data
-        let mut climate_temp_data = [0u8; 8];
-        climate_temp_data[0] = ((self.climate.cabin_temp + 40).max(0).min(255)) as u8;
-        climate_temp_data[1] = ((self.climate.target_temp + 40).max(0).min(255)) as u8;
-        climate_temp_data[2] = ((self.climate.outside_temp + 40).max(0).min(255)) as u8;
-
-        messages.push(CanMessage {
-            id: Self::CLIMATE_TEMP_CAN_ID,
-            dlc: 3,
-            data: climate_temp_data,
-            timestamp: timestamp.clone(),
-        });
-
-        // Climate fan and flags data
-        let mut climate_fan_data = [0u8; 8];
-        climate_fan_data[0] = self.climate.fan_speed;
-        let mut climate_flags = 0u8;
-        if self.climate.ac_compressor {
-            climate_flags |= 0b0000_0001; // Bit 0: AC compressor
+        // Temperatures are offset by 40 so that -40 °C encodes as 0
+        push(
+            FrameGroup::EngineTemp,
+            &[
+                ("coolant_temp", self.engine.coolant_temp as i64 + 40),
+                ("intake_temp", self.engine.intake_temp as i64 + 40),
+                ("throttle_pos", self.engine.throttle_pos as i64),
+                ("engine_load", self.engine.engine_load as i64),
+            ],
+        );
+
+        // Vehicle speed in 0.1 km/h, wheel speeds in whole km/h
+        let [fl, fr, rl, rr] = self.speed.wheel_speeds.map(|speed| speed.min(255.0) as i64);
+        push(
+            FrameGroup::SpeedData,
+            &[
+                (
+                    "vehicle_speed",
+                    (self.speed.vehicle_speed * 10.0).round().min(65535.0) as i64,
+                ),
+                ("gear_position", self.speed.gear_position as i64),
+                ("wheel_speed_fl", fl),
+                ("wheel_speed_fr", fr),
+                ("wheel_speed_rl", rl),
+                ("wheel_speed_rr", rr),
+            ],
+        );
+
+        // The byte order marker is read back by `detect_endianness`
+        push(
+            FrameGroup::SpeedFlags,
+            &[
+                ("abs_active", flag(self.speed.abs_active)),
+                ("traction_control", flag(self.speed.traction_control)),
+                ("cruise_control", flag(self.speed.cruise_control)),
+                ("byte_order_marked", 1),
+                ("byte_order_big", flag(is_big_endian)),
+            ],
+        );
+
+        push(
+            FrameGroup::ClimateTemp,
+            &[
+                ("cabin_temp", self.climate.cabin_temp as i64 + 40),
+                ("target_temp", self.climate.target_temp as i64 + 40),
+                ("outside_temp", self.climate.outside_temp as i64 + 40),
+            ],
+        );
+
+        push(
+            FrameGroup::ClimateFan,
+            &[
+                ("fan_speed", self.climate.fan_speed as i64),
+                ("ac_compressor", flag(self.climate.ac_compressor)),
+                ("heater", flag(self.climate.heater)),
+                ("defrost", flag(self.climate.defrost)),
+                ("auto_mode", flag(self.climate.auto_mode)),
+                ("air_recirculation", flag(self.climate.air_recirculation)),
+            ],
+        );
+
+        // Step info (duration only, no hash)
+        push(
+            FrameGroup::StepInfo,
+            &[("duration_ms", self.duration_ms as u32 as i64)],
+        );
+
+        // Driver-assistance data (optional group), distances and speeds in tenths
+        if let Some(adas) = &self.adas {
+            push(
+                FrameGroup::Adas,
+                &[
+                    (
+                        "lead_distance",
+                        (adas.lead_distance * 10.0).round().clamp(0.0, 65535.0) as i64,
+                    ),
+                    (
+                        "relative_speed",
+                        (adas.relative_speed * 10.0)
+                            .round()
+                            .clamp(-32768.0, 32767.0) as i64,
+                    ),
+                    ("acc_set_speed", adas.acc_set_speed as i64),
+                    ("acc_active", flag(adas.acc_active)),
+                    ("lane_keep_active", flag(adas.lane_keep_active)),
+                    ("lane_departure_warning", flag(adas.lane_departure_warning)),
+                ],
+            );
         }
-        if self.climate.heater {
-            climate_flags |= 0b0000_0010; // Bit 1: Heater
+
+        // Fuel system data (optional group)
+        if let Some(fuel) = &self.fuel {
+            push(
+                FrameGroup::Fuel,
+                &[
+                    ("tank_level", fuel.tank_level as i64),
+                    (
+                        "fuel_consumption",
+                        (fuel.consumption * 10.0).round().clamp(0.0, 65535.0) as i64,
+                    ),
+                    ("range_remaining", fuel.range_remaining as i64),
+                ],
+            );
         }
-        if self.climate.defrost {
-            climate_flags |= 0b0000_0100; // Bit 2: Defrost
+
+        // GPS position (optional group), signed 32-bit integers of 1e-7 degrees
+        if let Some(gps) = &self.gps {
+            push(
+                FrameGroup::Gps,
+                &[
+                    ("latitude", (gps.latitude * 1e7).round() as i32 as i64),
+                    ("longitude", (gps.longitude * 1e7).round() as i32 as i64),
+                ],
+            );
         }
-        if self.climate.auto_mode {
-            climate_flags |= 0b0000_1000; // Bit 3: Auto mode
+
+        messages
+    }
+
+    /// Check that `messages` hold exactly one frame for each expected CAN ID
+    ///
+    /// A duplicated ID means frames from different steps were mixed together, which
+    /// would otherwise decode silently into a step that never existed.
+    pub fn verify_frame_group(messages: &[CanMessage]) -> Result<(), String> {
+        let ids = can_ids();
+        for expected_id in FrameGroup::REQUIRED.map(|group| ids.id(group)) {
+            match messages.iter().filter(|msg| msg.id == expected_id).count() {
+                1 => {}
+                0 => return Err(format!("Missing CAN frame 0x{:03X}", expected_id)),
+                count => {
+                    return Err(format!(
+                        "CAN frame 0x{:03X} appears {} times in one step",
+                        expected_id, count
+                    ))
+                }
+            }
         }
-        if self.climate.air_recirculation {
-            climate_flags |= 0b0001_0000; // Bit 4: Air recirculation
+        for optional_id in FrameGroup::OPTIONAL.map(|group| ids.id(group)) {
+            let count = messages.iter().filter(|msg| msg.id == optional_id).count();
+            if count > 1 {
+                return Err(format!(
+                    "CAN frame 0x{:03X} appears {} times in one step",
+                    optional_id, count
+                ));
+            }
         }
-        climate_fan_data[1] = climate_flags;
-
-        messages.push(CanMessage {
-            id: Self::CLIMATE_FAN_CAN_ID,
-            dlc: 2,
-            data: climate_fan_data,
-            timestamp: timestamp.clone(),
-        });
 
-        // Step info (duration only, no hash)
-        let mut step_info_data = [0u8; 8];
+        Ok(())
+    }
 
-        // Duration (32 bits) at bytes 0-3 with endianness
-        let duration_bytes = Self::encode_u32_with_endian(self.duration_ms as u32, is_big_endian);
-        step_info_data[0..4].copy_from_slice(&duration_bytes);
+    /// Byte order a frame group was encoded with, for frames whose metadata does not say
+    ///
+    /// Reads the marker of the speed flags frame when the encoder wrote one. Frames of older
+    /// encoders are decoded both ways and each multi-byte signal votes for the order giving
+    /// it the smaller magnitude, as realistic values sit far below their byte-swapped ones.
+    /// `None` when the group does not decode or the vote is tied, e.g. for all-zero values.
+    pub fn detect_endianness(messages: &[CanMessage]) -> Option<Endianness> {
+        let flags_id = FrameGroup::SpeedFlags.id();
+        let marker = messages
+            .iter()
+            .find(|msg| msg.id == flags_id && msg.dlc >= 1)
+            .map(|msg| msg.data()[0])
+            .filter(|flags| flags & Self::BYTE_ORDER_MARKED != 0);
+        if let Some(flags) = marker {
+            return Some(Endianness::from_is_big_endian(
+                flags & Self::BYTE_ORDER_BIG != 0,
+            ));
+        }
 
-        messages.push(CanMessage {
-            id: Self::STEP_INFO_CAN_ID,
-            dlc: 4, // Only duration, no hash
-            data: step_info_data,
-            timestamp: timestamp.clone(),
-        });
+        let little = Self::from_can_messages_with_endian(messages, String::new(), false).ok()?;
+        let big = Self::from_can_messages_with_endian(messages, String::new(), true).ok()?;
+        let votes: i32 = little
+            .multi_byte_magnitudes()
+            .into_iter()
+            .zip(big.multi_byte_magnitudes())
+            .map(|(little, big)| match little.partial_cmp(&big) {
+                Some(std::cmp::Ordering::Less) => 1,
+                Some(std::cmp::Ordering::Greater) => -1,
+                _ => 0,
+            })
+            .sum();
+        match votes.cmp(&0) {
+            std::cmp::Ordering::Greater => Some(Endianness::Little),
+            std::cmp::Ordering::Less => Some(Endianness::Big),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
 
-        messages
+    /// Magnitude of every signal spread over several bytes, in a fixed order
+    fn multi_byte_magnitudes(&self) -> Vec<f64> {
+        let mut magnitudes = vec![
+            self.engine.rpm as f64,
+            self.engine.fuel_pressure as f64,
+            self.speed.vehicle_speed as f64,
+            self.duration_ms as f64,
+        ];
+        if let Some(adas) = &self.adas {
+            magnitudes.extend([adas.lead_distance as f64, adas.relative_speed.abs() as f64]);
+        }
+        if let Some(fuel) = &self.fuel {
+            magnitudes.extend([fuel.consumption as f64, fuel.range_remaining as f64]);
+        }
+        if let Some(gps) = &self.gps {
+            magnitudes.extend([gps.latitude.abs(), gps.longitude.abs()]);
+        }
+        magnitudes
     }
 
-    /// Reconstruct DrivingStep from multiple CAN messages with default endianness
+    /// Reconstruct DrivingStep from multiple CAN messages in their detected byte order,
+    /// `ENDIAN` when it cannot be told
     pub fn from_can_messages(messages: &[CanMessage], step_name: String) -> Result<Self, String> {
-        Self::from_can_messages_with_endian(messages, step_name, Self::get_endianness_from_env())
+        let is_big_endian = Self::detect_endianness(messages)
+            .map_or_else(Self::get_endianness_from_env, Endianness::is_big_endian);
+        Self::from_can_messages_with_endian(messages, step_name, is_big_endian)
     }
 
     /// Reconstruct DrivingStep from multiple CAN messages with explicit endianness
+    ///
+    /// Total over arbitrary input: malformed or incomplete frame groups return `Err`,
+    /// never panic. For values accepted by `validate`, decoding the output of
+    /// `to_can_messages_with_endian` gives back the original step up to the encoding
+    /// resolution (0.1 km/h speed, whole-km/h wheel speeds, 10 kPa fuel pressure).
     pub fn from_can_messages_with_endian(
         messages: &[CanMessage],
         step_name: String,
         is_big_endian: bool,
     ) -> Result<Self, String> {
+        Self::verify_frame_group(messages)?;
+
         let mut engine_data = None;
         let mut engine_temp_data = None;
         let mut speed_data = None;
@@ -274,97 +692,96 @@ impl DrivingStep {
         let mut climate_temp_data = None;
         let mut climate_fan_data = None;
         let mut step_info_data = None;
+        let mut adas = None;
+        let mut fuel = None;
+        let mut gps = None;
 
-        // Parse messages by CAN ID
+        // Parse messages by the frame group their CAN ID is mapped to, unpacking raw
+        // values through the DrivingStep layout in the requested order
+        let ids = can_ids();
         for msg in messages {
-            match msg.id {
-                Self::ENGINE_RPM_CAN_ID => {
-                    if msg.dlc >= 5 {
-                        // RPM (16 bits) with endianness
-                        let rpm =
-                            Self::decode_u16_with_endian([msg.data[0], msg.data[1]], is_big_endian);
-
-                        // Fuel pressure (16 bits) with endianness
-                        let fuel_raw =
-                            Self::decode_u16_with_endian([msg.data[2], msg.data[3]], is_big_endian);
-                        let fuel_pressure = (fuel_raw as u32 * 10) as u16;
-
-                        let engine_running = msg.data[4] != 0;
-                        engine_data = Some((rpm, fuel_pressure, engine_running));
-                    }
+            let Some(group) = ids.group(msg.id) else {
+                continue; // Unknown CAN ID, ignore
+            };
+            let layout = dbc::driving_step_message(group, is_big_endian);
+            if msg.dlc < layout.dlc {
+                continue;
+            }
+            let raw = |name: &str| layout.unpack(msg.data(), name);
+            let flag = |name: &str| raw(name) != 0;
+            match group {
+                FrameGroup::EngineRpm => {
+                    let rpm = raw("rpm") as u16;
+                    let fuel_pressure = (raw("fuel_pressure") as u16).saturating_mul(10);
+                    engine_data = Some((rpm, fuel_pressure, flag("engine_running")));
+                }
+                FrameGroup::EngineTemp => {
+                    let coolant_temp = raw("coolant_temp") as i16 - 40;
+                    let intake_temp = raw("intake_temp") as i16 - 40;
+                    let throttle_pos = raw("throttle_pos") as u8;
+                    let engine_load = raw("engine_load") as u8;
+                    engine_temp_data = Some((coolant_temp, intake_temp, throttle_pos, engine_load));
+                }
+                FrameGroup::SpeedData => {
+                    let vehicle_speed = raw("vehicle_speed") as f32 / 10.0;
+                    let gear_position = raw("gear_position") as u8;
+                    let wheel_speeds = [
+                        raw("wheel_speed_fl") as f32,
+                        raw("wheel_speed_fr") as f32,
+                        raw("wheel_speed_rl") as f32,
+                        raw("wheel_speed_rr") as f32,
+                    ];
+                    speed_data = Some((vehicle_speed, gear_position, wheel_speeds));
                 }
-                Self::ENGINE_TEMP_CAN_ID => {
-                    if msg.dlc >= 4 {
-                        let coolant_temp = msg.data[0] as i16 - 40;
-                        let intake_temp = msg.data[1] as i16 - 40;
-                        let throttle_pos = msg.data[2];
-                        let engine_load = msg.data[3];
-                        engine_temp_data =
-                            Some((coolant_temp, intake_temp, throttle_pos, engine_load));
-                    }
+                FrameGroup::SpeedFlags => {
+                    speed_flags_data = Some((
+                        flag("abs_active"),
+                        flag("traction_control"),
+                        flag("cruise_control"),
+                    ));
                 }
-                Self::SPEED_DATA_CAN_ID => {
-                    if msg.dlc >= 7 {
-                        // Vehicle speed (16 bits) with endianness
-                        let speed_raw =
-                            Self::decode_u16_with_endian([msg.data[0], msg.data[1]], is_big_endian);
-                        let vehicle_speed = speed_raw as f32 / 10.0;
-                        let gear_position = msg.data[2];
-                        let wheel_speeds = [
-                            msg.data[3] as f32,
-                            msg.data[4] as f32,
-                            msg.data[5] as f32,
-                            msg.data[6] as f32,
-                        ];
-                        speed_data = Some((vehicle_speed, gear_position, wheel_speeds));
-                    }
+                FrameGroup::ClimateTemp => {
+                    let cabin_temp = raw("cabin_temp") as i16 - 40;
+                    let target_temp = raw("target_temp") as i16 - 40;
+                    let outside_temp = raw("outside_temp") as i16 - 40;
+                    climate_temp_data = Some((cabin_temp, target_temp, outside_temp));
                 }
-                Self::SPEED_FLAGS_CAN_ID => {
-                    if msg.dlc >= 1 {
-                        let flags = msg.data[0];
-                        let abs_active = (flags & 0b0000_0001) != 0; // Bit 0: ABS active
-                        let traction_control = (flags & 0b0000_0010) != 0; // Bit 1: Traction control
-                        let cruise_control = (flags & 0b0000_0100) != 0; // Bit 2: Cruise control
-                        speed_flags_data = Some((abs_active, traction_control, cruise_control));
-                    }
+                FrameGroup::ClimateFan => {
+                    climate_fan_data = Some((
+                        raw("fan_speed") as u8,
+                        flag("ac_compressor"),
+                        flag("heater"),
+                        flag("defrost"),
+                        flag("auto_mode"),
+                        flag("air_recirculation"),
+                    ));
                 }
-                Self::CLIMATE_TEMP_CAN_ID => {
-                    if msg.dlc >= 3 {
-                        let cabin_temp = msg.data[0] as i16 - 40;
-                        let target_temp = msg.data[1] as i16 - 40;
-                        let outside_temp = msg.data[2] as i16 - 40;
-                        climate_temp_data = Some((cabin_temp, target_temp, outside_temp));
-                    }
+                FrameGroup::StepInfo => {
+                    step_info_data = Some(raw("duration_ms") as u64);
                 }
-                Self::CLIMATE_FAN_CAN_ID => {
-                    if msg.dlc >= 2 {
-                        let fan_speed = msg.data[0];
-                        let flags = msg.data[1];
-                        let ac_compressor = (flags & 0b0000_0001) != 0; // Bit 0: AC compressor
-                        let heater = (flags & 0b0000_0010) != 0; // Bit 1: Heater
-                        let defrost = (flags & 0b0000_0100) != 0; // Bit 2: Defrost
-                        let auto_mode = (flags & 0b0000_1000) != 0; // Bit 3: Auto mode
-                        let air_recirculation = (flags & 0b0001_0000) != 0; // Bit 4: Air recirculation
-                        climate_fan_data = Some((
-                            fan_speed,
-                            ac_compressor,
-                            heater,
-                            defrost,
-                            auto_mode,
-                            air_recirculation,
-                        ));
-                    }
+                FrameGroup::Adas => {
+                    adas = Some(AdasData {
+                        lead_distance: raw("lead_distance") as f32 / 10.0,
+                        relative_speed: raw("relative_speed") as f32 / 10.0,
+                        acc_set_speed: raw("acc_set_speed") as u8,
+                        acc_active: flag("acc_active"),
+                        lane_keep_active: flag("lane_keep_active"),
+                        lane_departure_warning: flag("lane_departure_warning"),
+                    });
                 }
-                Self::STEP_INFO_CAN_ID => {
-                    if msg.dlc >= 4 {
-                        // Duration (32 bits) with endianness
-                        let duration_bytes = [msg.data[0], msg.data[1], msg.data[2], msg.data[3]];
-                        let duration_ms =
-                            Self::decode_u32_with_endian(duration_bytes, is_big_endian) as u64;
-                        step_info_data = Some(duration_ms);
-                    }
+                FrameGroup::Fuel => {
+                    fuel = Some(FuelData {
+                        tank_level: raw("tank_level") as u8,
+                        consumption: raw("fuel_consumption") as f32 / 10.0,
+                        range_remaining: raw("range_remaining") as u16,
+                    });
+                }
+                FrameGroup::Gps => {
+                    gps = Some(GpsData {
+                        latitude: raw("latitude") as f64 / 1e7,
+                        longitude: raw("longitude") as f64 / 1e7,
+                    });
                 }
-                _ => {} // Unknown CAN ID, ignore
             }
         }
 
@@ -384,6 +801,7 @@ impl DrivingStep {
 
         Ok(DrivingStep {
             step_name,
+            step_id: None,
             engine: EngineData {
                 rpm,
                 coolant_temp,
@@ -412,145 +830,19 @@ impl DrivingStep {
                 auto_mode,
                 air_recirculation,
             },
+            adas,
+            fuel,
+            gps,
             duration_ms,
+            pipeline: None,
+            frames: Vec::new(),
+            tenant: None,
+            vehicle_id: None,
         })
     }
 
-    pub fn print_status(&self) {
-        println!("\n🚗 {} 🚗", self.step_name);
-        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-
-        // Engine display
-        println!("🔧 ENGINE:");
-        println!("   • RPM: {} rpm", self.engine.rpm);
-        println!("   • Temperature: {}°C", self.engine.coolant_temp);
-        println!("   • Throttle: {}%", self.engine.throttle_pos);
-        println!("   • Load: {}%", self.engine.engine_load);
-        println!("   • Intake Temp: {}°C", self.engine.intake_temp);
-        println!("   • Fuel Pressure: {} kPa", self.engine.fuel_pressure);
-        println!(
-            "   • Running: {}",
-            if self.engine.engine_running {
-                "✅ YES"
-            } else {
-                "❌ NO"
-            }
-        );
-
-        // Speed display
-        println!("\n🏃 SPEED & TRANSMISSION:");
-        println!("   • Speed: {:.1} km/h", self.speed.vehicle_speed);
-        println!(
-            "   • Gear: {}",
-            match self.speed.gear_position {
-                0 => "P (Park)".to_string(),
-                1..=6 => format!("{}st/nd/rd/th", self.speed.gear_position),
-                15 => "R (Reverse)".to_string(),
-                _ => "Unknown".to_string(),
-            }
-        );
-        println!(
-            "   • Wheel speeds: FL={:.1}, FR={:.1}, RL={:.1}, RR={:.1} km/h",
-            self.speed.wheel_speeds[0],
-            self.speed.wheel_speeds[1],
-            self.speed.wheel_speeds[2],
-            self.speed.wheel_speeds[3]
-        );
-        println!(
-            "   • ABS: {}",
-            if self.speed.abs_active {
-                "🔴 ACTIVE"
-            } else {
-                "⚪ INACTIVE"
-            }
-        );
-        println!(
-            "   • Traction Control: {}",
-            if self.speed.traction_control {
-                "🔴 ON"
-            } else {
-                "⚪ OFF"
-            }
-        );
-        println!(
-            "   • Cruise Control: {}",
-            if self.speed.cruise_control {
-                "🔴 ON"
-            } else {
-                "⚪ OFF"
-            }
-        );
-
-        // Climate display
-        println!("\n🌡️ CLIMATE CONTROL:");
-        println!("   • Cabin: {}°C", self.climate.cabin_temp);
-        println!("   • Target: {}°C", self.climate.target_temp);
-        println!("   • Outside: {}°C", self.climate.outside_temp);
-        println!("   • Fan Speed: {}/255", self.climate.fan_speed);
-        println!(
-            "   • AC: {}",
-            if self.climate.ac_compressor {
-                "❄️ ON"
-            } else {
-                "⚪ OFF"
-            }
-        );
-        println!(
-            "   • Heater: {}",
-            if self.climate.heater {
-                "🔥 ON"
-            } else {
-                "⚪ OFF"
-            }
-        );
-        println!(
-            "   • Defrost: {}",
-            if self.climate.defrost {
-                "💨 ON"
-            } else {
-                "⚪ OFF"
-            }
-        );
-        println!(
-            "   • Auto Mode: {}",
-            if self.climate.auto_mode {
-                "🤖 ON"
-            } else {
-                "👤 MANUAL"
-            }
-        );
-
-        println!("\n⏱️ Duration: {}ms", self.duration_ms);
-    }
-
-    pub fn show_can_messages(&self) {
-        let can_messages = self.to_can_messages();
-
-        println!("\n📡 CAN MESSAGES ({} total):", can_messages.len());
-        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-
-        for (i, msg) in can_messages.iter().enumerate() {
-            println!("🔌 CAN Message {}:", i + 1);
-            println!("   • ID: 0x{:03X}", msg.id);
-            println!("   • DLC: {}", msg.dlc);
-            println!("   • Data: {:02X?}", &msg.data[..msg.dlc as usize]);
-            println!(
-                "   • Purpose: {}",
-                match msg.id {
-                    0x100 => "Engine RPM + Fuel Pressure + Running status",
-                    0x101 => "Engine temperatures + Throttle + Load",
-                    0x200 => "Vehicle speed + Gear + Wheel speeds",
-                    0x201 => "Speed flags (ABS, Traction, Cruise)",
-                    0x300 => "Climate temperatures",
-                    0x301 => "Climate fan + flags",
-                    0x400 => "Step info (duration + name hash)",
-                    _ => "Unknown",
-                }
-            );
-            if i < can_messages.len() - 1 {
-                println!("   ├─────────────────────────────────────────");
-            }
-        }
-        println!("   └─────────────────────────────────────────");
+    /// Short description of the signals carried by a CAN ID
+    pub fn can_id_purpose(id: u16) -> &'static str {
+        can_ids().group(id).map_or("Unknown", FrameGroup::purpose)
     }
 }
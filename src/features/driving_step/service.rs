@@ -1,121 +1,583 @@
-use serde_json;
+use sqlx::any::AnyRow;
 use sqlx::Row;
-use std::collections::HashMap;
 
 use crate::common::error::AppError;
-use crate::core::can::CanMessage;
-use crate::features::driving_step::model::DrivingStep;
+use crate::common::pagination::Paging;
+use crate::config::db;
+use crate::config::encryption;
+use crate::config::frame_store::{self, frame_store, FrameBatch, FrameRange};
+use crate::config::rabbitmq::StepNotice;
+use crate::config::transport::StepTransport;
+use crate::core::can::{CanId, CanMessage, Endianness};
+use crate::core::clock::{Clock, SystemClock, TimeSource};
+use crate::core::metrics;
+use crate::core::signals::{self, SignalProfile};
+use crate::features::driving_step::model::{DrivingStep, StepFilter, StoredStep};
+use crate::features::vehicle::service as vehicle_service;
 
-pub async fn get_all_steps() -> Result<Vec<DrivingStep>, AppError> {
-    let pool = crate::config::sqlite::get_pool().await?;
+/// Convert a `can_messages` row into a CanMessage
+pub fn can_message_from_row(row: &AnyRow) -> Result<CanMessage, AppError> {
+    let id: i64 = row.try_get("id")?;
+    let dlc: i64 = row.try_get("dlc")?;
+    let data = encryption::open_frame(row.try_get("data")?, dlc as usize)
+        .map_err(AppError::internal_server_error)?;
+    let fd = db::flag(row, "fd")?;
+    let brs = db::flag(row, "brs")?;
+    let timestamp: String = row.try_get("timestamp")?;
+    let seq: Option<i64> = row.try_get("seq")?;
 
-    // Get all CAN messages ordered by timestamp
-    let rows = sqlx::query(
-        "SELECT id, dlc, data, timestamp 
-         FROM can_messages ORDER BY timestamp ASC",
+    if data.len() as i64 != dlc {
+        return Err(AppError::internal_server_error(format!(
+            "Stored frame 0x{:03X} has DLC {} but {} data bytes",
+            id,
+            dlc,
+            data.len()
+        )));
+    }
+    let mut message = CanId::try_from(id)
+        .and_then(|id| {
+            if fd {
+                CanMessage::try_new_fd(id, &data, brs, timestamp)
+            } else {
+                CanMessage::try_new(id, &data, timestamp)
+            }
+        })
+        .map_err(|e| AppError::internal_server_error(e.to_string()))?;
+    message.seq = seq.map(|seq| seq as u64);
+    Ok(message)
+}
+
+/// Read the byte order a `can_messages` row was encoded with
+pub fn endianness_from_row(row: &AnyRow) -> Result<Endianness, AppError> {
+    let endian: String = row.try_get("endian")?;
+    endian.parse().map_err(AppError::internal_server_error)
+}
+
+/// Byte order shared by every frame of a group, rejecting groups that mix encodings
+pub fn group_endianness(endians: &[Endianness]) -> Result<Endianness, String> {
+    let first = *endians.first().ok_or("No CAN frames in group")?;
+    if endians.iter().any(|endian| *endian != first) {
+        return Err("CAN frames of one step were stored with mixed endianness".to_string());
+    }
+
+    Ok(first)
+}
+
+/// Encode a DrivingStep and store all of its CAN frames under a fresh step id
+///
+/// Frames are written in a single transaction so readers never observe a partial step.
+pub async fn store_step(step: &DrivingStep, is_big_endian: bool) -> Result<StoredStep, AppError> {
+    store_step_with_clock(step, is_big_endian, &SystemClock).await
+}
+
+/// Store a step whose frames are stamped with the time read from `clock`
+pub async fn store_step_with_clock(
+    step: &DrivingStep,
+    is_big_endian: bool,
+    clock: &dyn Clock,
+) -> Result<StoredStep, AppError> {
+    store_frames(
+        step.to_can_messages_with_clock(is_big_endian, clock),
+        Endianness::from_is_big_endian(is_big_endian),
+        None,
+        None,
+        &step.step_name,
+    )
+    .await
+}
+
+/// Store already encoded frames as one step under a fresh step id
+///
+/// Frames are numbered after every frame stored so far, see `FrameStore::insert_batch`.
+/// `vehicle_id` must be a registered VIN, see `vehicle::controller::for_ingestion`, and
+/// `tenant` the name of the `TenantScope` storing the frames.
+pub async fn store_frames(
+    can_messages: Vec<CanMessage>,
+    endian: Endianness,
+    vehicle_id: Option<&str>,
+    tenant: Option<&str>,
+    step_name: &str,
+) -> Result<StoredStep, AppError> {
+    store_frames_as(
+        uuid::Uuid::new_v4().to_string(),
+        can_messages,
+        endian,
+        vehicle_id,
+        tenant,
+        step_name,
+    )
+    .await
+}
+
+/// Store frames as one step under an id chosen by the caller, such as an assembled step
+///
+/// Fails with `403 Forbidden` or `429 Too Many Requests` when the frames would exceed a quota
+/// of the tenant, see `tenant::service::check_quota`; stored frames count towards its usage.
+pub async fn store_frames_as(
+    step_id: String,
+    can_messages: Vec<CanMessage>,
+    endian: Endianness,
+    vehicle_id: Option<&str>,
+    tenant: Option<&str>,
+    step_name: &str,
+) -> Result<StoredStep, AppError> {
+    let batch = FrameBatch {
+        step_id,
+        frames: can_messages,
+        endian,
+        vehicle_id,
+        tenant,
+        step_name,
+    };
+    frame_store().insert_batch(batch).await
+}
+
+/// Store a step with the configured endianness and notify the reconstruction consumer
+pub async fn publish_step(
+    step: &DrivingStep,
+    transport: &StepTransport,
+) -> Result<StoredStep, AppError> {
+    publish_step_with_endian(step, DrivingStep::get_endianness_from_env(), transport).await
+}
+
+/// Store a step with explicit endianness, under its tenant, and notify the reconstruction
+/// consumer
+pub async fn publish_step_with_endian(
+    step: &DrivingStep,
+    is_big_endian: bool,
+    transport: &StepTransport,
+) -> Result<StoredStep, AppError> {
+    publish_vehicle_step(step, is_big_endian, None, step.tenant.as_deref(), transport).await
+}
+
+/// Store a step recorded by a registered vehicle for a tenant and notify the reconstruction
+/// consumer
+pub async fn publish_vehicle_step(
+    step: &DrivingStep,
+    is_big_endian: bool,
+    vehicle_id: Option<&str>,
+    tenant: Option<&str>,
+    transport: &StepTransport,
+) -> Result<StoredStep, AppError> {
+    let ingested_at_us = metrics::now_us();
+    let stored = store_frames(
+        step.to_can_messages_with_endian(is_big_endian),
+        Endianness::from_is_big_endian(is_big_endian),
+        vehicle_id,
+        tenant,
+        &step.step_name,
     )
-    .fetch_all(pool)
     .await?;
+    notify(&stored, &step.step_name, ingested_at_us, transport).await?;
+    Ok(stored)
+}
 
-    let mut can_messages = Vec::new();
-    for row in rows {
-        let id: i64 = row.try_get("id")?;
-        let dlc: i64 = row.try_get("dlc")?;
-        let data_json: String = row.try_get("data")?;
-        let timestamp: String = row.try_get("timestamp")?;
+/// Store frames encoded by a third party as one step and notify the reconstruction consumer
+pub async fn publish_frames(
+    frames: Vec<CanMessage>,
+    endian: Endianness,
+    vehicle_id: Option<&str>,
+    tenant: Option<&str>,
+    step_name: &str,
+    transport: &StepTransport,
+) -> Result<StoredStep, AppError> {
+    let ingested_at_us = metrics::now_us();
+    let stored = store_frames(frames, endian, vehicle_id, tenant, step_name).await?;
+    notify(&stored, step_name, ingested_at_us, transport).await?;
+    Ok(stored)
+}
 
-        let data: [u8; 8] = serde_json::from_str(&data_json)?;
+/// Store frames under the step id they were assembled with and notify the reconstruction
+/// consumer
+pub async fn publish_frames_as(
+    step_id: &str,
+    frames: Vec<CanMessage>,
+    endian: Endianness,
+    vehicle_id: Option<&str>,
+    tenant: Option<&str>,
+    step_name: &str,
+    transport: &StepTransport,
+) -> Result<StoredStep, AppError> {
+    let ingested_at_us = metrics::now_us();
+    let stored = store_frames_as(
+        step_id.to_string(),
+        frames,
+        endian,
+        vehicle_id,
+        tenant,
+        step_name,
+    )
+    .await?;
+    notify(&stored, step_name, ingested_at_us, transport).await?;
+    Ok(stored)
+}
+
+/// Send the step notice of freshly stored frames to the consumer
+async fn notify(
+    stored: &StoredStep,
+    step_name: &str,
+    ingested_at_us: i64,
+    transport: &StepTransport,
+) -> Result<(), AppError> {
+    let notice = StepNotice {
+        step_id: stored.step_id.clone(),
+        step_name: step_name.to_string(),
+        endian: stored.endian.as_str().to_string(),
+        ingested_at_us: Some(ingested_at_us),
+        stored_at_us: Some(metrics::now_us()),
+    };
+    transport
+        .publish(&notice)
+        .await
+        .map_err(|e| AppError::internal_server_error(e.to_string()))
+}
+
+/// Fetch the CAN frames stored for one step id, with the byte order they were stored in
+///
+/// With a `tenant`, steps stored by others are not found; `None` reads every step.
+pub async fn get_step_frames(
+    step_id: &str,
+    tenant: Option<&str>,
+) -> Result<Option<StoredStep>, AppError> {
+    let range = FrameRange {
+        tenant,
+        step_id: Some(step_id),
+        after_seq: None,
+    };
+    Ok(frame_store().query_range(range).await?.pop())
+}
 
-        can_messages.push(CanMessage {
-            id: id as u16,
-            dlc: dlc as u8,
-            data,
-            timestamp,
+/// CAN profile of the vehicle that recorded a step, the default one for anonymous steps
+///
+/// A vehicle deleted since its frames were stored falls back to the default profile.
+pub async fn signal_profile(vehicle_id: Option<&str>) -> Result<&'static SignalProfile, AppError> {
+    let vehicle = match vehicle_id {
+        Some(vin) => vehicle_service::get_vehicle(vin).await?,
+        None => None,
+    };
+    let name = vehicle
+        .as_ref()
+        .map_or(signals::DEFAULT_PROFILE, |vehicle| {
+            vehicle.can_profile.as_str()
         });
-    }
+    signals::profile(name)
+        .ok_or_else(|| AppError::internal_server_error(format!("Unknown CAN profile '{}'", name)))
+}
 
-    // Group CAN messages by timestamp to reconstruct driving steps
-    let mut grouped_messages: HashMap<String, Vec<CanMessage>> = HashMap::new();
+/// Reconstruct the DrivingStep stored under `step_id`, decoding with its stored endianness
+///
+/// The step keeps the name it was stored with; `step_name` names steps stored without one.
+pub async fn reconstruct_step(
+    step_id: &str,
+    step_name: String,
+    tenant: Option<&str>,
+) -> Result<Option<DrivingStep>, AppError> {
+    let Some(stored) = get_step_frames(step_id, tenant).await? else {
+        return Ok(None);
+    };
 
-    for msg in can_messages {
-        grouped_messages
-            .entry(msg.timestamp.clone())
-            .or_insert_with(Vec::new)
-            .push(msg);
-    }
+    let profile = signal_profile(stored.vehicle_id.as_deref()).await?;
+    let mut step = profile
+        .decode_step(
+            &stored.can_messages,
+            stored.step_name.unwrap_or(step_name),
+            stored.endian.is_big_endian(),
+        )
+        .map_err(AppError::internal_server_error)?;
+    step.step_id = Some(stored.step_id);
+    step.frames = stored.can_messages;
+    step.tenant = stored.tenant;
+    step.vehicle_id = stored.vehicle_id;
+    Ok(Some(step))
+}
 
-    let mut steps = Vec::new();
-    let mut step_counter = 1;
-
-    for (timestamp, messages) in grouped_messages {
-        if messages.len() >= 7 {
-            // We need 7 CAN messages for a complete DrivingStep
-            let step_name = format!("Step_{}", step_counter);
-            match DrivingStep::from_can_messages(&messages, step_name) {
-                Ok(step) => {
-                    steps.push(step);
-                    step_counter += 1;
+/// Every stored step, or those of `tenant`, in storage order
+///
+/// Steps are named as they were stored, or after their id when stored without a name.
+pub async fn get_all_steps(tenant: Option<&str>) -> Result<Vec<DrivingStep>, AppError> {
+    let range = FrameRange {
+        tenant,
+        ..FrameRange::default()
+    };
+    Ok(steps_from_stored(frame_store().query_range(range).await?))
+}
+
+/// Steps of `GET /driving-steps` by tenant, tag, time source, CAN ID and capture time of
+/// their first frame
+const STEP_FILTER: &str = "SELECT step_id FROM can_messages
+         WHERE step_id IS NOT NULL AND deleted_at IS NULL AND ($1 IS NULL OR tenant = $1)
+         GROUP BY step_id
+         HAVING ($2 IS NULL OR step_id IN (SELECT step_id FROM step_tags WHERE tag = $2))
+           AND ($3 IS NULL OR (step_id IN (SELECT step_id FROM simulated_steps)) = $3)
+           AND ($4 IS NULL OR COUNT(CASE WHEN id = $4 THEN 1 END) > 0)
+           AND ($5 IS NULL OR MIN(timestamp_ms) >= $5) AND ($6 IS NULL OR MIN(timestamp_ms) < $6)";
+
+/// One page of stored steps in storage order, with the number of steps matching
+pub async fn get_step_page(
+    tenant: Option<&str>,
+    filter: &StepFilter,
+    paging: Paging,
+) -> Result<(Vec<DrivingStep>, u64), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+    let simulated = filter.clock.map(|clock| clock == TimeSource::Simulated);
+    let can_id = filter.can_id.map(|id| u32::from(id) as i64);
+    let (from, to) = (
+        filter.from.map(|from| from.timestamp_millis()),
+        filter.to.map(|to| to.timestamp_millis()),
+    );
+
+    let sql = format!(
+        "SELECT id, dlc, data, fd, brs, timestamp, endian, seq, step_id, step_name, vehicle_id, tenant
+         FROM can_messages WHERE deleted_at IS NULL AND step_id IN (
+             {STEP_FILTER} ORDER BY MIN(seq) ASC LIMIT $7 OFFSET $8
+         )
+         ORDER BY seq ASC"
+    );
+    let query = sqlx::query(&sql)
+        .bind(tenant)
+        .bind(filter.tag.as_deref())
+        .bind(simulated)
+        .bind(can_id)
+        .bind(from)
+        .bind(to)
+        .bind(paging.limit as i64)
+        .bind(paging.offset as i64)
+        .fetch_all(pool);
+    let rows = db::labelled("step_page", query).await?;
+
+    let sql = format!("SELECT COUNT(*) FROM ({STEP_FILTER}) AS steps");
+    let query = sqlx::query_scalar(&sql)
+        .bind(tenant)
+        .bind(filter.tag.as_deref())
+        .bind(simulated)
+        .bind(can_id)
+        .bind(from)
+        .bind(to)
+        .fetch_one(pool);
+    let total: i64 = db::labelled("step_count", query).await?;
+
+    Ok((
+        steps_from_stored(frame_store::stored_steps(&rows)?),
+        total as u64,
+    ))
+}
+
+/// Decode stored steps, named as they were stored or after their id
+///
+/// Steps that no longer decode are skipped with a warning.
+pub fn steps_from_stored(stored: Vec<StoredStep>) -> Vec<DrivingStep> {
+    stored
+        .into_iter()
+        .filter_map(|stored| {
+            let step_name = stored.step_name.unwrap_or_else(|| stored.step_id.clone());
+            match DrivingStep::from_can_messages_with_endian(
+                &stored.can_messages,
+                step_name,
+                stored.endian.is_big_endian(),
+            ) {
+                Ok(mut step) => {
+                    step.frames = stored.can_messages;
+                    step.tenant = stored.tenant;
+                    step.vehicle_id = stored.vehicle_id;
+                    step.step_id = Some(stored.step_id);
+                    Some(step)
                 }
                 Err(e) => {
                     println!(
-                        "⚠️ Could not reconstruct driving step from timestamp {}: {}",
-                        timestamp, e
+                        "⚠️ Could not reconstruct driving step {}: {}",
+                        stored.step_id, e
                     );
+                    None
                 }
             }
+        })
+        .collect()
+}
+
+/// Most recently stored step that carries the frame `can_id`, for signals of optional groups
+pub async fn get_last_step_with_frame(
+    can_id: u16,
+    tenant: Option<&str>,
+) -> Result<Option<DrivingStep>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let query = sqlx::query_scalar(
+        "SELECT step_id FROM can_messages
+         WHERE id = $1 AND step_id IS NOT NULL AND deleted_at IS NULL
+           AND ($2 IS NULL OR tenant = $2)
+         ORDER BY seq DESC LIMIT 1",
+    )
+    .bind(can_id as i64)
+    .bind(tenant)
+    .fetch_optional(pool);
+    let step_id: Option<String> = db::labelled("last_step_with_frame", query).await?;
+
+    match step_id {
+        Some(step_id) => reconstruct_step(&step_id, step_id.clone(), tenant).await,
+        None => Ok(None),
+    }
+}
+
+/// Sequence number of the latest live frame of `tenant`, or of any tenant, `None` before the
+/// first one; it changes whenever a step is stored, trashed or restored at the end
+pub async fn latest_seq(tenant: Option<&str>) -> Result<Option<i64>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let query = sqlx::query_scalar(
+        "SELECT MAX(seq) FROM can_messages WHERE deleted_at IS NULL AND ($1 IS NULL OR tenant = $1)",
+    )
+    .bind(tenant)
+    .fetch_one(pool);
+    Ok(db::labelled("latest_seq", query).await?)
+}
+
+pub async fn get_last_step(tenant: Option<&str>) -> Result<Option<DrivingStep>, AppError> {
+    let Some(stored) = frame_store().latest_group(tenant).await? else {
+        return Ok(None);
+    };
+    let step_id = stored.step_id.clone();
+    let step_name = stored.step_name.unwrap_or_else(|| step_id.clone());
+    match DrivingStep::from_can_messages_with_endian(
+        &stored.can_messages,
+        step_name,
+        stored.endian.is_big_endian(),
+    ) {
+        Ok(mut step) => {
+            step.step_id = Some(step_id);
+            step.frames = stored.can_messages;
+            step.tenant = stored.tenant;
+            step.vehicle_id = stored.vehicle_id;
+            Ok(Some(step))
+        }
+        Err(e) => {
+            println!(
+                "⚠️ Could not reconstruct latest driving step {}: {}",
+                step_id, e
+            );
+            Ok(None)
         }
     }
+}
+
+/// The `count` latest steps stored before `step_id`, or before now, oldest first
+///
+/// Every tenant's steps are read; steps that can no longer be reconstructed are skipped.
+pub async fn get_steps_stored_before(
+    step_id: Option<&str>,
+    count: usize,
+) -> Result<Vec<DrivingStep>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
 
+    let query = sqlx::query_scalar(
+        "SELECT step_id FROM can_messages
+         WHERE step_id IS NOT NULL AND deleted_at IS NULL
+         GROUP BY step_id
+         HAVING $1 IS NULL OR MIN(seq) < (SELECT MIN(seq) FROM can_messages WHERE step_id = $1)
+         ORDER BY MIN(seq) DESC LIMIT $2",
+    )
+    .bind(step_id)
+    .bind(count as i64)
+    .fetch_all(pool);
+    let mut step_ids: Vec<String> = db::labelled("steps_stored_before", query).await?;
+    step_ids.reverse();
+
+    let mut steps = Vec::new();
+    for step_id in step_ids {
+        match reconstruct_step(&step_id, step_id.clone(), None).await {
+            Ok(Some(step)) => steps.push(step),
+            Ok(None) => {}
+            Err(e) => println!("⚠️ Could not reconstruct driving step {}: {}", step_id, e),
+        }
+    }
     Ok(steps)
 }
 
-pub async fn get_last_step() -> Result<Option<DrivingStep>, AppError> {
-    let pool = crate::config::sqlite::get_pool().await?;
+/// Ids of the stored steps `POST /admin/recode` would rewrite in `to`, oldest first
+pub async fn get_steps_to_recode(
+    to: Endianness,
+    from: Option<Endianness>,
+    vehicle_id: Option<&str>,
+) -> Result<Vec<String>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
 
-    // Get the latest 7 CAN messages (should contain one complete DrivingStep)
-    let rows = sqlx::query(
-        "SELECT id, dlc, data, timestamp 
-         FROM can_messages ORDER BY timestamp DESC LIMIT 7",
+    let step_ids = sqlx::query_scalar(
+        "SELECT step_id FROM can_messages
+         WHERE step_id IS NOT NULL AND endian != $1
+           AND ($2 IS NULL OR endian = $2) AND ($3 IS NULL OR vehicle_id = $3)
+         GROUP BY step_id ORDER BY MIN(seq) ASC",
     )
+    .bind(to.as_str())
+    .bind(from.map(Endianness::as_str))
+    .bind(vehicle_id)
     .fetch_all(pool)
     .await?;
+    Ok(step_ids)
+}
 
-    if rows.is_empty() {
-        return Ok(None);
+/// Rewrite the frames of a stored step in the byte order `to`, returning how many changed
+///
+/// Frames keep their CAN ID, timestamp and sequence number. The step is decoded with its
+/// stored byte order and re-encoded, and only rewritten when the new frames decode to the
+/// same values.
+pub async fn recode_step(step_id: &str, to: Endianness, dry_run: bool) -> Result<u64, AppError> {
+    let stored = get_step_frames(step_id, None)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Step '{}'", step_id)))?;
+    let profile = signal_profile(stored.vehicle_id.as_deref()).await?;
+    let recoded = recode_frames(&stored.can_messages, profile, stored.endian, to)
+        .map_err(AppError::internal_server_error)?;
+    if dry_run {
+        return Ok(recoded.len() as u64);
     }
 
-    let mut can_messages = Vec::new();
-    for row in rows {
-        let id: i64 = row.try_get("id")?;
-        let dlc: i64 = row.try_get("dlc")?;
-        let data_json: String = row.try_get("data")?;
-        let timestamp: String = row.try_get("timestamp")?;
+    let pool = crate::config::db::get_pool().await?;
+    let mut transaction = pool.begin().await?;
+    for frame in &recoded {
+        sqlx::query("UPDATE can_messages SET dlc = $1, data = $2, endian = $3 WHERE seq = $4")
+            .bind(frame.dlc as i64)
+            .bind(encryption::seal_frame(frame.data()))
+            .bind(to.as_str())
+            .bind(frame.seq.map(|seq| seq as i64))
+            .execute(&mut *transaction)
+            .await?;
+    }
+    transaction.commit().await?;
+    Ok(recoded.len() as u64)
+}
 
-        let data: [u8; 8] = serde_json::from_str(&data_json)?;
+/// `frames` encoded in `from` re-encoded in `to`, one frame for each stored one
+fn recode_frames(
+    frames: &[CanMessage],
+    profile: &SignalProfile,
+    from: Endianness,
+    to: Endianness,
+) -> Result<Vec<CanMessage>, String> {
+    let step = profile.decode_step(frames, String::new(), from.is_big_endian())?;
+    let encoded = step.to_can_messages_with_endian(to.is_big_endian());
 
-        can_messages.push(CanMessage {
-            id: id as u16,
-            dlc: dlc as u8,
-            data,
-            timestamp,
-        });
+    let mut recoded = Vec::with_capacity(frames.len());
+    for frame in frames {
+        let Some(new) = encoded.iter().find(|encoded| encoded.id == frame.id) else {
+            return Err(format!(
+                "Frame {} has no counterpart in the wire format",
+                CanId::from(frame.id)
+            ));
+        };
+        // Encoded frames fit in 8 bytes, a length CAN-FD frames carry too
+        let mut new = new.clone();
+        new.fd = frame.fd;
+        new.brs = frame.brs;
+        new.timestamp = frame.timestamp.clone();
+        new.seq = frame.seq;
+        recoded.push(new);
     }
 
-    // Try to reconstruct DrivingStep from the latest CAN messages
-    if can_messages.len() >= 7 {
-        let step_name = "Latest_Step".to_string();
-        match DrivingStep::from_can_messages(&can_messages, step_name) {
-            Ok(step) => Ok(Some(step)),
-            Err(e) => {
-                println!("⚠️ Could not reconstruct latest driving step: {}", e);
-                Ok(None)
-            }
-        }
-    } else {
-        println!(
-            "⚠️ Not enough CAN messages ({}) to reconstruct driving step",
-            can_messages.len()
-        );
-        Ok(None)
+    let decoded = profile.decode_step(&recoded, String::new(), to.is_big_endian())?;
+    if decoded != step {
+        return Err(format!(
+            "Frames re-encoded in {} decode to other values",
+            to
+        ));
     }
+    Ok(recoded)
 }
@@ -0,0 +1,141 @@
+use actix_web::error::PayloadError;
+use actix_web::http::header::EntityTag;
+use actix_web::web::{Bytes, BytesMut};
+use futures_util::{Stream, StreamExt};
+
+use crate::common::error::AppError;
+use crate::common::http;
+use crate::common::pagination::{self, Page, Paging};
+use crate::features::event::import::LineParser;
+use crate::features::event::model::{Event, EventQuery, ImportFormat, ImportProgress};
+use crate::features::event::service;
+use crate::features::ingest::ParseError;
+use crate::features::tenant::TenantScope;
+
+/// Events stored per transaction, each followed by a progress line
+const IMPORT_BATCH: usize = 500;
+/// Longest line of an import body
+const MAX_LINE_BYTES: usize = 64 * 1024;
+/// Parse failures listed in the last progress line, the others are only counted
+const MAX_IMPORT_ERRORS: usize = 100;
+
+/// Validator of `GET /events`, from the latest stored event and the number of live ones
+pub async fn etag(scope: &TenantScope) -> Result<EntityTag, AppError> {
+    let (latest, count) = service::events_version(scope.name()).await?;
+    Ok(http::weak_etag(format!("events-{}-{}", latest, count)))
+}
+
+pub async fn list(query: &EventQuery, scope: &TenantScope) -> Result<Page<Event>, AppError> {
+    let paging = Paging::new(query.limit, query.offset)?;
+    pagination::validate_range(query.from, query.to)?;
+    let (events, total) = service::get_events(
+        scope.name(),
+        query.name.as_deref(),
+        query.kind,
+        query.min_severity,
+        query.from,
+        query.to,
+        paging,
+    )
+    .await?;
+    Ok(Page::new(events, total, paging))
+}
+
+/// Store the events of an import body as its lines arrive, yielding the progress after
+/// every stored batch and once more when done
+///
+/// Lines that do not parse are counted and skipped. A body that cannot be read, a line
+/// longer than `MAX_LINE_BYTES` or a failed batch stops the import with an `error`;
+/// the batches stored before it are kept.
+pub fn import<B>(
+    format: ImportFormat,
+    mut body: B,
+    tenant: Option<String>,
+) -> impl Stream<Item = ImportProgress>
+where
+    B: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    async_stream::stream! {
+        let mut parser = LineParser::new(format);
+        let mut progress = ImportProgress::default();
+        let mut errors = Vec::new();
+        let mut batch = Vec::with_capacity(IMPORT_BATCH);
+        let mut buffer = BytesMut::new();
+        let mut ended = false;
+
+        loop {
+            // Next complete line, or what is left once the body ended
+            let line = match buffer.iter().position(|byte| *byte == b'\n') {
+                Some(end) => buffer.split_to(end + 1),
+                None if ended && !buffer.is_empty() => buffer.split(),
+                None if ended => break,
+                None if buffer.len() > MAX_LINE_BYTES => {
+                    progress.error = Some(format!(
+                        "Line {} is longer than {} bytes",
+                        progress.lines + 1,
+                        MAX_LINE_BYTES
+                    ));
+                    break;
+                }
+                None => {
+                    match body.next().await {
+                        Some(Ok(chunk)) => buffer.extend_from_slice(&chunk),
+                        Some(Err(e)) => {
+                            progress.error = Some(format!("Failed to read the body: {}", e));
+                            break;
+                        }
+                        None => ended = true,
+                    }
+                    continue;
+                }
+            };
+
+            progress.lines += 1;
+            let number = progress.lines as usize;
+            let parsed = std::str::from_utf8(&line)
+                .map_err(|_| ParseError::new(number, "Line is not valid UTF-8"))
+                .and_then(|text| parser.parse(number, text, tenant.as_deref()));
+            match parsed {
+                Ok(Some(event)) => batch.push(event),
+                Ok(None) => {}
+                Err(error) => {
+                    progress.failed += 1;
+                    if errors.len() < MAX_IMPORT_ERRORS {
+                        errors.push(error.to_string());
+                    }
+                }
+            }
+
+            if batch.len() == IMPORT_BATCH {
+                if let Err(e) = store_batch(&mut batch, &mut progress).await {
+                    progress.error = Some(e.to_string());
+                    break;
+                }
+                yield progress.clone();
+            }
+        }
+
+        if progress.error.is_none() {
+            if let Err(e) = store_batch(&mut batch, &mut progress).await {
+                progress.error = Some(e.to_string());
+            }
+        }
+        progress.done = true;
+        progress.errors = errors;
+        yield progress;
+    }
+}
+
+async fn store_batch(
+    batch: &mut Vec<Event>,
+    progress: &mut ImportProgress,
+) -> Result<(), AppError> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+    let stored = service::store_events(batch).await?;
+    progress.imported += stored;
+    progress.duplicates += batch.len() as u64 - stored;
+    batch.clear();
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use chrono::{DateTime, Utc};
+
+use crate::features::event::model::{Event, EventKind, ImportFormat, ImportedEvent};
+use crate::features::ingest::ParseError;
+
+/// Turns the lines of an import body into events, one line at a time
+///
+/// CSV bodies start with a header row naming their columns, in any order: `timestamp`,
+/// `name` and `message` are required, `id`, `kind`, `severity`, `step_name` and
+/// `payload` (JSON) are optional. Fields holding commas must be double-quoted, with `""`
+/// for a quote; a quoted field cannot span lines.
+pub struct LineParser {
+    format: ImportFormat,
+    /// Columns of the CSV header, once read
+    columns: Option<Vec<String>>,
+}
+
+impl LineParser {
+    pub fn new(format: ImportFormat) -> Self {
+        LineParser {
+            format,
+            columns: None,
+        }
+    }
+
+    /// Event of the 1-based line `line`, `None` for blank lines and the CSV header
+    pub fn parse(
+        &mut self,
+        line: usize,
+        text: &str,
+        tenant: Option<&str>,
+    ) -> Result<Option<Event>, ParseError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(None);
+        }
+        let imported = match self.format {
+            ImportFormat::Ndjson => {
+                serde_json::from_str(text).map_err(|e| ParseError::new(line, e))?
+            }
+            ImportFormat::Csv => match self.csv_row(line, text)? {
+                Some(imported) => imported,
+                None => return Ok(None),
+            },
+        };
+        event(imported, tenant)
+            .map(Some)
+            .map_err(|e| ParseError::new(line, e))
+    }
+
+    fn csv_row(&mut self, line: usize, text: &str) -> Result<Option<ImportedEvent>, ParseError> {
+        let fields = split_row(text).map_err(|e| ParseError::new(line, e))?;
+        let Some(columns) = &self.columns else {
+            let columns: Vec<String> = fields
+                .iter()
+                .map(|column| column.to_ascii_lowercase())
+                .collect();
+            if let Some(missing) = ["timestamp", "name", "message"]
+                .into_iter()
+                .find(|required| !columns.iter().any(|column| column == required))
+            {
+                return Err(ParseError::new(
+                    line,
+                    format!("Header must name a '{}' column", missing),
+                ));
+            }
+            self.columns = Some(columns);
+            return Ok(None);
+        };
+        if fields.len() != columns.len() {
+            return Err(ParseError::new(
+                line,
+                format!("Expected {} fields, found {}", columns.len(), fields.len()),
+            ));
+        }
+
+        let field = |name: &str| {
+            columns
+                .iter()
+                .position(|column| column == name)
+                .map(|index| fields[index].as_str())
+                .filter(|value| !value.is_empty())
+        };
+        let parse_error = |e: String| ParseError::new(line, e);
+        Ok(Some(ImportedEvent {
+            id: field("id").map(str::to_string),
+            kind: field("kind")
+                .map(str::parse)
+                .transpose()
+                .map_err(parse_error)?
+                .unwrap_or(EventKind::Imported),
+            name: field("name").unwrap_or_default().to_string(),
+            severity: field("severity")
+                .map(str::parse)
+                .transpose()
+                .map_err(parse_error)?
+                .unwrap_or_default(),
+            message: field("message").unwrap_or_default().to_string(),
+            payload: field("payload")
+                .map(serde_json::from_str)
+                .transpose()
+                .map_err(|e| ParseError::new(line, format!("Invalid payload, {}", e)))?
+                .unwrap_or_default(),
+            step_name: field("step_name").map(str::to_string),
+            source_ref: None,
+            timestamp: field("timestamp").unwrap_or_default().to_string(),
+        }))
+    }
+}
+
+/// Event stored for `imported`, owned by `tenant`
+fn event(imported: ImportedEvent, tenant: Option<&str>) -> Result<Event, String> {
+    if imported.name.trim().is_empty() {
+        return Err("Event name must not be empty".to_string());
+    }
+    let timestamp = DateTime::parse_from_rfc3339(&imported.timestamp)
+        .map_err(|e| format!("Invalid timestamp '{}': {}", imported.timestamp, e))?;
+
+    Ok(Event {
+        id: imported
+            .id
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        timestamp: timestamp.with_timezone(&Utc).to_rfc3339(),
+        ..Event::new(
+            imported.kind,
+            imported.name,
+            imported.message,
+            imported.step_name,
+        )
+        .with_severity(imported.severity)
+        .with_payload(imported.payload)
+        .with_source(imported.source_ref)
+        .with_tenant(tenant.map(str::to_string))
+    })
+}
+
+/// Fields of a CSV row, trimmed
+fn split_row(row: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = row.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            (',', false) => fields.push(std::mem::take(&mut field).trim().to_string()),
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("Unterminated quoted field".to_string());
+    }
+    fields.push(field.trim().to_string());
+    Ok(fields)
+}
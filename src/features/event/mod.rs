@@ -0,0 +1,75 @@
+pub mod controller;
+pub mod import;
+pub mod model;
+pub mod service;
+
+use actix_web::http::header::{ETag, CONTENT_TYPE};
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Result};
+use futures_util::StreamExt;
+
+use crate::common::error::AppError;
+use crate::common::http;
+use crate::features::tenant::TenantScope;
+use crate::features::trash::{self, DeleteQuery, TrashKind};
+
+pub use model::Event;
+use model::{EventQuery, ImportFormat};
+
+/// Answers `304` to an `If-None-Match` naming the ETag of the stored events, for pollers
+#[get("/events")]
+pub async fn list(
+    req: HttpRequest,
+    query: web::Query<EventQuery>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    let etag = controller::etag(&scope).await?;
+    if let Some(response) = http::not_modified(&req, &etag) {
+        return Ok(response);
+    }
+    let events = controller::list(&query, &scope).await?;
+    Ok(HttpResponse::Ok().insert_header(ETag(etag)).json(events))
+}
+
+/// Move an event to the trash, or delete it for good with `?permanent=true`
+#[delete("/events/{id}")]
+pub async fn remove(
+    id: web::Path<String>,
+    query: web::Query<DeleteQuery>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    trash::controller::discard(TrashKind::Event, &id, &query, scope.name()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Historical events from other systems, read line by line while progress is streamed back
+#[post("/events/import")]
+pub async fn import_events(
+    req: HttpRequest,
+    payload: web::Payload,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    let content_type = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let format = ImportFormat::for_content_type(content_type).ok_or_else(|| {
+        AppError::unsupported_media_type(format!(
+            "Unsupported Content-Type '{}', expected application/x-ndjson or text/csv",
+            content_type
+        ))
+    })?;
+
+    let progress =
+        controller::import(format, payload, scope.name().map(str::to_string)).map(|progress| {
+            let line = serde_json::to_string(&progress).unwrap_or_default() + "\n";
+            Ok::<_, actix_web::Error>(web::Bytes::from(line))
+        });
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", "application/x-ndjson"))
+        .streaming(progress))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list).service(remove).service(import_events);
+}
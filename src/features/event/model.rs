@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::features::driving_step::DrivingStep;
+
+/// What produced an event, which also determines the shape of its payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// A rule started matching: `rule`, `expression` and the `values` it read
+    Rule,
+    /// A harsh acceleration, braking or cornering: `manoeuvre`, `acceleration_ms2`,
+    /// `speed_kmh` and `trip_id`
+    HarshManoeuvre,
+    /// A step whose frames stopped arriving before it was complete: `step_id`,
+    /// `vehicle_id`, the `received` and `missing` CAN IDs and `waited_ms`
+    IncompleteStep,
+    /// A registered device stopped sending heartbeats: `device_id`, `kind`, `firmware`,
+    /// `version`, `last_seen_at` and `timeout_ms`
+    DeviceOffline,
+    /// Loaded from another system by `POST /events/import`, with the payload it came with
+    Imported,
+}
+
+impl EventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventKind::Rule => "rule",
+            EventKind::HarshManoeuvre => "harsh_manoeuvre",
+            EventKind::IncompleteStep => "incomplete_step",
+            EventKind::DeviceOffline => "device_offline",
+            EventKind::Imported => "imported",
+        }
+    }
+}
+
+impl std::str::FromStr for EventKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "rule" => Ok(EventKind::Rule),
+            "harsh_manoeuvre" => Ok(EventKind::HarshManoeuvre),
+            "incomplete_step" => Ok(EventKind::IncompleteStep),
+            "device_offline" => Ok(EventKind::DeviceOffline),
+            "imported" => Ok(EventKind::Imported),
+            other => Err(format!("Unknown event kind '{}'", other)),
+        }
+    }
+}
+
+/// Importance of an event, ordered from `debug` to `critical`
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Debug,
+    #[default]
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Debug => "debug",
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "debug" => Ok(Severity::Debug),
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "critical" => Ok(Severity::Critical),
+            other => Err(format!("Unknown event severity '{}'", other)),
+        }
+    }
+}
+
+/// Link from an event back to the telemetry it was derived from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SourceRef {
+    /// A stored step, served by `GET /driving-steps/{step_id}`
+    Step { step_id: String },
+    /// One CAN frame; frames stored before steps had ids have no `step_id`
+    Frame {
+        step_id: Option<String>,
+        can_id: u16,
+        timestamp: String,
+    },
+}
+
+impl SourceRef {
+    /// Reference to the stored step `step` was reconstructed from, if it was
+    pub fn of_step(step: &DrivingStep) -> Option<Self> {
+        step.step_id
+            .clone()
+            .map(|step_id| SourceRef::Step { step_id })
+    }
+}
+
+/// Something noteworthy that happened on the bus, stored in the `events` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    pub id: String,
+    pub kind: EventKind,
+    /// Name of the producer, e.g. the rule that fired
+    pub name: String,
+    #[serde(default)]
+    pub severity: Severity,
+    /// Human readable summary of the payload
+    pub message: String,
+    /// Structured details, shaped according to `kind`
+    #[serde(default)]
+    pub payload: Value,
+    /// Step the event was derived from, if any
+    pub step_name: Option<String>,
+    /// Telemetry the event was derived from, if known
+    #[serde(default)]
+    pub source_ref: Option<SourceRef>,
+    pub timestamp: String,
+    /// Tenant owning the telemetry the event was derived from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+}
+
+impl Event {
+    /// Create an `info` event with a fresh id and no payload, timestamped now
+    pub fn new(
+        kind: EventKind,
+        name: impl Into<String>,
+        message: impl Into<String>,
+        step_name: Option<String>,
+    ) -> Self {
+        Event {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            name: name.into(),
+            severity: Severity::default(),
+            message: message.into(),
+            payload: Value::Null,
+            step_name,
+            source_ref: None,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            tenant: None,
+        }
+    }
+
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    pub fn with_payload(mut self, payload: Value) -> Self {
+        self.payload = payload;
+        self
+    }
+
+    pub fn with_source(mut self, source_ref: Option<SourceRef>) -> Self {
+        self.source_ref = source_ref;
+        self
+    }
+
+    pub fn with_tenant(mut self, tenant: Option<String>) -> Self {
+        self.tenant = tenant;
+        self
+    }
+}
+
+/// Query parameters accepted by `GET /events`
+#[derive(Debug, Deserialize)]
+pub struct EventQuery {
+    /// Only return events with this name
+    pub name: Option<String>,
+    /// Only return events of this kind
+    pub kind: Option<EventKind>,
+    /// Only return events at least this severe
+    pub min_severity: Option<Severity>,
+    /// Only return events at or after this RFC3339 time
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    /// Only return events before this RFC3339 time
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+    /// Events per page, newest first, `DEFAULT_LIMIT` when omitted
+    pub limit: Option<u32>,
+    /// Events of the previous pages to skip
+    pub offset: Option<u32>,
+}
+
+/// Body formats accepted by `POST /events/import`, chosen by the Content-Type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// One JSON event per line (`application/x-ndjson`)
+    Ndjson,
+    /// A header row naming the columns, then one event per row (`text/csv`)
+    Csv,
+}
+
+impl ImportFormat {
+    /// Format registered for `content_type`, ignoring its parameters (`; charset=utf-8`)
+    pub fn for_content_type(content_type: &str) -> Option<Self> {
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        match essence.as_str() {
+            "application/x-ndjson" | "application/jsonl" => Some(ImportFormat::Ndjson),
+            "text/csv" => Some(ImportFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+fn imported_kind() -> EventKind {
+    EventKind::Imported
+}
+
+/// One line of an import body; `kind` defaults to `imported` and `id` to a fresh one
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportedEvent {
+    pub id: Option<String>,
+    #[serde(default = "imported_kind")]
+    pub kind: EventKind,
+    pub name: String,
+    #[serde(default)]
+    pub severity: Severity,
+    pub message: String,
+    #[serde(default)]
+    pub payload: Value,
+    pub step_name: Option<String>,
+    #[serde(default)]
+    pub source_ref: Option<SourceRef>,
+    /// RFC3339, stored in UTC
+    pub timestamp: String,
+}
+
+/// Progress of `POST /events/import`, streamed as one JSON line per stored batch
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportProgress {
+    /// Lines read so far, blank lines and the CSV header included
+    pub lines: u64,
+    pub imported: u64,
+    /// Events whose id was already stored, left unchanged
+    pub duplicates: u64,
+    /// Lines that did not parse into an event
+    pub failed: u64,
+    /// Set on the last line of the response only
+    pub done: bool,
+    /// Why the import stopped before the end of the body
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// First parse failures as `line N: reason`, on the last line of the response only
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
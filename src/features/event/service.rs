@@ -0,0 +1,190 @@
+use chrono::{DateTime, Utc};
+use sqlx::any::AnyRow;
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::common::pagination::Paging;
+use crate::config::db;
+use crate::config::encryption::{self, Column};
+use crate::core::clock;
+use crate::features::event::model::{Event, EventKind, Severity};
+
+fn event_from_row(row: &AnyRow) -> Result<Event, AppError> {
+    let kind: String = row.try_get("kind")?;
+    let severity: String = row.try_get("severity")?;
+    let message = encryption::open_text(Column::EventMessage, row.try_get("message")?)
+        .map_err(AppError::internal_server_error)?;
+    let payload = encryption::open_text(Column::EventPayload, row.try_get("payload")?)
+        .map_err(AppError::internal_server_error)?;
+    let source_ref: Option<String> = row.try_get("source_ref")?;
+
+    Ok(Event {
+        id: row.try_get("id")?,
+        kind: kind.parse().map_err(AppError::internal_server_error)?,
+        name: row.try_get("name")?,
+        severity: severity.parse().map_err(AppError::internal_server_error)?,
+        message,
+        payload: serde_json::from_str(&payload)?,
+        step_name: row.try_get("step_name")?,
+        source_ref: source_ref
+            .map(|source_ref| serde_json::from_str(&source_ref))
+            .transpose()?,
+        timestamp: row.try_get("timestamp")?,
+        tenant: row.try_get("tenant")?,
+    })
+}
+
+pub async fn store_event(event: &Event) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(
+        "INSERT INTO events
+         (id, kind, name, severity, message, payload, step_name, source_ref, timestamp,
+          timestamp_ms, tenant)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)",
+    )
+    .bind(&event.id)
+    .bind(event.kind.as_str())
+    .bind(&event.name)
+    .bind(event.severity.as_str())
+    .bind(encryption::seal_text(Column::EventMessage, &event.message))
+    .bind(encryption::seal_text(
+        Column::EventPayload,
+        &event.payload.to_string(),
+    ))
+    .bind(&event.step_name)
+    .bind(
+        event
+            .source_ref
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?,
+    )
+    .bind(&event.timestamp)
+    .bind(clock::epoch_ms(&event.timestamp))
+    .bind(&event.tenant)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Insert events in one transaction, leaving those whose id is already stored; returns
+/// the number inserted
+pub async fn store_events(events: &[Event]) -> Result<u64, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let mut transaction = pool.begin().await?;
+    let mut inserted = 0;
+    for event in events {
+        inserted += sqlx::query(
+            "INSERT INTO events
+             (id, kind, name, severity, message, payload, step_name, source_ref, timestamp,
+              timestamp_ms, tenant)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(&event.id)
+        .bind(event.kind.as_str())
+        .bind(&event.name)
+        .bind(event.severity.as_str())
+        .bind(encryption::seal_text(Column::EventMessage, &event.message))
+        .bind(encryption::seal_text(
+            Column::EventPayload,
+            &event.payload.to_string(),
+        ))
+        .bind(&event.step_name)
+        .bind(
+            event
+                .source_ref
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?,
+        )
+        .bind(&event.timestamp)
+        .bind(clock::epoch_ms(&event.timestamp))
+        .bind(&event.tenant)
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+    }
+    transaction.commit().await?;
+    Ok(inserted)
+}
+
+/// Latest row number and count of the live events of `tenant`, or of every tenant, which
+/// change whenever an event is stored, trashed or restored
+pub async fn events_version(tenant: Option<&str>) -> Result<(i64, i64), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let row = sqlx::query(
+        "SELECT COALESCE(MAX(rowid), 0) AS latest, COUNT(*) AS count FROM events
+         WHERE deleted_at IS NULL AND ($1 IS NULL OR tenant = $1)",
+    )
+    .bind(tenant)
+    .fetch_one(pool)
+    .await?;
+
+    Ok((row.try_get("latest")?, row.try_get("count")?))
+}
+
+/// Conditions of `GET /events` on name, kind, minimum severity, time range and tenant
+///
+/// Severities are stored by name, so they are ranked in declaration order to compare.
+const EVENT_FILTER: &str = "($1 IS NULL OR name = $1) AND ($2 IS NULL OR kind = $2)
+           AND ($3 IS NULL OR CASE severity
+                 WHEN 'debug' THEN 0 WHEN 'info' THEN 1 WHEN 'warning' THEN 2 ELSE 3
+               END >= $3)
+           AND ($4 IS NULL OR timestamp_ms >= $4) AND ($5 IS NULL OR timestamp_ms < $5)
+           AND ($6 IS NULL OR tenant = $6) AND deleted_at IS NULL";
+
+/// One page of events, most recent first, optionally restricted by name, kind, minimum
+/// severity and time range, with the number of events matching
+///
+/// With a `tenant`, only the events derived from its telemetry are returned.
+pub async fn get_events(
+    tenant: Option<&str>,
+    name: Option<&str>,
+    kind: Option<EventKind>,
+    min_severity: Option<Severity>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    paging: Paging,
+) -> Result<(Vec<Event>, u64), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+    let (from, to) = (
+        from.map(|from| from.timestamp_millis()),
+        to.map(|to| to.timestamp_millis()),
+    );
+
+    let sql = format!(
+        "SELECT id, kind, name, severity, message, payload, step_name, source_ref, timestamp, tenant
+         FROM events WHERE {EVENT_FILTER}
+         ORDER BY timestamp_ms DESC LIMIT $7 OFFSET $8"
+    );
+    let query = sqlx::query(&sql)
+        .bind(name)
+        .bind(kind.map(EventKind::as_str))
+        .bind(min_severity.map(|severity| severity as i64))
+        .bind(from)
+        .bind(to)
+        .bind(tenant)
+        .bind(paging.limit as i64)
+        .bind(paging.offset as i64)
+        .fetch_all(pool);
+    let rows = db::labelled("events", query).await?;
+
+    let sql = format!("SELECT COUNT(*) FROM events WHERE {EVENT_FILTER}");
+    let query = sqlx::query_scalar(&sql)
+        .bind(name)
+        .bind(kind.map(EventKind::as_str))
+        .bind(min_severity.map(|severity| severity as i64))
+        .bind(from)
+        .bind(to)
+        .bind(tenant)
+        .fetch_one(pool);
+    let total: i64 = db::labelled("events_count", query).await?;
+
+    let events = rows.iter().map(event_from_row).collect::<Result<_, _>>()?;
+    Ok((events, total as u64))
+}
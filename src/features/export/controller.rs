@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+
+use crate::common::error::AppError;
+use crate::features::export::model::{
+    Export, ExportRequest, ExportRun, RunListQuery, RunQuery, DAILY,
+};
+use crate::features::export::scheduler::ExportScheduler;
+use crate::features::export::service;
+use crate::features::job::Job;
+use crate::features::scenario::scheduler::parse_cron;
+use crate::features::tenant::{Role, TenantRegistry, TenantScope};
+
+/// Export names are 1 to 64 letters, digits, `.`, `_` or `-`, as they name files
+fn validate_name(name: &str) -> Result<(), AppError> {
+    let valid = (1..=64).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        && !name.starts_with('.');
+    if !valid {
+        return Err(AppError::bad_request(format!(
+            "Invalid export name '{}', expected 1 to 64 letters, digits, '.', '_' or '-'",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Exports write files and reach object stores from the server, so only the administrator
+/// key may create or replace them, and none may while tenancy is off
+fn check_admin(registry: &TenantRegistry, scope: &TenantScope) -> Result<(), AppError> {
+    if !registry.enabled() {
+        return Err(AppError::forbidden(
+            "Exports are created with the administrator key, start the server with ADMIN_API_KEY",
+        ));
+    }
+    if scope.name().is_some() || scope.role() != Role::Editor {
+        return Err(AppError::forbidden(
+            "Exports are created with the administrator key",
+        ));
+    }
+    Ok(())
+}
+
+/// Create or replace an export; a replaced export keeps where its next run starts
+pub async fn create(
+    scheduler: &ExportScheduler,
+    registry: &TenantRegistry,
+    scope: &TenantScope,
+    request: ExportRequest,
+) -> Result<Export, AppError> {
+    check_admin(registry, scope)?;
+    validate_name(&request.name)?;
+    let cron = request.cron.unwrap_or_else(|| DAILY.to_string());
+    parse_cron(&cron).map_err(AppError::bad_request)?;
+    scheduler
+        .check(&request.destination)
+        .map_err(AppError::bad_request)?;
+
+    let exported_until = service::get_export(&request.name)
+        .await?
+        .and_then(|export| export.exported_until);
+    let export = Export {
+        name: request.name,
+        format: request.format,
+        destination: request.destination,
+        cron,
+        created_at: Utc::now().to_rfc3339(),
+        exported_until,
+    };
+    service::store_export(&export).await?;
+    scheduler
+        .register(export.clone())
+        .map_err(AppError::bad_request)?;
+    Ok(export)
+}
+
+pub async fn get(name: &str) -> Result<Export, AppError> {
+    service::get_export(name)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Export '{}'", name)))
+}
+
+pub async fn delete(scheduler: &ExportScheduler, name: &str) -> Result<(), AppError> {
+    if !service::delete_export(name).await? {
+        return Err(AppError::not_found(format!("Export '{}'", name)));
+    }
+    scheduler.remove(name);
+    Ok(())
+}
+
+fn parse_bound(name: &str, value: &str) -> Result<DateTime<Utc>, AppError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|instant| instant.with_timezone(&Utc))
+        .map_err(|e| AppError::bad_request(format!("Invalid {} '{}': {}", name, value, e)))
+}
+
+/// Start an export job now, over the window of the query or its next scheduled window
+pub async fn run(
+    scheduler: &ExportScheduler,
+    name: &str,
+    query: &RunQuery,
+) -> Result<Job, AppError> {
+    if query.from.is_none() && query.to.is_none() {
+        return scheduler.submit(name, None).await;
+    }
+
+    let to = match &query.to {
+        Some(to) => parse_bound("to", to)?,
+        None => Utc::now(),
+    };
+    let from = match &query.from {
+        Some(from) => parse_bound("from", from)?,
+        None => {
+            let export = get(name).await?;
+            match export.exported_until {
+                Some(until) => parse_bound("exported_until", &until)?,
+                None => to - chrono::Duration::days(1),
+            }
+        }
+    };
+    if from >= to {
+        return Err(AppError::bad_request("from must be before to"));
+    }
+    scheduler.submit(name, Some((from, to))).await
+}
+
+pub async fn runs(name: &str, query: &RunListQuery) -> Result<Vec<ExportRun>, AppError> {
+    get(name).await?;
+    service::get_runs(name, query.limit).await
+}
@@ -0,0 +1,73 @@
+pub mod controller;
+pub mod model;
+pub mod scheduler;
+pub mod service;
+pub mod writer;
+
+use actix_web::web::Data;
+use actix_web::{delete, get, post, web, HttpResponse, Result};
+
+use crate::common::error::AppError;
+use crate::features::tenant::{TenantRegistry, TenantScope};
+
+pub use model::{Export, ExportRun};
+use model::{ExportRequest, RunListQuery, RunQuery};
+pub use scheduler::ExportScheduler;
+
+#[get("/exports")]
+pub async fn list() -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(service::get_exports().await?))
+}
+
+/// Create or replace a scheduled export, with the administrator key
+#[post("/exports")]
+pub async fn create(
+    scheduler: Data<ExportScheduler>,
+    registry: Data<TenantRegistry>,
+    scope: TenantScope,
+    request: web::Json<ExportRequest>,
+) -> Result<HttpResponse, AppError> {
+    let export = controller::create(&scheduler, &registry, &scope, request.into_inner()).await?;
+    Ok(HttpResponse::Created().json(export))
+}
+
+#[get("/exports/{name}")]
+pub async fn get(name: web::Path<String>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::get(&name).await?))
+}
+
+#[delete("/exports/{name}")]
+pub async fn remove(
+    scheduler: Data<ExportScheduler>,
+    name: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    controller::delete(&scheduler, &name).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Run an export now in an export job, answering the queued job
+#[post("/exports/{name}/run")]
+pub async fn run(
+    scheduler: Data<ExportScheduler>,
+    name: web::Path<String>,
+    query: web::Query<RunQuery>,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Accepted().json(controller::run(&scheduler, &name, &query).await?))
+}
+
+#[get("/exports/{name}/runs")]
+pub async fn runs(
+    name: web::Path<String>,
+    query: web::Query<RunListQuery>,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::runs(&name, &query).await?))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list)
+        .service(create)
+        .service(get)
+        .service(remove)
+        .service(run)
+        .service(runs);
+}
@@ -0,0 +1,157 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::s3::S3Bucket;
+
+/// Cron expression of exports created without one: every day at midnight UTC
+pub const DAILY: &str = "0 0 0 * * *";
+
+/// File format of an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Header row, then one step per row
+    #[default]
+    Csv,
+    /// Columnar, zstd-compressed, with typed and nullable columns
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "csv" => Ok(ExportFormat::Csv),
+            "parquet" => Ok(ExportFormat::Parquet),
+            other => Err(format!("Unknown export format '{}'", other)),
+        }
+    }
+}
+
+/// Where the files of an export are written
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Destination {
+    /// Directory of the server, created when missing
+    Local { dir: PathBuf },
+    /// Bucket of an S3-compatible object store
+    S3(S3Bucket),
+    /// The `[s3]` bucket of the server settings, below `prefix`, so no credentials are
+    /// stored with the export
+    Bucket {
+        #[serde(default)]
+        prefix: String,
+    },
+}
+
+/// Steps periodically written to files of `format`, one file per run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Export {
+    pub name: String,
+    pub format: ExportFormat,
+    pub destination: Destination,
+    /// Cron expression with seconds, evaluated in UTC
+    pub cron: String,
+    pub created_at: String,
+    /// End of the window of the latest successful run, where the next run starts
+    pub exported_until: Option<String>,
+}
+
+/// Body of `POST /exports`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExportRequest {
+    pub name: String,
+    #[serde(default)]
+    pub format: ExportFormat,
+    pub destination: Destination,
+    /// Defaults to every day at midnight UTC
+    pub cron: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    Succeeded,
+    Failed,
+}
+
+impl RunStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RunStatus::Succeeded => "succeeded",
+            RunStatus::Failed => "failed",
+        }
+    }
+}
+
+impl std::str::FromStr for RunStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "succeeded" => Ok(RunStatus::Succeeded),
+            "failed" => Ok(RunStatus::Failed),
+            other => Err(format!("Unknown export run status '{}'", other)),
+        }
+    }
+}
+
+/// One execution of an export, covering the steps first captured in `[from, to)`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportRun {
+    pub id: String,
+    pub export: String,
+    pub from: String,
+    pub to: String,
+    pub status: RunStatus,
+    /// Steps written, one row each
+    pub rows: u64,
+    pub bytes: u64,
+    /// Path or URL of the file written
+    pub location: Option<String>,
+    pub error: Option<String>,
+    pub started_at: String,
+    pub finished_at: String,
+}
+
+/// Query parameters accepted by `POST /exports/{name}/run`
+///
+/// A run given its own window leaves `exported_until` alone, so the schedule is not
+/// affected by re-exporting older data.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RunQuery {
+    /// RFC3339 start of the window, defaults to `exported_until` or a day before `to`
+    pub from: Option<String>,
+    /// RFC3339 end of the window, defaults to now
+    pub to: Option<String>,
+}
+
+fn default_limit() -> u32 {
+    100
+}
+
+/// Query parameters accepted by `GET /exports/{name}/runs`
+#[derive(Debug, Deserialize)]
+pub struct RunListQuery {
+    /// Maximum number of runs, newest first
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+}
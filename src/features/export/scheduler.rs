@@ -0,0 +1,336 @@
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::common::error::AppError;
+use crate::config::object_store::ObjectStore;
+use crate::config::s3::S3Bucket;
+use crate::config::settings::ExportSettings;
+use crate::core::bus::Bus;
+use crate::features::export::model::{Destination, Export, ExportRun, RunStatus};
+use crate::features::export::{service, writer};
+use crate::features::job::{Job, JobKind, JobQueue};
+use crate::features::scenario::scheduler::parse_cron;
+use crate::features::system::{self, SystemEventKind};
+
+/// Window of the first run of an export, ending at the run
+const FIRST_WINDOW: chrono::Duration = chrono::Duration::days(1);
+
+struct CompiledExport {
+    export: Export,
+    cron: cron::Schedule,
+}
+
+/// Registered exports, checked every second by the scheduler task
+///
+/// Each scheduled run writes the steps first captured since the end of the previous
+/// successful run, so a failed run is caught up by the next one. Runs are submitted as
+/// `export` jobs.
+#[derive(Clone)]
+pub struct ExportScheduler {
+    exports: Arc<Mutex<Vec<CompiledExport>>>,
+    /// Bus receiving an `export_written` or `export_failed` system event for every run
+    bus: Bus,
+    /// Held by the run in progress, so two runs never write at once
+    running: Arc<tokio::sync::Mutex<()>>,
+    /// Bucket of the server settings, written to by `bucket` destinations
+    bucket: Option<S3Bucket>,
+    /// Root of the `local` destinations and endpoints allowed to `s3` ones
+    settings: ExportSettings,
+    jobs: JobQueue,
+}
+
+/// Endpoints compared without their trailing slash
+fn same_endpoint(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/') == b.trim_end_matches('/')
+}
+
+impl ExportScheduler {
+    /// Compile every export stored in the database
+    pub async fn load(
+        bus: Bus,
+        bucket: Option<S3Bucket>,
+        settings: ExportSettings,
+        jobs: JobQueue,
+    ) -> Result<Self, AppError> {
+        let scheduler = ExportScheduler {
+            exports: Arc::default(),
+            bus,
+            running: Arc::default(),
+            bucket,
+            settings,
+            jobs,
+        };
+        for export in service::get_exports().await? {
+            let name = export.name.clone();
+            if let Err(e) = scheduler.register(export) {
+                println!("⚠️ Skipping stored export '{}': {}", name, e);
+            }
+        }
+        Ok(scheduler)
+    }
+
+    /// Compile and add an export, replacing any export with the same name
+    pub fn register(&self, export: Export) -> Result<(), String> {
+        let cron = parse_cron(&export.cron)?;
+        self.check(&export.destination)?;
+
+        let mut exports = self.exports.lock().unwrap();
+        exports.retain(|compiled| compiled.export.name != export.name);
+        exports.push(CompiledExport { export, cron });
+        Ok(())
+    }
+
+    pub fn bucket(&self) -> Option<&S3Bucket> {
+        self.bucket.as_ref()
+    }
+
+    /// Directory of a `local` destination, below the export root
+    ///
+    /// Only relative paths without `..` are accepted, so an export cannot write outside
+    /// the root.
+    fn local_dir(&self, dir: &Path) -> Result<PathBuf, String> {
+        if dir.as_os_str().is_empty() {
+            return Err("Export dir must not be empty".to_string());
+        }
+        if !dir
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(format!(
+                "Invalid export dir '{}', expected a relative path without '..'",
+                dir.display()
+            ));
+        }
+        Ok(self.settings.dir.join(dir))
+    }
+
+    /// Whether exports may write to `destination`
+    ///
+    /// `s3` destinations may only name the endpoint of the `[s3]` bucket or one listed in
+    /// `exports.s3_endpoints`, so the server is not made to send requests to any host.
+    pub fn check(&self, destination: &Destination) -> Result<(), String> {
+        match destination {
+            Destination::Local { dir } => self.local_dir(dir).map(|_| ()),
+            Destination::S3(bucket) => {
+                bucket.validate()?;
+                let allowed = self
+                    .bucket
+                    .iter()
+                    .map(|configured| configured.endpoint.as_str())
+                    .chain(self.settings.s3_endpoints.iter().map(String::as_str))
+                    .any(|endpoint| same_endpoint(endpoint, &bucket.endpoint));
+                if !allowed {
+                    return Err(format!(
+                        "S3 endpoint '{}' is not allowed, add it to exports.s3_endpoints",
+                        bucket.endpoint
+                    ));
+                }
+                Ok(())
+            }
+            Destination::Bucket { .. } if self.bucket.is_none() => {
+                Err("Bucket exports need an [s3] section in the server settings".to_string())
+            }
+            Destination::Bucket { .. } => Ok(()),
+        }
+    }
+
+    /// Store the files of `destination` are written to
+    fn store(&self, destination: &Destination) -> Result<ObjectStore, AppError> {
+        self.check(destination).map_err(AppError::bad_request)?;
+        match destination {
+            Destination::Local { dir } => self
+                .local_dir(dir)
+                .map(ObjectStore::Local)
+                .map_err(AppError::bad_request),
+            Destination::S3(bucket) => Ok(ObjectStore::S3(bucket.clone())),
+            Destination::Bucket { prefix } => match &self.bucket {
+                Some(bucket) => Ok(ObjectStore::S3(bucket.clone()).scoped(prefix)),
+                None => Err(AppError::internal_server_error(
+                    "No [s3] bucket in the server settings",
+                )),
+            },
+        }
+    }
+
+    pub fn remove(&self, name: &str) {
+        self.exports
+            .lock()
+            .unwrap()
+            .retain(|compiled| compiled.export.name != name);
+    }
+
+    /// Names of the exports with a fire time in `(since, until]`
+    fn due(&self, since: DateTime<Utc>, until: DateTime<Utc>) -> Vec<String> {
+        self.exports
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|compiled| {
+                compiled
+                    .cron
+                    .after(&since)
+                    .next()
+                    .is_some_and(|next| next <= until)
+            })
+            .map(|compiled| compiled.export.name.clone())
+            .collect()
+    }
+
+    /// Submit a job running `name` over `window` as `run` does; the job fails when the
+    /// run does, with the error of the run
+    pub async fn submit(
+        &self,
+        name: &str,
+        window: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    ) -> Result<Job, AppError> {
+        if service::get_export(name).await?.is_none() {
+            return Err(AppError::not_found(format!("Export '{}'", name)));
+        }
+        let scheduler = self.clone();
+        let name = name.to_string();
+        self.jobs
+            .submit(JobKind::Export, None, move |_| async move {
+                let run = scheduler.run(&name, window).await?;
+                match run.status {
+                    RunStatus::Failed => Err(AppError::internal_server_error(
+                        run.error.unwrap_or_default(),
+                    )),
+                    RunStatus::Succeeded => Ok(serde_json::to_value(run)?),
+                }
+            })
+            .await
+    }
+
+    /// Submit a job for every due export; the jobs run one export at a time
+    pub fn spawn(&self) {
+        let scheduler = self.clone();
+        let mut tick = tokio::time::interval(Duration::from_secs(1));
+
+        tokio::spawn(async move {
+            let mut last_check = Utc::now();
+            loop {
+                tick.tick().await;
+                let now = Utc::now();
+                for name in scheduler.due(last_check, now) {
+                    if let Err(e) = scheduler.submit(&name, None).await {
+                        println!("❌ Failed to run export '{}': {}", name, e);
+                    }
+                }
+                last_check = now;
+            }
+        });
+    }
+
+    /// Write the steps of `window`, or of the export's next window, to its destination
+    ///
+    /// Failures of the export itself are reported by the run; only a run of an unknown
+    /// export or a database error fail the call. Runs given a window do not move
+    /// `exported_until`.
+    pub async fn run(
+        &self,
+        name: &str,
+        window: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    ) -> Result<ExportRun, AppError> {
+        let _running = self.running.lock().await;
+        let export = service::get_export(name)
+            .await?
+            .ok_or_else(|| AppError::not_found(format!("Export '{}'", name)))?;
+        let scheduled = window.is_none();
+        let (from, to) = window.unwrap_or_else(|| {
+            let to = Utc::now();
+            let from = export
+                .exported_until
+                .as_deref()
+                .and_then(|until| DateTime::parse_from_rfc3339(until).ok())
+                .map_or(to - FIRST_WINDOW, |until| until.with_timezone(&Utc));
+            (from, to)
+        });
+
+        let started_at = Utc::now().to_rfc3339();
+        let written = match self.store(&export.destination) {
+            Ok(store) => write(&export, &store, from, to).await,
+            Err(e) => Err(e),
+        };
+        let (status, rows, bytes, location, error) = match written {
+            Ok((rows, bytes, location)) => {
+                (RunStatus::Succeeded, rows, bytes, Some(location), None)
+            }
+            // Recorded without the prefix of internal errors, which the failed job adds back
+            Err(AppError::InternalServerError { message }) => {
+                (RunStatus::Failed, 0, 0, None, Some(message))
+            }
+            Err(e) => (RunStatus::Failed, 0, 0, None, Some(e.to_string())),
+        };
+        let run = ExportRun {
+            id: uuid::Uuid::new_v4().to_string(),
+            export: export.name.clone(),
+            from: from.to_rfc3339(),
+            to: to.to_rfc3339(),
+            status,
+            rows,
+            bytes,
+            location,
+            error,
+            started_at,
+            finished_at: Utc::now().to_rfc3339(),
+        };
+        service::store_run(&run).await?;
+
+        let (kind, message) = match run.status {
+            RunStatus::Succeeded => {
+                if scheduled {
+                    service::set_exported_until(&export.name, &run.to).await?;
+                }
+                (
+                    SystemEventKind::ExportWritten,
+                    format!(
+                        "Export '{}' wrote {} step(s) to {}",
+                        export.name,
+                        run.rows,
+                        run.location.as_deref().unwrap_or_default()
+                    ),
+                )
+            }
+            RunStatus::Failed => (
+                SystemEventKind::ExportFailed,
+                format!(
+                    "Export '{}' failed: {}",
+                    export.name,
+                    run.error.as_deref().unwrap_or_default()
+                ),
+            ),
+        };
+        println!("📦 {}", message);
+        system::controller::publish(&self.bus, kind, message, serde_json::to_value(&run)?).await;
+        Ok(run)
+    }
+}
+
+/// Write the steps of `[from, to)` to `store`, returning the number of rows, the size of
+/// the file and where it went
+async fn write(
+    export: &Export,
+    store: &ObjectStore,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<(u64, u64, String), AppError> {
+    let rows = writer::collect_rows(from, to).await?;
+    let bytes = writer::encode(export.format, &rows)?;
+    let size = bytes.len() as u64;
+    let key = format!(
+        "{name}/{name}_{}_{}.{}",
+        from.format("%Y%m%dT%H%M%SZ"),
+        to.format("%Y%m%dT%H%M%SZ"),
+        export.format.as_str(),
+        name = export.name
+    );
+
+    let location = store
+        .put(&key, bytes, export.format.content_type())
+        .await
+        .map_err(|e| AppError::internal_server_error(format!("{}: {}", store.location(&key), e)))?;
+    Ok((rows.len() as u64, size, location))
+}
@@ -0,0 +1,179 @@
+use sqlx::any::AnyRow;
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::config::encryption::{self, Column};
+use crate::features::export::model::{Destination, Export, ExportRun};
+
+const EXPORT_COLUMNS: &str = "name, format, destination, secret, cron, created_at, exported_until";
+const RUN_COLUMNS: &str =
+    "id, export, \"from\", \"to\", status, rows, bytes, location, error, started_at, finished_at";
+
+/// Secret of an S3 destination, kept in a column of its own since the serialized
+/// destination leaves it out, encrypted under the key in force
+fn secret(destination: &Destination) -> Option<String> {
+    match destination {
+        Destination::S3(bucket) => Some(encryption::seal_text(
+            Column::ExportSecret,
+            &bucket.secret_access_key,
+        )),
+        Destination::Local { .. } | Destination::Bucket { .. } => None,
+    }
+}
+
+fn export_from_row(row: &AnyRow) -> Result<Export, AppError> {
+    let format: String = row.try_get("format")?;
+    let destination: String = row.try_get("destination")?;
+    let secret: Option<String> = row.try_get("secret")?;
+
+    let mut destination: Destination = serde_json::from_str(&destination)?;
+    if let (Destination::S3(bucket), Some(secret)) = (&mut destination, secret) {
+        bucket.secret_access_key = encryption::open_text(Column::ExportSecret, secret)
+            .map_err(AppError::internal_server_error)?;
+    }
+    Ok(Export {
+        name: row.try_get("name")?,
+        format: format.parse().map_err(AppError::internal_server_error)?,
+        destination,
+        cron: row.try_get("cron")?,
+        created_at: row.try_get("created_at")?,
+        exported_until: row.try_get("exported_until")?,
+    })
+}
+
+fn run_from_row(row: &AnyRow) -> Result<ExportRun, AppError> {
+    let status: String = row.try_get("status")?;
+    let rows: i64 = row.try_get("rows")?;
+    let bytes: i64 = row.try_get("bytes")?;
+
+    Ok(ExportRun {
+        id: row.try_get("id")?,
+        export: row.try_get("export")?,
+        from: row.try_get("from")?,
+        to: row.try_get("to")?,
+        status: status.parse().map_err(AppError::internal_server_error)?,
+        rows: rows as u64,
+        bytes: bytes as u64,
+        location: row.try_get("location")?,
+        error: row.try_get("error")?,
+        started_at: row.try_get("started_at")?,
+        finished_at: row.try_get("finished_at")?,
+    })
+}
+
+/// Insert an export, replacing any previous export with the same name
+pub async fn store_export(export: &Export) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(&format!(
+        "INSERT INTO exports ({EXPORT_COLUMNS}) VALUES ($1, $2, $3, $4, $5, $6, $7)
+         ON CONFLICT (name) DO UPDATE SET
+             format = excluded.format, destination = excluded.destination,
+             secret = excluded.secret, cron = excluded.cron, created_at = excluded.created_at,
+             exported_until = excluded.exported_until"
+    ))
+    .bind(&export.name)
+    .bind(export.format.as_str())
+    .bind(serde_json::to_string(&export.destination)?)
+    .bind(secret(&export.destination))
+    .bind(&export.cron)
+    .bind(&export.created_at)
+    .bind(&export.exported_until)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_exports() -> Result<Vec<Export>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(&format!(
+        "SELECT {EXPORT_COLUMNS} FROM exports ORDER BY created_at ASC"
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(export_from_row).collect()
+}
+
+pub async fn get_export(name: &str) -> Result<Option<Export>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let row = sqlx::query(&format!(
+        "SELECT {EXPORT_COLUMNS} FROM exports WHERE name = $1"
+    ))
+    .bind(name)
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(export_from_row).transpose()
+}
+
+/// Delete an export and its runs, returning whether it existed
+pub async fn delete_export(name: &str) -> Result<bool, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query("DELETE FROM export_runs WHERE export = $1")
+        .bind(name)
+        .execute(pool)
+        .await?;
+    let result = sqlx::query("DELETE FROM exports WHERE name = $1")
+        .bind(name)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Move the start of the next scheduled window of an export
+pub async fn set_exported_until(name: &str, until: &str) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query("UPDATE exports SET exported_until = $1 WHERE name = $2")
+        .bind(until)
+        .bind(name)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn store_run(run: &ExportRun) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(&format!(
+        "INSERT INTO export_runs ({RUN_COLUMNS}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)"
+    ))
+    .bind(&run.id)
+    .bind(&run.export)
+    .bind(&run.from)
+    .bind(&run.to)
+    .bind(run.status.as_str())
+    .bind(run.rows as i64)
+    .bind(run.bytes as i64)
+    .bind(&run.location)
+    .bind(&run.error)
+    .bind(&run.started_at)
+    .bind(&run.finished_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Runs of an export, newest first
+pub async fn get_runs(export: &str, limit: u32) -> Result<Vec<ExportRun>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(&format!(
+        "SELECT {RUN_COLUMNS} FROM export_runs WHERE export = $1
+         ORDER BY started_at DESC LIMIT $2"
+    ))
+    .bind(export)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(run_from_row).collect()
+}
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use parquet::basic::{Compression, LogicalType, Repetition, TimeUnit, Type as PhysicalType};
+use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+
+use crate::common::error::AppError;
+use crate::common::pagination::Paging;
+use crate::core::signals::{SignalDef, SIGNALS};
+use crate::features::driving_step::model::StepFilter;
+use crate::features::driving_step::{service as step_service, DrivingStep};
+use crate::features::export::model::ExportFormat;
+use crate::features::history::SignalRecorder;
+
+/// Steps read from the database at once
+const PAGE: u32 = 500;
+/// Signals holding several values, left out like in the signal history
+const ARRAY_SIGNALS: [&str; 1] = ["wheel_speeds"];
+
+/// One exported step: who stored it, when its first frame was captured and every scalar
+/// signal in registry units, flags as 0 and 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportRow {
+    pub step_id: String,
+    pub step_name: String,
+    pub vehicle_id: Option<String>,
+    pub tenant: Option<String>,
+    pub timestamp_ms: i64,
+    /// Value of each of `signal_columns`, `None` when the step does not carry it
+    pub values: Vec<Option<f64>>,
+}
+
+/// Signals written as columns, after the step columns
+pub fn signal_columns() -> impl Iterator<Item = &'static SignalDef> {
+    SIGNALS
+        .iter()
+        .filter(|signal| !ARRAY_SIGNALS.contains(&signal.name))
+}
+
+impl ExportRow {
+    pub fn from_step(step: &DrivingStep) -> Self {
+        let values: HashMap<&str, f64> = SignalRecorder::values(step)
+            .into_iter()
+            .map(|value| (value.signal, value.value))
+            .collect();
+        let timestamp_ms = step
+            .frames
+            .iter()
+            .filter_map(|frame| DateTime::parse_from_rfc3339(&frame.timestamp).ok())
+            .map(|timestamp| timestamp.timestamp_millis())
+            .min()
+            .unwrap_or_default();
+
+        ExportRow {
+            step_id: step.step_id.clone().unwrap_or_default(),
+            step_name: step.step_name.clone(),
+            vehicle_id: step.vehicle_id.clone(),
+            tenant: step.tenant.clone(),
+            timestamp_ms,
+            values: signal_columns()
+                .map(|signal| values.get(signal.name).copied())
+                .collect(),
+        }
+    }
+}
+
+/// Rows of every tenant's steps first captured in `[from, to)`, in storage order
+pub async fn collect_rows(
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<ExportRow>, AppError> {
+    let filter = StepFilter {
+        from: Some(from),
+        to: Some(to),
+        ..StepFilter::default()
+    };
+    let mut rows = Vec::new();
+    let mut offset = 0;
+    loop {
+        let paging = Paging {
+            limit: PAGE,
+            offset,
+        };
+        let (steps, total) = step_service::get_step_page(None, &filter, paging).await?;
+        rows.extend(steps.iter().map(ExportRow::from_step));
+        offset += PAGE;
+        if steps.is_empty() || u64::from(offset) >= total {
+            return Ok(rows);
+        }
+    }
+}
+
+/// Encode `rows` as a file of `format`
+pub fn encode(format: ExportFormat, rows: &[ExportRow]) -> Result<Vec<u8>, AppError> {
+    match format {
+        ExportFormat::Csv => Ok(encode_csv(rows)),
+        ExportFormat::Parquet => {
+            encode_parquet(rows).map_err(|e| AppError::internal_server_error(e.to_string()))
+        }
+    }
+}
+
+/// Quote a CSV field holding a separator, a quote or a line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Header row, then one row per step with its capture time in RFC3339 and empty fields
+/// for the signals it does not carry
+pub fn encode_csv(rows: &[ExportRow]) -> Vec<u8> {
+    let mut header = vec!["step_id", "step_name", "vehicle_id", "tenant", "timestamp"];
+    header.extend(signal_columns().map(|signal| signal.name));
+    let mut csv = header.join(",");
+    csv.push('\n');
+
+    for row in rows {
+        let timestamp = DateTime::from_timestamp_millis(row.timestamp_ms)
+            .map(|timestamp| timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+            .unwrap_or_default();
+        let mut fields = vec![
+            csv_field(&row.step_id),
+            csv_field(&row.step_name),
+            csv_field(row.vehicle_id.as_deref().unwrap_or_default()),
+            csv_field(row.tenant.as_deref().unwrap_or_default()),
+            timestamp,
+        ];
+        fields.extend(
+            row.values
+                .iter()
+                .map(|value| value.map(|value| value.to_string()).unwrap_or_default()),
+        );
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv.into_bytes()
+}
+
+fn string_column(name: &str, repetition: Repetition) -> Result<Arc<Type>, ParquetError> {
+    Ok(Arc::new(
+        Type::primitive_type_builder(name, PhysicalType::BYTE_ARRAY)
+            .with_repetition(repetition)
+            .with_logical_type(Some(LogicalType::String))
+            .build()?,
+    ))
+}
+
+/// One row group of zstd-compressed columns: the step columns as strings, the capture time
+/// as a UTC millisecond timestamp, and one optional double per signal
+pub fn encode_parquet(rows: &[ExportRow]) -> Result<Vec<u8>, ParquetError> {
+    let mut fields = vec![
+        string_column("step_id", Repetition::REQUIRED)?,
+        string_column("step_name", Repetition::REQUIRED)?,
+        string_column("vehicle_id", Repetition::OPTIONAL)?,
+        string_column("tenant", Repetition::OPTIONAL)?,
+        Arc::new(
+            Type::primitive_type_builder("timestamp", PhysicalType::INT64)
+                .with_repetition(Repetition::REQUIRED)
+                .with_logical_type(Some(LogicalType::Timestamp {
+                    is_adjusted_to_u_t_c: true,
+                    unit: TimeUnit::MILLIS(Default::default()),
+                }))
+                .build()?,
+        ),
+    ];
+    for signal in signal_columns() {
+        fields.push(Arc::new(
+            Type::primitive_type_builder(signal.name, PhysicalType::DOUBLE)
+                .with_repetition(Repetition::OPTIONAL)
+                .build()?,
+        ));
+    }
+    let schema = Arc::new(
+        Type::group_type_builder("step")
+            .with_fields(fields)
+            .build()?,
+    );
+    let properties = Arc::new(
+        WriterProperties::builder()
+            .set_compression(Compression::ZSTD(Default::default()))
+            .build(),
+    );
+
+    let mut writer = SerializedFileWriter::new(Vec::new(), schema, properties)?;
+    let mut group = writer.next_row_group()?;
+    let mut index = 0;
+    while let Some(mut column) = group.next_column()? {
+        match index {
+            0 | 1 => {
+                let values: Vec<ByteArray> = rows
+                    .iter()
+                    .map(|row| {
+                        let value = if index == 0 {
+                            &row.step_id
+                        } else {
+                            &row.step_name
+                        };
+                        ByteArray::from(value.as_str())
+                    })
+                    .collect();
+                column
+                    .typed::<ByteArrayType>()
+                    .write_batch(&values, None, None)?;
+            }
+            2 | 3 => {
+                let strings: Vec<Option<&str>> = rows
+                    .iter()
+                    .map(|row| {
+                        if index == 2 {
+                            row.vehicle_id.as_deref()
+                        } else {
+                            row.tenant.as_deref()
+                        }
+                    })
+                    .collect();
+                let (values, levels) = optional(&strings, |value| ByteArray::from(*value));
+                column
+                    .typed::<ByteArrayType>()
+                    .write_batch(&values, Some(&levels), None)?;
+            }
+            4 => {
+                let values: Vec<i64> = rows.iter().map(|row| row.timestamp_ms).collect();
+                column
+                    .typed::<Int64Type>()
+                    .write_batch(&values, None, None)?;
+            }
+            _ => {
+                let signal = index - 5;
+                let doubles: Vec<Option<f64>> = rows.iter().map(|row| row.values[signal]).collect();
+                let (values, levels) = optional(&doubles, |value| *value);
+                column
+                    .typed::<DoubleType>()
+                    .write_batch(&values, Some(&levels), None)?;
+            }
+        }
+        column.close()?;
+        index += 1;
+    }
+    group.close()?;
+    writer.into_inner()
+}
+
+/// Present values of an optional column with the definition level of every row
+fn optional<T, V>(items: &[Option<T>], convert: impl Fn(&T) -> V) -> (Vec<V>, Vec<i16>) {
+    let values = items.iter().flatten().map(convert).collect();
+    let levels = items.iter().map(|item| i16::from(item.is_some())).collect();
+    (values, levels)
+}
@@ -0,0 +1,36 @@
+use crate::common::error::AppError;
+use crate::features::geofence::model::{Geofence, GeofenceEvent, GeofenceRequest};
+use crate::features::geofence::service;
+use crate::features::geofence::tracker::GeofenceTracker;
+
+pub async fn create(
+    tracker: &GeofenceTracker,
+    request: GeofenceRequest,
+) -> Result<Geofence, AppError> {
+    if request.name.trim().is_empty() {
+        return Err(AppError::bad_request("Geofence name must not be empty"));
+    }
+    request.zone.validate().map_err(AppError::bad_request)?;
+
+    let geofence = Geofence {
+        name: request.name,
+        zone: request.zone,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    service::store_geofence(&geofence).await?;
+    tracker.register(geofence.clone());
+
+    Ok(geofence)
+}
+
+pub async fn delete(tracker: &GeofenceTracker, name: &str) -> Result<(), AppError> {
+    if !service::delete_geofence(name).await? {
+        return Err(AppError::not_found(format!("Geofence '{}'", name)));
+    }
+    tracker.remove(name);
+    Ok(())
+}
+
+pub async fn events(name: &str) -> Result<Vec<GeofenceEvent>, AppError> {
+    service::get_geofence_events(name).await
+}
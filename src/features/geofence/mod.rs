@@ -0,0 +1,48 @@
+pub mod controller;
+pub mod model;
+pub mod service;
+pub mod tracker;
+
+use actix_web::web::Data;
+use actix_web::{delete, get, post, web, HttpResponse, Result};
+
+use crate::common::error::AppError;
+
+use model::GeofenceRequest;
+pub use model::{Geofence, GeofenceEvent};
+pub use tracker::GeofenceTracker;
+
+#[get("/geofences")]
+pub async fn list(tracker: Data<GeofenceTracker>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(tracker.geofences()))
+}
+
+#[post("/geofences")]
+pub async fn create(
+    tracker: Data<GeofenceTracker>,
+    request: web::Json<GeofenceRequest>,
+) -> Result<HttpResponse, AppError> {
+    let geofence = controller::create(&tracker, request.into_inner()).await?;
+    Ok(HttpResponse::Created().json(geofence))
+}
+
+#[delete("/geofences/{name}")]
+pub async fn remove(
+    tracker: Data<GeofenceTracker>,
+    name: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    controller::delete(&tracker, &name).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[get("/geofences/{name}/events")]
+pub async fn events(name: web::Path<String>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::events(&name).await?))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list)
+        .service(create)
+        .service(remove)
+        .service(events);
+}
@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+
+/// Area covered by a geofence, in WGS84 degrees
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "shape", rename_all = "snake_case")]
+pub enum Zone {
+    Circle {
+        latitude: f64,
+        longitude: f64,
+        radius_m: f64,
+    },
+    /// Closed polygon given as `[latitude, longitude]` vertices
+    Polygon { points: Vec<[f64; 2]> },
+}
+
+/// Named zone whose boundary crossings generate GeofenceEvents
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Geofence {
+    pub name: String,
+    #[serde(flatten)]
+    pub zone: Zone,
+    pub created_at: String,
+}
+
+/// Body of `POST /geofences`
+#[derive(Debug, Clone, Deserialize)]
+pub struct GeofenceRequest {
+    pub name: String,
+    #[serde(flatten)]
+    pub zone: Zone,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transition {
+    Enter,
+    Leave,
+}
+
+impl Transition {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Transition::Enter => "enter",
+            Transition::Leave => "leave",
+        }
+    }
+}
+
+impl std::str::FromStr for Transition {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "enter" => Ok(Transition::Enter),
+            "leave" => Ok(Transition::Leave),
+            other => Err(format!("Unknown geofence transition '{}'", other)),
+        }
+    }
+}
+
+/// A vehicle crossing a geofence boundary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeofenceEvent {
+    pub id: String,
+    pub geofence: String,
+    pub transition: Transition,
+    pub step_name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub timestamp: String,
+}
+
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+
+/// Great-circle distance between two positions in meters (haversine formula)
+pub fn distance_m(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
+    let a = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_M * a.sqrt().asin()
+}
+
+impl Zone {
+    /// Reject zones that cannot describe an area on the globe
+    pub fn validate(&self) -> Result<(), String> {
+        fn check_position(latitude: f64, longitude: f64) -> Result<(), String> {
+            if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+                return Err(format!("Invalid position {}, {}", latitude, longitude));
+            }
+            Ok(())
+        }
+
+        match self {
+            Zone::Circle {
+                latitude,
+                longitude,
+                radius_m,
+            } => {
+                check_position(*latitude, *longitude)?;
+                if !radius_m.is_finite() || *radius_m <= 0.0 {
+                    return Err(format!("Radius must be positive, got {}", radius_m));
+                }
+            }
+            Zone::Polygon { points } => {
+                if points.len() < 3 {
+                    return Err("A polygon needs at least 3 points".to_string());
+                }
+                for [latitude, longitude] in points {
+                    check_position(*latitude, *longitude)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        match self {
+            Zone::Circle {
+                latitude: center_lat,
+                longitude: center_lon,
+                radius_m,
+            } => distance_m((*center_lat, *center_lon), (latitude, longitude)) <= *radius_m,
+            Zone::Polygon { points } => {
+                // Ray casting on the lat/lon plane, fine for zones that do not cross the antimeridian
+                let mut inside = false;
+                let mut previous = points[points.len() - 1];
+                for &point in points {
+                    let ([lat_i, lon_i], [lat_j, lon_j]) = (point, previous);
+                    if (lat_i > latitude) != (lat_j > latitude)
+                        && longitude
+                            < (lon_j - lon_i) * (latitude - lat_i) / (lat_j - lat_i) + lon_i
+                    {
+                        inside = !inside;
+                    }
+                    previous = point;
+                }
+                inside
+            }
+        }
+    }
+}
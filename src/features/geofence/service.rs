@@ -0,0 +1,105 @@
+use sqlx::any::AnyRow;
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::features::geofence::model::{Geofence, GeofenceEvent};
+
+fn geofence_from_row(row: &AnyRow) -> Result<Geofence, AppError> {
+    let zone_json: String = row.try_get("zone")?;
+
+    Ok(Geofence {
+        name: row.try_get("name")?,
+        zone: serde_json::from_str(&zone_json)?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+fn geofence_event_from_row(row: &AnyRow) -> Result<GeofenceEvent, AppError> {
+    let transition: String = row.try_get("transition")?;
+
+    Ok(GeofenceEvent {
+        id: row.try_get("id")?,
+        geofence: row.try_get("geofence")?,
+        transition: transition
+            .parse()
+            .map_err(AppError::internal_server_error)?,
+        step_name: row.try_get("step_name")?,
+        latitude: row.try_get("latitude")?,
+        longitude: row.try_get("longitude")?,
+        timestamp: row.try_get("timestamp")?,
+    })
+}
+
+/// Insert a geofence, replacing any previous geofence with the same name
+pub async fn store_geofence(geofence: &Geofence) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(
+        "INSERT INTO geofences (name, zone, created_at) VALUES ($1, $2, $3)
+         ON CONFLICT (name) DO UPDATE SET zone = excluded.zone, created_at = excluded.created_at",
+    )
+    .bind(&geofence.name)
+    .bind(serde_json::to_string(&geofence.zone)?)
+    .bind(&geofence.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_geofences() -> Result<Vec<Geofence>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query("SELECT name, zone, created_at FROM geofences ORDER BY created_at ASC")
+        .fetch_all(pool)
+        .await?;
+
+    rows.iter().map(geofence_from_row).collect()
+}
+
+/// Delete a geofence, returning whether it existed
+pub async fn delete_geofence(name: &str) -> Result<bool, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let result = sqlx::query("DELETE FROM geofences WHERE name = $1")
+        .bind(name)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn store_geofence_event(event: &GeofenceEvent) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(
+        "INSERT INTO geofence_events (id, geofence, transition, step_name, latitude, longitude, timestamp)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)",
+    )
+    .bind(&event.id)
+    .bind(&event.geofence)
+    .bind(event.transition.as_str())
+    .bind(&event.step_name)
+    .bind(event.latitude)
+    .bind(event.longitude)
+    .bind(&event.timestamp)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Boundary crossings of one geofence, oldest first
+pub async fn get_geofence_events(geofence: &str) -> Result<Vec<GeofenceEvent>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(
+        "SELECT id, geofence, transition, step_name, latitude, longitude, timestamp
+         FROM geofence_events WHERE geofence = $1 ORDER BY timestamp ASC",
+    )
+    .bind(geofence)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(geofence_event_from_row).collect()
+}
@@ -0,0 +1,128 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::broadcast;
+
+use crate::common::error::AppError;
+use crate::core::bus::{Bus, BusMessage};
+use crate::features::driving_step::DrivingStep;
+use crate::features::geofence::model::{Geofence, GeofenceEvent, Transition};
+use crate::features::geofence::service;
+
+struct TrackedZone {
+    geofence: Geofence,
+    /// Whether the last GPS fix was inside the zone
+    inside: bool,
+}
+
+/// Registered geofences and the vehicle's position relative to each of them
+#[derive(Clone, Default)]
+pub struct GeofenceTracker {
+    zones: Arc<Mutex<Vec<TrackedZone>>>,
+}
+
+impl GeofenceTracker {
+    /// Track every geofence stored in the database
+    pub async fn load() -> Result<Self, AppError> {
+        let tracker = GeofenceTracker::default();
+        tracker.reload().await?;
+        Ok(tracker)
+    }
+
+    /// Replace the tracked geofences with those stored in the database
+    pub async fn reload(&self) -> Result<(), AppError> {
+        let stored = service::get_geofences().await?;
+        self.zones.lock().unwrap().clear();
+        for geofence in stored {
+            self.register(geofence);
+        }
+        Ok(())
+    }
+
+    /// Add a geofence, replacing any geofence with the same name
+    pub fn register(&self, geofence: Geofence) {
+        let mut zones = self.zones.lock().unwrap();
+        zones.retain(|tracked| tracked.geofence.name != geofence.name);
+        zones.push(TrackedZone {
+            geofence,
+            inside: false,
+        });
+    }
+
+    pub fn remove(&self, name: &str) {
+        self.zones
+            .lock()
+            .unwrap()
+            .retain(|tracked| tracked.geofence.name != name);
+    }
+
+    pub fn geofences(&self) -> Vec<Geofence> {
+        self.zones
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|tracked| tracked.geofence.clone())
+            .collect()
+    }
+
+    /// Update zone membership from the step's GPS fix, returning the boundary crossings
+    ///
+    /// Until a first fix is seen the vehicle counts as outside every zone, so a fix
+    /// inside a zone produces an `enter` event. Steps without GPS data change nothing.
+    pub fn update(&self, step: &DrivingStep) -> Vec<GeofenceEvent> {
+        let Some(gps) = &step.gps else {
+            return Vec::new();
+        };
+
+        let mut events = Vec::new();
+        for tracked in self.zones.lock().unwrap().iter_mut() {
+            let inside = tracked.geofence.zone.contains(gps.latitude, gps.longitude);
+            if inside != tracked.inside {
+                events.push(GeofenceEvent {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    geofence: tracked.geofence.name.clone(),
+                    transition: if inside {
+                        Transition::Enter
+                    } else {
+                        Transition::Leave
+                    },
+                    step_name: step.step_name.clone(),
+                    latitude: gps.latitude,
+                    longitude: gps.longitude,
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                });
+                tracked.inside = inside;
+            }
+        }
+        events
+    }
+
+    /// Track every DrivingStep on the bus, storing and publishing the boundary crossings
+    pub fn spawn(&self, bus: &Bus) {
+        let tracker = self.clone();
+        let bus = bus.clone();
+        let mut rx = bus.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                let step = match rx.recv().await {
+                    Ok(BusMessage::DrivingStep(step)) => step,
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(_) => break,
+                };
+
+                for event in tracker.update(&step) {
+                    println!(
+                        "📍 Geofence '{}': {} on step '{}'",
+                        event.geofence,
+                        event.transition.as_str(),
+                        step.step_name
+                    );
+                    if let Err(e) = service::store_geofence_event(&event).await {
+                        println!("❌ Failed to store geofence event: {}", e);
+                    }
+                    let _ = bus.send(BusMessage::Geofence(event));
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::config::rabbitmq;
+use crate::config::transport::{StepTransport, TransportKind};
+use crate::features::health::model::{CheckStatus, DependencyCheck, Readiness, ReadinessStatus};
+use crate::features::health::service;
+
+/// Longest a dependency may take to answer before it is reported down
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+async fn probe<E: std::fmt::Display>(
+    check: impl Future<Output = Result<(), E>>,
+) -> DependencyCheck {
+    let started = Instant::now();
+    let error = match tokio::time::timeout(PROBE_TIMEOUT, check).await {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("No answer within {} ms", PROBE_TIMEOUT.as_millis())),
+    };
+    DependencyCheck {
+        status: if error.is_none() {
+            CheckStatus::Up
+        } else {
+            CheckStatus::Down
+        },
+        latency_ms: started.elapsed().as_millis() as u64,
+        error,
+    }
+}
+
+/// Probe the database and, for the amqp transport, the broker channel the step notices
+/// are consumed on, both at once
+pub async fn readiness(transport: &StepTransport) -> Readiness {
+    let kind = transport.kind();
+    let sqlite = probe(service::ping());
+    let rabbitmq = async {
+        match kind {
+            TransportKind::Amqp => Some(match transport.amqp_channel() {
+                Some(channel) => probe(rabbitmq::ping(&channel)).await,
+                None => DependencyCheck {
+                    status: CheckStatus::Down,
+                    latency_ms: 0,
+                    error: Some("Channel closed, reconnecting".to_string()),
+                },
+            }),
+            TransportKind::Memory => None,
+        }
+    };
+    let (sqlite, rabbitmq) = tokio::join!(sqlite, rabbitmq);
+
+    let mut checks = BTreeMap::from([("sqlite", sqlite)]);
+    if let Some(rabbitmq) = rabbitmq {
+        checks.insert("rabbitmq", rabbitmq);
+    }
+    let status = if checks.values().all(DependencyCheck::is_up) {
+        ReadinessStatus::Ready
+    } else {
+        ReadinessStatus::NotReady
+    };
+    Readiness {
+        status,
+        transport: kind.as_str(),
+        checked_at: chrono::Utc::now().to_rfc3339(),
+        checks,
+    }
+}
@@ -0,0 +1,37 @@
+pub mod controller;
+pub mod model;
+pub mod service;
+
+use actix_web::web::Data;
+use actix_web::{get, web, HttpResponse, Result};
+use serde_json::json;
+
+use crate::common::error::AppError;
+use crate::config::transport::StepTransport;
+
+pub use model::Readiness;
+use model::ReadinessStatus;
+
+/// Paths probes call without an API key
+pub const PROBE_PATHS: [&str; 2] = ["/health/live", "/health/ready"];
+
+/// The process is up and answering; dependencies are left to `/health/ready`
+#[get("/health/live")]
+pub async fn live() -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(json!({ "status": "live" })))
+}
+
+/// Whether SQLite and the RabbitMQ channel answer, `503` when one of them does not
+#[get("/health/ready")]
+pub async fn ready(transport: Data<StepTransport>) -> Result<HttpResponse, AppError> {
+    let readiness = controller::readiness(&transport).await;
+    let mut response = match readiness.status {
+        ReadinessStatus::Ready => HttpResponse::Ok(),
+        ReadinessStatus::NotReady => HttpResponse::ServiceUnavailable(),
+    };
+    Ok(response.json(readiness))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(live).service(ready);
+}
@@ -0,0 +1,44 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Up,
+    Down,
+}
+
+/// Outcome of probing one dependency
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyCheck {
+    pub status: CheckStatus,
+    /// Time the probe took, or waited before giving up
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl DependencyCheck {
+    pub fn is_up(&self) -> bool {
+        self.status == CheckStatus::Up
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessStatus {
+    Ready,
+    NotReady,
+}
+
+/// Response of `GET /health/ready`
+#[derive(Debug, Clone, Serialize)]
+pub struct Readiness {
+    pub status: ReadinessStatus,
+    /// `amqp` or `memory`; the in-memory queue has no broker to check
+    pub transport: &'static str,
+    pub checked_at: String,
+    /// Checks by dependency: `sqlite`, and `rabbitmq` for the amqp transport
+    pub checks: BTreeMap<&'static str, DependencyCheck>,
+}
@@ -0,0 +1,8 @@
+use crate::common::error::AppError;
+
+/// Run `SELECT 1` on a pooled connection
+pub async fn ping() -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+    sqlx::query("SELECT 1").execute(pool).await?;
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::common::error::AppError;
+use crate::core::bus::Bus;
+use crate::features::driving_step::service as step_service;
+use crate::features::history::model::CompactionReport;
+use crate::features::history::recorder::SignalRecorder;
+use crate::features::history::service;
+use crate::features::system::{self, SystemEventKind};
+
+/// Most steps compacted by one run, the rest waiting for the next one
+const BATCH_STEPS: usize = 500;
+
+/// Replaces the raw frames of steps older than the retention window with per-minute
+/// aggregates of their decoded signals
+///
+/// `GET /signals/{name}/history` keeps covering compacted ranges at minute resolution,
+/// while the frames, and the steps served by `/driving-steps`, are gone.
+#[derive(Clone)]
+pub struct Compactor {
+    /// Age after which raw frames are compacted, compaction being off when `None`
+    retention: Option<Duration>,
+    /// Bus receiving a `retention_pruned` system event for every run compacting steps
+    bus: Bus,
+    last_report: Arc<Mutex<Option<CompactionReport>>>,
+}
+
+impl Compactor {
+    pub fn new(retention: Option<Duration>, bus: Bus) -> Self {
+        Compactor {
+            retention,
+            bus,
+            last_report: Arc::default(),
+        }
+    }
+
+    pub fn retention(&self) -> Option<Duration> {
+        self.retention
+    }
+
+    /// Report of the latest run, `None` before the first one
+    pub fn last_report(&self) -> Option<CompactionReport> {
+        self.last_report.lock().unwrap().clone()
+    }
+
+    /// Retention window, `400` when compaction is disabled
+    pub fn require_retention(&self) -> Result<Duration, AppError> {
+        self.retention
+            .ok_or_else(|| AppError::bad_request("Compaction is disabled, set RAW_FRAME_RETENTION"))
+    }
+
+    /// Compact up to `BATCH_STEPS` steps stored before the retention window
+    pub async fn run(&self) -> Result<CompactionReport, AppError> {
+        let retention = self.require_retention()?;
+        let started_at = chrono::Utc::now();
+        let cutoff = started_at
+            - chrono::Duration::from_std(retention)
+                .map_err(|e| AppError::internal_server_error(e.to_string()))?;
+
+        let (step_ids, remaining) = service::get_steps_before(cutoff, BATCH_STEPS).await?;
+        let mut report = CompactionReport {
+            started_at: started_at.to_rfc3339(),
+            cutoff: cutoff.to_rfc3339(),
+            remaining,
+            ..Default::default()
+        };
+        for step_id in step_ids {
+            let step = match step_service::reconstruct_step(&step_id, step_id.clone(), None).await {
+                Ok(Some(step)) => step,
+                Ok(None) => continue,
+                Err(e) => {
+                    println!("⚠️ Step '{}' not compacted: {}", step_id, e);
+                    report.skipped += 1;
+                    continue;
+                }
+            };
+            let values = SignalRecorder::values(&step);
+            report.frames += service::compact_step(&step_id, &values).await?;
+            report.values += values.len() as u64;
+            report.steps += 1;
+        }
+
+        *self.last_report.lock().unwrap() = Some(report.clone());
+        if report.steps > 0 {
+            system::controller::publish(
+                &self.bus,
+                SystemEventKind::RetentionPruned,
+                format!(
+                    "Retention pruned {} step(s) stored before {}",
+                    report.steps, report.cutoff
+                ),
+                serde_json::to_value(&report)?,
+            )
+            .await;
+        }
+        Ok(report)
+    }
+
+    /// Run every `interval`, until the process exits; does nothing while compaction is off
+    pub fn spawn(&self, interval: Duration) {
+        if self.retention.is_none() {
+            return;
+        }
+        let compactor = self.clone();
+        let mut tick = tokio::time::interval(interval);
+        tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        tokio::spawn(async move {
+            loop {
+                tick.tick().await;
+                match compactor.run().await {
+                    Ok(report) if report.steps > 0 => println!(
+                        "🗜️ Compacted {} step(s), {} frame(s) into per-minute aggregates",
+                        report.steps, report.frames
+                    ),
+                    Ok(_) => {}
+                    Err(e) => println!("❌ Compaction failed: {}", e),
+                }
+            }
+        });
+    }
+}
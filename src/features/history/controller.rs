@@ -0,0 +1,158 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+use crate::common::error::AppError;
+use crate::core::clock::{SimulatedClock, TimeSource};
+use crate::core::signals;
+use crate::features::history::downsample;
+use crate::features::history::model::{
+    ChartPoint, ChartQuery, HistoryPoint, HistoryQuery, SignalChart, SignalHistory,
+    DEFAULT_CHART_POINTS, DEFAULT_POINTS, MAX_POINTS,
+};
+use crate::features::history::service;
+use crate::features::scenario::service as scenario_service;
+
+fn rfc3339(ms: i64) -> String {
+    Utc.timestamp_millis_opt(ms)
+        .single()
+        .map(|timestamp| timestamp.to_rfc3339())
+        .unwrap_or_default()
+}
+
+/// Start of the simulated time of `run`, or the default one of simulated runs
+async fn simulated_epoch(run: Option<&str>) -> Result<DateTime<Utc>, AppError> {
+    let Some(run_id) = run else {
+        return Ok(SimulatedClock::default().epoch());
+    };
+    let run = scenario_service::get_run(run_id)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Scenario run '{}'", run_id)))?;
+    let epoch = run.epoch.ok_or_else(|| {
+        AppError::bad_request(format!("Scenario run '{}' ran on the wall clock", run_id))
+    })?;
+    DateTime::parse_from_rfc3339(&epoch)
+        .map(|epoch| epoch.with_timezone(&Utc))
+        .map_err(|e| AppError::internal_server_error(e.to_string()))
+}
+
+/// Range of a query, the last hour by default or the first hour of simulated time
+///
+/// Values of simulated scenario runs are kept apart from wall-clock ones.
+async fn range(
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    simulated: bool,
+    run: Option<&str>,
+) -> Result<(DateTime<Utc>, DateTime<Utc>), AppError> {
+    let (from, to) = if simulated {
+        let from = match from {
+            Some(from) => from,
+            None => simulated_epoch(run).await?,
+        };
+        (from, to.unwrap_or(from + Duration::hours(1)))
+    } else {
+        let to: DateTime<Utc> = to.unwrap_or_else(Utc::now);
+        (from.unwrap_or(to - Duration::hours(1)), to)
+    };
+    if from.timestamp_millis() >= to.timestamp_millis() {
+        return Err(AppError::bad_request("'from' must be before 'to'"));
+    }
+    Ok((from, to))
+}
+
+/// Values of `name` between `from` and `to`, aggregated into buckets of `resolution`
+pub async fn history(name: &str, query: &HistoryQuery) -> Result<SignalHistory, AppError> {
+    let signal =
+        signals::find(name).ok_or_else(|| AppError::not_found(format!("Signal '{}'", name)))?;
+
+    let simulated = query.clock == TimeSource::Simulated || query.run.is_some();
+    let (from, to) = range(query.from, query.to, simulated, query.run.as_deref()).await?;
+    let (from_ms, to_ms) = (from.timestamp_millis(), to.timestamp_millis());
+
+    let span_ms = to_ms - from_ms;
+    let resolution_ms = match query.resolution {
+        Some(resolution) => resolution.0,
+        None => (span_ms + DEFAULT_POINTS - 1) / DEFAULT_POINTS,
+    };
+    if span_ms / resolution_ms > MAX_POINTS {
+        return Err(AppError::bad_request(format!(
+            "Resolution of {}ms splits the range into more than {} buckets",
+            resolution_ms, MAX_POINTS
+        )));
+    }
+
+    let unit = signal.unit.in_system(query.units);
+    let convert = |value: f64| signal.unit.convert(value, unit).unwrap_or(value);
+    let points = service::get_buckets(
+        signal.name,
+        from_ms,
+        to_ms,
+        resolution_ms,
+        simulated,
+        query.run.as_deref(),
+    )
+    .await?
+    .into_iter()
+    .map(|(bucket, count, min, max, avg)| {
+        // Conversions such as L/100km → mpg are decreasing, which swaps the bounds
+        let (min, max) = (convert(min), convert(max));
+        HistoryPoint {
+            timestamp: rfc3339(bucket),
+            count,
+            min: min.min(max),
+            max: min.max(max),
+            avg: convert(avg),
+        }
+    })
+    .collect();
+
+    Ok(SignalHistory {
+        signal: signal.name,
+        unit,
+        from: from.to_rfc3339(),
+        to: to.to_rfc3339(),
+        resolution_ms,
+        points,
+    })
+}
+
+/// `points` samples of `name` between `from` and `to`, picked by LTTB downsampling
+pub async fn chart(name: &str, query: &ChartQuery) -> Result<SignalChart, AppError> {
+    let signal =
+        signals::find(name).ok_or_else(|| AppError::not_found(format!("Signal '{}'", name)))?;
+    let points = query.points.unwrap_or(DEFAULT_CHART_POINTS);
+    if !(3..=MAX_POINTS as usize).contains(&points) {
+        return Err(AppError::bad_request(format!(
+            "'points' must be between 3 and {}",
+            MAX_POINTS
+        )));
+    }
+
+    let simulated = query.clock == TimeSource::Simulated || query.run.is_some();
+    let (from, to) = range(query.from, query.to, simulated, query.run.as_deref()).await?;
+    let samples = service::get_samples(
+        signal.name,
+        from.timestamp_millis(),
+        to.timestamp_millis(),
+        simulated,
+        query.run.as_deref(),
+    )
+    .await?;
+
+    // Samples are picked in registry units, then converted
+    let unit = signal.unit.in_system(query.units);
+    let convert = |value: f64| signal.unit.convert(value, unit).unwrap_or(value);
+    Ok(SignalChart {
+        signal: signal.name,
+        unit,
+        from: from.to_rfc3339(),
+        to: to.to_rfc3339(),
+        samples: samples.len(),
+        points: downsample::lttb(&samples, points)
+            .into_iter()
+            .map(|(timestamp_ms, value)| ChartPoint {
+                timestamp: rfc3339(timestamp_ms),
+                value: convert(value),
+            })
+            .collect(),
+    })
+}
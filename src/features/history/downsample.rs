@@ -0,0 +1,49 @@
+/// Keep `threshold` of the `(timestamp_ms, value)` samples with largest-triangle-three-buckets
+///
+/// The first and last samples are always kept. The samples in between are split into
+/// `threshold - 2` buckets of equal count, and each bucket keeps the sample forming the
+/// largest triangle with the one kept before it and the average of the next bucket, which
+/// preserves the peaks and troughs a plot needs. Series of at most `threshold` samples, or
+/// thresholds under 3, are returned as they are.
+pub fn lttb(samples: &[(i64, f64)], threshold: usize) -> Vec<(i64, f64)> {
+    if threshold < 3 || samples.len() <= threshold {
+        return samples.to_vec();
+    }
+
+    // Timestamps relative to the first sample keep the areas exact in f64
+    let origin = samples[0].0;
+    let x = |index: usize| (samples[index].0 - origin) as f64;
+    let y = |index: usize| samples[index].1;
+
+    let every = (samples.len() - 2) as f64 / (threshold - 2) as f64;
+    let mut kept = Vec::with_capacity(threshold);
+    kept.push(samples[0]);
+    let mut previous = 0;
+    for bucket in 0..threshold - 2 {
+        // Average of the next bucket, the last sample for the last bucket
+        let next_start = ((bucket + 1) as f64 * every) as usize + 1;
+        let next_end = (((bucket + 2) as f64 * every) as usize + 1).min(samples.len());
+        let count = (next_end - next_start) as f64;
+        let (next_x, next_y) = (next_start..next_end).fold((0.0, 0.0), |(sx, sy), index| {
+            (sx + x(index) / count, sy + y(index) / count)
+        });
+
+        let start = (bucket as f64 * every) as usize + 1;
+        let end = next_start;
+        let (prev_x, prev_y) = (x(previous), y(previous));
+        let selected = (start..end)
+            .max_by(|a, b| {
+                let area = |index: usize| {
+                    ((prev_x - next_x) * (y(index) - prev_y)
+                        - (prev_x - x(index)) * (next_y - prev_y))
+                        .abs()
+                };
+                area(*a).total_cmp(&area(*b))
+            })
+            .unwrap_or(start);
+        kept.push(samples[selected]);
+        previous = selected;
+    }
+    kept.push(samples[samples.len() - 1]);
+    kept
+}
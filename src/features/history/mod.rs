@@ -0,0 +1,69 @@
+pub mod compactor;
+pub mod controller;
+pub mod downsample;
+pub mod model;
+pub mod recorder;
+pub mod service;
+
+use actix_web::web::Data;
+use actix_web::{get, post, web, HttpResponse, Result};
+use serde_json::json;
+
+use crate::common::error::AppError;
+use crate::features::job::{JobKind, JobQueue};
+
+pub use compactor::Compactor;
+use model::{ChartQuery, HistoryQuery};
+pub use recorder::SignalRecorder;
+
+/// Downsampled time series of one signal, read from `signal_values`
+#[get("/signals/{name}/history")]
+pub async fn history(
+    name: web::Path<String>,
+    query: web::Query<HistoryQuery>,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::history(&name, &query).await?))
+}
+
+/// Samples of one signal downsampled to `?points=` with LTTB, for plotting long ranges
+#[get("/signals/{name}/chart")]
+pub async fn chart(
+    name: web::Path<String>,
+    query: web::Query<ChartQuery>,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::chart(&name, &query).await?))
+}
+
+/// Retention window of raw frames and the report of the latest compaction
+#[get("/admin/compaction")]
+pub async fn compaction(compactor: Data<Compactor>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(json!({
+        "retention_s": compactor.retention().map(|retention| retention.as_secs_f64()),
+        "last_report": compactor.last_report(),
+    })))
+}
+
+/// Compact the steps older than the retention window now, without waiting for the timer
+///
+/// Runs as a job: answers `202` with the job, whose result is the compaction report.
+#[post("/admin/compaction")]
+pub async fn compact(
+    compactor: Data<Compactor>,
+    jobs: Data<JobQueue>,
+) -> Result<HttpResponse, AppError> {
+    compactor.require_retention()?;
+    let compactor = compactor.get_ref().clone();
+    let job = jobs
+        .submit(JobKind::Compaction, None, move |_| async move {
+            Ok(serde_json::to_value(compactor.run().await?)?)
+        })
+        .await?;
+    Ok(HttpResponse::Accepted().json(job))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(history)
+        .service(chart)
+        .service(compaction)
+        .service(compact);
+}
@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::clock::TimeSource;
+use crate::core::units::{Unit, UnitSystem};
+
+/// Buckets returned when `?resolution=` is omitted
+pub const DEFAULT_POINTS: i64 = 300;
+/// Largest number of buckets one request may ask for
+pub const MAX_POINTS: i64 = 10_000;
+/// Points of a chart when `?points=` is omitted
+pub const DEFAULT_CHART_POINTS: usize = 500;
+
+/// One decoded value of a signal, as written to `signal_values`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignalValue {
+    pub signal: &'static str,
+    /// Flags are stored as 0 and 1
+    pub value: f64,
+    pub step_id: Option<String>,
+    /// Time of the frame the value was decoded from, in milliseconds since the Unix epoch
+    pub timestamp_ms: i64,
+}
+
+/// Bucket width of a history, written `500ms`, `10s`, `5m`, `1h` or as plain seconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resolution(pub i64);
+
+impl std::str::FromStr for Resolution {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let split = value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len());
+        let (amount, unit) = value.split_at(split);
+        let amount: i64 = amount
+            .parse()
+            .map_err(|_| format!("Invalid resolution '{}'", value))?;
+        let factor = match unit {
+            "ms" => 1,
+            "" | "s" => 1_000,
+            "m" => 60_000,
+            "h" => 3_600_000,
+            "d" => 86_400_000,
+            other => return Err(format!("Unknown resolution unit '{}'", other)),
+        };
+        match amount.checked_mul(factor) {
+            Some(ms) if ms > 0 => Ok(Resolution(ms)),
+            _ => Err(format!("Invalid resolution '{}'", value)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Resolution {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Query parameters accepted by `GET /signals/{name}/history`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct HistoryQuery {
+    /// RFC3339 start of the range, one hour before `to` when omitted, or the start of the
+    /// simulated time on the simulated clock
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    /// RFC3339 end of the range, now when omitted, or one hour after `from` on the
+    /// simulated clock
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+    /// Bucket width, the range split into `DEFAULT_POINTS` buckets when omitted
+    pub resolution: Option<Resolution>,
+    #[serde(default)]
+    pub units: UnitSystem,
+    /// Values of wall-clock steps (default), or of the steps of simulated scenario runs
+    #[serde(default)]
+    pub clock: TimeSource,
+    /// Only the values of this simulated scenario run, implying `clock=simulated`
+    pub run: Option<String>,
+}
+
+/// Query parameters accepted by `GET /signals/{name}/chart`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ChartQuery {
+    /// Points to plot, `DEFAULT_CHART_POINTS` when omitted
+    pub points: Option<usize>,
+    /// RFC3339 range, defaulting as for `HistoryQuery`
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub units: UnitSystem,
+    #[serde(default)]
+    pub clock: TimeSource,
+    pub run: Option<String>,
+}
+
+/// One sample kept by the downsampling of a chart
+#[derive(Debug, Clone, Serialize)]
+pub struct ChartPoint {
+    /// RFC3339 time of the sample
+    pub timestamp: String,
+    pub value: f64,
+}
+
+/// Response of `GET /signals/{name}/chart`
+#[derive(Debug, Clone, Serialize)]
+pub struct SignalChart {
+    pub signal: &'static str,
+    pub unit: Unit,
+    pub from: String,
+    pub to: String,
+    /// Samples stored in the range, before downsampling
+    pub samples: usize,
+    /// `points` samples of the range, or all of them when there are fewer, oldest first
+    pub points: Vec<ChartPoint>,
+}
+
+/// Aggregate of the values falling into one bucket
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryPoint {
+    /// RFC3339 start of the bucket
+    pub timestamp: String,
+    pub count: i64,
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+}
+
+/// Response of `GET /signals/{name}/history`
+#[derive(Debug, Clone, Serialize)]
+pub struct SignalHistory {
+    pub signal: &'static str,
+    pub unit: Unit,
+    pub from: String,
+    pub to: String,
+    pub resolution_ms: i64,
+    /// Buckets holding at least one value, oldest first
+    pub points: Vec<HistoryPoint>,
+}
+
+/// Outcome of one compaction run, returned by `POST /admin/compaction`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CompactionReport {
+    /// RFC3339 time the run started
+    pub started_at: String,
+    /// Steps whose frames all date from before this RFC3339 time were compacted
+    pub cutoff: String,
+    /// Steps replaced by aggregates
+    pub steps: u64,
+    /// Raw frames deleted
+    pub frames: u64,
+    /// Signal values folded into per-minute aggregates
+    pub values: u64,
+    /// Steps left stored because their frames no longer decode
+    pub skipped: u64,
+    /// Whether older steps are left for the next run
+    pub remaining: bool,
+}
@@ -0,0 +1,64 @@
+use tokio::sync::broadcast;
+
+use crate::core::bus::{Bus, BusMessage};
+use crate::core::signals::SIGNALS;
+use crate::features::driving_step::DrivingStep;
+use crate::features::history::model::SignalValue;
+use crate::features::history::service;
+
+/// Writes the decoded value of every scalar signal of each broadcast step to `signal_values`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SignalRecorder;
+
+impl SignalRecorder {
+    /// Scalar signals of `step`, stamped with the time of the frame carrying each of them
+    ///
+    /// Values are in registry units; array signals (`wheel_speeds`) are not recorded.
+    pub fn values(step: &DrivingStep) -> Vec<SignalValue> {
+        let Ok(value) = serde_json::to_value(step) else {
+            return Vec::new();
+        };
+        let now_ms = chrono::Utc::now().timestamp_millis();
+
+        SIGNALS
+            .iter()
+            .filter_map(|signal| {
+                let decoded = match value.pointer(&signal.pointer())? {
+                    serde_json::Value::Bool(flag) => *flag as u8 as f64,
+                    other => other.as_f64()?,
+                };
+                let timestamp_ms = step
+                    .frames
+                    .iter()
+                    .find(|frame| frame.id == signal.can_id())
+                    .and_then(|frame| chrono::DateTime::parse_from_rfc3339(&frame.timestamp).ok())
+                    .map_or(now_ms, |timestamp| timestamp.timestamp_millis());
+                Some(SignalValue {
+                    signal: signal.name,
+                    value: decoded,
+                    step_id: step.step_id.clone(),
+                    timestamp_ms,
+                })
+            })
+            .collect()
+    }
+
+    /// Record every DrivingStep on the bus
+    pub fn spawn(&self, bus: &Bus) {
+        let mut rx = bus.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                let step = match rx.recv().await {
+                    Ok(BusMessage::DrivingStep(step)) => step,
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(_) => break,
+                };
+
+                if let Err(e) = service::store_values(&Self::values(&step)).await {
+                    println!("❌ Failed to record signal values: {}", e);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::config::db;
+use crate::features::history::model::SignalValue;
+
+/// Width of the buckets compacted steps are aggregated into
+pub const AGGREGATE_MS: i64 = 60_000;
+
+/// Store the values decoded from one step in a single transaction
+pub async fn store_values(values: &[SignalValue]) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let mut transaction = pool.begin().await?;
+    for value in values {
+        sqlx::query(
+            "INSERT INTO signal_values (signal, value, step_id, timestamp_ms) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(value.signal)
+        .bind(value.value)
+        .bind(&value.step_id)
+        .bind(value.timestamp_ms)
+        .execute(&mut *transaction)
+        .await?;
+    }
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+/// Bucket start, count, min, max and average of the values of `signal` in `[from_ms, to_ms)`
+///
+/// Buckets are aligned on multiples of the resolution since the Unix epoch, so successive
+/// polls of a dashboard return the same boundaries. Compacted steps contribute their
+/// per-minute aggregates, counted in the bucket holding the start of their minute.
+///
+/// Values come from wall-clock steps, or with `simulated` from the steps of simulated
+/// scenario runs (only those of `run` when given), which are never compacted.
+pub async fn get_buckets(
+    signal: &str,
+    from_ms: i64,
+    to_ms: i64,
+    resolution_ms: i64,
+    simulated: bool,
+    run: Option<&str>,
+) -> Result<Vec<(i64, i64, f64, f64, f64)>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let query = sqlx::query(
+        "SELECT (timestamp_ms / $1) * $1 AS bucket,
+                CAST(SUM(count) AS BIGINT) AS count, MIN(min) AS min, MAX(max) AS max,
+                SUM(sum) / SUM(count) AS avg
+         FROM (
+             SELECT timestamp_ms, 1 AS count, value AS min, value AS max, value AS sum
+             FROM signal_values
+             WHERE signal = $2 AND timestamp_ms >= $3 AND timestamp_ms < $4
+               AND EXISTS (SELECT 1 FROM simulated_steps
+                           WHERE step_id = signal_values.step_id
+                             AND ($6 IS NULL OR run_id = $6)) = $5
+             UNION ALL
+             SELECT minute_ms, count, min, max, sum
+             FROM signal_aggregates
+             WHERE signal = $2 AND minute_ms >= $3 AND minute_ms < $4 AND NOT $5
+         ) AS samples
+         GROUP BY bucket ORDER BY bucket ASC",
+    )
+    .bind(resolution_ms)
+    .bind(signal)
+    .bind(from_ms)
+    .bind(to_ms)
+    .bind(simulated)
+    .bind(run)
+    .fetch_all(pool);
+    let rows = db::labelled("signal_history", query).await?;
+
+    rows.iter()
+        .map(|row| {
+            Ok((
+                row.try_get("bucket")?,
+                row.try_get("count")?,
+                row.try_get("min")?,
+                row.try_get("max")?,
+                row.try_get("avg")?,
+            ))
+        })
+        .collect()
+}
+
+/// Timestamp and value of every sample of `signal` in `[from_ms, to_ms)`, oldest first
+///
+/// Compacted steps contribute one sample per minute, the average of their aggregate
+/// stamped with the start of the minute. Simulated values are selected as by `get_buckets`.
+pub async fn get_samples(
+    signal: &str,
+    from_ms: i64,
+    to_ms: i64,
+    simulated: bool,
+    run: Option<&str>,
+) -> Result<Vec<(i64, f64)>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let query = sqlx::query(
+        "SELECT timestamp_ms, value FROM signal_values
+         WHERE signal = $1 AND timestamp_ms >= $2 AND timestamp_ms < $3
+           AND EXISTS (SELECT 1 FROM simulated_steps
+                       WHERE step_id = signal_values.step_id
+                         AND ($5 IS NULL OR run_id = $5)) = $4
+         UNION ALL
+         SELECT minute_ms, sum / count FROM signal_aggregates
+         WHERE signal = $1 AND minute_ms >= $2 AND minute_ms < $3 AND NOT $4
+         ORDER BY timestamp_ms ASC",
+    )
+    .bind(signal)
+    .bind(from_ms)
+    .bind(to_ms)
+    .bind(simulated)
+    .bind(run)
+    .fetch_all(pool);
+    let rows = db::labelled("signal_chart", query).await?;
+
+    rows.iter()
+        .map(|row| Ok((row.try_get("timestamp_ms")?, row.try_get("value")?)))
+        .collect()
+}
+
+/// Stored steps whose frames all date from before `cutoff`, oldest first, at most `limit`
+///
+/// Frames of the legacy rows without a step id, trashed steps and steps of simulated
+/// scenario runs, stamped with simulated time, are never compacted.
+pub async fn get_steps_before(
+    cutoff: chrono::DateTime<chrono::Utc>,
+    limit: usize,
+) -> Result<(Vec<String>, bool), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    // One step more than asked tells whether others remain
+    let mut step_ids: Vec<String> = sqlx::query_scalar(
+        "SELECT step_id FROM can_messages
+         WHERE step_id IS NOT NULL AND deleted_at IS NULL
+           AND step_id NOT IN (SELECT step_id FROM simulated_steps)
+         GROUP BY step_id HAVING MAX(timestamp_ms) < $1
+         ORDER BY MIN(seq) ASC LIMIT $2",
+    )
+    .bind(cutoff.timestamp_millis())
+    .bind(limit as i64 + 1)
+    .fetch_all(pool)
+    .await?;
+
+    let remaining = step_ids.len() > limit;
+    step_ids.truncate(limit);
+    Ok((step_ids, remaining))
+}
+
+/// Replace the raw frames and signal values of a step with per-minute aggregates of `values`
+///
+/// Returns the number of frames deleted. Aggregates of the same signal and minute are
+/// merged with those of earlier compactions.
+pub async fn compact_step(step_id: &str, values: &[SignalValue]) -> Result<u64, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    // Count, min, max and sum of each signal per minute
+    let mut buckets: HashMap<(&str, i64), (i64, f64, f64, f64)> = HashMap::new();
+    for value in values {
+        let minute_ms = value.timestamp_ms.div_euclid(AGGREGATE_MS) * AGGREGATE_MS;
+        let bucket =
+            buckets
+                .entry((value.signal, minute_ms))
+                .or_insert((0, value.value, value.value, 0.0));
+        bucket.0 += 1;
+        bucket.1 = bucket.1.min(value.value);
+        bucket.2 = bucket.2.max(value.value);
+        bucket.3 += value.value;
+    }
+
+    let mut transaction = pool.begin().await?;
+    for ((signal, minute_ms), (count, min, max, sum)) in buckets {
+        sqlx::query(
+            "INSERT INTO signal_aggregates (signal, minute_ms, count, min, max, sum)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (signal, minute_ms) DO UPDATE SET
+                 count = signal_aggregates.count + excluded.count,
+                 min = CASE WHEN excluded.min < signal_aggregates.min
+                     THEN excluded.min ELSE signal_aggregates.min END,
+                 max = CASE WHEN excluded.max > signal_aggregates.max
+                     THEN excluded.max ELSE signal_aggregates.max END,
+                 sum = signal_aggregates.sum + excluded.sum",
+        )
+        .bind(signal)
+        .bind(minute_ms)
+        .bind(count)
+        .bind(min)
+        .bind(max)
+        .bind(sum)
+        .execute(&mut *transaction)
+        .await?;
+    }
+    // The recorder's values of the step are now part of the aggregates
+    sqlx::query("DELETE FROM signal_values WHERE step_id = $1")
+        .bind(step_id)
+        .execute(&mut *transaction)
+        .await?;
+    let frames = sqlx::query("DELETE FROM can_messages WHERE step_id = $1")
+        .bind(step_id)
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+    transaction.commit().await?;
+
+    Ok(frames)
+}
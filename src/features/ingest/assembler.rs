@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::core::bus::{Bus, BusMessage};
+use crate::core::can::{CanId, CanMessage, Endianness};
+use crate::core::signals::SignalProfile;
+use crate::features::event::model::{EventKind, Severity};
+use crate::features::event::{service as event_service, Event};
+
+/// Byte order, vehicle, CAN profile, name and tenant of a step, set by its first frame
+#[derive(Debug, Clone)]
+pub struct StepMeta {
+    pub endianness: Endianness,
+    pub vehicle_id: Option<String>,
+    /// Profile of the vehicle, whose required frames complete the step
+    pub profile: &'static SignalProfile,
+    pub step_name: String,
+    pub tenant: Option<String>,
+}
+
+/// Frames of one step received so far
+struct PendingStep {
+    meta: StepMeta,
+    frames: Vec<CanMessage>,
+    first_frame_at: Instant,
+}
+
+/// Every expected frame of a step, ordered by CAN ID
+#[derive(Debug, Clone)]
+pub struct AssembledStep {
+    pub step_id: String,
+    pub meta: StepMeta,
+    pub frames: Vec<CanMessage>,
+}
+
+/// A step given up on before all of its expected frames arrived
+#[derive(Debug, Clone)]
+pub struct IncompleteStep {
+    pub step_id: String,
+    pub meta: StepMeta,
+    pub received: Vec<u16>,
+    pub missing: Vec<u16>,
+    pub waited: Duration,
+}
+
+impl IncompleteStep {
+    /// Diagnostic `incomplete_step` event describing the missing frames
+    pub fn event(&self) -> Event {
+        let ids = |ids: &[u16]| -> Vec<String> {
+            ids.iter().map(|id| CanId::from(*id).to_string()).collect()
+        };
+        Event::new(
+            EventKind::IncompleteStep,
+            "step_assembly",
+            format!(
+                "Step '{}' timed out after {:.1} s, missing {}",
+                self.step_id,
+                self.waited.as_secs_f64(),
+                ids(&self.missing).join(", ")
+            ),
+            Some(self.meta.step_name.clone()),
+        )
+        .with_severity(Severity::Warning)
+        .with_tenant(self.meta.tenant.clone())
+        .with_payload(serde_json::json!({
+            "step_id": self.step_id,
+            "vehicle_id": self.meta.vehicle_id,
+            "profile": self.meta.profile.name,
+            "received": ids(&self.received),
+            "missing": ids(&self.missing),
+            "waited_ms": self.waited.as_millis() as u64,
+        }))
+    }
+}
+
+#[derive(Default)]
+struct AssemblyState {
+    pending: HashMap<String, PendingStep>,
+    /// Recently assembled step ids, so late frames are reported instead of starting a new step
+    assembled: HashMap<String, Instant>,
+}
+
+/// Collects frames arriving one by one, in any order, into steps keyed by step id
+///
+/// A step is complete once a frame arrived for each CAN ID its profile requires; optional
+/// frames must arrive before the last required one. Steps still incomplete after
+/// `timeout` are dropped with a diagnostic event.
+#[derive(Clone)]
+pub struct StepAssembler {
+    state: Arc<Mutex<AssemblyState>>,
+    timeout: Duration,
+}
+
+impl StepAssembler {
+    pub fn new(timeout: Duration) -> Self {
+        StepAssembler {
+            state: Arc::new(Mutex::new(AssemblyState::default())),
+            timeout,
+        }
+    }
+
+    /// Whether frames of `step_id` are being collected
+    pub fn contains(&self, step_id: &str) -> bool {
+        self.state.lock().unwrap().pending.contains_key(step_id)
+    }
+
+    /// Number of steps waiting for frames
+    pub fn pending(&self) -> usize {
+        self.state.lock().unwrap().pending.len()
+    }
+
+    /// Add a frame to its step, returning the step once every expected frame arrived
+    ///
+    /// `meta` only applies to the first frame of a step; later frames must come from the
+    /// same vehicle and tenant.
+    pub fn push(
+        &self,
+        step_id: &str,
+        frame: CanMessage,
+        meta: StepMeta,
+    ) -> Result<Option<AssembledStep>, String> {
+        let mut state = self.state.lock().unwrap();
+        if state.assembled.contains_key(step_id) {
+            return Err(format!("Step '{}' was already assembled", step_id));
+        }
+
+        let pending = state
+            .pending
+            .entry(step_id.to_string())
+            .or_insert_with(|| PendingStep {
+                meta: meta.clone(),
+                frames: Vec::new(),
+                first_frame_at: Instant::now(),
+            });
+        if pending.meta.vehicle_id != meta.vehicle_id {
+            return Err(format!(
+                "Step '{}' belongs to vehicle {}, not {}",
+                step_id,
+                pending.meta.vehicle_id.as_deref().unwrap_or("(none)"),
+                meta.vehicle_id.as_deref().unwrap_or("(none)")
+            ));
+        }
+        if pending.meta.tenant != meta.tenant {
+            return Err(format!("Step '{}' belongs to another tenant", step_id));
+        }
+        if pending
+            .frames
+            .iter()
+            .any(|received| received.id == frame.id)
+        {
+            return Err(format!(
+                "CAN frame {} was already received for step '{}'",
+                CanId::from(frame.id),
+                step_id
+            ));
+        }
+        pending.frames.push(frame);
+
+        if !pending.meta.profile.missing(&pending.frames).is_empty() {
+            return Ok(None);
+        }
+
+        let Some(mut pending) = state.pending.remove(step_id) else {
+            return Ok(None);
+        };
+        state.assembled.insert(step_id.to_string(), Instant::now());
+        pending.frames.sort_by_key(|frame| frame.id);
+        Ok(Some(AssembledStep {
+            step_id: step_id.to_string(),
+            meta: pending.meta,
+            frames: pending.frames,
+        }))
+    }
+
+    /// Remove the steps that waited longer than the timeout for their missing frames
+    pub fn expire(&self) -> Vec<IncompleteStep> {
+        let mut state = self.state.lock().unwrap();
+        state
+            .assembled
+            .retain(|_, assembled_at| assembled_at.elapsed() < self.timeout);
+
+        let expired: Vec<String> = state
+            .pending
+            .iter()
+            .filter(|(_, pending)| pending.first_frame_at.elapsed() >= self.timeout)
+            .map(|(step_id, _)| step_id.clone())
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|step_id| {
+                let pending = state.pending.remove(&step_id)?;
+                let mut received: Vec<u16> = pending.frames.iter().map(|frame| frame.id).collect();
+                received.sort_unstable();
+                let missing = pending.meta.profile.missing(&pending.frames);
+                Some(IncompleteStep {
+                    step_id,
+                    meta: pending.meta,
+                    received,
+                    missing,
+                    waited: pending.first_frame_at.elapsed(),
+                })
+            })
+            .collect()
+    }
+
+    /// Time out incomplete steps, storing and publishing an `incomplete_step` event for each
+    pub fn spawn(&self, bus: &Bus) {
+        let assembler = self.clone();
+        let bus = bus.clone();
+        let mut check = tokio::time::interval((self.timeout / 4).max(Duration::from_millis(100)));
+        check.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        tokio::spawn(async move {
+            loop {
+                check.tick().await;
+                for incomplete in assembler.expire() {
+                    let event = incomplete.event();
+                    println!("⚠️ {}", event.message);
+                    if let Err(e) = event_service::store_event(&event).await {
+                        println!("❌ Failed to store event '{}': {}", event.name, e);
+                    }
+                    let _ = bus.send(BusMessage::Event(event));
+                }
+            }
+        });
+    }
+}
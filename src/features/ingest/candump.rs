@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+
+use crate::core::can::{from_hex, CanId, CanMessage};
+use crate::core::clock::Clock;
+use crate::features::ingest::parser::{FrameParser, ParseError};
+
+/// Output of the can-utils `candump` tool
+///
+/// Both the log format (`candump -l`, `(1609459200.123456) can0 100#B0041E0001`), whose
+/// timestamps are kept, and the default screen format (`can0  100   [5]  B0 04 1E 00 01`),
+/// stamped with the clock, are accepted. CAN FD frames are read from the log format
+/// (`100##1<data>`, the digit after `##` holding the BRS flag in its lowest bit) and from
+/// the screen format, whose DLC of two digits (`[12]`) marks them. Remote frames are
+/// rejected.
+pub struct CandumpParser;
+
+/// One frame read from a line, `fd` holding the BRS flag of CAN FD frames
+struct LineFrame {
+    id: CanId,
+    data: Vec<u8>,
+    fd: Option<bool>,
+    timestamp: String,
+}
+
+impl CandumpParser {
+    /// `(seconds.micros) interface id#data` or `(seconds.micros) interface id##<flags>data`
+    fn parse_log(line: &str) -> Result<LineFrame, String> {
+        let (time, rest) = line
+            .strip_prefix('(')
+            .and_then(|line| line.split_once(')'))
+            .ok_or("Malformed timestamp")?;
+        let seconds: f64 = time
+            .trim()
+            .parse()
+            .map_err(|_| format!("Malformed timestamp '{}'", time))?;
+        let timestamp = DateTime::<Utc>::from_timestamp_micros((seconds * 1e6).round() as i64)
+            .ok_or_else(|| format!("Timestamp {} out of range", time))?
+            .to_rfc3339();
+
+        let frame = rest
+            .split_whitespace()
+            .nth(1)
+            .ok_or("Missing frame after the interface")?;
+        if let Some((id, data)) = frame.split_once("##") {
+            let mut data = data.chars();
+            let flags = data
+                .next()
+                .and_then(|flags| flags.to_digit(16))
+                .ok_or_else(|| {
+                    format!(
+                        "Malformed CAN FD frame '{}' (expected id##<flags>data)",
+                        frame
+                    )
+                })?;
+            return Ok(LineFrame {
+                id: parse_id(id)?,
+                data: from_hex(data.as_str())?,
+                fd: Some(flags & 0x1 != 0),
+                timestamp,
+            });
+        }
+        let (id, data) = frame
+            .split_once('#')
+            .ok_or_else(|| format!("Malformed frame '{}' (expected id#data)", frame))?;
+        if data.starts_with(['R', 'r']) {
+            return Err("Remote frames carry no data".to_string());
+        }
+
+        Ok(LineFrame {
+            id: parse_id(id)?,
+            data: from_hex(data)?,
+            fd: None,
+            timestamp,
+        })
+    }
+
+    /// `interface  id   [dlc]  bytes...`, `[dd]` for CAN FD frames
+    fn parse_screen(line: &str, clock: &dyn Clock) -> Result<LineFrame, String> {
+        let mut fields = line.split_whitespace().skip(1);
+        let id = parse_id(fields.next().ok_or("Missing CAN ID")?)?;
+        let dlc = fields
+            .next()
+            .and_then(|dlc| dlc.strip_prefix('[')?.strip_suffix(']'))
+            .ok_or("Malformed DLC (expected [n])")?;
+        // The screen format does not show the BRS flag of CAN FD frames
+        let fd = (dlc.len() == 2).then_some(false);
+        let dlc: usize = dlc.parse().map_err(|_| "Malformed DLC (expected [n])")?;
+        let rest: Vec<&str> = fields.collect();
+        if rest
+            .first()
+            .is_some_and(|field| field.eq_ignore_ascii_case("remote"))
+        {
+            return Err("Remote frames carry no data".to_string());
+        }
+        let data = from_hex(&rest.concat())?;
+        if data.len() != dlc {
+            return Err(format!("DLC {} but {} data bytes", dlc, data.len()));
+        }
+
+        Ok(LineFrame {
+            id,
+            data,
+            fd,
+            timestamp: clock.timestamp(),
+        })
+    }
+}
+
+/// candump prints identifiers in hex without a prefix
+fn parse_id(id: &str) -> Result<CanId, String> {
+    format!("0x{}", id)
+        .parse()
+        .map_err(|e: crate::core::can::CanError| e.to_string())
+}
+
+impl FrameParser for CandumpParser {
+    fn name(&self) -> &'static str {
+        "candump"
+    }
+
+    fn content_types(&self) -> &'static [&'static str] {
+        &["text/x-candump", "text/plain"]
+    }
+
+    fn header_lines(&self) -> Option<usize> {
+        Some(0)
+    }
+
+    fn parse(&self, body: &str, clock: &dyn Clock) -> Result<Vec<CanMessage>, ParseError> {
+        body.lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(line_number, line)| {
+                let frame = if line.starts_with('(') {
+                    Self::parse_log(line)
+                } else {
+                    Self::parse_screen(line, clock)
+                }
+                .map_err(|e| ParseError::new(line_number, e))?;
+                match frame.fd {
+                    Some(brs) => {
+                        CanMessage::try_new_fd(frame.id, &frame.data, brs, frame.timestamp)
+                    }
+                    None => CanMessage::try_new(frame.id, &frame.data, frame.timestamp),
+                }
+                .map_err(|e| ParseError::new(line_number, e))
+            })
+            .collect()
+    }
+}
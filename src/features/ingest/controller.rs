@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+
+use crate::common::error::AppError;
+use crate::config::db::get_pool;
+use crate::config::transport::StepTransport;
+use crate::core::can::{CanId, CanMessage, Endianness};
+use crate::core::clock::SystemClock;
+use crate::core::signals::{self, SignalProfile};
+use crate::features::driving_step::model::{DecodeFailure, StoredStep};
+use crate::features::driving_step::service as step_service;
+use crate::features::driving_step::DrivingStep;
+use crate::features::ingest::assembler::{AssembledStep, StepAssembler, StepMeta};
+use crate::features::ingest::model::{AssemblyReport, StepFrame};
+use crate::features::ingest::parser::{self, FrameParser};
+use crate::features::tenant::service as tenant_service;
+use crate::features::tenant::Usage;
+use crate::features::validation::{self, FrameValidator, ValidationContext};
+use crate::features::vehicle::controller as vehicle_controller;
+use crate::features::vehicle::Vehicle;
+
+/// Parser registered for the request's Content-Type
+pub fn parser(content_type: &str) -> Result<&'static dyn FrameParser, AppError> {
+    parser::parser_for(content_type).ok_or_else(|| {
+        AppError::unsupported_media_type(format!(
+            "Content-Type '{}', expected one of {}",
+            content_type,
+            parser::supported_content_types().join(", ")
+        ))
+    })
+}
+
+pub fn parse(parser: &dyn FrameParser, body: &[u8]) -> Result<Vec<CanMessage>, AppError> {
+    let body =
+        std::str::from_utf8(body).map_err(|_| AppError::bad_request("Body must be UTF-8 text"))?;
+    let frames = parser
+        .parse(body, &SystemClock)
+        .map_err(|e| AppError::bad_request(format!("Invalid {} body, {}", parser.name(), e)))?;
+    if frames.is_empty() {
+        return Err(AppError::bad_request("No CAN frames in body"));
+    }
+    Ok(frames)
+}
+
+/// Cut a frame sequence into steps, a new step starting at the first repeated CAN ID
+pub fn split_steps(frames: Vec<CanMessage>) -> Vec<Vec<CanMessage>> {
+    let mut steps: Vec<Vec<CanMessage>> = Vec::new();
+    for frame in frames {
+        match steps.last_mut() {
+            Some(step) if !step.iter().any(|stored| stored.id == frame.id) => step.push(frame),
+            _ => steps.push(vec![frame]),
+        }
+    }
+    steps
+}
+
+/// Signal map of the vehicle the frames come from, the default one without a vehicle
+pub fn profile(vehicle: Option<&Vehicle>) -> Result<&'static SignalProfile, AppError> {
+    let name = vehicle.map_or(signals::DEFAULT_PROFILE, |vehicle| {
+        vehicle.can_profile.as_str()
+    });
+    signals::profile(name)
+        .ok_or_else(|| AppError::bad_request(format!("Unknown CAN profile '{}'", name)))
+}
+
+/// Decode every step before storing any, so a bad group leaves the database untouched
+pub fn verify(
+    steps: &[Vec<CanMessage>],
+    endianness: Endianness,
+    profile: &SignalProfile,
+) -> Result<(), DecodeFailure> {
+    for (index, frames) in steps.iter().enumerate() {
+        if let Err(error) = profile.decode_step(frames, String::new(), endianness.is_big_endian()) {
+            return Err(DecodeFailure {
+                error: format!("Step {} of the body: {}", index + 1, error),
+                endianness,
+                can_ids: frames
+                    .iter()
+                    .map(|frame| CanId::from(frame.id).to_string())
+                    .collect(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Store each step and notify the consumer, as `POST /driving-steps` does
+pub async fn publish(
+    steps: Vec<Vec<CanMessage>>,
+    endianness: Endianness,
+    vehicle_id: Option<&str>,
+    tenant: Option<&str>,
+    step_name: &str,
+    transport: &StepTransport,
+) -> Result<Vec<StoredStep>, AppError> {
+    // Refuse the whole body rather than store the steps that still fit the quota
+    if let Some(tenant) = tenant {
+        let mut conn = get_pool().await?.acquire().await?;
+        let usage = steps.iter().map(|frames| Usage::of(frames)).sum();
+        tenant_service::check_quota(&mut conn, tenant, steps.len() as u64, usage).await?;
+    }
+
+    let mut stored = Vec::with_capacity(steps.len());
+    for frames in steps {
+        stored.push(
+            step_service::publish_frames(
+                frames, endianness, vehicle_id, tenant, step_name, transport,
+            )
+            .await?,
+        );
+    }
+    Ok(stored)
+}
+
+/// Feed tagged frames to the assembler, validating and publishing every step they complete
+///
+/// Frames are pushed in body order; a frame the assembler refuses fails the request, the
+/// frames before it staying pushed.
+pub async fn assemble(
+    assembler: &StepAssembler,
+    validator: &FrameValidator,
+    frames: Vec<StepFrame>,
+    tenant: Option<&str>,
+    transport: &StepTransport,
+) -> Result<AssemblyReport, AppError> {
+    let mut report = AssemblyReport {
+        frames: frames.len(),
+        ..Default::default()
+    };
+    let mut vehicles: HashMap<Option<String>, Option<Vehicle>> = HashMap::new();
+
+    for tagged in frames {
+        if !assembler.contains(&tagged.step_id)
+            && step_service::get_step_frames(&tagged.step_id, None)
+                .await?
+                .is_some()
+        {
+            return Err(AppError::bad_request(format!(
+                "Step '{}' is already stored",
+                tagged.step_id
+            )));
+        }
+        let vehicle = match vehicles.get(&tagged.vehicle_id) {
+            Some(vehicle) => vehicle.clone(),
+            None => {
+                let vehicle =
+                    vehicle_controller::for_ingestion(tagged.vehicle_id.as_deref()).await?;
+                vehicles.insert(tagged.vehicle_id.clone(), vehicle.clone());
+                vehicle
+            }
+        };
+
+        let meta = StepMeta {
+            endianness: tagged
+                .endianness
+                .or(vehicle.as_ref().map(|vehicle| vehicle.endianness))
+                .unwrap_or_else(|| {
+                    Endianness::from_is_big_endian(DrivingStep::get_endianness_from_env())
+                }),
+            vehicle_id: tagged.vehicle_id,
+            profile: profile(vehicle.as_ref())?,
+            step_name: tagged.step_name.unwrap_or_else(|| "Assembled".to_string()),
+            tenant: tenant.map(str::to_string),
+        };
+        let assembled = assembler
+            .push(&tagged.step_id, tagged.frame, meta)
+            .map_err(AppError::bad_request)?;
+        if let Some(assembled) = assembled {
+            complete(assembled, validator, transport, &mut report).await?;
+        }
+    }
+
+    report.pending = assembler.pending();
+    Ok(report)
+}
+
+/// Validate, decode and publish an assembled step, recording the outcome in `report`
+async fn complete(
+    assembled: AssembledStep,
+    validator: &FrameValidator,
+    transport: &StepTransport,
+    report: &mut AssemblyReport,
+) -> Result<(), AppError> {
+    let AssembledStep {
+        step_id,
+        meta,
+        frames,
+    } = assembled;
+    let profile = meta.profile;
+    let context = ValidationContext {
+        profile,
+        endianness: meta.endianness,
+        vehicle_id: meta.vehicle_id.as_deref(),
+        step_name: &meta.step_name,
+    };
+
+    let (steps, rejected) =
+        match validation::controller::screen(validator, vec![frames], &context).await? {
+            Ok(screened) => screened,
+            Err(failure) => {
+                let reasons: Vec<String> = failure
+                    .violations
+                    .iter()
+                    .map(|violation| format!("{} {}", violation.can_id, violation.reason))
+                    .collect();
+                report.errors.push(format!(
+                    "Step '{}' breaks the validation rules: {}",
+                    step_id,
+                    reasons.join("; ")
+                ));
+                return Ok(());
+            }
+        };
+    report.rejected += rejected;
+
+    for frames in steps {
+        if let Err(error) =
+            profile.decode_step(&frames, String::new(), meta.endianness.is_big_endian())
+        {
+            report
+                .errors
+                .push(format!("Step '{}' does not decode: {}", step_id, error));
+            continue;
+        }
+        report.steps.push(
+            step_service::publish_frames_as(
+                &step_id,
+                frames,
+                meta.endianness,
+                meta.vehicle_id.as_deref(),
+                meta.tenant.as_deref(),
+                &meta.step_name,
+                transport,
+            )
+            .await?,
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use crate::core::can::{from_hex, CanId, CanMessage};
+use crate::core::clock::Clock;
+use crate::features::ingest::parser::{FrameParser, ParseError};
+
+/// CSV with a header row naming its columns, in any order
+///
+/// `id` (decimal or `0x` hex) and `data` (hex, spaces allowed) are required; `dlc` is
+/// checked against the payload length and `timestamp` (RFC3339) defaults to the clock.
+/// Optional `fd` and `brs` flags (`1`/`0` or `true`/`false`) mark CAN-FD frames.
+/// Quoted fields are not supported.
+pub struct CsvParser;
+
+impl FrameParser for CsvParser {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    fn content_types(&self) -> &'static [&'static str] {
+        &["text/csv"]
+    }
+
+    fn header_lines(&self) -> Option<usize> {
+        Some(1)
+    }
+
+    fn parse(&self, body: &str, clock: &dyn Clock) -> Result<Vec<CanMessage>, ParseError> {
+        let mut lines = body
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty());
+
+        let (header_line, header) = lines
+            .next()
+            .ok_or_else(|| ParseError::new(1, "Missing header row"))?;
+        let columns: Vec<String> = header
+            .split(',')
+            .map(|column| column.trim().to_ascii_lowercase())
+            .collect();
+        let column = |name: &str| columns.iter().position(|column| column == name);
+        let (Some(id_column), Some(data_column)) = (column("id"), column("data")) else {
+            return Err(ParseError::new(
+                header_line,
+                "Header must name an 'id' and a 'data' column",
+            ));
+        };
+        let dlc_column = column("dlc");
+        let timestamp_column = column("timestamp");
+        let (fd_column, brs_column) = (column("fd"), column("brs"));
+
+        lines
+            .map(|(line, row)| {
+                let fields: Vec<&str> = row.split(',').map(str::trim).collect();
+                if fields.len() != columns.len() {
+                    return Err(ParseError::new(
+                        line,
+                        format!("Expected {} fields, found {}", columns.len(), fields.len()),
+                    ));
+                }
+
+                let id: CanId = fields[id_column]
+                    .parse()
+                    .map_err(|e| ParseError::new(line, e))?;
+                let data = from_hex(&fields[data_column].replace(' ', ""))
+                    .map_err(|e| ParseError::new(line, e))?;
+                if let Some(dlc_column) = dlc_column {
+                    let dlc: usize = fields[dlc_column]
+                        .parse()
+                        .map_err(|_| ParseError::new(line, "DLC must be a number"))?;
+                    if dlc != data.len() {
+                        return Err(ParseError::new(
+                            line,
+                            format!("DLC {} but {} data bytes", dlc, data.len()),
+                        ));
+                    }
+                }
+                let timestamp = match timestamp_column {
+                    Some(column) => fields[column].to_string(),
+                    None => clock.timestamp(),
+                };
+
+                let flag =
+                    |column: Option<usize>, name: &str| match column.map(|column| fields[column]) {
+                        None | Some("" | "0" | "false") => Ok(false),
+                        Some("1" | "true") => Ok(true),
+                        Some(other) => Err(ParseError::new(
+                            line,
+                            format!("'{}' must be 0, 1, true or false, got '{}'", name, other),
+                        )),
+                    };
+                let (fd, brs) = (flag(fd_column, "fd")?, flag(brs_column, "brs")?);
+
+                if fd {
+                    CanMessage::try_new_fd(id, &data, brs, timestamp)
+                } else if brs {
+                    return Err(ParseError::new(
+                        line,
+                        "BRS is only defined for CAN-FD frames",
+                    ));
+                } else {
+                    CanMessage::try_new(id, &data, timestamp)
+                }
+                .map_err(|e| ParseError::new(line, e))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,34 @@
+use serde::Deserialize;
+
+use crate::core::can::CanMessage;
+use crate::core::clock::Clock;
+use crate::features::ingest::parser::{FrameParser, ParseError};
+
+/// The `{"frames": [...]}` shape of the reconstruct endpoint
+#[derive(Deserialize)]
+struct Wrapped {
+    #[serde(alias = "can_messages")]
+    frames: Vec<CanMessage>,
+}
+
+/// JSON frames as served by `GET /driving-steps/{step_id}/frames`, or a bare frame array
+pub struct JsonParser;
+
+impl FrameParser for JsonParser {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn content_types(&self) -> &'static [&'static str] {
+        &["application/json"]
+    }
+
+    fn parse(&self, body: &str, _clock: &dyn Clock) -> Result<Vec<CanMessage>, ParseError> {
+        let parsed = if body.trim_start().starts_with('[') {
+            serde_json::from_str::<Vec<CanMessage>>(body)
+        } else {
+            serde_json::from_str::<Wrapped>(body).map(|wrapped| wrapped.frames)
+        };
+        parsed.map_err(|e| ParseError::new(e.line(), e))
+    }
+}
@@ -0,0 +1,225 @@
+pub mod assembler;
+pub mod candump;
+pub mod controller;
+pub mod csv;
+pub mod json;
+pub mod model;
+pub mod parser;
+pub mod service;
+pub mod signature;
+pub mod upload;
+
+use actix_web::http::header::CONTENT_TYPE;
+use actix_web::web::Data;
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Result};
+
+use crate::common::error::AppError;
+use crate::config::transport::StepTransport;
+use crate::core::can::Endianness;
+use crate::features::driving_step::DrivingStep;
+use crate::features::job::JobQueue;
+use crate::features::tenant::TenantScope;
+use crate::features::validation::{self, FrameValidator, ValidationContext};
+use crate::features::vehicle::controller as vehicle_controller;
+
+pub use assembler::StepAssembler;
+pub use model::{AssemblyReport, BatchSignature, IngestReport};
+use model::{ChunkQuery, IngestQuery, StepFrame};
+pub use model::{UploadProgress, UploadStatus};
+pub use parser::{FrameParser, ParseError, PARSERS};
+pub use signature::SigningPolicy;
+pub use upload::UploadRegistry;
+
+/// Content-Type of the request, without checking it names a parser
+fn content_type(req: &HttpRequest) -> &str {
+    req.headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+}
+
+/// Frames from third-party tools, parsed according to the Content-Type
+///
+/// A batch signed with `X-Signature` is verified before it is parsed, and its signer
+/// recorded for every step it stores.
+#[post("/ingest")]
+pub async fn ingest(
+    req: HttpRequest,
+    body: web::Bytes,
+    query: web::Query<IngestQuery>,
+    scope: TenantScope,
+    transport: Data<StepTransport>,
+    validator: Data<FrameValidator>,
+) -> Result<HttpResponse, AppError> {
+    let signature = signature::verify(&req, &body, &scope)?;
+    let parser = controller::parser(content_type(&req))?;
+    let frames = controller::parse(parser, &body)?;
+    let frame_count = frames.len();
+
+    let vehicle = vehicle_controller::for_ingestion(query.vehicle_id.as_deref()).await?;
+    let profile = controller::profile(vehicle.as_ref())?;
+    let steps = controller::split_steps(frames);
+    let endianness = query
+        .endianness
+        .or(vehicle.as_ref().map(|vehicle| vehicle.endianness))
+        .or_else(|| {
+            steps
+                .iter()
+                .find_map(|frames| DrivingStep::detect_endianness(frames))
+        })
+        .unwrap_or_else(|| Endianness::from_is_big_endian(DrivingStep::get_endianness_from_env()));
+    let step_name = query.step_name.as_deref().unwrap_or("Ingested");
+    let context = ValidationContext {
+        profile,
+        endianness,
+        vehicle_id: query.vehicle_id.as_deref(),
+        step_name,
+    };
+    let (steps, rejected) =
+        match validation::controller::screen(&validator, steps, &context).await? {
+            Ok(screened) => screened,
+            Err(failure) => return Ok(HttpResponse::UnprocessableEntity().json(failure)),
+        };
+    if let Err(failure) = controller::verify(&steps, endianness, profile) {
+        return Ok(HttpResponse::UnprocessableEntity().json(failure));
+    }
+
+    let steps = controller::publish(
+        steps,
+        endianness,
+        query.vehicle_id.as_deref(),
+        scope.name(),
+        step_name,
+        &transport,
+    )
+    .await?;
+    if let Some(signature) = &signature {
+        for step in &steps {
+            service::store_signature(&step.step_id, signature, scope.name()).await?;
+        }
+    }
+    Ok(HttpResponse::Accepted().json(IngestReport {
+        format: parser.name(),
+        frames: frame_count,
+        rejected,
+        steps,
+        signer: signature.map(|signature| signature.signer),
+    }))
+}
+
+/// Signature of the batch a step was ingested from, `404` for steps stored unsigned
+#[get("/driving-steps/{step_id}/signature")]
+pub async fn get_signature(
+    path: web::Path<String>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    let signature = service::get_signature(&path, scope.name())
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Signature of driving step '{}'", path)))?;
+    Ok(HttpResponse::Ok().json(signature))
+}
+
+/// Frames sent one by one or out of order, assembled into steps by their `step_id`
+#[post("/frames")]
+pub async fn assemble(
+    frames: web::Json<Vec<StepFrame>>,
+    assembler: Data<StepAssembler>,
+    validator: Data<FrameValidator>,
+    scope: TenantScope,
+    transport: Data<StepTransport>,
+) -> Result<HttpResponse, AppError> {
+    let frames = frames.into_inner();
+    if frames.is_empty() {
+        return Err(AppError::bad_request("No CAN frames in body"));
+    }
+    let report =
+        controller::assemble(&assembler, &validator, frames, scope.name(), &transport).await?;
+    Ok(HttpResponse::Accepted().json(report))
+}
+
+/// Open a chunked upload of a log too large for one `POST /ingest` body
+///
+/// The Content-Type and query parameters are those `POST /ingest` takes; no body is read.
+#[post("/ingest/uploads")]
+pub async fn open_upload(
+    req: HttpRequest,
+    query: web::Query<IngestQuery>,
+    scope: TenantScope,
+    uploads: Data<UploadRegistry>,
+) -> Result<HttpResponse, AppError> {
+    let parser = controller::parser(content_type(&req))?;
+    let vehicle = vehicle_controller::for_ingestion(query.vehicle_id.as_deref()).await?;
+    controller::profile(vehicle.as_ref())?;
+    let upload = uploads.open(parser, query.into_inner(), scope.name())?;
+    Ok(HttpResponse::Created().json(upload))
+}
+
+#[get("/ingest/uploads")]
+pub async fn list_uploads(
+    scope: TenantScope,
+    uploads: Data<UploadRegistry>,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(uploads.list(scope.name())))
+}
+
+#[get("/ingest/uploads/{id}")]
+pub async fn get_upload(
+    path: web::Path<String>,
+    scope: TenantScope,
+    uploads: Data<UploadRegistry>,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(uploads.get(&path, scope.name())?))
+}
+
+/// Append the request body to the upload, at `?offset=` bytes
+#[post("/ingest/uploads/{id}/chunks")]
+pub async fn append_upload(
+    path: web::Path<String>,
+    query: web::Query<ChunkQuery>,
+    mut payload: web::Payload,
+    scope: TenantScope,
+    uploads: Data<UploadRegistry>,
+) -> Result<HttpResponse, AppError> {
+    let upload = uploads
+        .append(&path, scope.name(), query.offset, &mut payload)
+        .await?;
+    Ok(HttpResponse::Ok().json(upload))
+}
+
+/// Stop accepting chunks and store the upload in an import job, see `GET` or the job for progress
+#[post("/ingest/uploads/{id}/commit")]
+pub async fn commit_upload(
+    path: web::Path<String>,
+    scope: TenantScope,
+    uploads: Data<UploadRegistry>,
+    transport: Data<StepTransport>,
+    validator: Data<FrameValidator>,
+    jobs: Data<JobQueue>,
+) -> Result<HttpResponse, AppError> {
+    let upload = uploads
+        .commit(&path, scope.name(), &transport, &validator, &jobs)
+        .await?;
+    Ok(HttpResponse::Accepted().json(upload))
+}
+
+#[delete("/ingest/uploads/{id}")]
+pub async fn abort_upload(
+    path: web::Path<String>,
+    scope: TenantScope,
+    uploads: Data<UploadRegistry>,
+) -> Result<HttpResponse, AppError> {
+    uploads.abort(&path, scope.name())?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(ingest)
+        .service(get_signature)
+        .service(assemble)
+        .service(open_upload)
+        .service(list_uploads)
+        .service(get_upload)
+        .service(append_upload)
+        .service(commit_upload)
+        .service(abort_upload);
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::can::{CanMessage, Endianness};
+use crate::features::driving_step::model::StoredStep;
+
+/// Query parameters accepted by `POST /ingest`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IngestQuery {
+    /// Byte order the frames were encoded with; when omitted the vehicle's, else the one
+    /// detected from the frames, else `ENDIAN` of the server
+    pub endianness: Option<Endianness>,
+    /// VIN of a registered vehicle, whose CAN profile decodes the frames
+    pub vehicle_id: Option<String>,
+    /// Name given to the reconstructed steps, `Ingested` when omitted
+    pub step_name: Option<String>,
+}
+
+/// Response of `POST /ingest`
+#[derive(Debug, Clone, Serialize)]
+pub struct IngestReport {
+    /// Parser selected by the Content-Type
+    pub format: &'static str,
+    pub frames: usize,
+    /// Frames quarantined by the validation stage, see `GET /rejected-frames`
+    pub rejected: usize,
+    /// One stored step per frame group, in body order
+    pub steps: Vec<StoredStep>,
+    /// Who signed the batch, when it came with a verified `X-Signature`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+}
+
+/// Verified signature of an ingested batch, recorded for each step it stored
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchSignature {
+    /// Tenant whose API key signed the batch, `admin` for the administrator key
+    pub signer: String,
+    /// Hex HMAC-SHA256 sent in `X-Signature`
+    pub signature: String,
+    /// Time given by `X-Signature-Timestamp`
+    pub signed_at: String,
+    pub verified_at: String,
+}
+
+/// One CAN frame sent to `POST /frames` or `/ws`, tagged with the step it belongs to
+#[derive(Debug, Clone, Deserialize)]
+pub struct StepFrame {
+    /// Groups the frames of one step; the assembled step is stored under this id
+    pub step_id: String,
+    /// Byte order of the step, the vehicle's or `ENDIAN` of the server when omitted
+    pub endianness: Option<Endianness>,
+    /// VIN of a registered vehicle, whose CAN profile decodes the step
+    pub vehicle_id: Option<String>,
+    /// Name given to the reconstructed step, `Assembled` when omitted
+    pub step_name: Option<String>,
+    #[serde(flatten)]
+    pub frame: CanMessage,
+}
+
+/// Response of `POST /frames`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AssemblyReport {
+    pub frames: usize,
+    /// Steps still waiting for frames, across every client
+    pub pending: usize,
+    /// Frames of completed steps quarantined by the validation stage
+    pub rejected: usize,
+    /// Steps completed by these frames, stored and announced to the consumer
+    pub steps: Vec<StoredStep>,
+    /// Completed steps dropped because they broke a validation rule or did not decode
+    pub errors: Vec<String>,
+}
+
+/// Stage of an upload session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadStatus {
+    /// Accepting chunks
+    Open,
+    /// Committed, being parsed and stored in the background
+    Processing,
+    /// Every step of the upload is stored
+    Done,
+    /// Parsing or storing stopped, the steps stored before staying stored
+    Failed,
+}
+
+/// Query parameters accepted by `POST /ingest/uploads/{id}/chunks`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChunkQuery {
+    /// Byte of the upload the chunk starts at; below `received_bytes` the upload is cut
+    /// back to it, so a chunk whose acknowledgement was lost can be sent again
+    pub offset: u64,
+}
+
+/// State of an upload session, as answered by every `/ingest/uploads` endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadProgress {
+    pub id: String,
+    /// Parser selected by the Content-Type of `POST /ingest/uploads`
+    pub format: &'static str,
+    pub status: UploadStatus,
+    pub created_at: String,
+    /// Bytes written so far, the offset of the next chunk
+    pub received_bytes: u64,
+    /// Bytes parsed by the background task, out of `received_bytes`
+    pub parsed_bytes: u64,
+    pub frames: usize,
+    /// Frames quarantined by the validation stage, see `GET /rejected-frames`
+    pub rejected: usize,
+    /// Steps stored so far
+    pub steps: usize,
+    /// Why the upload failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Body `POST /ingest` would have answered with `422`, for failures of the validation
+    /// or decoding stage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure: Option<serde_json::Value>,
+    /// Import job storing the committed upload, see `GET /jobs/{id}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+}
@@ -0,0 +1,70 @@
+use derive_more::Display;
+
+use crate::core::can::CanMessage;
+use crate::core::clock::Clock;
+use crate::features::ingest::candump::CandumpParser;
+use crate::features::ingest::csv::CsvParser;
+use crate::features::ingest::json::JsonParser;
+
+/// Why a request body could not be turned into CAN frames
+#[derive(Debug, Display, Clone, PartialEq)]
+#[display("line {}: {}", line, message)]
+pub struct ParseError {
+    /// 1-based line of the body the error was found on
+    pub line: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(line: usize, message: impl ToString) -> Self {
+        ParseError {
+            line,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Turns a request body in one telemetry format into CAN frames
+///
+/// Frames must come out validated (`CanMessage::try_new` or its serde form). Formats
+/// without timestamps stamp their frames with `clock`.
+pub trait FrameParser: Send + Sync {
+    /// Short format name used in responses
+    fn name(&self) -> &'static str;
+
+    /// Media types routed to this parser, without parameters
+    fn content_types(&self) -> &'static [&'static str];
+
+    fn parse(&self, body: &str, clock: &dyn Clock) -> Result<Vec<CanMessage>, ParseError>;
+
+    /// Leading non-empty lines repeated at the top of every batch when an upload is parsed a
+    /// batch of lines at a time, `None` for formats that only parse whole
+    fn header_lines(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Every parser `POST /ingest` can route to
+pub static PARSERS: &[&dyn FrameParser] = &[&JsonParser, &CsvParser, &CandumpParser];
+
+/// Parser registered for `content_type`, ignoring its parameters (`; charset=utf-8`)
+pub fn parser_for(content_type: &str) -> Option<&'static dyn FrameParser> {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    PARSERS
+        .iter()
+        .copied()
+        .find(|parser| parser.content_types().contains(&essence.as_str()))
+}
+
+/// Media types of every parser, for error messages
+pub fn supported_content_types() -> Vec<&'static str> {
+    PARSERS
+        .iter()
+        .flat_map(|parser| parser.content_types().iter().copied())
+        .collect()
+}
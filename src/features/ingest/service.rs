@@ -0,0 +1,62 @@
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::features::ingest::model::BatchSignature;
+
+/// Record the signature of the batch `step_id` was ingested from
+pub async fn store_signature(
+    step_id: &str,
+    signature: &BatchSignature,
+    tenant: Option<&str>,
+) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(
+        "INSERT INTO step_signatures
+         (step_id, signer, signature, signed_at, verified_at, tenant)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (step_id) DO UPDATE SET
+             signer = excluded.signer, signature = excluded.signature,
+             signed_at = excluded.signed_at, verified_at = excluded.verified_at,
+             tenant = excluded.tenant",
+    )
+    .bind(step_id)
+    .bind(&signature.signer)
+    .bind(&signature.signature)
+    .bind(&signature.signed_at)
+    .bind(&signature.verified_at)
+    .bind(tenant)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Signature of the batch `step_id` was ingested from, `None` for steps stored unsigned
+///
+/// With a `tenant`, signatures of other tenants' steps are not returned.
+pub async fn get_signature(
+    step_id: &str,
+    tenant: Option<&str>,
+) -> Result<Option<BatchSignature>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let row = sqlx::query(
+        "SELECT signer, signature, signed_at, verified_at FROM step_signatures
+         WHERE step_id = $1 AND ($2 IS NULL OR tenant = $2)",
+    )
+    .bind(step_id)
+    .bind(tenant)
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(|row| {
+        Ok(BatchSignature {
+            signer: row.try_get("signer")?,
+            signature: row.try_get("signature")?,
+            signed_at: row.try_get("signed_at")?,
+            verified_at: row.try_get("verified_at")?,
+        })
+    })
+    .transpose()
+}
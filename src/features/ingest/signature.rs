@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use actix_web::web::Data;
+use actix_web::HttpRequest;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::common::error::AppError;
+use crate::core::can::{from_hex, to_hex};
+use crate::features::ingest::model::BatchSignature;
+use crate::features::tenant::{self, TenantRegistry, TenantScope};
+
+/// Hex HMAC-SHA256 of `{timestamp}.{body}`, keyed with the API key of the request
+///
+/// The body is signed as the exact bytes sent, not canonicalised: clients sign the bytes
+/// they put on the wire, after serialization and before any transfer encoding.
+pub const SIGNATURE_HEADER: &str = "x-signature";
+/// Unix time in seconds the signature was computed at
+pub const TIMESTAMP_HEADER: &str = "x-signature-timestamp";
+
+/// Name recorded as the signer of batches sent with the administrator key
+const ADMIN_SIGNER: &str = "admin";
+
+/// Whether ingested batches must be signed, and how old a signature may be
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SigningPolicy {
+    /// Refuse unsigned batches with `401`; signed ones are verified either way
+    pub required: bool,
+    /// Largest distance between the signature timestamp and the server clock
+    pub max_skew: Duration,
+}
+
+impl Default for SigningPolicy {
+    fn default() -> Self {
+        SigningPolicy {
+            required: false,
+            max_skew: Duration::from_secs(300),
+        }
+    }
+}
+
+fn mac(api_key: &str, timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(api_key.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// Hex signature of `body` sent at `timestamp` with `api_key`, as clients compute it
+pub fn sign(api_key: &str, timestamp: i64, body: &[u8]) -> String {
+    to_hex(&mac(api_key, timestamp, body).finalize().into_bytes())
+}
+
+/// Check the signature of a batch against the API key it was sent with
+///
+/// Returns the verified signature, or `None` for an unsigned batch the app's
+/// `Data<SigningPolicy>` (defaults without one) lets through. Signing needs tenancy, as
+/// without `ADMIN_API_KEY` keys are never checked and sign for nobody.
+pub fn verify(
+    req: &HttpRequest,
+    body: &[u8],
+    scope: &TenantScope,
+) -> Result<Option<BatchSignature>, AppError> {
+    let policy = req
+        .app_data::<Data<SigningPolicy>>()
+        .map(|policy| *policy.get_ref())
+        .unwrap_or_default();
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+    let (signature, timestamp) = match (header(SIGNATURE_HEADER), header(TIMESTAMP_HEADER)) {
+        (Some(signature), Some(timestamp)) => (signature, timestamp),
+        (None, None) if policy.required => {
+            return Err(AppError::unauthorized(
+                "Batches must be signed with X-Signature and X-Signature-Timestamp",
+            ))
+        }
+        (None, None) => return Ok(None),
+        _ => {
+            return Err(AppError::bad_request(
+                "X-Signature and X-Signature-Timestamp must be sent together",
+            ))
+        }
+    };
+
+    let tenancy = req
+        .app_data::<Data<TenantRegistry>>()
+        .is_some_and(|registry| registry.enabled());
+    let api_key = tenant::api_key(req).filter(|_| tenancy).ok_or_else(|| {
+        AppError::bad_request("Signed batches need API keys, start the server with ADMIN_API_KEY")
+    })?;
+    let timestamp: i64 = timestamp
+        .parse()
+        .map_err(|_| AppError::bad_request("X-Signature-Timestamp must be Unix seconds"))?;
+    let skew = chrono::Utc::now().timestamp().abs_diff(timestamp);
+    if skew > policy.max_skew.as_secs() {
+        return Err(AppError::unauthorized(format!(
+            "Signature timestamp is {} s away from the server clock, at most {} s is accepted",
+            skew,
+            policy.max_skew.as_secs()
+        )));
+    }
+    let expected = from_hex(signature).map_err(AppError::bad_request)?;
+    mac(&api_key, timestamp, body)
+        .verify_slice(&expected)
+        .map_err(|_| AppError::unauthorized("Signature does not match the batch"))?;
+
+    Ok(Some(BatchSignature {
+        signer: scope.name().unwrap_or(ADMIN_SIGNER).to_string(),
+        signature: signature.to_ascii_lowercase(),
+        signed_at: chrono::DateTime::from_timestamp(timestamp, 0)
+            .unwrap_or_default()
+            .to_rfc3339(),
+        verified_at: chrono::Utc::now().to_rfc3339(),
+    }))
+}
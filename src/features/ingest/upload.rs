@@ -0,0 +1,597 @@
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_web::web;
+use futures_util::StreamExt;
+use tokio::io::{AsyncBufReadExt, AsyncSeekExt, AsyncWriteExt, BufReader};
+
+use crate::common::error::AppError;
+use crate::config::transport::StepTransport;
+use crate::core::can::{CanMessage, Endianness};
+use crate::core::clock::SystemClock;
+use crate::core::signals::SignalProfile;
+use crate::features::driving_step::DrivingStep;
+use crate::features::ingest::controller;
+use crate::features::ingest::model::{IngestQuery, UploadProgress, UploadStatus};
+use crate::features::ingest::parser::FrameParser;
+use crate::features::job::{JobKind, JobProgress, JobQueue};
+use crate::features::validation::{self, FrameValidator, ValidationContext};
+use crate::features::vehicle::controller as vehicle_controller;
+
+/// Lines of an upload parsed, validated and stored together by its import job
+pub const BATCH_LINES: usize = 10_000;
+
+struct UploadSession {
+    progress: UploadProgress,
+    parser: &'static dyn FrameParser,
+    query: IngestQuery,
+    tenant: Option<String>,
+    /// A chunk is being written; other chunks, the commit and the abort wait for it
+    writing: bool,
+    touched_at: Instant,
+}
+
+/// Why the background task of an upload stopped
+struct UploadFailure {
+    error: String,
+    failure: Option<serde_json::Value>,
+}
+
+impl From<String> for UploadFailure {
+    fn from(error: String) -> Self {
+        UploadFailure {
+            error,
+            failure: None,
+        }
+    }
+}
+
+impl From<AppError> for UploadFailure {
+    fn from(error: AppError) -> Self {
+        error.to_string().into()
+    }
+}
+
+impl From<std::io::Error> for UploadFailure {
+    fn from(error: std::io::Error) -> Self {
+        AppError::from(error).into()
+    }
+}
+
+/// Frames carried from one batch to the next, and the byte order fixed by the first step
+#[derive(Default)]
+struct JobState {
+    endianness: Option<Endianness>,
+    /// Frames of the last step of the batch, which may go on in the next one
+    carried: Vec<CanMessage>,
+}
+
+/// Settings of a committed upload, resolved once before its first batch
+struct Job<'a> {
+    id: &'a str,
+    parser: &'static dyn FrameParser,
+    query: &'a IngestQuery,
+    tenant: Option<&'a str>,
+    vehicle_endianness: Option<Endianness>,
+    profile: &'static SignalProfile,
+}
+
+/// Upload sessions of large logs sent to `/ingest/uploads` in chunks
+///
+/// Chunks are appended to a file per session under `dir`; a committed upload is parsed,
+/// validated and stored by an import job, `BATCH_LINES` lines at a time for line-based
+/// formats so the whole log never sits in memory. Sessions live in memory: a restart
+/// forgets them and deletes their files. Sessions left untouched for `idle_timeout` are
+/// dropped, except while their upload is being processed.
+#[derive(Clone)]
+pub struct UploadRegistry {
+    dir: PathBuf,
+    idle_timeout: Duration,
+    sessions: Arc<Mutex<HashMap<String, UploadSession>>>,
+}
+
+impl UploadRegistry {
+    /// Registry writing under `dir`, created when missing and emptied of a previous run's files
+    pub fn new(dir: impl Into<PathBuf>, idle_timeout: Duration) -> std::io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "part")
+            {
+                std::fs::remove_file(path)?;
+            }
+        }
+        Ok(UploadRegistry {
+            dir,
+            idle_timeout,
+            sessions: Arc::default(),
+        })
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.part", id))
+    }
+
+    /// Session `id`, `404` when it belongs to another tenant
+    fn session<'a>(
+        sessions: &'a mut HashMap<String, UploadSession>,
+        id: &str,
+        tenant: Option<&str>,
+    ) -> Result<&'a mut UploadSession, AppError> {
+        sessions
+            .get_mut(id)
+            .filter(|session| tenant.is_none_or(|tenant| session.tenant.as_deref() == Some(tenant)))
+            .ok_or_else(|| AppError::not_found(format!("Upload '{}'", id)))
+    }
+
+    /// Session `id` while it accepts chunks
+    fn open_session<'a>(
+        sessions: &'a mut HashMap<String, UploadSession>,
+        id: &str,
+        tenant: Option<&str>,
+    ) -> Result<&'a mut UploadSession, AppError> {
+        let session = Self::session(sessions, id, tenant)?;
+        if session.progress.status != UploadStatus::Open {
+            return Err(AppError::conflict(format!("Upload '{}' is committed", id)));
+        }
+        if session.writing {
+            return Err(AppError::conflict(format!(
+                "A chunk of upload '{}' is still being written",
+                id
+            )));
+        }
+        Ok(session)
+    }
+
+    /// Start a session whose chunks `parser` will read
+    pub fn open(
+        &self,
+        parser: &'static dyn FrameParser,
+        query: IngestQuery,
+        tenant: Option<&str>,
+    ) -> Result<UploadProgress, AppError> {
+        let id = uuid::Uuid::new_v4().to_string();
+        std::fs::File::create(self.path(&id))?;
+        let progress = UploadProgress {
+            id: id.clone(),
+            format: parser.name(),
+            status: UploadStatus::Open,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            received_bytes: 0,
+            parsed_bytes: 0,
+            frames: 0,
+            rejected: 0,
+            steps: 0,
+            error: None,
+            failure: None,
+            job_id: None,
+        };
+        self.sessions.lock().unwrap().insert(
+            id,
+            UploadSession {
+                progress: progress.clone(),
+                parser,
+                query,
+                tenant: tenant.map(str::to_string),
+                writing: false,
+                touched_at: Instant::now(),
+            },
+        );
+        Ok(progress)
+    }
+
+    pub fn get(&self, id: &str, tenant: Option<&str>) -> Result<UploadProgress, AppError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        Ok(Self::session(&mut sessions, id, tenant)?.progress.clone())
+    }
+
+    /// Sessions of `tenant`, every session when `None`, oldest first
+    pub fn list(&self, tenant: Option<&str>) -> Vec<UploadProgress> {
+        let sessions = self.sessions.lock().unwrap();
+        let mut uploads: Vec<UploadProgress> = sessions
+            .values()
+            .filter(|session| tenant.is_none_or(|tenant| session.tenant.as_deref() == Some(tenant)))
+            .map(|session| session.progress.clone())
+            .collect();
+        uploads.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        uploads
+    }
+
+    /// Write a chunk at `offset`, cutting the upload back to it first
+    ///
+    /// An offset past the bytes received returns `409`. Bytes of a chunk cut short by the
+    /// client stay written, so the next chunk can resume at `received_bytes`.
+    pub async fn append(
+        &self,
+        id: &str,
+        tenant: Option<&str>,
+        offset: u64,
+        payload: &mut web::Payload,
+    ) -> Result<UploadProgress, AppError> {
+        {
+            let mut sessions = self.sessions.lock().unwrap();
+            let session = Self::open_session(&mut sessions, id, tenant)?;
+            if offset > session.progress.received_bytes {
+                return Err(AppError::conflict(format!(
+                    "Upload '{}' has {} bytes, chunks cannot start past them",
+                    id, session.progress.received_bytes
+                )));
+            }
+            session.writing = true;
+        }
+
+        let path = self.path(id);
+        let written = Self::write(&path, offset, payload).await;
+        let received = tokio::fs::metadata(&path)
+            .await
+            .map(|metadata| metadata.len());
+
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = Self::session(&mut sessions, id, None)?;
+        session.writing = false;
+        session.touched_at = Instant::now();
+        session.progress.received_bytes = received.unwrap_or(offset);
+        written?;
+        Ok(session.progress.clone())
+    }
+
+    async fn write(path: &Path, offset: u64, payload: &mut web::Payload) -> Result<(), AppError> {
+        let mut file = tokio::fs::OpenOptions::new().write(true).open(path).await?;
+        file.set_len(offset).await?;
+        file.seek(SeekFrom::Start(offset)).await?;
+
+        let mut result = Ok(());
+        while let Some(chunk) = payload.next().await {
+            match chunk {
+                Ok(chunk) => file.write_all(&chunk).await?,
+                Err(e) => {
+                    result = Err(AppError::bad_request(format!("Chunk cut short, {}", e)));
+                    break;
+                }
+            }
+        }
+        file.flush().await?;
+        result
+    }
+
+    /// Close the session to chunks and store its steps in an import job
+    pub async fn commit(
+        &self,
+        id: &str,
+        tenant: Option<&str>,
+        transport: &StepTransport,
+        validator: &FrameValidator,
+        jobs: &JobQueue,
+    ) -> Result<UploadProgress, AppError> {
+        {
+            let mut sessions = self.sessions.lock().unwrap();
+            let session = Self::open_session(&mut sessions, id, tenant)?;
+            if session.progress.received_bytes == 0 {
+                return Err(AppError::bad_request(format!("Upload '{}' is empty", id)));
+            }
+            session.progress.status = UploadStatus::Processing;
+            session.touched_at = Instant::now();
+        }
+
+        let registry = self.clone();
+        let upload_id = id.to_string();
+        let transport = transport.clone();
+        let validator = validator.clone();
+        let submitted = jobs
+            .submit(JobKind::Import, tenant, move |job| async move {
+                let id = upload_id;
+                let result = registry.process(&id, &transport, &validator, &job).await;
+                let _ = tokio::fs::remove_file(registry.path(&id)).await;
+                let mut error = None;
+                registry.update(&id, |progress| match result {
+                    Ok(()) => {
+                        progress.status = UploadStatus::Done;
+                        progress.parsed_bytes = progress.received_bytes;
+                    }
+                    Err(failure) => {
+                        println!("❌ Upload '{}' failed: {}", id, failure.error);
+                        progress.status = UploadStatus::Failed;
+                        error = Some(failure.error.clone());
+                        progress.error = Some(failure.error);
+                        progress.failure = failure.failure;
+                    }
+                });
+                let progress = registry.get(&id, None)?;
+                match error {
+                    Some(error) => Err(AppError::bad_request(error)),
+                    None => Ok(serde_json::to_value(progress)?),
+                }
+            })
+            .await;
+
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = Self::session(&mut sessions, id, None)?;
+        match submitted {
+            Ok(job) => session.progress.job_id = Some(job.id),
+            Err(e) => {
+                session.progress.status = UploadStatus::Open;
+                return Err(e);
+            }
+        }
+        Ok(session.progress.clone())
+    }
+
+    /// Forget a session and its chunks; a session being processed cannot be aborted
+    pub fn abort(&self, id: &str, tenant: Option<&str>) -> Result<(), AppError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = Self::session(&mut sessions, id, tenant)?;
+        if session.progress.status == UploadStatus::Processing || session.writing {
+            return Err(AppError::conflict(format!(
+                "Upload '{}' is being written or processed",
+                id
+            )));
+        }
+        sessions.remove(id);
+        match std::fs::remove_file(self.path(id)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn update(&self, id: &str, change: impl FnOnce(&mut UploadProgress)) {
+        if let Some(session) = self.sessions.lock().unwrap().get_mut(id) {
+            change(&mut session.progress);
+            session.touched_at = Instant::now();
+        }
+    }
+
+    /// Remove the sessions untouched for longer than the idle timeout, with their files
+    pub fn expire(&self) -> Vec<String> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let expired: Vec<String> = sessions
+            .iter()
+            .filter(|(_, session)| {
+                session.progress.status != UploadStatus::Processing
+                    && !session.writing
+                    && session.touched_at.elapsed() >= self.idle_timeout
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &expired {
+            sessions.remove(id);
+            let _ = std::fs::remove_file(self.path(id));
+        }
+        expired
+    }
+
+    /// Expire idle sessions, until the process exits
+    pub fn spawn(&self) {
+        let registry = self.clone();
+        let mut check = tokio::time::interval((self.idle_timeout / 4).max(Duration::from_secs(1)));
+        check.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        tokio::spawn(async move {
+            loop {
+                check.tick().await;
+                for id in registry.expire() {
+                    println!("🗑️ Upload '{}' expired", id);
+                }
+            }
+        });
+    }
+
+    /// Parse the committed upload `id` and store its steps, a batch at a time
+    async fn process(
+        &self,
+        id: &str,
+        transport: &StepTransport,
+        validator: &FrameValidator,
+        progress: &JobProgress,
+    ) -> Result<(), UploadFailure> {
+        let (parser, query, tenant) = {
+            let sessions = self.sessions.lock().unwrap();
+            let session = sessions
+                .get(id)
+                .ok_or_else(|| format!("Upload '{}' is gone", id))?;
+            (
+                session.parser,
+                session.query.clone(),
+                session.tenant.clone(),
+            )
+        };
+        let vehicle = vehicle_controller::for_ingestion(query.vehicle_id.as_deref()).await?;
+        let job = Job {
+            id,
+            parser,
+            query: &query,
+            tenant: tenant.as_deref(),
+            vehicle_endianness: vehicle.as_ref().map(|vehicle| vehicle.endianness),
+            profile: controller::profile(vehicle.as_ref())?,
+        };
+        let mut state = JobState::default();
+        let path = self.path(id);
+
+        let Some(header_lines) = parser.header_lines() else {
+            let body = String::from_utf8(tokio::fs::read(&path).await?)
+                .map_err(|_| "Upload must be UTF-8 text".to_string())?;
+            self.store_batch(
+                &job,
+                &body,
+                |line| line,
+                true,
+                &mut state,
+                transport,
+                validator,
+            )
+            .await?;
+            return self.finish(id);
+        };
+
+        let mut lines = BufReader::new(tokio::fs::File::open(&path).await?).lines();
+        let mut header = String::new();
+        let mut header_at = Vec::with_capacity(header_lines);
+        let mut batch = String::new();
+        let mut batch_lines = 0;
+        let mut first_line = 1;
+        let mut line_number = 0;
+        let mut parsed_bytes = 0;
+        while let Some(line) = lines
+            .next_line()
+            .await
+            .map_err(|_| format!("Upload must be UTF-8 text, line {}", line_number + 1))?
+        {
+            line_number += 1;
+            parsed_bytes += line.len() as u64 + 1;
+            if header_at.len() < header_lines {
+                if !line.trim().is_empty() {
+                    header.push_str(&line);
+                    header.push('\n');
+                    header_at.push(line_number);
+                }
+                continue;
+            }
+            if batch_lines == 0 {
+                first_line = line_number;
+            }
+            batch.push_str(&line);
+            batch.push('\n');
+            batch_lines += 1;
+
+            if batch_lines == BATCH_LINES {
+                let text = format!("{}{}", header, batch);
+                let line_of = |line| upload_line(&header_at, first_line, line);
+                self.store_batch(
+                    &job, &text, line_of, false, &mut state, transport, validator,
+                )
+                .await?;
+                self.update(id, |progress| progress.parsed_bytes = parsed_bytes);
+                if let Ok(upload) = self.get(id, None) {
+                    progress.report(upload).await;
+                }
+                batch.clear();
+                batch_lines = 0;
+            }
+        }
+
+        let text = format!("{}{}", header, batch);
+        let line_of = |line| upload_line(&header_at, first_line, line);
+        self.store_batch(&job, &text, line_of, true, &mut state, transport, validator)
+            .await?;
+        self.finish(id)
+    }
+
+    /// Fail an upload that held no frame at all, as `POST /ingest` does
+    fn finish(&self, id: &str) -> Result<(), UploadFailure> {
+        let frames = self
+            .sessions
+            .lock()
+            .unwrap()
+            .get(id)
+            .map_or(0, |session| session.progress.frames);
+        if frames == 0 {
+            return Err("No CAN frames in upload".to_string().into());
+        }
+        Ok(())
+    }
+
+    /// Parse one batch, then validate, decode and store the steps it completes
+    ///
+    /// `line_of` maps a line of `text` back to the line of the upload; the last step of a
+    /// batch is carried to the next one unless `last`.
+    #[allow(clippy::too_many_arguments)]
+    async fn store_batch(
+        &self,
+        job: &Job<'_>,
+        text: &str,
+        line_of: impl Fn(usize) -> usize,
+        last: bool,
+        state: &mut JobState,
+        transport: &StepTransport,
+        validator: &FrameValidator,
+    ) -> Result<(), UploadFailure> {
+        let frames = job.parser.parse(text, &SystemClock).map_err(|e| {
+            format!(
+                "Invalid {} upload, line {}: {}",
+                job.parser.name(),
+                line_of(e.line),
+                e.message
+            )
+        })?;
+        let frame_count = frames.len();
+        state.carried.extend(frames);
+        let mut steps = controller::split_steps(std::mem::take(&mut state.carried));
+        if !last {
+            state.carried = steps.pop().unwrap_or_default();
+        }
+        self.update(job.id, |progress| progress.frames += frame_count);
+        if steps.is_empty() {
+            return Ok(());
+        }
+
+        let endianness = *state.endianness.get_or_insert_with(|| {
+            job.query
+                .endianness
+                .or(job.vehicle_endianness)
+                .or_else(|| {
+                    steps
+                        .iter()
+                        .find_map(|frames| DrivingStep::detect_endianness(frames))
+                })
+                .unwrap_or_else(|| {
+                    Endianness::from_is_big_endian(DrivingStep::get_endianness_from_env())
+                })
+        });
+        let step_name = job.query.step_name.as_deref().unwrap_or("Ingested");
+        let context = ValidationContext {
+            profile: job.profile,
+            endianness,
+            vehicle_id: job.query.vehicle_id.as_deref(),
+            step_name,
+        };
+        let stored_before = self.get(job.id, None).map_or(0, |progress| progress.steps);
+        let (steps, rejected) =
+            match validation::controller::screen(validator, steps, &context).await? {
+                Ok(screened) => screened,
+                Err(mut failure) => {
+                    failure.error =
+                        format!("After {} stored step(s): {}", stored_before, failure.error);
+                    return Err(UploadFailure {
+                        error: failure.error.clone(),
+                        failure: serde_json::to_value(&failure).ok(),
+                    });
+                }
+            };
+        if let Err(mut failure) = controller::verify(&steps, endianness, job.profile) {
+            failure.error = format!("After {} stored step(s): {}", stored_before, failure.error);
+            return Err(UploadFailure {
+                error: failure.error.clone(),
+                failure: serde_json::to_value(&failure).ok(),
+            });
+        }
+
+        let stored = controller::publish(
+            steps,
+            endianness,
+            job.query.vehicle_id.as_deref(),
+            job.tenant,
+            step_name,
+            transport,
+        )
+        .await?;
+        self.update(job.id, |progress| {
+            progress.rejected += rejected;
+            progress.steps += stored.len();
+        });
+        Ok(())
+    }
+}
+
+/// Line of the upload holding `line` of a batch, whose header lines were found at
+/// `header_at` and whose other lines start at `first_line`
+fn upload_line(header_at: &[usize], first_line: usize, line: usize) -> usize {
+    match header_at.get(line.wrapping_sub(1)) {
+        Some(header_line) => *header_line,
+        None => (first_line + line).saturating_sub(header_at.len() + 1),
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use crate::common::error::AppError;
+use crate::core::can::{CanId, CanMessage, Endianness};
+use crate::core::signals::SignalProfile;
+use crate::features::driving_step::service as step_service;
+use crate::features::integrity::model::{
+    BrokenGroup, FailureReason, IntegrityReport, Repair, StoredGroup,
+};
+use crate::features::integrity::service;
+
+/// Scan every stored frame group for steps that cannot be reconstructed, repairing them
+/// as `repair` says
+pub async fn verify(repair: Repair) -> Result<IntegrityReport, AppError> {
+    let mut report = IntegrityReport {
+        started_at: chrono::Utc::now().to_rfc3339(),
+        repair,
+        groups: 0,
+        frames: 0,
+        failures: Default::default(),
+        repaired: 0,
+        broken: Vec::new(),
+    };
+    let mut profiles: HashMap<Option<String>, Result<&'static SignalProfile, String>> =
+        HashMap::new();
+
+    for group in service::get_groups().await? {
+        report.groups += 1;
+        report.frames += group.seqs.len() as u64;
+
+        if !profiles.contains_key(&group.vehicle_id) {
+            let profile = step_service::signal_profile(group.vehicle_id.as_deref())
+                .await
+                .map_err(|e| message(&e));
+            profiles.insert(group.vehicle_id.clone(), profile);
+        }
+        let Err((reason, error)) = check(&group, &profiles[&group.vehicle_id]) else {
+            continue;
+        };
+
+        let batch_id = match repair {
+            Repair::None => None,
+            Repair::Quarantine => {
+                let batch_id = uuid::Uuid::new_v4().to_string();
+                service::quarantine_frames(&group.seqs, &batch_id, &group.step_id, &error).await?;
+                Some(batch_id)
+            }
+            Repair::Delete => {
+                service::delete_frames(&group.seqs).await?;
+                None
+            }
+        };
+        if repair != Repair::None {
+            report.repaired += 1;
+        }
+        *report.failures.entry(reason).or_default() += 1;
+        report.broken.push(BrokenGroup {
+            step_id: group.step_id,
+            reason,
+            error,
+            frames: group.seqs.len() as u64,
+            batch_id,
+        });
+    }
+    Ok(report)
+}
+
+/// Why `group` does not reconstruct into a step, checked in the order the readers fail
+fn check(
+    group: &StoredGroup,
+    profile: &Result<&'static SignalProfile, String>,
+) -> Result<(), (FailureReason, String)> {
+    let mut frames: Vec<CanMessage> = Vec::with_capacity(group.frames.len());
+    let mut endians: Vec<Endianness> = Vec::with_capacity(group.frames.len());
+    for frame in &group.frames {
+        let (frame, endian) = frame
+            .as_ref()
+            .map_err(|e| (FailureReason::CorruptFrame, message(e)))?;
+        frames.push(frame.clone());
+        endians.push(*endian);
+    }
+    let endian = step_service::group_endianness(&endians)
+        .map_err(|error| (FailureReason::MixedEndianness, error))?;
+    let profile = profile
+        .as_ref()
+        .map_err(|error| (FailureReason::Undecodable, error.clone()))?;
+
+    let missing = profile.missing(&frames);
+    if !missing.is_empty() {
+        let ids: Vec<String> = missing
+            .into_iter()
+            .map(|id| CanId::from(id).to_string())
+            .collect();
+        return Err((
+            FailureReason::Incomplete,
+            format!(
+                "Missing CAN frames required by profile '{}': {}",
+                profile.name,
+                ids.join(", ")
+            ),
+        ));
+    }
+    profile
+        .decode_step(&frames, String::new(), endian.is_big_endian())
+        .map(|_| ())
+        .map_err(|error| (FailureReason::Undecodable, error))
+}
+
+/// Error text without the status prefix of server errors
+fn message(error: &AppError) -> String {
+    match error {
+        AppError::InternalServerError { message } => message.clone(),
+        other => other.to_string(),
+    }
+}
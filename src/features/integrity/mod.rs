@@ -0,0 +1,20 @@
+pub mod controller;
+pub mod model;
+pub mod service;
+
+use actix_web::{post, web, HttpResponse, Result};
+
+use crate::common::error::AppError;
+
+use model::VerifyQuery;
+pub use model::{IntegrityReport, Repair};
+
+/// Scan stored frame groups for steps that no longer reconstruct (`?repair=quarantine|delete`)
+#[post("/admin/verify")]
+pub async fn verify(query: web::Query<VerifyQuery>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::verify(query.repair).await?))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(verify);
+}
@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+
+use crate::common::error::AppError;
+use crate::core::can::{CanMessage, Endianness};
+
+/// What an integrity check does with the broken frame groups it finds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Repair {
+    /// Only report them
+    #[default]
+    None,
+    /// Move them to `rejected_frames` under the `integrity` rule, to be reviewed as batches
+    Quarantine,
+    /// Delete their frames
+    Delete,
+}
+
+impl std::str::FromStr for Repair {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "none" => Ok(Repair::None),
+            "quarantine" => Ok(Repair::Quarantine),
+            "delete" => Ok(Repair::Delete),
+            other => Err(format!("Unknown repair mode '{}'", other)),
+        }
+    }
+}
+
+/// Query parameters accepted by `POST /admin/verify`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct VerifyQuery {
+    #[serde(default)]
+    pub repair: Repair,
+}
+
+/// Why a stored frame group does not reconstruct into a step
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureReason {
+    /// A row holds an invalid CAN ID, or a payload that does not match its DLC
+    #[display("corrupt_frame")]
+    CorruptFrame,
+    /// Frames of the group were stored with different byte orders
+    #[display("mixed_endianness")]
+    MixedEndianness,
+    /// A frame required by the vehicle's CAN profile is missing
+    #[display("incomplete")]
+    Incomplete,
+    /// The frames are complete but the decoder refuses them
+    #[display("undecodable")]
+    Undecodable,
+}
+
+/// Rows of one stored step, as read before any decoding
+#[derive(Debug)]
+pub struct StoredGroup {
+    pub step_id: String,
+    pub vehicle_id: Option<String>,
+    /// Sequence numbers of the rows, in storage order
+    pub seqs: Vec<i64>,
+    pub frames: Vec<Result<(CanMessage, Endianness), AppError>>,
+}
+
+/// One broken frame group found by an integrity check
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokenGroup {
+    pub step_id: String,
+    pub reason: FailureReason,
+    pub error: String,
+    pub frames: u64,
+    /// Quarantine batch now holding the frames, see `GET /rejected-frames/batches/{batch_id}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+}
+
+/// Outcome of an integrity check, returned by `POST /admin/verify`
+#[derive(Debug, Clone, Serialize)]
+pub struct IntegrityReport {
+    /// RFC3339 time the check started
+    pub started_at: String,
+    pub repair: Repair,
+    /// Frame groups scanned
+    pub groups: u64,
+    /// Frames scanned
+    pub frames: u64,
+    /// Broken groups per failure reason
+    pub failures: BTreeMap<FailureReason, u64>,
+    /// Broken groups quarantined or deleted
+    pub repaired: u64,
+    pub broken: Vec<BrokenGroup>,
+}
@@ -0,0 +1,90 @@
+use sqlx::Row;
+use std::collections::HashMap;
+
+use crate::common::error::AppError;
+use crate::config::frame_store::frame_store;
+use crate::features::driving_step::service as step_service;
+use crate::features::integrity::model::StoredGroup;
+
+/// Rule recorded on the frames of quarantined broken groups
+pub const INTEGRITY_RULE: &str = "integrity";
+
+/// Every stored frame group, in the order steps were first stored
+pub async fn get_groups() -> Result<Vec<StoredGroup>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(
+        "SELECT id, dlc, data, fd, brs, timestamp, endian, seq, vehicle_id, step_id
+         FROM can_messages ORDER BY seq ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut groups: Vec<StoredGroup> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for row in rows {
+        let step_id: String = row.try_get("step_id")?;
+        let vehicle_id: Option<String> = row.try_get("vehicle_id")?;
+        let position = *index.entry(step_id.clone()).or_insert_with(|| {
+            groups.push(StoredGroup {
+                step_id,
+                vehicle_id,
+                seqs: Vec::new(),
+                frames: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        let group = &mut groups[position];
+        group.seqs.push(row.try_get("seq")?);
+        group.frames.push(
+            step_service::can_message_from_row(&row)
+                .and_then(|frame| Ok((frame, step_service::endianness_from_row(&row)?))),
+        );
+    }
+    Ok(groups)
+}
+
+/// Move the rows `seqs` to `rejected_frames` as the batch `batch_id`, in one transaction
+///
+/// Rows are copied as stored, so frames that no longer parse are quarantined too.
+pub async fn quarantine_frames(
+    seqs: &[i64],
+    batch_id: &str,
+    step_name: &str,
+    reason: &str,
+) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+    let rejected_at = chrono::Utc::now().to_rfc3339();
+
+    let mut transaction = pool.begin().await?;
+    for seq in seqs {
+        sqlx::query(
+            "INSERT INTO rejected_frames (id, batch_id, can_id, dlc, data, fd, brs, timestamp,
+                 endian, vehicle_id, step_name, rule, reason, rejected_at)
+             SELECT $1, $2, id, dlc, data, fd, brs, timestamp, endian, vehicle_id, $3, $4, $5, $6
+             FROM can_messages WHERE seq = $7",
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(batch_id)
+        .bind(step_name)
+        .bind(INTEGRITY_RULE)
+        .bind(reason)
+        .bind(&rejected_at)
+        .bind(seq)
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query("DELETE FROM can_messages WHERE seq = $1")
+            .bind(seq)
+            .execute(&mut *transaction)
+            .await?;
+    }
+    transaction.commit().await?;
+    Ok(())
+}
+
+/// Delete the rows `seqs`, in one transaction
+pub async fn delete_frames(seqs: &[i64]) -> Result<(), AppError> {
+    let seqs: Vec<u64> = seqs.iter().map(|seq| *seq as u64).collect();
+    frame_store().prune(&seqs).await?;
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use crate::common::error::AppError;
+use crate::features::job::model::{Job, JobQuery};
+use crate::features::job::service;
+
+pub const DEFAULT_LIMIT: u32 = 100;
+
+pub async fn get(id: &str, tenant: Option<&str>) -> Result<Job, AppError> {
+    service::get_job(id, tenant)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Job '{}'", id)))
+}
+
+pub async fn list(query: &JobQuery, tenant: Option<&str>) -> Result<Vec<Job>, AppError> {
+    service::get_jobs(
+        tenant,
+        query.kind,
+        query.status,
+        query.limit.unwrap_or(DEFAULT_LIMIT),
+    )
+    .await
+}
@@ -0,0 +1,32 @@
+pub mod controller;
+pub mod model;
+pub mod queue;
+pub mod service;
+
+use actix_web::{get, web, HttpResponse, Result};
+
+use crate::common::error::AppError;
+use crate::features::tenant::TenantScope;
+
+use model::JobQuery;
+pub use model::{Job, JobKind, JobStatus};
+pub use queue::{JobProgress, JobQueue};
+
+/// Latest jobs, filtered by kind and status
+#[get("/jobs")]
+pub async fn list(
+    query: web::Query<JobQuery>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::list(&query, scope.name()).await?))
+}
+
+/// Status, progress and result of a job started by an import, replay, recode or compaction
+#[get("/jobs/{id}")]
+pub async fn get(id: web::Path<String>, scope: TenantScope) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::get(&id, scope.name()).await?))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list).service(get);
+}
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Long operation run by a `JobQueue` worker instead of the request handler
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Committed upload of `/ingest/uploads` parsed and stored
+    Import,
+    /// Manual run of a scenario published step by step
+    Replay,
+    /// Stored frames rewritten in another byte order
+    Recode,
+    /// Old raw frames folded into signal aggregates
+    Compaction,
+    /// Steps of an export window written to its destination
+    Export,
+}
+
+impl JobKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobKind::Import => "import",
+            JobKind::Replay => "replay",
+            JobKind::Recode => "recode",
+            JobKind::Compaction => "compaction",
+            JobKind::Export => "export",
+        }
+    }
+}
+
+impl std::str::FromStr for JobKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "import" => Ok(JobKind::Import),
+            "replay" => Ok(JobKind::Replay),
+            "recode" => Ok(JobKind::Recode),
+            "compaction" => Ok(JobKind::Compaction),
+            "export" => Ok(JobKind::Export),
+            other => Err(format!("Unknown job kind '{}'", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Waiting for a free worker
+    Queued,
+    Running,
+    Succeeded,
+    /// The operation returned an error, or the server stopped while it ran
+    Failed,
+}
+
+impl JobStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Failed => "failed",
+        }
+    }
+}
+
+impl std::str::FromStr for JobStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "queued" => Ok(JobStatus::Queued),
+            "running" => Ok(JobStatus::Running),
+            "succeeded" => Ok(JobStatus::Succeeded),
+            "failed" => Ok(JobStatus::Failed),
+            other => Err(format!("Unknown job status '{}'", other)),
+        }
+    }
+}
+
+/// One long operation, as answered by `GET /jobs/{id}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    pub id: String,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    /// Tenant that started the job, `None` for the administrator
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    /// Latest progress reported by the operation, its shape depends on the kind
+    pub progress: Option<Value>,
+    /// Report of a succeeded job, its shape depends on the kind
+    pub result: Option<Value>,
+    /// Why the job failed
+    pub error: Option<String>,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+}
+
+/// Query parameters accepted by `GET /jobs`
+#[derive(Debug, Deserialize)]
+pub struct JobQuery {
+    pub kind: Option<JobKind>,
+    pub status: Option<JobStatus>,
+    /// Most recent jobs returned, 100 by default
+    pub limit: Option<u32>,
+}
@@ -0,0 +1,153 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::common::error::AppError;
+use crate::features::job::model::{Job, JobKind, JobStatus};
+use crate::features::job::service;
+
+type Task = Pin<Box<dyn Future<Output = Result<Value, AppError>> + Send>>;
+
+/// Job waiting for a worker, with the operation it runs
+struct Pending {
+    job: Job,
+    start: Box<dyn FnOnce(JobProgress) -> Task + Send>,
+}
+
+/// Handle an operation reports its progress through, read back by `GET /jobs/{id}`
+#[derive(Debug, Clone)]
+pub struct JobProgress {
+    id: String,
+}
+
+impl JobProgress {
+    pub fn job_id(&self) -> &str {
+        &self.id
+    }
+
+    /// Replace the progress of the job; a failed write is logged, the job goes on
+    pub async fn report(&self, progress: impl Serialize) {
+        let saved = match serde_json::to_value(progress) {
+            Ok(progress) => service::save_progress(&self.id, &progress).await,
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = saved {
+            println!("❌ Failed to record the progress of job {}: {}", self.id, e);
+        }
+    }
+}
+
+/// Imports, replays, recodes, compactions and exports run by background workers
+///
+/// `submit` records a queued job in the `jobs` table and returns it at once; one of
+/// `workers` tasks then runs it and records its progress and outcome. Jobs do not survive
+/// a restart: those a previous process left queued or running are marked failed on load.
+#[derive(Clone)]
+pub struct JobQueue {
+    sender: mpsc::UnboundedSender<Pending>,
+    receiver: Arc<Mutex<mpsc::UnboundedReceiver<Pending>>>,
+    workers: usize,
+}
+
+impl JobQueue {
+    pub async fn load(workers: usize) -> Result<Self, AppError> {
+        let interrupted = service::fail_interrupted().await?;
+        if interrupted > 0 {
+            println!(
+                "⚠️ {} job(s) were interrupted by the restart and marked failed",
+                interrupted
+            );
+        }
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Ok(JobQueue {
+            sender,
+            receiver: Arc::new(Mutex::new(receiver)),
+            workers: workers.max(1),
+        })
+    }
+
+    /// Record a queued job of `kind` and hand `operation` to the next free worker
+    pub async fn submit<F, Fut>(
+        &self,
+        kind: JobKind,
+        tenant: Option<&str>,
+        operation: F,
+    ) -> Result<Job, AppError>
+    where
+        F: FnOnce(JobProgress) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Value, AppError>> + Send + 'static,
+    {
+        let job = Job {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            status: JobStatus::Queued,
+            tenant: tenant.map(str::to_string),
+            progress: None,
+            result: None,
+            error: None,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            started_at: None,
+            finished_at: None,
+        };
+        service::save_job(&job).await?;
+        self.sender
+            .send(Pending {
+                job: job.clone(),
+                start: Box::new(move |progress| Box::pin(operation(progress))),
+            })
+            .map_err(|_| AppError::internal_server_error("Job workers are stopped"))?;
+        Ok(job)
+    }
+
+    /// Start the workers, running queued jobs until the process exits
+    pub fn spawn(&self) {
+        for _ in 0..self.workers {
+            let receiver = self.receiver.clone();
+            tokio::spawn(async move {
+                loop {
+                    let Some(pending) = receiver.lock().await.recv().await else {
+                        return;
+                    };
+                    Self::run(pending).await;
+                }
+            });
+        }
+    }
+
+    async fn run(Pending { mut job, start }: Pending) {
+        job.status = JobStatus::Running;
+        job.started_at = Some(chrono::Utc::now().to_rfc3339());
+        if let Err(e) = service::save_job(&job).await {
+            println!("❌ Failed to record job {}: {}", job.id, e);
+        }
+
+        // A panicking operation fails its job instead of taking the worker down
+        let progress = JobProgress { id: job.id.clone() };
+        let outcome = tokio::spawn(start(progress))
+            .await
+            .unwrap_or_else(|e| Err(AppError::internal_server_error(e.to_string())));
+
+        if let Ok(Some(stored)) = service::get_job(&job.id, None).await {
+            job.progress = stored.progress;
+        }
+        match outcome {
+            Ok(result) => {
+                job.status = JobStatus::Succeeded;
+                job.result = Some(result);
+            }
+            Err(e) => {
+                println!("❌ Job {} ({}) failed: {}", job.id, job.kind.as_str(), e);
+                job.status = JobStatus::Failed;
+                job.error = Some(e.to_string());
+            }
+        }
+        job.finished_at = Some(chrono::Utc::now().to_rfc3339());
+        if let Err(e) = service::save_job(&job).await {
+            println!("❌ Failed to record job {}: {}", job.id, e);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use serde_json::Value;
+use sqlx::any::AnyRow;
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::features::job::model::{Job, JobKind, JobStatus};
+
+fn job_from_row(row: &AnyRow) -> Result<Job, AppError> {
+    let kind: String = row.try_get("kind")?;
+    let status: String = row.try_get("status")?;
+    let progress: Option<String> = row.try_get("progress")?;
+    let result: Option<String> = row.try_get("result")?;
+
+    Ok(Job {
+        id: row.try_get("id")?,
+        kind: kind.parse().map_err(AppError::internal_server_error)?,
+        status: status.parse().map_err(AppError::internal_server_error)?,
+        tenant: row.try_get("tenant")?,
+        progress: progress
+            .map(|progress| serde_json::from_str(&progress))
+            .transpose()?,
+        result: result
+            .map(|result| serde_json::from_str(&result))
+            .transpose()?,
+        error: row.try_get("error")?,
+        created_at: row.try_get("created_at")?,
+        started_at: row.try_get("started_at")?,
+        finished_at: row.try_get("finished_at")?,
+    })
+}
+
+/// Insert or update a job record
+pub async fn save_job(job: &Job) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(
+        "INSERT INTO jobs
+         (id, kind, status, tenant, progress, result, error, created_at, started_at, finished_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+         ON CONFLICT (id) DO UPDATE SET
+             kind = excluded.kind, status = excluded.status, tenant = excluded.tenant,
+             progress = excluded.progress, result = excluded.result,
+             error = excluded.error, created_at = excluded.created_at,
+             started_at = excluded.started_at, finished_at = excluded.finished_at",
+    )
+    .bind(&job.id)
+    .bind(job.kind.as_str())
+    .bind(job.status.as_str())
+    .bind(&job.tenant)
+    .bind(job.progress.as_ref().map(Value::to_string))
+    .bind(job.result.as_ref().map(Value::to_string))
+    .bind(&job.error)
+    .bind(&job.created_at)
+    .bind(&job.started_at)
+    .bind(&job.finished_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Replace the progress of a running job
+pub async fn save_progress(id: &str, progress: &Value) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query("UPDATE jobs SET progress = $1 WHERE id = $2 AND status = 'running'")
+        .bind(progress.to_string())
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Job `id`, not matched when it was started by another tenant than `tenant`
+pub async fn get_job(id: &str, tenant: Option<&str>) -> Result<Option<Job>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let row = sqlx::query(
+        "SELECT id, kind, status, tenant, progress, result, error, created_at, started_at,
+                finished_at
+         FROM jobs WHERE id = $1 AND ($2 IS NULL OR tenant = $2)",
+    )
+    .bind(id)
+    .bind(tenant)
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(job_from_row).transpose()
+}
+
+/// Jobs of `tenant`, every job when `None`, most recent first
+pub async fn get_jobs(
+    tenant: Option<&str>,
+    kind: Option<JobKind>,
+    status: Option<JobStatus>,
+    limit: u32,
+) -> Result<Vec<Job>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(
+        "SELECT id, kind, status, tenant, progress, result, error, created_at, started_at,
+                finished_at
+         FROM jobs
+         WHERE ($1 IS NULL OR tenant = $1) AND ($2 IS NULL OR kind = $2)
+           AND ($3 IS NULL OR status = $3)
+         ORDER BY created_at DESC LIMIT $4",
+    )
+    .bind(tenant)
+    .bind(kind.map(JobKind::as_str))
+    .bind(status.map(JobStatus::as_str))
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(job_from_row).collect()
+}
+
+/// Fail the jobs a previous process left queued or running, returning how many there were
+pub async fn fail_interrupted() -> Result<u64, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let result = sqlx::query(
+        "UPDATE jobs SET status = 'failed', error = 'Interrupted by a server restart',
+                finished_at = $1
+         WHERE status IN ('queued', 'running')",
+    )
+    .bind(chrono::Utc::now().to_rfc3339())
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
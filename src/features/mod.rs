@@ -1 +1,29 @@
+pub mod admin;
+pub mod annotation;
+pub mod anomaly;
+pub mod archive;
+pub mod command;
+pub mod dashboard;
+pub mod device;
 pub mod driving_step;
+pub mod event;
+pub mod export;
+pub mod geofence;
+pub mod health;
+pub mod history;
+pub mod ingest;
+pub mod integrity;
+pub mod job;
+pub mod rule;
+pub mod scenario;
+pub mod snapshot;
+pub mod subscription;
+pub mod summary;
+pub mod system;
+pub mod tag;
+pub mod tenant;
+pub mod trash;
+pub mod trip;
+pub mod validation;
+pub mod vehicle;
+pub mod webhook;
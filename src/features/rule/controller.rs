@@ -0,0 +1,37 @@
+use crate::common::error::AppError;
+use crate::features::event::model::Severity;
+use crate::features::rule::engine::RuleEngine;
+use crate::features::rule::model::{Rule, RuleRequest};
+use crate::features::rule::service;
+
+/// Compile the rule first so invalid expressions are rejected before being stored
+pub async fn create(engine: &RuleEngine, request: RuleRequest) -> Result<Rule, AppError> {
+    if request.name.trim().is_empty() {
+        return Err(AppError::bad_request("Rule name must not be empty"));
+    }
+
+    let rule = Rule {
+        name: request.name,
+        expression: request.expression,
+        message: request.message,
+        severity: request.severity.unwrap_or(Severity::Warning),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    crate::features::rule::dsl::parse(&rule.expression)
+        .map_err(|e| AppError::bad_request(format!("Invalid rule expression: {}", e)))?;
+
+    service::store_rule(&rule).await?;
+    engine
+        .register(rule.clone())
+        .map_err(|e| AppError::bad_request(e.to_string()))?;
+
+    Ok(rule)
+}
+
+pub async fn delete(engine: &RuleEngine, name: &str) -> Result<(), AppError> {
+    if !service::delete_rule(name).await? {
+        return Err(AppError::not_found(format!("Rule '{}'", name)));
+    }
+    engine.remove(name);
+    Ok(())
+}
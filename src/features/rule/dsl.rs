@@ -0,0 +1,383 @@
+//! Boolean expression language evaluated against reconstructed DrivingSteps
+//!
+//! ```text
+//! speed.vehicle_speed > 120 && !speed.cruise_control
+//! coolant_temp >= 110 || (adas.lead_distance < 10 && vehicle_speed > 50)
+//! ```
+//!
+//! Operands are numbers, `true`/`false`, double-quoted strings, dotted DrivingStep paths
+//! (`speed.wheel_speeds.0` indexes arrays) or signal names from `core::signals`.
+//! Operators by increasing precedence: `||`, `&&`, comparisons, `+ -`, `* /`, unary `! -`.
+//! Fields of an absent optional group evaluate to null, and any comparison with null is false.
+
+use derive_more::Display;
+use serde_json::Value;
+
+use crate::core::signals;
+
+#[derive(Debug, Clone, PartialEq, Display)]
+#[display("{} at position {}", message, position)]
+pub struct ParseError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Bool(bool),
+    Str(String),
+    /// JSON pointer into the serialized DrivingStep
+    Field(String),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+const OPERATORS: [&str; 15] = [
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "(", ")",
+];
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        let c = chars[pos];
+        if c.is_whitespace() {
+            pos += 1;
+        } else if c.is_ascii_digit() {
+            let start = pos;
+            while pos < chars.len() && (chars[pos].is_ascii_digit() || chars[pos] == '.') {
+                pos += 1;
+            }
+            let text: String = chars[start..pos].iter().collect();
+            let number = text.parse().map_err(|_| ParseError {
+                position: start,
+                message: format!("invalid number '{}'", text),
+            })?;
+            tokens.push((start, Token::Number(number)));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = pos;
+            while pos < chars.len()
+                && (chars[pos].is_ascii_alphanumeric() || chars[pos] == '_' || chars[pos] == '.')
+            {
+                pos += 1;
+            }
+            tokens.push((start, Token::Ident(chars[start..pos].iter().collect())));
+        } else if c == '"' {
+            let start = pos;
+            pos += 1;
+            let mut text = String::new();
+            while pos < chars.len() && chars[pos] != '"' {
+                text.push(chars[pos]);
+                pos += 1;
+            }
+            if pos == chars.len() {
+                return Err(ParseError {
+                    position: start,
+                    message: "unterminated string".to_string(),
+                });
+            }
+            pos += 1;
+            tokens.push((start, Token::Str(text)));
+        } else {
+            let rest: String = chars[pos..].iter().take(2).collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| ParseError {
+                    position: pos,
+                    message: format!("unexpected character '{}'", c),
+                })?;
+            let token = match *op {
+                "(" => Token::LParen,
+                ")" => Token::RParen,
+                op => Token::Op(op),
+            };
+            tokens.push((pos, token));
+            pos += op.len();
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Resolve a dotted path or signal name to a JSON pointer, rejecting unknown fields
+fn resolve_field(name: &str, position: usize) -> Result<String, ParseError> {
+    if let Some(signal) = signals::find(name) {
+        return Ok(signal.pointer());
+    }
+
+    // Array indexes (`speed.wheel_speeds.0`) address elements of a registered signal
+    let base = match name.rsplit_once('.') {
+        Some((base, index)) if index.parse::<usize>().is_ok() => base,
+        _ => name,
+    };
+    let known = base == "step_name" || signals::SIGNALS.iter().any(|signal| signal.path == base);
+    if !known {
+        return Err(ParseError {
+            position,
+            message: format!("unknown field '{}'", name),
+        });
+    }
+
+    Ok(format!("/{}", name.replace('.', "/")))
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.end, |(position, _)| *position)
+    }
+
+    fn eat_op(&mut self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError {
+            position: self.position(),
+            message: message.into(),
+        }
+    }
+
+    fn binary(
+        &mut self,
+        ops: &[&'static str],
+        next: fn(&mut Self) -> Result<Expr, ParseError>,
+    ) -> Result<Expr, ParseError> {
+        let mut left = next(self)?;
+        while let Some(op) = self.eat_op(ops) {
+            let right = next(self)?;
+            left = Expr::Binary(binary_op(op), Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Expr, ParseError> {
+        self.binary(&["||"], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        self.binary(&["&&"], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, ParseError> {
+        let left = self.additive()?;
+        match self.eat_op(&["==", "!=", "<", "<=", ">", ">="]) {
+            Some(op) => {
+                let right = self.additive()?;
+                Ok(Expr::Binary(binary_op(op), Box::new(left), Box::new(right)))
+            }
+            None => Ok(left),
+        }
+    }
+
+    fn additive(&mut self) -> Result<Expr, ParseError> {
+        self.binary(&["+", "-"], Self::multiplicative)
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, ParseError> {
+        self.binary(&["*", "/"], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        match self.eat_op(&["!", "-"]) {
+            Some("!") => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some(_) => Ok(Expr::Neg(Box::new(self.unary()?))),
+            None => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        let position = self.position();
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| self.error("unexpected end of expression"))?;
+        self.pos += 1;
+
+        match token {
+            Token::Number(number) => Ok(Expr::Number(number)),
+            Token::Str(text) => Ok(Expr::Str(text)),
+            Token::Ident(name) => match name.as_str() {
+                "true" => Ok(Expr::Bool(true)),
+                "false" => Ok(Expr::Bool(false)),
+                _ => Ok(Expr::Field(resolve_field(&name, position)?)),
+            },
+            Token::LParen => {
+                let expr = self.or()?;
+                match self.peek() {
+                    Some(Token::RParen) => {
+                        self.pos += 1;
+                        Ok(expr)
+                    }
+                    _ => Err(self.error("expected ')'")),
+                }
+            }
+            Token::RParen | Token::Op(_) => Err(ParseError {
+                position,
+                message: "expected a value".to_string(),
+            }),
+        }
+    }
+}
+
+fn binary_op(op: &str) -> BinaryOp {
+    match op {
+        "||" => BinaryOp::Or,
+        "&&" => BinaryOp::And,
+        "==" => BinaryOp::Eq,
+        "!=" => BinaryOp::Ne,
+        "<" => BinaryOp::Lt,
+        "<=" => BinaryOp::Le,
+        ">" => BinaryOp::Gt,
+        ">=" => BinaryOp::Ge,
+        "+" => BinaryOp::Add,
+        "-" => BinaryOp::Sub,
+        "*" => BinaryOp::Mul,
+        _ => BinaryOp::Div,
+    }
+}
+
+/// Parse an expression, resolving every field reference up front
+pub fn parse(input: &str) -> Result<Expr, ParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        pos: 0,
+        end: input.chars().count(),
+    };
+    let expr = parser.or()?;
+    if parser.peek().is_some() {
+        return Err(parser.error("unexpected token"));
+    }
+    Ok(expr)
+}
+
+impl Expr {
+    /// Evaluate against a serialized DrivingStep; only a boolean `true` counts as a match
+    pub fn matches(&self, step: &Value) -> bool {
+        self.eval(step) == Value::Bool(true)
+    }
+
+    /// JSON pointers of the fields the expression reads, in order of first use
+    pub fn fields(&self) -> Vec<&str> {
+        let mut fields = Vec::new();
+        self.collect_fields(&mut fields);
+        fields
+    }
+
+    fn collect_fields<'a>(&'a self, fields: &mut Vec<&'a str>) {
+        match self {
+            Expr::Field(pointer) if !fields.contains(&pointer.as_str()) => fields.push(pointer),
+            Expr::Not(inner) | Expr::Neg(inner) => inner.collect_fields(fields),
+            Expr::Binary(_, left, right) => {
+                left.collect_fields(fields);
+                right.collect_fields(fields);
+            }
+            _ => {}
+        }
+    }
+
+    fn eval(&self, step: &Value) -> Value {
+        match self {
+            Expr::Number(number) => serde_json::json!(number),
+            Expr::Bool(value) => Value::Bool(*value),
+            Expr::Str(text) => Value::String(text.clone()),
+            Expr::Field(pointer) => step.pointer(pointer).cloned().unwrap_or(Value::Null),
+            Expr::Not(inner) => match inner.eval(step) {
+                Value::Bool(value) => Value::Bool(!value),
+                _ => Value::Null,
+            },
+            Expr::Neg(inner) => match inner.eval(step).as_f64() {
+                Some(number) => serde_json::json!(-number),
+                None => Value::Null,
+            },
+            Expr::Binary(BinaryOp::And, left, right) => {
+                Value::Bool(left.matches(step) && right.matches(step))
+            }
+            Expr::Binary(BinaryOp::Or, left, right) => {
+                Value::Bool(left.matches(step) || right.matches(step))
+            }
+            Expr::Binary(op, left, right) => apply(*op, left.eval(step), right.eval(step)),
+        }
+    }
+}
+
+fn apply(op: BinaryOp, left: Value, right: Value) -> Value {
+    if let (Some(a), Some(b)) = (left.as_f64(), right.as_f64()) {
+        return match op {
+            BinaryOp::Eq => Value::Bool(a == b),
+            BinaryOp::Ne => Value::Bool(a != b),
+            BinaryOp::Lt => Value::Bool(a < b),
+            BinaryOp::Le => Value::Bool(a <= b),
+            BinaryOp::Gt => Value::Bool(a > b),
+            BinaryOp::Ge => Value::Bool(a >= b),
+            BinaryOp::Add => serde_json::json!(a + b),
+            BinaryOp::Sub => serde_json::json!(a - b),
+            BinaryOp::Mul => serde_json::json!(a * b),
+            BinaryOp::Div if b != 0.0 => serde_json::json!(a / b),
+            _ => Value::Null,
+        };
+    }
+
+    let comparison = matches!(
+        op,
+        BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge
+    );
+    match op {
+        _ if !comparison => Value::Null,
+        _ if left.is_null() || right.is_null() => Value::Bool(false),
+        BinaryOp::Eq => Value::Bool(left == right),
+        BinaryOp::Ne => Value::Bool(left != right),
+        _ => Value::Bool(false),
+    }
+}
@@ -0,0 +1,167 @@
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use crate::common::error::AppError;
+use crate::core::bus::{Bus, BusMessage};
+use crate::features::driving_step::DrivingStep;
+use crate::features::event::model::{EventKind, SourceRef};
+use crate::features::event::{service as event_service, Event};
+use crate::features::rule::dsl::{self, Expr, ParseError};
+use crate::features::rule::model::Rule;
+use crate::features::rule::service;
+
+struct CompiledRule {
+    rule: Rule,
+    expr: Expr,
+    /// Whether the expression held for the previous step
+    active: bool,
+}
+
+/// Registered rules in compiled form, shared between the REST handlers and the bus task
+#[derive(Clone, Default)]
+pub struct RuleEngine {
+    rules: Arc<Mutex<Vec<CompiledRule>>>,
+}
+
+impl RuleEngine {
+    /// Compile every rule stored in the database
+    pub async fn load() -> Result<Self, AppError> {
+        let engine = RuleEngine::default();
+        engine.reload().await?;
+        Ok(engine)
+    }
+
+    /// Replace the registered rules with those stored in the database
+    pub async fn reload(&self) -> Result<(), AppError> {
+        let stored = service::get_rules().await?;
+        self.rules.lock().unwrap().clear();
+        for rule in stored {
+            let name = rule.name.clone();
+            if let Err(e) = self.register(rule) {
+                println!("⚠️ Skipping stored rule '{}': {}", name, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Compile and add a rule, replacing any rule with the same name
+    pub fn register(&self, rule: Rule) -> Result<(), ParseError> {
+        let expr = dsl::parse(&rule.expression)?;
+        let mut rules = self.rules.lock().unwrap();
+        rules.retain(|compiled| compiled.rule.name != rule.name);
+        rules.push(CompiledRule {
+            rule,
+            expr,
+            active: false,
+        });
+        Ok(())
+    }
+
+    pub fn remove(&self, name: &str) {
+        self.rules
+            .lock()
+            .unwrap()
+            .retain(|compiled| compiled.rule.name != name);
+    }
+
+    pub fn rules(&self) -> Vec<Rule> {
+        self.rules
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|compiled| compiled.rule.clone())
+            .collect()
+    }
+
+    /// Rules whose expression held for the latest step, the alerts currently raised
+    pub fn active(&self) -> Vec<Rule> {
+        self.rules
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|compiled| compiled.active)
+            .map(|compiled| compiled.rule.clone())
+            .collect()
+    }
+
+    /// Evaluate every rule against `step`, returning one event per rule that starts matching
+    ///
+    /// Rules fire on the transition from not matching to matching, so a condition that
+    /// holds over consecutive steps produces a single event.
+    pub fn evaluate(&self, step: &DrivingStep) -> Vec<Event> {
+        let Ok(value) = serde_json::to_value(step) else {
+            return Vec::new();
+        };
+
+        let mut events = Vec::new();
+        for compiled in self.rules.lock().unwrap().iter_mut() {
+            let matched = compiled.expr.matches(&value);
+            if matched && !compiled.active {
+                let message = compiled.rule.message.clone().unwrap_or_else(|| {
+                    format!(
+                        "Rule '{}' matched: {}",
+                        compiled.rule.name, compiled.rule.expression
+                    )
+                });
+                // The values that made the rule match, keyed by dotted path
+                let values: serde_json::Map<String, Value> = compiled
+                    .expr
+                    .fields()
+                    .into_iter()
+                    .map(|pointer| {
+                        let path = pointer.trim_start_matches('/').replace('/', ".");
+                        (path, value.pointer(pointer).cloned().unwrap_or(Value::Null))
+                    })
+                    .collect();
+                events.push(
+                    Event::new(
+                        EventKind::Rule,
+                        compiled.rule.name.clone(),
+                        message,
+                        Some(step.step_name.clone()),
+                    )
+                    .with_severity(compiled.rule.severity)
+                    .with_source(SourceRef::of_step(step))
+                    .with_tenant(step.tenant.clone())
+                    .with_payload(serde_json::json!({
+                        "rule": compiled.rule.name,
+                        "expression": compiled.rule.expression,
+                        "values": values,
+                    })),
+                );
+            }
+            compiled.active = matched;
+        }
+        events
+    }
+
+    /// Evaluate rules against every DrivingStep on the bus, storing and publishing the events
+    pub fn spawn(&self, bus: &Bus) {
+        let engine = self.clone();
+        let bus = bus.clone();
+        let mut rx = bus.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                let step = match rx.recv().await {
+                    Ok(BusMessage::DrivingStep(step)) => step,
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(_) => break,
+                };
+
+                for event in engine.evaluate(&step) {
+                    println!(
+                        "🔔 Rule '{}' fired on step '{}'",
+                        event.name, step.step_name
+                    );
+                    if let Err(e) = event_service::store_event(&event).await {
+                        println!("❌ Failed to store event '{}': {}", event.name, e);
+                    }
+                    let _ = bus.send(BusMessage::Event(event));
+                }
+            }
+        });
+    }
+}
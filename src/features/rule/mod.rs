@@ -0,0 +1,92 @@
+pub mod controller;
+pub mod dsl;
+pub mod engine;
+pub mod model;
+pub mod service;
+pub mod watch;
+
+use actix_web::web::Data;
+use actix_web::{delete, get, post, web, Error, HttpResponse, Result};
+use tokio::sync::broadcast;
+
+use crate::common::error::AppError;
+use crate::core::bus::{Bus, BusMessage};
+use crate::core::connections::{self, StreamSlot};
+use crate::core::scrub::Scrub;
+use crate::features::tenant::TenantScope;
+
+pub use engine::RuleEngine;
+pub use model::Rule;
+use model::{RuleRequest, WatchQuery};
+pub use watch::SignalWatch;
+
+#[get("/rules")]
+pub async fn list(engine: Data<RuleEngine>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(engine.rules()))
+}
+
+#[post("/rules")]
+pub async fn create(
+    engine: Data<RuleEngine>,
+    request: web::Json<RuleRequest>,
+) -> Result<HttpResponse, AppError> {
+    let rule = controller::create(&engine, request.into_inner()).await?;
+    Ok(HttpResponse::Created().json(rule))
+}
+
+#[delete("/rules/{name}")]
+pub async fn remove(
+    engine: Data<RuleEngine>,
+    name: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    controller::delete(&engine, &name).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// SSE stream of the crossings of one signal over a threshold, `?above=` or `?below=`
+#[get("/signals/{name}/watch")]
+pub async fn watch_signal(
+    name: web::Path<String>,
+    query: web::Query<WatchQuery>,
+    tx: Data<Bus>,
+    scope: TenantScope,
+    scrub: Scrub,
+    slot: StreamSlot,
+) -> Result<HttpResponse, AppError> {
+    let mut watch = SignalWatch::new(&name, &query).map_err(AppError::bad_request)?;
+    let tenant = scope.name().map(str::to_string);
+    let mut rx = tx.subscribe();
+
+    let stream = async_stream::stream! {
+        let _slot = slot;
+        loop {
+            let mut step = match rx.recv().await {
+                Ok(BusMessage::DrivingStep(step)) => step,
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            };
+            if tenant.is_some() && step.tenant != tenant {
+                continue;
+            }
+            if let Some(rules) = &scrub.0 {
+                rules.scrub_step(&mut step);
+            }
+            let Some(crossing) = watch.update(&step) else { continue };
+            let payload = serde_json::to_string(&crossing).unwrap_or_default();
+            yield Ok::<_, Error>(web::Bytes::from(format!("data: {}\n\n", payload)));
+        }
+    };
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", "text/event-stream"))
+        .insert_header(("Cache-Control", "no-cache"))
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(connections::keep_alive(stream)))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list)
+        .service(create)
+        .service(remove)
+        .service(watch_signal);
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+use crate::features::event::model::Severity;
+
+/// Named expression evaluated against every reconstructed DrivingStep
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub name: String,
+    /// Expression in the rule DSL, e.g. `speed.vehicle_speed > 120 && !speed.cruise_control`
+    pub expression: String,
+    /// Message of the generated events (defaults to a description of the rule)
+    pub message: Option<String>,
+    /// Severity of the generated events
+    pub severity: Severity,
+    pub created_at: String,
+}
+
+/// Body of `POST /rules`
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuleRequest {
+    pub name: String,
+    pub expression: String,
+    pub message: Option<String>,
+    /// Defaults to `warning`
+    pub severity: Option<Severity>,
+}
+
+/// Query parameters accepted by `GET /signals/{name}/watch`, in registry units
+#[derive(Debug, Clone, Deserialize)]
+pub struct WatchQuery {
+    /// Trigger when the signal rises above this value
+    pub above: Option<f64>,
+    /// Trigger when the signal falls below this value
+    pub below: Option<f64>,
+    /// Distance back past the threshold the signal must travel before the watch clears
+    #[serde(default)]
+    pub hysteresis: f64,
+}
+
+/// Whether a watched signal crossed its threshold or came back past the hysteresis band
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchState {
+    Triggered,
+    Cleared,
+}
+
+/// Message of a watch stream, sent on each change of its state
+#[derive(Debug, Clone, Serialize)]
+pub struct Crossing {
+    pub signal: &'static str,
+    pub state: WatchState,
+    pub value: f64,
+    pub threshold: f64,
+    pub step_id: Option<String>,
+    /// Timestamp of the frame the value was decoded from
+    pub timestamp: Option<String>,
+}
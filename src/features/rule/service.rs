@@ -0,0 +1,64 @@
+use sqlx::any::AnyRow;
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::features::rule::model::Rule;
+
+fn rule_from_row(row: &AnyRow) -> Result<Rule, AppError> {
+    let severity: String = row.try_get("severity")?;
+
+    Ok(Rule {
+        name: row.try_get("name")?,
+        expression: row.try_get("expression")?,
+        message: row.try_get("message")?,
+        severity: severity.parse().map_err(AppError::internal_server_error)?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+/// Insert a rule, replacing any previous rule with the same name
+pub async fn store_rule(rule: &Rule) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(
+        "INSERT INTO rules (name, expression, message, severity, created_at)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (name) DO UPDATE SET
+             expression = excluded.expression, message = excluded.message,
+             severity = excluded.severity, created_at = excluded.created_at",
+    )
+    .bind(&rule.name)
+    .bind(&rule.expression)
+    .bind(&rule.message)
+    .bind(rule.severity.as_str())
+    .bind(&rule.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_rules() -> Result<Vec<Rule>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(
+        "SELECT name, expression, message, severity, created_at FROM rules
+         ORDER BY created_at ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(rule_from_row).collect()
+}
+
+/// Delete a rule, returning whether it existed
+pub async fn delete_rule(name: &str) -> Result<bool, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let result = sqlx::query("DELETE FROM rules WHERE name = $1")
+        .bind(name)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
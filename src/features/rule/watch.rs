@@ -0,0 +1,87 @@
+use serde_json::Value;
+
+use crate::core::signals::{self, SignalDef};
+use crate::core::units::Unit;
+use crate::features::driving_step::DrivingStep;
+use crate::features::rule::dsl::{self, Expr};
+use crate::features::rule::model::{Crossing, WatchQuery, WatchState};
+
+/// Threshold watch on one signal, evaluated like a rule against every step
+///
+/// The watch triggers on the transition into the `trigger` expression and clears once the
+/// `release` expression holds, which sits `hysteresis` past the threshold, so a value
+/// hovering around the threshold produces a single crossing.
+pub struct SignalWatch {
+    signal: &'static SignalDef,
+    threshold: f64,
+    trigger: Expr,
+    release: Expr,
+    active: bool,
+}
+
+impl SignalWatch {
+    /// Watch `name` with one of `above` or `below`, failing with a message for `400`
+    pub fn new(name: &str, query: &WatchQuery) -> Result<Self, String> {
+        let signal = signals::find(name).ok_or_else(|| format!("Unknown signal '{}'", name))?;
+        if signal.unit == Unit::Boolean {
+            return Err(format!("Signal '{}' is a flag, not a value", name));
+        }
+        if !query.hysteresis.is_finite() || query.hysteresis < 0.0 {
+            return Err("hysteresis must be a non-negative number".to_string());
+        }
+
+        let (threshold, trigger, release) = match (query.above, query.below) {
+            (Some(above), None) => (
+                above,
+                format!("{} > {}", signal.name, above),
+                format!("{} <= {}", signal.name, above - query.hysteresis),
+            ),
+            (None, Some(below)) => (
+                below,
+                format!("{} < {}", signal.name, below),
+                format!("{} >= {}", signal.name, below + query.hysteresis),
+            ),
+            _ => return Err("Exactly one of 'above' and 'below' is required".to_string()),
+        };
+        if !threshold.is_finite() {
+            return Err("The threshold must be a finite number".to_string());
+        }
+
+        let parse = |expression: &str| dsl::parse(expression).map_err(|e| e.to_string());
+        Ok(SignalWatch {
+            signal,
+            threshold,
+            trigger: parse(&trigger)?,
+            release: parse(&release)?,
+            active: false,
+        })
+    }
+
+    /// Crossing caused by `step`, if it changed the state of the watch
+    pub fn update(&mut self, step: &DrivingStep) -> Option<Crossing> {
+        let value = serde_json::to_value(step).ok()?;
+        let state = if !self.active && self.trigger.matches(&value) {
+            WatchState::Triggered
+        } else if self.active && self.release.matches(&value) {
+            WatchState::Cleared
+        } else {
+            return None;
+        };
+        self.active = state == WatchState::Triggered;
+
+        Some(Crossing {
+            signal: self.signal.name,
+            state,
+            value: value
+                .pointer(&self.signal.pointer())
+                .and_then(Value::as_f64)?,
+            threshold: self.threshold,
+            step_id: step.step_id.clone(),
+            timestamp: step
+                .frames
+                .iter()
+                .find(|frame| frame.id == self.signal.can_id())
+                .map(|frame| frame.timestamp.clone()),
+        })
+    }
+}
@@ -0,0 +1,152 @@
+//! Built-in driving scenarios that can be run on demand or on a schedule
+
+use crate::common::error::AppError;
+use crate::features::driving_step::model::{AdasData, ClimateData, EngineData, VehicleSpeedData};
+use crate::features::driving_step::DrivingStep;
+use crate::features::scenario::model::Scenario;
+use crate::features::scenario::service;
+
+/// Names of every built-in scenario
+pub const SCENARIOS: [&str; 2] = ["commute", "city_loop"];
+
+/// Look up a built-in scenario by name
+pub fn builtin(name: &str) -> Option<Scenario> {
+    let (description, steps) = match name {
+        "commute" => (
+            "Six-step drive: start, first gear, acceleration, cruise, braking, stop",
+            commute(),
+        ),
+        "city_loop" => (
+            "Stop-and-go city driving ending with the engine switched off",
+            city_loop(),
+        ),
+        _ => return None,
+    };
+    Some(Scenario {
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        steps,
+    })
+}
+
+/// Look up a scenario by name, built-in scenarios first, then imported ones
+pub async fn find(name: &str) -> Result<Option<Scenario>, AppError> {
+    match builtin(name) {
+        Some(scenario) => Ok(Some(scenario)),
+        None => service::get_scenario(name).await,
+    }
+}
+
+/// Idling vehicle in park, the starting point of every scenario step
+fn idle(step_name: &str, duration_ms: u64) -> DrivingStep {
+    DrivingStep {
+        step_name: step_name.to_string(),
+        step_id: None,
+        engine: EngineData {
+            rpm: 800,
+            coolant_temp: 80,
+            throttle_pos: 0,
+            engine_load: 15,
+            intake_temp: 30,
+            fuel_pressure: 300,
+            engine_running: true,
+        },
+        speed: VehicleSpeedData {
+            vehicle_speed: 0.0,
+            gear_position: 0,
+            wheel_speeds: [0.0; 4],
+            abs_active: false,
+            traction_control: true,
+            cruise_control: false,
+        },
+        climate: ClimateData {
+            cabin_temp: 21,
+            target_temp: 21,
+            outside_temp: 15,
+            fan_speed: 30,
+            ac_compressor: false,
+            heater: false,
+            defrost: false,
+            auto_mode: true,
+            air_recirculation: false,
+        },
+        adas: None,
+        fuel: None,
+        gps: None,
+        duration_ms,
+        pipeline: None,
+        frames: Vec::new(),
+        tenant: None,
+        vehicle_id: None,
+    }
+}
+
+/// Step driving at `speed` km/h in `gear`, all four wheels turning at the same speed
+fn driving(step_name: &str, duration_ms: u64, rpm: u16, speed: f32, gear: u8) -> DrivingStep {
+    let mut step = idle(step_name, duration_ms);
+    step.engine.rpm = rpm;
+    step.speed.vehicle_speed = speed;
+    step.speed.wheel_speeds = [speed.min(255.0); 4];
+    step.speed.gear_position = gear;
+    step
+}
+
+/// Six-step drive: start, first gear, acceleration, cruise, braking, stop
+pub fn commute() -> Vec<DrivingStep> {
+    let mut start = idle("Vehicle Start", 2000);
+    start.engine.coolant_temp = 20;
+    start.climate.cabin_temp = 18;
+
+    let mut first_gear = driving("First Gear Engagement", 1500, 1200, 0.0, 1);
+    first_gear.engine.throttle_pos = 15;
+    first_gear.engine.coolant_temp = 25;
+
+    let mut acceleration = driving("Acceleration", 3000, 2500, 25.0, 2);
+    acceleration.engine.throttle_pos = 45;
+    acceleration.engine.coolant_temp = 45;
+
+    let mut cruise = driving("Highway Cruise", 5000, 2000, 90.0, 5);
+    cruise.engine.throttle_pos = 25;
+    cruise.engine.coolant_temp = 75;
+    cruise.speed.cruise_control = true;
+    cruise.adas = Some(AdasData {
+        lead_distance: 62.5,
+        relative_speed: -3.2,
+        acc_set_speed: 90,
+        acc_active: true,
+        lane_keep_active: true,
+        lane_departure_warning: false,
+    });
+
+    let mut braking = driving("Emergency Braking", 2000, 1500, 45.0, 3);
+    braking.engine.coolant_temp = 78;
+    braking.speed.abs_active = true;
+
+    let stop = idle("Vehicle Stop", 1000);
+
+    vec![start, first_gear, acceleration, cruise, braking, stop]
+}
+
+/// Stop-and-go city driving ending with the engine switched off
+pub fn city_loop() -> Vec<DrivingStep> {
+    let mut park_exit = driving("Leaving Parking", 3000, 1100, 8.0, 1);
+    park_exit.engine.throttle_pos = 10;
+
+    let mut avenue = driving("City Avenue", 6000, 2100, 48.0, 3);
+    avenue.engine.throttle_pos = 30;
+    avenue.engine.engine_load = 35;
+
+    let red_light = driving("Red Light", 4000, 800, 0.0, 1);
+
+    let mut turn = driving("Right Turn", 2000, 1400, 20.0, 2);
+    turn.speed.wheel_speeds = [21.0, 19.0, 21.0, 19.0];
+
+    let mut boulevard = driving("Boulevard", 5000, 2300, 55.0, 3);
+    boulevard.engine.throttle_pos = 35;
+
+    let mut engine_off = idle("Engine Off", 1000);
+    engine_off.engine.rpm = 0;
+    engine_off.engine.engine_running = false;
+
+    vec![park_exit, avenue, red_light, turn, boulevard, engine_off]
+}
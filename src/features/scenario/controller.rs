@@ -0,0 +1,225 @@
+use std::collections::HashSet;
+
+use crate::common::error::AppError;
+use crate::core::clock::{SimulatedClock, TimeSource};
+use crate::features::job::{JobKind, JobQueue};
+use crate::features::scenario::faults::FaultInjector;
+use crate::features::scenario::model::{
+    ImportReport, RunRequest, RunStatus, Scenario, ScenarioExport, ScenarioRun, Schedule,
+    ScheduleRequest, StartedRun, EXPORT_VERSION,
+};
+use crate::features::scenario::scheduler::Scheduler;
+use crate::features::scenario::{catalog, runner, service};
+use crate::features::tag::controller as tag_controller;
+use crate::features::tag::{TagFilter, TagTarget};
+use crate::features::trash::controller as trash_controller;
+use crate::features::trash::{DeleteQuery, TrashKind};
+
+/// Names of the built-in scenarios followed by the imported ones, only those carrying the
+/// tag of `filter` when it names one
+pub async fn list(filter: &TagFilter) -> Result<Vec<String>, AppError> {
+    let mut names: Vec<String> = catalog::SCENARIOS
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    names.extend(
+        service::get_scenarios()
+            .await?
+            .into_iter()
+            .map(|scenario| scenario.name),
+    );
+    if let Some(tagged) = tag_controller::filter(TagTarget::Scenario, filter).await? {
+        names.retain(|name| tagged.contains(name));
+    }
+    Ok(names)
+}
+
+/// Every scenario with its steps, or only the one named `name`
+pub async fn export(name: Option<&str>) -> Result<ScenarioExport, AppError> {
+    let scenarios = match name {
+        Some(name) => vec![catalog::find(name)
+            .await?
+            .ok_or_else(|| AppError::not_found(format!("Scenario '{}'", name)))?],
+        None => {
+            let mut scenarios: Vec<Scenario> = catalog::SCENARIOS
+                .iter()
+                .filter_map(|name| catalog::builtin(name))
+                .collect();
+            scenarios.extend(service::get_scenarios().await?);
+            scenarios
+        }
+    };
+
+    Ok(ScenarioExport {
+        version: EXPORT_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        scenarios,
+    })
+}
+
+/// Check and store the scenarios of an export, all or none
+///
+/// Imported scenarios replace those of the same name; built-in names are reserved.
+pub async fn import(export: ScenarioExport) -> Result<ImportReport, AppError> {
+    if export.version != EXPORT_VERSION {
+        return Err(AppError::bad_request(format!(
+            "Unsupported scenario export version {} (expected {})",
+            export.version, EXPORT_VERSION
+        )));
+    }
+    if export.scenarios.is_empty() {
+        return Err(AppError::bad_request("No scenarios in document"));
+    }
+
+    let mut names = HashSet::new();
+    for scenario in &export.scenarios {
+        if scenario.name.trim().is_empty() {
+            return Err(AppError::bad_request("Scenario name must not be empty"));
+        }
+        if catalog::builtin(&scenario.name).is_some() {
+            return Err(AppError::bad_request(format!(
+                "Scenario '{}' is built in and cannot be replaced",
+                scenario.name
+            )));
+        }
+        if !names.insert(scenario.name.as_str()) {
+            return Err(AppError::bad_request(format!(
+                "Scenario '{}' appears twice in the document",
+                scenario.name
+            )));
+        }
+        if scenario.steps.is_empty() {
+            return Err(AppError::bad_request(format!(
+                "Scenario '{}' has no steps",
+                scenario.name
+            )));
+        }
+        for (index, step) in scenario.steps.iter().enumerate() {
+            step.validate().map_err(|e| {
+                AppError::bad_request(format!(
+                    "Scenario '{}', step {}: {}",
+                    scenario.name,
+                    index + 1,
+                    e
+                ))
+            })?;
+        }
+    }
+
+    let replaced = service::store_scenarios(&export.scenarios).await?;
+    Ok(ImportReport {
+        imported: export
+            .scenarios
+            .into_iter()
+            .map(|scenario| scenario.name)
+            .collect(),
+        replaced,
+    })
+}
+
+/// Move an imported scenario to the trash, or delete it for good with `?permanent=true`
+pub async fn delete(name: &str, query: &DeleteQuery) -> Result<(), AppError> {
+    if catalog::builtin(name).is_some() {
+        return Err(AppError::bad_request(format!(
+            "Scenario '{}' is built in and cannot be deleted",
+            name
+        )));
+    }
+    trash_controller::discard(TrashKind::Scenario, name, query, None).await
+}
+
+/// Start a manual run in a replay job and return its record
+///
+/// The faults of `request` are checked before the run starts. `epoch` without a simulated
+/// `clock` is refused. The job fails when the run does, with the error of the run.
+pub async fn run(
+    scheduler: &Scheduler,
+    jobs: &JobQueue,
+    scenario: &str,
+    request: RunRequest,
+) -> Result<StartedRun, AppError> {
+    let steps = catalog::find(scenario)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Scenario '{}'", scenario)))?
+        .steps;
+
+    if request.epoch.is_some() && request.clock == TimeSource::Wall {
+        return Err(AppError::bad_request(
+            "epoch only applies to runs with \"clock\": \"simulated\"",
+        ));
+    }
+    let seed = (!request.faults.is_empty()).then(|| request.seed.unwrap_or_else(rand::random));
+    FaultInjector::new(&request.faults, seed.unwrap_or_default()).map_err(AppError::bad_request)?;
+
+    let epoch = match request.clock {
+        TimeSource::Wall => None,
+        TimeSource::Simulated => Some(
+            request
+                .epoch
+                .unwrap_or_else(|| SimulatedClock::default().epoch()),
+        ),
+    };
+    let run = runner::start_run(scenario, None, request.faults, seed, epoch, scheduler.bus()).await;
+    let transport = scheduler.transport().clone();
+    let started = run.clone();
+    let job = jobs
+        .submit(JobKind::Replay, None, move |_| async move {
+            let run = runner::execute(started, steps, transport).await;
+            match run.status {
+                RunStatus::Failed => Err(AppError::internal_server_error(
+                    run.error.unwrap_or_default(),
+                )),
+                _ => Ok(serde_json::to_value(run)?),
+            }
+        })
+        .await?;
+    Ok(StartedRun {
+        run,
+        job_id: job.id,
+    })
+}
+
+pub async fn create_schedule(
+    scheduler: &Scheduler,
+    request: ScheduleRequest,
+) -> Result<Schedule, AppError> {
+    if request.name.trim().is_empty() {
+        return Err(AppError::bad_request("Schedule name must not be empty"));
+    }
+    if catalog::find(&request.scenario).await?.is_none() {
+        return Err(AppError::bad_request(format!(
+            "Unknown scenario '{}'",
+            request.scenario
+        )));
+    }
+
+    let schedule = Schedule {
+        name: request.name,
+        scenario: request.scenario,
+        cron: request.cron,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    // Compile before storing so invalid schedules are never persisted
+    scheduler
+        .register(schedule.clone())
+        .map_err(AppError::bad_request)?;
+    service::store_schedule(&schedule).await?;
+
+    Ok(schedule)
+}
+
+pub async fn delete_schedule(scheduler: &Scheduler, name: &str) -> Result<(), AppError> {
+    if !service::delete_schedule(name).await? {
+        return Err(AppError::not_found(format!("Schedule '{}'", name)));
+    }
+    scheduler.remove(name);
+    Ok(())
+}
+
+pub async fn scenario_runs(scenario: &str) -> Result<Vec<ScenarioRun>, AppError> {
+    service::get_runs(Some(scenario), None).await
+}
+
+pub async fn schedule_runs(schedule: &str) -> Result<Vec<ScenarioRun>, AppError> {
+    service::get_runs(None, Some(schedule)).await
+}
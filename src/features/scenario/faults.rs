@@ -0,0 +1,154 @@
+//! Sensor faults injected into the steps of a scenario run
+
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::Value;
+
+use crate::core::can::CanMessage;
+use crate::core::signals::{self, SignalDef};
+use crate::core::units::Unit;
+use crate::features::driving_step::DrivingStep;
+use crate::features::scenario::model::{Fault, SignalFault};
+
+/// Applies the faults of a run to each of its steps, in order
+///
+/// Steps without the group of a faulty signal (no `adas`, `fuel` or `gps`) are left
+/// untouched. Faulty readings are clamped to the range the signal can be encoded in.
+pub struct FaultInjector {
+    faults: Vec<(&'static SignalDef, Fault)>,
+    /// Reading of each stuck signal without a value, frozen at the first step carrying it
+    stuck: HashMap<usize, Value>,
+    rng: StdRng,
+}
+
+impl FaultInjector {
+    /// Check the faults against the signal registry
+    pub fn new(faults: &[SignalFault], seed: u64) -> Result<Self, String> {
+        let faults = faults
+            .iter()
+            .map(|fault| {
+                let signal = signals::find(&fault.signal)
+                    .ok_or_else(|| format!("Unknown signal '{}'", fault.signal))?;
+                check(signal, &fault.fault)?;
+                Ok((signal, fault.fault.clone()))
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(FaultInjector {
+            faults,
+            stuck: HashMap::new(),
+            rng: StdRng::seed_from_u64(seed),
+        })
+    }
+
+    /// Frames of `step` once every fault is applied, without the frames that dropped out
+    pub fn apply(
+        &mut self,
+        step: &DrivingStep,
+        is_big_endian: bool,
+    ) -> Result<Vec<CanMessage>, String> {
+        let FaultInjector { faults, stuck, rng } = self;
+        let mut value = serde_json::to_value(step).map_err(|e| e.to_string())?;
+        let mut dropped = Vec::new();
+
+        for (index, (signal, fault)) in faults.iter().enumerate() {
+            let Some(reading) = value.pointer_mut(&signal.pointer()) else {
+                continue;
+            };
+            match fault {
+                Fault::Noise { std_dev } => {
+                    perturb(reading, signal, &mut |raw| raw + std_dev * gaussian(rng))
+                }
+                Fault::Stuck {
+                    value: Some(stuck_at),
+                } => perturb(reading, signal, &mut |_| *stuck_at),
+                Fault::Stuck { value: None } => {
+                    *reading = stuck
+                        .entry(index)
+                        .or_insert_with(|| reading.clone())
+                        .clone()
+                }
+                Fault::Dropout { probability } => {
+                    if rng.gen_bool(*probability) {
+                        dropped.push(signal.can_id());
+                    }
+                }
+            }
+        }
+
+        let step: DrivingStep = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        Ok(step
+            .to_can_messages_with_endian(is_big_endian)
+            .into_iter()
+            .filter(|frame| !dropped.contains(&frame.id))
+            .collect())
+    }
+}
+
+fn check(signal: &SignalDef, fault: &Fault) -> Result<(), String> {
+    match *fault {
+        Fault::Noise { std_dev } => {
+            if signal.unit == Unit::Boolean {
+                return Err(format!("Flag signal '{}' cannot take noise", signal.name));
+            }
+            if !(std_dev.is_finite() && std_dev > 0.0) {
+                return Err(format!(
+                    "Noise on '{}' needs a positive std_dev, got {}",
+                    signal.name, std_dev
+                ));
+            }
+        }
+        Fault::Stuck { value: Some(value) } => {
+            if !(signal.min..=signal.max).contains(&value) {
+                return Err(format!(
+                    "Signal '{}' cannot be stuck at {}, outside {}..{}",
+                    signal.name, value, signal.min, signal.max
+                ));
+            }
+        }
+        Fault::Stuck { value: None } => {}
+        Fault::Dropout { probability } => {
+            if !(probability > 0.0 && probability <= 1.0) {
+                return Err(format!(
+                    "Dropout of '{}' needs a probability in (0, 1], got {}",
+                    signal.name, probability
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Standard normal sample (Box-Muller transform)
+fn gaussian(rng: &mut StdRng) -> f64 {
+    let radius = (-2.0 * rng.gen_range(f64::EPSILON..1.0).ln()).sqrt();
+    radius * (TAU * rng.gen::<f64>()).cos()
+}
+
+/// Replace a reading (or each reading of an array) with `fault(reading)`
+///
+/// The result is clamped to the signal range, rounded for integer fields, and read as
+/// `true` from 0.5 up for flags.
+fn perturb(reading: &mut Value, signal: &SignalDef, fault: &mut dyn FnMut(f64) -> f64) {
+    match reading {
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| perturb(item, signal, fault)),
+        Value::Bool(flag) => *flag = fault(f64::from(u8::from(*flag))) >= 0.5,
+        Value::Number(number) => {
+            let Some(raw) = number.as_f64() else {
+                return;
+            };
+            let faulty = fault(raw).clamp(signal.min, signal.max);
+            *reading = if number.is_f64() {
+                Value::from(faulty)
+            } else {
+                Value::from(faulty.round() as i64)
+            };
+        }
+        _ => {}
+    }
+}
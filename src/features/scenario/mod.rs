@@ -0,0 +1,147 @@
+pub mod catalog;
+pub mod controller;
+pub mod faults;
+pub mod model;
+pub mod runner;
+pub mod scheduler;
+pub mod service;
+
+use actix_web::http::header::{HeaderName, ACCEPT, CONTENT_TYPE};
+use actix_web::web::Data;
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Result};
+
+use crate::common::error::AppError;
+use crate::features::job::JobQueue;
+use crate::features::tag::TagFilter;
+use crate::features::trash::DeleteQuery;
+
+use model::{ExportFormat, ExportQuery, RunRequest, ScenarioExport, ScheduleRequest};
+pub use model::{Scenario, ScenarioRun, Schedule};
+pub use scheduler::Scheduler;
+
+fn header(req: &HttpRequest, name: HeaderName) -> &str {
+    req.headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+}
+
+/// Names of the built-in and imported scenarios
+#[get("/scenarios")]
+pub async fn list_scenarios(query: web::Query<TagFilter>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::list(&query).await?))
+}
+
+/// Scenarios with their steps, as JSON or YAML (`?format=` or the Accept header)
+#[get("/scenarios/export")]
+pub async fn export_scenarios(
+    req: HttpRequest,
+    query: web::Query<ExportQuery>,
+) -> Result<HttpResponse, AppError> {
+    let format = query
+        .format
+        .unwrap_or(if header(&req, ACCEPT).contains("yaml") {
+            ExportFormat::Yaml
+        } else {
+            ExportFormat::Json
+        });
+    let export = controller::export(query.name.as_deref()).await?;
+
+    match format {
+        ExportFormat::Json => Ok(HttpResponse::Ok().json(export)),
+        ExportFormat::Yaml => Ok(HttpResponse::Ok().content_type("application/yaml").body(
+            serde_yaml::to_string(&export)
+                .map_err(|e| AppError::internal_server_error(e.to_string()))?,
+        )),
+    }
+}
+
+/// Import the scenarios of an export document, JSON or YAML according to the Content-Type
+#[post("/scenarios/export")]
+pub async fn import_scenarios(
+    req: HttpRequest,
+    body: web::Bytes,
+) -> Result<HttpResponse, AppError> {
+    let document: ScenarioExport = if header(&req, CONTENT_TYPE).contains("yaml") {
+        serde_yaml::from_slice(&body)
+            .map_err(|e| AppError::bad_request(format!("Invalid YAML document, {}", e)))?
+    } else {
+        serde_json::from_slice(&body)
+            .map_err(|e| AppError::bad_request(format!("Invalid JSON document, {}", e)))?
+    };
+    Ok(HttpResponse::Created().json(controller::import(document).await?))
+}
+
+/// Trash an imported scenario; its schedules are skipped until it is restored or imported again
+#[delete("/scenarios/{name}")]
+pub async fn delete_scenario(
+    name: web::Path<String>,
+    query: web::Query<DeleteQuery>,
+) -> Result<HttpResponse, AppError> {
+    controller::delete(&name, &query).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Run a scenario now, with the faults of an optional JSON body injected into its steps
+#[post("/scenarios/{name}/runs")]
+pub async fn run(
+    scheduler: Data<Scheduler>,
+    jobs: Data<JobQueue>,
+    name: web::Path<String>,
+    body: web::Bytes,
+) -> Result<HttpResponse, AppError> {
+    let request: RunRequest = if body.is_empty() {
+        RunRequest::default()
+    } else {
+        serde_json::from_slice(&body)
+            .map_err(|e| AppError::bad_request(format!("Invalid run request, {}", e)))?
+    };
+    let run = controller::run(&scheduler, &jobs, &name, request).await?;
+    Ok(HttpResponse::Accepted().json(run))
+}
+
+#[get("/scenarios/{name}/runs")]
+pub async fn scenario_runs(name: web::Path<String>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::scenario_runs(&name).await?))
+}
+
+#[get("/schedules")]
+pub async fn list_schedules(scheduler: Data<Scheduler>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(scheduler.schedules()))
+}
+
+#[post("/schedules")]
+pub async fn create_schedule(
+    scheduler: Data<Scheduler>,
+    request: web::Json<ScheduleRequest>,
+) -> Result<HttpResponse, AppError> {
+    let schedule = controller::create_schedule(&scheduler, request.into_inner()).await?;
+    Ok(HttpResponse::Created().json(schedule))
+}
+
+#[delete("/schedules/{name}")]
+pub async fn delete_schedule(
+    scheduler: Data<Scheduler>,
+    name: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    controller::delete_schedule(&scheduler, &name).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[get("/schedules/{name}/runs")]
+pub async fn schedule_runs(name: web::Path<String>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::schedule_runs(&name).await?))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_scenarios)
+        .service(export_scenarios)
+        .service(import_scenarios)
+        .service(delete_scenario)
+        .service(run)
+        .service(scenario_runs)
+        .service(list_schedules)
+        .service(create_schedule)
+        .service(delete_schedule)
+        .service(schedule_runs);
+}
@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::clock::TimeSource;
+use crate::features::driving_step::DrivingStep;
+
+/// Format version written into every scenario export
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Named sequence of steps, built in or imported
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub steps: Vec<DrivingStep>,
+}
+
+/// Document served by `GET /scenarios/export` and accepted by `POST /scenarios/export`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioExport {
+    pub version: u32,
+    #[serde(default)]
+    pub exported_at: String,
+    pub scenarios: Vec<Scenario>,
+}
+
+/// Serialization of a scenario export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+/// Query parameters accepted by `GET /scenarios/export`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExportQuery {
+    /// `json` or `yaml`; otherwise picked from the Accept header, JSON by default
+    pub format: Option<ExportFormat>,
+    /// Only export this scenario
+    pub name: Option<String>,
+}
+
+/// Response of `POST /scenarios/export`
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    /// Names of the scenarios stored, in document order
+    pub imported: Vec<String>,
+    /// Those that replaced a previously imported scenario of the same name
+    pub replaced: Vec<String>,
+}
+
+/// Imperfection injected into one signal while a scenario runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "fault", rename_all = "snake_case")]
+pub enum Fault {
+    /// Gaussian noise of standard deviation `std_dev`, in the unit of the signal
+    Noise { std_dev: f64 },
+    /// Sensor stuck at `value`, or at its first reading of the run when omitted
+    Stuck {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        value: Option<f64>,
+    },
+    /// Frame carrying the signal lost on each step with this probability
+    Dropout { probability: f64 },
+}
+
+/// Fault applied to a signal of every step of a run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignalFault {
+    /// Signal name, see `GET /signals`
+    pub signal: String,
+    #[serde(flatten)]
+    pub fault: Fault,
+}
+
+/// Optional body of `POST /scenarios/{name}/runs`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RunRequest {
+    #[serde(default)]
+    pub faults: Vec<SignalFault>,
+    /// Seed of the random faults, so a run can be reproduced; random when omitted
+    pub seed: Option<u64>,
+    /// `simulated` stamps the steps with scenario-relative time instead of the wall clock
+    #[serde(default)]
+    pub clock: TimeSource,
+    /// Start of the simulated time, `2000-01-01T00:00:00Z` when omitted
+    pub epoch: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Response of `POST /scenarios/{name}/runs`, the run and the replay job publishing it
+#[derive(Debug, Clone, Serialize)]
+pub struct StartedRun {
+    #[serde(flatten)]
+    pub run: ScenarioRun,
+    /// See `GET /jobs/{id}`, whose result is the finished run
+    pub job_id: String,
+}
+
+/// Cron schedule running a scenario
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Schedule {
+    pub name: String,
+    /// Built-in or imported scenario
+    pub scenario: String,
+    /// Cron expression with seconds, evaluated in UTC (`0 0 8 * * Mon-Fri`)
+    pub cron: String,
+    pub created_at: String,
+}
+
+/// Body of `POST /schedules`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScheduleRequest {
+    pub name: String,
+    pub scenario: String,
+    pub cron: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl RunStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RunStatus::Running => "running",
+            RunStatus::Succeeded => "succeeded",
+            RunStatus::Failed => "failed",
+        }
+    }
+}
+
+impl std::str::FromStr for RunStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "running" => Ok(RunStatus::Running),
+            "succeeded" => Ok(RunStatus::Succeeded),
+            "failed" => Ok(RunStatus::Failed),
+            other => Err(format!("Unknown run status '{}'", other)),
+        }
+    }
+}
+
+/// One execution of a scenario, manual or scheduled
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioRun {
+    pub id: String,
+    pub scenario: String,
+    /// Schedule that triggered the run, `None` for manual runs
+    pub schedule: Option<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub status: RunStatus,
+    pub steps_published: u32,
+    pub error: Option<String>,
+    /// Faults injected into the published steps
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub faults: Vec<SignalFault>,
+    /// Seed the random faults were drawn with, set when the run has faults
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Start of the simulated time its steps are stamped from, `None` on the wall clock
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<String>,
+}
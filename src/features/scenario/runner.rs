@@ -0,0 +1,167 @@
+use std::time::Duration;
+
+use crate::common::error::AppError;
+use crate::config::transport::StepTransport;
+use crate::core::bus::Bus;
+use crate::core::can::Endianness;
+use crate::core::clock::{Clock, SimulatedClock};
+use crate::features::driving_step::{service as step_service, DrivingStep};
+use crate::features::scenario::faults::FaultInjector;
+use crate::features::scenario::model::{RunStatus, ScenarioRun, SignalFault};
+use crate::features::scenario::service;
+use crate::features::system::{self, SystemEventKind};
+
+/// Record a new run of `scenario` before it starts, announcing it on `bus`
+///
+/// With an `epoch`, the run is stamped on a simulated clock starting there.
+pub async fn start_run(
+    scenario: &str,
+    schedule: Option<String>,
+    faults: Vec<SignalFault>,
+    seed: Option<u64>,
+    epoch: Option<chrono::DateTime<chrono::Utc>>,
+    bus: &Bus,
+) -> ScenarioRun {
+    let run = ScenarioRun {
+        id: uuid::Uuid::new_v4().to_string(),
+        scenario: scenario.to_string(),
+        schedule,
+        started_at: chrono::Utc::now().to_rfc3339(),
+        finished_at: None,
+        status: RunStatus::Running,
+        steps_published: 0,
+        error: None,
+        faults,
+        seed,
+        epoch: epoch.map(|epoch| epoch.to_rfc3339()),
+    };
+    if let Err(e) = service::save_run(&run).await {
+        println!("❌ Failed to record scenario run {}: {}", run.id, e);
+    }
+    system::controller::publish(
+        bus,
+        SystemEventKind::SimulatorStarted,
+        format!("Scenario '{}' started (run {})", run.scenario, run.id),
+        serde_json::json!({
+            "run_id": run.id,
+            "scenario": run.scenario,
+            "schedule": run.schedule,
+        }),
+    )
+    .await;
+    run
+}
+
+/// Publish every step of the scenario in real time, then record and return the outcome
+///
+/// Each step is followed by a pause of its own `duration_ms`, as a vehicle would
+/// report it, and the run stops at the first step that fails. Steps of a run with
+/// faults are published as the frames left once the faults are applied. A run with an
+/// `epoch` stamps its steps on a simulated clock, advanced by the same `duration_ms`.
+pub async fn execute(
+    mut run: ScenarioRun,
+    steps: Vec<DrivingStep>,
+    transport: StepTransport,
+) -> ScenarioRun {
+    println!("🎬 Running scenario '{}' (run {})", run.scenario, run.id);
+
+    let mut injector = match FaultInjector::new(&run.faults, run.seed.unwrap_or_default()) {
+        Ok(injector) => injector,
+        Err(e) => {
+            run.status = RunStatus::Failed;
+            run.error = Some(e);
+            return finish(run).await;
+        }
+    };
+
+    let clock = match run
+        .epoch
+        .as_deref()
+        .map(chrono::DateTime::parse_from_rfc3339)
+    {
+        Some(Ok(epoch)) => Some(SimulatedClock::new(epoch.into())),
+        Some(Err(e)) => {
+            run.status = RunStatus::Failed;
+            run.error = Some(format!("Invalid epoch: {}", e));
+            return finish(run).await;
+        }
+        None => None,
+    };
+
+    for step in &steps {
+        let published = if run.faults.is_empty() && clock.is_none() {
+            step_service::publish_step(step, &transport).await.map(drop)
+        } else {
+            publish_frames(&mut injector, step, clock.as_ref(), &run.id, &transport).await
+        };
+        if let Err(e) = published {
+            run.status = RunStatus::Failed;
+            run.error = Some(format!("Step '{}': {}", step.step_name, e));
+            break;
+        }
+        run.steps_published += 1;
+        if let Some(clock) = &clock {
+            clock.advance(Duration::from_millis(step.duration_ms));
+        }
+        tokio::time::sleep(Duration::from_millis(step.duration_ms)).await;
+    }
+
+    finish(run).await
+}
+
+/// Publish the frames of a step left once the faults are applied, stamped with `clock`
+/// when the run is simulated
+async fn publish_frames(
+    injector: &mut FaultInjector,
+    step: &DrivingStep,
+    clock: Option<&SimulatedClock>,
+    run_id: &str,
+    transport: &StepTransport,
+) -> Result<(), AppError> {
+    let is_big_endian = DrivingStep::get_endianness_from_env();
+    let mut frames = injector
+        .apply(step, is_big_endian)
+        .map_err(AppError::internal_server_error)?;
+    if frames.is_empty() {
+        println!("⚠️ Every frame of step '{}' dropped out", step.step_name);
+        return Ok(());
+    }
+    if let Some(clock) = clock {
+        let timestamp = clock.timestamp();
+        for frame in &mut frames {
+            frame.timestamp = timestamp.clone();
+        }
+    }
+    let stored = step_service::publish_frames(
+        frames,
+        Endianness::from_is_big_endian(is_big_endian),
+        None,
+        None,
+        &step.step_name,
+        transport,
+    )
+    .await?;
+    if clock.is_some() {
+        service::mark_simulated(&stored.step_id, run_id).await?;
+    }
+    Ok(())
+}
+
+/// Record the outcome of a run
+async fn finish(mut run: ScenarioRun) -> ScenarioRun {
+    if run.status == RunStatus::Running {
+        run.status = RunStatus::Succeeded;
+    }
+    run.finished_at = Some(chrono::Utc::now().to_rfc3339());
+
+    println!(
+        "🎬 Scenario '{}' {} after {} step(s)",
+        run.scenario,
+        run.status.as_str(),
+        run.steps_published
+    );
+    if let Err(e) = service::save_run(&run).await {
+        println!("❌ Failed to record scenario run {}: {}", run.id, e);
+    }
+    run
+}
@@ -0,0 +1,156 @@
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::common::error::AppError;
+use crate::config::transport::StepTransport;
+use crate::core::bus::Bus;
+use crate::features::scenario::model::Schedule;
+use crate::features::scenario::{catalog, runner, service};
+
+struct CompiledSchedule {
+    schedule: Schedule,
+    cron: cron::Schedule,
+}
+
+/// Registered schedules, checked every second by the scheduler task
+#[derive(Clone)]
+pub struct Scheduler {
+    schedules: Arc<Mutex<Vec<CompiledSchedule>>>,
+    transport: StepTransport,
+    /// Bus receiving a `simulator_started` system event for every run
+    bus: Bus,
+}
+
+/// Parse a cron expression with seconds (`sec min hour day month weekday [year]`)
+pub fn parse_cron(expression: &str) -> Result<cron::Schedule, String> {
+    cron::Schedule::from_str(expression)
+        .map_err(|e| format!("Invalid cron expression '{}': {}", expression, e))
+}
+
+impl Scheduler {
+    /// Compile every schedule stored in the database
+    pub async fn load(transport: StepTransport, bus: Bus) -> Result<Self, AppError> {
+        let scheduler = Scheduler {
+            schedules: Arc::new(Mutex::new(Vec::new())),
+            transport,
+            bus,
+        };
+        scheduler.reload().await?;
+        Ok(scheduler)
+    }
+
+    /// Replace the registered schedules with those stored in the database
+    pub async fn reload(&self) -> Result<(), AppError> {
+        let stored = service::get_schedules().await?;
+        self.schedules.lock().unwrap().clear();
+        for schedule in stored {
+            let name = schedule.name.clone();
+            if let Err(e) = self.register(schedule) {
+                println!("⚠️ Skipping stored schedule '{}': {}", name, e);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn transport(&self) -> &StepTransport {
+        &self.transport
+    }
+
+    pub fn bus(&self) -> &Bus {
+        &self.bus
+    }
+
+    /// Compile and add a schedule, replacing any schedule with the same name
+    ///
+    /// The scenario is looked up when the schedule fires, so imported scenarios can be
+    /// replaced without touching their schedules.
+    pub fn register(&self, schedule: Schedule) -> Result<(), String> {
+        let cron = parse_cron(&schedule.cron)?;
+
+        let mut schedules = self.schedules.lock().unwrap();
+        schedules.retain(|compiled| compiled.schedule.name != schedule.name);
+        schedules.push(CompiledSchedule { schedule, cron });
+        Ok(())
+    }
+
+    pub fn remove(&self, name: &str) {
+        self.schedules
+            .lock()
+            .unwrap()
+            .retain(|compiled| compiled.schedule.name != name);
+    }
+
+    pub fn schedules(&self) -> Vec<Schedule> {
+        self.schedules
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|compiled| compiled.schedule.clone())
+            .collect()
+    }
+
+    /// Schedules with a fire time in `(since, until]`
+    fn due(&self, since: DateTime<Utc>, until: DateTime<Utc>) -> Vec<Schedule> {
+        self.schedules
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|compiled| {
+                compiled
+                    .cron
+                    .after(&since)
+                    .next()
+                    .is_some_and(|next| next <= until)
+            })
+            .map(|compiled| compiled.schedule.clone())
+            .collect()
+    }
+
+    /// Start every due schedule's scenario; runs of one schedule may overlap
+    pub fn spawn(&self) {
+        let scheduler = self.clone();
+        let mut tick = tokio::time::interval(Duration::from_secs(1));
+
+        tokio::spawn(async move {
+            let mut last_check = Utc::now();
+            loop {
+                tick.tick().await;
+                let now = Utc::now();
+                for schedule in scheduler.due(last_check, now) {
+                    let scenario = match catalog::find(&schedule.scenario).await {
+                        Ok(Some(scenario)) => scenario,
+                        Ok(None) => {
+                            println!(
+                                "⚠️ Schedule '{}' skipped, scenario '{}' no longer exists",
+                                schedule.name, schedule.scenario
+                            );
+                            continue;
+                        }
+                        Err(e) => {
+                            println!("❌ Failed to load scenario '{}': {}", schedule.scenario, e);
+                            continue;
+                        }
+                    };
+                    let run = runner::start_run(
+                        &schedule.scenario,
+                        Some(schedule.name),
+                        Vec::new(),
+                        None,
+                        None,
+                        &scheduler.bus,
+                    )
+                    .await;
+                    tokio::spawn(runner::execute(
+                        run,
+                        scenario.steps,
+                        scheduler.transport.clone(),
+                    ));
+                }
+                last_check = now;
+            }
+        });
+    }
+}
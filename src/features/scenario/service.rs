@@ -0,0 +1,241 @@
+use sqlx::any::AnyRow;
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::features::scenario::model::{Scenario, ScenarioRun, Schedule};
+
+fn schedule_from_row(row: &AnyRow) -> Result<Schedule, AppError> {
+    Ok(Schedule {
+        name: row.try_get("name")?,
+        scenario: row.try_get("scenario")?,
+        cron: row.try_get("cron")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+fn run_from_row(row: &AnyRow) -> Result<ScenarioRun, AppError> {
+    let status: String = row.try_get("status")?;
+    let steps_published: i64 = row.try_get("steps_published")?;
+    let faults: String = row.try_get("faults")?;
+    let seed: Option<i64> = row.try_get("seed")?;
+
+    Ok(ScenarioRun {
+        id: row.try_get("id")?,
+        scenario: row.try_get("scenario")?,
+        schedule: row.try_get("schedule")?,
+        started_at: row.try_get("started_at")?,
+        finished_at: row.try_get("finished_at")?,
+        status: status.parse().map_err(AppError::internal_server_error)?,
+        steps_published: steps_published as u32,
+        error: row.try_get("error")?,
+        faults: serde_json::from_str(&faults)?,
+        seed: seed.map(|seed| seed as u64),
+        epoch: row.try_get("epoch")?,
+    })
+}
+
+/// Insert a schedule, replacing any previous schedule with the same name
+pub async fn store_schedule(schedule: &Schedule) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(
+        "INSERT INTO schedules (name, scenario, cron, created_at) VALUES ($1, $2, $3, $4)
+         ON CONFLICT (name) DO UPDATE SET
+             scenario = excluded.scenario, cron = excluded.cron,
+             created_at = excluded.created_at",
+    )
+    .bind(&schedule.name)
+    .bind(&schedule.scenario)
+    .bind(&schedule.cron)
+    .bind(&schedule.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_schedules() -> Result<Vec<Schedule>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(
+        "SELECT name, scenario, cron, created_at FROM schedules ORDER BY created_at ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(schedule_from_row).collect()
+}
+
+/// Delete a schedule, returning whether it existed
+pub async fn delete_schedule(name: &str) -> Result<bool, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let result = sqlx::query("DELETE FROM schedules WHERE name = $1")
+        .bind(name)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Insert or update a run record
+pub async fn save_run(run: &ScenarioRun) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(
+        "INSERT INTO scenario_runs
+         (id, scenario, schedule, started_at, finished_at, status, steps_published, error,
+          faults, seed, epoch)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+         ON CONFLICT (id) DO UPDATE SET
+             scenario = excluded.scenario, schedule = excluded.schedule,
+             started_at = excluded.started_at, finished_at = excluded.finished_at,
+             status = excluded.status, steps_published = excluded.steps_published,
+             error = excluded.error, faults = excluded.faults, seed = excluded.seed,
+             epoch = excluded.epoch",
+    )
+    .bind(&run.id)
+    .bind(&run.scenario)
+    .bind(&run.schedule)
+    .bind(&run.started_at)
+    .bind(&run.finished_at)
+    .bind(run.status.as_str())
+    .bind(run.steps_published as i64)
+    .bind(&run.error)
+    .bind(serde_json::to_string(&run.faults)?)
+    // SQLite integers are signed, seeds above i64::MAX wrap around
+    .bind(run.seed.map(|seed| seed as i64))
+    .bind(&run.epoch)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Runs of a scenario, or of a schedule, most recent first
+pub async fn get_runs(
+    scenario: Option<&str>,
+    schedule: Option<&str>,
+) -> Result<Vec<ScenarioRun>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(
+        "SELECT id, scenario, schedule, started_at, finished_at, status, steps_published, error,
+                faults, seed, epoch
+         FROM scenario_runs
+         WHERE ($1 IS NULL OR scenario = $1) AND ($2 IS NULL OR schedule = $2)
+         ORDER BY started_at DESC",
+    )
+    .bind(scenario)
+    .bind(schedule)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(run_from_row).collect()
+}
+
+fn scenario_from_row(row: &AnyRow) -> Result<Scenario, AppError> {
+    let steps: String = row.try_get("steps")?;
+
+    Ok(Scenario {
+        name: row.try_get("name")?,
+        description: row.try_get("description")?,
+        steps: serde_json::from_str(&steps)?,
+    })
+}
+
+/// Store imported scenarios in one transaction, returning the names that replaced a previous one
+pub async fn store_scenarios(scenarios: &[Scenario]) -> Result<Vec<String>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+    let now = chrono::Utc::now().to_rfc3339();
+
+    let mut replaced = Vec::new();
+    let mut transaction = pool.begin().await?;
+    for scenario in scenarios {
+        let created_at: Option<String> =
+            sqlx::query_scalar("SELECT created_at FROM scenarios WHERE name = $1")
+                .bind(&scenario.name)
+                .fetch_optional(&mut *transaction)
+                .await?;
+        if created_at.is_some() {
+            replaced.push(scenario.name.clone());
+        }
+
+        sqlx::query(
+            "INSERT INTO scenarios (name, description, steps, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (name) DO UPDATE SET
+                 description = excluded.description, steps = excluded.steps,
+                 created_at = excluded.created_at, updated_at = excluded.updated_at,
+                 deleted_at = NULL",
+        )
+        .bind(&scenario.name)
+        .bind(&scenario.description)
+        .bind(serde_json::to_string(&scenario.steps)?)
+        .bind(created_at.as_deref().unwrap_or(&now))
+        .bind(&now)
+        .execute(&mut *transaction)
+        .await?;
+    }
+    transaction.commit().await?;
+
+    Ok(replaced)
+}
+
+/// Imported scenarios not in the trash, oldest first
+pub async fn get_scenarios() -> Result<Vec<Scenario>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(
+        "SELECT name, description, steps FROM scenarios
+             WHERE deleted_at IS NULL ORDER BY created_at ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(scenario_from_row).collect()
+}
+
+pub async fn get_scenario(name: &str) -> Result<Option<Scenario>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let row = sqlx::query(
+        "SELECT name, description, steps FROM scenarios WHERE name = $1 AND deleted_at IS NULL",
+    )
+    .bind(name)
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(scenario_from_row).transpose()
+}
+
+/// Record that `step_id` was published by the run `run_id` on its simulated clock
+pub async fn mark_simulated(step_id: &str, run_id: &str) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(
+        "INSERT INTO simulated_steps (step_id, run_id) VALUES ($1, $2)
+         ON CONFLICT (step_id) DO UPDATE SET run_id = excluded.run_id",
+    )
+    .bind(step_id)
+    .bind(run_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_run(id: &str) -> Result<Option<ScenarioRun>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let row = sqlx::query(
+        "SELECT id, scenario, schedule, started_at, finished_at, status, steps_published, error,
+                faults, seed, epoch
+         FROM scenario_runs WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(run_from_row).transpose()
+}
@@ -0,0 +1,69 @@
+pub mod model;
+pub mod service;
+
+use actix_web::web::Data;
+use actix_web::{get, post, web, HttpResponse, Result};
+use futures_util::StreamExt;
+use serde_json::json;
+
+use crate::common::error::AppError;
+use crate::features::geofence::GeofenceTracker;
+use crate::features::rule::RuleEngine;
+use crate::features::scenario::Scheduler;
+use crate::features::subscription::SubscriptionRegistry;
+
+pub use model::Snapshot;
+
+/// Largest snapshot accepted by the restore endpoint
+const MAX_SNAPSHOT_BYTES: usize = 64 * 1024 * 1024;
+
+#[get("/admin/snapshot")]
+pub async fn capture() -> Result<HttpResponse, AppError> {
+    let snapshot = service::capture().await?;
+    let filename = format!("snapshot-{}.json", snapshot.created_at.replace(':', "-"));
+
+    Ok(HttpResponse::Ok()
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", filename),
+        ))
+        .json(snapshot))
+}
+
+/// Replace the stored state with a snapshot, then reload the in-memory registries
+#[post("/admin/snapshot/restore")]
+pub async fn restore(
+    mut payload: web::Payload,
+    rules: Data<RuleEngine>,
+    geofences: Data<GeofenceTracker>,
+    scheduler: Data<Scheduler>,
+    subscriptions: Data<SubscriptionRegistry>,
+) -> Result<HttpResponse, AppError> {
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|e| AppError::bad_request(e.to_string()))?;
+        if body.len() + chunk.len() > MAX_SNAPSHOT_BYTES {
+            return Err(AppError::bad_request(format!(
+                "Snapshot larger than {} bytes",
+                MAX_SNAPSHOT_BYTES
+            )));
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    let snapshot: Snapshot = serde_json::from_slice(&body)
+        .map_err(|e| AppError::bad_request(format!("Invalid snapshot: {}", e)))?;
+    let restored = service::restore(&snapshot).await?;
+
+    rules.reload().await?;
+    geofences.reload().await?;
+    scheduler.reload().await?;
+    subscriptions.reload().await?;
+    println!("♻️ Restored snapshot taken at {}", snapshot.created_at);
+
+    Ok(HttpResponse::Ok().json(json!({ "restored": restored })))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(capture).service(restore);
+}
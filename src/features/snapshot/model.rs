@@ -0,0 +1,43 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Format version written into every snapshot
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Tables captured by a snapshot, in restore order
+pub const SNAPSHOT_TABLES: [&str; 23] = [
+    "can_messages",
+    "events",
+    "rules",
+    "geofences",
+    "geofence_events",
+    "trips",
+    "anomalies",
+    "annotations",
+    "system_events",
+    "scenarios",
+    "schedules",
+    "scenario_runs",
+    "subscriptions",
+    "vehicles",
+    "signal_values",
+    "signal_aggregates",
+    "rejected_frames",
+    "tags",
+    "step_tags",
+    "trip_tags",
+    "scenario_tags",
+    "step_signatures",
+    "simulated_steps",
+];
+
+/// Portable copy of the demo state: every stored row, keyed by table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub created_at: String,
+    /// Rows as JSON objects mapping column names to values
+    pub tables: BTreeMap<String, Vec<Map<String, Value>>>,
+}
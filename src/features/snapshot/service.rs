@@ -0,0 +1,240 @@
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+use sqlx::any::{AnyArguments, AnyRow, AnyTypeInfoKind};
+use sqlx::{Any, Column, Row, ValueRef};
+
+use crate::common::error::AppError;
+use crate::config::db::{self, DbBackend};
+use crate::features::snapshot::model::{Snapshot, SNAPSHOT_TABLES, SNAPSHOT_VERSION};
+
+/// Convert a row to a JSON object, following each value's type
+///
+/// The `rowid` column PostgreSQL tables carry in place of SQLite's implicit one is left
+/// out, so snapshots restore into either backend.
+fn row_to_json(row: &AnyRow) -> Result<Map<String, Value>, AppError> {
+    let mut object = Map::new();
+    for column in row.columns() {
+        if column.name() == "rowid" {
+            continue;
+        }
+        let index = column.ordinal();
+        let raw = row.try_get_raw(index)?;
+        let value = if raw.is_null() {
+            Value::Null
+        } else {
+            match raw.type_info().kind() {
+                AnyTypeInfoKind::Bool => Value::from(row.try_get::<bool, _>(index)?),
+                AnyTypeInfoKind::SmallInt | AnyTypeInfoKind::Integer | AnyTypeInfoKind::BigInt => {
+                    Value::from(row.try_get::<i64, _>(index)?)
+                }
+                AnyTypeInfoKind::Real | AnyTypeInfoKind::Double => {
+                    Value::from(row.try_get::<f64, _>(index)?)
+                }
+                AnyTypeInfoKind::Blob => Value::from(row.try_get::<Vec<u8>, _>(index)?),
+                _ => Value::from(row.try_get::<String, _>(index)?),
+            }
+        };
+        object.insert(column.name().to_string(), value);
+    }
+    Ok(object)
+}
+
+/// Bind `value` for a column of SQL type `column_type`
+///
+/// Flags are integers in SQLite snapshots and booleans in PostgreSQL ones, and are bound
+/// as the column expects.
+fn bind_json<'q>(
+    query: sqlx::query::Query<'q, Any, AnyArguments<'q>>,
+    value: &Value,
+    column_type: &str,
+) -> sqlx::query::Query<'q, Any, AnyArguments<'q>> {
+    let boolean = column_type.eq_ignore_ascii_case("boolean");
+    match value {
+        // PostgreSQL types the parameter after the bound value, nulls included
+        Value::Null => match column_type.to_ascii_lowercase().as_str() {
+            "integer" | "bigint" => query.bind(None::<i64>),
+            "real" | "double precision" => query.bind(None::<f64>),
+            "blob" | "bytea" => query.bind(None::<Vec<u8>>),
+            _ if boolean => query.bind(None::<bool>),
+            _ => query.bind(None::<String>),
+        },
+        Value::Bool(flag) if boolean => query.bind(*flag),
+        Value::Bool(flag) => query.bind(*flag as i64),
+        Value::Number(number) if boolean => query.bind(number.as_f64().unwrap_or(0.0) != 0.0),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => query.bind(integer),
+            None => query.bind(number.as_f64()),
+        },
+        Value::String(text) => query.bind(text.clone()),
+        // Byte arrays are captured BLOB columns, such as frame payloads
+        Value::Array(items)
+            if items
+                .iter()
+                .all(|item| item.as_u64().is_some_and(|byte| byte <= 255)) =>
+        {
+            query.bind(
+                items
+                    .iter()
+                    .filter_map(Value::as_u64)
+                    .map(|byte| byte as u8)
+                    .collect::<Vec<u8>>(),
+            )
+        }
+        other => query.bind(other.to_string()),
+    }
+}
+
+/// Names and SQL types of the columns of `table`, used to reject snapshots that do not
+/// match the schema
+async fn table_columns(table: &str) -> Result<BTreeMap<String, String>, AppError> {
+    let pool = db::get_pool().await?;
+    let rows = match DbBackend::of(pool)? {
+        DbBackend::Sqlite => {
+            sqlx::query(&format!(
+                "SELECT name, type AS data_type FROM pragma_table_info('{}')",
+                table
+            ))
+            .fetch_all(pool)
+            .await?
+        }
+        DbBackend::Postgres => {
+            sqlx::query(
+                "SELECT CAST(column_name AS TEXT) AS name, CAST(data_type AS TEXT) AS data_type
+                 FROM information_schema.columns WHERE table_schema = current_schema()
+                 AND table_name = $1 AND column_name <> 'rowid'",
+            )
+            .bind(table)
+            .fetch_all(pool)
+            .await?
+        }
+    };
+    rows.iter()
+        .map(|row| Ok((row.try_get("name")?, row.try_get("data_type")?)))
+        .collect()
+}
+
+/// Capture every snapshot table
+pub async fn capture() -> Result<Snapshot, AppError> {
+    let pool = db::get_pool().await?;
+
+    let mut tables = BTreeMap::new();
+    for table in SNAPSHOT_TABLES {
+        let rows = sqlx::query(&format!("SELECT * FROM {}", table))
+            .fetch_all(pool)
+            .await?;
+        let rows = rows
+            .iter()
+            .map(row_to_json)
+            .collect::<Result<Vec<_>, _>>()?;
+        tables.insert(table.to_string(), rows);
+    }
+
+    Ok(Snapshot {
+        version: SNAPSHOT_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        tables,
+    })
+}
+
+/// Replace the contents of every snapshot table with the rows of `snapshot`
+///
+/// Tables missing from the snapshot are emptied. Everything happens in one
+/// transaction, so a rejected snapshot leaves the database untouched.
+pub async fn restore(snapshot: &Snapshot) -> Result<BTreeMap<String, usize>, AppError> {
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(AppError::bad_request(format!(
+            "Unsupported snapshot version {} (expected {})",
+            snapshot.version, SNAPSHOT_VERSION
+        )));
+    }
+    if let Some(unknown) = snapshot
+        .tables
+        .keys()
+        .find(|table| !SNAPSHOT_TABLES.contains(&table.as_str()))
+    {
+        return Err(AppError::bad_request(format!(
+            "Unknown table '{}' in snapshot",
+            unknown
+        )));
+    }
+
+    // Read the schema before the transaction holds the (possibly only) connection
+    let mut schema = BTreeMap::new();
+    for table in SNAPSHOT_TABLES {
+        schema.insert(table, table_columns(table).await?);
+    }
+
+    let pool = db::get_pool().await?;
+    let backend = DbBackend::of(pool)?;
+    let mut transaction = pool.begin().await?;
+    let mut restored = BTreeMap::new();
+
+    for table in SNAPSHOT_TABLES {
+        sqlx::query(&format!("DELETE FROM {}", table))
+            .execute(&mut *transaction)
+            .await?;
+
+        let rows = snapshot.tables.get(table).map(Vec::as_slice).unwrap_or(&[]);
+        let columns = &schema[table];
+        for row in rows {
+            if let Some(unknown) = row.keys().find(|column| !columns.contains_key(*column)) {
+                return Err(AppError::bad_request(format!(
+                    "Unknown column '{}' for table '{}'",
+                    unknown, table
+                )));
+            }
+            if let Some(column) = row
+                .iter()
+                .find(|(column, value)| value.is_string() && columns[*column] == "bytea")
+                .map(|(column, _)| column)
+            {
+                return Err(AppError::bad_request(format!(
+                    "Column '{}' of table '{}' holds JSON payloads from before binary payloads, \
+                     which only restore into SQLite",
+                    column, table
+                )));
+            }
+
+            let names: Vec<&str> = row.keys().map(String::as_str).collect();
+            let placeholders: Vec<String> = (1..=names.len()).map(|n| format!("${}", n)).collect();
+            let sql = format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                table,
+                names
+                    .iter()
+                    .map(|name| format!("\"{}\"", name))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                placeholders.join(", ")
+            );
+            let query = row
+                .iter()
+                .fold(sqlx::query(&sql), |query, (column, value)| {
+                    bind_json(query, value, &columns[column])
+                });
+            query.execute(&mut *transaction).await?;
+        }
+        restored.insert(table.to_string(), rows.len());
+    }
+
+    // Snapshots taken before frames had sequence numbers are numbered in archive order
+    sqlx::query(
+        "UPDATE can_messages
+         SET seq = rowid + (SELECT COALESCE(MAX(seq), 0) FROM can_messages)
+         WHERE seq IS NULL",
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    // Snapshots taken before frames had step ids are cut into steps
+    crate::config::sqlite::migrate_step_ids(&mut transaction).await?;
+
+    // Snapshots taken before frame payloads were binary hold JSON arrays
+    if backend == DbBackend::Sqlite {
+        crate::config::sqlite::migrate_frame_data(&mut transaction).await?;
+    }
+
+    transaction.commit().await?;
+    Ok(restored)
+}
@@ -0,0 +1,89 @@
+use crate::common::error::AppError;
+use crate::core::bus::{self, BusMessage};
+use crate::features::subscription::model::{Subscription, SubscriptionRequest};
+use crate::features::subscription::registry::SubscriptionRegistry;
+use crate::features::subscription::service;
+
+fn validate(request: &SubscriptionRequest) -> Result<(), AppError> {
+    if request.name.trim().is_empty() {
+        return Err(AppError::bad_request("Subscription name must not be empty"));
+    }
+    if let Some(topic) = request
+        .topics
+        .iter()
+        .find(|topic| !BusMessage::TOPICS.contains(&topic.as_str()))
+    {
+        return Err(AppError::bad_request(format!(
+            "Unknown topic '{}', expected one of {}",
+            topic,
+            BusMessage::TOPICS.join(", ")
+        )));
+    }
+    bus::validate_max_rate(request.max_rate)
+}
+
+pub fn get(registry: &SubscriptionRegistry, id: &str) -> Result<Subscription, AppError> {
+    registry
+        .get(id)
+        .ok_or_else(|| AppError::not_found(format!("Subscription '{}'", id)))
+}
+
+pub async fn create(
+    registry: &SubscriptionRegistry,
+    request: SubscriptionRequest,
+) -> Result<Subscription, AppError> {
+    validate(&request)?;
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let subscription = Subscription {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: request.name,
+        topics: request.topics,
+        min_severity: request.min_severity,
+        latency: request.latency,
+        mode: request.mode,
+        transport: request.transport,
+        max_rate: request.max_rate,
+        created_at: now.clone(),
+        updated_at: now,
+    };
+    service::store_subscription(&subscription).await?;
+    registry.register(subscription.clone());
+
+    Ok(subscription)
+}
+
+/// Replace the filters of a subscription, applied to its connected clients right away
+pub async fn update(
+    registry: &SubscriptionRegistry,
+    id: &str,
+    request: SubscriptionRequest,
+) -> Result<Subscription, AppError> {
+    validate(&request)?;
+    let existing = get(registry, id)?;
+
+    let subscription = Subscription {
+        id: existing.id,
+        name: request.name,
+        topics: request.topics,
+        min_severity: request.min_severity,
+        latency: request.latency,
+        mode: request.mode,
+        transport: request.transport,
+        max_rate: request.max_rate,
+        created_at: existing.created_at,
+        updated_at: chrono::Utc::now().to_rfc3339(),
+    };
+    service::store_subscription(&subscription).await?;
+    registry.register(subscription.clone());
+
+    Ok(subscription)
+}
+
+pub async fn delete(registry: &SubscriptionRegistry, id: &str) -> Result<(), AppError> {
+    if !service::delete_subscription(id).await? {
+        return Err(AppError::not_found(format!("Subscription '{}'", id)));
+    }
+    registry.remove(id);
+    Ok(())
+}
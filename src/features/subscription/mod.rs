@@ -0,0 +1,62 @@
+pub mod controller;
+pub mod model;
+pub mod registry;
+pub mod service;
+
+use actix_web::web::Data;
+use actix_web::{delete, get, post, put, web, HttpResponse, Result};
+
+use crate::common::error::AppError;
+
+use model::SubscriptionRequest;
+pub use model::{StreamTransport, Subscription};
+pub use registry::{LiveFilter, SubscriptionRegistry};
+
+#[get("/subscriptions")]
+pub async fn list(registry: Data<SubscriptionRegistry>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(registry.subscriptions()))
+}
+
+#[post("/subscriptions")]
+pub async fn create(
+    registry: Data<SubscriptionRegistry>,
+    request: web::Json<SubscriptionRequest>,
+) -> Result<HttpResponse, AppError> {
+    let subscription = controller::create(&registry, request.into_inner()).await?;
+    Ok(HttpResponse::Created().json(subscription))
+}
+
+#[get("/subscriptions/{id}")]
+pub async fn get(
+    registry: Data<SubscriptionRegistry>,
+    id: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::get(&registry, &id)?))
+}
+
+#[put("/subscriptions/{id}")]
+pub async fn update(
+    registry: Data<SubscriptionRegistry>,
+    id: web::Path<String>,
+    request: web::Json<SubscriptionRequest>,
+) -> Result<HttpResponse, AppError> {
+    let subscription = controller::update(&registry, &id, request.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(subscription))
+}
+
+#[delete("/subscriptions/{id}")]
+pub async fn remove(
+    registry: Data<SubscriptionRegistry>,
+    id: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    controller::delete(&registry, &id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list)
+        .service(create)
+        .service(get)
+        .service(update)
+        .service(remove);
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::bus::{StreamMode, SubscriptionFilter};
+use crate::core::timestamps::TimestampFormat;
+use crate::features::event::model::Severity;
+
+/// Stream endpoints a subscription can be used from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamTransport {
+    /// `/ws`
+    Ws,
+    /// `/stream` and `/stream-lab`
+    Sse,
+}
+
+impl StreamTransport {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StreamTransport::Ws => "ws",
+            StreamTransport::Sse => "sse",
+        }
+    }
+}
+
+impl std::str::FromStr for StreamTransport {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "ws" => Ok(StreamTransport::Ws),
+            "sse" => Ok(StreamTransport::Sse),
+            other => Err(format!("Unknown stream transport '{}'", other)),
+        }
+    }
+}
+
+/// Stored filter set a stream client references with `?subscription=<id>`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subscription {
+    pub id: String,
+    pub name: String,
+    /// Bus topics to deliver, every topic when empty
+    pub topics: Vec<String>,
+    /// Drop events below this severity; other topics are not affected
+    pub min_severity: Option<Severity>,
+    /// Attach the `X-Pipeline-Latency` debug field to driving steps
+    pub latency: bool,
+    /// Driving steps as steps or as frames with their decoded signals
+    pub mode: StreamMode,
+    /// Only usable from this transport, any transport when unset
+    pub transport: Option<StreamTransport>,
+    /// Most messages per second delivered for each topic, the latest one kept when faster
+    pub max_rate: Option<f64>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl Subscription {
+    pub fn allows(&self, transport: StreamTransport) -> bool {
+        self.transport.is_none_or(|allowed| allowed == transport)
+    }
+
+    pub fn filter(&self) -> SubscriptionFilter {
+        SubscriptionFilter {
+            min_severity: self.min_severity,
+            latency: self.latency,
+            mode: self.mode,
+            topics: self.topics.clone(),
+            can_ids: Vec::new(),
+            subscription: Some(self.id.clone()),
+            tenant: None,
+            scrub: None,
+            max_rate: self.max_rate,
+            timestamps: TimestampFormat::default(),
+        }
+    }
+}
+
+/// Body of `POST /subscriptions` and `PUT /subscriptions/{id}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubscriptionRequest {
+    pub name: String,
+    #[serde(default)]
+    pub topics: Vec<String>,
+    pub min_severity: Option<Severity>,
+    #[serde(default)]
+    pub latency: bool,
+    #[serde(default)]
+    pub mode: StreamMode,
+    pub transport: Option<StreamTransport>,
+    pub max_rate: Option<f64>,
+}
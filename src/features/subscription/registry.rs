@@ -0,0 +1,149 @@
+use std::sync::{Arc, Mutex};
+
+use crate::common::error::AppError;
+use crate::core::bus::{validate_max_rate, SubscriptionFilter};
+use crate::core::scrub::ScrubRules;
+use crate::core::selection::Selection;
+use crate::core::timestamps::TimestampFormat;
+use crate::features::subscription::model::{StreamTransport, Subscription};
+use crate::features::subscription::service;
+
+/// Stored subscriptions, looked up by stream clients on every message
+///
+/// Clients follow changes made through the REST API without reconnecting.
+#[derive(Clone, Default)]
+pub struct SubscriptionRegistry {
+    subscriptions: Arc<Mutex<Vec<Subscription>>>,
+}
+
+impl SubscriptionRegistry {
+    /// Serve every subscription stored in the database
+    pub async fn load() -> Result<Self, AppError> {
+        let registry = SubscriptionRegistry::default();
+        registry.reload().await?;
+        Ok(registry)
+    }
+
+    /// Replace the registered subscriptions with those stored in the database
+    pub async fn reload(&self) -> Result<(), AppError> {
+        let stored = service::get_subscriptions().await?;
+        *self.subscriptions.lock().unwrap() = stored;
+        Ok(())
+    }
+
+    /// Add a subscription, replacing any subscription with the same id
+    pub fn register(&self, subscription: Subscription) {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        match subscriptions
+            .iter_mut()
+            .find(|registered| registered.id == subscription.id)
+        {
+            Some(registered) => *registered = subscription,
+            None => subscriptions.push(subscription),
+        }
+    }
+
+    pub fn remove(&self, id: &str) {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .retain(|registered| registered.id != id);
+    }
+
+    pub fn subscriptions(&self) -> Vec<Subscription> {
+        self.subscriptions.lock().unwrap().clone()
+    }
+
+    pub fn get(&self, id: &str) -> Option<Subscription> {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|subscription| subscription.id == id)
+            .cloned()
+    }
+
+    /// Filter of a client connecting through `transport` with the query parameters `query`
+    ///
+    /// With `?subscription=<id>` the stored subscription replaces the other parameters,
+    /// except the tenant and scrub rules of the connection.
+    pub fn resolve(
+        &self,
+        query: SubscriptionFilter,
+        transport: StreamTransport,
+    ) -> Result<LiveFilter, AppError> {
+        validate_max_rate(query.max_rate)?;
+        let Some(id) = query.subscription.clone() else {
+            return Ok(LiveFilter::Fixed(query));
+        };
+        let subscription = self
+            .get(&id)
+            .ok_or_else(|| AppError::not_found(format!("Subscription '{}'", id)))?;
+        if !subscription.allows(transport) {
+            return Err(AppError::bad_request(format!(
+                "Subscription '{}' is restricted to the {} transport",
+                id,
+                subscription
+                    .transport
+                    .map(StreamTransport::as_str)
+                    .unwrap_or_default()
+            )));
+        }
+
+        Ok(LiveFilter::Stored {
+            registry: self.clone(),
+            id,
+            transport,
+            tenant: query.tenant,
+            scrub: query.scrub,
+            timestamps: query.timestamps,
+        })
+    }
+}
+
+/// Filter of one connected stream client
+#[derive(Clone)]
+pub enum LiveFilter {
+    /// Built from the query parameters of the connection
+    Fixed(SubscriptionFilter),
+    /// Read from a stored subscription for every message
+    Stored {
+        registry: SubscriptionRegistry,
+        id: String,
+        transport: StreamTransport,
+        tenant: Option<String>,
+        scrub: Option<ScrubRules>,
+        timestamps: TimestampFormat,
+    },
+    /// Another filter narrowed to the topics and CAN IDs a websocket client chose
+    Selected(Box<LiveFilter>, Selection),
+}
+
+impl LiveFilter {
+    /// Filter to apply to the next message, `None` once the subscription was deleted or
+    /// no longer allows the client's transport, which ends the stream
+    pub fn current(&self) -> Option<SubscriptionFilter> {
+        match self {
+            LiveFilter::Fixed(filter) => Some(filter.clone()),
+            LiveFilter::Stored {
+                registry,
+                id,
+                transport,
+                tenant,
+                scrub,
+                timestamps,
+            } => registry
+                .get(id)
+                .filter(|subscription| subscription.allows(*transport))
+                .map(|subscription| SubscriptionFilter {
+                    tenant: tenant.clone(),
+                    scrub: *scrub,
+                    timestamps: *timestamps,
+                    ..subscription.filter()
+                }),
+            LiveFilter::Selected(filter, selection) => {
+                filter.current().map(|filter| selection.narrow(filter))
+            }
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use sqlx::any::AnyRow;
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::features::subscription::model::Subscription;
+
+fn subscription_from_row(row: &AnyRow) -> Result<Subscription, AppError> {
+    let topics: String = row.try_get("topics")?;
+    let min_severity: Option<String> = row.try_get("min_severity")?;
+    let latency: i64 = row.try_get("latency")?;
+    let mode: String = row.try_get("mode")?;
+    let transport: Option<String> = row.try_get("transport")?;
+
+    Ok(Subscription {
+        id: row.try_get("id")?,
+        name: row.try_get("name")?,
+        topics: serde_json::from_str(&topics)?,
+        min_severity: min_severity
+            .map(|severity| severity.parse())
+            .transpose()
+            .map_err(AppError::internal_server_error)?,
+        latency: latency != 0,
+        mode: mode.parse().map_err(AppError::internal_server_error)?,
+        transport: transport
+            .map(|transport| transport.parse())
+            .transpose()
+            .map_err(AppError::internal_server_error)?,
+        max_rate: row.try_get("max_rate")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+/// Insert a subscription, replacing any previous subscription with the same id
+pub async fn store_subscription(subscription: &Subscription) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(
+        "INSERT INTO subscriptions
+         (id, name, topics, min_severity, latency, mode, transport, max_rate, created_at,
+          updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+         ON CONFLICT (id) DO UPDATE SET
+             name = excluded.name, topics = excluded.topics,
+             min_severity = excluded.min_severity, latency = excluded.latency,
+             mode = excluded.mode, transport = excluded.transport,
+             max_rate = excluded.max_rate, created_at = excluded.created_at,
+             updated_at = excluded.updated_at",
+    )
+    .bind(&subscription.id)
+    .bind(&subscription.name)
+    .bind(serde_json::to_string(&subscription.topics)?)
+    .bind(subscription.min_severity.map(|severity| severity.as_str()))
+    .bind(subscription.latency as i64)
+    .bind(subscription.mode.as_str())
+    .bind(subscription.transport.map(|transport| transport.as_str()))
+    .bind(subscription.max_rate)
+    .bind(&subscription.created_at)
+    .bind(&subscription.updated_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_subscriptions() -> Result<Vec<Subscription>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(
+        "SELECT id, name, topics, min_severity, latency, mode, transport, max_rate, created_at,
+                updated_at
+         FROM subscriptions ORDER BY created_at ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(subscription_from_row).collect()
+}
+
+/// Delete a subscription, returning whether it existed
+pub async fn delete_subscription(id: &str) -> Result<bool, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let result = sqlx::query("DELETE FROM subscriptions WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
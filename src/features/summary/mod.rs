@@ -0,0 +1,51 @@
+pub mod model;
+pub mod summarizer;
+
+use actix_web::web::Data;
+use actix_web::{get, web, Error, HttpResponse, Result};
+
+use crate::common::error::AppError;
+use crate::core::connections::{self, StreamSlot};
+use crate::core::scrub::Scrub;
+use crate::features::tenant::TenantScope;
+
+pub use model::Summary;
+pub use summarizer::Summarizer;
+
+/// One rolled-up state per interval instead of every message, for slow clients
+#[get("/stream/summary")]
+pub async fn stream(
+    summarizer: Data<Summarizer>,
+    scope: TenantScope,
+    scrub: Scrub,
+    slot: StreamSlot,
+) -> Result<HttpResponse, AppError> {
+    let tenant = scope.name().map(str::to_string);
+    let mut rx = summarizer.subscribe();
+
+    let stream = async_stream::stream! {
+        let _slot = slot;
+        // A client that falls behind only sees the newest summary once it catches up
+        while rx.changed().await.is_ok() {
+            let mut summary = Summarizer::summary_of(&rx.borrow_and_update(), tenant.as_deref());
+            if let Some(rules) = &scrub.0 {
+                for (name, sample) in summary.signals.iter_mut() {
+                    sample.value = rules.signal(name, sample.value);
+                }
+            }
+            let Ok(payload) = serde_json::to_string(&summary) else { continue };
+            let line = format!("data: {}\n\n", payload);
+            yield Ok::<_, Error>(web::Bytes::from(line));
+        }
+    };
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Type", "text/event-stream"))
+        .insert_header(("Cache-Control", "no-cache"))
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(connections::keep_alive(stream)))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(stream);
+}
@@ -0,0 +1,27 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+/// Latest known value of a signal
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SignalSample {
+    /// In registry units, flags as 0 and 1
+    pub value: f64,
+    /// Time of the frame the value was decoded from, in milliseconds since the Unix epoch
+    pub timestamp_ms: i64,
+}
+
+/// Rolled-up bus state sent by `GET /stream/summary` once per interval
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Summary {
+    /// RFC3339 end of the window
+    pub at: String,
+    pub interval_ms: u64,
+    /// Bus messages of each topic during the window
+    pub counts: BTreeMap<&'static str, u64>,
+    /// Latest value of every signal received so far
+    pub signals: BTreeMap<&'static str, SignalSample>,
+    /// Id of the latest driving step received so far
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_step_id: Option<String>,
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{broadcast, watch};
+
+use crate::core::bus::{Bus, BusMessage};
+use crate::features::history::SignalRecorder;
+use crate::features::summary::model::{SignalSample, Summary};
+
+/// Summaries of the latest window, by tenant; `None` summarizes the whole bus
+pub type Summaries = Arc<HashMap<Option<String>, Summary>>;
+
+/// Rolls the bus up into one summary per interval for bandwidth-constrained clients
+///
+/// Summaries go out on a `watch` channel rather than the bus: a client that falls behind
+/// skips straight to the newest summary and never makes the bus lag.
+#[derive(Clone)]
+pub struct Summarizer {
+    interval: Duration,
+    current: Arc<Mutex<HashMap<Option<String>, Summary>>>,
+    summaries: watch::Sender<Summaries>,
+}
+
+impl Summarizer {
+    pub fn new(interval: Duration) -> Self {
+        Summarizer {
+            interval,
+            current: Arc::default(),
+            summaries: watch::channel(Summaries::default()).0,
+        }
+    }
+
+    /// Receiver of the summaries, holding the latest one
+    pub fn subscribe(&self) -> watch::Receiver<Summaries> {
+        self.summaries.subscribe()
+    }
+
+    /// Summary of `tenant` in `summaries`, an empty one while none of its data went by
+    pub fn summary_of(summaries: &Summaries, tenant: Option<&str>) -> Summary {
+        let key = tenant.map(str::to_string);
+        if let Some(summary) = summaries.get(&key) {
+            return summary.clone();
+        }
+        let whole = summaries.get(&None);
+        Summary {
+            at: whole.map(|summary| summary.at.clone()).unwrap_or_default(),
+            interval_ms: whole.map_or(0, |summary| summary.interval_ms),
+            ..empty_summary()
+        }
+    }
+
+    /// Fold `message` into the current window of the whole bus and of its tenant
+    ///
+    /// Geofence, anomaly and system messages are only summarized for the whole bus, as they are
+    /// only streamed to the administrator.
+    pub fn observe(&self, message: &BusMessage) {
+        let owner = match message {
+            BusMessage::DrivingStep(step) => step.tenant.clone(),
+            BusMessage::Event(event) => event.tenant.clone(),
+            BusMessage::Geofence(_) | BusMessage::Anomaly(_) | BusMessage::System(_) => None,
+        };
+        let values = match message {
+            BusMessage::DrivingStep(step) => SignalRecorder::values(step),
+            _ => Vec::new(),
+        };
+
+        let mut current = self.current.lock().unwrap();
+        let mut keys = vec![None];
+        if owner.is_some() {
+            keys.push(owner);
+        }
+        for key in keys {
+            let summary = current.entry(key).or_insert_with(empty_summary);
+            *summary.counts.entry(message.topic()).or_default() += 1;
+            for value in &values {
+                summary.signals.insert(
+                    value.signal,
+                    SignalSample {
+                        value: value.value,
+                        timestamp_ms: value.timestamp_ms,
+                    },
+                );
+            }
+            if let BusMessage::DrivingStep(step) = message {
+                summary.last_step_id = step.step_id.clone().or(summary.last_step_id.take());
+            }
+        }
+    }
+
+    /// Publish the current window and start the next one, keeping the latest values
+    pub fn flush(&self) {
+        let at = chrono::Utc::now().to_rfc3339();
+        let interval_ms = self.interval.as_millis() as u64;
+
+        let mut current = self.current.lock().unwrap();
+        let mut summaries = HashMap::with_capacity(current.len().max(1));
+        current.entry(None).or_insert_with(empty_summary);
+        for (key, summary) in current.iter_mut() {
+            summaries.insert(
+                key.clone(),
+                Summary {
+                    at: at.clone(),
+                    interval_ms,
+                    ..summary.clone()
+                },
+            );
+            summary.counts.values_mut().for_each(|count| *count = 0);
+        }
+        self.summaries.send_replace(Arc::new(summaries));
+    }
+
+    /// Follow the bus and publish a summary every interval, until the process exits
+    pub fn spawn(&self, bus: &Bus) {
+        let summarizer = self.clone();
+        let mut rx = bus.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(message) => summarizer.observe(&message),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(_) => break,
+                }
+            }
+        });
+
+        let summarizer = self.clone();
+        let mut tick = tokio::time::interval(self.interval);
+        tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        tokio::spawn(async move {
+            loop {
+                tick.tick().await;
+                summarizer.flush();
+            }
+        });
+    }
+}
+
+/// Summary of a window without messages, every topic counted
+fn empty_summary() -> Summary {
+    Summary {
+        counts: BusMessage::TOPICS.iter().map(|topic| (*topic, 0)).collect(),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,22 @@
+use serde_json::Value;
+
+use crate::common::error::AppError;
+use crate::core::bus::{Bus, BusMessage};
+use crate::features::system::model::{SystemEvent, SystemEventKind, SystemEventQuery};
+use crate::features::system::service;
+
+pub async fn list(query: &SystemEventQuery) -> Result<Vec<SystemEvent>, AppError> {
+    service::get_system_events(query.kind, query.limit).await
+}
+
+/// Store a system event and broadcast it to stream clients
+///
+/// A failed store is only logged, the event is still broadcast.
+pub async fn publish(bus: &Bus, kind: SystemEventKind, message: impl Into<String>, details: Value) {
+    let event = SystemEvent::new(kind, message, details);
+    println!("🛰️ System: {}", event.message);
+    if let Err(e) = service::store_system_event(&event).await {
+        println!("❌ Failed to store system event: {}", e);
+    }
+    let _ = bus.send(BusMessage::System(event));
+}
@@ -0,0 +1,19 @@
+pub mod controller;
+pub mod model;
+pub mod service;
+
+use actix_web::{get, web, HttpResponse, Result};
+
+use crate::common::error::AppError;
+
+use model::SystemEventQuery;
+pub use model::{SystemEvent, SystemEventKind};
+
+#[get("/admin/system-events")]
+pub async fn list(query: web::Query<SystemEventQuery>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::list(&query).await?))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list);
+}
@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Lifecycle change of the server itself, which also determines the shape of its details
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemEventKind {
+    /// The HTTP server is bound: `host`, `port`, `transport` and `version`
+    ServerStarted,
+    /// The broker cancelled the step-notice consumer and it subscribed again: `queue`,
+    /// `consumer_tag` and `attempts`
+    ConsumerReconnected,
+    /// The RabbitMQ connection was lost and opened again: `queue`, `attempts` and
+    /// `downtime_ms`
+    BrokerReconnected,
+    /// A compaction run replaced raw frames past the retention window: the
+    /// `CompactionReport` of the run
+    RetentionPruned,
+    /// An archive run moved old steps to a segment file: the `ArchiveReport` of the run
+    FramesArchived,
+    /// A scheduled or manual export wrote its file: the `ExportRun`
+    ExportWritten,
+    /// An export run could not read, encode or write its steps: the `ExportRun` with its
+    /// `error`
+    ExportFailed,
+    /// A scenario run began publishing steps: `run_id`, `scenario` and `schedule`
+    SimulatorStarted,
+}
+
+impl SystemEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SystemEventKind::ServerStarted => "server_started",
+            SystemEventKind::ConsumerReconnected => "consumer_reconnected",
+            SystemEventKind::BrokerReconnected => "broker_reconnected",
+            SystemEventKind::RetentionPruned => "retention_pruned",
+            SystemEventKind::FramesArchived => "frames_archived",
+            SystemEventKind::ExportWritten => "export_written",
+            SystemEventKind::ExportFailed => "export_failed",
+            SystemEventKind::SimulatorStarted => "simulator_started",
+        }
+    }
+}
+
+impl std::str::FromStr for SystemEventKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "server_started" => Ok(SystemEventKind::ServerStarted),
+            "consumer_reconnected" => Ok(SystemEventKind::ConsumerReconnected),
+            "broker_reconnected" => Ok(SystemEventKind::BrokerReconnected),
+            "retention_pruned" => Ok(SystemEventKind::RetentionPruned),
+            "frames_archived" => Ok(SystemEventKind::FramesArchived),
+            "export_written" => Ok(SystemEventKind::ExportWritten),
+            "export_failed" => Ok(SystemEventKind::ExportFailed),
+            "simulator_started" => Ok(SystemEventKind::SimulatorStarted),
+            other => Err(format!("Unknown system event kind '{}'", other)),
+        }
+    }
+}
+
+/// Infrastructure change published on the bus next to the telemetry, stored in the
+/// `system_events` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SystemEvent {
+    pub id: String,
+    pub kind: SystemEventKind,
+    /// Human readable summary of the details
+    pub message: String,
+    /// Structured details, shaped according to `kind`
+    #[serde(default)]
+    pub details: Value,
+    pub timestamp: String,
+}
+
+impl SystemEvent {
+    pub fn new(kind: SystemEventKind, message: impl Into<String>, details: Value) -> Self {
+        SystemEvent {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            message: message.into(),
+            details,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+fn default_limit() -> u32 {
+    100
+}
+
+/// Query parameters accepted by `GET /admin/system-events`
+#[derive(Debug, Deserialize)]
+pub struct SystemEventQuery {
+    /// Only return events of this kind
+    pub kind: Option<SystemEventKind>,
+    /// Maximum number of events, newest first
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+}
@@ -0,0 +1,56 @@
+use sqlx::any::AnyRow;
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::features::system::model::{SystemEvent, SystemEventKind};
+
+fn system_event_from_row(row: &AnyRow) -> Result<SystemEvent, AppError> {
+    let kind: String = row.try_get("kind")?;
+    let details: String = row.try_get("details")?;
+
+    Ok(SystemEvent {
+        id: row.try_get("id")?,
+        kind: kind.parse().map_err(AppError::internal_server_error)?,
+        message: row.try_get("message")?,
+        details: serde_json::from_str(&details)?,
+        timestamp: row.try_get("timestamp")?,
+    })
+}
+
+pub async fn store_system_event(event: &SystemEvent) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(
+        "INSERT INTO system_events (id, kind, message, details, timestamp)
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(&event.id)
+    .bind(event.kind.as_str())
+    .bind(&event.message)
+    .bind(serde_json::to_string(&event.details)?)
+    .bind(&event.timestamp)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Most recent system events first, optionally restricted to one kind
+pub async fn get_system_events(
+    kind: Option<SystemEventKind>,
+    limit: u32,
+) -> Result<Vec<SystemEvent>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(
+        "SELECT id, kind, message, details, timestamp FROM system_events
+         WHERE $1 IS NULL OR kind = $1
+         ORDER BY timestamp DESC LIMIT $2",
+    )
+    .bind(kind.map(SystemEventKind::as_str))
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(system_event_from_row).collect()
+}
@@ -0,0 +1,110 @@
+use std::collections::HashSet;
+
+use crate::common::error::AppError;
+use crate::features::driving_step::service as step_service;
+use crate::features::scenario::catalog;
+use crate::features::tag::model::{TagFilter, TagSummary, TagTarget, MAX_TAG_LEN};
+use crate::features::tag::service;
+use crate::features::tenant::TenantScope;
+use crate::features::trip::service as trip_service;
+
+/// Tags are compared lowercase, made of letters, digits, `-`, `_`, `.` and `:`
+pub fn normalize(tag: &str) -> Result<String, AppError> {
+    let tag = tag.trim().to_lowercase();
+    let valid = !tag.is_empty()
+        && tag.len() <= MAX_TAG_LEN
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+    if !valid {
+        return Err(AppError::bad_request(format!(
+            "Invalid tag '{}', expected 1 to {} letters, digits, '-', '_', '.' or ':'",
+            tag, MAX_TAG_LEN
+        )));
+    }
+    Ok(tag)
+}
+
+/// Keys of the records of one kind carrying the tag of `filter`, `None` without one
+pub async fn filter(
+    target: TagTarget,
+    filter: &TagFilter,
+) -> Result<Option<HashSet<String>>, AppError> {
+    match &filter.tag {
+        Some(tag) => Ok(Some(service::tagged(target, &normalize(tag)?).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Fail with `404` unless the record exists and, for steps, is visible to the scope
+async fn ensure_exists(target: TagTarget, id: &str, scope: &TenantScope) -> Result<(), AppError> {
+    let exists = match target {
+        TagTarget::Step => step_service::get_step_frames(id, scope.name())
+            .await?
+            .is_some(),
+        TagTarget::Trip => trip_service::get_trip(id).await?.is_some(),
+        TagTarget::Scenario => catalog::find(id).await?.is_some(),
+    };
+    if !exists {
+        return Err(AppError::not_found(format!("{} '{}'", target.label(), id)));
+    }
+    Ok(())
+}
+
+pub async fn list(
+    target: TagTarget,
+    id: &str,
+    scope: &TenantScope,
+) -> Result<Vec<String>, AppError> {
+    ensure_exists(target, id, scope).await?;
+    service::get_tags(target, id).await
+}
+
+/// Attach tags to a record, returning all of its tags
+pub async fn add(
+    target: TagTarget,
+    id: &str,
+    tags: &[String],
+    scope: &TenantScope,
+) -> Result<Vec<String>, AppError> {
+    if tags.is_empty() {
+        return Err(AppError::bad_request("At least one tag is required"));
+    }
+    let tags = tags
+        .iter()
+        .map(|tag| normalize(tag))
+        .collect::<Result<Vec<_>, _>>()?;
+    ensure_exists(target, id, scope).await?;
+    service::add_tags(target, id, &tags).await?;
+    service::get_tags(target, id).await
+}
+
+pub async fn remove(
+    target: TagTarget,
+    id: &str,
+    tag: &str,
+    scope: &TenantScope,
+) -> Result<(), AppError> {
+    ensure_exists(target, id, scope).await?;
+    let tag = normalize(tag)?;
+    if !service::remove_tag(target, id, &tag).await? {
+        return Err(AppError::not_found(format!(
+            "Tag '{}' on {} '{}'",
+            tag,
+            target.label().to_lowercase(),
+            id
+        )));
+    }
+    Ok(())
+}
+
+pub async fn summaries() -> Result<Vec<TagSummary>, AppError> {
+    service::get_tag_summaries().await
+}
+
+pub async fn delete(name: &str) -> Result<(), AppError> {
+    if !service::delete_tag(&normalize(name)?).await? {
+        return Err(AppError::not_found(format!("Tag '{}'", name)));
+    }
+    Ok(())
+}
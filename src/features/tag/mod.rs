@@ -0,0 +1,63 @@
+pub mod controller;
+pub mod model;
+pub mod service;
+
+use actix_web::{delete, get, post, web, HttpResponse, Result};
+
+use crate::common::error::AppError;
+use crate::features::tenant::TenantScope;
+
+use model::TagRequest;
+pub use model::{TagFilter, TagTarget};
+
+/// Every tag with the number of steps, trips and scenarios carrying it
+#[get("/tags")]
+pub async fn list_tags() -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::summaries().await?))
+}
+
+/// Delete a tag from every record carrying it
+#[delete("/tags/{name}")]
+pub async fn delete_tag(name: web::Path<String>) -> Result<HttpResponse, AppError> {
+    controller::delete(&name).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Tags of a step, trip or scenario (`/driving-steps/{id}/tags`, `/trips/{id}/tags` or
+/// `/scenarios/{name}/tags`)
+#[get("/{target}/{id}/tags")]
+pub async fn list(
+    path: web::Path<(TagTarget, String)>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    let (target, id) = path.into_inner();
+    Ok(HttpResponse::Ok().json(controller::list(target, &id, &scope).await?))
+}
+
+#[post("/{target}/{id}/tags")]
+pub async fn add(
+    path: web::Path<(TagTarget, String)>,
+    request: web::Json<TagRequest>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    let (target, id) = path.into_inner();
+    Ok(HttpResponse::Ok().json(controller::add(target, &id, &request.tags, &scope).await?))
+}
+
+#[delete("/{target}/{id}/tags/{tag}")]
+pub async fn remove(
+    path: web::Path<(TagTarget, String, String)>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    let (target, id, tag) = path.into_inner();
+    controller::remove(target, &id, &tag, &scope).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_tags)
+        .service(delete_tag)
+        .service(list)
+        .service(add)
+        .service(remove);
+}
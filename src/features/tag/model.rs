@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+/// Longest accepted tag name
+pub const MAX_TAG_LEN: usize = 64;
+
+/// Kind of record a tag can be attached to, named after its collection in URLs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TagTarget {
+    #[serde(rename = "driving-steps")]
+    Step,
+    #[serde(rename = "trips")]
+    Trip,
+    #[serde(rename = "scenarios")]
+    Scenario,
+}
+
+impl TagTarget {
+    pub const ALL: [TagTarget; 3] = [TagTarget::Step, TagTarget::Trip, TagTarget::Scenario];
+
+    /// Join table linking tags to records of this kind
+    pub fn table(self) -> &'static str {
+        match self {
+            TagTarget::Step => "step_tags",
+            TagTarget::Trip => "trip_tags",
+            TagTarget::Scenario => "scenario_tags",
+        }
+    }
+
+    /// Column of the join table holding the record key
+    pub fn column(self) -> &'static str {
+        match self {
+            TagTarget::Step => "step_id",
+            TagTarget::Trip => "trip_id",
+            TagTarget::Scenario => "scenario",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TagTarget::Step => "Driving step",
+            TagTarget::Trip => "Trip",
+            TagTarget::Scenario => "Scenario",
+        }
+    }
+}
+
+/// Tag with the number of records of each kind carrying it, served by `GET /tags`
+#[derive(Debug, Clone, Serialize)]
+pub struct TagSummary {
+    pub name: String,
+    pub created_at: String,
+    pub steps: i64,
+    pub trips: i64,
+    pub scenarios: i64,
+}
+
+/// Body accepted by `POST /{collection}/{id}/tags`
+#[derive(Debug, Deserialize)]
+pub struct TagRequest {
+    pub tags: Vec<String>,
+}
+
+/// Filter of the list endpoints accepting `?tag=`
+#[derive(Debug, Default, Deserialize)]
+pub struct TagFilter {
+    /// Only return records carrying this tag
+    pub tag: Option<String>,
+}
@@ -0,0 +1,129 @@
+use std::collections::HashSet;
+
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::features::tag::model::{TagSummary, TagTarget};
+
+/// Attach `tags` to a record, creating the tags that do not exist yet
+pub async fn add_tags(target: TagTarget, id: &str, tags: &[String]) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut tx = pool.begin().await?;
+
+    for tag in tags {
+        sqlx::query("INSERT INTO tags (name, created_at) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+            .bind(tag)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(&format!(
+            "INSERT INTO {} (tag, {}) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+            target.table(),
+            target.column()
+        ))
+        .bind(tag)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Detach one tag from a record, returning whether it carried it
+pub async fn remove_tag(target: TagTarget, id: &str, tag: &str) -> Result<bool, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let result = sqlx::query(&format!(
+        "DELETE FROM {} WHERE tag = $1 AND {} = $2",
+        target.table(),
+        target.column()
+    ))
+    .bind(tag)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Tags of a record, in name order
+pub async fn get_tags(target: TagTarget, id: &str) -> Result<Vec<String>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let tags = sqlx::query_scalar(&format!(
+        "SELECT tag FROM {} WHERE {} = $1 ORDER BY tag ASC",
+        target.table(),
+        target.column()
+    ))
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(tags)
+}
+
+/// Keys of the records of one kind carrying `tag`
+pub async fn tagged(target: TagTarget, tag: &str) -> Result<HashSet<String>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let ids: Vec<String> = sqlx::query_scalar(&format!(
+        "SELECT {} FROM {} WHERE tag = $1",
+        target.column(),
+        target.table()
+    ))
+    .bind(tag)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids.into_iter().collect())
+}
+
+/// Every tag with its usage, in name order
+pub async fn get_tag_summaries() -> Result<Vec<TagSummary>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(
+        "SELECT name, created_at,
+                (SELECT COUNT(*) FROM step_tags WHERE tag = name) AS steps,
+                (SELECT COUNT(*) FROM trip_tags WHERE tag = name) AS trips,
+                (SELECT COUNT(*) FROM scenario_tags WHERE tag = name) AS scenarios
+         FROM tags ORDER BY name ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(TagSummary {
+                name: row.try_get("name")?,
+                created_at: row.try_get("created_at")?,
+                steps: row.try_get("steps")?,
+                trips: row.try_get("trips")?,
+                scenarios: row.try_get("scenarios")?,
+            })
+        })
+        .collect()
+}
+
+/// Delete a tag and detach it from every record, returning whether it existed
+pub async fn delete_tag(name: &str) -> Result<bool, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+    let mut tx = pool.begin().await?;
+
+    for target in TagTarget::ALL {
+        sqlx::query(&format!("DELETE FROM {} WHERE tag = $1", target.table()))
+            .bind(name)
+            .execute(&mut *tx)
+            .await?;
+    }
+    let result = sqlx::query("DELETE FROM tags WHERE name = $1")
+        .bind(name)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(result.rows_affected() > 0)
+}
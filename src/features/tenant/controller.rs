@@ -0,0 +1,108 @@
+use crate::common::error::AppError;
+use crate::features::tenant::model::{
+    CreatedTenant, CreatedViewerKey, KeyUsage, Role, Tenant, TenantRequest, TenantUsage,
+};
+use crate::features::tenant::registry::TenantRegistry;
+use crate::features::tenant::service;
+
+/// Every tenant with the steps and events stored under its name
+pub async fn list() -> Result<Vec<TenantUsage>, AppError> {
+    let mut tenants = Vec::new();
+    for (_, role, tenant) in service::get_tenants().await? {
+        if role == Role::Viewer {
+            continue;
+        }
+        let (steps, events) = service::usage(&tenant.name).await?;
+        tenants.push(TenantUsage {
+            tenant,
+            steps,
+            events,
+        });
+    }
+    Ok(tenants)
+}
+
+/// Frames and bytes stored with the key of every tenant, with its quotas
+pub async fn usage() -> Result<Vec<KeyUsage>, AppError> {
+    service::get_key_usage().await
+}
+
+/// Create a tenant with a fresh API key, accepted immediately
+pub async fn create(
+    registry: &TenantRegistry,
+    request: TenantRequest,
+) -> Result<CreatedTenant, AppError> {
+    if !registry.enabled() {
+        return Err(AppError::bad_request(
+            "Tenancy is disabled, start the server with ADMIN_API_KEY",
+        ));
+    }
+    if request.name.trim().is_empty() {
+        return Err(AppError::bad_request("Tenant name must not be empty"));
+    }
+    for (quota, value) in [
+        ("max_steps", request.max_steps),
+        ("max_messages_per_minute", request.max_messages_per_minute),
+        ("max_bytes", request.max_bytes),
+    ] {
+        if value == Some(0) {
+            return Err(AppError::bad_request(format!(
+                "{} must be at least 1",
+                quota
+            )));
+        }
+    }
+
+    let tenant = Tenant {
+        name: request.name,
+        max_steps: request.max_steps,
+        max_messages_per_minute: request.max_messages_per_minute,
+        max_bytes: request.max_bytes,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let api_key = new_api_key();
+    let key_hash = service::hash_key(&api_key);
+    if !service::store_tenant(&tenant, &key_hash).await? {
+        return Err(AppError::bad_request(format!(
+            "Tenant '{}' already exists",
+            tenant.name
+        )));
+    }
+    registry.register(key_hash, Role::Editor, tenant.clone());
+
+    Ok(CreatedTenant { tenant, api_key })
+}
+
+/// Give a tenant a fresh viewer key, revoking the previous one
+pub async fn create_viewer_key(
+    registry: &TenantRegistry,
+    name: &str,
+) -> Result<CreatedViewerKey, AppError> {
+    let api_key = new_api_key();
+    if !service::set_viewer_key(name, &service::hash_key(&api_key)).await? {
+        return Err(AppError::not_found(format!("Tenant '{}'", name)));
+    }
+    registry.reload().await?;
+
+    Ok(CreatedViewerKey {
+        tenant: name.to_string(),
+        api_key,
+    })
+}
+
+fn new_api_key() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// Revoke the API keys of a tenant; its data stays stored, visible to the administrator
+pub async fn delete(registry: &TenantRegistry, name: &str) -> Result<(), AppError> {
+    if !service::delete_tenant(name).await? {
+        return Err(AppError::not_found(format!("Tenant '{}'", name)));
+    }
+    registry.remove(name);
+    Ok(())
+}
@@ -0,0 +1,175 @@
+pub mod controller;
+pub mod model;
+pub mod registry;
+pub mod service;
+
+use std::future::{ready, Ready};
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::web::Data;
+use actix_web::{
+    delete, get, post, web, FromRequest, HttpMessage, HttpRequest, HttpResponse, Result,
+};
+use serde::Deserialize;
+
+use crate::common::error::AppError;
+use crate::features::health;
+
+use model::TenantRequest;
+pub use model::{Role, Tenant, TenantScope, Usage};
+pub use registry::TenantRegistry;
+
+/// `?api_key=`, for browser WebSocket and EventSource clients that cannot set headers
+#[derive(Deserialize)]
+struct ApiKeyQuery {
+    api_key: Option<String>,
+}
+
+/// Key of a request: `X-Api-Key`, `Authorization: Bearer <key>`, or `?api_key=`
+pub(crate) fn api_key(req: &HttpRequest) -> Option<String> {
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    header("x-api-key")
+        .or_else(|| {
+            header(AUTHORIZATION.as_str())
+                .and_then(|value| value.strip_prefix("Bearer ").map(str::to_string))
+        })
+        .or_else(|| {
+            web::Query::<ApiKeyQuery>::from_query(req.query_string())
+                .ok()
+                .and_then(|query| query.into_inner().api_key)
+        })
+}
+
+/// Routes a tenant key may call: those of tenant-scoped data, and read-only shared settings
+///
+/// Everything else (rules, scenarios, webhooks, tenants, `/admin`, ...) acts on data shared
+/// by all tenants and needs the administrator key.
+fn tenant_route(method: &Method, path: &str) -> bool {
+    match path.split('/').nth(1).unwrap_or_default() {
+        "driving-steps" | "ingest" | "frames" | "events" | "stream" | "stream-lab" | "ws"
+        | "trash" | "jobs" | "devices" => true,
+        // Signal history is recorded from every tenant's steps
+        "signals" => !path.ends_with("/history"),
+        "vehicles" | "validation" => method == Method::GET,
+        _ => false,
+    }
+}
+
+/// Routes a viewer key may call: the streams, whose messages it receives scrubbed
+fn viewer_route(method: &Method, path: &str) -> bool {
+    let segments: Vec<&str> = path.split('/').skip(1).collect();
+    method == Method::GET
+        && matches!(
+            segments.as_slice(),
+            ["ws"] | ["stream"] | ["stream-lab"] | ["stream", "summary"] | ["signals", _, "watch"]
+        )
+}
+
+/// Middleware resolving the `TenantScope` of every request, see `TenantRegistry`
+///
+/// Answers `401` for missing or unknown keys once tenancy is enabled, and `403` to tenant
+/// keys outside the tenant routes and to viewer keys outside the streams. Apps without a
+/// `Data<TenantRegistry>` are not checked, and neither are the health probes, which
+/// orchestrators call without a key.
+pub async fn authenticate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let probe = health::PROBE_PATHS.contains(&req.path());
+    if let Some(registry) = req.app_data::<Data<TenantRegistry>>().filter(|_| !probe) {
+        let scope = registry.authenticate(api_key(req.request()).as_deref())?;
+        if scope.role() == Role::Viewer && !viewer_route(req.method(), req.path()) {
+            return Err(AppError::forbidden(format!(
+                "Viewer keys cannot use {} {}",
+                req.method(),
+                req.path()
+            ))
+            .into());
+        }
+        if let Some(tenant) = scope.name() {
+            if !tenant_route(req.method(), req.path()) {
+                return Err(AppError::forbidden(format!(
+                    "Tenant '{}' cannot use {} {}",
+                    tenant,
+                    req.method(),
+                    req.path()
+                ))
+                .into());
+            }
+        }
+        req.extensions_mut().insert(scope);
+    }
+    next.call(req).await
+}
+
+/// Scope resolved by `authenticate`, unrestricted for apps not using the middleware
+impl FromRequest for TenantScope {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(req
+            .extensions()
+            .get::<TenantScope>()
+            .cloned()
+            .unwrap_or_default()))
+    }
+}
+
+/// Tenants with their quota and the amount of data they stored
+#[get("/tenants")]
+pub async fn list() -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::list().await?))
+}
+
+/// Create a tenant; the response is the only one carrying its API key
+#[post("/tenants")]
+pub async fn create(
+    registry: Data<TenantRegistry>,
+    request: web::Json<TenantRequest>,
+) -> Result<HttpResponse, AppError> {
+    let created = controller::create(&registry, request.into_inner()).await?;
+    Ok(HttpResponse::Created().json(created))
+}
+
+/// Frames and bytes stored per API key, and the quotas they count against
+#[get("/admin/usage")]
+pub async fn usage() -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::usage().await?))
+}
+
+/// Give a tenant a viewer key, following its streams scrubbed; replaces the previous one
+#[post("/tenants/{name}/viewer-key")]
+pub async fn create_viewer_key(
+    registry: Data<TenantRegistry>,
+    name: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let created = controller::create_viewer_key(&registry, &name).await?;
+    Ok(HttpResponse::Created().json(created))
+}
+
+#[delete("/tenants/{name}")]
+pub async fn delete(
+    registry: Data<TenantRegistry>,
+    name: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    controller::delete(&registry, &name).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list)
+        .service(create)
+        .service(create_viewer_key)
+        .service(delete)
+        .service(usage);
+}
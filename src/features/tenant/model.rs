@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::can::CanMessage;
+
+/// Group of users sharing the server, who only see the data stored with their API key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tenant {
+    pub name: String,
+    /// Most steps the tenant may store, unlimited when `None`
+    pub max_steps: Option<u64>,
+    /// Most frames the tenant may store in one minute, unlimited when `None`
+    pub max_messages_per_minute: Option<u64>,
+    /// Most payload bytes the tenant may store, unlimited when `None`
+    pub max_bytes: Option<u64>,
+    pub created_at: String,
+}
+
+/// Response of `POST /tenants/{name}/viewer-key`, the only one that reveals the key
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedViewerKey {
+    pub tenant: String,
+    pub api_key: String,
+}
+
+/// Body of `POST /tenants`
+#[derive(Debug, Clone, Deserialize)]
+pub struct TenantRequest {
+    pub name: String,
+    pub max_steps: Option<u64>,
+    pub max_messages_per_minute: Option<u64>,
+    pub max_bytes: Option<u64>,
+}
+
+/// Response of `POST /tenants`, the only one that reveals the API key
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedTenant {
+    #[serde(flatten)]
+    pub tenant: Tenant,
+    pub api_key: String,
+}
+
+/// Tenant with the amount of data stored under its name, listed by `GET /tenants`
+#[derive(Debug, Clone, Serialize)]
+pub struct TenantUsage {
+    #[serde(flatten)]
+    pub tenant: Tenant,
+    pub steps: u64,
+    pub events: u64,
+}
+
+/// Frames and payload bytes stored by one write, counted against the tenant's quotas
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Usage {
+    pub messages: u64,
+    pub bytes: u64,
+}
+
+impl Usage {
+    pub fn of(frames: &[CanMessage]) -> Self {
+        Usage {
+            messages: frames.len() as u64,
+            bytes: frames.iter().map(|frame| frame.data().len() as u64).sum(),
+        }
+    }
+}
+
+impl std::iter::Sum for Usage {
+    fn sum<I: Iterator<Item = Usage>>(iter: I) -> Self {
+        iter.fold(Usage::default(), |total, usage| Usage {
+            messages: total.messages + usage.messages,
+            bytes: total.bytes + usage.bytes,
+        })
+    }
+}
+
+/// Accounting of one tenant's API key, listed by `GET /admin/usage`
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyUsage {
+    pub tenant: String,
+    /// Frames stored with the key since the tenant was created
+    pub messages: u64,
+    /// Payload bytes of the frames it holds, trash excluded
+    pub bytes: u64,
+    /// Frames stored during the current one-minute window
+    pub messages_this_minute: u64,
+    pub max_steps: Option<u64>,
+    pub max_messages_per_minute: Option<u64>,
+    pub max_bytes: Option<u64>,
+}
+
+/// What an API key may do with the data it sees
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Stores data and reads it in full
+    #[default]
+    Editor,
+    /// Only follows the streams, whose messages are scrubbed by the server's `ScrubRules`
+    Viewer,
+}
+
+/// Tenant a request acts for, resolved from its API key by `tenant::authenticate`
+///
+/// `None` for the administrator and viewer keys, and for every request while tenancy is
+/// disabled: such requests see the data of every tenant and store data owned by none.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TenantScope(pub Option<Tenant>, pub Role);
+
+impl TenantScope {
+    /// Name the data of the request is restricted to and stored under
+    pub fn name(&self) -> Option<&str> {
+        self.0.as_ref().map(|tenant| tenant.name.as_str())
+    }
+
+    pub fn role(&self) -> Role {
+        self.1
+    }
+}
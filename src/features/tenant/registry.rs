@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::common::error::AppError;
+use crate::features::tenant::model::{Role, Tenant, TenantScope};
+use crate::features::tenant::service;
+
+/// API keys accepted by the server, checked on every request
+///
+/// Tenancy is enabled by an administrator key (`ADMIN_API_KEY`). Without one, keys are
+/// ignored and every request sees all data, as a single-tenant server. The viewer key
+/// (`VIEWER_API_KEY`) follows the streams of every tenant, scrubbed.
+#[derive(Clone, Default)]
+pub struct TenantRegistry {
+    admin_key_hash: Option<String>,
+    viewer_key_hash: Option<String>,
+    /// Tenants and the role of the key, by the hash of their API keys
+    tenants: Arc<Mutex<HashMap<String, (Tenant, Role)>>>,
+}
+
+impl TenantRegistry {
+    /// Accept the administrator and viewer keys and the keys of every tenant stored in the
+    /// database
+    pub async fn load(
+        admin_api_key: Option<&str>,
+        viewer_api_key: Option<&str>,
+    ) -> Result<Self, AppError> {
+        let registry = TenantRegistry {
+            admin_key_hash: admin_api_key.map(service::hash_key),
+            viewer_key_hash: viewer_api_key.map(service::hash_key),
+            tenants: Arc::default(),
+        };
+        registry.reload().await?;
+        Ok(registry)
+    }
+
+    /// Replace the registered tenants with those stored in the database
+    pub async fn reload(&self) -> Result<(), AppError> {
+        let stored = service::get_tenants().await?;
+        *self.tenants.lock().unwrap() = stored
+            .into_iter()
+            .map(|(key_hash, role, tenant)| (key_hash, (tenant, role)))
+            .collect();
+        Ok(())
+    }
+
+    /// Whether requests need an API key
+    pub fn enabled(&self) -> bool {
+        self.admin_key_hash.is_some()
+    }
+
+    pub fn register(&self, key_hash: String, role: Role, tenant: Tenant) {
+        self.tenants
+            .lock()
+            .unwrap()
+            .insert(key_hash, (tenant, role));
+    }
+
+    pub fn remove(&self, name: &str) {
+        self.tenants
+            .lock()
+            .unwrap()
+            .retain(|_, (tenant, _)| tenant.name != name);
+    }
+
+    /// Scope of a request presenting `api_key`, `401 Unauthorized` for missing or unknown keys
+    pub fn authenticate(&self, api_key: Option<&str>) -> Result<TenantScope, AppError> {
+        let Some(admin_key_hash) = &self.admin_key_hash else {
+            return Ok(TenantScope::default());
+        };
+        let api_key = api_key.ok_or_else(|| AppError::unauthorized("API key required"))?;
+
+        let key_hash = service::hash_key(api_key);
+        if key_hash == *admin_key_hash {
+            return Ok(TenantScope::default());
+        }
+        if self.viewer_key_hash.as_ref() == Some(&key_hash) {
+            return Ok(TenantScope(None, Role::Viewer));
+        }
+        self.tenants
+            .lock()
+            .unwrap()
+            .get(&key_hash)
+            .cloned()
+            .map(|(tenant, role)| TenantScope(Some(tenant), role))
+            .ok_or_else(|| AppError::unauthorized("Unknown API key"))
+    }
+}
@@ -0,0 +1,256 @@
+use sha2::{Digest, Sha256};
+use sqlx::any::AnyRow;
+use sqlx::{AnyConnection, Row};
+
+use crate::common::error::AppError;
+use crate::features::tenant::model::{KeyUsage, Role, Tenant, Usage};
+
+/// Length of the window `max_messages_per_minute` is counted over
+const USAGE_WINDOW_MS: i64 = 60_000;
+
+fn quota(row: &AnyRow, column: &str) -> Result<Option<u64>, AppError> {
+    let value: Option<i64> = row.try_get(column)?;
+    Ok(value.map(|value| value as u64))
+}
+
+fn tenant_from_row(row: &AnyRow) -> Result<Tenant, AppError> {
+    Ok(Tenant {
+        name: row.try_get("name")?,
+        max_steps: quota(row, "max_steps")?,
+        max_messages_per_minute: quota(row, "max_messages_per_minute")?,
+        max_bytes: quota(row, "max_bytes")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+/// Hex SHA-256 of an API key; only hashes are stored
+pub fn hash_key(api_key: &str) -> String {
+    Sha256::digest(api_key.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Insert a tenant, returning `false` when the name is taken
+pub async fn store_tenant(tenant: &Tenant, key_hash: &str) -> Result<bool, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let result = sqlx::query(
+        "INSERT INTO tenants
+         (name, key_hash, max_steps, max_messages_per_minute, max_bytes, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(&tenant.name)
+    .bind(key_hash)
+    .bind(tenant.max_steps.map(|max_steps| max_steps as i64))
+    .bind(tenant.max_messages_per_minute.map(|max| max as i64))
+    .bind(tenant.max_bytes.map(|max_bytes| max_bytes as i64))
+    .bind(&tenant.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Hash and role of every API key of every tenant, with the tenant
+pub async fn get_tenants() -> Result<Vec<(String, Role, Tenant)>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(
+        "SELECT name, key_hash, viewer_key_hash, max_steps, max_messages_per_minute,
+                max_bytes, created_at
+         FROM tenants ORDER BY created_at ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut keys = Vec::new();
+    for row in &rows {
+        let tenant = tenant_from_row(row)?;
+        if let Some(viewer_key_hash) = row.try_get("viewer_key_hash")? {
+            keys.push((viewer_key_hash, Role::Viewer, tenant.clone()));
+        }
+        keys.push((row.try_get("key_hash")?, Role::Editor, tenant));
+    }
+    Ok(keys)
+}
+
+/// Replace the viewer key of a tenant, returning `false` when it does not exist
+pub async fn set_viewer_key(name: &str, key_hash: &str) -> Result<bool, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let result = sqlx::query("UPDATE tenants SET viewer_key_hash = $1 WHERE name = $2")
+        .bind(key_hash)
+        .bind(name)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete a tenant, returning whether it existed; its data stays stored under its name
+pub async fn delete_tenant(name: &str) -> Result<bool, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let result = sqlx::query("DELETE FROM tenants WHERE name = $1")
+        .bind(name)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Payload bytes of the frames outside the trash of the tenant of the `tenants` row `t`
+const STORED_BYTES: &str = "(SELECT CAST(COALESCE(SUM(m.dlc), 0) AS BIGINT) FROM can_messages m
+     WHERE m.tenant = t.name AND m.deleted_at IS NULL)";
+
+/// Number of steps and events stored for `tenant`, trash excluded
+pub async fn usage(tenant: &str) -> Result<(u64, u64), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let steps: i64 = sqlx::query_scalar(
+        "SELECT COUNT(DISTINCT step_id) FROM can_messages
+         WHERE tenant = $1 AND deleted_at IS NULL",
+    )
+    .bind(tenant)
+    .fetch_one(pool)
+    .await?;
+    let events: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM events WHERE tenant = $1")
+        .bind(tenant)
+        .fetch_one(pool)
+        .await?;
+
+    Ok((steps as u64, events as u64))
+}
+
+/// Frames stored with the key of every tenant and bytes it holds, with its quotas
+pub async fn get_key_usage() -> Result<Vec<KeyUsage>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+    let window_start_ms = chrono::Utc::now().timestamp_millis() - USAGE_WINDOW_MS;
+
+    let rows = sqlx::query(&format!(
+        "SELECT t.name, t.max_steps, t.max_messages_per_minute, t.max_bytes,
+                COALESCE(u.messages, 0) AS messages, {} AS bytes,
+                CASE WHEN u.window_start_ms > $1 THEN u.window_messages ELSE 0 END
+                    AS messages_this_minute
+         FROM tenants t LEFT JOIN tenant_usage u ON u.tenant = t.name
+         ORDER BY t.created_at ASC",
+        STORED_BYTES
+    ))
+    .bind(window_start_ms)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter()
+        .map(|row| {
+            let count = |column: &str| -> Result<u64, AppError> {
+                Ok(row.try_get::<i64, _>(column)? as u64)
+            };
+            Ok(KeyUsage {
+                tenant: row.try_get("name")?,
+                messages: count("messages")?,
+                bytes: count("bytes")?,
+                messages_this_minute: count("messages_this_minute")?,
+                max_steps: quota(row, "max_steps")?,
+                max_messages_per_minute: quota(row, "max_messages_per_minute")?,
+                max_bytes: quota(row, "max_bytes")?,
+            })
+        })
+        .collect()
+}
+
+/// Fail when storing `steps` more steps holding `usage` would exceed a quota of `tenant`
+///
+/// Step and byte quotas answer `403 Forbidden`, the per-minute message quota
+/// `429 Too Many Requests`. Takes the connection of the transaction storing the steps, so
+/// the counts include the steps written before it. Steps and bytes are those stored
+/// outside the trash, so deleting data frees quota.
+pub async fn check_quota(
+    conn: &mut AnyConnection,
+    tenant: &str,
+    steps: u64,
+    usage: Usage,
+) -> Result<(), AppError> {
+    let Some(row) = sqlx::query(&format!(
+        "SELECT t.max_steps, t.max_messages_per_minute, t.max_bytes, {} AS bytes,
+                CASE WHEN u.window_start_ms > $1 THEN u.window_messages ELSE 0 END
+                    AS window_messages
+         FROM tenants t LEFT JOIN tenant_usage u ON u.tenant = t.name
+         WHERE t.name = $2",
+        STORED_BYTES
+    ))
+    .bind(chrono::Utc::now().timestamp_millis() - USAGE_WINDOW_MS)
+    .bind(tenant)
+    .fetch_optional(&mut *conn)
+    .await?
+    else {
+        return Ok(());
+    };
+
+    if let Some(max_messages) = quota(&row, "max_messages_per_minute")? {
+        let window_messages: i64 = row.try_get("window_messages")?;
+        if window_messages as u64 + usage.messages > max_messages {
+            return Err(AppError::too_many_requests(format!(
+                "Tenant '{}' reached its quota of {} messages per minute",
+                tenant, max_messages
+            )));
+        }
+    }
+    if let Some(max_bytes) = quota(&row, "max_bytes")? {
+        let bytes: i64 = row.try_get("bytes")?;
+        if bytes as u64 + usage.bytes > max_bytes {
+            return Err(AppError::forbidden(format!(
+                "Tenant '{}' reached its quota of {} bytes",
+                tenant, max_bytes
+            )));
+        }
+    }
+    if let Some(max_steps) = quota(&row, "max_steps")? {
+        let stored: i64 = sqlx::query_scalar(
+            "SELECT COUNT(DISTINCT step_id) FROM can_messages
+             WHERE tenant = $1 AND deleted_at IS NULL",
+        )
+        .bind(tenant)
+        .fetch_one(&mut *conn)
+        .await?;
+        if stored as u64 + steps > max_steps {
+            return Err(AppError::forbidden(format!(
+                "Tenant '{}' reached its quota of {} steps",
+                tenant, max_steps
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Count frames stored by `tenant`, in the transaction that stored them
+///
+/// The per-minute window restarts with the first message stored after it ran out. Bytes
+/// are not counted here: they are summed from the stored frames, which deletions reduce.
+pub async fn record_usage(
+    conn: &mut AnyConnection,
+    tenant: &str,
+    usage: Usage,
+) -> Result<(), AppError> {
+    let now_ms = chrono::Utc::now().timestamp_millis();
+
+    sqlx::query(
+        "INSERT INTO tenant_usage (tenant, messages, window_start_ms, window_messages)
+         VALUES ($1, $2, $3, $2)
+         ON CONFLICT (tenant) DO UPDATE SET
+             messages = tenant_usage.messages + $2,
+             window_messages = CASE WHEN tenant_usage.window_start_ms > $4
+                 THEN tenant_usage.window_messages + $2 ELSE $2 END,
+             window_start_ms = CASE WHEN tenant_usage.window_start_ms > $4
+                 THEN tenant_usage.window_start_ms ELSE $3 END",
+    )
+    .bind(tenant)
+    .bind(usage.messages as i64)
+    .bind(now_ms)
+    .bind(now_ms - USAGE_WINDOW_MS)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use crate::common::error::AppError;
+use crate::features::trash::model::PurgeReport;
+use crate::features::trash::service;
+
+/// Keeps soft-deleted steps, events and scenarios restorable for `retention`, then purges them
+#[derive(Clone)]
+pub struct TrashBin {
+    retention: Duration,
+}
+
+impl TrashBin {
+    pub fn new(retention: Duration) -> Self {
+        TrashBin { retention }
+    }
+
+    pub fn retention(&self) -> Duration {
+        self.retention
+    }
+
+    /// Time after which a record trashed at `deleted_at` is purged
+    pub fn purge_after(&self, deleted_at: &str) -> Option<String> {
+        let deleted_at = chrono::DateTime::parse_from_rfc3339(deleted_at).ok()?;
+        let retention = chrono::Duration::from_std(self.retention).ok()?;
+        Some((deleted_at + retention).to_rfc3339())
+    }
+
+    /// Delete for good every record trashed for longer than the retention
+    pub async fn purge(&self) -> Result<PurgeReport, AppError> {
+        let cutoff = chrono::Utc::now()
+            - chrono::Duration::from_std(self.retention)
+                .map_err(|e| AppError::internal_server_error(e.to_string()))?;
+        service::purge_before(cutoff).await
+    }
+
+    /// Purge every `interval`, until the process exits
+    pub fn spawn(&self, interval: Duration) {
+        let bin = self.clone();
+        let mut tick = tokio::time::interval(interval);
+        tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        tokio::spawn(async move {
+            loop {
+                tick.tick().await;
+                match bin.purge().await {
+                    Ok(report) if report.steps + report.events + report.scenarios > 0 => println!(
+                        "🗑️ Purged {} step(s), {} event(s) and {} scenario(s) from the trash",
+                        report.steps, report.events, report.scenarios
+                    ),
+                    Ok(_) => {}
+                    Err(e) => println!("❌ Trash purge failed: {}", e),
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,69 @@
+use crate::common::error::AppError;
+use crate::features::tenant::TenantScope;
+use crate::features::trash::bin::TrashBin;
+use crate::features::trash::model::{DeleteQuery, RestoredItem, TrashItem, TrashKind, TrashQuery};
+use crate::features::trash::service;
+
+/// Kinds of records the scope can see in the trash; scenarios are shared and only
+/// managed with the administrator key
+fn kinds(scope: &TenantScope) -> &'static [TrashKind] {
+    match scope.name() {
+        Some(_) => &[TrashKind::Step, TrashKind::Event],
+        None => &TrashKind::ALL,
+    }
+}
+
+/// Move a record to the trash, or delete it for good with `?permanent=true`
+pub async fn discard(
+    kind: TrashKind,
+    id: &str,
+    query: &DeleteQuery,
+    tenant: Option<&str>,
+) -> Result<(), AppError> {
+    let deleted = if query.permanent {
+        service::purge(kind, id, tenant).await?
+    } else {
+        service::soft_delete(kind, id, tenant).await?
+    };
+    if !deleted {
+        return Err(AppError::not_found(format!("{} '{}'", kind.label(), id)));
+    }
+    Ok(())
+}
+
+/// Trashed records visible to the scope, most recently deleted first
+pub async fn list(
+    bin: &TrashBin,
+    query: &TrashQuery,
+    scope: &TenantScope,
+) -> Result<Vec<TrashItem>, AppError> {
+    let mut items = Vec::new();
+    for &kind in kinds(scope) {
+        if query.kind.is_some_and(|wanted| wanted != kind) {
+            continue;
+        }
+        for (id, deleted_at) in service::get_trashed(kind, scope.name()).await? {
+            items.push(TrashItem {
+                kind,
+                id,
+                purge_after: bin.purge_after(&deleted_at).unwrap_or_default(),
+                deleted_at,
+            });
+        }
+    }
+    items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    Ok(items)
+}
+
+/// Take the trashed step, event or scenario with `id` out of the trash
+pub async fn restore(id: &str, scope: &TenantScope) -> Result<RestoredItem, AppError> {
+    for &kind in kinds(scope) {
+        if service::restore(kind, id, scope.name()).await? {
+            return Ok(RestoredItem {
+                kind,
+                id: id.to_string(),
+            });
+        }
+    }
+    Err(AppError::not_found(format!("Trashed record '{}'", id)))
+}
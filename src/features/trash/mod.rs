@@ -0,0 +1,33 @@
+pub mod bin;
+pub mod controller;
+pub mod model;
+pub mod service;
+
+use actix_web::web::Data;
+use actix_web::{get, post, web, HttpResponse, Result};
+
+use crate::common::error::AppError;
+use crate::features::tenant::TenantScope;
+
+pub use bin::TrashBin;
+use model::TrashQuery;
+pub use model::{DeleteQuery, TrashKind};
+
+/// Soft-deleted steps, events and scenarios, until the purge task deletes them
+#[get("/trash")]
+pub async fn list(
+    bin: Data<TrashBin>,
+    query: web::Query<TrashQuery>,
+    scope: TenantScope,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::list(&bin, &query, &scope).await?))
+}
+
+#[post("/trash/{id}/restore")]
+pub async fn restore(id: web::Path<String>, scope: TenantScope) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::restore(&id, &scope).await?))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list).service(restore);
+}
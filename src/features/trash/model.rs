@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+/// Kind of record moved to the trash instead of being deleted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrashKind {
+    Step,
+    Event,
+    Scenario,
+}
+
+impl TrashKind {
+    pub const ALL: [TrashKind; 3] = [TrashKind::Step, TrashKind::Event, TrashKind::Scenario];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TrashKind::Step => "Driving step",
+            TrashKind::Event => "Event",
+            TrashKind::Scenario => "Scenario",
+        }
+    }
+}
+
+/// Soft-deleted record, served by `GET /trash`
+#[derive(Debug, Clone, Serialize)]
+pub struct TrashItem {
+    pub kind: TrashKind,
+    /// Step id, event id or scenario name
+    pub id: String,
+    pub deleted_at: String,
+    /// Time after which the purge task deletes the record for good
+    pub purge_after: String,
+}
+
+/// Query parameters accepted by `GET /trash`
+#[derive(Debug, Deserialize)]
+pub struct TrashQuery {
+    /// Only return records of this kind
+    pub kind: Option<TrashKind>,
+}
+
+/// Query parameters accepted by the `DELETE` endpoints of trashable records
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteQuery {
+    /// Delete the record for good instead of moving it to the trash
+    #[serde(default)]
+    pub permanent: bool,
+}
+
+/// Records deleted for good by one purge
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PurgeReport {
+    pub steps: u64,
+    pub events: u64,
+    pub scenarios: u64,
+}
+
+/// Record taken out of the trash by `POST /trash/{id}/restore`
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoredItem {
+    pub kind: TrashKind,
+    pub id: String,
+}
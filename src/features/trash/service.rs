@@ -0,0 +1,169 @@
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::features::trash::model::{PurgeReport, TrashKind};
+
+/// Move a record to the trash, returning whether a live record matched
+///
+/// With a `tenant`, steps and events stored by others are not matched; scenarios are shared.
+pub async fn soft_delete(
+    kind: TrashKind,
+    id: &str,
+    tenant: Option<&str>,
+) -> Result<bool, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let sql = match kind {
+        TrashKind::Step => {
+            "UPDATE can_messages SET deleted_at = $1
+             WHERE step_id = $2 AND deleted_at IS NULL AND ($3 IS NULL OR tenant = $3)"
+        }
+        TrashKind::Event => {
+            "UPDATE events SET deleted_at = $1
+             WHERE id = $2 AND deleted_at IS NULL AND ($3 IS NULL OR tenant = $3)"
+        }
+        TrashKind::Scenario => {
+            "UPDATE scenarios SET deleted_at = $1 WHERE name = $2 AND deleted_at IS NULL"
+        }
+    };
+    let result = sqlx::query(sql)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(id)
+        .bind(tenant)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Take a record out of the trash, returning whether a trashed record matched
+pub async fn restore(kind: TrashKind, id: &str, tenant: Option<&str>) -> Result<bool, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let sql = match kind {
+        TrashKind::Step => {
+            "UPDATE can_messages SET deleted_at = NULL
+             WHERE step_id = $1 AND deleted_at IS NOT NULL AND ($2 IS NULL OR tenant = $2)"
+        }
+        TrashKind::Event => {
+            "UPDATE events SET deleted_at = NULL
+             WHERE id = $1 AND deleted_at IS NOT NULL AND ($2 IS NULL OR tenant = $2)"
+        }
+        TrashKind::Scenario => {
+            "UPDATE scenarios SET deleted_at = NULL WHERE name = $1 AND deleted_at IS NOT NULL"
+        }
+    };
+    let result = sqlx::query(sql).bind(id).bind(tenant).execute(pool).await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete a record for good, trashed or not, with what hangs off it: the frames, signal
+/// values, annotations, tags and signature of a step, the tags of a scenario
+///
+/// Returns whether a record matched.
+pub async fn purge(kind: TrashKind, id: &str, tenant: Option<&str>) -> Result<bool, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+    let mut transaction = pool.begin().await?;
+
+    let deleted = match kind {
+        TrashKind::Step => {
+            let frames = sqlx::query(
+                "DELETE FROM can_messages WHERE step_id = $1 AND ($2 IS NULL OR tenant = $2)",
+            )
+            .bind(id)
+            .bind(tenant)
+            .execute(&mut *transaction)
+            .await?
+            .rows_affected();
+            if frames > 0 {
+                for sql in [
+                    "DELETE FROM signal_values WHERE step_id = $1",
+                    "DELETE FROM annotations WHERE step_id = $1",
+                    "DELETE FROM step_tags WHERE step_id = $1",
+                    "DELETE FROM step_signatures WHERE step_id = $1",
+                    "DELETE FROM simulated_steps WHERE step_id = $1",
+                ] {
+                    sqlx::query(sql).bind(id).execute(&mut *transaction).await?;
+                }
+            }
+            frames > 0
+        }
+        TrashKind::Event => {
+            sqlx::query("DELETE FROM events WHERE id = $1 AND ($2 IS NULL OR tenant = $2)")
+                .bind(id)
+                .bind(tenant)
+                .execute(&mut *transaction)
+                .await?
+                .rows_affected()
+                > 0
+        }
+        TrashKind::Scenario => {
+            sqlx::query("DELETE FROM scenario_tags WHERE scenario = $1")
+                .bind(id)
+                .execute(&mut *transaction)
+                .await?;
+            sqlx::query("DELETE FROM scenarios WHERE name = $1")
+                .bind(id)
+                .execute(&mut *transaction)
+                .await?
+                .rows_affected()
+                > 0
+        }
+    };
+    transaction.commit().await?;
+
+    Ok(deleted)
+}
+
+/// Trashed records of one kind as `(id, deleted_at)`, most recently deleted first
+pub async fn get_trashed(
+    kind: TrashKind,
+    tenant: Option<&str>,
+) -> Result<Vec<(String, String)>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let sql = match kind {
+        TrashKind::Step => {
+            "SELECT step_id AS id, MAX(deleted_at) AS deleted_at FROM can_messages
+             WHERE step_id IS NOT NULL AND deleted_at IS NOT NULL AND ($1 IS NULL OR tenant = $1)
+             GROUP BY step_id ORDER BY deleted_at DESC"
+        }
+        TrashKind::Event => {
+            "SELECT id, deleted_at FROM events
+             WHERE deleted_at IS NOT NULL AND ($1 IS NULL OR tenant = $1)
+             ORDER BY deleted_at DESC"
+        }
+        TrashKind::Scenario => {
+            "SELECT name AS id, deleted_at FROM scenarios
+             WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC"
+        }
+    };
+    let rows = sqlx::query(sql).bind(tenant).fetch_all(pool).await?;
+
+    rows.iter()
+        .map(|row| Ok((row.try_get("id")?, row.try_get("deleted_at")?)))
+        .collect()
+}
+
+/// Delete for good every record trashed before `cutoff`
+pub async fn purge_before(cutoff: chrono::DateTime<chrono::Utc>) -> Result<PurgeReport, AppError> {
+    let mut report = PurgeReport::default();
+
+    for kind in TrashKind::ALL {
+        for (id, deleted_at) in get_trashed(kind, None).await? {
+            let expired = chrono::DateTime::parse_from_rfc3339(&deleted_at)
+                .is_ok_and(|deleted_at| deleted_at < cutoff);
+            if !expired || !purge(kind, &id, None).await? {
+                continue;
+            }
+            match kind {
+                TrashKind::Step => report.steps += 1,
+                TrashKind::Event => report.events += 1,
+                TrashKind::Scenario => report.scenarios += 1,
+            }
+        }
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,29 @@
+use crate::common::error::AppError;
+use crate::features::tag::controller as tag_controller;
+use crate::features::tag::{TagFilter, TagTarget};
+use crate::features::trip::model::{Trip, TripSummary};
+use crate::features::trip::scoring::TripScore;
+use crate::features::trip::service;
+
+pub async fn list(filter: &TagFilter) -> Result<Vec<Trip>, AppError> {
+    let mut trips = service::get_trips().await?;
+    if let Some(tagged) = tag_controller::filter(TagTarget::Trip, filter).await? {
+        trips.retain(|trip| tagged.contains(&trip.id));
+    }
+    Ok(trips)
+}
+
+pub async fn score(id: &str) -> Result<TripScore, AppError> {
+    service::get_trip(id)
+        .await?
+        .as_ref()
+        .map(TripScore::from)
+        .ok_or_else(|| AppError::not_found(format!("Trip '{}'", id)))
+}
+
+pub async fn summary(id: &str) -> Result<TripSummary, AppError> {
+    service::get_trip(id)
+        .await?
+        .map(TripSummary::from)
+        .ok_or_else(|| AppError::not_found(format!("Trip '{}'", id)))
+}
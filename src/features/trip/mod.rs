@@ -0,0 +1,32 @@
+pub mod controller;
+pub mod model;
+pub mod scoring;
+pub mod service;
+pub mod tracker;
+
+use actix_web::{get, web, HttpResponse, Result};
+
+use crate::common::error::AppError;
+use crate::features::tag::TagFilter;
+
+pub use model::{Trip, TripSummary};
+pub use tracker::TripTracker;
+
+#[get("/trips")]
+pub async fn list(query: web::Query<TagFilter>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::list(&query).await?))
+}
+
+#[get("/trips/{id}/summary")]
+pub async fn summary(id: web::Path<String>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::summary(&id).await?))
+}
+
+#[get("/trips/{id}/score")]
+pub async fn score(id: web::Path<String>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::score(&id).await?))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list).service(summary).service(score);
+}
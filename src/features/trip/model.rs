@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+/// One drive, from the engine starting to the engine stopping (or going idle)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trip {
+    pub id: String,
+    pub started_at: String,
+    /// `None` while the trip is still in progress
+    pub ended_at: Option<String>,
+    /// Sum of the step durations
+    pub duration_ms: u64,
+    pub distance_km: f64,
+    /// Highest vehicle speed in km/h
+    pub max_speed: f32,
+    pub step_count: u32,
+    /// Rule events raised while the trip was in progress
+    pub alerts_count: u32,
+    pub harsh_accelerations: u32,
+    pub harsh_brakings: u32,
+    pub harsh_cornerings: u32,
+    /// Fuel tank level of the first step carrying fuel data
+    pub start_tank_level: Option<u8>,
+    /// Fuel tank level of the last step carrying fuel data
+    pub end_tank_level: Option<u8>,
+    /// Range remaining reported by the last step carrying fuel data, in km
+    pub range_remaining: Option<u16>,
+    /// Distance driven while fuel consumption was reported, in km
+    #[serde(skip)]
+    pub fuel_distance_km: f64,
+    /// Fuel burnt over `fuel_distance_km`, in liters
+    #[serde(skip)]
+    pub fuel_used_l: f64,
+}
+
+/// Figures derived from a trip record for `GET /trips/{id}/summary`
+#[derive(Debug, Clone, Serialize)]
+pub struct TripSummary {
+    #[serde(flatten)]
+    pub trip: Trip,
+    pub in_progress: bool,
+    /// Mean speed over the trip duration in km/h
+    pub average_speed: f64,
+    /// Distance-weighted fuel consumption in L/100km
+    pub average_consumption: Option<f64>,
+    /// Tank percentage points used during the trip
+    pub fuel_used_pct: Option<i16>,
+}
+
+impl From<Trip> for TripSummary {
+    fn from(trip: Trip) -> Self {
+        let hours = trip.duration_ms as f64 / 3_600_000.0;
+        let average_speed = if hours > 0.0 {
+            trip.distance_km / hours
+        } else {
+            0.0
+        };
+        let average_consumption =
+            (trip.fuel_distance_km > 0.0).then(|| trip.fuel_used_l / trip.fuel_distance_km * 100.0);
+        let fuel_used_pct = trip
+            .start_tank_level
+            .zip(trip.end_tank_level)
+            .map(|(start, end)| start as i16 - end as i16);
+
+        TripSummary {
+            in_progress: trip.ended_at.is_none(),
+            average_speed: (average_speed * 10.0).round() / 10.0,
+            average_consumption: average_consumption.map(|value| (value * 10.0).round() / 10.0),
+            fuel_used_pct,
+            trip,
+        }
+    }
+}
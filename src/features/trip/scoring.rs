@@ -0,0 +1,130 @@
+use serde::Serialize;
+
+use crate::features::driving_step::DrivingStep;
+use crate::features::event::model::Severity;
+use crate::features::trip::model::Trip;
+
+/// Distance between left and right wheels used to derive the yaw rate, in meters
+const TRACK_WIDTH_M: f64 = 1.6;
+/// Longitudinal acceleration above which a speed-up counts as harsh, in m/s²
+const HARSH_ACCELERATION: f64 = 3.0;
+/// Longitudinal deceleration above which braking counts as harsh, in m/s²
+const HARSH_BRAKING: f64 = 3.5;
+/// Lateral acceleration above which a turn counts as harsh, in m/s²
+const HARSH_CORNERING: f64 = 4.0;
+
+fn kmh_to_ms(speed: f64) -> f64 {
+    speed / 3.6
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HarshKind {
+    Acceleration,
+    Braking,
+    Cornering,
+}
+
+impl HarshKind {
+    /// Name of the bus events reporting this kind of manoeuvre
+    pub fn event_name(self) -> &'static str {
+        match self {
+            HarshKind::Acceleration => "harsh_acceleration",
+            HarshKind::Braking => "harsh_braking",
+            HarshKind::Cornering => "harsh_cornering",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HarshKind::Acceleration => "Harsh acceleration",
+            HarshKind::Braking => "Harsh braking",
+            HarshKind::Cornering => "Harsh cornering",
+        }
+    }
+
+    /// Severity of the published events: hard braking points to a near miss
+    pub fn severity(self) -> Severity {
+        match self {
+            HarshKind::Acceleration => Severity::Info,
+            HarshKind::Braking => Severity::Warning,
+            HarshKind::Cornering => Severity::Warning,
+        }
+    }
+
+    /// Score points lost for each occurrence
+    fn penalty(self) -> f64 {
+        match self {
+            HarshKind::Acceleration => 5.0,
+            HarshKind::Braking => 8.0,
+            HarshKind::Cornering => 5.0,
+        }
+    }
+}
+
+/// Speed change from `previous` to `current` over the current step, in m/s²
+fn longitudinal_acceleration(previous: &DrivingStep, current: &DrivingStep) -> Option<f64> {
+    let seconds = current.duration_ms as f64 / 1000.0;
+    if seconds <= 0.0 {
+        return None;
+    }
+    let delta = current.speed.vehicle_speed as f64 - previous.speed.vehicle_speed as f64;
+    Some(kmh_to_ms(delta) / seconds)
+}
+
+/// Lateral acceleration from the left/right wheel speed difference, in m/s²
+fn lateral_acceleration(step: &DrivingStep) -> f64 {
+    let [front_left, front_right, rear_left, rear_right] = step.speed.wheel_speeds.map(f64::from);
+    let left = kmh_to_ms((front_left + rear_left) / 2.0);
+    let right = kmh_to_ms((front_right + rear_right) / 2.0);
+    let yaw_rate = (right - left) / TRACK_WIDTH_M;
+    kmh_to_ms(step.speed.vehicle_speed as f64) * yaw_rate
+}
+
+/// Harsh manoeuvres between two consecutive steps of a trip, with their acceleration
+pub fn harsh_manoeuvres(previous: &DrivingStep, current: &DrivingStep) -> Vec<(HarshKind, f64)> {
+    let mut manoeuvres = Vec::new();
+
+    if let Some(acceleration) = longitudinal_acceleration(previous, current) {
+        if acceleration > HARSH_ACCELERATION {
+            manoeuvres.push((HarshKind::Acceleration, acceleration));
+        } else if acceleration < -HARSH_BRAKING {
+            manoeuvres.push((HarshKind::Braking, acceleration));
+        }
+    }
+
+    let lateral = lateral_acceleration(current);
+    if lateral.abs() > HARSH_CORNERING {
+        manoeuvres.push((HarshKind::Cornering, lateral));
+    }
+
+    manoeuvres
+}
+
+/// Driving score of a trip: 100 minus a penalty per harsh manoeuvre, floored at 0
+#[derive(Debug, Clone, Serialize)]
+pub struct TripScore {
+    pub trip_id: String,
+    pub score: f64,
+    pub harsh_accelerations: u32,
+    pub harsh_brakings: u32,
+    pub harsh_cornerings: u32,
+    pub distance_km: f64,
+}
+
+impl From<&Trip> for TripScore {
+    fn from(trip: &Trip) -> Self {
+        let penalty = trip.harsh_accelerations as f64 * HarshKind::Acceleration.penalty()
+            + trip.harsh_brakings as f64 * HarshKind::Braking.penalty()
+            + trip.harsh_cornerings as f64 * HarshKind::Cornering.penalty();
+
+        TripScore {
+            trip_id: trip.id.clone(),
+            score: (100.0 - penalty).max(0.0),
+            harsh_accelerations: trip.harsh_accelerations,
+            harsh_brakings: trip.harsh_brakings,
+            harsh_cornerings: trip.harsh_cornerings,
+            distance_km: trip.distance_km,
+        }
+    }
+}
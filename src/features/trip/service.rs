@@ -0,0 +1,134 @@
+use sqlx::any::AnyRow;
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::features::trip::model::Trip;
+
+fn trip_from_row(row: &AnyRow) -> Result<Trip, AppError> {
+    let duration_ms: i64 = row.try_get("duration_ms")?;
+    let max_speed: f64 = row.try_get("max_speed")?;
+    let step_count: i64 = row.try_get("step_count")?;
+    let alerts_count: i64 = row.try_get("alerts_count")?;
+    let harsh_accelerations: i64 = row.try_get("harsh_accelerations")?;
+    let harsh_brakings: i64 = row.try_get("harsh_brakings")?;
+    let harsh_cornerings: i64 = row.try_get("harsh_cornerings")?;
+    let start_tank_level: Option<i64> = row.try_get("start_tank_level")?;
+    let end_tank_level: Option<i64> = row.try_get("end_tank_level")?;
+    let range_remaining: Option<i64> = row.try_get("range_remaining")?;
+
+    Ok(Trip {
+        id: row.try_get("id")?,
+        started_at: row.try_get("started_at")?,
+        ended_at: row.try_get("ended_at")?,
+        duration_ms: duration_ms as u64,
+        distance_km: row.try_get("distance_km")?,
+        max_speed: max_speed as f32,
+        step_count: step_count as u32,
+        alerts_count: alerts_count as u32,
+        harsh_accelerations: harsh_accelerations as u32,
+        harsh_brakings: harsh_brakings as u32,
+        harsh_cornerings: harsh_cornerings as u32,
+        start_tank_level: start_tank_level.map(|level| level as u8),
+        end_tank_level: end_tank_level.map(|level| level as u8),
+        range_remaining: range_remaining.map(|range| range as u16),
+        fuel_distance_km: row.try_get("fuel_distance_km")?,
+        fuel_used_l: row.try_get("fuel_used_l")?,
+    })
+}
+
+const TRIP_COLUMNS: &str = "id, started_at, ended_at, duration_ms, distance_km, max_speed,
+    step_count, alerts_count, harsh_accelerations, harsh_brakings, harsh_cornerings, start_tank_level, end_tank_level, range_remaining,
+    fuel_distance_km, fuel_used_l";
+
+/// Insert or update a trip record
+pub async fn save_trip(trip: &Trip) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(&format!(
+        "INSERT INTO trips ({}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+         ON CONFLICT (id) DO UPDATE SET
+             started_at = excluded.started_at, ended_at = excluded.ended_at,
+             duration_ms = excluded.duration_ms, distance_km = excluded.distance_km,
+             max_speed = excluded.max_speed, step_count = excluded.step_count,
+             alerts_count = excluded.alerts_count,
+             harsh_accelerations = excluded.harsh_accelerations,
+             harsh_brakings = excluded.harsh_brakings,
+             harsh_cornerings = excluded.harsh_cornerings,
+             start_tank_level = excluded.start_tank_level,
+             end_tank_level = excluded.end_tank_level,
+             range_remaining = excluded.range_remaining,
+             fuel_distance_km = excluded.fuel_distance_km, fuel_used_l = excluded.fuel_used_l",
+        TRIP_COLUMNS
+    ))
+    .bind(&trip.id)
+    .bind(&trip.started_at)
+    .bind(&trip.ended_at)
+    .bind(trip.duration_ms as i64)
+    .bind(trip.distance_km)
+    .bind(trip.max_speed as f64)
+    .bind(trip.step_count as i64)
+    .bind(trip.alerts_count as i64)
+    .bind(trip.harsh_accelerations as i64)
+    .bind(trip.harsh_brakings as i64)
+    .bind(trip.harsh_cornerings as i64)
+    .bind(trip.start_tank_level.map(|level| level as i64))
+    .bind(trip.end_tank_level.map(|level| level as i64))
+    .bind(trip.range_remaining.map(|range| range as i64))
+    .bind(trip.fuel_distance_km)
+    .bind(trip.fuel_used_l)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// All trips, most recent first
+/// Most recently started trip, in progress or not
+pub async fn get_last_trip() -> Result<Option<Trip>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let row = sqlx::query(&format!(
+        "SELECT {} FROM trips ORDER BY started_at DESC LIMIT 1",
+        TRIP_COLUMNS
+    ))
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(trip_from_row).transpose()
+}
+
+pub async fn get_trips() -> Result<Vec<Trip>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM trips ORDER BY started_at DESC",
+        TRIP_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(trip_from_row).collect()
+}
+
+pub async fn get_trip(id: &str) -> Result<Option<Trip>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let row = sqlx::query(&format!("SELECT {} FROM trips WHERE id = $1", TRIP_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    row.as_ref().map(trip_from_row).transpose()
+}
+
+/// Close trips left open by a previous run, which can no longer receive steps
+pub async fn close_open_trips(ended_at: &str) -> Result<u64, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let result = sqlx::query("UPDATE trips SET ended_at = $1 WHERE ended_at IS NULL")
+        .bind(ended_at)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,255 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::broadcast;
+
+use crate::common::error::AppError;
+use crate::core::bus::{Bus, BusMessage};
+use crate::features::driving_step::DrivingStep;
+use crate::features::event::model::{EventKind, SourceRef};
+use crate::features::event::{service as event_service, Event};
+use crate::features::trip::model::Trip;
+use crate::features::trip::scoring::{self, HarshKind};
+use crate::features::trip::service;
+
+/// Trip in progress, its latest step and when that step arrived
+struct OpenTrip {
+    trip: Trip,
+    last_step: Option<DrivingStep>,
+    last_step_at: Instant,
+}
+
+/// Trip records changed by a step, and the harsh-driving events it produced
+#[derive(Default)]
+pub struct StepOutcome {
+    pub trips: Vec<Trip>,
+    pub events: Vec<Event>,
+}
+
+/// Segments the stream of DrivingSteps into trips
+///
+/// A trip starts with the first step whose engine is running and ends with the first
+/// step whose engine is off, or once no step has arrived for `idle_timeout`.
+#[derive(Clone)]
+pub struct TripTracker {
+    current: Arc<Mutex<Option<OpenTrip>>>,
+    idle_timeout: Duration,
+}
+
+impl TripTracker {
+    pub fn new(idle_timeout: Duration) -> Self {
+        TripTracker {
+            current: Arc::new(Mutex::new(None)),
+            idle_timeout,
+        }
+    }
+
+    /// Create a tracker, closing trips a previous run left in progress
+    pub async fn load(idle_timeout: Duration) -> Result<Self, AppError> {
+        let closed = service::close_open_trips(&chrono::Utc::now().to_rfc3339()).await?;
+        if closed > 0 {
+            println!("🏁 Closed {} trip(s) left open by a previous run", closed);
+        }
+        Ok(TripTracker::new(idle_timeout))
+    }
+
+    /// Trip currently in progress, if any
+    pub fn current(&self) -> Option<Trip> {
+        self.current
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|open| open.trip.clone())
+    }
+
+    /// Fold a step into the current trip, scoring it against the previous step
+    pub fn record_step(&self, step: &DrivingStep) -> StepOutcome {
+        let mut current = self.current.lock().unwrap();
+        let mut changed = Vec::new();
+
+        // A long silence ends the previous trip even if its engine never reported off
+        if current
+            .as_ref()
+            .is_some_and(|open| open.last_step_at.elapsed() >= self.idle_timeout)
+        {
+            changed.extend(current.take().map(|open| close(open.trip)));
+        }
+
+        if !step.engine.engine_running {
+            changed.extend(current.take().map(|open| close(open.trip)));
+            return StepOutcome {
+                trips: changed,
+                events: Vec::new(),
+            };
+        }
+
+        let open = current.get_or_insert_with(|| OpenTrip {
+            trip: start(),
+            last_step: None,
+            last_step_at: Instant::now(),
+        });
+
+        let mut events = Vec::new();
+        if let Some(previous) = &open.last_step {
+            for (kind, acceleration) in scoring::harsh_manoeuvres(previous, step) {
+                match kind {
+                    HarshKind::Acceleration => open.trip.harsh_accelerations += 1,
+                    HarshKind::Braking => open.trip.harsh_brakings += 1,
+                    HarshKind::Cornering => open.trip.harsh_cornerings += 1,
+                }
+                events.push(
+                    Event::new(
+                        EventKind::HarshManoeuvre,
+                        kind.event_name(),
+                        format!(
+                            "{} at {:.1} m/s² on step '{}'",
+                            kind.label(),
+                            acceleration,
+                            step.step_name
+                        ),
+                        Some(step.step_name.clone()),
+                    )
+                    .with_severity(kind.severity())
+                    .with_source(SourceRef::of_step(step))
+                    .with_tenant(step.tenant.clone())
+                    .with_payload(serde_json::json!({
+                        "manoeuvre": kind,
+                        "acceleration_ms2": acceleration,
+                        "speed_kmh": step.speed.vehicle_speed,
+                        "trip_id": open.trip.id,
+                    })),
+                );
+            }
+        }
+
+        accumulate(&mut open.trip, step);
+        open.last_step = Some(step.clone());
+        open.last_step_at = Instant::now();
+        changed.push(open.trip.clone());
+        StepOutcome {
+            trips: changed,
+            events,
+        }
+    }
+
+    /// Count a rule alert against the trip in progress
+    pub fn record_alert(&self) -> Option<Trip> {
+        let mut current = self.current.lock().unwrap();
+        let open = current.as_mut()?;
+        open.trip.alerts_count += 1;
+        Some(open.trip.clone())
+    }
+
+    /// Close the trip in progress if it has been idle for longer than the timeout
+    pub fn expire_idle(&self) -> Option<Trip> {
+        let mut current = self.current.lock().unwrap();
+        if current
+            .as_ref()
+            .is_some_and(|open| open.last_step_at.elapsed() >= self.idle_timeout)
+        {
+            return current.take().map(|open| close(open.trip));
+        }
+        None
+    }
+
+    /// Segment the DrivingSteps on the bus into trips, persisting every change
+    ///
+    /// Harsh-driving events are stored and published on the bus like rule events.
+    pub fn spawn(&self, bus: &Bus) {
+        let tracker = self.clone();
+        let bus = bus.clone();
+        let mut rx = bus.subscribe();
+        let mut idle_check = tokio::time::interval(Duration::from_secs(1));
+
+        tokio::spawn(async move {
+            loop {
+                let outcome = tokio::select! {
+                    message = rx.recv() => match message {
+                        Ok(BusMessage::DrivingStep(step)) => tracker.record_step(&step),
+                        // Harsh-driving events are already counted by `record_step`
+                        Ok(BusMessage::Event(event)) if event.kind == EventKind::Rule =>
+                        {
+                            StepOutcome {
+                                trips: tracker.record_alert().into_iter().collect(),
+                                events: Vec::new(),
+                            }
+                        }
+                        Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(_) => break,
+                    },
+                    _ = idle_check.tick() => StepOutcome {
+                        trips: tracker.expire_idle().into_iter().collect(),
+                        events: Vec::new(),
+                    },
+                };
+
+                for event in outcome.events {
+                    println!("⚠️ {}", event.message);
+                    if let Err(e) = event_service::store_event(&event).await {
+                        println!("❌ Failed to store event '{}': {}", event.name, e);
+                    }
+                    let _ = bus.send(BusMessage::Event(event));
+                }
+
+                for trip in outcome.trips {
+                    if let Some(ended_at) = &trip.ended_at {
+                        println!(
+                            "🏁 Trip {} ended at {}: {:.2} km, max {:.1} km/h",
+                            trip.id, ended_at, trip.distance_km, trip.max_speed
+                        );
+                    }
+                    if let Err(e) = service::save_trip(&trip).await {
+                        println!("❌ Failed to save trip {}: {}", trip.id, e);
+                    }
+                }
+            }
+        });
+    }
+}
+
+fn start() -> Trip {
+    let trip = Trip {
+        id: uuid::Uuid::new_v4().to_string(),
+        started_at: chrono::Utc::now().to_rfc3339(),
+        ended_at: None,
+        duration_ms: 0,
+        distance_km: 0.0,
+        max_speed: 0.0,
+        step_count: 0,
+        alerts_count: 0,
+        harsh_accelerations: 0,
+        harsh_brakings: 0,
+        harsh_cornerings: 0,
+        start_tank_level: None,
+        end_tank_level: None,
+        range_remaining: None,
+        fuel_distance_km: 0.0,
+        fuel_used_l: 0.0,
+    };
+    println!("🚦 Trip {} started", trip.id);
+    trip
+}
+
+fn close(mut trip: Trip) -> Trip {
+    trip.ended_at = Some(chrono::Utc::now().to_rfc3339());
+    trip
+}
+
+/// Add the distance, speed and fuel figures of one step to a trip
+fn accumulate(trip: &mut Trip, step: &DrivingStep) {
+    // Distance covered at the step's speed over its duration
+    let distance_km = step.speed.vehicle_speed as f64 * step.duration_ms as f64 / 3_600_000.0;
+
+    trip.duration_ms += step.duration_ms;
+    trip.distance_km += distance_km;
+    trip.max_speed = trip.max_speed.max(step.speed.vehicle_speed);
+    trip.step_count += 1;
+
+    if let Some(fuel) = &step.fuel {
+        trip.start_tank_level.get_or_insert(fuel.tank_level);
+        trip.end_tank_level = Some(fuel.tank_level);
+        trip.range_remaining = Some(fuel.range_remaining);
+        trip.fuel_distance_km += distance_km;
+        trip.fuel_used_l += fuel.consumption as f64 * distance_km / 100.0;
+    }
+}
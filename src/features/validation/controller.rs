@@ -0,0 +1,209 @@
+use crate::common::error::AppError;
+use crate::config::transport::StepTransport;
+use crate::core::can::{CanId, CanMessage};
+use crate::core::signals;
+use crate::features::driving_step::model::{DecodeFailure, StoredStep};
+use crate::features::driving_step::service as step_service;
+use crate::features::validation::model::{
+    RejectedBatch, RejectedFrame, RejectedQuery, ValidationContext, ValidationFailure,
+    ValidationMode, Violation,
+};
+use crate::features::validation::service;
+use crate::features::validation::FrameValidator;
+use crate::features::vehicle::service as vehicle_service;
+
+pub async fn list(query: &RejectedQuery) -> Result<Vec<RejectedFrame>, AppError> {
+    service::get_rejected(
+        query.rule.as_deref(),
+        query.batch_id.as_deref(),
+        query.limit,
+    )
+    .await
+}
+
+/// Quarantine every frame of a step, each with the rules it broke
+fn quarantine(
+    frames: Vec<CanMessage>,
+    violations: &[Violation],
+    context: &ValidationContext,
+) -> Vec<RejectedFrame> {
+    let batch_id = uuid::Uuid::new_v4().to_string();
+    let rejected_at = chrono::Utc::now().to_rfc3339();
+    let mut offending: Vec<(usize, &str)> = violations
+        .iter()
+        .map(|violation| (violation.frame, violation.can_id.as_str()))
+        .collect();
+    offending.sort();
+    offending.dedup();
+    let offending: Vec<&str> = offending.into_iter().map(|(_, can_id)| can_id).collect();
+
+    frames
+        .into_iter()
+        .enumerate()
+        .map(|(position, frame)| {
+            let broken: Vec<&Violation> = violations
+                .iter()
+                .filter(|violation| violation.frame == position)
+                .collect();
+            let (rule, reason) = match broken.first() {
+                Some(first) => (
+                    first.rule.to_string(),
+                    broken
+                        .iter()
+                        .map(|violation| violation.reason.as_str())
+                        .collect::<Vec<_>>()
+                        .join("; "),
+                ),
+                None => (
+                    "quarantined_step".to_string(),
+                    format!("Step holds offending frames {}", offending.join(", ")),
+                ),
+            };
+            RejectedFrame {
+                id: uuid::Uuid::new_v4().to_string(),
+                batch_id: batch_id.clone(),
+                frame,
+                endianness: context.endianness,
+                vehicle_id: context.vehicle_id.map(str::to_string),
+                step_name: context.step_name.to_string(),
+                rule,
+                reason,
+                rejected_at: rejected_at.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Run the rules over every step of a request, returning the steps to store and the number
+/// of frames quarantined
+///
+/// In reject mode any violation fails the request. In quarantine mode a step holding an
+/// offending frame is stored whole in `rejected_frames`, to be discarded or accepted later.
+pub async fn screen(
+    validator: &FrameValidator,
+    steps: Vec<Vec<CanMessage>>,
+    context: &ValidationContext<'_>,
+) -> Result<Result<(Vec<Vec<CanMessage>>, usize), ValidationFailure>, AppError> {
+    let mut kept = Vec::with_capacity(steps.len());
+    let mut quarantined = Vec::new();
+
+    for (index, frames) in steps.into_iter().enumerate() {
+        let violations = validator.check(&frames, context);
+        if violations.is_empty() {
+            kept.push(frames);
+            continue;
+        }
+        if validator.mode() == ValidationMode::Reject {
+            return Ok(Err(ValidationFailure {
+                error: format!("Step {} of the body breaks the validation rules", index + 1),
+                violations,
+            }));
+        }
+        quarantined.extend(quarantine(frames, &violations, context));
+    }
+
+    if !quarantined.is_empty() {
+        println!("🚫 Quarantined {} CAN frames", quarantined.len());
+        service::store_rejected(&quarantined).await?;
+    }
+    Ok(Ok((kept, quarantined.len())))
+}
+
+async fn batch_frames(batch_id: &str) -> Result<Vec<RejectedFrame>, AppError> {
+    let frames = service::get_batch(batch_id).await?;
+    if frames.is_empty() {
+        return Err(AppError::not_found(format!(
+            "Quarantined step '{}'",
+            batch_id
+        )));
+    }
+    Ok(frames)
+}
+
+/// Decode the frames of a batch with the profile of its vehicle
+async fn decode(frames: &[RejectedFrame]) -> Result<Result<(), String>, AppError> {
+    let first = &frames[0];
+    let profile_name = match &first.vehicle_id {
+        Some(vin) => vehicle_service::get_vehicle(vin)
+            .await?
+            .map(|vehicle| vehicle.can_profile),
+        None => None,
+    };
+    let profile_name = profile_name.as_deref().unwrap_or(signals::DEFAULT_PROFILE);
+    let Some(profile) = signals::profile(profile_name) else {
+        return Ok(Err(format!("Unknown CAN profile '{}'", profile_name)));
+    };
+
+    let messages: Vec<CanMessage> = frames
+        .iter()
+        .map(|rejected| rejected.frame.clone())
+        .collect();
+    Ok(profile
+        .decode_step(&messages, String::new(), first.endianness.is_big_endian())
+        .map(|_| ()))
+}
+
+/// A quarantined step with the violations of its frames
+pub async fn batch(batch_id: &str) -> Result<RejectedBatch, AppError> {
+    let frames = batch_frames(batch_id).await?;
+    let decode_error = decode(&frames).await?.err();
+    let first = &frames[0];
+
+    Ok(RejectedBatch {
+        batch_id: batch_id.to_string(),
+        endianness: first.endianness,
+        vehicle_id: first.vehicle_id.clone(),
+        step_name: first.step_name.clone(),
+        rejected_at: first.rejected_at.clone(),
+        decode_error,
+        frames,
+    })
+}
+
+pub async fn discard(batch_id: &str) -> Result<(), AppError> {
+    if service::delete_batch(batch_id).await? == 0 {
+        return Err(AppError::not_found(format!(
+            "Quarantined step '{}'",
+            batch_id
+        )));
+    }
+    Ok(())
+}
+
+/// Store and publish a quarantined step regardless of the rules it broke
+///
+/// The frames still have to decode; a step that does not stays quarantined.
+pub async fn accept(
+    batch_id: &str,
+    transport: &StepTransport,
+) -> Result<Result<StoredStep, DecodeFailure>, AppError> {
+    let frames = batch_frames(batch_id).await?;
+    let first = frames[0].clone();
+    if let Err(error) = decode(&frames).await? {
+        return Ok(Err(DecodeFailure {
+            error,
+            endianness: first.endianness,
+            can_ids: frames
+                .iter()
+                .map(|rejected| CanId::from(rejected.frame.id).to_string())
+                .collect(),
+        }));
+    }
+
+    let messages = frames.into_iter().map(|rejected| rejected.frame).collect();
+    let stored = step_service::publish_frames(
+        messages,
+        first.endianness,
+        first.vehicle_id.as_deref(),
+        None,
+        &first.step_name,
+        transport,
+    )
+    .await?;
+    service::delete_batch(batch_id).await?;
+    println!(
+        "✅ Accepted quarantined step '{}' as step {}",
+        batch_id, stored.step_id
+    );
+    Ok(Ok(stored))
+}
@@ -0,0 +1,63 @@
+pub mod controller;
+pub mod model;
+pub mod rules;
+pub mod service;
+pub mod validator;
+
+use actix_web::web::Data;
+use actix_web::{delete, get, post, web, HttpResponse, Result};
+
+use crate::common::error::AppError;
+use crate::config::transport::StepTransport;
+
+use model::RejectedQuery;
+pub use model::{RejectedBatch, RejectedFrame, ValidationContext, ValidationMode, Violation};
+pub use rules::FrameRule;
+pub use validator::FrameValidator;
+
+/// Frames quarantined by the validation stage, newest first
+#[get("/rejected-frames")]
+pub async fn list(query: web::Query<RejectedQuery>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::list(&query).await?))
+}
+
+/// Every frame of a quarantined step, with the rules they broke
+#[get("/rejected-frames/batches/{batch_id}")]
+pub async fn batch(batch_id: web::Path<String>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::batch(&batch_id).await?))
+}
+
+#[delete("/rejected-frames/batches/{batch_id}")]
+pub async fn discard(batch_id: web::Path<String>) -> Result<HttpResponse, AppError> {
+    controller::discard(&batch_id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Store and publish a quarantined step, bypassing the rules
+#[post("/rejected-frames/batches/{batch_id}/accept")]
+pub async fn accept(
+    batch_id: web::Path<String>,
+    transport: Data<StepTransport>,
+) -> Result<HttpResponse, AppError> {
+    match controller::accept(&batch_id, &transport).await? {
+        Ok(stored) => Ok(HttpResponse::Accepted().json(stored)),
+        Err(failure) => Ok(HttpResponse::UnprocessableEntity().json(failure)),
+    }
+}
+
+/// Mode and rules of the validation stage
+#[get("/validation/rules")]
+pub async fn settings(validator: Data<FrameValidator>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "mode": validator.mode(),
+        "rules": validator.rules(),
+    })))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list)
+        .service(batch)
+        .service(discard)
+        .service(accept)
+        .service(settings);
+}
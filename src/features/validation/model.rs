@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::can::{CanMessage, Endianness};
+use crate::core::signals::SignalProfile;
+
+/// What happens to a step whose frames break a validation rule
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidationMode {
+    /// Refuse the whole request (`422`), storing nothing
+    #[default]
+    Reject,
+    /// Move the steps holding offending frames to `rejected_frames` for review, storing the rest
+    Quarantine,
+}
+
+impl std::str::FromStr for ValidationMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "reject" => Ok(ValidationMode::Reject),
+            "quarantine" => Ok(ValidationMode::Quarantine),
+            other => Err(format!("Unknown validation mode '{}'", other)),
+        }
+    }
+}
+
+/// What the rules know about the frames they check
+#[derive(Debug, Clone, Copy)]
+pub struct ValidationContext<'a> {
+    /// Signal map of the vehicle, the default profile without one
+    pub profile: &'static SignalProfile,
+    pub endianness: Endianness,
+    pub vehicle_id: Option<&'a str>,
+    /// Name the steps are published under
+    pub step_name: &'a str,
+}
+
+/// One rule broken by one frame
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Violation {
+    /// Index of the frame in the checked step
+    #[serde(skip)]
+    pub frame: usize,
+    /// Hex CAN ID of the frame
+    pub can_id: String,
+    pub rule: &'static str,
+    pub reason: String,
+}
+
+/// Body of the `422` answered in reject mode
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationFailure {
+    pub error: String,
+    pub violations: Vec<Violation>,
+}
+
+/// Frame kept out of storage by the validation stage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RejectedFrame {
+    pub id: String,
+    /// Shared by the frames of one quarantined step, which are reviewed together
+    pub batch_id: String,
+    pub frame: CanMessage,
+    pub endianness: Endianness,
+    pub vehicle_id: Option<String>,
+    pub step_name: String,
+    /// Name of the rule that rejected the frame, `quarantined_step` for the valid frames of
+    /// the step
+    pub rule: String,
+    pub reason: String,
+    pub rejected_at: String,
+}
+
+/// Frames of one quarantined step, as returned by `GET /rejected-frames/batches/{batch_id}`
+#[derive(Debug, Clone, Serialize)]
+pub struct RejectedBatch {
+    pub batch_id: String,
+    pub endianness: Endianness,
+    pub vehicle_id: Option<String>,
+    pub step_name: String,
+    pub rejected_at: String,
+    /// Every frame of the step, in ingestion order
+    pub frames: Vec<RejectedFrame>,
+    /// Outcome of decoding the frames again, `None` when they form a valid step
+    pub decode_error: Option<String>,
+}
+
+fn default_limit() -> u32 {
+    100
+}
+
+/// Query parameters accepted by `GET /rejected-frames`
+#[derive(Debug, Deserialize)]
+pub struct RejectedQuery {
+    /// Only return frames rejected by this rule
+    pub rule: Option<String>,
+    /// Only return the frames of this quarantined step
+    pub batch_id: Option<String>,
+    /// Maximum number of frames, newest first
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+}
@@ -0,0 +1,189 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use serde_json::Value;
+
+use crate::core::can::{CanId, CanMessage};
+use crate::features::validation::model::{ValidationContext, Violation};
+
+/// Check applied to the frames of one step before they are stored
+///
+/// Rules see every frame of a step at once, so checks needing the decoded values (such as
+/// `Plausibility`) can unpack the group. Register custom rules with
+/// `FrameValidator::register`.
+pub trait FrameRule: Send + Sync {
+    /// Name stored with the frames the rule rejects
+    fn name(&self) -> &'static str;
+
+    fn check(&self, frames: &[CanMessage], context: &ValidationContext) -> Vec<Violation>;
+}
+
+fn violation(rule: &dyn FrameRule, index: usize, frame: &CanMessage, reason: String) -> Violation {
+    Violation {
+        frame: index,
+        can_id: CanId::from(frame.id).to_string(),
+        rule: rule.name(),
+        reason,
+    }
+}
+
+/// Frames must have the DLC their profile defines for their CAN ID
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DlcConsistency;
+
+impl FrameRule for DlcConsistency {
+    fn name(&self) -> &'static str {
+        "dlc_consistency"
+    }
+
+    fn check(&self, frames: &[CanMessage], context: &ValidationContext) -> Vec<Violation> {
+        frames
+            .iter()
+            .enumerate()
+            .filter_map(|(index, frame)| {
+                let expected = context.profile.dlc(frame.id)?;
+                (frame.dlc != expected).then(|| {
+                    violation(
+                        self,
+                        index,
+                        frame,
+                        format!("DLC {} where the profile defines {}", frame.dlc, expected),
+                    )
+                })
+            })
+            .collect()
+    }
+}
+
+/// Frames must carry a CAN ID of their profile
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IdWhitelist;
+
+impl FrameRule for IdWhitelist {
+    fn name(&self) -> &'static str {
+        "id_whitelist"
+    }
+
+    fn check(&self, frames: &[CanMessage], context: &ValidationContext) -> Vec<Violation> {
+        frames
+            .iter()
+            .enumerate()
+            .filter(|(_, frame)| context.profile.dlc(frame.id).is_none())
+            .map(|(index, frame)| {
+                violation(
+                    self,
+                    index,
+                    frame,
+                    format!("CAN ID not defined by profile '{}'", context.profile.name),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Decoded signals must lie within the range of the profile
+///
+/// Steps that do not decode are left to the decode check that follows validation.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Plausibility;
+
+impl FrameRule for Plausibility {
+    fn name(&self) -> &'static str {
+        "plausibility"
+    }
+
+    fn check(&self, frames: &[CanMessage], context: &ValidationContext) -> Vec<Violation> {
+        let decoded =
+            context
+                .profile
+                .decode_step(frames, String::new(), context.endianness.is_big_endian());
+        let Ok(value) =
+            decoded.and_then(|step| serde_json::to_value(step).map_err(|e| e.to_string()))
+        else {
+            return Vec::new();
+        };
+
+        let mut violations = Vec::new();
+        for signal in context.profile.signals {
+            let Some(index) = frames.iter().position(|frame| frame.id == signal.can_id()) else {
+                continue;
+            };
+            let values = match value.pointer(&signal.pointer()) {
+                Some(Value::Array(items)) => items.iter().filter_map(Value::as_f64).collect(),
+                Some(other) => other.as_f64().into_iter().collect(),
+                None => Vec::new(),
+            };
+            if let Some(outlier) = values
+                .into_iter()
+                .find(|value| *value < signal.min || *value > signal.max)
+            {
+                violations.push(violation(
+                    self,
+                    index,
+                    &frames[index],
+                    format!(
+                        "{} = {} is outside [{}, {}]",
+                        signal.name, outlier, signal.min, signal.max
+                    ),
+                ));
+            }
+        }
+        violations
+    }
+}
+
+/// Timestamps (ms) of the frames seen in the last second, by vehicle and CAN ID
+type RateWindows = HashMap<(Option<String>, u16), VecDeque<i64>>;
+
+/// At most `max_per_second` frames per CAN ID and vehicle, over a sliding one-second window
+/// of frame timestamps
+///
+/// Frames without an RFC3339 timestamp are not counted.
+#[derive(Debug)]
+pub struct RateLimit {
+    max_per_second: u32,
+    seen: Mutex<RateWindows>,
+}
+
+impl RateLimit {
+    pub fn new(max_per_second: u32) -> Self {
+        RateLimit {
+            max_per_second,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl FrameRule for RateLimit {
+    fn name(&self) -> &'static str {
+        "rate_limit"
+    }
+
+    fn check(&self, frames: &[CanMessage], context: &ValidationContext) -> Vec<Violation> {
+        let mut seen = self.seen.lock().unwrap();
+        let mut violations = Vec::new();
+        for (index, frame) in frames.iter().enumerate() {
+            let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(&frame.timestamp) else {
+                continue;
+            };
+            let now_ms = timestamp.timestamp_millis();
+            let window = seen
+                .entry((context.vehicle_id.map(str::to_string), frame.id))
+                .or_default();
+            while window.front().is_some_and(|first| *first <= now_ms - 1000) {
+                window.pop_front();
+            }
+            if window.len() >= self.max_per_second as usize {
+                violations.push(violation(
+                    self,
+                    index,
+                    frame,
+                    format!("More than {} frames in one second", self.max_per_second),
+                ));
+                continue;
+            }
+            window.push_back(now_ms);
+        }
+        violations
+    }
+}
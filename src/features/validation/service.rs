@@ -0,0 +1,125 @@
+use sqlx::any::AnyRow;
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::config::db;
+use crate::config::encryption;
+use crate::core::can::{CanId, CanMessage};
+use crate::features::validation::model::RejectedFrame;
+
+const COLUMNS: &str = "id, batch_id, can_id, dlc, data, fd, brs, timestamp, endian, vehicle_id,
+     step_name, rule, reason, rejected_at";
+
+fn rejected_frame_from_row(row: &AnyRow) -> Result<RejectedFrame, AppError> {
+    let can_id: i64 = row.try_get("can_id")?;
+    let dlc: i64 = row.try_get("dlc")?;
+    let data = encryption::open_frame(row.try_get("data")?, dlc as usize)
+        .map_err(AppError::internal_server_error)?;
+    let fd = db::flag(row, "fd")?;
+    let brs = db::flag(row, "brs")?;
+    let timestamp: String = row.try_get("timestamp")?;
+    let endian: String = row.try_get("endian")?;
+
+    let frame = CanId::try_from(can_id)
+        .and_then(|id| {
+            if fd {
+                CanMessage::try_new_fd(id, &data, brs, timestamp)
+            } else {
+                CanMessage::try_new(id, &data, timestamp)
+            }
+        })
+        .map_err(|e| AppError::internal_server_error(e.to_string()))?;
+    Ok(RejectedFrame {
+        id: row.try_get("id")?,
+        batch_id: row.try_get("batch_id")?,
+        frame,
+        endianness: endian.parse().map_err(AppError::internal_server_error)?,
+        vehicle_id: row.try_get("vehicle_id")?,
+        step_name: row.try_get("step_name")?,
+        rule: row.try_get("rule")?,
+        reason: row.try_get("reason")?,
+        rejected_at: row.try_get("rejected_at")?,
+    })
+}
+
+/// Store quarantined frames in a single transaction
+pub async fn store_rejected(frames: &[RejectedFrame]) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let mut transaction = pool.begin().await?;
+    for rejected in frames {
+        sqlx::query(&format!(
+            "INSERT INTO rejected_frames ({}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)",
+            COLUMNS
+        ))
+        .bind(&rejected.id)
+        .bind(&rejected.batch_id)
+        .bind(rejected.frame.id as i64)
+        .bind(rejected.frame.dlc as i64)
+        .bind(encryption::seal_frame(rejected.frame.data()))
+        .bind(rejected.frame.fd)
+        .bind(rejected.frame.brs)
+        .bind(&rejected.frame.timestamp)
+        .bind(rejected.endianness.as_str())
+        .bind(&rejected.vehicle_id)
+        .bind(&rejected.step_name)
+        .bind(&rejected.rule)
+        .bind(&rejected.reason)
+        .bind(&rejected.rejected_at)
+        .execute(&mut *transaction)
+        .await?;
+    }
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+/// Quarantined frames, newest first
+pub async fn get_rejected(
+    rule: Option<&str>,
+    batch_id: Option<&str>,
+    limit: u32,
+) -> Result<Vec<RejectedFrame>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM rejected_frames
+         WHERE ($1 IS NULL OR rule = $1) AND ($2 IS NULL OR batch_id = $2)
+         ORDER BY rejected_at DESC, rowid DESC LIMIT $3",
+        COLUMNS
+    ))
+    .bind(rule)
+    .bind(batch_id)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(rejected_frame_from_row).collect()
+}
+
+/// Frames of one quarantined step, in ingestion order
+pub async fn get_batch(batch_id: &str) -> Result<Vec<RejectedFrame>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM rejected_frames WHERE batch_id = $1 ORDER BY rowid ASC",
+        COLUMNS
+    ))
+    .bind(batch_id)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(rejected_frame_from_row).collect()
+}
+
+/// Drop the frames of a quarantined step, returning how many were deleted
+pub async fn delete_batch(batch_id: &str) -> Result<u64, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let result = sqlx::query("DELETE FROM rejected_frames WHERE batch_id = $1")
+        .bind(batch_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
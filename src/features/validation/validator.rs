@@ -0,0 +1,64 @@
+use std::sync::{Arc, RwLock};
+
+use crate::core::can::CanMessage;
+use crate::features::validation::model::{ValidationContext, ValidationMode, Violation};
+use crate::features::validation::rules::{
+    DlcConsistency, FrameRule, IdWhitelist, Plausibility, RateLimit,
+};
+
+/// Ordered set of rules applied to ingested frames, shared as `Data<FrameValidator>`
+#[derive(Clone)]
+pub struct FrameValidator {
+    rules: Arc<RwLock<Vec<Arc<dyn FrameRule>>>>,
+    mode: ValidationMode,
+}
+
+impl FrameValidator {
+    /// Validator without rules
+    pub fn new(mode: ValidationMode) -> Self {
+        FrameValidator {
+            rules: Arc::new(RwLock::new(Vec::new())),
+            mode,
+        }
+    }
+
+    /// DLC, CAN ID and range checks, plus the per-ID rate limit when one is given
+    pub fn with_defaults(mode: ValidationMode, rate_limit: Option<u32>) -> Self {
+        let validator = Self::new(mode);
+        validator.register(DlcConsistency);
+        validator.register(IdWhitelist);
+        validator.register(Plausibility);
+        if let Some(max_per_second) = rate_limit {
+            validator.register(RateLimit::new(max_per_second));
+        }
+        validator
+    }
+
+    /// Append a rule, applied after those already registered
+    pub fn register(&self, rule: impl FrameRule + 'static) {
+        self.rules.write().unwrap().push(Arc::new(rule));
+    }
+
+    pub fn mode(&self) -> ValidationMode {
+        self.mode
+    }
+
+    /// Names of the registered rules, in order
+    pub fn rules(&self) -> Vec<&'static str> {
+        self.rules
+            .read()
+            .unwrap()
+            .iter()
+            .map(|rule| rule.name())
+            .collect()
+    }
+
+    /// Every violation of the frames of one step
+    pub fn check(&self, frames: &[CanMessage], context: &ValidationContext) -> Vec<Violation> {
+        let rules = self.rules.read().unwrap().clone();
+        rules
+            .iter()
+            .flat_map(|rule| rule.check(frames, context))
+            .collect()
+    }
+}
@@ -0,0 +1,108 @@
+use crate::common::error::AppError;
+use crate::core::can::Endianness;
+use crate::core::signals;
+use crate::features::driving_step::DrivingStep;
+use crate::features::vehicle::model::{Vehicle, VehicleRequest};
+use crate::features::vehicle::service;
+
+/// VINs are 17 characters of digits and capital letters other than I, O and Q
+fn validate_vin(vin: &str) -> Result<(), AppError> {
+    let valid = vin.len() == 17
+        && vin.chars().all(|c| {
+            c.is_ascii_digit() || (c.is_ascii_uppercase() && !matches!(c, 'I' | 'O' | 'Q'))
+        });
+    if !valid {
+        return Err(AppError::bad_request(format!(
+            "Invalid VIN '{}', expected 17 characters of digits and capital letters except I, O and Q",
+            vin
+        )));
+    }
+    Ok(())
+}
+
+fn build(
+    vin: String,
+    request: VehicleRequest,
+    created_at: Option<String>,
+) -> Result<Vehicle, AppError> {
+    validate_vin(&vin)?;
+    if request.make.trim().is_empty() || request.model.trim().is_empty() {
+        return Err(AppError::bad_request(
+            "Vehicle make and model must not be empty",
+        ));
+    }
+    let can_profile = request
+        .can_profile
+        .unwrap_or_else(|| signals::DEFAULT_PROFILE.to_string());
+    if signals::profile(&can_profile).is_none() {
+        return Err(AppError::bad_request(format!(
+            "Unknown CAN profile '{}', expected one of {}",
+            can_profile,
+            signals::PROFILES
+                .iter()
+                .map(|profile| profile.name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )));
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    Ok(Vehicle {
+        vin,
+        make: request.make,
+        model: request.model,
+        year: request.year,
+        can_profile,
+        dbc: request.dbc,
+        endianness: request.endianness.unwrap_or_else(|| {
+            Endianness::from_is_big_endian(DrivingStep::get_endianness_from_env())
+        }),
+        created_at: created_at.unwrap_or_else(|| now.clone()),
+        updated_at: now,
+    })
+}
+
+/// Registered vehicle, rejecting unknown ids as ingestion does
+pub async fn get(vin: &str) -> Result<Vehicle, AppError> {
+    service::get_vehicle(vin)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Vehicle '{}'", vin)))
+}
+
+pub async fn create(request: VehicleRequest) -> Result<Vehicle, AppError> {
+    let vin = request.vin.clone();
+    if service::get_vehicle(&vin).await?.is_some() {
+        return Err(AppError::bad_request(format!(
+            "Vehicle '{}' is already registered",
+            vin
+        )));
+    }
+    let vehicle = build(vin, request, None)?;
+    service::store_vehicle(&vehicle).await?;
+    Ok(vehicle)
+}
+
+pub async fn update(vin: &str, request: VehicleRequest) -> Result<Vehicle, AppError> {
+    let existing = get(vin).await?;
+    let vehicle = build(existing.vin, request, Some(existing.created_at))?;
+    service::store_vehicle(&vehicle).await?;
+    Ok(vehicle)
+}
+
+pub async fn delete(vin: &str) -> Result<(), AppError> {
+    if !service::delete_vehicle(vin).await? {
+        return Err(AppError::not_found(format!("Vehicle '{}'", vin)));
+    }
+    Ok(())
+}
+
+/// Vehicle named by an ingestion request, which must be registered
+pub async fn for_ingestion(vehicle_id: Option<&str>) -> Result<Option<Vehicle>, AppError> {
+    let Some(vin) = vehicle_id else {
+        return Ok(None);
+    };
+    service::get_vehicle(vin)
+        .await?
+        .map(Some)
+        .ok_or_else(|| AppError::bad_request(format!("Unknown vehicle_id '{}'", vin)))
+}
@@ -0,0 +1,78 @@
+pub mod controller;
+pub mod model;
+pub mod service;
+pub mod state;
+
+use actix_web::web::Data;
+use actix_web::{delete, get, post, put, web, HttpResponse, Result};
+
+use crate::common::error::AppError;
+use crate::core::scrub::Scrub;
+use crate::features::tenant::TenantScope;
+
+use model::{StateQuery, VehicleRequest};
+pub use model::{Vehicle, VehicleState};
+pub use state::VehicleStates;
+
+#[get("/vehicles")]
+pub async fn list() -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(service::get_vehicles().await?))
+}
+
+#[post("/vehicles")]
+pub async fn create(request: web::Json<VehicleRequest>) -> Result<HttpResponse, AppError> {
+    let vehicle = controller::create(request.into_inner()).await?;
+    Ok(HttpResponse::Created().json(vehicle))
+}
+
+#[get("/vehicles/{vin}")]
+pub async fn get(vin: web::Path<String>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::get(&vin).await?))
+}
+
+#[put("/vehicles/{vin}")]
+pub async fn update(
+    vin: web::Path<String>,
+    request: web::Json<VehicleRequest>,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::update(&vin, request.into_inner()).await?))
+}
+
+#[delete("/vehicles/{vin}")]
+pub async fn remove(
+    vin: web::Path<String>,
+    states: Data<VehicleStates>,
+) -> Result<HttpResponse, AppError> {
+    controller::delete(&vin).await?;
+    states.remove(&vin);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Latest value of every signal of the vehicle, as materialized from the bus
+#[get("/vehicles/{vin}/state")]
+pub async fn current_state(
+    vin: web::Path<String>,
+    query: web::Query<StateQuery>,
+    states: Data<VehicleStates>,
+    scope: TenantScope,
+    scrub: Scrub,
+) -> Result<HttpResponse, AppError> {
+    let state = states
+        .get(&vin)
+        .filter(|state| scope.name().is_none() || state.tenant.as_deref() == scope.name())
+        .ok_or_else(|| AppError::not_found(format!("State of vehicle '{}'", vin)))?;
+    let mut state = state.in_units(query.units);
+    if let Some(rules) = &scrub.0 {
+        rules.scrub_state(&mut state);
+    }
+    Ok(HttpResponse::Ok().json(state))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list)
+        .service(create)
+        .service(get)
+        .service(update)
+        .service(remove)
+        .service(current_state);
+}
@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::can::Endianness;
+use crate::core::units::{Unit, UnitSystem};
+
+/// Vehicle whose telemetry is ingested, keyed by its VIN
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Vehicle {
+    /// 17-character Vehicle Identification Number, also the `vehicle_id` of ingestion
+    pub vin: String,
+    pub make: String,
+    pub model: String,
+    pub year: Option<u16>,
+    /// Signal map the frames of the vehicle are decoded with, see `core::signals::PROFILES`
+    pub can_profile: String,
+    /// Reference to the DBC file the profile was derived from (path, URL or revision)
+    pub dbc: Option<String>,
+    /// Byte order of the vehicle's frames, used when ingestion does not give one
+    pub endianness: Endianness,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Body of `POST /vehicles` and `PUT /vehicles/{vin}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct VehicleRequest {
+    /// Ignored by `PUT`, which takes the VIN from the path
+    #[serde(default)]
+    pub vin: String,
+    pub make: String,
+    pub model: String,
+    pub year: Option<u16>,
+    /// `core::signals::DEFAULT_PROFILE` when omitted
+    pub can_profile: Option<String>,
+    pub dbc: Option<String>,
+    /// `ENDIAN` of the server when omitted
+    pub endianness: Option<Endianness>,
+}
+
+/// Latest value of one signal of a vehicle
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SignalState {
+    pub value: f64,
+    pub unit: Unit,
+    /// RFC3339 time of the frame that carried the value
+    pub timestamp: String,
+    /// Step the value was decoded from
+    pub step_id: Option<String>,
+}
+
+/// Latest value of every signal seen for a vehicle, kept up to date from the bus
+///
+/// A signal missing from a step, such as one of an optional group, keeps the value of the
+/// last step that carried it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VehicleState {
+    pub vehicle_id: String,
+    /// Step that last changed the state
+    pub step_id: Option<String>,
+    /// RFC3339 time the state last changed
+    pub updated_at: String,
+    /// Keyed by signal name, see `core::signals::SIGNALS`
+    pub signals: BTreeMap<String, SignalState>,
+    /// Tenant owning the steps of the vehicle, only shown to its own API key
+    #[serde(skip)]
+    pub tenant: Option<String>,
+}
+
+impl VehicleState {
+    /// Copy with the values converted to `system`
+    pub fn in_units(&self, system: UnitSystem) -> VehicleState {
+        let mut state = self.clone();
+        for signal in state.signals.values_mut() {
+            let unit = signal.unit.in_system(system);
+            if let Some(value) = signal.unit.convert(signal.value, unit) {
+                signal.value = value;
+                signal.unit = unit;
+            }
+        }
+        state
+    }
+}
+
+/// Query parameters accepted by `GET /vehicles/{vin}/state`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StateQuery {
+    #[serde(default)]
+    pub units: UnitSystem,
+}
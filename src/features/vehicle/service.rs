@@ -0,0 +1,90 @@
+use sqlx::any::AnyRow;
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::features::vehicle::model::Vehicle;
+
+fn vehicle_from_row(row: &AnyRow) -> Result<Vehicle, AppError> {
+    let year: Option<i64> = row.try_get("year")?;
+    let endian: String = row.try_get("endian")?;
+
+    Ok(Vehicle {
+        vin: row.try_get("vin")?,
+        make: row.try_get("make")?,
+        model: row.try_get("model")?,
+        year: year.map(|year| year as u16),
+        can_profile: row.try_get("can_profile")?,
+        dbc: row.try_get("dbc")?,
+        endianness: endian.parse().map_err(AppError::internal_server_error)?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+/// Insert a vehicle, replacing any previous vehicle with the same VIN
+pub async fn store_vehicle(vehicle: &Vehicle) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(
+        "INSERT INTO vehicles
+         (vin, make, model, year, can_profile, dbc, endian, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+         ON CONFLICT (vin) DO UPDATE SET
+             make = excluded.make, model = excluded.model, year = excluded.year,
+             can_profile = excluded.can_profile, dbc = excluded.dbc,
+             endian = excluded.endian, created_at = excluded.created_at,
+             updated_at = excluded.updated_at",
+    )
+    .bind(&vehicle.vin)
+    .bind(&vehicle.make)
+    .bind(&vehicle.model)
+    .bind(vehicle.year.map(|year| year as i64))
+    .bind(&vehicle.can_profile)
+    .bind(&vehicle.dbc)
+    .bind(vehicle.endianness.as_str())
+    .bind(&vehicle.created_at)
+    .bind(&vehicle.updated_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_vehicles() -> Result<Vec<Vehicle>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(
+        "SELECT vin, make, model, year, can_profile, dbc, endian, created_at, updated_at
+         FROM vehicles ORDER BY vin ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(vehicle_from_row).collect()
+}
+
+pub async fn get_vehicle(vin: &str) -> Result<Option<Vehicle>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let row = sqlx::query(
+        "SELECT vin, make, model, year, can_profile, dbc, endian, created_at, updated_at
+         FROM vehicles WHERE vin = $1",
+    )
+    .bind(vin)
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(vehicle_from_row).transpose()
+}
+
+/// Delete a vehicle, returning whether it existed; its stored frames are kept
+pub async fn delete_vehicle(vin: &str) -> Result<bool, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let result = sqlx::query("DELETE FROM vehicles WHERE vin = $1")
+        .bind(vin)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
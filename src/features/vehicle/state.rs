@@ -0,0 +1,114 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+use tokio::sync::broadcast;
+
+use crate::core::bus::{Bus, BusMessage};
+use crate::core::signals;
+use crate::features::driving_step::DrivingStep;
+use crate::features::history::SignalRecorder;
+use crate::features::vehicle::model::{SignalState, VehicleState};
+
+/// Current state of every vehicle, materialized from the DrivingSteps on the bus
+///
+/// Steps ingested without a `vehicle_id` belong to no vehicle and are not tracked. The
+/// states live in memory: after a restart a vehicle has no state until its next step.
+#[derive(Clone, Default)]
+pub struct VehicleStates {
+    states: Arc<RwLock<HashMap<String, VehicleState>>>,
+}
+
+impl VehicleStates {
+    pub fn new() -> Self {
+        VehicleStates::default()
+    }
+
+    /// State of `vin`, if one of its steps has been seen
+    pub fn get(&self, vin: &str) -> Option<VehicleState> {
+        self.states.read().unwrap().get(vin).cloned()
+    }
+
+    /// States of every vehicle of `tenant`, or of all of them, ordered by VIN
+    pub fn all(&self, tenant: Option<&str>) -> Vec<VehicleState> {
+        let mut states: Vec<VehicleState> = self
+            .states
+            .read()
+            .unwrap()
+            .values()
+            .filter(|state| tenant.is_none() || state.tenant.as_deref() == tenant)
+            .cloned()
+            .collect();
+        states.sort_by(|a, b| a.vehicle_id.cmp(&b.vehicle_id));
+        states
+    }
+
+    /// Forget the state of a vehicle that is no longer registered
+    pub fn remove(&self, vin: &str) {
+        self.states.write().unwrap().remove(vin);
+    }
+
+    /// Fold the signals of `step` into the state of its vehicle
+    ///
+    /// Values older than the one already held, from steps reconstructed out of order, are
+    /// ignored.
+    pub fn record_step(&self, step: &DrivingStep) {
+        let Some(vin) = &step.vehicle_id else { return };
+        let values = SignalRecorder::values(step);
+        if values.is_empty() {
+            return;
+        }
+
+        let mut states = self.states.write().unwrap();
+        let state = states.entry(vin.clone()).or_insert_with(|| VehicleState {
+            vehicle_id: vin.clone(),
+            step_id: None,
+            updated_at: String::new(),
+            signals: BTreeMap::new(),
+            tenant: None,
+        });
+        for value in values {
+            let Some(signal) = signals::find(value.signal) else {
+                continue;
+            };
+            let Some(timestamp) = chrono::DateTime::from_timestamp_millis(value.timestamp_ms)
+            else {
+                continue;
+            };
+            let newer_held = state.signals.get(value.signal).is_some_and(|held| {
+                chrono::DateTime::parse_from_rfc3339(&held.timestamp)
+                    .is_ok_and(|held| held.timestamp_millis() > value.timestamp_ms)
+            });
+            if newer_held {
+                continue;
+            }
+            state.signals.insert(
+                value.signal.to_string(),
+                SignalState {
+                    value: value.value,
+                    unit: signal.unit,
+                    timestamp: timestamp.to_rfc3339(),
+                    step_id: value.step_id,
+                },
+            );
+        }
+        state.step_id = step.step_id.clone();
+        state.updated_at = chrono::Utc::now().to_rfc3339();
+        state.tenant = step.tenant.clone();
+    }
+
+    /// Keep the states up to date with every DrivingStep on the bus
+    pub fn spawn(&self, bus: &Bus) {
+        let states = self.clone();
+        let mut rx = bus.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(BusMessage::DrivingStep(step)) => states.record_step(&step),
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(_) => break,
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,97 @@
+use crate::common::error::AppError;
+use crate::core::bus::BusMessage;
+use crate::features::webhook::dispatcher::WebhookDispatcher;
+use crate::features::webhook::model::{
+    CreatedWebhook, Delivery, DeliveryQuery, Webhook, WebhookRequest, DEFAULT_MAX_ATTEMPTS,
+    DEFAULT_TOPICS, MAX_ATTEMPTS_LIMIT,
+};
+use crate::features::webhook::service;
+
+pub async fn create(
+    dispatcher: &WebhookDispatcher,
+    request: WebhookRequest,
+) -> Result<CreatedWebhook, AppError> {
+    if request.name.trim().is_empty() {
+        return Err(AppError::bad_request("Webhook name must not be empty"));
+    }
+    let url = reqwest::Url::parse(&request.url)
+        .map_err(|e| AppError::bad_request(format!("Invalid webhook URL: {}", e)))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(AppError::bad_request("Webhook URL must use http or https"));
+    }
+    if let Some(topic) = request
+        .topics
+        .iter()
+        .find(|topic| !BusMessage::TOPICS.contains(&topic.as_str()))
+    {
+        return Err(AppError::bad_request(format!(
+            "Unknown topic '{}', expected one of {}",
+            topic,
+            BusMessage::TOPICS.join(", ")
+        )));
+    }
+    let max_attempts = request.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS);
+    if !(1..=MAX_ATTEMPTS_LIMIT).contains(&max_attempts) {
+        return Err(AppError::bad_request(format!(
+            "max_attempts must be between 1 and {}",
+            MAX_ATTEMPTS_LIMIT
+        )));
+    }
+
+    let secret = match request.secret {
+        Some(secret) if secret.is_empty() => {
+            return Err(AppError::bad_request("Webhook secret must not be empty"))
+        }
+        Some(secret) => secret,
+        None => format!(
+            "{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        ),
+    };
+    let topics = if request.topics.is_empty() {
+        DEFAULT_TOPICS
+            .iter()
+            .map(|topic| topic.to_string())
+            .collect()
+    } else {
+        request.topics
+    };
+
+    let webhook = Webhook {
+        name: request.name,
+        url: request.url,
+        secret: secret.clone(),
+        topics,
+        min_severity: request.min_severity,
+        max_attempts,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    service::store_webhook(&webhook).await?;
+    dispatcher.register(webhook.clone());
+
+    Ok(CreatedWebhook { webhook, secret })
+}
+
+pub async fn delete(dispatcher: &WebhookDispatcher, name: &str) -> Result<(), AppError> {
+    if !service::delete_webhook(name).await? {
+        return Err(AppError::not_found(format!("Webhook '{}'", name)));
+    }
+    dispatcher.remove(name);
+    Ok(())
+}
+
+pub async fn deliveries(
+    dispatcher: &WebhookDispatcher,
+    name: &str,
+    query: &DeliveryQuery,
+) -> Result<Vec<Delivery>, AppError> {
+    if !dispatcher
+        .webhooks()
+        .iter()
+        .any(|webhook| webhook.name == name)
+    {
+        return Err(AppError::not_found(format!("Webhook '{}'", name)));
+    }
+    service::get_deliveries(name, query.status, query.limit).await
+}
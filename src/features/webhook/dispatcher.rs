@@ -0,0 +1,228 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tokio::sync::broadcast;
+
+use crate::common::error::AppError;
+use crate::core::bus::{Bus, BusMessage, SubscriptionFilter};
+use crate::features::webhook::model::{Delivery, DeliveryStatus, Webhook};
+use crate::features::webhook::service;
+
+/// Delay before the second attempt, doubled for every further attempt
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Hex HMAC-SHA256 of `{timestamp}.{body}`, as sent in `X-Webhook-Signature`
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Wait before attempt `attempt + 1`
+fn backoff(attempt: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(MAX_BACKOFF)
+}
+
+/// Outcome of one POST to a webhook endpoint
+enum Attempt {
+    Delivered(u16),
+    /// Server errors, rate limiting and transport failures are worth retrying
+    Retry(Option<u16>, String),
+    /// Any other client error means the endpoint rejects the message itself
+    Reject(u16, String),
+}
+
+/// Registered webhooks, each receiving the bus messages it subscribed to
+#[derive(Clone, Default)]
+pub struct WebhookDispatcher {
+    webhooks: Arc<Mutex<Vec<Webhook>>>,
+    client: reqwest::Client,
+}
+
+impl WebhookDispatcher {
+    /// Dispatch to every webhook stored in the database
+    pub async fn load() -> Result<Self, AppError> {
+        let dispatcher = WebhookDispatcher {
+            webhooks: Arc::default(),
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .map_err(|e| AppError::internal_server_error(e.to_string()))?,
+        };
+        dispatcher.reload().await?;
+        Ok(dispatcher)
+    }
+
+    /// Replace the registered webhooks with those stored in the database
+    pub async fn reload(&self) -> Result<(), AppError> {
+        let stored = service::get_webhooks().await?;
+        *self.webhooks.lock().unwrap() = stored;
+        Ok(())
+    }
+
+    /// Add a webhook, replacing any webhook with the same name
+    pub fn register(&self, webhook: Webhook) {
+        let mut webhooks = self.webhooks.lock().unwrap();
+        webhooks.retain(|registered| registered.name != webhook.name);
+        webhooks.push(webhook);
+    }
+
+    pub fn remove(&self, name: &str) {
+        self.webhooks
+            .lock()
+            .unwrap()
+            .retain(|registered| registered.name != name);
+    }
+
+    pub fn webhooks(&self) -> Vec<Webhook> {
+        self.webhooks.lock().unwrap().clone()
+    }
+
+    /// Webhooks subscribed to `message`
+    fn subscribers(&self, message: &BusMessage) -> Vec<Webhook> {
+        self.webhooks
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|webhook| {
+                let filter = SubscriptionFilter {
+                    min_severity: webhook.min_severity,
+                    ..Default::default()
+                };
+                webhook.topics.iter().any(|topic| topic == message.topic())
+                    && filter.accepts(message)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Deliver every bus message to its subscribers, each delivery retrying on its own
+    pub fn spawn(&self, bus: &Bus) {
+        let dispatcher = self.clone();
+        let mut rx = bus.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                let message = match rx.recv().await {
+                    Ok(message) => message,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(_) => break,
+                };
+
+                let subscribers = dispatcher.subscribers(&message);
+                if subscribers.is_empty() {
+                    continue;
+                }
+                let Ok(payload) = serde_json::to_value(&message) else {
+                    continue;
+                };
+                for webhook in subscribers {
+                    let delivery = Delivery {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        webhook: webhook.name.clone(),
+                        topic: message.topic().to_string(),
+                        payload: payload.clone(),
+                        status: DeliveryStatus::Pending,
+                        attempts: 0,
+                        response_status: None,
+                        error: None,
+                        created_at: chrono::Utc::now().to_rfc3339(),
+                        finished_at: None,
+                    };
+                    tokio::spawn(deliver(dispatcher.client.clone(), webhook, delivery));
+                }
+            }
+        });
+    }
+}
+
+async fn post(client: &reqwest::Client, webhook: &Webhook, delivery: &Delivery) -> Attempt {
+    let body = delivery.payload.to_string();
+    let timestamp = chrono::Utc::now().timestamp();
+
+    let response = client
+        .post(&webhook.url)
+        .header("Content-Type", "application/json")
+        .header("X-Webhook-Id", &delivery.id)
+        .header("X-Webhook-Timestamp", timestamp.to_string())
+        .header(
+            "X-Webhook-Signature",
+            format!("sha256={}", sign(&webhook.secret, timestamp, &body)),
+        )
+        .body(body)
+        .send()
+        .await;
+
+    match response {
+        Ok(response) => {
+            let status = response.status();
+            if status.is_success() {
+                Attempt::Delivered(status.as_u16())
+            } else if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                Attempt::Retry(Some(status.as_u16()), format!("HTTP {}", status))
+            } else {
+                Attempt::Reject(status.as_u16(), format!("HTTP {}", status))
+            }
+        }
+        Err(e) => Attempt::Retry(None, e.to_string()),
+    }
+}
+
+/// POST one message until it is acknowledged, rejected or out of attempts
+async fn deliver(client: reqwest::Client, webhook: Webhook, mut delivery: Delivery) {
+    loop {
+        delivery.attempts += 1;
+        let retry = match post(&client, &webhook, &delivery).await {
+            Attempt::Delivered(status) => {
+                delivery.status = DeliveryStatus::Delivered;
+                delivery.response_status = Some(status);
+                delivery.error = None;
+                false
+            }
+            Attempt::Retry(status, error) => {
+                delivery.response_status = status;
+                delivery.error = Some(error);
+                delivery.attempts < webhook.max_attempts
+            }
+            Attempt::Reject(status, error) => {
+                delivery.response_status = Some(status);
+                delivery.error = Some(error);
+                false
+            }
+        };
+
+        if !retry {
+            if delivery.status == DeliveryStatus::Pending {
+                delivery.status = DeliveryStatus::Failed;
+                println!(
+                    "❌ Webhook '{}' delivery {} failed after {} attempt(s): {}",
+                    webhook.name,
+                    delivery.id,
+                    delivery.attempts,
+                    delivery.error.as_deref().unwrap_or_default()
+                );
+            }
+            delivery.finished_at = Some(chrono::Utc::now().to_rfc3339());
+        }
+        if let Err(e) = service::save_delivery(&delivery).await {
+            println!("❌ Failed to store webhook delivery {}: {}", delivery.id, e);
+        }
+        if !retry {
+            return;
+        }
+        tokio::time::sleep(backoff(delivery.attempts)).await;
+    }
+}
@@ -0,0 +1,52 @@
+pub mod controller;
+pub mod dispatcher;
+pub mod model;
+pub mod service;
+
+use actix_web::web::Data;
+use actix_web::{delete, get, post, web, HttpResponse, Result};
+
+use crate::common::error::AppError;
+
+pub use dispatcher::WebhookDispatcher;
+pub use model::{Delivery, Webhook};
+use model::{DeliveryQuery, WebhookRequest};
+
+#[get("/webhooks")]
+pub async fn list(dispatcher: Data<WebhookDispatcher>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(dispatcher.webhooks()))
+}
+
+#[post("/webhooks")]
+pub async fn create(
+    dispatcher: Data<WebhookDispatcher>,
+    request: web::Json<WebhookRequest>,
+) -> Result<HttpResponse, AppError> {
+    let created = controller::create(&dispatcher, request.into_inner()).await?;
+    Ok(HttpResponse::Created().json(created))
+}
+
+#[delete("/webhooks/{name}")]
+pub async fn remove(
+    dispatcher: Data<WebhookDispatcher>,
+    name: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    controller::delete(&dispatcher, &name).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[get("/webhooks/{name}/deliveries")]
+pub async fn deliveries(
+    dispatcher: Data<WebhookDispatcher>,
+    name: web::Path<String>,
+    query: web::Query<DeliveryQuery>,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(controller::deliveries(&dispatcher, &name, &query).await?))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list)
+        .service(create)
+        .service(remove)
+        .service(deliveries);
+}
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::features::event::model::Severity;
+
+/// Topics delivered to subscriptions that do not list any
+pub const DEFAULT_TOPICS: [&str; 3] = ["event", "geofence", "anomaly"];
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+pub const MAX_ATTEMPTS_LIMIT: u32 = 20;
+
+/// Outbound HTTP subscription to bus messages
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub name: String,
+    /// Endpoint receiving one POST per bus message
+    pub url: String,
+    /// HMAC-SHA256 key of the `X-Webhook-Signature` header, only returned on creation
+    #[serde(skip_serializing)]
+    pub secret: String,
+    /// Bus topics to deliver (`event`, `geofence`, `anomaly`, `driving_step`)
+    pub topics: Vec<String>,
+    /// Drop events below this severity; other topics are not affected
+    pub min_severity: Option<Severity>,
+    /// Attempts per message before the delivery is marked failed
+    pub max_attempts: u32,
+    pub created_at: String,
+}
+
+/// Body of `POST /webhooks`
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookRequest {
+    pub name: String,
+    pub url: String,
+    /// Generated when omitted
+    pub secret: Option<String>,
+    /// Defaults to `event`, `geofence` and `anomaly`
+    #[serde(default)]
+    pub topics: Vec<String>,
+    pub min_severity: Option<Severity>,
+    pub max_attempts: Option<u32>,
+}
+
+/// Response of `POST /webhooks`, the only one that reveals the secret
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedWebhook {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// Not yet acknowledged, attempts may remain
+    Pending,
+    Delivered,
+    /// Attempts exhausted or the endpoint rejected the message
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+}
+
+impl std::str::FromStr for DeliveryStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "pending" => Ok(DeliveryStatus::Pending),
+            "delivered" => Ok(DeliveryStatus::Delivered),
+            "failed" => Ok(DeliveryStatus::Failed),
+            other => Err(format!("Unknown delivery status '{}'", other)),
+        }
+    }
+}
+
+/// One bus message sent to one webhook, with the outcome of its latest attempt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Delivery {
+    /// Also sent as the `X-Webhook-Id` header, stable across retries
+    pub id: String,
+    pub webhook: String,
+    pub topic: String,
+    pub payload: Value,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    /// HTTP status of the latest response
+    pub response_status: Option<u16>,
+    /// Transport error or non-success status of the latest attempt
+    pub error: Option<String>,
+    pub created_at: String,
+    pub finished_at: Option<String>,
+}
+
+fn default_limit() -> u32 {
+    100
+}
+
+/// Query parameters accepted by `GET /webhooks/{name}/deliveries`
+#[derive(Debug, Deserialize)]
+pub struct DeliveryQuery {
+    pub status: Option<DeliveryStatus>,
+    /// Maximum number of deliveries, newest first
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+}
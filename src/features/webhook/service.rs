@@ -0,0 +1,154 @@
+use sqlx::any::AnyRow;
+use sqlx::Row;
+
+use crate::common::error::AppError;
+use crate::features::webhook::model::{Delivery, DeliveryStatus, Webhook};
+
+fn webhook_from_row(row: &AnyRow) -> Result<Webhook, AppError> {
+    let topics: String = row.try_get("topics")?;
+    let min_severity: Option<String> = row.try_get("min_severity")?;
+    let max_attempts: i64 = row.try_get("max_attempts")?;
+
+    Ok(Webhook {
+        name: row.try_get("name")?,
+        url: row.try_get("url")?,
+        secret: row.try_get("secret")?,
+        topics: serde_json::from_str(&topics)?,
+        min_severity: min_severity
+            .map(|severity| severity.parse())
+            .transpose()
+            .map_err(AppError::internal_server_error)?,
+        max_attempts: max_attempts as u32,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+fn delivery_from_row(row: &AnyRow) -> Result<Delivery, AppError> {
+    let payload: String = row.try_get("payload")?;
+    let status: String = row.try_get("status")?;
+    let attempts: i64 = row.try_get("attempts")?;
+    let response_status: Option<i64> = row.try_get("response_status")?;
+
+    Ok(Delivery {
+        id: row.try_get("id")?,
+        webhook: row.try_get("webhook")?,
+        topic: row.try_get("topic")?,
+        payload: serde_json::from_str(&payload)?,
+        status: status.parse().map_err(AppError::internal_server_error)?,
+        attempts: attempts as u32,
+        response_status: response_status.map(|status| status as u16),
+        error: row.try_get("error")?,
+        created_at: row.try_get("created_at")?,
+        finished_at: row.try_get("finished_at")?,
+    })
+}
+
+/// Insert a webhook, replacing any previous webhook with the same name
+pub async fn store_webhook(webhook: &Webhook) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(
+        "INSERT INTO webhooks
+         (name, url, secret, topics, min_severity, max_attempts, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         ON CONFLICT (name) DO UPDATE SET
+             url = excluded.url, secret = excluded.secret, topics = excluded.topics,
+             min_severity = excluded.min_severity, max_attempts = excluded.max_attempts,
+             created_at = excluded.created_at",
+    )
+    .bind(&webhook.name)
+    .bind(&webhook.url)
+    .bind(&webhook.secret)
+    .bind(serde_json::to_string(&webhook.topics)?)
+    .bind(webhook.min_severity.map(|severity| severity.as_str()))
+    .bind(webhook.max_attempts as i64)
+    .bind(&webhook.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_webhooks() -> Result<Vec<Webhook>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(
+        "SELECT name, url, secret, topics, min_severity, max_attempts, created_at
+         FROM webhooks ORDER BY created_at ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(webhook_from_row).collect()
+}
+
+/// Delete a webhook and its delivery history, returning whether it existed
+pub async fn delete_webhook(name: &str) -> Result<bool, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let result = sqlx::query("DELETE FROM webhooks WHERE name = $1")
+        .bind(name)
+        .execute(pool)
+        .await?;
+    sqlx::query("DELETE FROM webhook_deliveries WHERE webhook = $1")
+        .bind(name)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Insert or update a delivery record
+pub async fn save_delivery(delivery: &Delivery) -> Result<(), AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    sqlx::query(
+        "INSERT INTO webhook_deliveries
+         (id, webhook, topic, payload, status, attempts, response_status, error, created_at, finished_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+         ON CONFLICT (id) DO UPDATE SET
+             webhook = excluded.webhook, topic = excluded.topic,
+             payload = excluded.payload, status = excluded.status,
+             attempts = excluded.attempts, response_status = excluded.response_status,
+             error = excluded.error, created_at = excluded.created_at,
+             finished_at = excluded.finished_at",
+    )
+    .bind(&delivery.id)
+    .bind(&delivery.webhook)
+    .bind(&delivery.topic)
+    .bind(delivery.payload.to_string())
+    .bind(delivery.status.as_str())
+    .bind(delivery.attempts as i64)
+    .bind(delivery.response_status.map(i64::from))
+    .bind(&delivery.error)
+    .bind(&delivery.created_at)
+    .bind(&delivery.finished_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Deliveries of a webhook, most recent first
+pub async fn get_deliveries(
+    webhook: &str,
+    status: Option<DeliveryStatus>,
+    limit: u32,
+) -> Result<Vec<Delivery>, AppError> {
+    let pool = crate::config::db::get_pool().await?;
+
+    let rows = sqlx::query(
+        "SELECT id, webhook, topic, payload, status, attempts, response_status, error,
+                created_at, finished_at
+         FROM webhook_deliveries
+         WHERE webhook = $1 AND ($2 IS NULL OR status = $2)
+         ORDER BY created_at DESC LIMIT $3",
+    )
+    .bind(webhook)
+    .bind(status.map(DeliveryStatus::as_str))
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(delivery_from_row).collect()
+}
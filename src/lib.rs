@@ -1,10 +1,18 @@
 /// Library exports for the CAN Bus + RabbitMQ + SQLx real-time system
 /// This allows examples to import the actual structs and modules
+#[cfg(feature = "client")]
+pub mod client;
 pub mod common;
 pub mod config;
 pub mod core;
 pub mod features;
+pub mod monitor;
+pub mod server;
+#[cfg(feature = "test_support")]
+pub mod test_support;
 
 // Re-export commonly used items for convenience
+pub use config::AppConfig;
 pub use core::can::CanMessage;
 pub use features::driving_step::DrivingStep;
+pub use server::{AppBuilder, Server, ServerHandles};
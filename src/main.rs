@@ -1,55 +1,37 @@
-mod common;
-mod config;
-mod core;
-mod features;
-
-use actix_web::middleware;
-use actix_web::{web::Data, App, HttpServer};
-use tokio::sync::broadcast;
-
-use crate::features::driving_step::DrivingStep;
+use canbus_rmq_realtime::config::settings::SqlLogLevel;
+use canbus_rmq_realtime::config::AppSettings;
+use canbus_rmq_realtime::{monitor, AppConfig, Server};
 
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
+    // `monitor [ws-url]` follows a running server in the terminal instead of serving
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() == Some("monitor") {
+        let url = args
+            .next()
+            .unwrap_or_else(|| monitor::DEFAULT_WS_URL.to_string());
+        return monitor::run(&url).await;
+    }
+
+    let invalid = |e: String| std::io::Error::new(std::io::ErrorKind::InvalidData, e);
+    // config.toml (or CONFIG_FILE=<file>), EVENTBUS_<SECTION>__<KEY> and the unprefixed
+    // variables of earlier versions, e.g. EVENTBUS_SERVER__PORT=9090 or JOB_WORKERS=4
+    let settings = AppSettings::load().map_err(invalid)?;
+
     std::env::set_var("RUST_BACKTRACE", "1");
     if std::env::var("RUST_LOG").is_err() {
-        std::env::set_var("RUST_LOG", "actix_web=debug,info,warn");
+        let filter = if settings.database.log == SqlLogLevel::All {
+            "actix_web=debug,info,warn,sql=info,sqlx::query=info"
+        } else {
+            "actix_web=debug,info,warn"
+        };
+        std::env::set_var("RUST_LOG", filter);
     }
     env_logger::init();
 
-    let (tx, _rx) = broadcast::channel::<DrivingStep>(512);
-
-    // RabbitMQ
-    let rabit_connection = config::rabbitmq::connect()
-        .await
-        .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error.to_string()))?;
-    let channel = config::rabbitmq::create_step_name_channel(&rabit_connection)
-        .await
-        .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error.to_string()))?;
-    config::rabbitmq::consume_step_names(&channel, &tx)
-        .await
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
-
-    // SQLite
-    config::sqlite::init()
-        .await
-        .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error.to_string()))?;
-
-    // Server HTTP
-    HttpServer::new(move || {
-        App::new()
-            .wrap(middleware::Logger::new(
-                "%{r}a %r %s %b %{Referer}i %{User-Agent}i %T",
-            ))
-            .app_data(Data::new(channel.clone()))
-            .app_data(Data::new(tx.clone()))
-            .configure(features::driving_step::configure)
-            .configure(core::stream::configure)
-            .configure(core::websocket::configure)
-    })
-    .bind(("127.0.0.1", 8080))?
-    .run()
-    .await?;
+    let config = AppConfig::from_settings(settings).map_err(invalid)?;
+    let server = Server::builder().config(config).build().await?;
+    server.run().await?;
 
     Ok(())
 }
@@ -0,0 +1,90 @@
+//! Terminal monitor following the server's websocket, run as `canbus_rmq_realtime monitor`
+//!
+//! Renders gauges of the latest DrivingStep, the CAN frames it was carried in and a log
+//! of events, geofence transitions and anomalies. The connection is retried until the
+//! operator quits with `q` or `Esc`.
+
+mod state;
+mod ui;
+
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::core::bus::BusMessage;
+pub use state::{FrameStats, MonitorState, MonitorUpdate};
+
+pub const DEFAULT_WS_URL: &str = "ws://127.0.0.1:8080/ws";
+/// Delay before reconnecting after the websocket closed or could not be opened
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+/// Longest wait for keyboard input between two redraws
+const FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Run the monitor until the operator quits
+pub async fn run(url: &str) -> std::io::Result<()> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let reader = tokio::spawn(follow(url.to_string(), tx));
+
+    let mut state = MonitorState::new(url);
+    let mut terminal = ratatui::init();
+    let result = loop {
+        while let Ok(update) = rx.try_recv() {
+            state.apply(update);
+        }
+        if let Err(e) = terminal.draw(|frame| ui::draw(frame, &mut state)) {
+            break Err(e);
+        }
+
+        // Polling blocks this worker only; the reader keeps running on the others
+        match tokio::task::block_in_place(|| event::poll(FRAME_INTERVAL)) {
+            Ok(true) => match event::read() {
+                Ok(Event::Key(key))
+                    if key.kind == KeyEventKind::Press
+                        && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) =>
+                {
+                    break Ok(())
+                }
+                Ok(_) => {}
+                Err(e) => break Err(e),
+            },
+            Ok(false) => {}
+            Err(e) => break Err(e),
+        }
+    };
+    ratatui::restore();
+    reader.abort();
+
+    result
+}
+
+/// Forward every bus message received on the websocket, reconnecting when it drops
+async fn follow(url: String, tx: mpsc::UnboundedSender<MonitorUpdate>) {
+    loop {
+        let reason = match tokio_tungstenite::connect_async(url.as_str()).await {
+            Ok((mut socket, _)) => {
+                let _ = tx.send(MonitorUpdate::Connected);
+                loop {
+                    match socket.next().await {
+                        Some(Ok(Message::Text(text))) => {
+                            // Replies to rejected writes are not bus messages
+                            if let Ok(message) = serde_json::from_str::<BusMessage>(&text) {
+                                let _ = tx.send(MonitorUpdate::Message(Box::new(message)));
+                            }
+                        }
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => break e.to_string(),
+                        None => break "connection closed".to_string(),
+                    }
+                }
+            }
+            Err(e) => e.to_string(),
+        };
+        if tx.send(MonitorUpdate::Disconnected(reason)).is_err() {
+            return;
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
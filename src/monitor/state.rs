@@ -0,0 +1,141 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::core::bus::BusMessage;
+use crate::features::driving_step::DrivingStep;
+
+/// Lines kept in the activity log
+const LOG_CAPACITY: usize = 100;
+/// Window over which the frame rate is measured
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// What the websocket reader reports to the monitor
+#[derive(Debug)]
+pub enum MonitorUpdate {
+    Connected,
+    Disconnected(String),
+    Message(Box<BusMessage>),
+}
+
+/// Traffic seen on one CAN ID
+#[derive(Debug, Clone)]
+pub struct FrameStats {
+    pub count: u64,
+    pub dlc: u8,
+    pub payload: Vec<u8>,
+    pub last_seen: Instant,
+}
+
+/// Everything the monitor renders, folded from the messages received so far
+#[derive(Debug)]
+pub struct MonitorState {
+    pub url: String,
+    pub connected: bool,
+    pub status: String,
+    pub latest: Option<DrivingStep>,
+    pub steps: u64,
+    pub frames: BTreeMap<u16, FrameStats>,
+    /// Arrival time and frame count of recent steps, for the frame rate
+    arrivals: VecDeque<(Instant, usize)>,
+    pub log: VecDeque<String>,
+}
+
+impl MonitorState {
+    pub fn new(url: impl Into<String>) -> Self {
+        MonitorState {
+            url: url.into(),
+            connected: false,
+            status: "connecting".to_string(),
+            latest: None,
+            steps: 0,
+            frames: BTreeMap::new(),
+            arrivals: VecDeque::new(),
+            log: VecDeque::new(),
+        }
+    }
+
+    pub fn apply(&mut self, update: MonitorUpdate) {
+        match update {
+            MonitorUpdate::Connected => {
+                self.connected = true;
+                self.status = "connected".to_string();
+                self.push_log(format!("connected to {}", self.url));
+            }
+            MonitorUpdate::Disconnected(reason) => {
+                if self.connected {
+                    self.push_log(format!("disconnected: {}", reason));
+                }
+                self.connected = false;
+                self.status = format!("disconnected ({}), retrying", reason);
+            }
+            MonitorUpdate::Message(message) => self.apply_message(*message),
+        }
+    }
+
+    fn apply_message(&mut self, message: BusMessage) {
+        match message {
+            BusMessage::DrivingStep(step) => self.record_step(step),
+            BusMessage::Event(event) => {
+                self.push_log(format!("event {}: {}", event.name, event.message))
+            }
+            BusMessage::Geofence(event) => self.push_log(format!(
+                "geofence {} {:?} ({})",
+                event.geofence, event.transition, event.step_name
+            )),
+            BusMessage::Anomaly(anomaly) => self.push_log(format!(
+                "anomaly {} = {:.2} ({:.1}σ, mean {:.2})",
+                anomaly.signal, anomaly.value, anomaly.sigma, anomaly.mean
+            )),
+            BusMessage::System(event) => {
+                self.push_log(format!("system {}: {}", event.kind.as_str(), event.message))
+            }
+        }
+    }
+
+    /// Count the frames the step was carried in and keep it as the latest step
+    fn record_step(&mut self, step: DrivingStep) {
+        let now = Instant::now();
+        let frames = step.to_can_messages();
+        for frame in &frames {
+            let stats = self.frames.entry(frame.id).or_insert(FrameStats {
+                count: 0,
+                dlc: 0,
+                payload: Vec::new(),
+                last_seen: now,
+            });
+            stats.count += 1;
+            stats.dlc = frame.dlc;
+            stats.payload = frame.data().to_vec();
+            stats.last_seen = now;
+        }
+
+        self.arrivals.push_back((now, frames.len()));
+        self.steps += 1;
+        self.push_log(format!(
+            "step '{}' ({} frames)",
+            step.step_name,
+            frames.len()
+        ));
+        self.latest = Some(step);
+    }
+
+    /// CAN frames per second over the last few seconds
+    pub fn frame_rate(&mut self) -> f64 {
+        let now = Instant::now();
+        while self
+            .arrivals
+            .front()
+            .is_some_and(|(arrived, _)| now.duration_since(*arrived) > RATE_WINDOW)
+        {
+            self.arrivals.pop_front();
+        }
+        let frames: usize = self.arrivals.iter().map(|(_, frames)| frames).sum();
+        frames as f64 / RATE_WINDOW.as_secs_f64()
+    }
+
+    fn push_log(&mut self, line: String) {
+        let timestamp = chrono::Local::now().format("%H:%M:%S");
+        self.log.push_front(format!("{} {}", timestamp, line));
+        self.log.truncate(LOG_CAPACITY);
+    }
+}
@@ -0,0 +1,258 @@
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Cell, Gauge, List, ListItem, Paragraph, Row, Table};
+use ratatui::Frame;
+use std::time::Duration;
+
+use super::state::MonitorState;
+use crate::core::format;
+use crate::features::driving_step::DrivingStep;
+
+const STALE_AFTER: Duration = Duration::from_secs(10);
+
+pub fn draw(frame: &mut Frame, state: &mut MonitorState) {
+    let [header, body, footer] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [top, bottom] = Layout::vertical([Constraint::Length(20), Constraint::Min(6)]).areas(body);
+    let [gauges, details] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(top);
+    let [frames, log] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(bottom);
+
+    draw_header(frame, header, state);
+    draw_gauges(frame, gauges, state.latest.as_ref());
+    draw_details(frame, details, state.latest.as_ref());
+    draw_frames(frame, frames, state);
+    draw_log(frame, log, state);
+    frame.render_widget(
+        Paragraph::new(" q / Esc: quit").style(Style::new().fg(Color::DarkGray)),
+        footer,
+    );
+}
+
+fn draw_header(frame: &mut Frame, area: Rect, state: &mut MonitorState) {
+    let status_color = if state.connected {
+        Color::Green
+    } else {
+        Color::Red
+    };
+    let frame_rate = state.frame_rate();
+    let line = Line::from(vec![
+        Span::styled(
+            format!(" {} ", state.status),
+            Style::new().fg(status_color).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(format!(
+            "│ {} steps │ {:.1} frames/s",
+            state.steps, frame_rate
+        )),
+    ]);
+    let title = format!(" CAN bus monitor — {} ", state.url);
+    frame.render_widget(
+        Paragraph::new(line).block(Block::bordered().title(title)),
+        area,
+    );
+}
+
+fn gauge(title: &str, value: f64, min: f64, max: f64, label: String, color: Color) -> Gauge<'_> {
+    let ratio = ((value - min) / (max - min)).clamp(0.0, 1.0);
+    Gauge::default()
+        .block(Block::bordered().title(title))
+        .gauge_style(Style::new().fg(color))
+        .ratio(ratio)
+        .label(label)
+}
+
+fn draw_gauges(frame: &mut Frame, area: Rect, step: Option<&DrivingStep>) {
+    let Some(step) = step else {
+        frame.render_widget(
+            Paragraph::new("waiting for the first driving step…")
+                .block(Block::bordered().title(" Gauges ")),
+            area,
+        );
+        return;
+    };
+
+    let mut gauges = vec![
+        gauge(
+            "Speed",
+            step.speed.vehicle_speed as f64,
+            0.0,
+            250.0,
+            format!("{:.1} km/h", step.speed.vehicle_speed),
+            Color::Cyan,
+        ),
+        gauge(
+            "RPM",
+            step.engine.rpm as f64,
+            0.0,
+            8000.0,
+            format!("{} rpm", step.engine.rpm),
+            Color::Yellow,
+        ),
+        gauge(
+            "Throttle",
+            step.engine.throttle_pos as f64,
+            0.0,
+            100.0,
+            format!("{}%", step.engine.throttle_pos),
+            Color::Green,
+        ),
+        gauge(
+            "Engine load",
+            step.engine.engine_load as f64,
+            0.0,
+            100.0,
+            format!("{}%", step.engine.engine_load),
+            Color::Magenta,
+        ),
+        gauge(
+            "Coolant",
+            step.engine.coolant_temp as f64,
+            -40.0,
+            130.0,
+            format!("{}°C", step.engine.coolant_temp),
+            if step.engine.coolant_temp >= 110 {
+                Color::Red
+            } else {
+                Color::Blue
+            },
+        ),
+    ];
+    if let Some(fuel) = &step.fuel {
+        gauges.push(gauge(
+            "Fuel",
+            fuel.tank_level as f64,
+            0.0,
+            100.0,
+            format!("{}% ({} km)", fuel.tank_level, fuel.range_remaining),
+            Color::LightGreen,
+        ));
+    }
+
+    let rows = Layout::vertical(vec![Constraint::Length(3); gauges.len()]).split(area);
+    for (gauge, row) in gauges.into_iter().zip(rows.iter()) {
+        frame.render_widget(gauge, *row);
+    }
+}
+
+fn flag(on: bool) -> &'static str {
+    if on {
+        "ON"
+    } else {
+        "off"
+    }
+}
+
+fn draw_details(frame: &mut Frame, area: Rect, step: Option<&DrivingStep>) {
+    let block = Block::bordered().title(" Latest step ");
+    let Some(step) = step else {
+        frame.render_widget(block, area);
+        return;
+    };
+
+    let wheels = step.speed.wheel_speeds;
+    let mut rows = vec![
+        ("Step", step.step_name.clone()),
+        ("Duration", format!("{} ms", step.duration_ms)),
+        ("Engine", flag(step.engine.engine_running).to_string()),
+        ("Gear", format::gear_label(step.speed.gear_position)),
+        (
+            "Wheels",
+            format!(
+                "{:.0} / {:.0} / {:.0} / {:.0}",
+                wheels[0], wheels[1], wheels[2], wheels[3]
+            ),
+        ),
+        ("ABS", flag(step.speed.abs_active).to_string()),
+        ("Traction", flag(step.speed.traction_control).to_string()),
+        ("Cruise", flag(step.speed.cruise_control).to_string()),
+        ("Intake", format!("{}°C", step.engine.intake_temp)),
+        (
+            "Fuel pressure",
+            format!("{} kPa", step.engine.fuel_pressure),
+        ),
+        (
+            "Cabin",
+            format!(
+                "{}°C (target {}°C, outside {}°C)",
+                step.climate.cabin_temp, step.climate.target_temp, step.climate.outside_temp
+            ),
+        ),
+        ("Fan", format!("{}%", step.climate.fan_speed)),
+    ];
+    if let Some(adas) = &step.adas {
+        rows.push(("Lead", format!("{:.1} m", adas.lead_distance)));
+    }
+    if let Some(fuel) = &step.fuel {
+        rows.push(("Consumption", format!("{:.1} L/100km", fuel.consumption)));
+    }
+    if let Some(gps) = &step.gps {
+        rows.push(("GPS", format!("{:.5}, {:.5}", gps.latitude, gps.longitude)));
+    }
+
+    let table = Table::new(
+        rows.into_iter()
+            .map(|(name, value)| Row::new(vec![Cell::from(name), Cell::from(value)])),
+        [Constraint::Length(14), Constraint::Min(10)],
+    )
+    .block(block);
+    frame.render_widget(table, area);
+}
+
+fn draw_frames(frame: &mut Frame, area: Rect, state: &MonitorState) {
+    let header = Row::new(vec!["ID", "Purpose", "DLC", "Data", "Count"])
+        .style(Style::new().add_modifier(Modifier::BOLD));
+    let rows = state.frames.iter().map(|(id, stats)| {
+        let data = stats
+            .payload
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect::<Vec<_>>()
+            .join(" ");
+        // Frames missing from recent steps are dimmed
+        let style = if stats.last_seen.elapsed() > STALE_AFTER {
+            Style::new().fg(Color::DarkGray)
+        } else {
+            Style::new()
+        };
+        Row::new(vec![
+            Cell::from(format!("0x{:03X}", id)),
+            Cell::from(DrivingStep::can_id_purpose(*id)),
+            Cell::from(stats.dlc.to_string()),
+            Cell::from(data),
+            Cell::from(stats.count.to_string()),
+        ])
+        .style(style)
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(6),
+            Constraint::Min(20),
+            Constraint::Length(3),
+            Constraint::Length(23),
+            Constraint::Length(7),
+        ],
+    )
+    .header(header)
+    .block(Block::bordered().title(" CAN frames "));
+    frame.render_widget(table, area);
+}
+
+fn draw_log(frame: &mut Frame, area: Rect, state: &MonitorState) {
+    let items = state
+        .log
+        .iter()
+        .map(|line| ListItem::new(line.as_str()))
+        .collect::<Vec<_>>();
+    frame.render_widget(
+        List::new(items).block(Block::bordered().title(" Activity ")),
+        area,
+    );
+}
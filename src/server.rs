@@ -0,0 +1,611 @@
+use actix_web::dev::ServerHandle;
+use actix_web::middleware;
+use actix_web::{web, web::Data, App, HttpServer};
+use lapin::Channel;
+use sqlx::AnyPool;
+use tokio::sync::broadcast;
+
+use crate::config::amqp_link::AmqpLink;
+use crate::config::memory_queue::MemoryQueue;
+use crate::config::object_store::ObjectStore;
+use crate::config::resilience::CircuitBreaker;
+use crate::config::transport::{ChaosControl, ConsumerControl, StepTransport, TransportKind};
+use crate::config::{self, AppConfig};
+use crate::core::bus::Bus;
+use crate::core::connections::ConnectionLimiter;
+use crate::core::journal::Journal;
+use crate::core::session::SessionStore;
+use crate::core::topics::TopicRegistry;
+use crate::features::anomaly::AnomalyDetector;
+use crate::features::archive::Archive;
+use crate::features::command::CommandDispatcher;
+use crate::features::device::DevicePresence;
+use crate::features::export::ExportScheduler;
+use crate::features::geofence::GeofenceTracker;
+use crate::features::history::{Compactor, SignalRecorder};
+use crate::features::ingest::{StepAssembler, UploadRegistry};
+use crate::features::job::JobQueue;
+use crate::features::rule::RuleEngine;
+use crate::features::scenario::Scheduler;
+use crate::features::subscription::SubscriptionRegistry;
+use crate::features::summary::Summarizer;
+use crate::features::system::SystemEventKind;
+use crate::features::tenant::TenantRegistry;
+use crate::features::trash::TrashBin;
+use crate::features::trip::TripTracker;
+use crate::features::validation::FrameValidator;
+use crate::features::vehicle::VehicleStates;
+use crate::features::webhook::WebhookDispatcher;
+use crate::{core, features};
+
+fn io_error(error: impl ToString) -> std::io::Error {
+    std::io::Error::other(error.to_string())
+}
+
+/// Register every HTTP, SSE and WebSocket route of the event bus
+///
+/// Embedding applications that build their own `App` must also provide `Data<StepTransport>`,
+/// `Data<Bus>`, `Data<RuleEngine>`, `Data<GeofenceTracker>`, `Data<TripTracker>`,
+/// `Data<DevicePresence>`, `Data<CommandDispatcher>`, `Data<Scheduler>`, `Data<WebhookDispatcher>`,
+/// `Data<SubscriptionRegistry>`, `Data<ConsumerControl>`, `Data<ChaosControl>`,
+/// `Data<CircuitBreaker>`, `Data<FrameValidator>`, `Data<StepAssembler>`, `Data<UploadRegistry>`,
+/// `Data<TenantRegistry>`, `Data<Compactor>`, `Data<Archive>`, `Data<TrashBin>`, `Data<JobQueue>`,
+/// `Data<ExportScheduler>`, `Data<Summarizer>`, `Data<Journal>`, `Data<SessionStore>`,
+/// `Data<ConnectionLimiter>`, `Data<TopicRegistry>`, `Data<VehicleStates>` and `Data<AppSettings>`
+/// (`Data<CompressionConfig>`, `Data<ScrubRules>` and `Data<SigningPolicy>` fall back to defaults),
+/// and wrap the app with `tenant::authenticate` for API keys to be checked and
+/// `compression::compress` for compressed responses, then `timestamps::rewrite` for the
+/// `?timestamps=epoch_ms` format.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.configure(features::driving_step::configure)
+        .configure(features::summary::configure)
+        .configure(core::stream::configure)
+        .configure(core::topics::configure)
+        .configure(core::changes::configure)
+        .configure(core::metrics::configure)
+        .configure(core::signals::configure)
+        .configure(features::history::configure)
+        .configure(features::archive::configure)
+        .configure(features::export::configure)
+        .configure(core::websocket::configure)
+        .configure(features::event::configure)
+        .configure(features::rule::configure)
+        .configure(features::geofence::configure)
+        .configure(features::trip::configure)
+        .configure(features::anomaly::configure)
+        .configure(features::annotation::configure)
+        .configure(features::system::configure)
+        .configure(features::scenario::configure)
+        .configure(features::webhook::configure)
+        .configure(features::ingest::configure)
+        .configure(features::integrity::configure)
+        .configure(features::subscription::configure)
+        .configure(features::vehicle::configure)
+        .configure(features::device::configure)
+        .configure(features::command::configure)
+        .configure(features::validation::configure)
+        .configure(features::admin::configure)
+        .configure(features::snapshot::configure)
+        .configure(features::tag::configure)
+        .configure(features::trash::configure)
+        .configure(features::job::configure)
+        .configure(features::tenant::configure)
+        .configure(features::dashboard::configure)
+        .configure(features::health::configure);
+}
+
+/// Builder for an embeddable event-bus server
+///
+/// Every dependency the builder is not given is created from the `AppConfig`.
+#[derive(Default)]
+pub struct AppBuilder {
+    config: AppConfig,
+    pool: Option<AnyPool>,
+    channel: Option<Channel>,
+    transport: Option<StepTransport>,
+    bus: Option<Bus>,
+    validator: Option<FrameValidator>,
+}
+
+impl AppBuilder {
+    pub fn config(mut self, config: AppConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Use an existing database pool instead of opening the database of `config.settings`
+    pub fn pool(mut self, pool: AnyPool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Use an existing AMQP channel instead of connecting to the broker of `config.settings`,
+    /// still publishing on its queue
+    pub fn channel(mut self, channel: Channel) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    /// Use an existing step-notice transport, e.g. a shared in-memory queue, over any
+    /// `channel`
+    pub fn transport(mut self, transport: StepTransport) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Share an existing broadcast bus with the server
+    pub fn bus(mut self, bus: Bus) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Validate ingested frames with these rules instead of the defaults of the `AppConfig`
+    pub fn validator(mut self, validator: FrameValidator) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    /// Connect dependencies, start the step-name consumer and bind the HTTP server
+    pub async fn build(self) -> std::io::Result<Server> {
+        let AppBuilder {
+            config,
+            pool,
+            channel,
+            transport,
+            bus,
+            validator,
+        } = self;
+
+        let bus = bus.unwrap_or_else(|| broadcast::channel(config.settings.bus.capacity).0);
+
+        // CAN IDs (process-wide, fixed by the first server or the first frame encoded)
+        if let Err(can_ids) = core::can_map::set_can_ids(config.can_ids.clone()) {
+            if &can_ids != core::can_map::can_ids() {
+                return Err(io_error("Another CAN ID map is already in force"));
+            }
+        }
+
+        // User DBC (process-wide, after the CAN IDs its messages must stay clear of)
+        config
+            .dbc
+            .check_ids(core::can_map::can_ids())
+            .map_err(io_error)?;
+        if let Err(dbc) = core::dbc::set_dbc(config.dbc.clone()) {
+            if &dbc != core::dbc::dbc() {
+                return Err(io_error("Another DBC is already in force"));
+            }
+        }
+
+        // Encryption key (process-wide, fixed by the first server)
+        if let Err(key) = config::encryption::set_key(config.encryption_key.clone()) {
+            if key.as_ref() != config::encryption::key() {
+                return Err(io_error("Another encryption key is already in force"));
+            }
+        }
+
+        // Database (before the consumer, which reads frames through the shared pool)
+        // Logging is process-wide, a second server keeps the settings of the first
+        let _ = config::db::set_sql_log(config.sql_log);
+        let pool = match pool {
+            Some(pool) => {
+                config::db::set_pool(pool)
+                    .map_err(|_| io_error("Database pool is already initialized"))?;
+                config::db::get_pool().await.map_err(io_error)?
+            }
+            None => {
+                config
+                    .settings
+                    .database
+                    .url
+                    .parse::<config::db::DbBackend>()
+                    .map_err(io_error)?;
+                config::db::connect(&config.settings.database)
+                    .await
+                    .map_err(io_error)?
+            }
+        };
+        config::db::init().await.map_err(io_error)?;
+
+        // Integrity check (frame groups left broken by a crash, before anything reads them)
+        if let Some(repair) = config.startup_verify {
+            let report = features::integrity::controller::verify(repair)
+                .await
+                .map_err(io_error)?;
+            println!(
+                "🩺 Verified {} stored step(s): {} broken, {} repaired",
+                report.groups,
+                report.broken.len(),
+                report.repaired
+            );
+            for (reason, count) in &report.failures {
+                println!("   {}: {}", reason, count);
+            }
+        }
+
+        // Rules (evaluated against every reconstructed step on the bus)
+        let rules = RuleEngine::load().await.map_err(io_error)?;
+        rules.spawn(&bus);
+
+        // Geofences (tracked against the GPS fix of every reconstructed step)
+        let geofences = GeofenceTracker::load().await.map_err(io_error)?;
+        geofences.spawn(&bus);
+
+        // Trips (segmented from engine on/off and idle periods)
+        let trips = TripTracker::load(config.trip_idle_timeout)
+            .await
+            .map_err(io_error)?;
+        trips.spawn(&bus);
+
+        // Device presence (heartbeats of ingesting devices, silent ones reported offline)
+        let devices = DevicePresence::load(config.device_timeout)
+            .await
+            .map_err(io_error)?;
+        devices.spawn(&bus);
+
+        // Anomaly detection (moving statistics of every numeric signal)
+        let anomalies = AnomalyDetector::new(config.anomaly_sigma);
+        anomalies.spawn(&bus);
+
+        // Journal (numbered bus messages replayed to websocket clients resuming a session)
+        let journal = Journal::new(config.journal_capacity, config.settings.bus.capacity);
+        journal.spawn(&bus);
+        let sessions = SessionStore::new(config.ws_resume_window);
+
+        // Topic statistics (publish rate of each topic and the stream clients following it)
+        let topics = TopicRegistry::new();
+        topics.spawn(&bus);
+
+        // Stream clients (WebSocket and SSE connections counted against their limits)
+        let connections = ConnectionLimiter::new(config.connection_limits);
+
+        // Summaries (the bus rolled up once per interval for slow stream clients)
+        let summarizer = Summarizer::new(config.summary_interval);
+        summarizer.spawn(&bus);
+
+        // Signal history (every decoded value, for the bucketed time-series queries)
+        SignalRecorder.spawn(&bus);
+
+        // Vehicle states (latest value of every signal of each vehicle)
+        let vehicle_states = VehicleStates::new();
+        vehicle_states.spawn(&bus);
+
+        // Compaction (raw frames past the retention window folded into signal aggregates)
+        let compactor = Compactor::new(config.raw_frame_retention, bus.clone());
+        compactor.spawn(config.compaction_interval);
+
+        // Archive (frames past the archive window moved to compressed segment files)
+        let archive_store = match (&config.settings.s3, config.archive_to_s3) {
+            (_, false) => ObjectStore::Local(config.archive_dir.clone()),
+            (Some(bucket), true) => ObjectStore::S3(bucket.clone()).scoped("archive/"),
+            (None, true) => {
+                return Err(io_error(
+                    "Archiving to S3 needs an [s3] section in the settings",
+                ))
+            }
+        };
+        let archive = Archive::new(archive_store, config.archive_after, bus.clone());
+        archive.spawn(config.compaction_interval);
+
+        // Trash (soft-deleted steps, events and scenarios purged past their retention)
+        let trash = TrashBin::new(config.trash_retention);
+        trash.spawn(config.compaction_interval);
+
+        // Jobs (imports, replays, recodes and compactions run off the request handlers)
+        let jobs = JobQueue::load(config.job_workers).await.map_err(io_error)?;
+        jobs.spawn();
+
+        // Exports (steps written to CSV or Parquet files on their cron schedule)
+        let exports = ExportScheduler::load(
+            bus.clone(),
+            config.settings.s3.clone(),
+            config.settings.exports.clone(),
+            jobs.clone(),
+        )
+        .await
+        .map_err(io_error)?;
+        exports.spawn();
+
+        // Webhooks (signed POST of subscribed bus messages, retried with backoff)
+        let webhooks = WebhookDispatcher::load().await.map_err(io_error)?;
+        webhooks.spawn(&bus);
+
+        // Validation of ingested frames (rejected or quarantined to `rejected_frames`)
+        let validator = validator.unwrap_or_else(|| {
+            FrameValidator::with_defaults(config.validation_mode, config.frame_rate_limit)
+        });
+
+        // Step assembly (frames sent one by one, incomplete steps timed out with an event)
+        let assembler = StepAssembler::new(config.step_assembly_timeout);
+        assembler.spawn(&bus);
+
+        // Chunked uploads (large logs written to disk, then stored by a background task)
+        let uploads = UploadRegistry::new(&config.upload_dir, config.upload_idle_timeout)?;
+        uploads.spawn();
+
+        // Stream subscriptions (stored filter sets referenced by `/ws` and `/stream` clients)
+        let subscriptions = SubscriptionRegistry::load().await.map_err(io_error)?;
+
+        // API keys (checked on every request once an administrator key is configured)
+        let tenants = TenantRegistry::load(
+            config.admin_api_key.as_deref(),
+            config.viewer_api_key.as_deref(),
+        )
+        .await
+        .map_err(io_error)?;
+
+        // RabbitMQ (or the in-memory queue standing in for it), reconnected when it fails
+        let queue = config.settings.rabbitmq.queue.as_str();
+        let transport = match (transport, channel, config.transport) {
+            (Some(transport), _, _) => transport,
+            (None, Some(channel), _) => StepTransport::Amqp(channel, queue.into()),
+            (None, None, TransportKind::Memory) => StepTransport::Memory(MemoryQueue::new()),
+            (None, None, TransportKind::Amqp) => {
+                let link = AmqpLink::connect(
+                    &config.settings.rabbitmq.url,
+                    queue,
+                    config.single_active_consumer,
+                    &bus,
+                )
+                .await
+                .map_err(io_error)?;
+                StepTransport::Supervised(link)
+            }
+        };
+        let consumer = ConsumerControl::default();
+        transport.consume(&bus, &consumer).await.map_err(io_error)?;
+
+        // Chaos testing (notices dropped, duplicated or delayed, off until `/admin/chaos`)
+        let chaos = ChaosControl::default();
+        let transport = transport.with_chaos(chaos.clone());
+
+        // Publish retries (notices kept in `step_outbox` while the broker is unavailable)
+        let breaker = CircuitBreaker::new(config.breaker_threshold, config.breaker_cooldown);
+        let transport = transport.with_resilience(config.publish_retry, breaker.clone());
+        transport.relay_outbox();
+
+        // SocketCAN (frames of a real or virtual interface, stored like `POST /ingest` bodies)
+        if let Some(interface) = &config.can_interface {
+            #[cfg(all(feature = "socketcan", target_os = "linux"))]
+            core::socketcan::SocketCanReader::open(interface)?
+                .spawn(validator.clone(), transport.clone());
+            #[cfg(not(all(feature = "socketcan", target_os = "linux")))]
+            return Err(io_error(format!(
+                "CAN_INTERFACE={} needs a Linux build with the socketcan feature",
+                interface
+            )));
+        }
+
+        // Scheduled scenario runs (publishing through the same transport as writers)
+        let scheduler = Scheduler::load(transport.clone(), bus.clone())
+            .await
+            .map_err(io_error)?;
+        scheduler.spawn();
+
+        // Device commands (sent over device websockets or AMQP queues, tracked until acked)
+        let commands = CommandDispatcher::new(transport.clone(), config.command_ttl);
+        commands.spawn();
+
+        // Server HTTP
+        let app_transport = transport.clone();
+        let app_bus = bus.clone();
+        let app_rules = rules.clone();
+        let app_geofences = geofences.clone();
+        let app_trips = trips.clone();
+        let app_devices = devices.clone();
+        let app_commands = commands.clone();
+        let app_scheduler = scheduler.clone();
+        let app_webhooks = webhooks.clone();
+        let app_subscriptions = subscriptions.clone();
+        let app_consumer = consumer.clone();
+        let app_chaos = chaos.clone();
+        let app_breaker = breaker.clone();
+        let app_validator = validator.clone();
+        let app_assembler = assembler.clone();
+        let app_uploads = uploads.clone();
+        let app_tenants = tenants.clone();
+        let app_compactor = compactor.clone();
+        let app_archive = archive.clone();
+        let app_trash = trash.clone();
+        let app_jobs = jobs.clone();
+        let app_exports = exports.clone();
+        let compression = config.compression.clamped();
+        let scrub = config.scrub;
+        let signing = config.ingest_signing;
+        let app_summarizer = summarizer.clone();
+        let app_journal = journal.clone();
+        let app_sessions = sessions.clone();
+        let app_connections = connections.clone();
+        let app_topics = topics.clone();
+        let app_vehicle_states = vehicle_states.clone();
+        let app_settings = config.settings.clone();
+        let server = &config.settings.server;
+        let http = HttpServer::new(move || {
+            App::new()
+                .wrap(middleware::from_fn(core::timestamps::rewrite))
+                .wrap(middleware::from_fn(core::compression::compress))
+                .wrap(middleware::from_fn(features::tenant::authenticate))
+                .wrap(middleware::Logger::new(
+                    "%{r}a %r %s %b %{Referer}i %{User-Agent}i %T",
+                ))
+                .app_data(Data::new(app_transport.clone()))
+                .app_data(Data::new(app_bus.clone()))
+                .app_data(Data::new(app_rules.clone()))
+                .app_data(Data::new(app_geofences.clone()))
+                .app_data(Data::new(app_trips.clone()))
+                .app_data(Data::new(app_devices.clone()))
+                .app_data(Data::new(app_commands.clone()))
+                .app_data(Data::new(app_scheduler.clone()))
+                .app_data(Data::new(app_webhooks.clone()))
+                .app_data(Data::new(app_subscriptions.clone()))
+                .app_data(Data::new(app_consumer.clone()))
+                .app_data(Data::new(app_chaos.clone()))
+                .app_data(Data::new(app_breaker.clone()))
+                .app_data(Data::new(app_validator.clone()))
+                .app_data(Data::new(app_assembler.clone()))
+                .app_data(Data::new(app_uploads.clone()))
+                .app_data(Data::new(app_tenants.clone()))
+                .app_data(Data::new(app_compactor.clone()))
+                .app_data(Data::new(app_archive.clone()))
+                .app_data(Data::new(app_trash.clone()))
+                .app_data(Data::new(app_jobs.clone()))
+                .app_data(Data::new(app_exports.clone()))
+                .app_data(Data::new(compression))
+                .app_data(Data::new(scrub))
+                .app_data(Data::new(signing))
+                .app_data(Data::new(app_summarizer.clone()))
+                .app_data(Data::new(app_journal.clone()))
+                .app_data(Data::new(app_sessions.clone()))
+                .app_data(Data::new(app_connections.clone()))
+                .app_data(Data::new(app_topics.clone()))
+                .app_data(Data::new(app_vehicle_states.clone()))
+                .app_data(Data::new(app_settings.clone()))
+                .configure(configure)
+        })
+        .bind((server.host.as_str(), server.port))?
+        .run();
+
+        // Lifecycle (stream clients see the restart next to the telemetry)
+        features::system::controller::publish(
+            &bus,
+            SystemEventKind::ServerStarted,
+            format!(
+                "Server {} listening on {}:{} ({} transport)",
+                env!("CARGO_PKG_VERSION"),
+                server.host,
+                server.port,
+                transport.kind().as_str()
+            ),
+            serde_json::json!({
+                "host": server.host,
+                "port": server.port,
+                "transport": transport.kind().as_str(),
+                "version": env!("CARGO_PKG_VERSION"),
+            }),
+        )
+        .await;
+
+        let handles = ServerHandles {
+            bus,
+            transport,
+            rules,
+            geofences,
+            trips,
+            devices,
+            commands,
+            anomalies,
+            scheduler,
+            webhooks,
+            subscriptions,
+            consumer,
+            chaos,
+            breaker,
+            validator,
+            assembler,
+            uploads,
+            tenants,
+            compactor,
+            archive,
+            trash,
+            jobs,
+            exports,
+            summarizer,
+            journal,
+            sessions,
+            connections,
+            topics,
+            vehicle_states,
+            pool: pool.clone(),
+            http: http.handle(),
+        };
+
+        Ok(Server { http, handles })
+    }
+}
+
+/// Handles to the running stack, usable while the server is running
+#[derive(Clone)]
+pub struct ServerHandles {
+    /// Broadcast bus carrying reconstructed DrivingSteps and events
+    pub bus: Bus,
+    /// Transport used to publish step notices
+    pub transport: StepTransport,
+    /// Rules evaluated against every reconstructed step
+    pub rules: RuleEngine,
+    /// Geofences tracked against every reconstructed step
+    pub geofences: GeofenceTracker,
+    /// Trip segmentation of the reconstructed steps
+    pub trips: TripTracker,
+    /// Heartbeats of the ingesting devices, reporting silent ones offline
+    pub devices: DevicePresence,
+    /// Commands queued for devices, waiting for delivery or acknowledgment
+    pub commands: CommandDispatcher,
+    /// Moving signal statistics used to flag anomalies
+    pub anomalies: AnomalyDetector,
+    /// Cron schedules running built-in scenarios
+    pub scheduler: Scheduler,
+    /// Outbound HTTP subscriptions to bus messages
+    pub webhooks: WebhookDispatcher,
+    /// Stored filter sets of stream clients
+    pub subscriptions: SubscriptionRegistry,
+    /// Pause switch of the step-notice consumer
+    pub consumer: ConsumerControl,
+    /// Faults injected into published step notices
+    pub chaos: ChaosControl,
+    /// Circuit breaker routing step notices to the outbox while the broker fails
+    pub breaker: CircuitBreaker,
+    /// Rules applied to ingested frames before they are stored
+    pub validator: FrameValidator,
+    /// Frames of steps sent one by one, waiting for the rest of their step
+    pub assembler: StepAssembler,
+    /// Chunked uploads of large logs, open or being stored
+    pub uploads: UploadRegistry,
+    /// API keys accepted by the server, with the tenant of each
+    pub tenants: TenantRegistry,
+    /// Periodic replacement of old raw frames by signal aggregates
+    pub compactor: Compactor,
+    /// Periodic move of old frames to compressed segment files
+    pub archive: Archive,
+    /// Soft-deleted records, purged once past their retention
+    pub trash: TrashBin,
+    /// Background workers of imports, replays, recodes and compactions
+    pub jobs: JobQueue,
+    /// Scheduled exports of the stored steps to CSV or Parquet files
+    pub exports: ExportScheduler,
+    /// Rolled-up bus state published once per interval
+    pub summarizer: Summarizer,
+    /// Latest bus messages, numbered for websocket clients resuming a session
+    pub journal: Journal,
+    /// Websocket sessions that can be resumed with their token
+    pub sessions: SessionStore,
+    /// WebSocket and SSE clients connected, within their limits
+    pub connections: ConnectionLimiter,
+    /// Traffic of every bus topic and the stream clients following it
+    pub topics: TopicRegistry,
+    /// Latest signal values of every vehicle
+    pub vehicle_states: VehicleStates,
+    /// Database pool storing CAN frames, SQLite or PostgreSQL
+    pub pool: AnyPool,
+    /// Handle to stop the HTTP server
+    pub http: ServerHandle,
+}
+
+/// A bound event-bus server, ready to be run or spawned
+pub struct Server {
+    http: actix_web::dev::Server,
+    handles: ServerHandles,
+}
+
+impl Server {
+    pub fn builder() -> AppBuilder {
+        AppBuilder::default()
+    }
+
+    pub fn handles(&self) -> &ServerHandles {
+        &self.handles
+    }
+
+    /// Run until the HTTP server is stopped
+    pub async fn run(self) -> std::io::Result<()> {
+        self.http.await
+    }
+}
@@ -0,0 +1,305 @@
+//! Factories and in-memory infrastructure for writing pipeline tests without a broker
+//!
+//! Enabled with the `test_support` feature.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use sqlx::any::AnyConnectOptions;
+use sqlx::AnyPool;
+use tokio::sync::broadcast;
+
+use crate::config::memory_queue::MemoryQueue;
+use crate::config::transport::StepTransport;
+use crate::core::bus::{Bus, BusMessage};
+use crate::core::can::{CanError, CanMessage};
+use crate::core::clock::Clock;
+use crate::features::driving_step::model::{
+    AdasData, ClimateData, EngineData, FuelData, GpsData, VehicleSpeedData,
+};
+use crate::features::driving_step::DrivingStep;
+
+/// Clock producing predictable, strictly increasing RFC3339 timestamps
+#[derive(Debug, Clone)]
+pub struct TestClock {
+    now: DateTime<Utc>,
+    tick: Duration,
+}
+
+impl Default for TestClock {
+    fn default() -> Self {
+        TestClock::new(
+            Utc.with_ymd_and_hms(2024, 1, 1, 8, 0, 0).unwrap(),
+            Duration::milliseconds(100),
+        )
+    }
+}
+
+impl TestClock {
+    pub fn new(start: DateTime<Utc>, tick: Duration) -> Self {
+        TestClock { now: start, tick }
+    }
+
+    /// Current time without advancing the clock
+    pub fn now(&self) -> DateTime<Utc> {
+        self.now
+    }
+
+    /// Return the current timestamp, then advance by one tick
+    pub fn next_timestamp(&mut self) -> String {
+        let timestamp = self.now.to_rfc3339();
+        self.now += self.tick;
+        timestamp
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.now
+    }
+}
+
+/// Builder for DrivingSteps, starting from an idling vehicle in park
+#[derive(Debug, Clone)]
+pub struct DrivingStepBuilder {
+    step: DrivingStep,
+}
+
+impl Default for DrivingStepBuilder {
+    fn default() -> Self {
+        DrivingStepBuilder {
+            step: DrivingStep {
+                step_name: "Test Step".to_string(),
+                step_id: None,
+                engine: EngineData {
+                    rpm: 800,
+                    coolant_temp: 80,
+                    throttle_pos: 0,
+                    engine_load: 15,
+                    intake_temp: 30,
+                    fuel_pressure: 300,
+                    engine_running: true,
+                },
+                speed: VehicleSpeedData {
+                    vehicle_speed: 0.0,
+                    gear_position: 0,
+                    wheel_speeds: [0.0; 4],
+                    abs_active: false,
+                    traction_control: true,
+                    cruise_control: false,
+                },
+                climate: ClimateData {
+                    cabin_temp: 21,
+                    target_temp: 21,
+                    outside_temp: 15,
+                    fan_speed: 30,
+                    ac_compressor: false,
+                    heater: false,
+                    defrost: false,
+                    auto_mode: true,
+                    air_recirculation: false,
+                },
+                adas: None,
+                fuel: None,
+                gps: None,
+                duration_ms: 1000,
+                pipeline: None,
+                frames: Vec::new(),
+                tenant: None,
+                vehicle_id: None,
+            },
+        }
+    }
+}
+
+impl DrivingStepBuilder {
+    pub fn new(step_name: impl Into<String>) -> Self {
+        DrivingStepBuilder::default().name(step_name)
+    }
+
+    pub fn name(mut self, step_name: impl Into<String>) -> Self {
+        self.step.step_name = step_name.into();
+        self
+    }
+
+    pub fn rpm(mut self, rpm: u16) -> Self {
+        self.step.engine.rpm = rpm;
+        self
+    }
+
+    pub fn throttle(mut self, throttle_pos: u8) -> Self {
+        self.step.engine.throttle_pos = throttle_pos;
+        self
+    }
+
+    pub fn coolant_temp(mut self, coolant_temp: i16) -> Self {
+        self.step.engine.coolant_temp = coolant_temp;
+        self
+    }
+
+    pub fn engine_running(mut self, engine_running: bool) -> Self {
+        self.step.engine.engine_running = engine_running;
+        self
+    }
+
+    /// Set vehicle speed and give all four wheels the same speed
+    pub fn speed(mut self, vehicle_speed: f32) -> Self {
+        self.step.speed.vehicle_speed = vehicle_speed;
+        self.step.speed.wheel_speeds = [vehicle_speed.min(255.0); 4];
+        self
+    }
+
+    pub fn gear(mut self, gear_position: u8) -> Self {
+        self.step.speed.gear_position = gear_position;
+        self
+    }
+
+    pub fn abs_active(mut self, abs_active: bool) -> Self {
+        self.step.speed.abs_active = abs_active;
+        self
+    }
+
+    pub fn cruise_control(mut self, cruise_control: bool) -> Self {
+        self.step.speed.cruise_control = cruise_control;
+        self
+    }
+
+    pub fn cabin_temp(mut self, cabin_temp: i16) -> Self {
+        self.step.climate.cabin_temp = cabin_temp;
+        self
+    }
+
+    pub fn adas(mut self, adas: AdasData) -> Self {
+        self.step.adas = Some(adas);
+        self
+    }
+
+    pub fn fuel(mut self, fuel: FuelData) -> Self {
+        self.step.fuel = Some(fuel);
+        self
+    }
+
+    pub fn gps(mut self, latitude: f64, longitude: f64) -> Self {
+        self.step.gps = Some(GpsData {
+            latitude,
+            longitude,
+        });
+        self
+    }
+
+    pub fn duration_ms(mut self, duration_ms: u64) -> Self {
+        self.step.duration_ms = duration_ms;
+        self
+    }
+
+    pub fn build(self) -> DrivingStep {
+        self.step
+    }
+}
+
+/// Builder for single CAN frames
+#[derive(Debug, Clone)]
+pub struct CanMessageBuilder {
+    id: u16,
+    dlc: u8,
+    data: [u8; 8],
+    timestamp: String,
+}
+
+impl CanMessageBuilder {
+    pub fn new(id: u16) -> Self {
+        CanMessageBuilder {
+            id,
+            dlc: 0,
+            data: [0; 8],
+            timestamp: TestClock::default().next_timestamp(),
+        }
+    }
+
+    /// Set the payload, deriving the DLC from its length (extra bytes are ignored)
+    pub fn payload(mut self, payload: &[u8]) -> Self {
+        let len = payload.len().min(8);
+        self.data = [0; 8];
+        self.data[..len].copy_from_slice(&payload[..len]);
+        self.dlc = len as u8;
+        self
+    }
+
+    pub fn dlc(mut self, dlc: u8) -> Self {
+        self.dlc = dlc;
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: impl Into<String>) -> Self {
+        self.timestamp = timestamp.into();
+        self
+    }
+
+    /// Build through `CanMessage::try_new`, as untrusted input would be
+    pub fn try_build(self) -> Result<CanMessage, CanError> {
+        if self.dlc > 8 {
+            return Err(CanError::InvalidDlc(self.dlc));
+        }
+        CanMessage::try_new(self.id, &self.data[..self.dlc as usize], self.timestamp)
+    }
+
+    /// Build without validation, to produce identifiers a real bus could not carry (a DLC
+    /// past 8 is clamped by `CanMessage::new_unchecked`)
+    pub fn build_unchecked(self) -> CanMessage {
+        CanMessage::new_unchecked(self.id, self.dlc, self.data, self.timestamp)
+    }
+}
+
+/// Encode `step` with timestamps taken from `clock` instead of the wall clock
+pub fn frames_for(
+    step: &DrivingStep,
+    is_big_endian: bool,
+    clock: &mut TestClock,
+) -> Vec<CanMessage> {
+    let frames = step.to_can_messages_with_clock(is_big_endian, clock);
+    clock.next_timestamp();
+    frames
+}
+
+/// Six-step drive used by the example: start, first gear, acceleration, cruise, braking, stop
+pub fn commute_scenario() -> Vec<DrivingStep> {
+    crate::features::scenario::catalog::commute()
+}
+
+/// Open a private in-memory SQLite database with the crate schema applied
+///
+/// A single connection is used because every in-memory connection is its own database.
+/// When `TEST_DATABASE_URL` names a PostgreSQL database, its `public` schema is dropped and
+/// the crate schema applied there instead, to run the tests against PostgreSQL; tests
+/// sharing that database must then run one at a time (`--test-threads=1`).
+pub async fn memory_pool() -> sqlx::Result<AnyPool> {
+    let url = std::env::var("TEST_DATABASE_URL").unwrap_or_else(|_| "sqlite::memory:".into());
+    let pool = crate::config::db::open(AnyConnectOptions::from_str(&url)?).await?;
+    if crate::config::db::DbBackend::of(&pool)? == crate::config::db::DbBackend::Postgres {
+        sqlx::raw_sql("DROP SCHEMA IF EXISTS public CASCADE; CREATE SCHEMA public")
+            .execute(&pool)
+            .await?;
+    }
+    crate::config::db::apply_schema(&pool).await?;
+    Ok(pool)
+}
+
+/// Install an in-memory database as the crate-wide pool used by the services
+///
+/// The pool is process-wide, so this can only take effect once per test binary;
+/// later calls reuse the already installed pool. Its connection does not outlive the
+/// runtime that opened it, so the tests using it must share one runtime.
+pub async fn install_memory_database() -> sqlx::Result<&'static AnyPool> {
+    let _ = crate::config::db::set_pool(memory_pool().await?);
+    crate::config::db::get_pool().await
+}
+
+/// In-process bus with one subscriber already attached
+pub fn memory_bus(capacity: usize) -> (Bus, broadcast::Receiver<BusMessage>) {
+    broadcast::channel(capacity)
+}
+
+/// Step-notice transport backed by an in-process queue instead of RabbitMQ
+pub fn memory_transport() -> StepTransport {
+    StepTransport::Memory(MemoryQueue::new())
+}
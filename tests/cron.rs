@@ -0,0 +1,99 @@
+//! Fire times of the cron expressions accepted by scenario schedules and exports
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use canbus_rmq_realtime::features::scenario::catalog;
+use canbus_rmq_realtime::features::scenario::scheduler::parse_cron;
+
+fn at(rfc3339: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(rfc3339)
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+/// The next `count` fire times after `since`, as RFC 3339 strings
+fn next(expression: &str, since: &str, count: usize) -> Vec<String> {
+    parse_cron(expression)
+        .unwrap()
+        .after(&at(since))
+        .take(count)
+        .map(|time| time.to_rfc3339())
+        .collect()
+}
+
+#[test]
+fn weekday_schedule_skips_the_weekend() {
+    // 2024-01-05 is a Friday
+    assert_eq!(
+        next("0 0 8 * * Mon-Fri", "2024-01-05T08:00:00Z", 2),
+        ["2024-01-08T08:00:00+00:00", "2024-01-09T08:00:00+00:00"]
+    );
+    assert_eq!(
+        next("0 0 8 * * Mon-Fri", "2024-01-05T07:59:59Z", 1),
+        ["2024-01-05T08:00:00+00:00"]
+    );
+}
+
+#[test]
+fn first_field_is_seconds() {
+    assert_eq!(
+        next("*/20 * * * * *", "2024-01-01T00:00:00Z", 3),
+        [
+            "2024-01-01T00:00:20+00:00",
+            "2024-01-01T00:00:40+00:00",
+            "2024-01-01T00:01:00+00:00"
+        ]
+    );
+    assert_eq!(
+        next("0 0 * * * *", "2024-01-01T10:30:00Z", 1),
+        ["2024-01-01T11:00:00+00:00"]
+    );
+}
+
+#[test]
+fn optional_year_field_ends_the_schedule() {
+    assert_eq!(
+        next("0 0 12 * * * 2025", "2024-12-30T00:00:00Z", 2),
+        ["2025-01-01T12:00:00+00:00", "2025-01-02T12:00:00+00:00"]
+    );
+    assert_eq!(
+        next("0 0 0 1 1 * 2025", "2024-06-01T00:00:00Z", 2),
+        ["2025-01-01T00:00:00+00:00"]
+    );
+    assert!(parse_cron("0 0 0 1 1 * 2020")
+        .unwrap()
+        .after(&Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap())
+        .next()
+        .is_none());
+}
+
+#[test]
+fn invalid_expressions_are_refused_with_the_expression() {
+    for expression in [
+        "",
+        "0 8 * * Mon",
+        "0 0 25 * * *",
+        "every day",
+        "0 0 8 * * Funday",
+    ] {
+        let error = parse_cron(expression).unwrap_err();
+        assert!(
+            error.starts_with(&format!("Invalid cron expression '{}': ", expression)),
+            "{}",
+            error
+        );
+    }
+}
+
+#[test]
+fn builtin_scenarios_are_found_by_name() {
+    assert_eq!(
+        catalog::builtin("commute").unwrap().steps,
+        catalog::commute()
+    );
+    assert_eq!(
+        catalog::builtin("city_loop").unwrap().steps,
+        catalog::city_loop()
+    );
+    assert!(catalog::builtin("moon_landing").is_none());
+}
@@ -0,0 +1,259 @@
+//! Property checks backing the decode API contract: decoding never panics on arbitrary
+//! input, encode → decode is the identity for values `DrivingStep::validate` accepts, and
+//! the byte order of encoded frames is detected back. Hex payloads and identifiers parse
+//! from hex digits only.
+
+use proptest::prelude::*;
+
+use canbus_rmq_realtime::core::can::{from_hex, to_hex, CanId, Endianness};
+use canbus_rmq_realtime::features::driving_step::model::{
+    AdasData, ClimateData, EngineData, FuelData, GpsData, VehicleSpeedData,
+};
+use canbus_rmq_realtime::{CanMessage, DrivingStep};
+
+fn temperature() -> impl Strategy<Value = i16> {
+    -40i16..=215
+}
+
+fn gear() -> impl Strategy<Value = u8> {
+    prop_oneof![0u8..=6, Just(15u8)]
+}
+
+/// DrivingSteps whose values sit exactly on the encoding resolution
+fn valid_step() -> impl Strategy<Value = DrivingStep> {
+    let engine = (
+        any::<u16>(),
+        temperature(),
+        0u8..=100,
+        0u8..=100,
+        temperature(),
+        (0u16..=6553).prop_map(|fuel| fuel * 10),
+        any::<bool>(),
+    )
+        .prop_map(
+            |(
+                rpm,
+                coolant_temp,
+                throttle_pos,
+                engine_load,
+                intake_temp,
+                fuel_pressure,
+                running,
+            )| {
+                EngineData {
+                    rpm,
+                    coolant_temp,
+                    throttle_pos,
+                    engine_load,
+                    intake_temp,
+                    fuel_pressure,
+                    engine_running: running,
+                }
+            },
+        );
+    let speed = (
+        (0u16..=u16::MAX).prop_map(|speed| speed as f32 / 10.0),
+        gear(),
+        prop::array::uniform4((0u8..=255).prop_map(f32::from)),
+        any::<(bool, bool, bool)>(),
+    )
+        .prop_map(
+            |(vehicle_speed, gear_position, wheel_speeds, (abs, traction, cruise))| {
+                VehicleSpeedData {
+                    vehicle_speed,
+                    gear_position,
+                    wheel_speeds,
+                    abs_active: abs,
+                    traction_control: traction,
+                    cruise_control: cruise,
+                }
+            },
+        );
+    let climate = (
+        temperature(),
+        temperature(),
+        temperature(),
+        any::<u8>(),
+        any::<(bool, bool, bool, bool, bool)>(),
+    )
+        .prop_map(
+            |(cabin_temp, target_temp, outside_temp, fan_speed, flags)| ClimateData {
+                cabin_temp,
+                target_temp,
+                outside_temp,
+                fan_speed,
+                ac_compressor: flags.0,
+                heater: flags.1,
+                defrost: flags.2,
+                auto_mode: flags.3,
+                air_recirculation: flags.4,
+            },
+        );
+
+    let adas = (
+        any::<u16>().prop_map(|distance| distance as f32 / 10.0),
+        any::<i16>().prop_map(|speed| speed as f32 / 10.0),
+        any::<u8>(),
+        any::<(bool, bool, bool)>(),
+    )
+        .prop_map(
+            |(lead_distance, relative_speed, acc_set_speed, flags)| AdasData {
+                lead_distance,
+                relative_speed,
+                acc_set_speed,
+                acc_active: flags.0,
+                lane_keep_active: flags.1,
+                lane_departure_warning: flags.2,
+            },
+        );
+
+    let fuel = (
+        0u8..=100,
+        any::<u16>().prop_map(|consumption| consumption as f32 / 10.0),
+        any::<u16>(),
+    )
+        .prop_map(|(tank_level, consumption, range_remaining)| FuelData {
+            tank_level,
+            consumption,
+            range_remaining,
+        });
+
+    let gps = (
+        -900_000_000i32..=900_000_000,
+        -1_800_000_000i32..=1_800_000_000,
+    )
+        .prop_map(|(latitude, longitude)| GpsData {
+            latitude: latitude as f64 / 1e7,
+            longitude: longitude as f64 / 1e7,
+        });
+
+    (
+        "[A-Za-z ]{1,16}",
+        engine,
+        speed,
+        climate,
+        prop::option::of(adas),
+        prop::option::of(fuel),
+        prop::option::of(gps),
+        0u64..=u32::MAX as u64,
+    )
+        .prop_map(
+            |(step_name, engine, speed, climate, adas, fuel, gps, duration_ms)| DrivingStep {
+                step_name,
+                step_id: None,
+                engine,
+                speed,
+                climate,
+                adas,
+                fuel,
+                gps,
+                duration_ms,
+                pipeline: None,
+                frames: Vec::new(),
+                tenant: None,
+                vehicle_id: None,
+            },
+        )
+}
+
+fn arbitrary_frame() -> impl Strategy<Value = CanMessage> {
+    (
+        prop_oneof![
+            Just(0x100u16),
+            Just(0x101),
+            Just(0x102),
+            Just(0x600),
+            Just(0x200),
+            Just(0x201),
+            Just(0x300),
+            Just(0x301),
+            Just(0x400),
+            Just(0x500),
+            any::<u16>()
+        ],
+        any::<u8>(),
+        any::<[u8; 8]>(),
+    )
+        .prop_map(|(id, dlc, data)| CanMessage::new_unchecked(id, dlc, data, String::new()))
+}
+
+proptest! {
+    #[test]
+    fn encode_then_decode_is_identity(step in valid_step(), is_big_endian in any::<bool>()) {
+        prop_assert!(step.validate().is_ok());
+
+        let frames = step.to_can_messages_with_endian(is_big_endian);
+        let decoded = DrivingStep::from_can_messages_with_endian(
+            &frames,
+            step.step_name.clone(),
+            is_big_endian,
+        );
+
+        prop_assert_eq!(decoded, Ok(step));
+    }
+
+    #[test]
+    fn detected_endianness_is_the_encoded_one(step in valid_step(), is_big_endian in any::<bool>()) {
+        let frames = step.to_can_messages_with_endian(is_big_endian);
+
+        prop_assert_eq!(
+            DrivingStep::detect_endianness(&frames),
+            Some(Endianness::from_is_big_endian(is_big_endian))
+        );
+    }
+
+    #[test]
+    fn decode_never_panics(
+        frames in prop::collection::vec(arbitrary_frame(), 0..16),
+        is_big_endian in any::<bool>(),
+    ) {
+        let _ = DrivingStep::from_can_messages_with_endian(&frames, String::new(), is_big_endian);
+        for frame in &frames {
+            prop_assert!(frame.data().len() <= 8);
+        }
+    }
+
+    #[test]
+    fn bit_helpers_never_panic(
+        data in prop::collection::vec(any::<u8>(), 0..16),
+        start_bit in 0usize..256,
+        num_bits in 0usize..80,
+        value in any::<u64>(),
+    ) {
+        let mut buffer = data.clone();
+        CanMessage::set_bits_in_bytes(&mut buffer, start_bit, num_bits, value);
+        let _ = CanMessage::extract_bits_from_bytes(&buffer, start_bit, num_bits);
+        prop_assert_eq!(buffer.len(), data.len());
+    }
+
+    #[test]
+    fn set_then_extract_round_trips(
+        start_bit in 0usize..64,
+        num_bits in 1usize..=64,
+        value in any::<u64>(),
+    ) {
+        let mut buffer = [0u8; 16];
+        CanMessage::set_bits_in_bytes(&mut buffer, start_bit, num_bits, value);
+
+        let mask = if num_bits == 64 { u64::MAX } else { (1u64 << num_bits) - 1 };
+        prop_assert_eq!(
+            CanMessage::extract_bits_from_bytes(&buffer, start_bit, num_bits),
+            value & mask
+        );
+    }
+
+    #[test]
+    fn hex_round_trips(bytes in prop::collection::vec(any::<u8>(), 0..64)) {
+        prop_assert_eq!(from_hex(&to_hex(&bytes)), Ok(bytes));
+    }
+
+    #[test]
+    fn hex_parsing_takes_digits_only(hex in "[0-9a-fA-F+-]{0,7}") {
+        let digits = hex.bytes().all(|b| b.is_ascii_hexdigit());
+        prop_assert_eq!(from_hex(&hex).is_ok(), digits && hex.len().is_multiple_of(2));
+        prop_assert_eq!(
+            format!("0x{}", hex).parse::<CanId>().is_ok(),
+            digits && !hex.is_empty()
+        );
+    }
+}
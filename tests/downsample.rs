@@ -0,0 +1,75 @@
+//! Largest-triangle-three-buckets downsampling of signal history: the shape of its output
+//! on arbitrary series, and the extremes it keeps.
+
+use proptest::prelude::*;
+
+use canbus_rmq_realtime::features::history::downsample::lttb;
+
+/// Series of strictly increasing timestamps, as signal history is read
+fn series() -> impl Strategy<Value = Vec<(i64, f64)>> {
+    prop::collection::vec((1i64..5_000, -1e6f64..1e6), 0..400).prop_map(|steps| {
+        let mut timestamp = 1_700_000_000_000;
+        steps
+            .into_iter()
+            .map(|(step, value)| {
+                timestamp += step;
+                (timestamp, value)
+            })
+            .collect()
+    })
+}
+
+proptest! {
+    #[test]
+    fn keeps_threshold_samples_in_order(samples in series(), threshold in 0usize..500) {
+        let kept = lttb(&samples, threshold);
+
+        if threshold < 3 || samples.len() <= threshold {
+            prop_assert_eq!(kept, samples);
+        } else {
+            prop_assert_eq!(kept.len(), threshold);
+            prop_assert_eq!(kept[0], samples[0]);
+            prop_assert_eq!(kept[threshold - 1], samples[samples.len() - 1]);
+            prop_assert!(kept.windows(2).all(|pair| pair[0].0 < pair[1].0));
+            prop_assert!(kept.iter().all(|sample| samples.contains(sample)));
+        }
+    }
+}
+
+#[test]
+fn keeps_isolated_peaks_and_troughs() {
+    let mut samples: Vec<(i64, f64)> = (0..1_000).map(|i| (i * 100, 50.0)).collect();
+    samples[137].1 = 180.0;
+    samples[612].1 = -40.0;
+
+    let kept = lttb(&samples, 20);
+    assert!(kept.contains(&(13_700, 180.0)), "{:?}", kept);
+    assert!(kept.contains(&(61_200, -40.0)), "{:?}", kept);
+}
+
+#[test]
+fn keeps_a_ramp_straight() {
+    // Every sample of a straight line forms a triangle of zero area: the last of each
+    // bucket of two is kept
+    let samples: Vec<(i64, f64)> = (0..10).map(|i| (i, i as f64 * 2.0)).collect();
+    assert_eq!(
+        lttb(&samples, 6),
+        [
+            (0, 0.0),
+            (2, 4.0),
+            (4, 8.0),
+            (6, 12.0),
+            (8, 16.0),
+            (9, 18.0)
+        ]
+    );
+}
+
+#[test]
+fn large_timestamps_do_not_lose_precision() {
+    // Millisecond Unix timestamps squared overflow f64 precision without the origin shift
+    let samples: Vec<(i64, f64)> = (0..100)
+        .map(|i| (1_700_000_000_000 + i, if i == 42 { 1.0 } else { 0.0 }))
+        .collect();
+    assert!(lttb(&samples, 10).contains(&(1_700_000_000_042, 1.0)));
+}
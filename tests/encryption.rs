@@ -0,0 +1,122 @@
+//! Values sealed at rest: the key forms accepted, and what opens with which key and column
+//!
+//! The key is process-wide, so every test installs the same one.
+
+use canbus_rmq_realtime::config::encryption::{self, Column, EncryptionKey};
+
+const KEY_HEX: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+const KEY_BASE64: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+
+fn install_key() {
+    let key: EncryptionKey = KEY_HEX.parse().unwrap();
+    if let Err(installed) = encryption::set_key(Some(key.clone())) {
+        assert_eq!(installed, Some(key), "another key is already installed");
+    }
+    assert!(encryption::key().is_some());
+}
+
+#[test]
+fn keys_parse_from_hex_or_base64() {
+    let hex: EncryptionKey = KEY_HEX.parse().unwrap();
+    assert_eq!(KEY_BASE64.parse::<EncryptionKey>().unwrap(), hex);
+    assert_eq!(
+        format!(" {}\n", KEY_HEX).parse::<EncryptionKey>().unwrap(),
+        hex
+    );
+    assert_eq!(format!("{:?}", hex), "EncryptionKey(..)");
+
+    assert_eq!(
+        "AAECAwQFBgcICQoLDA0ODw=="
+            .parse::<EncryptionKey>()
+            .unwrap_err(),
+        "16 bytes, expected 32"
+    );
+    assert!("not a key!"
+        .parse::<EncryptionKey>()
+        .unwrap_err()
+        .starts_with("not base64 or hex: "));
+}
+
+#[test]
+fn text_round_trips_under_a_fresh_nonce() {
+    install_key();
+    let secret = "minio123";
+
+    let sealed = encryption::seal_text(Column::ExportSecret, secret);
+    assert!(sealed.starts_with("enc1:"), "{}", sealed);
+    assert!(!sealed.contains(secret));
+    assert_ne!(sealed, encryption::seal_text(Column::ExportSecret, secret));
+    assert_eq!(
+        encryption::open_text(Column::ExportSecret, sealed).unwrap(),
+        secret
+    );
+
+    // Rows written before the key was set read as they are
+    assert_eq!(
+        encryption::open_text(Column::EventMessage, "Overspeed".to_string()).unwrap(),
+        "Overspeed"
+    );
+}
+
+#[test]
+fn text_only_opens_in_its_column() {
+    install_key();
+    let sealed = encryption::seal_text(Column::EventMessage, "Harsh braking");
+
+    for column in [
+        Column::EventPayload,
+        Column::ExportSecret,
+        Column::FrameData,
+    ] {
+        assert_eq!(
+            encryption::open_text(column, sealed.clone()).unwrap_err(),
+            "Stored value does not decrypt with the configured key"
+        );
+    }
+}
+
+#[test]
+fn tampered_text_is_refused() {
+    install_key();
+    let sealed = encryption::seal_text(Column::EventPayload, r#"{"speed":130}"#);
+
+    let mut flipped = sealed.clone().into_bytes();
+    let last = flipped.len() - 3;
+    flipped[last] = if flipped[last] == b'A' { b'B' } else { b'A' };
+    assert!(
+        encryption::open_text(Column::EventPayload, String::from_utf8(flipped).unwrap()).is_err()
+    );
+
+    assert!(
+        encryption::open_text(Column::EventPayload, "enc1:%%%".to_string())
+            .unwrap_err()
+            .starts_with("Stored value is not valid ciphertext: ")
+    );
+    assert_eq!(
+        encryption::open_text(Column::EventPayload, "enc1:AAAA".to_string()).unwrap_err(),
+        "Stored value is too short to be encrypted"
+    );
+}
+
+#[test]
+fn frames_round_trip_and_clear_frames_pass_through() {
+    install_key();
+
+    for len in [0, 1, 8, 12, 64] {
+        let data: Vec<u8> = (0..len as u8).collect();
+        let sealed = encryption::seal_frame(&data);
+        // 12-byte nonce and 16-byte tag around the ciphertext
+        assert_eq!(sealed.len(), len + 28);
+        assert_eq!(encryption::open_frame(sealed, len).unwrap(), data);
+
+        // Frames stored in clear are as long as their DLC
+        assert_eq!(encryption::open_frame(data.clone(), len).unwrap(), data);
+    }
+
+    let segment = encryption::seal_frame(b"zstd archive segment");
+    assert_eq!(
+        encryption::open_sealed_frame(&segment).unwrap(),
+        b"zstd archive segment"
+    );
+    assert!(encryption::open_frame(vec![0; 40], 8).is_err());
+}
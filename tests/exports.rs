@@ -0,0 +1,143 @@
+//! Where scheduled exports may write, and who may create them
+
+use std::path::PathBuf;
+
+use actix_web::http::StatusCode;
+use actix_web::ResponseError;
+
+use canbus_rmq_realtime::config::s3::S3Bucket;
+use canbus_rmq_realtime::config::settings::ExportSettings;
+use canbus_rmq_realtime::features::export::controller;
+use canbus_rmq_realtime::features::export::model::{Destination, ExportFormat, ExportRequest};
+use canbus_rmq_realtime::features::export::ExportScheduler;
+use canbus_rmq_realtime::features::job::JobQueue;
+use canbus_rmq_realtime::features::tenant::{Role, Tenant, TenantRegistry, TenantScope};
+use canbus_rmq_realtime::test_support::{install_memory_database, memory_bus};
+
+const ADMIN_KEY: &str = "admin-secret";
+
+fn bucket(endpoint: &str) -> S3Bucket {
+    S3Bucket {
+        endpoint: endpoint.to_string(),
+        bucket: "telemetry".to_string(),
+        region: "us-east-1".to_string(),
+        prefix: String::new(),
+        access_key_id: "minio".to_string(),
+        secret_access_key: "minio123".to_string(),
+    }
+}
+
+fn local(dir: &str) -> Destination {
+    Destination::Local {
+        dir: PathBuf::from(dir),
+    }
+}
+
+fn request(name: &str, destination: Destination) -> ExportRequest {
+    ExportRequest {
+        name: name.to_string(),
+        format: ExportFormat::Csv,
+        destination,
+        cron: None,
+    }
+}
+
+/// Tests of the crate-wide pool share one runtime: its connection does not outlive the
+/// runtime that opened it
+#[actix_web::test]
+async fn exports_on_the_memory_database() {
+    install_memory_database().await.unwrap();
+    let (bus, _received) = memory_bus(16);
+    let settings = ExportSettings {
+        dir: std::env::temp_dir().join("eventbus-exports"),
+        s3_endpoints: vec!["https://s3.eu-west-3.amazonaws.com".to_string()],
+    };
+    let scheduler = ExportScheduler::load(
+        bus,
+        Some(bucket("http://minio:9000")),
+        settings,
+        JobQueue::load(1).await.unwrap(),
+    )
+    .await
+    .unwrap();
+
+    local_dirs_stay_under_the_root(&scheduler);
+    s3_endpoints_are_allowed_ones_only(&scheduler);
+    only_the_administrator_creates_exports(&scheduler).await;
+}
+
+fn local_dirs_stay_under_the_root(scheduler: &ExportScheduler) {
+    for dir in ["nightly", "fleet/nightly", "./nightly"] {
+        assert!(scheduler.check(&local(dir)).is_ok(), "{}", dir);
+    }
+    for dir in ["", "/etc", "../outside", "fleet/../../outside"] {
+        assert!(scheduler.check(&local(dir)).is_err(), "{}", dir);
+    }
+}
+
+fn s3_endpoints_are_allowed_ones_only(scheduler: &ExportScheduler) {
+    for endpoint in [
+        "http://minio:9000",
+        "http://minio:9000/",
+        "https://s3.eu-west-3.amazonaws.com",
+    ] {
+        assert!(
+            scheduler.check(&Destination::S3(bucket(endpoint))).is_ok(),
+            "{}",
+            endpoint
+        );
+    }
+    for endpoint in [
+        "http://169.254.169.254",
+        "http://127.0.0.1:8080",
+        "http://minio:9001",
+    ] {
+        let error = scheduler
+            .check(&Destination::S3(bucket(endpoint)))
+            .unwrap_err();
+        assert!(error.contains("exports.s3_endpoints"), "{}", error);
+    }
+}
+
+async fn only_the_administrator_creates_exports(scheduler: &ExportScheduler) {
+    let disabled = TenantRegistry::load(None, None).await.unwrap();
+    let registry = TenantRegistry::load(Some(ADMIN_KEY), None).await.unwrap();
+    let tenant = TenantScope(
+        Some(Tenant {
+            name: "acme".to_string(),
+            max_steps: None,
+            max_messages_per_minute: None,
+            max_bytes: None,
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+        }),
+        Role::Editor,
+    );
+    let viewer = TenantScope(None, Role::Viewer);
+
+    for (registry, scope) in [
+        (&disabled, &TenantScope::default()),
+        (&registry, &tenant),
+        (&registry, &viewer),
+    ] {
+        let error = controller::create(scheduler, registry, scope, request("nightly", local("n")))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status_code(), StatusCode::FORBIDDEN);
+    }
+
+    let admin = TenantScope::default();
+    let error = controller::create(
+        scheduler,
+        &registry,
+        &admin,
+        request("escape", local("/tmp")),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+
+    let export = controller::create(scheduler, &registry, &admin, request("nightly", local("n")))
+        .await
+        .unwrap();
+    assert_eq!(export.destination, local("n"));
+}
@@ -0,0 +1,85 @@
+//! Decimation of the stream clients: a topic delivers at most `max_rate` messages per
+//! second, always ending on the latest message.
+
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+
+use canbus_rmq_realtime::core::bus::{validate_max_rate, BusMessage, SubscriptionFilter};
+use canbus_rmq_realtime::core::feed::ClientFeed;
+use canbus_rmq_realtime::core::journal::Journal;
+use canbus_rmq_realtime::core::topics::TopicRegistry;
+use canbus_rmq_realtime::features::subscription::LiveFilter;
+use canbus_rmq_realtime::features::system::{SystemEvent, SystemEventKind};
+
+fn system(message: &str) -> BusMessage {
+    BusMessage::System(SystemEvent::new(
+        SystemEventKind::ServerStarted,
+        message,
+        serde_json::Value::Null,
+    ))
+}
+
+fn message_of(received: &BusMessage) -> &str {
+    match received {
+        BusMessage::System(event) => &event.message,
+        other => panic!("expected a system message, got {:?}", other),
+    }
+}
+
+fn feed(bus: &broadcast::Sender<BusMessage>, max_rate: f64) -> (ClientFeed, TopicRegistry) {
+    let registry = TopicRegistry::new();
+    let filter = SubscriptionFilter {
+        max_rate: Some(max_rate),
+        ..SubscriptionFilter::default()
+    };
+    let feed = ClientFeed::new(bus.subscribe(), LiveFilter::Fixed(filter), &registry);
+    (feed, registry)
+}
+
+#[tokio::test]
+async fn faster_messages_are_replaced_by_the_latest() {
+    let (bus, _rx) = broadcast::channel(16);
+    let (mut feed, registry) = feed(&bus, 20.0);
+
+    for message in ["first", "second", "third"] {
+        bus.send(system(message)).unwrap();
+    }
+
+    let (_, first) = feed.next().await.unwrap();
+    assert_eq!(message_of(&first), "first");
+
+    let (_, latest) = tokio::time::timeout(Duration::from_secs(1), feed.next())
+        .await
+        .expect("held message not delivered once the slot opened")
+        .unwrap();
+    assert_eq!(message_of(&latest), "third");
+
+    let stats = registry.stats(&bus, &Journal::new(16, 16));
+    let system = stats.topics.iter().find(|t| t.topic == "system").unwrap();
+    assert_eq!(system.dropped, 1);
+}
+
+#[tokio::test]
+async fn tiny_rates_are_refused_and_do_not_overflow_stored_feeds() {
+    assert!(validate_max_rate(Some(1e-20)).is_err());
+    assert!(validate_max_rate(Some(0.0)).is_err());
+    assert!(validate_max_rate(Some(f64::NAN)).is_err());
+    assert!(validate_max_rate(Some(0.001)).is_ok());
+    assert!(validate_max_rate(None).is_ok());
+
+    // A rate stored before the minimum was enforced holds the next message instead of
+    // panicking on its gap
+    let (bus, _rx) = broadcast::channel(16);
+    let (mut feed, _registry) = feed(&bus, 1e-20);
+    bus.send(system("first")).unwrap();
+    bus.send(system("second")).unwrap();
+
+    let (_, first) = feed.next().await.unwrap();
+    assert_eq!(message_of(&first), "first");
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), feed.next())
+            .await
+            .is_err()
+    );
+}
@@ -0,0 +1,119 @@
+//! The step pipeline end to end on the `test_support` fixtures: frames stored in an
+//! in-memory database, the notice sent through the in-process transport, and the step
+//! reconstructed by the consumer and broadcast on the bus.
+
+use std::time::Duration;
+
+use canbus_rmq_realtime::config::transport::ConsumerControl;
+use canbus_rmq_realtime::core::bus::BusMessage;
+use canbus_rmq_realtime::core::can::{CanMessage, Endianness};
+use canbus_rmq_realtime::features::driving_step::service;
+use canbus_rmq_realtime::test_support::{
+    commute_scenario, install_memory_database, memory_bus, memory_pool, memory_transport,
+    DrivingStepBuilder, TestClock,
+};
+
+#[tokio::test]
+async fn memory_pool_has_the_schema() {
+    let pool = memory_pool().await.unwrap();
+
+    for table in ["can_messages", "events", "jobs", "exports"] {
+        let count: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+            .fetch_one(&pool)
+            .await
+            .unwrap_or_else(|e| panic!("table {}: {}", table, e));
+        assert_eq!(count, 0, "table {}", table);
+    }
+}
+
+/// Tests of the crate-wide pool share one runtime: its connection does not outlive the
+/// runtime that opened it
+#[tokio::test]
+async fn services_on_the_memory_database() {
+    install_memory_database().await.unwrap();
+    published_step_is_broadcast_reconstructed().await;
+    commute_scenario_reads_back_as_stored().await;
+    can_fd_frames_read_back_in_full().await;
+}
+
+async fn published_step_is_broadcast_reconstructed() {
+    let (bus, mut received) = memory_bus(16);
+    let transport = memory_transport();
+    transport
+        .consume(&bus, &ConsumerControl::default())
+        .await
+        .unwrap();
+
+    let step = DrivingStepBuilder::new("Highway Cruise")
+        .rpm(2200)
+        .throttle(25)
+        .speed(90.0)
+        .gear(5)
+        .cruise_control(true)
+        .build();
+    let stored = service::publish_step_with_endian(&step, true, &transport)
+        .await
+        .unwrap();
+
+    let message = tokio::time::timeout(Duration::from_secs(5), received.recv())
+        .await
+        .expect("no step broadcast within 5s")
+        .unwrap();
+    let BusMessage::DrivingStep(broadcast) = message else {
+        panic!("expected a DrivingStep, got {:?}", message);
+    };
+    assert_eq!(broadcast.step_id.as_deref(), Some(stored.step_id.as_str()));
+    assert_eq!(broadcast.step_name, "Highway Cruise");
+    assert_eq!(broadcast.engine, step.engine);
+    assert_eq!(broadcast.speed.gear_position, 5);
+    assert_eq!(broadcast.speed.vehicle_speed, 90.0);
+    assert!(broadcast.speed.cruise_control);
+    assert!(broadcast.pipeline.is_some());
+}
+
+async fn commute_scenario_reads_back_as_stored() {
+    let mut clock = TestClock::default();
+
+    for step in commute_scenario() {
+        clock.next_timestamp();
+        let stored = service::store_step_with_clock(&step, false, &clock)
+            .await
+            .unwrap();
+        let read = service::reconstruct_step(&stored.step_id, String::new(), None)
+            .await
+            .unwrap()
+            .expect("stored step not found");
+
+        assert_eq!(read.step_name, step.step_name);
+        assert_eq!(read.engine, step.engine);
+        assert_eq!(read.climate, step.climate);
+        assert_eq!(read.speed.gear_position, step.speed.gear_position);
+        assert!(read
+            .frames
+            .iter()
+            .all(|frame| frame.timestamp == clock.now().to_rfc3339()));
+    }
+}
+
+async fn can_fd_frames_read_back_in_full() {
+    let frames: Vec<CanMessage> = [(0x321u16, 8u8), (0x322, 12), (0x323, 64)]
+        .into_iter()
+        .map(|(id, len)| {
+            let payload: Vec<u8> = (0..len).collect();
+            CanMessage::try_new_fd(id, &payload, len > 8, "2024-01-01T08:00:00Z").unwrap()
+        })
+        .collect();
+    let stored = service::store_frames(frames.clone(), Endianness::Little, None, None, "FD")
+        .await
+        .unwrap();
+
+    let read = service::get_step_frames(&stored.step_id, None)
+        .await
+        .unwrap()
+        .expect("stored step not found");
+    assert_eq!(read.can_messages.len(), frames.len());
+    for (read, frame) in read.can_messages.iter().zip(&frames) {
+        assert_eq!(read.data(), frame.data());
+        assert_eq!((read.fd, read.brs), (frame.fd, frame.brs));
+    }
+}
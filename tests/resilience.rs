@@ -0,0 +1,86 @@
+//! State machine of the circuit breaker in front of the step-notice broker
+
+use std::time::Duration;
+
+use canbus_rmq_realtime::config::resilience::{BreakerState, CircuitBreaker};
+
+const COOLDOWN: Duration = Duration::from_millis(50);
+
+fn opened_breaker() -> CircuitBreaker {
+    let breaker = CircuitBreaker::new(2, COOLDOWN);
+    for _ in 0..2 {
+        assert!(breaker.allow());
+        breaker.record_failure();
+    }
+    breaker
+}
+
+#[test]
+fn opens_after_threshold_failures_in_a_row() {
+    let breaker = CircuitBreaker::new(3, COOLDOWN);
+    for _ in 0..2 {
+        assert!(breaker.allow());
+        breaker.record_failure();
+    }
+    assert_eq!(breaker.status().state, BreakerState::Closed);
+
+    // A success in between starts the count again
+    breaker.record_success();
+    for _ in 0..2 {
+        breaker.record_failure();
+    }
+    assert_eq!(breaker.status().state, BreakerState::Closed);
+
+    breaker.record_failure();
+    let status = breaker.status();
+    assert_eq!(status.state, BreakerState::Open);
+    assert_eq!(status.opened, 1);
+    assert!(status.retry_in_ms.is_some());
+    assert!(!breaker.allow());
+}
+
+#[test]
+fn one_probe_after_the_cooldown_closes_on_success() {
+    let breaker = opened_breaker();
+    std::thread::sleep(COOLDOWN);
+
+    assert!(breaker.allow());
+    assert_eq!(breaker.status().state, BreakerState::HalfOpen);
+    // A second probe while the first is in flight
+    assert!(!breaker.allow());
+
+    breaker.record_success();
+    let status = breaker.status();
+    assert_eq!(status.state, BreakerState::Closed);
+    assert_eq!(status.consecutive_failures, 0);
+    assert!(breaker.allow());
+}
+
+#[test]
+fn failed_probe_opens_again() {
+    let breaker = opened_breaker();
+    std::thread::sleep(COOLDOWN);
+
+    assert!(breaker.allow());
+    breaker.record_failure();
+    let status = breaker.status();
+    assert_eq!(status.state, BreakerState::Open);
+    assert_eq!(status.opened, 2);
+    assert!(!breaker.allow());
+}
+
+#[test]
+fn abandoned_probe_is_given_up_after_the_cooldown() {
+    let breaker = opened_breaker();
+    std::thread::sleep(COOLDOWN);
+
+    // The probe is never recorded, as when its request is dropped mid-publish
+    assert!(breaker.allow());
+    assert!(!breaker.allow());
+    std::thread::sleep(COOLDOWN);
+
+    assert!(breaker.allow());
+    assert_eq!(breaker.status().state, BreakerState::HalfOpen);
+    breaker.record_success();
+    assert_eq!(breaker.status().state, BreakerState::Closed);
+}
@@ -0,0 +1,159 @@
+//! Behaviour of the rule expression language and of the engine firing rule events
+
+use canbus_rmq_realtime::features::event::model::Severity;
+use canbus_rmq_realtime::features::rule::dsl::{self, BinaryOp, Expr};
+use canbus_rmq_realtime::features::rule::engine::RuleEngine;
+use canbus_rmq_realtime::features::rule::model::Rule;
+use canbus_rmq_realtime::features::scenario::catalog;
+use canbus_rmq_realtime::DrivingStep;
+
+/// Vehicle at `speed` km/h, cruise control as given, without ADAS data
+fn cruising(speed: f32, cruise_control: bool) -> DrivingStep {
+    let mut step = catalog::commute()[0].clone();
+    step.speed.vehicle_speed = speed;
+    step.speed.wheel_speeds = [speed; 4];
+    step.speed.cruise_control = cruise_control;
+    step.adas = None;
+    step
+}
+
+fn matches(expression: &str, step: &DrivingStep) -> bool {
+    let expr = dsl::parse(expression).unwrap();
+    expr.matches(&serde_json::to_value(step).unwrap())
+}
+
+fn rule(name: &str, expression: &str) -> Rule {
+    Rule {
+        name: name.to_string(),
+        expression: expression.to_string(),
+        message: None,
+        severity: Severity::Critical,
+        created_at: "2024-01-01T00:00:00Z".to_string(),
+    }
+}
+
+#[test]
+fn operators_follow_their_precedence() {
+    let expr = dsl::parse("1 + 2 * 3 > 6 || false && false").unwrap();
+    let Expr::Binary(BinaryOp::Or, left, _) = &expr else {
+        panic!("|| should bind loosest, got {:?}", expr);
+    };
+    assert!(matches!(**left, Expr::Binary(BinaryOp::Gt, ..)));
+    assert!(matches(
+        "1 + 2 * 3 > 6 || false && false",
+        &cruising(0.0, false)
+    ));
+    assert!(!matches("(1 + 2) * 3 < 9", &cruising(0.0, false)));
+    assert!(matches(
+        "-speed.vehicle_speed < -100",
+        &cruising(120.0, false)
+    ));
+}
+
+#[test]
+fn paths_and_signal_names_read_the_step() {
+    let step = cruising(130.0, false);
+    assert!(matches(
+        "speed.vehicle_speed > 120 && !speed.cruise_control",
+        &step
+    ));
+    assert!(!matches(
+        "speed.vehicle_speed > 120 && !speed.cruise_control",
+        &cruising(130.0, true)
+    ));
+    assert!(matches("speed.wheel_speeds.3 == 130", &step));
+    assert!(matches("vehicle_speed / 2 == 65", &step));
+
+    // A signal name resolves to the same pointer as its path
+    assert_eq!(
+        dsl::parse("vehicle_speed").unwrap(),
+        dsl::parse("speed.vehicle_speed").unwrap()
+    );
+    assert!(matches(
+        &format!("step_name == \"{}\"", step.step_name),
+        &step
+    ));
+}
+
+#[test]
+fn absent_groups_make_comparisons_false() {
+    let step = cruising(90.0, false);
+    assert!(!matches("adas.lead_distance < 10", &step));
+    assert!(!matches("adas.lead_distance >= 10", &step));
+    // The comparison is false rather than null, so its negation holds
+    assert!(matches("!(adas.lead_distance < 10)", &step));
+    assert!(matches(
+        "adas.lead_distance < 10 || vehicle_speed > 50",
+        &step
+    ));
+    assert!(!matches("vehicle_speed / 0 > 1", &step));
+}
+
+#[test]
+fn invalid_expressions_report_where() {
+    for (expression, position, message) in [
+        ("speed.vehicle_speed >", 21, "unexpected end of expression"),
+        (
+            "speed.warp_factor > 9",
+            0,
+            "unknown field 'speed.warp_factor'",
+        ),
+        ("(vehicle_speed > 1", 18, "expected ')'"),
+        ("step_name == \"open", 13, "unterminated string"),
+        ("vehicle_speed # 2", 14, "unexpected character '#'"),
+        ("vehicle_speed 2", 14, "unexpected token"),
+    ] {
+        let error = dsl::parse(expression).unwrap_err();
+        assert_eq!(
+            (error.position, error.message.as_str()),
+            (position, message),
+            "{}",
+            expression
+        );
+    }
+}
+
+#[test]
+fn fields_are_listed_once_in_order_of_use() {
+    let expr = dsl::parse("vehicle_speed > 1 && coolant_temp > 2 && vehicle_speed < 3").unwrap();
+    assert_eq!(
+        expr.fields(),
+        vec!["/speed/vehicle_speed", "/engine/coolant_temp"]
+    );
+}
+
+#[test]
+fn rules_fire_once_per_transition() {
+    let engine = RuleEngine::default();
+    engine
+        .register(rule(
+            "overspeed",
+            "speed.vehicle_speed > 120 && !speed.cruise_control",
+        ))
+        .unwrap();
+    assert!(engine
+        .register(rule("broken", "speed.vehicle_speed >"))
+        .is_err());
+    assert_eq!(engine.rules().len(), 1);
+
+    assert!(engine.evaluate(&cruising(100.0, false)).is_empty());
+    let events = engine.evaluate(&cruising(130.0, false));
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].name, "overspeed");
+    assert_eq!(events[0].severity, Severity::Critical);
+    assert_eq!(
+        events[0].payload["values"],
+        serde_json::json!({"speed.vehicle_speed": 130.0, "speed.cruise_control": false})
+    );
+    assert_eq!(engine.active().len(), 1);
+
+    // Still speeding: the rule stays raised without another event
+    assert!(engine.evaluate(&cruising(140.0, false)).is_empty());
+    assert!(engine.evaluate(&cruising(90.0, false)).is_empty());
+    assert!(engine.active().is_empty());
+    assert_eq!(engine.evaluate(&cruising(125.0, false)).len(), 1);
+
+    engine.remove("overspeed");
+    assert!(engine.evaluate(&cruising(20.0, false)).is_empty());
+    assert!(engine.evaluate(&cruising(150.0, false)).is_empty());
+}
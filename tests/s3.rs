@@ -0,0 +1,197 @@
+//! The S3 client of exports and archives against a stub S3 endpoint: signed path-style
+//! requests, content types, missing objects and retried failures
+
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::sync::Mutex;
+
+use actix_web::web::{Bytes, Data};
+use actix_web::{App, HttpRequest, HttpResponse, HttpServer};
+
+use canbus_rmq_realtime::config::s3::{S3Bucket, S3Error};
+
+/// Request received by the stub: method, path and the headers the tests look at
+#[derive(Debug, Clone)]
+struct Received {
+    method: String,
+    path: String,
+    authorization: String,
+    content_type: Option<String>,
+}
+
+#[derive(Default)]
+struct Stub {
+    objects: Mutex<HashMap<String, (Bytes, String)>>,
+    received: Mutex<Vec<Received>>,
+    /// Requests still to answer with `503 Slow Down`
+    failures: Mutex<usize>,
+}
+
+async fn handle(req: HttpRequest, body: Bytes, stub: Data<Stub>) -> HttpResponse {
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let path = req.uri().path().to_string();
+    stub.received.lock().unwrap().push(Received {
+        method: req.method().to_string(),
+        path: path.clone(),
+        authorization: header("authorization").unwrap_or_default(),
+        content_type: header("content-type"),
+    });
+
+    let mut failures = stub.failures.lock().unwrap();
+    if *failures > 0 {
+        *failures -= 1;
+        return HttpResponse::ServiceUnavailable()
+            .body("<Error><Code>SlowDown</Code><Message>Slow Down</Message></Error>");
+    }
+
+    let mut objects = stub.objects.lock().unwrap();
+    match req.method().as_str() {
+        "PUT" => {
+            let content_type = header("content-type").unwrap_or_default();
+            objects.insert(path, (body, content_type));
+            HttpResponse::Ok().insert_header(("ETag", "\"1\"")).finish()
+        }
+        "GET" => match objects.get(&path) {
+            Some((body, content_type)) => HttpResponse::Ok()
+                .insert_header(("ETag", "\"1\""))
+                .insert_header(("Last-Modified", "Mon, 01 Jan 2024 08:00:00 GMT"))
+                .content_type(content_type.as_str())
+                .body(body.clone()),
+            None => HttpResponse::NotFound()
+                .body("<Error><Code>NoSuchKey</Code><Message>Not found</Message></Error>"),
+        },
+        _ => HttpResponse::MethodNotAllowed().finish(),
+    }
+}
+
+/// Serve the stub on a free local port, returning its endpoint
+fn serve(stub: Data<Stub>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(stub.clone())
+            .default_service(actix_web::web::to(handle))
+    })
+    .workers(1)
+    .listen(listener)
+    .unwrap()
+    .run();
+    actix_web::rt::spawn(server);
+    endpoint
+}
+
+fn bucket(endpoint: &str, prefix: &str) -> S3Bucket {
+    S3Bucket {
+        endpoint: format!("{}/", endpoint),
+        bucket: "telemetry".to_string(),
+        region: "eu-west-3".to_string(),
+        prefix: prefix.to_string(),
+        access_key_id: "minio".to_string(),
+        secret_access_key: "minio123".to_string(),
+    }
+}
+
+#[test]
+fn urls_are_path_style_and_encoded() {
+    let bucket = bucket("https://s3.example.com", "exports/");
+    assert_eq!(
+        bucket.url("daily/steps 2024-01-01.csv"),
+        "https://s3.example.com/telemetry/exports/daily/steps%202024-01-01.csv"
+    );
+    assert!(bucket.validate().is_ok());
+
+    let mut invalid = bucket.clone();
+    invalid.endpoint = "ftp://s3.example.com".to_string();
+    assert!(invalid
+        .validate()
+        .unwrap_err()
+        .contains("expected an http or https URL"));
+    let mut invalid = bucket;
+    invalid.secret_access_key = " ".to_string();
+    assert_eq!(
+        invalid.validate().unwrap_err(),
+        "S3 secret_access_key must not be empty"
+    );
+}
+
+#[actix_web::test]
+async fn objects_round_trip_through_the_stub() {
+    let stub = Data::new(Stub::default());
+    let endpoint = serve(stub.clone());
+    let exports = bucket(&endpoint, "exports/");
+
+    let url = exports
+        .put_object("daily/run 1.csv", b"step_id,speed\n".to_vec(), "text/csv")
+        .await
+        .unwrap();
+    assert_eq!(
+        url,
+        format!("{}/telemetry/exports/daily/run%201.csv", endpoint)
+    );
+    assert_eq!(
+        exports
+            .get_object("daily/run 1.csv")
+            .await
+            .unwrap()
+            .unwrap(),
+        b"step_id,speed\n"
+    );
+    // The prefix is part of the key, and buckets differing by prefix share the endpoint
+    assert_eq!(
+        bucket(&endpoint, "")
+            .get_object("exports/daily/run 1.csv")
+            .await
+            .unwrap()
+            .unwrap(),
+        b"step_id,speed\n"
+    );
+    assert!(exports
+        .get_object("daily/missing.csv")
+        .await
+        .unwrap()
+        .is_none());
+
+    let received = stub.received.lock().unwrap().clone();
+    let put = &received[0];
+    assert_eq!(put.method, "PUT");
+    assert_eq!(put.path, "/telemetry/exports/daily/run%201.csv");
+    assert_eq!(put.content_type.as_deref(), Some("text/csv"));
+    for request in &received {
+        assert!(
+            request
+                .authorization
+                .starts_with("AWS4-HMAC-SHA256 Credential=minio/"),
+            "{:?}",
+            request
+        );
+        assert!(request.authorization.contains("/eu-west-3/s3/aws4_request"));
+    }
+
+    failed_requests_are_retried(&stub, &exports).await;
+}
+
+async fn failed_requests_are_retried(stub: &Stub, exports: &S3Bucket) {
+    *stub.failures.lock().unwrap() = 2;
+    stub.received.lock().unwrap().clear();
+    exports
+        .put_object(
+            "segment.zst",
+            vec![0x28, 0xb5, 0x2f, 0xfd],
+            "application/zstd",
+        )
+        .await
+        .unwrap();
+    assert_eq!(stub.received.lock().unwrap().len(), 3);
+
+    *stub.failures.lock().unwrap() = 10;
+    let error = exports.get_object("segment.zst").await.unwrap_err();
+    assert!(matches!(error, S3Error::Request(_)), "{:?}", error);
+    assert!(error.to_string().starts_with("S3 request failed: "));
+    *stub.failures.lock().unwrap() = 0;
+}
@@ -0,0 +1,157 @@
+//! Settings files and variables: the `[database]` section and its deprecated `[sqlite]`
+//! alias, the unprefixed variables of earlier versions, validation and redaction
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use canbus_rmq_realtime::config::settings::{AppSettings, DatabaseSettings};
+use canbus_rmq_realtime::AppConfig;
+
+/// `config.toml` holding `contents` in a directory of its own
+fn settings_file(name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "eventbus-settings-{}-{}",
+        name,
+        uuid::Uuid::new_v4()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+fn load(name: &str, contents: &str) -> Result<AppSettings, String> {
+    let path = settings_file(name, contents);
+    let settings = AppSettings::load_from(&path);
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    settings
+}
+
+fn database_url(name: &str, contents: &str) -> String {
+    load(name, contents).unwrap().database.url
+}
+
+#[test]
+fn database_section_sets_the_url() {
+    assert_eq!(
+        database_url("database", "[database]\nurl = \"sqlite:a.db\"\n"),
+        "sqlite:a.db"
+    );
+    assert_eq!(database_url("none", ""), DatabaseSettings::default().url);
+}
+
+#[test]
+fn sqlite_section_is_a_deprecated_alias() {
+    assert_eq!(
+        database_url("sqlite", "[sqlite]\nurl = \"sqlite:b.db\"\n"),
+        "sqlite:b.db"
+    );
+    assert_eq!(
+        database_url(
+            "both",
+            "[database]\nurl = \"sqlite:a.db\"\n\n[sqlite]\nurl = \"sqlite:b.db\"\n"
+        ),
+        "sqlite:a.db"
+    );
+}
+
+#[test]
+fn alias_is_not_shown() {
+    let path = settings_file("shown", "[sqlite]\nurl = \"sqlite:b.db\"\n");
+    let settings = AppSettings::load_from(&path).unwrap();
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+    let shown = serde_json::to_value(settings.redacted()).unwrap();
+    assert_eq!(shown["database"]["url"], "sqlite:b.db");
+    assert!(shown.get("sqlite").is_none());
+}
+
+#[test]
+fn sections_configure_the_server() {
+    let settings = load(
+        "sections",
+        "[transport]\nkind = \"memory\"\nconsumer = \"single-active\"\n\n\
+         [streams]\nmax_connections = 100\nresume_window_secs = 600\n\n\
+         [archive]\nafter_days = 1\n\n\
+         [scrub]\ngps_decimals = \"off\"\n",
+    )
+    .unwrap();
+    let config = AppConfig::from_settings(settings).unwrap();
+
+    assert!(config.single_active_consumer);
+    assert_eq!(config.transport.as_str(), "memory");
+    assert_eq!(config.connection_limits.max_total, Some(100));
+    assert_eq!(config.ws_resume_window, Duration::from_secs(600));
+    assert_eq!(config.archive_after, Some(Duration::from_secs(24 * 3600)));
+    assert_eq!(config.scrub.gps_decimals, None);
+    assert_eq!(config.job_workers, AppConfig::default().job_workers);
+}
+
+/// The only test setting variables, which the others do not read
+#[test]
+fn earlier_variables_are_read_below_prefixed_ones() {
+    std::env::set_var("JOB_WORKERS", "4");
+    std::env::set_var("SCRUB_MASK_VIN", "false");
+    std::env::set_var("MAX_STREAM_CONNECTIONS_PER_IP", "5");
+    std::env::set_var("EVENTBUS_STREAMS__MAX_CONNECTIONS_PER_IP", "10");
+    let settings = load("variables", "[jobs]\nworkers = 3\n");
+    for name in [
+        "JOB_WORKERS",
+        "SCRUB_MASK_VIN",
+        "MAX_STREAM_CONNECTIONS_PER_IP",
+        "EVENTBUS_STREAMS__MAX_CONNECTIONS_PER_IP",
+    ] {
+        std::env::remove_var(name);
+    }
+    let settings = settings.unwrap();
+
+    assert_eq!(settings.jobs.workers, 4);
+    assert!(!settings.scrub.mask_vin);
+    assert_eq!(settings.streams.max_connections_per_ip, Some(10));
+}
+
+#[test]
+fn invalid_values_are_refused() {
+    for (name, contents, field) in [
+        (
+            "negative",
+            "[devices]\ntimeout_secs = -1\n",
+            "devices.timeout_secs",
+        ),
+        (
+            "gzip",
+            "[compression]\ngzip_level = 12\n",
+            "compression.gzip_level",
+        ),
+        ("workers", "[jobs]\nworkers = 0\n", "jobs.workers"),
+        ("archive", "[archive]\nstore = \"s3\"\n", "archive.store"),
+        (
+            "viewer",
+            "[auth]\nviewer_api_key = \"viewer\"\n",
+            "auth.viewer_api_key",
+        ),
+    ] {
+        let error = load(name, contents).unwrap_err();
+        assert!(error.contains(field), "{}: {}", name, error);
+    }
+    assert!(load("unknown", "[jobs]\nthreads = 2\n").is_err());
+    assert!(load("decimals", "[scrub]\ngps_decimals = \"some\"\n").is_err());
+}
+
+#[test]
+fn secrets_are_redacted() {
+    let settings = load(
+        "secrets",
+        "[database]\nurl = \"postgres://bus:hunter2@db/bus\"\n\n\
+         [encryption]\nkey = \"c2VjcmV0\"\n\n\
+         [auth]\nadmin_api_key = 1234\nviewer_api_key = \"viewer\"\n",
+    )
+    .unwrap();
+    assert_eq!(settings.admin_api_key(), Some("1234"));
+
+    let shown = serde_json::to_string(&settings.redacted()).unwrap();
+    for secret in ["hunter2", "c2VjcmV0", "1234", "viewer\""] {
+        assert!(!shown.contains(secret), "{} shown in {}", secret, shown);
+    }
+    assert!(shown.contains("postgres://bus:***@db/bus"));
+}
@@ -0,0 +1,212 @@
+//! HMAC signatures of ingested batches, checked against the API key they were sent with
+
+use std::time::Duration;
+
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use actix_web::web::Data;
+use actix_web::{HttpRequest, ResponseError};
+
+use canbus_rmq_realtime::common::error::AppError;
+use canbus_rmq_realtime::features::ingest::model::BatchSignature;
+use canbus_rmq_realtime::features::ingest::signature::{
+    self, SigningPolicy, SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
+use canbus_rmq_realtime::features::tenant::{Role, Tenant, TenantRegistry, TenantScope};
+use canbus_rmq_realtime::test_support::install_memory_database;
+
+const ADMIN_KEY: &str = "admin-secret";
+const BODY: &[u8] = br#"{"frames":[]}"#;
+
+#[test]
+fn signature_is_the_hmac_of_timestamp_and_body() {
+    // python3 -c "import hmac; print(hmac.new(b'key-123', b'1700000000.{\"frames\":[]}', 'sha256').hexdigest())"
+    assert_eq!(
+        signature::sign("key-123", 1_700_000_000, BODY),
+        "10850183c7f7686cc65d4a04fe9a28d87a41dd30feb51aa3f72cf429c2b57e79"
+    );
+    assert_ne!(
+        signature::sign("key-123", 1_700_000_001, BODY),
+        signature::sign("key-123", 1_700_000_000, BODY)
+    );
+}
+
+/// Ingest request sent with `api_key`, and the signature headers when given
+fn request(
+    registry: Option<&TenantRegistry>,
+    policy: SigningPolicy,
+    api_key: &str,
+    headers: &[(&str, String)],
+) -> HttpRequest {
+    let mut req = TestRequest::post()
+        .uri("/ingest")
+        .app_data(Data::new(policy))
+        .insert_header(("X-Api-Key", api_key));
+    if let Some(registry) = registry {
+        req = req.app_data(Data::new(registry.clone()));
+    }
+    for (name, value) in headers {
+        req = req.insert_header((*name, value.clone()));
+    }
+    req.to_http_request()
+}
+
+fn signed(api_key: &str, timestamp: i64, body: &[u8]) -> Vec<(&'static str, String)> {
+    vec![
+        (SIGNATURE_HEADER, signature::sign(api_key, timestamp, body)),
+        (TIMESTAMP_HEADER, timestamp.to_string()),
+    ]
+}
+
+fn status(result: Result<Option<BatchSignature>, AppError>) -> StatusCode {
+    result.unwrap_err().status_code()
+}
+
+fn tenant(name: &str) -> TenantScope {
+    TenantScope(
+        Some(Tenant {
+            name: name.to_string(),
+            max_steps: None,
+            max_messages_per_minute: None,
+            max_bytes: None,
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+        }),
+        Role::Editor,
+    )
+}
+
+#[actix_web::test]
+async fn batches_are_verified_against_their_key() {
+    install_memory_database().await.unwrap();
+    let registry = TenantRegistry::load(Some(ADMIN_KEY), None).await.unwrap();
+    let policy = SigningPolicy::default();
+    let now = chrono::Utc::now().timestamp();
+    let verify = |headers: &[(&str, String)], api_key: &str, scope: &TenantScope| {
+        signature::verify(
+            &request(Some(&registry), policy, api_key, headers),
+            BODY,
+            scope,
+        )
+    };
+
+    let verified = verify(&signed("acme-key", now, BODY), "acme-key", &tenant("acme"))
+        .unwrap()
+        .expect("signed batch not verified");
+    assert_eq!(verified.signer, "acme");
+    assert_eq!(verified.signature, signature::sign("acme-key", now, BODY));
+    assert_eq!(
+        verified.signed_at,
+        chrono::DateTime::from_timestamp(now, 0)
+            .unwrap()
+            .to_rfc3339()
+    );
+
+    // Hex digits of either case, signer `admin` for the administrator key
+    let upper = vec![
+        (
+            SIGNATURE_HEADER,
+            signature::sign(ADMIN_KEY, now, BODY).to_uppercase(),
+        ),
+        (TIMESTAMP_HEADER, now.to_string()),
+    ];
+    let verified = verify(&upper, ADMIN_KEY, &TenantScope::default())
+        .unwrap()
+        .unwrap();
+    assert_eq!(verified.signer, "admin");
+    assert_eq!(verified.signature, signature::sign(ADMIN_KEY, now, BODY));
+
+    // Signed with another key, over another body, or at another time
+    let scope = tenant("acme");
+    for headers in [
+        signed("globex-key", now, BODY),
+        signed("acme-key", now, br#"{"frames":[1]}"#),
+        vec![
+            (SIGNATURE_HEADER, signature::sign("acme-key", now - 1, BODY)),
+            (TIMESTAMP_HEADER, now.to_string()),
+        ],
+    ] {
+        assert_eq!(
+            status(verify(&headers, "acme-key", &scope)),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    let stale = verify(&signed("acme-key", now - 301, BODY), "acme-key", &scope).unwrap_err();
+    assert_eq!(stale.status_code(), StatusCode::UNAUTHORIZED);
+    assert!(
+        stale.to_string().contains("at most 300 s is accepted"),
+        "{}",
+        stale
+    );
+    assert!(verify(&signed("acme-key", now + 290, BODY), "acme-key", &scope).is_ok());
+    // Timestamps at the ends of the range are refused, not overflowing the skew
+    for timestamp in [i64::MIN, i64::MAX] {
+        let headers = signed("acme-key", timestamp, BODY);
+        assert_eq!(
+            status(verify(&headers, "acme-key", &scope)),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    for headers in [
+        vec![(SIGNATURE_HEADER, signature::sign("acme-key", now, BODY))],
+        vec![
+            (SIGNATURE_HEADER, "zz".to_string()),
+            (TIMESTAMP_HEADER, now.to_string()),
+        ],
+        vec![
+            (SIGNATURE_HEADER, signature::sign("acme-key", now, BODY)),
+            (TIMESTAMP_HEADER, "yesterday".to_string()),
+        ],
+    ] {
+        assert_eq!(
+            status(verify(&headers, "acme-key", &scope)),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    unsigned_batches_follow_the_policy(&registry, now);
+}
+
+fn unsigned_batches_follow_the_policy(registry: &TenantRegistry, now: i64) {
+    let scope = tenant("acme");
+    let optional = SigningPolicy::default();
+    let required = SigningPolicy {
+        required: true,
+        max_skew: Duration::from_secs(60),
+    };
+
+    let req = request(Some(registry), optional, "acme-key", &[]);
+    assert!(signature::verify(&req, BODY, &scope).unwrap().is_none());
+    let req = request(Some(registry), required, "acme-key", &[]);
+    assert_eq!(
+        status(signature::verify(&req, BODY, &scope)),
+        StatusCode::UNAUTHORIZED
+    );
+
+    let req = request(
+        Some(registry),
+        required,
+        "acme-key",
+        &signed("acme-key", now - 61, BODY),
+    );
+    assert_eq!(
+        status(signature::verify(&req, BODY, &scope)),
+        StatusCode::UNAUTHORIZED
+    );
+
+    // Without tenancy keys are never checked, so they cannot sign
+    let disabled = TenantRegistry::default();
+    for registry in [None, Some(&disabled)] {
+        let req = request(
+            registry,
+            optional,
+            "acme-key",
+            &signed("acme-key", now, BODY),
+        );
+        assert_eq!(
+            status(signature::verify(&req, BODY, &TenantScope::default())),
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
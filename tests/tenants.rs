@@ -0,0 +1,310 @@
+//! Tenant API keys: who they authenticate as, which routes they reach and which stored
+//! steps they see
+
+use actix_web::dev::Service;
+use actix_web::http::StatusCode;
+use actix_web::web::Data;
+use actix_web::{middleware, test, web, App, HttpResponse, ResponseError};
+
+use canbus_rmq_realtime::common::error::AppError;
+use canbus_rmq_realtime::core::can::Endianness;
+use canbus_rmq_realtime::features::driving_step::service;
+use canbus_rmq_realtime::features::tenant::model::TenantRequest;
+use canbus_rmq_realtime::features::tenant::{
+    self, controller, service as tenant_service, Role, TenantRegistry, TenantScope, Usage,
+};
+use canbus_rmq_realtime::features::trash::{self, TrashKind};
+use canbus_rmq_realtime::test_support::{install_memory_database, DrivingStepBuilder};
+
+const ADMIN_KEY: &str = "admin-secret";
+const VIEWER_KEY: &str = "viewer-secret";
+
+fn request(name: &str) -> TenantRequest {
+    TenantRequest {
+        name: name.to_string(),
+        max_steps: None,
+        max_messages_per_minute: None,
+        max_bytes: None,
+    }
+}
+
+/// Store a step named `name` as `tenant`, returning its id
+async fn store(name: &str, tenant: Option<&str>) -> String {
+    try_store(name, tenant).await.unwrap()
+}
+
+async fn try_store(name: &str, tenant: Option<&str>) -> Result<String, AppError> {
+    let step = DrivingStepBuilder::new(name).speed(50.0).build();
+    let stored = service::store_frames(
+        step.to_can_messages(),
+        Endianness::Little,
+        None,
+        tenant,
+        name,
+    )
+    .await?;
+    Ok(stored.step_id)
+}
+
+/// Frames and payload bytes of one step stored by `store`
+fn step_usage() -> Usage {
+    Usage::of(&DrivingStepBuilder::default().build().to_can_messages())
+}
+
+/// Tests of the crate-wide pool share one runtime: its connection does not outlive the
+/// runtime that opened it
+#[actix_web::test]
+async fn tenants_on_the_memory_database() {
+    install_memory_database().await.unwrap();
+    let registry = TenantRegistry::load(Some(ADMIN_KEY), Some(VIEWER_KEY))
+        .await
+        .unwrap();
+    let acme = controller::create(&registry, request("acme"))
+        .await
+        .unwrap();
+    let globex = controller::create(&registry, request("globex"))
+        .await
+        .unwrap();
+
+    keys_resolve_to_their_scope(&registry, &acme.api_key).await;
+    tenant_keys_only_reach_tenant_routes(&registry, &acme.api_key).await;
+    steps_are_only_seen_by_their_tenant(&acme.api_key, &globex.api_key, &registry).await;
+    quotas_refuse_the_write_that_exceeds_them(&registry).await;
+}
+
+async fn keys_resolve_to_their_scope(registry: &TenantRegistry, acme_key: &str) {
+    assert!(registry.enabled());
+    assert_eq!(
+        registry.authenticate(Some(ADMIN_KEY)).unwrap(),
+        TenantScope::default()
+    );
+    assert_eq!(
+        registry.authenticate(Some(VIEWER_KEY)).unwrap(),
+        TenantScope(None, Role::Viewer)
+    );
+
+    let scope = registry.authenticate(Some(acme_key)).unwrap();
+    assert_eq!(scope.name(), Some("acme"));
+    assert_eq!(scope.role(), Role::Editor);
+
+    for key in [None, Some("guess")] {
+        assert_eq!(
+            registry.authenticate(key).unwrap_err().status_code(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    // Keys are accepted again after a restart, from their stored hash
+    let reloaded = TenantRegistry::load(Some(ADMIN_KEY), None).await.unwrap();
+    assert_eq!(
+        reloaded.authenticate(Some(acme_key)).unwrap().name(),
+        Some("acme")
+    );
+
+    // Without an administrator key tenancy is off and keys are ignored
+    let disabled = TenantRegistry::load(None, None).await.unwrap();
+    assert_eq!(
+        disabled.authenticate(Some(acme_key)).unwrap(),
+        TenantScope::default()
+    );
+    assert_eq!(
+        controller::create(&disabled, request("initech"))
+            .await
+            .unwrap_err()
+            .status_code(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
+async fn tenant_keys_only_reach_tenant_routes(registry: &TenantRegistry, acme_key: &str) {
+    let scope_name = |scope: TenantScope| async move {
+        HttpResponse::Ok().body(scope.name().unwrap_or("*").to_string())
+    };
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(registry.clone()))
+            .wrap(middleware::from_fn(tenant::authenticate))
+            .route("/driving-steps", web::get().to(scope_name))
+            .route("/rules", web::get().to(scope_name))
+            .route("/stream", web::get().to(scope_name))
+            .route("/health/live", web::get().to(scope_name)),
+    )
+    .await;
+
+    for (path, key, status, body) in [
+        ("/driving-steps", Some(acme_key), StatusCode::OK, "acme"),
+        ("/driving-steps", Some(ADMIN_KEY), StatusCode::OK, "*"),
+        ("/rules", Some(acme_key), StatusCode::FORBIDDEN, ""),
+        ("/rules", Some(ADMIN_KEY), StatusCode::OK, "*"),
+        (
+            "/driving-steps",
+            Some(VIEWER_KEY),
+            StatusCode::FORBIDDEN,
+            "",
+        ),
+        ("/stream", Some(VIEWER_KEY), StatusCode::OK, "*"),
+        ("/driving-steps", None, StatusCode::UNAUTHORIZED, ""),
+        ("/health/live", None, StatusCode::OK, "*"),
+    ] {
+        let mut req = test::TestRequest::get().uri(path);
+        if let Some(key) = key {
+            req = req.insert_header(("X-Api-Key", key));
+        }
+        let response = app.call(req.to_request()).await;
+        let (status_code, response_body) = match response {
+            Ok(response) => (response.status(), test::read_body(response).await),
+            Err(e) => (e.as_response_error().status_code(), Default::default()),
+        };
+        assert_eq!(status_code, status, "GET {} with {:?}", path, key);
+        if status == StatusCode::OK {
+            assert_eq!(response_body, body.as_bytes(), "GET {}", path);
+        }
+    }
+
+    // The key may also come as a bearer token or, for browsers, in the query
+    let req = test::TestRequest::get()
+        .uri("/driving-steps")
+        .insert_header(("Authorization", format!("Bearer {}", acme_key)))
+        .to_request();
+    assert_eq!(test::call_and_read_body(&app, req).await, "acme");
+    let req = test::TestRequest::get()
+        .uri(&format!("/driving-steps?api_key={}", acme_key))
+        .to_request();
+    assert_eq!(test::call_and_read_body(&app, req).await, "acme");
+}
+
+async fn steps_are_only_seen_by_their_tenant(
+    acme_key: &str,
+    globex_key: &str,
+    registry: &TenantRegistry,
+) {
+    let acme = registry.authenticate(Some(acme_key)).unwrap();
+    let globex = registry.authenticate(Some(globex_key)).unwrap();
+    let acme_step = store("Acme Delivery", acme.name()).await;
+    let globex_step = store("Globex Survey", globex.name()).await;
+    let shared_step = store("Admin Check", None).await;
+
+    let names = |steps: Vec<canbus_rmq_realtime::DrivingStep>| {
+        steps
+            .into_iter()
+            .map(|step| step.step_name)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        names(service::get_all_steps(acme.name()).await.unwrap()),
+        ["Acme Delivery"]
+    );
+    assert_eq!(
+        names(service::get_all_steps(globex.name()).await.unwrap()),
+        ["Globex Survey"]
+    );
+    assert_eq!(
+        names(service::get_all_steps(None).await.unwrap()),
+        ["Acme Delivery", "Globex Survey", "Admin Check"]
+    );
+
+    // Another tenant's step is not found rather than forbidden
+    for step_id in [&globex_step, &shared_step] {
+        assert!(service::get_step_frames(step_id, acme.name())
+            .await
+            .unwrap()
+            .is_none());
+    }
+    let read = service::reconstruct_step(&acme_step, String::new(), acme.name())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(read.tenant.as_deref(), Some("acme"));
+    assert!(service::reconstruct_step(&acme_step, String::new(), None)
+        .await
+        .unwrap()
+        .is_some());
+
+    assert_eq!(
+        service::get_last_step(globex.name())
+            .await
+            .unwrap()
+            .unwrap()
+            .step_name,
+        "Globex Survey"
+    );
+}
+
+async fn quotas_refuse_the_write_that_exceeds_them(registry: &TenantRegistry) {
+    let usage = step_usage();
+    let status = |result: Result<String, AppError>| result.unwrap_err().status_code();
+
+    let mut zero = request("hooli");
+    zero.max_bytes = Some(0);
+    assert_eq!(
+        status(
+            controller::create(registry, zero)
+                .await
+                .map(|_| String::new())
+        ),
+        StatusCode::BAD_REQUEST
+    );
+    controller::create(registry, request("hooli"))
+        .await
+        .unwrap();
+
+    let mut steps = request("initech");
+    steps.max_steps = Some(2);
+    controller::create(registry, steps).await.unwrap();
+    let initech = store("Initech 1", Some("initech")).await;
+    store("Initech 2", Some("initech")).await;
+    assert_eq!(
+        status(try_store("Initech 3", Some("initech")).await),
+        StatusCode::FORBIDDEN
+    );
+    // Steps in the trash no longer count
+    assert!(
+        trash::service::soft_delete(TrashKind::Step, &initech, Some("initech"))
+            .await
+            .unwrap()
+    );
+    store("Initech 3", Some("initech")).await;
+
+    let mut rate = request("umbrella");
+    rate.max_messages_per_minute = Some(usage.messages * 2);
+    controller::create(registry, rate).await.unwrap();
+    store("Umbrella 1", Some("umbrella")).await;
+    store("Umbrella 2", Some("umbrella")).await;
+    assert_eq!(
+        status(try_store("Umbrella 3", Some("umbrella")).await),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+
+    let mut bytes = request("vandelay");
+    bytes.max_bytes = Some(usage.bytes + usage.bytes / 2);
+    controller::create(registry, bytes).await.unwrap();
+    let vandelay = store("Vandelay 1", Some("vandelay")).await;
+    assert_eq!(
+        status(try_store("Vandelay 2", Some("vandelay")).await),
+        StatusCode::FORBIDDEN
+    );
+    assert!(
+        trash::service::soft_delete(TrashKind::Step, &vandelay, Some("vandelay"))
+            .await
+            .unwrap()
+    );
+    store("Vandelay 2", Some("vandelay")).await;
+
+    // Refused writes store nothing and are not counted
+    let (stored, _) = tenant_service::usage("initech").await.unwrap();
+    assert_eq!(stored, 2);
+    let accounted = tenant_service::get_key_usage().await.unwrap();
+    let of = |name: &str| {
+        accounted
+            .iter()
+            .find(|key| key.tenant == name)
+            .unwrap_or_else(|| panic!("no usage for {}", name))
+    };
+    assert_eq!(of("umbrella").messages, usage.messages * 2);
+    assert_eq!(of("umbrella").messages_this_minute, usage.messages * 2);
+    assert_eq!(of("vandelay").bytes, usage.bytes);
+    assert_eq!(of("initech").max_steps, Some(2));
+    assert_eq!(of("globex").messages, usage.messages);
+    // Keys that stored nothing are listed with nothing counted
+    assert_eq!(of("hooli").messages, 0);
+}